@@ -0,0 +1,41 @@
+//! Redaction throughput: `cargo bench --bench redact`.
+use blameprompt_core::core::config::RedactionConfig;
+use blameprompt_core::core::redact;
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+const CLEAN_LINE: &str =
+    "Refactor the parser so every section is validated on load and errors carry a line number.\n";
+const SECRET_LINE: &str =
+    "export OPENAI_API_KEY=sk-proj-abcdefghijklmnopqrstuvwxyz0123 # from alice@build-host\n";
+
+fn sample(bytes: usize) -> String {
+    let mut text = String::with_capacity(bytes + SECRET_LINE.len());
+    let mut i = 0;
+    while text.len() < bytes {
+        // One secret every 50 lines, like a pasted env dump in a long prompt.
+        text.push_str(if i % 50 == 0 { SECRET_LINE } else { CLEAN_LINE });
+        i += 1;
+    }
+    text
+}
+
+fn bench_redact(c: &mut Criterion) {
+    let config = RedactionConfig::default();
+    let mut group = c.benchmark_group("redact");
+    for size in [1024, 64 * 1024, 4 * 1024 * 1024] {
+        let text = sample(size);
+        group.throughput(Throughput::Bytes(text.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &text, |b, text| {
+            b.iter(|| redact::redact_with_report_and_config(text, &config))
+        });
+    }
+    group.finish();
+
+    let prompt = CLEAN_LINE.repeat(3);
+    c.bench_function("redact/clean_prompt", |b| {
+        b.iter(|| redact::redact_with_report_and_config(&prompt, &config))
+    });
+}
+
+criterion_group!(benches, bench_redact);
+criterion_main!(benches);
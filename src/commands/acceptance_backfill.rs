@@ -0,0 +1,290 @@
+/// Acceptance stats for imported sessions (`record`, `record-cursor`).
+///
+/// Imported receipts never went through the hooks that capture the blob the
+/// AI wrote, so there was nothing to compare a commit against. After an import
+/// the AI's version of each file is reconstructed instead: from the session's
+/// own file snapshots when it has them (the transcript's Write, Edit and
+/// MultiEdit calls replayed over the file as of the last commit before the
+/// session, or HEAD when the start is unknown), otherwise from the first commit after the session that touched
+/// the file, or the working tree when there is none yet. The result is stored
+/// as the file change's blob, scored against HEAD right away, and scored again
+/// by attach like any captured blob. Such receipts are flagged
+/// `acceptance_estimated`.
+use crate::commands::partial_staging;
+use crate::core::receipt::{FileChange, Receipt};
+use crate::core::transcript::{Message, Transcript};
+use crate::core::util;
+use crate::git::blob_reader;
+use crate::git::storage::git_with_stdin;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// One change a tool call made to a file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum FileEdit {
+    /// Whole new content.
+    Write(String),
+    Replace {
+        old: String,
+        new: String,
+        all: bool,
+    },
+}
+
+/// The edits the transcript made to `path`, in order.
+pub fn file_edits(transcript: &Transcript, path: &str) -> Vec<FileEdit> {
+    let mut edits = Vec::new();
+    for m in &transcript.messages {
+        let Message::ToolUse { name, input, .. } = m else {
+            continue;
+        };
+        let target = input.get("file_path").and_then(|v| v.as_str());
+        let is_ours = |p: Option<&str>| p.is_some_and(|p| util::paths_match(p, path));
+        match name.as_str() {
+            "Write" if is_ours(target) => {
+                if let Some(content) = input.get("content").and_then(|v| v.as_str()) {
+                    edits.push(FileEdit::Write(content.to_string()));
+                }
+            }
+            "Edit" if is_ours(target) => edits.extend(replace_edit(input)),
+            "MultiEdit" => {
+                for edit in input
+                    .get("edits")
+                    .and_then(|e| e.as_array())
+                    .into_iter()
+                    .flatten()
+                {
+                    let edit_target = edit.get("file_path").and_then(|v| v.as_str()).or(target);
+                    if is_ours(edit_target) {
+                        edits.extend(replace_edit(edit));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    edits
+}
+
+fn replace_edit(input: &serde_json::Value) -> Option<FileEdit> {
+    Some(FileEdit::Replace {
+        old: input.get("old_string")?.as_str()?.to_string(),
+        new: input.get("new_string")?.as_str()?.to_string(),
+        all: input
+            .get("replace_all")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(false),
+    })
+}
+
+/// `base` with `edits` applied; `None` when an edit's text isn't found, i.e.
+/// the file changed in ways the transcript doesn't show.
+pub fn replay(base: Option<&str>, edits: &[FileEdit]) -> Option<String> {
+    let mut content = base.map(String::from);
+    for edit in edits {
+        content = Some(match edit {
+            FileEdit::Write(new) => new.clone(),
+            FileEdit::Replace { old, new, all } => {
+                let current = content?;
+                if old.is_empty() || !current.contains(old.as_str()) {
+                    return None;
+                }
+                if *all {
+                    current.replace(old.as_str(), new)
+                } else {
+                    current.replacen(old.as_str(), new, 1)
+                }
+            }
+        });
+    }
+    content
+}
+
+/// 1-based numbers of the lines of `ai` that `base` doesn't have (counting
+/// repeated lines).
+pub fn added_lines(base: &str, ai: &str) -> Vec<u32> {
+    let mut available: HashMap<&str, usize> = HashMap::new();
+    for line in base.lines() {
+        *available.entry(line).or_default() += 1;
+    }
+    ai.lines()
+        .enumerate()
+        .filter_map(|(i, line)| match available.get_mut(line) {
+            Some(n) if *n > 0 => {
+                *n -= 1;
+                None
+            }
+            _ => Some(i as u32 + 1),
+        })
+        .collect()
+}
+
+/// Reconstruct the AI's version of each file in `receipt` and score it.
+/// `transcript` is the session's edit history, when the tool has one.
+pub fn backfill(receipt: &mut Receipt, transcript: Option<&Transcript>) {
+    let base_commit = match receipt.session_start {
+        Some(start) => commit_before(start),
+        None => git(&["rev-parse", "HEAD"]),
+    };
+    let session_end = receipt.session_end;
+    let mut estimated = false;
+    for fc in receipt.files_changed.iter_mut() {
+        if fc.blob_hash.is_some() || fc.is_binary {
+            continue;
+        }
+        let base = base_commit.as_deref().and_then(|c| show_file(c, &fc.path));
+        let Some(ai) = transcript
+            .map(|t| file_edits(t, &fc.path))
+            .filter(|edits| !edits.is_empty())
+            .and_then(|edits| replay(base.as_deref(), &edits))
+            .or_else(|| {
+                session_end
+                    .and_then(|end| commit_after(end, &fc.path))
+                    .and_then(|c| show_file(&c, &fc.path))
+            })
+            .or_else(|| std::fs::read_to_string(&fc.path).ok())
+        else {
+            continue;
+        };
+        estimated |= record_ai_version(fc, base.as_deref().unwrap_or(""), &ai);
+    }
+    if estimated {
+        score_against_head(receipt);
+        receipt.acceptance_estimated = true;
+    }
+}
+
+/// Store `ai` as the file change's blob, with the lines it adds over `base`.
+/// Returns whether anything was recorded.
+fn record_ai_version(fc: &mut FileChange, base: &str, ai: &str) -> bool {
+    let added = added_lines(base, ai);
+    let (Some(first), Some(last)) = (added.first(), added.last()) else {
+        return false;
+    };
+    let Ok(blob) = git_with_stdin(Path::new("."), &["hash-object", "-w", "--stdin"], ai) else {
+        return false;
+    };
+    fc.blob_hash = Some(blob);
+    fc.additions = added.len() as u32;
+    fc.line_range = (*first, *last);
+    true
+}
+
+/// Accepted/overridden lines of the reconstructed blobs against HEAD, the way
+/// attach scores captured blobs against the commit.
+fn score_against_head(receipt: &mut Receipt) {
+    let mut accepted = 0;
+    let mut overridden = 0;
+    let mut has_data = false;
+    for fc in &receipt.files_changed {
+        let (Some(blob), Some(head)) = (&fc.blob_hash, show_file("HEAD", &fc.path)) else {
+            continue;
+        };
+        has_data = true;
+        let ai = blob_reader::blob_lines(blob);
+        let head: Vec<String> = head.lines().map(String::from).collect();
+        let split =
+            partial_staging::split_lines(&ai, &head, &partial_staging::worktree_lines(&fc.path));
+        accepted += split.accepted;
+        overridden += split.overridden;
+    }
+    if has_data {
+        receipt.accepted_lines = Some(accepted);
+        receipt.overridden_lines = Some(overridden);
+    }
+}
+
+/// The last commit on HEAD at or before `time`.
+fn commit_before(time: DateTime<Utc>) -> Option<String> {
+    git(&[
+        "rev-list",
+        "-1",
+        &format!("--before={}", time.to_rfc3339()),
+        "HEAD",
+    ])
+}
+
+/// The first commit on HEAD after `time` that touched `path`.
+fn commit_after(time: DateTime<Utc>, path: &str) -> Option<String> {
+    git(&[
+        "rev-list",
+        "--reverse",
+        &format!("--since={}", time.to_rfc3339()),
+        "HEAD",
+        "--",
+        path,
+    ])
+    .and_then(|out| out.lines().next().map(String::from))
+}
+
+/// `path` as of `commit`; `None` when it didn't exist there.
+fn show_file(commit: &str, path: &str) -> Option<String> {
+    blob_reader::read_blob(&format!("{}:{}", commit, path))
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let out = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!out.is_empty()).then_some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn tool_use(name: &str, input: serde_json::Value) -> Message {
+        Message::ToolUse {
+            id: String::new(),
+            name: name.to_string(),
+            input,
+        }
+    }
+
+    #[test]
+    fn test_replay_transcript_edits() {
+        let transcript = Transcript {
+            messages: vec![
+                tool_use(
+                    "Write",
+                    json!({"file_path": "/repo/src/a.rs", "content": "fn a() {}\nfn b() {}\n"}),
+                ),
+                tool_use("Read", json!({"file_path": "/repo/src/a.rs"})),
+                tool_use(
+                    "Write",
+                    json!({"file_path": "/repo/src/other.rs", "content": "x"}),
+                ),
+                tool_use(
+                    "Edit",
+                    json!({"file_path": "/repo/src/a.rs", "old_string": "fn b() {}", "new_string": "fn b() { c() }"}),
+                ),
+                tool_use(
+                    "MultiEdit",
+                    json!({"file_path": "/repo/src/a.rs", "edits": [
+                        {"old_string": "fn", "new_string": "pub fn", "replace_all": true}
+                    ]}),
+                ),
+            ],
+        };
+        let edits = file_edits(&transcript, "src/a.rs");
+        assert_eq!(edits.len(), 3);
+        let ai = replay(None, &edits).unwrap();
+        assert_eq!(ai, "pub fn a() {}\npub fn b() { c() }\n");
+
+        // An edit before any snapshot, or one whose text isn't there, can't be replayed.
+        assert_eq!(replay(None, &edits[1..]), None);
+        assert_eq!(replay(Some("fn a() {}\n"), &edits[1..2]), None);
+        assert_eq!(
+            replay(Some("fn a() {}\nfn b() {}\n"), &edits[1..2]).unwrap(),
+            "fn a() {}\nfn b() { c() }\n"
+        );
+
+        assert_eq!(added_lines("a\nb\n}\n", "a\nx\n}\n}\nb\n"), vec![2, 4]);
+    }
+}
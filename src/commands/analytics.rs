@@ -1,6 +1,17 @@
-use crate::commands::audit;
+use crate::commands::anonymize_paths;
+use crate::commands::audit::{self, AuditEntry};
+use crate::commands::grouping::{self, GroupBy};
+use crate::commands::lifecycle::{self, FileLifecycle};
+use crate::commands::metrics::{self, DurationStats};
+use crate::commands::rework::{self, ReworkLink};
+use crate::core::config::{self, AnalyticsConfig};
+use crate::core::pairing;
+use crate::core::pricing::{self, CostBreakdown};
+use crate::core::receipt::{CodeOriginStats, LineCounts, Receipt};
+use chrono::{DateTime, Datelike, Duration, NaiveDate};
 use serde::Serialize;
-use std::collections::{HashMap, HashSet};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Serialize)]
 pub struct AnalyticsReport {
@@ -10,10 +21,144 @@ pub struct AnalyticsReport {
     pub total_receipts: u32,
     pub total_sessions: u32,
     pub total_estimated_cost_usd: f64,
+    /// Part of the total whose tokens were estimated from text because the
+    /// provider reported no usage (`cost_estimated` receipts).
+    pub text_estimated_cost_usd: f64,
+    /// Spend by token type (fresh input, cache writes, cache reads, output)
+    /// and the prompt-caching savings, over receipts with token counts.
+    pub cost_by_token_type: CostBreakdown,
+    /// Spend of receipts without token counts, which can't be split.
+    pub cost_without_token_data_usd: f64,
     pub total_ai_lines: u32,
+    /// Binary assets written by AI. Kept out of line counts; tracked by size instead.
+    pub binary_files: u32,
+    pub binary_bytes_delta: i64,
     pub by_provider: HashMap<String, ProviderStats>,
     pub by_model: HashMap<String, ModelStats>,
     pub by_user: HashMap<String, UserStats>,
+    /// AI commits with a linked revert, fix or heavy rewrite (see `rework`).
+    pub commits_requiring_rework: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub rework: Vec<ReworkLink>,
+    /// Questions the AI asked and the human answered (`user_decisions`).
+    pub human_decisions: DecisionStats,
+    /// Time-bucketed series, filled by `analytics --trend`.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub trend: Vec<TrendPoint>,
+    /// Receipts left out because all their files were deleted later.
+    pub deleted_receipts: u32,
+    /// Time from each AI suggestion to its commit reaching the default branch.
+    pub merge_latency: MergeLatency,
+    /// AI share of the lines AI commits added, raw and normalized.
+    pub line_share: LineShare,
+    /// Staged receipts included by `--include-uncommitted`. Provisional: they
+    /// are in the receipt totals but not yet in any commit.
+    pub uncommitted_receipts: u32,
+}
+
+/// AI share of the lines added by commits whose notes carry line-level
+/// origin, counting every line (`raw`) and leaving out blank and brace-only
+/// lines (`normalized`), which inflate the share unevenly across languages.
+/// Shared by `analytics` and `report`.
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+pub struct LineShare {
+    /// The share shown first: "raw" or "normalized" (`[analytics] line_counting`).
+    pub counting: String,
+    pub commits: u32,
+    pub raw: Option<ShareOfLines>,
+    /// Over the `normalized_commits` whose notes have normalized counts.
+    pub normalized: Option<ShareOfLines>,
+    pub normalized_commits: u32,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub struct ShareOfLines {
+    pub ai_lines: u32,
+    pub total_lines: u32,
+    pub ai_share_pct: f64,
+}
+
+impl ShareOfLines {
+    fn of(counts: LineCounts) -> Option<Self> {
+        (counts.total() > 0).then(|| ShareOfLines {
+            ai_lines: counts.ai(),
+            total_lines: counts.total(),
+            ai_share_pct: counts.ai() as f64 / counts.total() as f64 * 100.0,
+        })
+    }
+}
+
+impl LineShare {
+    /// (label, share) in display order, the configured counting first.
+    pub fn ordered(&self) -> Vec<(&'static str, ShareOfLines)> {
+        let mut shares: Vec<(&'static str, ShareOfLines)> =
+            [("raw", self.raw), ("normalized", self.normalized)]
+                .into_iter()
+                .filter_map(|(label, share)| Some((label, share?)))
+                .collect();
+        if self.counting == "normalized" {
+            shares.reverse();
+        }
+        shares
+    }
+}
+
+pub fn line_share(entries: &[AuditEntry], counting: &str) -> LineShare {
+    let origins: Vec<&CodeOriginStats> = entries
+        .iter()
+        .filter_map(|e| e.code_origin.as_ref())
+        .filter(|o| o.total_lines() > 0)
+        .collect();
+    let sum = CodeOriginStats::sum(origins.iter().copied());
+    LineShare {
+        counting: counting.to_string(),
+        commits: origins.len() as u32,
+        raw: sum.as_ref().and_then(|s| ShareOfLines::of(s.raw())),
+        normalized: sum.and_then(|s| ShareOfLines::of(s.normalized?)),
+        normalized_commits: origins.iter().filter(|o| o.normalized.is_some()).count() as u32,
+    }
+}
+
+fn print_line_share(share: &LineShare) {
+    let ordered = share.ordered();
+    let Some(((label, first), rest)) = ordered.split_first() else {
+        return;
+    };
+    print!(
+        "AI share of added lines: {:.1}% {} ({} of {})",
+        first.ai_share_pct, label, first.ai_lines, first.total_lines
+    );
+    for (label, other) in rest {
+        print!(", {} {:.1}%", label, other.ai_share_pct);
+    }
+    println!();
+    if share.normalized.is_some() && share.normalized_commits < share.commits {
+        println!(
+            "  normalized over {} of {} commit(s); older notes have raw counts only",
+            share.normalized_commits, share.commits
+        );
+    }
+}
+
+/// "AI suggestion -> merged" latency: from a receipt's prompt to the moment
+/// its commit first reached the default branch (its first-parent history),
+/// overall and per model and team.
+#[derive(Debug, Serialize, Default)]
+pub struct MergeLatency {
+    pub base: String,
+    /// Receipts whose commit is not on the default branch yet.
+    pub unmerged_receipts: usize,
+    pub overall: DurationStats,
+    pub by_model: Vec<LatencyGroup>,
+    /// Keyed by `[analytics.teams]`; empty when no teams are configured.
+    pub by_team: Vec<LatencyGroup>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LatencyGroup {
+    pub key: String,
+    #[serde(flatten)]
+    pub stats: DurationStats,
 }
 
 #[derive(Debug, Serialize, Default)]
@@ -28,6 +173,7 @@ pub struct ModelStats {
     pub sessions: u32,
     pub files_modified: u32,
     pub total_cost: f64,
+    pub cost_by_token_type: CostBreakdown,
 }
 
 #[derive(Debug, Serialize, Default)]
@@ -37,12 +183,343 @@ pub struct UserStats {
     pub total_cost: f64,
 }
 
-pub fn generate_report(from: Option<&str>, to: Option<&str>) -> Result<AnalyticsReport, String> {
+/// One bucket of `analytics --trend`, keyed by the day (or the Monday of the
+/// week) the commits were authored.
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+pub struct TrendPoint {
+    pub period: String,
+    pub receipts: u32,
+    pub ai_lines: u32,
+    pub total_lines_added: u32,
+    pub ai_line_share: Option<f64>,
+    pub accepted_lines: u32,
+    pub overridden_lines: u32,
+    pub acceptance_rate: Option<f64>,
+    pub cost_usd: f64,
+}
+
+/// Per-developer rollup for `analytics --by-author`. Keyed by commit author,
+/// which is either the raw identity or a stable pseudonym depending on config.
+#[derive(Debug, Serialize, Default)]
+pub struct AuthorStats {
+    pub author: String,
+    pub ai_commits: u32,
+    pub prompts: u32,
+    pub total_cost: f64,
+    pub accepted_lines: u32,
+    pub overridden_lines: u32,
+    pub acceptance_rate: Option<f64>,
+    pub ai_lines: u32,
+    pub total_lines_added: u32,
+    pub ai_share_pct: f64,
+}
+
+/// Spend rollup per ticket/issue ID for `analytics --by-ticket`.
+#[derive(Debug, Serialize, Default)]
+pub struct TicketStats {
+    pub ticket: String,
+    pub commits: u32,
+    pub prompts: u32,
+    pub sessions: u32,
+    pub total_cost: f64,
+    pub ai_lines: u32,
+}
+
+/// How often sessions stopped for an explicit human decision, as evidence of
+/// oversight. Shared by `analytics` and `report`.
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+pub struct DecisionStats {
+    /// Questions the AI asked (each AskUserQuestion call counts once).
+    pub questions: u32,
+    pub answered: u32,
+    pub sessions: u32,
+    pub sessions_with_decisions: u32,
+    pub sessions_with_decisions_pct: f64,
+    /// Most common question headers first; questions without one are
+    /// counted under "(none)".
+    pub categories: Vec<DecisionCategory>,
+}
+
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+pub struct DecisionCategory {
+    pub header: String,
+    pub questions: u32,
+    pub answered: u32,
+}
+
+/// Work done by subagents of one type, for `analytics --subagents`.
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+pub struct SubagentStats {
+    pub agent_type: String,
+    pub runs: u32,
+    /// Runs whose transcript was read at SubagentStop (tokens and cost known).
+    pub measured_runs: u32,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost_usd: f64,
+    pub files_touched: u32,
+    /// Share of all spend (prompts plus subagents).
+    pub cost_share_pct: f64,
+}
+
+/// Team of receipts whose author matches no `[analytics.teams]` entry.
+const NO_TEAM: &str = "(no team)";
+
+/// Label used for receipts without a detected ticket.
+const NO_TICKET: &str = "(none)";
+
+/// Category of AI questions asked without a header.
+const NO_HEADER: &str = "(none)";
+
+/// Audit entries for the range, without receipts whose files were all deleted
+/// later unless `include_deleted`. Also returns how many were left out.
+fn collect_entries(
+    from: Option<&str>,
+    to: Option<&str>,
+    include_deleted: bool,
+) -> Result<(Vec<AuditEntry>, usize), String> {
+    let mut entries = audit::collect_audit_entries(from, to, None)?;
+    let dropped = if include_deleted {
+        0
+    } else {
+        lifecycle::drop_deleted(&mut entries, &FileLifecycle::load())
+    };
+    Ok((entries, dropped))
+}
+
+/// Stable pseudonym for an author identity such as `Jane Doe <jane@example.com>`.
+/// The email (or the whole string when no email is present) is lowercased and
+/// hashed with the configured salt so renamed authors keep the same ID.
+pub fn pseudonymize_author(identity: &str, salt: &str) -> String {
+    let key = pairing::identity_key(identity);
+    let mut hasher = Sha256::new();
+    hasher.update(salt.as_bytes());
+    hasher.update(b":");
+    hasher.update(key.as_bytes());
+    let digest = format!("{:x}", hasher.finalize());
+    format!("dev-{}", &digest[..8])
+}
+
+pub fn display_author(identity: &str, config: &AnalyticsConfig) -> String {
+    if config.author_identity == "full" {
+        identity.to_string()
+    } else {
+        pseudonymize_author(identity, &config.pseudonym_salt)
+    }
+}
+
+fn acceptance_rate(accepted: u32, overridden: u32) -> Option<f64> {
+    if accepted + overridden == 0 {
+        None
+    } else {
+        Some(accepted as f64 / (accepted + overridden) as f64 * 100.0)
+    }
+}
+
+/// Lines added per commit author across all commits in range (AI or not),
+/// used as the denominator for each developer's AI share.
+/// Lines added per person (keyed by `pairing::identity_key`), from `git log`.
+/// Commits with `Co-authored-by` trailers credit everyone on them, split or
+/// shared per `co_author_credit`.
+fn lines_added_by_author(
+    from: Option<&str>,
+    to: Option<&str>,
+    co_author_credit: &str,
+) -> Result<HashMap<String, u32>, String> {
+    let mut args = vec![
+        "log".to_string(),
+        "--numstat".to_string(),
+        "--format=@@%an <%ae>%x09%(trailers:key=Co-authored-by,valueonly,separator=%x09)"
+            .to_string(),
+    ];
+    if let Some(f) = from {
+        args.push(format!("--since={}", f));
+    }
+    if let Some(t) = to {
+        args.push(format!("--until={}", t));
+    }
+
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .output()
+        .map_err(|e| format!("git log failed: {}", e))?;
+    if !output.status.success() {
+        return Ok(HashMap::new());
+    }
+
+    let mut totals: HashMap<String, u32> = HashMap::new();
+    let mut credit = |people: &[String], added: u32| {
+        for (person, share) in
+            people
+                .iter()
+                .zip(credit_shares(added, people.len(), co_author_credit))
+        {
+            *totals.entry(person.clone()).or_insert(0) += share;
+        }
+    };
+    let mut current: Vec<String> = Vec::new();
+    let mut added_in_commit = 0u32;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(people) = line.strip_prefix("@@") {
+            credit(&current, added_in_commit);
+            added_in_commit = 0;
+            current.clear();
+            for key in people
+                .split('\t')
+                .filter(|p| !p.trim().is_empty())
+                .map(pairing::identity_key)
+            {
+                if !current.contains(&key) {
+                    current.push(key);
+                }
+            }
+            continue;
+        }
+        // numstat lines: "<added>\t<deleted>\t<path>"; binary files report "-"
+        if let Some(added) = line.split('\t').next().and_then(|n| n.parse::<u32>().ok()) {
+            added_in_commit += added;
+        }
+    }
+    credit(&current, added_in_commit);
+    Ok(totals)
+}
+
+/// `amount` credited to each of `people` people: divided with the remainder
+/// going to the first ones ("split"), or in full to everyone ("share").
+fn credit_shares(amount: u32, people: usize, co_author_credit: &str) -> Vec<u32> {
+    let n = people.max(1) as u32;
+    if co_author_credit == "share" {
+        return vec![amount; people];
+    }
+    (0..n)
+        .map(|i| amount / n + u32::from(i < amount % n))
+        .take(people)
+        .collect()
+}
+
+pub fn generate_author_report(
+    from: Option<&str>,
+    to: Option<&str>,
+    config: &AnalyticsConfig,
+    include_deleted: bool,
+) -> Result<Vec<AuthorStats>, String> {
+    let (entries, _) = collect_entries(from, to, include_deleted)?;
+    let lines_added = lines_added_by_author(from, to, &config.co_author_credit)?;
+    let share = config.co_author_credit == "share";
+
+    // Keyed by `identity_key`, with the first identity seen for display.
+    let mut by_author: HashMap<String, (String, AuthorStats)> = HashMap::new();
+    for entry in &entries {
+        let mut in_commit: HashSet<String> = HashSet::new();
+        for r in &entry.receipts {
+            let people = pairing::participants(&entry.commit_author, r);
+            let n = if share { 1.0 } else { people.len() as f64 };
+            let mode = &config.co_author_credit;
+            let accepted = credit_shares(r.accepted_lines.unwrap_or(0), people.len(), mode);
+            let overridden = credit_shares(r.overridden_lines.unwrap_or(0), people.len(), mode);
+            let ai_lines = credit_shares(r.effective_total_additions(), people.len(), mode);
+            for (i, person) in people.iter().enumerate() {
+                let key = pairing::identity_key(person);
+                let (_, stats) = by_author
+                    .entry(key.clone())
+                    .or_insert_with(|| (person.clone(), AuthorStats::default()));
+                if in_commit.insert(key) {
+                    stats.ai_commits += 1;
+                }
+                stats.prompts += 1;
+                stats.total_cost += r.cost_usd / n;
+                stats.accepted_lines += accepted[i];
+                stats.overridden_lines += overridden[i];
+                stats.ai_lines += ai_lines[i];
+            }
+        }
+    }
+
+    let mut result: Vec<AuthorStats> = by_author
+        .into_iter()
+        .map(|(key, (identity, mut stats))| {
+            stats.total_lines_added = lines_added.get(&key).copied().unwrap_or(0);
+            stats.acceptance_rate = acceptance_rate(stats.accepted_lines, stats.overridden_lines);
+            stats.ai_share_pct = if stats.total_lines_added > 0 {
+                (stats.ai_lines as f64 / stats.total_lines_added as f64 * 100.0).min(100.0)
+            } else {
+                0.0
+            };
+            stats.author = display_author(&identity, config);
+            stats
+        })
+        .collect();
+    result.sort_by(|a, b| {
+        b.total_cost
+            .partial_cmp(&a.total_cost)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(result)
+}
+
+pub fn generate_ticket_report(
+    from: Option<&str>,
+    to: Option<&str>,
+    include_deleted: bool,
+) -> Result<Vec<TicketStats>, String> {
+    let (entries, _) = collect_entries(from, to, include_deleted)?;
+
+    let mut by_ticket: HashMap<String, TicketStats> = HashMap::new();
+    let mut sessions: HashMap<String, HashSet<String>> = HashMap::new();
+    for entry in &entries {
+        let mut seen_in_commit: HashSet<String> = HashSet::new();
+        for r in &entry.receipts {
+            let key = r.ticket.clone().unwrap_or_else(|| NO_TICKET.to_string());
+            let stats = by_ticket.entry(key.clone()).or_insert_with(|| TicketStats {
+                ticket: key.clone(),
+                ..Default::default()
+            });
+            if seen_in_commit.insert(key.clone()) {
+                stats.commits += 1;
+            }
+            stats.prompts += 1;
+            stats.total_cost += r.cost_usd;
+            stats.ai_lines += r.effective_total_additions();
+            sessions
+                .entry(key)
+                .or_default()
+                .insert(r.session_id.clone());
+        }
+    }
+
+    let mut result: Vec<TicketStats> = by_ticket
+        .into_values()
+        .map(|mut stats| {
+            stats.sessions = sessions.get(&stats.ticket).map_or(0, |s| s.len() as u32);
+            stats
+        })
+        .collect();
+    result.sort_by(|a, b| {
+        b.total_cost
+            .partial_cmp(&a.total_cost)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    Ok(result)
+}
+
+/// With `include_uncommitted`, staged receipts count toward the receipt-level
+/// totals (cost, lines, models); commit-level figures stay committed-only.
+pub fn generate_report(
+    from: Option<&str>,
+    to: Option<&str>,
+    include_deleted: bool,
+    include_uncommitted: bool,
+) -> Result<AnalyticsReport, String> {
     // Get total commits
     let total_commits = count_total_commits()?;
 
     // Get audit entries (commits with AI)
-    let entries = audit::collect_audit_entries(from, to, None)?;
+    let (entries, deleted_receipts) = collect_entries(from, to, include_deleted)?;
+    let staged = if include_uncommitted {
+        audit::collect_staged_entries()
+    } else {
+        Vec::new()
+    };
 
     let commits_with_ai = entries.len() as u32;
     let ai_commit_percentage = if total_commits > 0 {
@@ -53,21 +530,32 @@ pub fn generate_report(from: Option<&str>, to: Option<&str>) -> Result<Analytics
 
     let mut total_receipts = 0u32;
     let mut total_cost = 0.0f64;
+    let mut text_estimated_cost = 0.0f64;
+    let mut by_token_type = CostBreakdown::default();
+    let mut without_token_data = 0.0f64;
     let mut total_lines = 0u32;
+    let mut binary_files = 0u32;
+    let mut binary_bytes_delta = 0i64;
     let mut session_ids: HashSet<String> = HashSet::new();
     let mut by_provider: HashMap<String, ProviderStats> = HashMap::new();
     let mut by_model: HashMap<String, ModelStats> = HashMap::new();
-    let mut by_user: HashMap<String, UserStats> = HashMap::new();
 
-    for entry in &entries {
+    for entry in entries.iter().chain(&staged) {
         for r in &entry.receipts {
             total_receipts += 1;
             total_cost += r.cost_usd;
+            if r.cost_estimated {
+                text_estimated_cost += r.cost_usd;
+            }
             // Use precise diff additions when available,
             // falling back to the legacy line-range span for older receipts.
             let lines = r.effective_total_additions();
             total_lines += lines;
             session_ids.insert(r.session_id.clone());
+            for fc in r.all_file_changes().iter().filter(|fc| fc.is_binary) {
+                binary_files += 1;
+                binary_bytes_delta += fc.byte_delta();
+            }
 
             // By provider
             let ps = by_provider.entry(r.provider.clone()).or_default();
@@ -80,15 +568,36 @@ pub fn generate_report(from: Option<&str>, to: Option<&str>) -> Result<Analytics
             ms.sessions += 1;
             ms.files_modified += r.all_file_changes().len() as u32;
             ms.total_cost += r.cost_usd;
-
-            // By user
-            let us = by_user.entry(r.user.clone()).or_default();
-            us.sessions += 1;
-            us.lines_generated += lines;
-            us.total_cost += r.cost_usd;
+            match token_costs(r) {
+                Some(costs) => {
+                    by_token_type.add(&costs);
+                    ms.cost_by_token_type.add(&costs);
+                }
+                None => without_token_data += r.cost_usd,
+            }
         }
     }
 
+    let by_user: HashMap<String, UserStats> = grouping::group(&entries, GroupBy::Author)
+        .into_iter()
+        .map(|g| {
+            let stats = UserStats {
+                sessions: g.receipts as u32,
+                lines_generated: g.ai_lines,
+                total_cost: g.cost_usd,
+            };
+            (g.key, stats)
+        })
+        .collect();
+
+    let cfg = config::load_config();
+    let rework = rework::analyze(&entries, &cfg.metrics)?;
+    let receipts: Vec<&Receipt> = entries
+        .iter()
+        .chain(&staged)
+        .flat_map(|e| &e.receipts)
+        .collect();
+
     Ok(AnalyticsReport {
         total_commits_scanned: total_commits,
         commits_with_ai,
@@ -96,13 +605,413 @@ pub fn generate_report(from: Option<&str>, to: Option<&str>) -> Result<Analytics
         total_receipts,
         total_sessions: session_ids.len() as u32,
         total_estimated_cost_usd: total_cost,
+        text_estimated_cost_usd: text_estimated_cost,
+        cost_by_token_type: by_token_type,
+        cost_without_token_data_usd: without_token_data,
         total_ai_lines: total_lines,
+        binary_files,
+        binary_bytes_delta,
         by_provider,
         by_model,
         by_user,
+        commits_requiring_rework: rework.commits_reworked() as u32,
+        rework: rework.links,
+        human_decisions: decision_stats(&receipts),
+        trend: Vec::new(),
+        deleted_receipts: deleted_receipts as u32,
+        merge_latency: merge_latency(&entries, &cfg.analytics),
+        line_share: line_share(&entries, &cfg.analytics.line_counting),
+        uncommitted_receipts: staged.iter().map(|e| e.receipts.len() as u32).sum(),
     })
 }
 
+/// `r`'s spend by token type, priced like its `cost_usd`; `None` when the
+/// receipt has no token counts.
+pub fn token_costs(r: &Receipt) -> Option<CostBreakdown> {
+    if r.input_tokens.is_none() && r.output_tokens.is_none() {
+        return None;
+    }
+    Some(pricing::cost_breakdown(
+        &r.model,
+        r.input_tokens.unwrap_or(0),
+        r.output_tokens.unwrap_or(0),
+        r.cache_read_tokens.unwrap_or(0),
+        r.cache_creation_tokens.unwrap_or(0),
+    ))
+}
+
+/// Team of `author` per `[analytics.teams]`: the first team (by name) with
+/// an entry contained in the author's `Name <email>`, case-insensitively.
+pub fn team_of(author: &str, config: &AnalyticsConfig) -> String {
+    let author = author.to_lowercase();
+    config
+        .teams
+        .iter()
+        .find(|(_, members)| {
+            members
+                .iter()
+                .any(|m| !m.is_empty() && author.contains(&m.to_lowercase()))
+        })
+        .map(|(team, _)| team.clone())
+        .unwrap_or_else(|| NO_TEAM.to_string())
+}
+
+fn merge_latency(entries: &[AuditEntry], config: &AnalyticsConfig) -> MergeLatency {
+    let Some(base) = metrics::default_base() else {
+        return MergeLatency::default();
+    };
+    let first_parent: HashSet<String> = metrics::git_lines(&["rev-list", "--first-parent", &base])
+        .into_iter()
+        .collect();
+    let mut samples = Vec::new();
+    let mut unmerged = 0;
+    for entry in entries {
+        let Some(merged) = metrics::merged_at(&entry.commit_sha, &base, &first_parent) else {
+            unmerged += entry.receipts.len();
+            continue;
+        };
+        for r in &entry.receipts {
+            let suggested = r.prompt_submitted_at.unwrap_or(r.timestamp);
+            if suggested <= merged {
+                samples.push(LatencySample {
+                    model: r.model.clone(),
+                    team: team_of(&r.user, config),
+                    hours: metrics::hours(merged - suggested),
+                });
+            }
+        }
+    }
+    summarize_latency(base, unmerged, &samples, !config.teams.is_empty())
+}
+
+#[derive(Debug, Clone)]
+pub struct LatencySample {
+    pub model: String,
+    pub team: String,
+    pub hours: f64,
+}
+
+pub fn summarize_latency(
+    base: String,
+    unmerged_receipts: usize,
+    samples: &[LatencySample],
+    by_team: bool,
+) -> MergeLatency {
+    let groups = |key: fn(&LatencySample) -> &str| -> Vec<LatencyGroup> {
+        let mut grouped: BTreeMap<&str, Vec<f64>> = BTreeMap::new();
+        for s in samples {
+            grouped.entry(key(s)).or_default().push(s.hours);
+        }
+        grouped
+            .into_iter()
+            .map(|(k, hours)| LatencyGroup {
+                key: k.to_string(),
+                stats: metrics::duration_stats(hours),
+            })
+            .collect()
+    };
+    MergeLatency {
+        base,
+        unmerged_receipts,
+        overall: metrics::duration_stats(samples.iter().map(|s| s.hours).collect()),
+        by_model: groups(|s| &s.model),
+        by_team: if by_team {
+            groups(|s| &s.team)
+        } else {
+            Vec::new()
+        },
+    }
+}
+
+/// Summarize the `user_decisions` of `receipts`. A decision carried by
+/// several receipts of the same session is counted once.
+pub fn decision_stats(receipts: &[&Receipt]) -> DecisionStats {
+    let sessions: HashSet<&str> = receipts.iter().map(|r| r.session_id.as_str()).collect();
+    let mut seen: HashSet<(&str, &str)> = HashSet::new();
+    let mut with_decisions: HashSet<&str> = HashSet::new();
+    let mut categories: Vec<DecisionCategory> = Vec::new();
+    let mut stats = DecisionStats {
+        sessions: sessions.len() as u32,
+        ..DecisionStats::default()
+    };
+    for r in receipts {
+        for d in &r.user_decisions {
+            if !seen.insert((r.session_id.as_str(), d.tool_use_id.as_str())) {
+                continue;
+            }
+            with_decisions.insert(r.session_id.as_str());
+            let answered = d.answer.as_ref().is_some_and(|a| !a.trim().is_empty())
+                || d.options.iter().any(|o| o.selected);
+            stats.questions += 1;
+            stats.answered += answered as u32;
+
+            let header = d
+                .header
+                .as_deref()
+                .map(str::trim)
+                .filter(|h| !h.is_empty())
+                .unwrap_or(NO_HEADER);
+            let i = match categories
+                .iter()
+                .position(|c| c.header.eq_ignore_ascii_case(header))
+            {
+                Some(i) => i,
+                None => {
+                    categories.push(DecisionCategory {
+                        header: header.to_string(),
+                        ..DecisionCategory::default()
+                    });
+                    categories.len() - 1
+                }
+            };
+            categories[i].questions += 1;
+            categories[i].answered += answered as u32;
+        }
+    }
+    categories.sort_by(|a, b| b.questions.cmp(&a.questions).then(a.header.cmp(&b.header)));
+    stats.sessions_with_decisions = with_decisions.len() as u32;
+    if stats.sessions > 0 {
+        stats.sessions_with_decisions_pct =
+            stats.sessions_with_decisions as f64 / stats.sessions as f64 * 100.0;
+    }
+    stats.categories = categories;
+    stats
+}
+
+/// Subagent work per agent type, most expensive first.
+pub fn subagent_breakdown(receipts: &[&Receipt]) -> Vec<SubagentStats> {
+    let mut by_type: Vec<(SubagentStats, HashSet<String>)> = Vec::new();
+    for a in receipts.iter().flat_map(|r| &r.subagent_activities) {
+        let agent_type = a.agent_type.as_deref().unwrap_or("unknown");
+        let i = match by_type.iter().position(|(s, _)| s.agent_type == agent_type) {
+            Some(i) => i,
+            None => {
+                by_type.push((
+                    SubagentStats {
+                        agent_type: agent_type.to_string(),
+                        ..SubagentStats::default()
+                    },
+                    HashSet::new(),
+                ));
+                by_type.len() - 1
+            }
+        };
+        let (stats, files) = &mut by_type[i];
+        stats.runs += 1;
+        if let Some(cost) = a.cost_usd {
+            stats.measured_runs += 1;
+            stats.cost_usd += cost;
+        }
+        stats.input_tokens += a.input_tokens.unwrap_or(0);
+        stats.output_tokens += a.output_tokens.unwrap_or(0);
+        files.extend(a.files_touched.iter().cloned());
+    }
+
+    let total: f64 = receipts
+        .iter()
+        .map(|r| r.cost_usd + r.subagent_cost_usd())
+        .sum();
+    let mut out: Vec<SubagentStats> = by_type
+        .into_iter()
+        .map(|(mut stats, files)| {
+            stats.files_touched = files.len() as u32;
+            if total > 0.0 {
+                stats.cost_share_pct = stats.cost_usd / total * 100.0;
+            }
+            stats
+        })
+        .collect();
+    out.sort_by(|a, b| {
+        b.cost_usd
+            .partial_cmp(&a.cost_usd)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(b.runs.cmp(&a.runs))
+    });
+    out
+}
+
+/// Bucket key for a commit date: the day, or the Monday starting its week.
+fn trend_bucket(date: NaiveDate, weekly: bool) -> NaiveDate {
+    if weekly {
+        date - Duration::days(date.weekday().num_days_from_monday() as i64)
+    } else {
+        date
+    }
+}
+
+fn parse_commit_day(iso: &str) -> Option<NaiveDate> {
+    DateTime::parse_from_rfc3339(iso)
+        .ok()
+        .map(|d| d.date_naive())
+        .or_else(|| NaiveDate::parse_from_str(iso.get(..10)?, "%Y-%m-%d").ok())
+}
+
+/// Daily or weekly series of acceptance rate, AI line share and cost over
+/// commits in `[from, to]`. Empty buckets between the first and last commit are
+/// kept so the series is evenly spaced.
+pub fn generate_trend(
+    from: Option<&str>,
+    to: Option<&str>,
+    period: &str,
+    include_deleted: bool,
+) -> Result<Vec<TrendPoint>, String> {
+    let weekly = match period {
+        "daily" => false,
+        "weekly" => true,
+        other => {
+            return Err(format!(
+                "Unknown --trend period \"{}\" (use daily or weekly)",
+                other
+            ))
+        }
+    };
+
+    let mut buckets: HashMap<NaiveDate, TrendPoint> = HashMap::new();
+    for entry in collect_entries(from, to, include_deleted)?.0 {
+        let Some(day) = parse_commit_day(&entry.commit_date) else {
+            continue;
+        };
+        let point = buckets.entry(trend_bucket(day, weekly)).or_default();
+        for r in &entry.receipts {
+            point.receipts += 1;
+            point.ai_lines += r.effective_total_additions();
+            point.accepted_lines += r.accepted_lines.unwrap_or(0);
+            point.overridden_lines += r.overridden_lines.unwrap_or(0);
+            point.cost_usd += r.cost_usd;
+        }
+    }
+    for (day, added) in lines_added_by_day(from, to)? {
+        buckets
+            .entry(trend_bucket(day, weekly))
+            .or_default()
+            .total_lines_added += added;
+    }
+
+    let (Some(&first), Some(&last)) = (buckets.keys().min(), buckets.keys().max()) else {
+        return Ok(Vec::new());
+    };
+    let step = Duration::days(if weekly { 7 } else { 1 });
+    let mut series = Vec::new();
+    let mut day = first;
+    while day <= last {
+        let mut point = buckets.remove(&day).unwrap_or_default();
+        point.period = day.format("%Y-%m-%d").to_string();
+        point.acceptance_rate = acceptance_rate(point.accepted_lines, point.overridden_lines);
+        point.ai_line_share = (point.total_lines_added > 0).then(|| {
+            (point.ai_lines.min(point.total_lines_added) as f64 / point.total_lines_added as f64)
+                * 100.0
+        });
+        series.push(point);
+        day += step;
+    }
+    Ok(series)
+}
+
+/// Lines added per author-date day across all commits in range (AI or not).
+fn lines_added_by_day(
+    from: Option<&str>,
+    to: Option<&str>,
+) -> Result<HashMap<NaiveDate, u32>, String> {
+    let mut args = vec![
+        "log".to_string(),
+        "--numstat".to_string(),
+        "--format=@@%aI".to_string(),
+    ];
+    if let Some(f) = from {
+        args.push(format!("--since={}", f));
+    }
+    if let Some(t) = to {
+        args.push(format!("--until={}", t));
+    }
+
+    let output = std::process::Command::new("git")
+        .args(&args)
+        .output()
+        .map_err(|e| format!("git log failed: {}", e))?;
+    if !output.status.success() {
+        return Ok(HashMap::new());
+    }
+
+    let mut totals: HashMap<NaiveDate, u32> = HashMap::new();
+    let mut current: Option<NaiveDate> = None;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        if let Some(date) = line.strip_prefix("@@") {
+            current = parse_commit_day(date);
+            continue;
+        }
+        let added = line.split('\t').next().and_then(|n| n.parse::<u32>().ok());
+        if let (Some(day), Some(added)) = (current, added) {
+            *totals.entry(day).or_insert(0) += added;
+        }
+    }
+    Ok(totals)
+}
+
+/// Terminal sparkline (▁..█) scaled between the series' min and max. Missing
+/// values render as a space.
+pub fn sparkline(values: &[Option<f64>]) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+    let present: Vec<f64> = values.iter().flatten().copied().collect();
+    let min = present.iter().copied().fold(f64::INFINITY, f64::min);
+    let max = present.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    values
+        .iter()
+        .map(|v| match v {
+            None => ' ',
+            Some(_) if max <= min => BARS[3],
+            Some(v) => BARS[(((v - min) / (max - min)) * 7.0).round() as usize],
+        })
+        .collect()
+}
+
+fn print_trend(trend: &[TrendPoint], period: &str) {
+    let title = format!("TREND ({})", period);
+    println!("{}", title);
+    println!("{}", "=".repeat(title.len()));
+    let (Some(first), Some(last)) = (trend.first(), trend.last()) else {
+        println!("No AI commits in range.");
+        println!();
+        return;
+    };
+    println!(
+        "{} .. {} ({} {})",
+        first.period,
+        last.period,
+        trend.len(),
+        if period == "weekly" { "weeks" } else { "days" }
+    );
+
+    let endpoints = |values: &[Option<f64>], fmt: &dyn Fn(f64) -> String| {
+        let mut present = values.iter().flatten();
+        match (present.next(), values.iter().flatten().last()) {
+            (Some(a), Some(b)) => format!("{} -> {}", fmt(*a), fmt(*b)),
+            _ => "n/a".to_string(),
+        }
+    };
+    let pct = |v: f64| format!("{:.0}%", v);
+    let usd = |v: f64| format!("${:.2}", v);
+
+    let acceptance: Vec<Option<f64>> = trend.iter().map(|p| p.acceptance_rate).collect();
+    let share: Vec<Option<f64>> = trend.iter().map(|p| p.ai_line_share).collect();
+    let cost: Vec<Option<f64>> = trend.iter().map(|p| Some(p.cost_usd)).collect();
+    println!(
+        "Acceptance rate  {}  {}",
+        sparkline(&acceptance),
+        endpoints(&acceptance, &pct)
+    );
+    println!(
+        "AI line share    {}  {}",
+        sparkline(&share),
+        endpoints(&share, &pct)
+    );
+    println!(
+        "Cost             {}  {}",
+        sparkline(&cost),
+        endpoints(&cost, &usd)
+    );
+    println!();
+}
+
 fn count_total_commits() -> Result<u32, String> {
     let output = std::process::Command::new("git")
         .args(["rev-list", "--count", "HEAD"])
@@ -119,14 +1028,44 @@ fn count_total_commits() -> Result<u32, String> {
         .map_err(|e| format!("Parse error: {}", e))
 }
 
-pub fn run(export_format: Option<&str>) {
-    let report = match generate_report(None, None) {
+pub fn run(
+    export_format: Option<&str>,
+    by_author: bool,
+    by_ticket: bool,
+    trend: Option<&str>,
+    include_deleted: bool,
+    include_uncommitted: bool,
+    anonymize_paths: bool,
+) {
+    if by_author {
+        run_by_author(export_format, include_deleted);
+        return;
+    }
+    if by_ticket {
+        run_by_ticket(export_format, include_deleted);
+        return;
+    }
+
+    let mut report = match generate_report(None, None, include_deleted, include_uncommitted) {
         Ok(r) => r,
         Err(e) => {
             eprintln!("Error: {}", e);
             return;
         }
     };
+    if anonymize_paths {
+        let salt = config::load_config().analytics.pseudonym_salt;
+        anonymize_paths::anonymize_rework(&mut report.rework, &salt);
+    }
+    if let Some(period) = trend {
+        match generate_trend(None, None, period, include_deleted) {
+            Ok(series) => report.trend = series,
+            Err(e) => {
+                eprintln!("Error: {}", e);
+                return;
+            }
+        }
+    }
 
     match export_format {
         Some("json") => {
@@ -146,15 +1085,76 @@ pub fn run(export_format: Option<&str>) {
                 "total_estimated_cost_usd,{:.2}",
                 report.total_estimated_cost_usd
             );
+            println!(
+                "text_estimated_cost_usd,{:.2}",
+                report.text_estimated_cost_usd
+            );
+            let spend = &report.cost_by_token_type;
+            println!("fresh_input_cost_usd,{:.2}", spend.fresh_input_usd);
+            println!("cache_write_cost_usd,{:.2}", spend.cache_write_usd);
+            println!("cache_read_cost_usd,{:.2}", spend.cache_read_usd);
+            println!("output_cost_usd,{:.2}", spend.output_usd);
+            println!("cache_savings_usd,{:.2}", spend.cache_savings_usd);
             println!("total_ai_lines,{}", report.total_ai_lines);
+            for (label, share) in report.line_share.ordered() {
+                println!("ai_line_share_{}_pct,{:.1}", label, share.ai_share_pct);
+            }
+            println!("binary_files,{}", report.binary_files);
+            println!("binary_bytes_delta,{}", report.binary_bytes_delta);
+            println!(
+                "commits_requiring_rework,{}",
+                report.commits_requiring_rework
+            );
+            println!("deleted_receipts_excluded,{}", report.deleted_receipts);
+            println!(
+                "uncommitted_receipts_included,{}",
+                report.uncommitted_receipts
+            );
+            println!("ai_questions,{}", report.human_decisions.questions);
+            println!("ai_questions_answered,{}", report.human_decisions.answered);
+            println!(
+                "sessions_with_human_decisions_pct,{:.1}",
+                report.human_decisions.sessions_with_decisions_pct
+            );
+            if let Some(median) = report.merge_latency.overall.median_hours {
+                println!("median_suggestion_to_merge_hours,{:.1}", median);
+            }
             println!();
-            println!("model,sessions,files_modified,total_cost");
+            println!("model,sessions,files_modified,total_cost,fresh_input_cost,cache_write_cost,cache_read_cost,output_cost,cache_savings");
             for (model, stats) in &report.by_model {
+                let spend = &stats.cost_by_token_type;
                 println!(
-                    "{},{},{},{:.4}",
-                    model, stats.sessions, stats.files_modified, stats.total_cost
+                    "{},{},{},{:.4},{:.4},{:.4},{:.4},{:.4},{:.4}",
+                    model,
+                    stats.sessions,
+                    stats.files_modified,
+                    stats.total_cost,
+                    spend.fresh_input_usd,
+                    spend.cache_write_usd,
+                    spend.cache_read_usd,
+                    spend.output_usd,
+                    spend.cache_savings_usd
                 );
             }
+            if !report.trend.is_empty() {
+                let opt = |v: Option<f64>| v.map(|v| format!("{:.1}", v)).unwrap_or_default();
+                println!();
+                println!("period,receipts,ai_lines,total_lines_added,ai_line_share_pct,accepted_lines,overridden_lines,acceptance_rate_pct,cost_usd");
+                for p in &report.trend {
+                    println!(
+                        "{},{},{},{},{},{},{},{},{:.4}",
+                        p.period,
+                        p.receipts,
+                        p.ai_lines,
+                        p.total_lines_added,
+                        opt(p.ai_line_share),
+                        p.accepted_lines,
+                        p.overridden_lines,
+                        opt(p.acceptance_rate),
+                        p.cost_usd
+                    );
+                }
+            }
         }
         _ => {
             println!("OVERVIEW");
@@ -166,6 +1166,25 @@ pub fn run(export_format: Option<&str>) {
             );
             println!("Total sessions: {}", report.total_sessions);
             println!("Total AI lines: {}", report.total_ai_lines);
+            print_line_share(&report.line_share);
+            if report.uncommitted_receipts > 0 {
+                println!(
+                    "Includes {} uncommitted receipt(s) from staging (provisional until committed)",
+                    report.uncommitted_receipts
+                );
+            }
+            if report.deleted_receipts > 0 {
+                println!(
+                    "Left out: {} receipt(s) whose files were all deleted later (--include-deleted)",
+                    report.deleted_receipts
+                );
+            }
+            if report.binary_files > 0 {
+                println!(
+                    "Binary assets: {} file change(s), {:+} bytes (not counted as lines)",
+                    report.binary_files, report.binary_bytes_delta
+                );
+            }
             println!();
 
             println!("COST");
@@ -174,6 +1193,12 @@ pub fn run(export_format: Option<&str>) {
                 "Total estimated cost: ${:.2}",
                 report.total_estimated_cost_usd
             );
+            if report.text_estimated_cost_usd > 0.0 {
+                println!(
+                    "  of which estimated from text (no usage data): ${:.2}",
+                    report.text_estimated_cost_usd
+                );
+            }
             if report.total_sessions > 0 {
                 println!(
                     "Avg cost per session: ${:.3}",
@@ -181,6 +1206,50 @@ pub fn run(export_format: Option<&str>) {
                 );
             }
             println!();
+            print_token_costs(&report);
+
+            if let Some(period) = trend {
+                print_trend(&report.trend, period);
+            }
+
+            if !report.rework.is_empty() {
+                println!("AI CODE THAT REQUIRED REWORK");
+                println!("============================");
+                println!(
+                    "{} of {} AI commit(s) were reverted, fixed or heavily rewritten soon after",
+                    report.commits_requiring_rework, report.commits_with_ai
+                );
+                let mut table = comfy_table::Table::new();
+                table.set_header(vec![
+                    "AI Commit",
+                    "Follow-up",
+                    "Kind",
+                    "File",
+                    "Lines",
+                    "Days",
+                ]);
+                for link in report.rework.iter().take(10) {
+                    table.add_row(vec![
+                        link.ai_commit.clone(),
+                        format!("{} {}", link.follow_up, link.follow_up_subject),
+                        link.kind.to_string(),
+                        link.file.clone(),
+                        format!("{}/{}", link.lines_modified, link.ai_lines),
+                        format!("{:.1}", link.days_after),
+                    ]);
+                }
+                println!("{table}");
+                if report.rework.len() > 10 {
+                    println!(
+                        "... and {} more (see --export json)",
+                        report.rework.len() - 10
+                    );
+                }
+                println!();
+            }
+
+            print_decisions(&report.human_decisions);
+            print_merge_latency(&report.merge_latency);
 
             println!("BY MODEL");
             println!("========");
@@ -212,12 +1281,14 @@ pub fn run(export_format: Option<&str>) {
             println!("{table}");
             println!();
 
-            // Collect unique files from audit entries to calculate code origin
+            // Collect unique text files from audit entries to calculate code origin
             let all_files: std::collections::HashSet<String> =
                 if let Ok(entries) = audit::collect_audit_entries(None, None, None) {
                     entries
                         .iter()
-                        .flat_map(|e| e.receipts.iter().flat_map(|r| r.all_file_paths()))
+                        .flat_map(|e| e.receipts.iter().flat_map(|r| r.all_file_changes()))
+                        .filter(|fc| !fc.is_binary)
+                        .map(|fc| fc.path)
                         .collect()
                 } else {
                     std::collections::HashSet::new()
@@ -244,3 +1315,556 @@ pub fn run(export_format: Option<&str>) {
         }
     }
 }
+
+fn print_decisions(d: &DecisionStats) {
+    println!("HUMAN DECISIONS");
+    println!("===============");
+    if d.questions == 0 {
+        println!("The AI asked no questions in the scanned sessions.");
+        println!();
+        return;
+    }
+    println!(
+        "Questions asked by the AI: {} ({} answered)",
+        d.questions, d.answered
+    );
+    println!(
+        "Sessions with explicit human decisions: {} of {} ({:.1}%)",
+        d.sessions_with_decisions, d.sessions, d.sessions_with_decisions_pct
+    );
+    let mut table = comfy_table::Table::new();
+    table.set_header(vec!["Category", "Questions", "Answered"]);
+    for c in d.categories.iter().take(10) {
+        table.add_row(vec![
+            c.header.clone(),
+            c.questions.to_string(),
+            c.answered.to_string(),
+        ]);
+    }
+    println!("{table}");
+    println!();
+}
+
+fn print_token_costs(report: &AnalyticsReport) {
+    let total = &report.cost_by_token_type;
+    if total.total_usd() == 0.0 && total.cache_read_tokens == 0 {
+        return;
+    }
+    println!("COST BY TOKEN TYPE");
+    println!("==================");
+    let mut table = comfy_table::Table::new();
+    table.set_header(vec![
+        "Model",
+        "Fresh input",
+        "Cache writes",
+        "Cache reads",
+        "Output",
+        "Cache savings",
+    ]);
+    let usd = |v: f64| format!("${:.4}", v);
+    let mut models: Vec<(&String, &ModelStats)> = report.by_model.iter().collect();
+    models.sort_by(|a, b| b.1.total_cost.total_cmp(&a.1.total_cost));
+    let rows = models
+        .into_iter()
+        .map(|(model, stats)| (model.as_str(), &stats.cost_by_token_type))
+        .chain(std::iter::once(("Total", total)));
+    for (label, spend) in rows {
+        table.add_row(vec![
+            label.to_string(),
+            usd(spend.fresh_input_usd),
+            usd(spend.cache_write_usd),
+            usd(spend.cache_read_usd),
+            usd(spend.output_usd),
+            usd(spend.cache_savings_usd),
+        ]);
+    }
+    println!("{table}");
+    if report.cost_without_token_data_usd > 0.0 {
+        println!(
+            "Not split: ${:.2} from receipts without token counts",
+            report.cost_without_token_data_usd
+        );
+    }
+    println!();
+}
+
+fn print_merge_latency(latency: &MergeLatency) {
+    if latency.overall.samples == 0 {
+        return;
+    }
+    let title = format!("AI SUGGESTION -> MERGED ({})", latency.base);
+    println!("{}", title);
+    println!("{}", "=".repeat(title.len()));
+    let hours = |v: Option<f64>| v.map(|h| format!("{:.1}h", h)).unwrap_or("-".into());
+    println!(
+        "Median {} over {} receipt(s); {} not merged yet",
+        hours(latency.overall.median_hours),
+        latency.overall.samples,
+        latency.unmerged_receipts
+    );
+    let mut table = comfy_table::Table::new();
+    table.set_header(vec!["Model / Team", "Receipts", "Median", "P90"]);
+    let teams = latency
+        .by_team
+        .iter()
+        .map(|g| (format!("team: {}", g.key), g));
+    for (label, g) in latency
+        .by_model
+        .iter()
+        .map(|g| (g.key.clone(), g))
+        .chain(teams)
+    {
+        table.add_row(vec![
+            label,
+            g.stats.samples.to_string(),
+            hours(g.stats.median_hours),
+            hours(g.stats.p90_hours),
+        ]);
+    }
+    println!("{table}");
+    println!();
+}
+
+pub fn run_subagents(export_format: Option<&str>) {
+    let entries = match audit::collect_audit_entries(None, None, None) {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    let receipts: Vec<&Receipt> = entries.iter().flat_map(|e| &e.receipts).collect();
+    let stats = subagent_breakdown(&receipts);
+
+    match export_format {
+        Some("json") => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&stats).unwrap_or_default()
+            );
+        }
+        Some("csv") => {
+            println!("agent_type,runs,measured_runs,input_tokens,output_tokens,cost_usd,files_touched,cost_share_pct");
+            for s in &stats {
+                println!(
+                    "{},{},{},{},{},{:.4},{},{:.1}",
+                    audit::csv_escape(&s.agent_type),
+                    s.runs,
+                    s.measured_runs,
+                    s.input_tokens,
+                    s.output_tokens,
+                    s.cost_usd,
+                    s.files_touched,
+                    s.cost_share_pct
+                );
+            }
+        }
+        _ => {
+            println!("SUBAGENTS");
+            println!("=========");
+            if stats.is_empty() {
+                println!("No subagent activity recorded.");
+                return;
+            }
+            let mut table = comfy_table::Table::new();
+            table.set_header(vec![
+                "Agent Type",
+                "Runs",
+                "Input Tokens",
+                "Output Tokens",
+                "Est. Cost",
+                "Files",
+                "Share of Spend",
+            ]);
+            for s in &stats {
+                table.add_row(vec![
+                    s.agent_type.clone(),
+                    if s.measured_runs < s.runs {
+                        format!("{} ({} measured)", s.runs, s.measured_runs)
+                    } else {
+                        s.runs.to_string()
+                    },
+                    s.input_tokens.to_string(),
+                    s.output_tokens.to_string(),
+                    format!("${:.4}", s.cost_usd),
+                    s.files_touched.to_string(),
+                    format!("{:.1}%", s.cost_share_pct),
+                ]);
+            }
+            println!("{table}");
+            let total: f64 = stats.iter().map(|s| s.cost_usd).sum();
+            let runs: u32 = stats.iter().map(|s| s.runs).sum();
+            println!("{} subagent run(s), ${:.2} in total", runs, total);
+        }
+    }
+}
+
+fn run_by_author(export_format: Option<&str>, include_deleted: bool) {
+    let config = crate::core::config::load_config().analytics;
+    if !config.per_author {
+        eprintln!("[BlamePrompt] Per-author analytics are disabled.");
+        eprintln!("  Opt in by adding the following to .blamepromptrc:");
+        eprintln!();
+        eprintln!("  [analytics]");
+        eprintln!("  per_author = true");
+        eprintln!("  author_identity = \"pseudonym\"   # or \"full\"");
+        std::process::exit(1);
+    }
+
+    let authors = match generate_author_report(None, None, &config, include_deleted) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    let rate_str = |rate: Option<f64>| rate.map(|r| format!("{:.1}", r)).unwrap_or_default();
+
+    match export_format {
+        Some("json") => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&authors).unwrap_or_default()
+            );
+        }
+        Some("csv") => {
+            println!("author,ai_commits,prompts,total_cost,acceptance_rate,ai_lines,total_lines_added,ai_share_pct");
+            for a in &authors {
+                println!(
+                    "{},{},{},{:.4},{},{},{},{:.1}",
+                    audit::csv_escape(&a.author),
+                    a.ai_commits,
+                    a.prompts,
+                    a.total_cost,
+                    rate_str(a.acceptance_rate),
+                    a.ai_lines,
+                    a.total_lines_added,
+                    a.ai_share_pct
+                );
+            }
+        }
+        _ => {
+            println!("BY AUTHOR");
+            println!("=========");
+            if authors.is_empty() {
+                println!("No AI-assisted commits found.");
+                return;
+            }
+            let mut table = comfy_table::Table::new();
+            table.set_header(vec![
+                "Author",
+                "AI Commits",
+                "Prompts",
+                "Est. Cost",
+                "Acceptance",
+                "AI Share",
+            ]);
+            for a in &authors {
+                let acceptance = a
+                    .acceptance_rate
+                    .map(|r| format!("{:.0}%", r))
+                    .unwrap_or_else(|| "-".to_string());
+                table.add_row(vec![
+                    a.author.clone(),
+                    a.ai_commits.to_string(),
+                    a.prompts.to_string(),
+                    format!("${:.4}", a.total_cost),
+                    acceptance,
+                    format!("{:.1}%", a.ai_share_pct),
+                ]);
+            }
+            println!("{table}");
+            if config.author_identity != "full" {
+                println!("Author identities are pseudonymized (analytics.author_identity = \"pseudonym\").");
+            }
+        }
+    }
+}
+
+fn run_by_ticket(export_format: Option<&str>, include_deleted: bool) {
+    let tickets = match generate_ticket_report(None, None, include_deleted) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+
+    match export_format {
+        Some("json") => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&tickets).unwrap_or_default()
+            );
+        }
+        Some("csv") => {
+            println!("ticket,commits,prompts,sessions,total_cost,ai_lines");
+            for t in &tickets {
+                println!(
+                    "{},{},{},{},{:.4},{}",
+                    audit::csv_escape(&t.ticket),
+                    t.commits,
+                    t.prompts,
+                    t.sessions,
+                    t.total_cost,
+                    t.ai_lines
+                );
+            }
+        }
+        _ => {
+            println!("BY TICKET");
+            println!("=========");
+            if tickets.is_empty() {
+                println!("No AI-assisted commits found.");
+                return;
+            }
+            let total_cost: f64 = tickets.iter().map(|t| t.total_cost).sum();
+            let mut table = comfy_table::Table::new();
+            table.set_header(vec![
+                "Ticket",
+                "Commits",
+                "Prompts",
+                "Sessions",
+                "AI Lines",
+                "Est. Cost",
+                "% of Spend",
+            ]);
+            for t in &tickets {
+                let pct = if total_cost > 0.0 {
+                    t.total_cost / total_cost * 100.0
+                } else {
+                    0.0
+                };
+                table.add_row(vec![
+                    t.ticket.clone(),
+                    t.commits.to_string(),
+                    t.prompts.to_string(),
+                    t.sessions.to_string(),
+                    t.ai_lines.to_string(),
+                    format!("${:.4}", t.total_cost),
+                    format!("{:.1}%", pct),
+                ]);
+            }
+            println!("{table}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decision_stats() {
+        let receipt = |session: &str, decisions: serde_json::Value| -> Receipt {
+            let mut r = Receipt::test(&format!("r-{}", session))
+                .with_model("m")
+                .with_session(session)
+                .with_timestamp("2026-01-01T00:00:00Z");
+            r.user_decisions = serde_json::from_value(decisions).unwrap();
+            r
+        };
+        let a = receipt(
+            "s1",
+            serde_json::json!([
+                {"tool_use_id": "t1", "question": "Which library?", "header": "Library",
+                 "options": [{"label": "serde", "selected": true}], "answer": "serde"},
+                {"tool_use_id": "t2", "question": "Approach?", "header": "approach", "options": []},
+                {"tool_use_id": "t3", "question": "Keep it?", "header": "Approach",
+                 "options": [], "answer": "yes"},
+            ]),
+        );
+        let b = receipt("s2", serde_json::json!([]));
+        let c = receipt(
+            "s3",
+            serde_json::json!([{"tool_use_id": "t4", "question": "Go?", "options": []}]),
+        );
+        // The same decision seen again in a later receipt of s1.
+        let mut a2 = receipt("s1", serde_json::json!([]));
+        a2.user_decisions = a.user_decisions[..1].to_vec();
+
+        let stats = decision_stats(&[&a, &b, &c, &a2]);
+        assert_eq!((stats.questions, stats.answered), (4, 2));
+        assert_eq!((stats.sessions, stats.sessions_with_decisions), (3, 2));
+        assert!((stats.sessions_with_decisions_pct - 200.0 / 3.0).abs() < 1e-9);
+        assert_eq!(stats.categories[0].header, "approach");
+        assert_eq!(
+            (stats.categories[0].questions, stats.categories[0].answered),
+            (2, 1)
+        );
+        assert_eq!(stats.categories.len(), 3);
+    }
+
+    #[test]
+    fn test_subagent_breakdown() {
+        let mut r = Receipt::test("r1")
+            .with_model("m")
+            .with_session("s1")
+            .with_cost(1.0)
+            .with_timestamp("2026-01-01T00:00:00Z");
+        r.subagent_activities = serde_json::from_value(serde_json::json!([
+            {"agent_type": "Explore", "status": "completed", "cost_usd": 0.5,
+             "input_tokens": 100, "output_tokens": 10, "files_touched": ["a.rs"]},
+            {"agent_type": "Explore", "status": "completed", "cost_usd": 0.25,
+             "input_tokens": 50, "output_tokens": 5, "files_touched": ["a.rs", "b.rs"]},
+            {"agent_type": "Plan", "status": "started"},
+        ]))
+        .unwrap();
+        assert!((r.subagent_cost_usd() - 0.75).abs() < 1e-9);
+
+        let stats = subagent_breakdown(&[&r]);
+        assert_eq!(stats[0].agent_type, "Explore");
+        assert_eq!((stats[0].runs, stats[0].measured_runs), (2, 2));
+        assert_eq!((stats[0].input_tokens, stats[0].files_touched), (150, 2));
+        assert!((stats[0].cost_share_pct - 0.75 / 1.75 * 100.0).abs() < 1e-9);
+        assert_eq!(
+            (stats[1].agent_type.as_str(), stats[1].measured_runs),
+            ("Plan", 0)
+        );
+
+        r.subagent_activities.clear();
+        assert!(subagent_breakdown(&[&r]).is_empty());
+    }
+
+    #[test]
+    fn test_pseudonym_is_stable_and_keyed_on_email() {
+        let a = pseudonymize_author("Jane Doe <Jane@Example.com>", "");
+        let b = pseudonymize_author("J. Doe <jane@example.com>", "");
+        assert_eq!(a, b);
+        assert!(a.starts_with("dev-"));
+        assert_eq!(a.len(), 12);
+        assert!(!a.contains("jane"));
+    }
+
+    #[test]
+    fn test_pseudonym_salt_changes_id() {
+        let a = pseudonymize_author("Jane <jane@example.com>", "org-a");
+        let b = pseudonymize_author("Jane <jane@example.com>", "org-b");
+        assert_ne!(a, b);
+    }
+
+    #[test]
+    fn test_display_author_full_mode() {
+        let config = AnalyticsConfig {
+            per_author: true,
+            author_identity: "full".to_string(),
+            pseudonym_salt: String::new(),
+            teams: Default::default(),
+            line_counting: "raw".to_string(),
+            co_author_credit: "split".to_string(),
+        };
+        assert_eq!(
+            display_author("Jane <jane@example.com>", &config),
+            "Jane <jane@example.com>"
+        );
+    }
+
+    #[test]
+    fn test_credit_shares_split_and_share() {
+        assert_eq!(credit_shares(10, 3, "split"), vec![4, 3, 3]);
+        assert_eq!(credit_shares(1, 2, "split"), vec![1, 0]);
+        assert_eq!(credit_shares(10, 1, "split"), vec![10]);
+        assert_eq!(credit_shares(10, 2, "share"), vec![10, 10]);
+    }
+
+    #[test]
+    fn test_acceptance_rate() {
+        assert_eq!(acceptance_rate(0, 0), None);
+        assert_eq!(acceptance_rate(3, 1), Some(75.0));
+    }
+
+    #[test]
+    fn test_sparkline_scales_and_skips_gaps() {
+        assert_eq!(sparkline(&[Some(0.0), Some(50.0), Some(100.0)]), "▁▅█");
+        assert_eq!(sparkline(&[Some(10.0), None, Some(20.0)]), "▁ █");
+        assert_eq!(sparkline(&[Some(3.0), Some(3.0)]), "▄▄");
+        assert_eq!(sparkline(&[]), "");
+    }
+
+    #[test]
+    fn test_trend_bucket_weekly_starts_monday() {
+        let thu = NaiveDate::from_ymd_opt(2026, 3, 5).unwrap();
+        assert_eq!(
+            trend_bucket(thu, true),
+            NaiveDate::from_ymd_opt(2026, 3, 2).unwrap()
+        );
+        assert_eq!(trend_bucket(thu, false), thu);
+        assert_eq!(
+            parse_commit_day("2026-03-05T23:30:00-08:00"),
+            Some(NaiveDate::from_ymd_opt(2026, 3, 5).unwrap())
+        );
+    }
+
+    #[test]
+    fn test_merge_latency_by_model_and_team() {
+        let mut config = AnalyticsConfig::default();
+        config
+            .teams
+            .insert("platform".into(), vec!["DANA@example.com".into()]);
+        config.teams.insert("web".into(), vec!["lee@".into()]);
+        assert_eq!(team_of("Dana <dana@example.com>", &config), "platform");
+        assert_eq!(team_of("Lee <lee@example.com>", &config), "web");
+        assert_eq!(team_of("Sam <sam@example.com>", &config), NO_TEAM);
+
+        let sample = |model: &str, team: &str, hours| LatencySample {
+            model: model.into(),
+            team: team.into(),
+            hours,
+        };
+        let samples = [
+            sample("opus", "platform", 2.0),
+            sample("opus", "web", 6.0),
+            sample("gpt-4o", "platform", 30.0),
+        ];
+        let latency = summarize_latency("main".into(), 4, &samples, true);
+        assert_eq!(latency.overall.median_hours, Some(6.0));
+        assert_eq!(latency.unmerged_receipts, 4);
+        let keys: Vec<&str> = latency.by_model.iter().map(|g| g.key.as_str()).collect();
+        assert_eq!(keys, ["gpt-4o", "opus"]);
+        assert_eq!(latency.by_model[1].stats.median_hours, Some(4.0));
+        assert_eq!(latency.by_team[0].key, "platform");
+        assert_eq!(latency.by_team[0].stats.median_hours, Some(16.0));
+        assert!(summarize_latency("main".into(), 0, &samples, false)
+            .by_team
+            .is_empty());
+    }
+
+    #[test]
+    fn test_line_share_raw_and_normalized() {
+        let entry = |origin: Option<CodeOriginStats>| AuditEntry {
+            commit_sha: "a".into(),
+            commit_date: String::new(),
+            commit_author: String::new(),
+            commit_message: String::new(),
+            receipts: Vec::new(),
+            total_ai_lines: 0,
+            total_cost_usd: 0.0,
+            code_origin: origin,
+            pull_request: None,
+        };
+        let mut new = CodeOriginStats::from_lines(8, 0, 2).unwrap();
+        new.normalized = Some(LineCounts {
+            ai_generated: 5,
+            human_edited: 0,
+            pure_human: 2,
+        });
+        let old = CodeOriginStats::from_lines(0, 0, 10).unwrap();
+        let entries = [entry(Some(new)), entry(Some(old)), entry(None)];
+
+        let share = line_share(&entries, "normalized");
+        assert_eq!(share.commits, 2);
+        assert_eq!(share.normalized_commits, 1);
+        assert_eq!(share.raw.unwrap().ai_share_pct, 40.0);
+        let labels: Vec<&str> = share.ordered().iter().map(|(l, _)| *l).collect();
+        assert_eq!(labels, ["normalized", "raw"]);
+        assert_eq!(share.ordered()[0].1.total_lines, 7);
+        assert_eq!(
+            line_share(&entries[2..], "raw"),
+            LineShare {
+                counting: "raw".into(),
+                ..Default::default()
+            }
+        );
+    }
+}
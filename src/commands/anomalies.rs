@@ -0,0 +1,1270 @@
+/// Anomaly detection over AI receipts.
+///
+/// The detectors started out in the hackathon report; `blameprompt anomalies`
+/// runs them over any date range so teams can run periodic integrity checks.
+/// Thresholds come from the `[anomalies]` section of `.blamepromptrc`.
+use crate::commands::audit;
+use crate::core::config::{self, AnomalyConfig};
+use crate::core::{model_classifier, receipt::Receipt, session_stats, util};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+// ---------------------------------------------------------------------------
+// Data structures
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+
+    pub fn weight(&self) -> f64 {
+        match self {
+            Severity::Info => 2.0,
+            Severity::Warning => 8.0,
+            Severity::Critical => 20.0,
+        }
+    }
+}
+
+#[derive(Serialize)]
+pub struct AnomalyFlag {
+    pub severity: Severity,
+    pub category: String,
+    pub description: String,
+    pub evidence: String,
+}
+
+pub struct TimelineEntry {
+    pub timestamp: DateTime<Utc>,
+    pub prompt_summary: String,
+    pub duration_secs: Option<u64>,
+    pub model: String,
+    pub files_touched: Vec<String>,
+    pub additions: u32,
+    pub deletions: u32,
+    pub within_window: bool,
+}
+
+#[derive(Serialize)]
+struct AnomalyReport<'a> {
+    from: Option<&'a str>,
+    to: Option<&'a str>,
+    commits: usize,
+    receipts: usize,
+    integrity_score: u32,
+    verdict: &'static str,
+    anomalies: &'a [AnomalyFlag],
+}
+
+// ---------------------------------------------------------------------------
+// Timeline builder
+// ---------------------------------------------------------------------------
+
+pub fn build_timeline(
+    receipts: &[&Receipt],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<TimelineEntry> {
+    let mut timeline: Vec<TimelineEntry> = receipts
+        .iter()
+        .map(|r| {
+            let ts = r.prompt_submitted_at.unwrap_or(r.timestamp);
+            let files: Vec<String> = r.all_file_paths().iter().map(|f| make_rel(f)).collect();
+            TimelineEntry {
+                timestamp: ts,
+                prompt_summary: r.prompt_summary.chars().take(200).collect(),
+                duration_secs: r.prompt_duration_secs,
+                model: model_classifier::display_name(&r.model),
+                files_touched: files,
+                additions: r.effective_total_additions(),
+                deletions: r.effective_total_deletions(),
+                within_window: ts >= start && ts <= end,
+            }
+        })
+        .collect();
+
+    timeline.sort_by_key(|t| t.timestamp);
+    timeline
+}
+
+// ---------------------------------------------------------------------------
+// Standalone command
+// ---------------------------------------------------------------------------
+
+/// `blameprompt anomalies`: run the detectors over committed receipts in
+/// `[from, to]`. The time-window and activity-gap detectors only make sense for
+/// a bounded event, so they are left to the hackathon report.
+pub fn run(
+    from: Option<&str>,
+    to: Option<&str>,
+    author: Option<&str>,
+    format: &str,
+) -> Result<(), String> {
+    let cfg = config::load_config().anomalies;
+    let entries = audit::collect_all_entries(from, to, author, false)?;
+    let receipts: Vec<&Receipt> = entries.iter().flat_map(|e| &e.receipts).collect();
+
+    let timeline = build_timeline(
+        &receipts,
+        DateTime::<Utc>::MIN_UTC,
+        DateTime::<Utc>::MAX_UTC,
+    );
+    let mut log_args = Vec::new();
+    if let Some(f) = from {
+        log_args.push(format!("--since={}", f));
+    }
+    if let Some(t) = to {
+        log_args.push(format!("--until={}", t));
+    }
+    if log_args.is_empty() {
+        log_args.push("-50".to_string());
+    }
+    let mut anomalies = detect_anomalies(&receipts, &entries, &timeline, None, &log_args, &cfg);
+    anomalies.sort_by_key(|a| std::cmp::Reverse(a.severity.weight() as u32));
+    let score = calculate_integrity_score(&anomalies);
+
+    if format == "json" {
+        let report = AnomalyReport {
+            from,
+            to,
+            commits: entries.len(),
+            receipts: receipts.len(),
+            integrity_score: score,
+            verdict: score_label(score),
+            anomalies: &anomalies,
+        };
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+
+    println!(
+        "Anomaly check: {} commit(s), {} receipt(s){}",
+        entries.len(),
+        receipts.len(),
+        match (from, to) {
+            (None, None) => String::new(),
+            (f, t) => format!(" ({} to {})", f.unwrap_or("start"), t.unwrap_or("now")),
+        }
+    );
+    if anomalies.is_empty() {
+        println!("No anomalies detected. Integrity score: {}/100", score);
+        return Ok(());
+    }
+    let mut table = comfy_table::Table::new();
+    table.set_header(vec!["Severity", "Category", "Description", "Evidence"]);
+    for a in &anomalies {
+        table.add_row(vec![
+            a.severity.label(),
+            &a.category,
+            &a.description,
+            &truncate(&a.evidence, 80),
+        ]);
+    }
+    println!("{table}");
+    println!("Integrity score: {}/100 ({})", score, score_label(score));
+    Ok(())
+}
+
+// ---------------------------------------------------------------------------
+// Anomaly detection — orchestrator + 8 detectors
+// ---------------------------------------------------------------------------
+
+/// Run every detector. `window` is the event window (hackathon start/end) and
+/// enables the time-window and activity-gap detectors; `log_args` bound the
+/// `git log` used to find newly added files.
+pub fn detect_anomalies(
+    receipts: &[&Receipt],
+    entries: &[audit::AuditEntry],
+    timeline: &[TimelineEntry],
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    log_args: &[String],
+    cfg: &AnomalyConfig,
+) -> Vec<AnomalyFlag> {
+    let mut flags = Vec::new();
+    if let Some((start, end)) = window {
+        flags.extend(detect_time_window_violations(timeline, start, end));
+    }
+    flags.extend(detect_pre_written_code(receipts, cfg));
+    flags.extend(detect_untracked_files(receipts, log_args));
+    flags.extend(detect_duplicate_prompt_hashes(receipts, cfg));
+    flags.extend(detect_batch_commits(entries, cfg));
+    flags.extend(detect_unusual_session_patterns(receipts, timeline, cfg));
+    if let Some((start, _)) = window {
+        flags.extend(detect_time_gaps(timeline, start, cfg));
+    }
+    flags
+}
+
+/// Detector 1: Prompts submitted outside the hackathon time window.
+fn detect_time_window_violations(
+    timeline: &[TimelineEntry],
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+) -> Vec<AnomalyFlag> {
+    let outside: Vec<&TimelineEntry> = timeline.iter().filter(|t| !t.within_window).collect();
+    if outside.is_empty() {
+        return vec![];
+    }
+
+    let before_count = outside.iter().filter(|t| t.timestamp < start).count();
+    let after_count = outside.iter().filter(|t| t.timestamp > end).count();
+    let additions_outside: u32 = outside.iter().map(|t| t.additions).sum();
+
+    let severity = if additions_outside > 100 {
+        Severity::Critical
+    } else {
+        Severity::Warning
+    };
+
+    vec![AnomalyFlag {
+        severity,
+        category: "Time Window Violation".into(),
+        description: format!(
+            "{} prompt(s) submitted outside the hackathon window ({} before start, {} after end)",
+            outside.len(),
+            before_count,
+            after_count,
+        ),
+        evidence: format!(
+            "{} lines added outside window. Earliest: {}. Latest: {}",
+            additions_outside,
+            outside
+                .first()
+                .map(|t| t.timestamp.to_rfc3339())
+                .unwrap_or_default(),
+            outside
+                .last()
+                .map(|t| t.timestamp.to_rfc3339())
+                .unwrap_or_default(),
+        ),
+    }]
+}
+
+/// Detector 2: File appears fully-formed in a single prompt with no iterative history.
+fn detect_pre_written_code(receipts: &[&Receipt], cfg: &AnomalyConfig) -> Vec<AnomalyFlag> {
+    let mut flags = Vec::new();
+
+    // Build per-file history: (timestamp, additions, prompt_summary)
+    let mut file_history: HashMap<String, Vec<(DateTime<Utc>, u32, String)>> = HashMap::new();
+    for r in receipts {
+        let ts = r.prompt_submitted_at.unwrap_or(r.timestamp);
+        for fc in r.all_file_changes() {
+            file_history.entry(make_rel(&fc.path)).or_default().push((
+                ts,
+                fc.additions,
+                r.prompt_summary.clone(),
+            ));
+        }
+    }
+
+    for (file, mut history) in file_history {
+        history.sort_by_key(|(ts, _, _)| *ts);
+        if let Some((_, first_additions, prompt_summary)) = history.first() {
+            let total_touches = history.len();
+            let total_additions: u32 = history.iter().map(|(_, a, _)| a).sum();
+
+            // Heuristic: first touch >80 lines, <=2 total touches, >70% of all additions
+            if *first_additions > cfg.pre_written_min_lines
+                && total_touches <= cfg.pre_written_max_touches
+                && (*first_additions as f64 / total_additions.max(1) as f64) > 0.7
+            {
+                let severity = if *first_additions > cfg.pre_written_min_lines * 5 / 2 {
+                    Severity::Critical
+                } else {
+                    Severity::Warning
+                };
+
+                flags.push(AnomalyFlag {
+                    severity,
+                    category: "Pre-written Code Suspected".into(),
+                    description: format!(
+                        "`{}` appeared with {} lines in a single prompt ({} total touches)",
+                        file, first_additions, total_touches,
+                    ),
+                    evidence: format!(
+                        "Prompt: \"{}\". {} of {} total lines in first touch ({:.0}%)",
+                        truncate(prompt_summary, 100),
+                        first_additions,
+                        total_additions,
+                        (*first_additions as f64 / total_additions.max(1) as f64) * 100.0,
+                    ),
+                });
+            }
+        }
+    }
+
+    flags
+}
+
+/// Detector 3: Source files committed with zero receipt trail.
+fn detect_untracked_files(receipts: &[&Receipt], log_args: &[String]) -> Vec<AnomalyFlag> {
+    let receipted_files: HashSet<String> = receipts
+        .iter()
+        .flat_map(|r| r.all_file_paths())
+        .map(|f| make_rel(&f))
+        .collect();
+
+    let recently_added = get_recently_added_files(log_args);
+    let untracked: Vec<String> = recently_added
+        .into_iter()
+        .filter(|f| !receipted_files.contains(f) && is_source_file(f))
+        .collect();
+
+    if untracked.is_empty() {
+        return vec![];
+    }
+
+    let severity = if untracked.len() > 5 {
+        Severity::Critical
+    } else {
+        Severity::Warning
+    };
+
+    vec![AnomalyFlag {
+        severity,
+        category: "Files Without Receipt Trail".into(),
+        description: format!(
+            "{} source file(s) were added with no AI receipt or iterative history",
+            untracked.len(),
+        ),
+        evidence: format!(
+            "Files: {}",
+            untracked
+                .iter()
+                .take(10)
+                .cloned()
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    }]
+}
+
+/// Detector 4: Same prompt_hash submitted multiple times (rehearsed prompts).
+fn detect_duplicate_prompt_hashes(receipts: &[&Receipt], cfg: &AnomalyConfig) -> Vec<AnomalyFlag> {
+    let mut hash_counts: HashMap<&str, usize> = HashMap::new();
+    for r in receipts {
+        if !r.prompt_hash.is_empty() {
+            *hash_counts.entry(r.prompt_hash.as_str()).or_insert(0) += 1;
+        }
+    }
+
+    let duplicates: Vec<(&str, usize)> = hash_counts
+        .into_iter()
+        .filter(|(_, count)| *count >= cfg.duplicate_prompt_min)
+        .collect();
+
+    if duplicates.is_empty() {
+        return vec![];
+    }
+
+    let total_dupes: usize = duplicates.iter().map(|(_, c)| c).sum();
+    let severity = if total_dupes > 10 {
+        Severity::Critical
+    } else if total_dupes > 3 {
+        Severity::Warning
+    } else {
+        Severity::Info
+    };
+
+    vec![AnomalyFlag {
+        severity,
+        category: "Duplicate Prompt Hashes".into(),
+        description: format!(
+            "{} unique prompt(s) submitted more than once ({} total duplicated)",
+            duplicates.len(),
+            total_dupes,
+        ),
+        evidence: format!(
+            "Hashes: {}",
+            duplicates
+                .iter()
+                .take(5)
+                .map(|(h, c)| format!("{}.. ({}x)", util::short_sha(h), c))
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+    }]
+}
+
+/// Detector 5: Commit touches many files but few have receipt coverage.
+fn detect_batch_commits(entries: &[audit::AuditEntry], cfg: &AnomalyConfig) -> Vec<AnomalyFlag> {
+    let mut flags = Vec::new();
+
+    for entry in entries {
+        if entry.commit_sha == "uncommitted" {
+            continue;
+        }
+
+        let files_in_commit = count_files_in_commit(&entry.commit_sha);
+        let receipted_files: HashSet<String> = entry
+            .receipts
+            .iter()
+            .flat_map(|r| r.all_file_paths())
+            .map(|f| make_rel(&f))
+            .collect();
+
+        let coverage = if files_in_commit > 0 {
+            receipted_files.len() as f64 / files_in_commit as f64
+        } else {
+            1.0
+        };
+
+        if files_in_commit > cfg.batch_commit_min_files && coverage < cfg.batch_commit_max_coverage
+        {
+            let severity = if files_in_commit > 15 && coverage < 0.10 {
+                Severity::Critical
+            } else {
+                Severity::Warning
+            };
+
+            flags.push(AnomalyFlag {
+                severity,
+                category: "Batch Commit".into(),
+                description: format!(
+                    "Commit {} changed {} files but only {} ({:.0}%) have receipt coverage",
+                    util::short_sha(&entry.commit_sha),
+                    files_in_commit,
+                    receipted_files.len(),
+                    coverage * 100.0,
+                ),
+                evidence: format!(
+                    "Commit: \"{}\". Date: {}",
+                    entry.commit_message, entry.commit_date,
+                ),
+            });
+        }
+    }
+
+    flags
+}
+
+/// Detector 6: Very short prompt producing disproportionately large output.
+fn detect_unusual_session_patterns(
+    receipts: &[&Receipt],
+    timeline: &[TimelineEntry],
+    cfg: &AnomalyConfig,
+) -> Vec<AnomalyFlag> {
+    let mut flags = Vec::new();
+
+    // Per-receipt: <60s duration producing >50 lines at >2 lines/sec
+    for r in receipts {
+        let duration = match r.prompt_duration_secs {
+            Some(d) if d > 0 => d,
+            _ => continue,
+        };
+        let additions = r.effective_total_additions();
+
+        if duration < 60 && additions > cfg.burst_min_lines {
+            let lps = additions as f64 / duration as f64;
+            if lps > cfg.burst_lines_per_sec {
+                flags.push(AnomalyFlag {
+                    severity: Severity::Warning,
+                    category: "Unusual Session Pattern".into(),
+                    description: format!(
+                        "Prompt produced {} lines in {}s ({:.1} lines/sec)",
+                        additions, duration, lps,
+                    ),
+                    evidence: format!(
+                        "Prompt: \"{}\". Model: {}",
+                        truncate(&r.prompt_summary, 100),
+                        model_classifier::display_name(&r.model),
+                    ),
+                });
+            }
+        }
+    }
+
+    // Aggregate: very few prompts with very large total output
+    let total_prompts = timeline.len();
+    let total_additions: u32 = timeline.iter().map(|t| t.additions).sum();
+    if total_prompts > 0 && total_prompts <= 3 && total_additions > 500 {
+        flags.push(AnomalyFlag {
+            severity: Severity::Warning,
+            category: "Unusual Session Pattern".into(),
+            description: format!(
+                "Only {} prompt(s) produced {} total lines",
+                total_prompts, total_additions,
+            ),
+            evidence: format!(
+                "Average: {:.0} lines per prompt",
+                total_additions as f64 / total_prompts as f64,
+            ),
+        });
+    }
+
+    flags
+}
+
+/// Detector 7: Suspiciously long gaps between consecutive prompts during the hackathon.
+fn detect_time_gaps(
+    timeline: &[TimelineEntry],
+    start: DateTime<Utc>,
+    cfg: &AnomalyConfig,
+) -> Vec<AnomalyFlag> {
+    let mut flags = Vec::new();
+
+    let within: Vec<&TimelineEntry> = timeline.iter().filter(|t| t.within_window).collect();
+    if within.len() < 2 {
+        return flags;
+    }
+
+    // Gap between hackathon start and first prompt
+    if let Some(first) = within.first() {
+        let gap = (first.timestamp - start).num_seconds().max(0) as u64;
+        if gap > 7200 {
+            flags.push(AnomalyFlag {
+                severity: Severity::Info,
+                category: "Late Start".into(),
+                description: format!(
+                    "First AI prompt was {} after hackathon start",
+                    session_stats::format_duration(gap),
+                ),
+                evidence: format!(
+                    "Hackathon start: {}. First prompt: {}",
+                    start.format("%H:%M:%S UTC"),
+                    first.timestamp.format("%H:%M:%S UTC"),
+                ),
+            });
+        }
+    }
+
+    // Inter-prompt gaps
+    for window in within.windows(2) {
+        let gap = (window[1].timestamp - window[0].timestamp)
+            .num_seconds()
+            .max(0) as u64;
+
+        let threshold = cfg.activity_gap_mins * 60;
+        if gap > threshold {
+            let severity = if gap > threshold * 2 {
+                Severity::Warning
+            } else {
+                Severity::Info
+            };
+
+            flags.push(AnomalyFlag {
+                severity,
+                category: "Activity Gap".into(),
+                description: format!(
+                    "{} gap between prompts during hackathon window",
+                    session_stats::format_duration(gap),
+                ),
+                evidence: format!(
+                    "From {} to {}. Prompt before gap: \"{}\"",
+                    window[0].timestamp.format("%H:%M:%S"),
+                    window[1].timestamp.format("%H:%M:%S"),
+                    truncate(&window[0].prompt_summary, 60),
+                ),
+            });
+        }
+    }
+
+    flags
+}
+
+/// Detector 8: commit author dates that disagree with when GitHub received the
+/// push (`pushes` maps commit sha to its earliest push time). Local commit dates
+/// can be forged; push times are recorded server-side.
+pub fn detect_push_time_discrepancies(
+    commits: &[(String, DateTime<Utc>)],
+    pushes: &HashMap<String, DateTime<Utc>>,
+    window: Option<(DateTime<Utc>, DateTime<Utc>)>,
+    cfg: &AnomalyConfig,
+) -> Vec<AnomalyFlag> {
+    let grace = chrono::Duration::minutes(cfg.push_grace_mins);
+    let mut future_dated = Vec::new();
+    let mut pushed_early = Vec::new();
+    let mut pushed_late = Vec::new();
+
+    for (sha, authored) in commits {
+        let Some(&pushed) = pushes.get(sha) else {
+            continue;
+        };
+        let short = &sha[..sha.len().min(8)];
+        if *authored > pushed + grace {
+            future_dated.push(format!(
+                "{} authored {} but pushed {}",
+                short,
+                authored.to_rfc3339(),
+                pushed.to_rfc3339()
+            ));
+        }
+        if let Some((start, end)) = window {
+            if pushed < start {
+                pushed_early.push(format!("{} pushed {}", short, pushed.to_rfc3339()));
+            } else if pushed > end + grace && *authored >= start && *authored <= end {
+                pushed_late.push(format!(
+                    "{} authored {} but pushed {}",
+                    short,
+                    authored.to_rfc3339(),
+                    pushed.to_rfc3339()
+                ));
+            }
+        }
+    }
+
+    let mut flags = Vec::new();
+    if !future_dated.is_empty() {
+        flags.push(AnomalyFlag {
+            severity: Severity::Critical,
+            category: "Forged Commit Date".into(),
+            description: format!(
+                "{} commit(s) carry an author date later than their push to GitHub",
+                future_dated.len()
+            ),
+            evidence: future_dated.join("; "),
+        });
+    }
+    if !pushed_early.is_empty() {
+        flags.push(AnomalyFlag {
+            severity: Severity::Critical,
+            category: "Pushed Before Start".into(),
+            description: format!(
+                "{} commit(s) reached GitHub before the hackathon started",
+                pushed_early.len()
+            ),
+            evidence: pushed_early.join("; "),
+        });
+    }
+    if !pushed_late.is_empty() {
+        flags.push(AnomalyFlag {
+            severity: Severity::Warning,
+            category: "Backdated Commit".into(),
+            description: format!(
+                "{} commit(s) dated inside the window were first pushed after it ended",
+                pushed_late.len()
+            ),
+            evidence: pushed_late.join("; "),
+        });
+    }
+    flags
+}
+
+// ---------------------------------------------------------------------------
+// Integrity score
+// ---------------------------------------------------------------------------
+
+pub fn calculate_integrity_score(anomalies: &[AnomalyFlag]) -> u32 {
+    let total_deduction: f64 = anomalies.iter().map(|a| a.severity.weight()).sum();
+    // Exponential decay: score = 100 * e^(-deduction/50)
+    let raw = 100.0 * (-total_deduction / 50.0_f64).exp();
+    raw.round().clamp(0.0, 100.0) as u32
+}
+
+pub fn score_label(score: u32) -> &'static str {
+    if score >= 80 {
+        "PASS"
+    } else if score >= 50 {
+        "REVIEW"
+    } else {
+        "FAIL"
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+pub fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        let truncated: String = s.chars().take(max.saturating_sub(3)).collect();
+        format!("{}...", truncated)
+    }
+}
+
+pub fn make_rel(path: &str) -> String {
+    let cwd = std::env::current_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    util::make_relative(path, &cwd)
+}
+
+fn is_source_file(path: &str) -> bool {
+    let extensions = [
+        ".rs", ".py", ".js", ".ts", ".tsx", ".jsx", ".go", ".java", ".c", ".cpp", ".h", ".hpp",
+        ".rb", ".swift", ".kt", ".cs", ".php", ".scala", ".ex", ".exs", ".zig", ".vue", ".svelte",
+    ];
+    extensions.iter().any(|ext| path.ends_with(ext))
+}
+
+fn get_recently_added_files(log_args: &[String]) -> Vec<String> {
+    std::process::Command::new("git")
+        .args(["log", "--diff-filter=A", "--name-only", "--pretty=format:"])
+        .args(log_args)
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| {
+            s.lines()
+                .filter(|l| !l.is_empty())
+                .map(String::from)
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `(sha, author date)` for the commits selected by `log_args`.
+pub fn commit_author_dates(log_args: &[String]) -> Vec<(String, DateTime<Utc>)> {
+    std::process::Command::new("git")
+        .args(["log", "--format=%H %aI"])
+        .args(log_args)
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| {
+            s.lines()
+                .filter_map(|l| {
+                    let (sha, date) = l.split_once(' ')?;
+                    let date = DateTime::parse_from_rfc3339(date).ok()?;
+                    Some((sha.to_string(), date.with_timezone(&Utc)))
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn count_files_in_commit(sha: &str) -> usize {
+    std::process::Command::new("git")
+        .args(["diff-tree", "--no-commit-id", "--name-only", "-r", sha])
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.lines().filter(|l| !l.is_empty()).count())
+        .unwrap_or(0)
+}
+
+// ---------------------------------------------------------------------------
+// Tests
+// ---------------------------------------------------------------------------
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn make_anomaly(severity: Severity) -> AnomalyFlag {
+        AnomalyFlag {
+            severity,
+            category: "Test".into(),
+            description: "test".into(),
+            evidence: "test".into(),
+        }
+    }
+
+    #[test]
+    fn test_integrity_score_no_anomalies() {
+        assert_eq!(calculate_integrity_score(&[]), 100);
+    }
+
+    #[test]
+    fn test_integrity_score_one_warning() {
+        let anomalies = vec![make_anomaly(Severity::Warning)];
+        let score = calculate_integrity_score(&anomalies);
+        assert!((80..=90).contains(&score), "score={}", score);
+    }
+
+    #[test]
+    fn test_integrity_score_one_critical() {
+        let anomalies = vec![make_anomaly(Severity::Critical)];
+        let score = calculate_integrity_score(&anomalies);
+        assert!((60..=75).contains(&score), "score={}", score);
+    }
+
+    #[test]
+    fn test_integrity_score_multiple_criticals() {
+        let anomalies = vec![
+            make_anomaly(Severity::Critical),
+            make_anomaly(Severity::Critical),
+        ];
+        let score = calculate_integrity_score(&anomalies);
+        assert!(score < 50, "score={}", score);
+    }
+
+    #[test]
+    fn test_time_window_violations() {
+        let start = Utc::now() - chrono::Duration::hours(2);
+        let end = Utc::now() - chrono::Duration::hours(1);
+
+        let timeline = vec![TimelineEntry {
+            timestamp: Utc::now(), // AFTER the window
+            prompt_summary: "test".into(),
+            duration_secs: None,
+            model: "test".into(),
+            files_touched: vec![],
+            additions: 50,
+            deletions: 0,
+            within_window: false,
+        }];
+
+        let flags = detect_time_window_violations(&timeline, start, end);
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].severity, Severity::Warning);
+    }
+
+    #[test]
+    fn test_pre_written_code_detection() {
+        let now = Utc::now();
+        let r = Receipt {
+            id: "test".into(),
+            provider: "claude".into(),
+            model: "opus".into(),
+            session_id: "s1".into(),
+            prompt_summary: "Create the entire app".into(),
+            response_summary: None,
+            prompt_hash: "hash1".into(),
+            message_count: 2,
+            cost_usd: 0.1,
+            input_tokens: None,
+            output_tokens: None,
+            cache_read_tokens: None,
+            cache_creation_tokens: None,
+            timestamp: now,
+            session_start: None,
+            session_end: None,
+            session_duration_secs: None,
+            ai_response_time_secs: None,
+            prompt_submitted_at: Some(now),
+            prompt_duration_secs: Some(120),
+            accepted_lines: None,
+            overridden_lines: None,
+            prompt_quality: None,
+            user: "test".into(),
+            file_path: String::new(),
+            line_range: (0, 0),
+            files_changed: vec![crate::core::receipt::FileChange {
+                path: "src/big_file.rs".into(),
+                line_range: (1, 250),
+                blob_hash: None,
+                additions: 250,
+                deletions: 0,
+                notebook_cells: vec![],
+                is_binary: false,
+                bytes_before: None,
+                bytes_after: None,
+                change_type: None,
+                old_path: None,
+                unstaged_ranges: Vec::new(),
+            }],
+            parent_receipt_id: None,
+            parent_session_id: None,
+            is_continuation: None,
+            continuation_depth: None,
+            prompt_number: Some(1),
+            total_additions: 250,
+            total_deletions: 0,
+            tools_used: vec![],
+            mcp_servers: vec![],
+            environment: None,
+            agents_spawned: vec![],
+            subagent_activities: vec![],
+            concurrent_tool_calls: None,
+            user_decisions: vec![],
+            conversation: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
+        };
+
+        let receipts = vec![&r];
+        let flags = detect_pre_written_code(&receipts, &AnomalyConfig::default());
+        assert!(!flags.is_empty(), "Should flag 250-line single-touch file");
+        assert_eq!(flags[0].severity, Severity::Critical);
+    }
+
+    #[test]
+    fn test_no_false_positive_on_small_file() {
+        let now = Utc::now();
+        let r = Receipt {
+            id: "test".into(),
+            provider: "claude".into(),
+            model: "opus".into(),
+            session_id: "s1".into(),
+            prompt_summary: "Add config".into(),
+            response_summary: None,
+            prompt_hash: "hash1".into(),
+            message_count: 2,
+            cost_usd: 0.01,
+            input_tokens: None,
+            output_tokens: None,
+            cache_read_tokens: None,
+            cache_creation_tokens: None,
+            timestamp: now,
+            session_start: None,
+            session_end: None,
+            session_duration_secs: None,
+            ai_response_time_secs: None,
+            prompt_submitted_at: Some(now),
+            prompt_duration_secs: Some(60),
+            accepted_lines: None,
+            overridden_lines: None,
+            prompt_quality: None,
+            user: "test".into(),
+            file_path: String::new(),
+            line_range: (0, 0),
+            files_changed: vec![crate::core::receipt::FileChange {
+                path: "src/config.rs".into(),
+                line_range: (1, 30),
+                blob_hash: None,
+                additions: 30,
+                deletions: 0,
+                notebook_cells: vec![],
+                is_binary: false,
+                bytes_before: None,
+                bytes_after: None,
+                change_type: None,
+                old_path: None,
+                unstaged_ranges: Vec::new(),
+            }],
+            parent_receipt_id: None,
+            parent_session_id: None,
+            is_continuation: None,
+            continuation_depth: None,
+            prompt_number: Some(1),
+            total_additions: 30,
+            total_deletions: 0,
+            tools_used: vec![],
+            mcp_servers: vec![],
+            environment: None,
+            agents_spawned: vec![],
+            subagent_activities: vec![],
+            concurrent_tool_calls: None,
+            user_decisions: vec![],
+            conversation: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
+        };
+
+        let receipts = vec![&r];
+        let flags = detect_pre_written_code(&receipts, &AnomalyConfig::default());
+        assert!(flags.is_empty(), "30-line file should NOT be flagged");
+    }
+
+    #[test]
+    fn test_duplicate_prompt_hashes() {
+        let now = Utc::now();
+        let make_receipt = |hash: &str| Receipt {
+            id: Receipt::new_id(),
+            provider: "claude".into(),
+            model: "opus".into(),
+            session_id: "s1".into(),
+            prompt_summary: "test".into(),
+            response_summary: None,
+            prompt_hash: hash.into(),
+            message_count: 1,
+            cost_usd: 0.0,
+            input_tokens: None,
+            output_tokens: None,
+            cache_read_tokens: None,
+            cache_creation_tokens: None,
+            timestamp: now,
+            session_start: None,
+            session_end: None,
+            session_duration_secs: None,
+            ai_response_time_secs: None,
+            prompt_submitted_at: None,
+            prompt_duration_secs: None,
+            accepted_lines: None,
+            overridden_lines: None,
+            prompt_quality: None,
+            user: "test".into(),
+            file_path: String::new(),
+            line_range: (0, 0),
+            files_changed: vec![],
+            parent_receipt_id: None,
+            parent_session_id: None,
+            is_continuation: None,
+            continuation_depth: None,
+            prompt_number: Some(1),
+            total_additions: 0,
+            total_deletions: 0,
+            tools_used: vec![],
+            mcp_servers: vec![],
+            environment: None,
+            agents_spawned: vec![],
+            subagent_activities: vec![],
+            concurrent_tool_calls: None,
+            user_decisions: vec![],
+            conversation: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
+        };
+
+        let r1 = make_receipt("sha256:abc123");
+        let r2 = make_receipt("sha256:abc123");
+        let r3 = make_receipt("sha256:abc123");
+        let r4 = make_receipt("sha256:different");
+
+        let receipts = vec![&r1, &r2, &r3, &r4];
+        let flags = detect_duplicate_prompt_hashes(&receipts, &AnomalyConfig::default());
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].severity, Severity::Info); // 3 dupes → INFO (threshold: >3 for WARNING)
+    }
+
+    #[test]
+    fn test_unusual_session_pattern() {
+        let now = Utc::now();
+        let r = Receipt {
+            id: "test".into(),
+            provider: "claude".into(),
+            model: "opus".into(),
+            session_id: "s1".into(),
+            prompt_summary: "Do everything".into(),
+            response_summary: None,
+            prompt_hash: "hash".into(),
+            message_count: 2,
+            cost_usd: 0.0,
+            input_tokens: None,
+            output_tokens: None,
+            cache_read_tokens: None,
+            cache_creation_tokens: None,
+            timestamp: now,
+            session_start: None,
+            session_end: None,
+            session_duration_secs: None,
+            ai_response_time_secs: None,
+            prompt_submitted_at: Some(now),
+            prompt_duration_secs: Some(30), // 30 seconds
+            accepted_lines: None,
+            overridden_lines: None,
+            prompt_quality: None,
+            user: "test".into(),
+            file_path: String::new(),
+            line_range: (0, 0),
+            files_changed: vec![],
+            parent_receipt_id: None,
+            parent_session_id: None,
+            is_continuation: None,
+            continuation_depth: None,
+            prompt_number: Some(1),
+            total_additions: 100, // 100 lines in 30s = 3.3 lines/sec
+            total_deletions: 0,
+            tools_used: vec![],
+            mcp_servers: vec![],
+            environment: None,
+            agents_spawned: vec![],
+            subagent_activities: vec![],
+            concurrent_tool_calls: None,
+            user_decisions: vec![],
+            conversation: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
+        };
+
+        let timeline = vec![]; // empty timeline so aggregate check doesn't fire
+        let flags = detect_unusual_session_patterns(&[&r], &timeline, &AnomalyConfig::default());
+        assert!(!flags.is_empty(), "100 lines in 30s should be flagged");
+    }
+
+    #[test]
+    fn test_timeline_ordering() {
+        let now = Utc::now();
+        let start = now - chrono::Duration::hours(2);
+        let end = now + chrono::Duration::hours(2);
+
+        let make_receipt = |mins_ago: i64| Receipt {
+            id: Receipt::new_id(),
+            provider: "claude".into(),
+            model: "opus".into(),
+            session_id: "s1".into(),
+            prompt_summary: format!("prompt at -{}", mins_ago),
+            response_summary: None,
+            prompt_hash: "h".into(),
+            message_count: 1,
+            cost_usd: 0.0,
+            input_tokens: None,
+            output_tokens: None,
+            cache_read_tokens: None,
+            cache_creation_tokens: None,
+            timestamp: now - chrono::Duration::minutes(mins_ago),
+            session_start: None,
+            session_end: None,
+            session_duration_secs: None,
+            ai_response_time_secs: None,
+            prompt_submitted_at: Some(now - chrono::Duration::minutes(mins_ago)),
+            prompt_duration_secs: None,
+            accepted_lines: None,
+            overridden_lines: None,
+            prompt_quality: None,
+            user: "test".into(),
+            file_path: String::new(),
+            line_range: (0, 0),
+            files_changed: vec![],
+            parent_receipt_id: None,
+            parent_session_id: None,
+            is_continuation: None,
+            continuation_depth: None,
+            prompt_number: Some(1),
+            total_additions: 0,
+            total_deletions: 0,
+            tools_used: vec![],
+            mcp_servers: vec![],
+            environment: None,
+            agents_spawned: vec![],
+            subagent_activities: vec![],
+            concurrent_tool_calls: None,
+            user_decisions: vec![],
+            conversation: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
+        };
+
+        let r1 = make_receipt(30); // 30 min ago
+        let r2 = make_receipt(60); // 60 min ago
+        let r3 = make_receipt(10); // 10 min ago
+
+        let receipts = vec![&r1, &r2, &r3];
+        let timeline = build_timeline(&receipts, start, end);
+
+        // Should be sorted chronologically (oldest first)
+        assert!(timeline[0].timestamp <= timeline[1].timestamp);
+        assert!(timeline[1].timestamp <= timeline[2].timestamp);
+    }
+
+    #[test]
+    fn test_is_source_file() {
+        assert!(is_source_file("src/main.rs"));
+        assert!(is_source_file("app.py"));
+        assert!(is_source_file("index.tsx"));
+        assert!(!is_source_file("README.md"));
+        assert!(!is_source_file("config.json"));
+        assert!(!is_source_file("image.png"));
+    }
+
+    #[test]
+    fn test_time_gap_detection() {
+        let now = Utc::now();
+        let start = now - chrono::Duration::hours(4);
+
+        let timeline = vec![
+            TimelineEntry {
+                timestamp: now - chrono::Duration::hours(3),
+                prompt_summary: "first".into(),
+                duration_secs: None,
+                model: "test".into(),
+                files_touched: vec![],
+                additions: 10,
+                deletions: 0,
+                within_window: true,
+            },
+            TimelineEntry {
+                timestamp: now, // 3 hour gap
+                prompt_summary: "second".into(),
+                duration_secs: None,
+                model: "test".into(),
+                files_touched: vec![],
+                additions: 10,
+                deletions: 0,
+                within_window: true,
+            },
+        ];
+
+        let flags = detect_time_gaps(&timeline, start, &AnomalyConfig::default());
+        assert!(!flags.is_empty(), "3-hour gap should be flagged");
+    }
+
+    #[test]
+    fn test_push_time_discrepancies() {
+        let at = |h: u32, m: u32| {
+            DateTime::parse_from_rfc3339(&format!("2026-02-26T{:02}:{:02}:00Z", h, m))
+                .unwrap()
+                .with_timezone(&Utc)
+        };
+        let window = Some((at(9, 0), at(21, 0)));
+        let commits = vec![
+            ("aaaa1111".to_string(), at(12, 0)), // pushed 10 min later: fine
+            ("bbbb2222".to_string(), at(15, 0)), // dated after its push
+            ("cccc3333".to_string(), at(10, 0)), // pushed before the start
+            ("dddd4444".to_string(), at(20, 0)), // pushed well after the end
+            ("eeee5555".to_string(), at(11, 0)), // never pushed
+        ];
+        let pushes: HashMap<String, DateTime<Utc>> = [
+            ("aaaa1111", at(12, 10)),
+            ("bbbb2222", at(13, 0)),
+            ("cccc3333", at(8, 30)),
+            ("dddd4444", at(23, 0)),
+        ]
+        .into_iter()
+        .map(|(s, t)| (s.to_string(), t))
+        .collect();
+
+        let flags =
+            detect_push_time_discrepancies(&commits, &pushes, window, &AnomalyConfig::default());
+        let categories: Vec<&str> = flags.iter().map(|f| f.category.as_str()).collect();
+        assert_eq!(
+            categories,
+            vec![
+                "Forged Commit Date",
+                "Pushed Before Start",
+                "Backdated Commit"
+            ]
+        );
+        assert!(flags[0].evidence.contains("bbbb2222"));
+        assert!(flags[1].evidence.contains("cccc3333"));
+        assert!(flags[2].evidence.contains("dddd4444"));
+        assert!(flags.iter().all(|f| !f.evidence.contains("aaaa1111")));
+
+        // Without a window only forged dates are checked.
+        let flags =
+            detect_push_time_discrepancies(&commits, &pushes, None, &AnomalyConfig::default());
+        assert_eq!(flags.len(), 1);
+    }
+}
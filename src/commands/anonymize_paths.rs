@@ -0,0 +1,126 @@
+/// `--anonymize-paths` for `report`, `audit` and `analytics`.
+///
+/// Reports shared outside the team shouldn't leak the repo layout. Every path
+/// component is replaced by a hash of the path up to and including it, salted
+/// with `[analytics] pseudonym_salt`. The same file therefore gets the same
+/// pseudonym in every report, and files in one directory still share a
+/// directory pseudonym. The file extension is kept so per-language
+/// breakdowns still make sense. Paths are rewritten only after everything that
+/// looks files up in git (deletion tracking, rework), so totals don't change.
+/// Prompt text is left as is.
+use crate::commands::audit::AuditEntry;
+use crate::commands::rework::ReworkLink;
+use crate::core::receipt::Receipt;
+use crate::core::util;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+/// Stable pseudonym for `path`, e.g. `src/auth/login.rs` -> `3f1c0a9e/b27d45c0/0e9a8f61.rs`.
+pub fn pseudonymize_path(path: &str, salt: &str) -> String {
+    let path = util::make_relative(path, ".");
+    let components: Vec<&str> = path.split('/').filter(|c| !c.is_empty()).collect();
+    let mut prefix = String::new();
+    let mut out = Vec::with_capacity(components.len());
+    for (i, component) in components.iter().enumerate() {
+        if !prefix.is_empty() {
+            prefix.push('/');
+        }
+        prefix.push_str(component);
+        let mut hasher = Sha256::new();
+        hasher.update(salt.as_bytes());
+        hasher.update(b":");
+        hasher.update(prefix.as_bytes());
+        let mut pseudonym = format!("{:x}", hasher.finalize())[..8].to_string();
+        if i + 1 == components.len() {
+            if let Some(ext) = Path::new(component).extension() {
+                pseudonym = format!("{}.{}", pseudonym, ext.to_string_lossy());
+            }
+        }
+        out.push(pseudonym);
+    }
+    out.join("/")
+}
+
+/// Rewrite every file path recorded in `receipt`.
+pub fn anonymize_receipt(receipt: &mut Receipt, salt: &str) {
+    let anon = |p: &mut String| *p = pseudonymize_path(p, salt);
+    if !receipt.file_path.is_empty() {
+        anon(&mut receipt.file_path);
+    }
+    for fc in &mut receipt.files_changed {
+        anon(&mut fc.path);
+        if let Some(old) = &mut fc.old_path {
+            anon(old);
+        }
+    }
+    for turn in receipt.conversation.iter_mut().flatten() {
+        turn.files_touched.iter_mut().flatten().for_each(anon);
+    }
+    for activity in &mut receipt.subagent_activities {
+        activity.files_touched.iter_mut().for_each(anon);
+    }
+}
+
+pub fn anonymize_entries(entries: &mut [AuditEntry], salt: &str) {
+    for receipt in entries.iter_mut().flat_map(|e| &mut e.receipts) {
+        anonymize_receipt(receipt, salt);
+    }
+}
+
+pub fn anonymize_rework(links: &mut [ReworkLink], salt: &str) {
+    for link in links {
+        link.file = pseudonymize_path(&link.file, salt);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::FileChange;
+
+    #[test]
+    fn test_pseudonymize_path() {
+        let login = pseudonymize_path("src/auth/login.rs", "acme");
+        let parts: Vec<&str> = login.split('/').collect();
+        assert_eq!(parts.len(), 3);
+        assert!(parts[2].ends_with(".rs") && parts[2].len() == 11);
+        assert!(!login.contains("auth") && !login.contains("login"));
+
+        // Stable, and siblings share their directory pseudonym.
+        assert_eq!(pseudonymize_path("src/auth/login.rs", "acme"), login);
+        let logout = pseudonymize_path("src/auth/logout.rs", "acme");
+        assert_eq!(
+            logout.rsplit_once('/').unwrap().0,
+            login.rsplit_once('/').unwrap().0
+        );
+        assert_ne!(logout, login);
+        // Same name in another directory, or another salt, looks unrelated.
+        let other = pseudonymize_path("lib/auth/login.rs", "acme");
+        assert_ne!(other.split('/').nth(1), Some(parts[1]));
+        assert_ne!(pseudonymize_path("src/auth/login.rs", "other"), login);
+        assert!(!pseudonymize_path("Makefile", "acme").contains('.'));
+
+        let mut receipt = Receipt::test("r1")
+            .with_file("src/auth/login.rs", (1, 4), 4, 0)
+            .with_change(FileChange {
+                old_path: Some("src/old.rs".to_string()),
+                ..FileChange::test("src/new.rs", (1, 1), 0, 0)
+            });
+        receipt.subagent_activities = vec![serde_json::from_value(serde_json::json!({
+            "status": "completed", "files_touched": ["src/new.rs"]
+        }))
+        .unwrap()];
+        let lines = receipt.total_lines_changed();
+        anonymize_receipt(&mut receipt, "acme");
+        assert_eq!(receipt.files_changed[0].path, login);
+        assert_eq!(
+            receipt.files_changed[1].old_path.as_deref(),
+            Some(pseudonymize_path("src/old.rs", "acme").as_str())
+        );
+        assert_eq!(
+            receipt.subagent_activities[0].files_touched[0],
+            receipt.files_changed[1].path
+        );
+        assert_eq!(receipt.total_lines_changed(), lines);
+    }
+}
@@ -0,0 +1,499 @@
+/// Classroom / take-home assignment report.
+///
+/// `blameprompt assignment-report --rubric rubric.toml` gives instructors one
+/// section per student: the share of their committed lines that came from AI,
+/// every prompt they sent, whether the prompts read as iterative
+/// problem-solving or wholesale "solve this" requests, and a flag when a single
+/// prompt reproduces most of the assignment text. Students are matched against
+/// commit authors like `--author`.
+use crate::commands::anomalies::truncate;
+use crate::commands::audit;
+use crate::core::receipt::Receipt;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt::Write;
+
+/// The rubric file (TOML).
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct Rubric {
+    pub title: String,
+    /// Full assignment text; prompts that reproduce most of it are flagged.
+    pub assignment: String,
+    /// Git author names or emails, one per student.
+    pub students: Vec<String>,
+    /// Only count commits after this date (e.g. when the assignment was handed out).
+    pub from: Option<String>,
+    /// Only count commits before this date (e.g. the deadline).
+    pub to: Option<String>,
+    /// Flag students whose AI line share (0.0–1.0) is above this.
+    pub max_ai_share: f64,
+    /// Fewer prompts than this can't count as iterative problem-solving.
+    pub iterative_min_prompts: usize,
+    /// A single prompt producing more lines than this counts as wholesale.
+    pub wholesale_min_lines: u32,
+    /// Share (0.0–1.0) of the assignment's words a prompt must contain to be
+    /// treated as "the whole assignment pasted in".
+    pub assignment_overlap: f64,
+    /// Phrases that mark a prompt as a wholesale request (case-insensitive).
+    pub wholesale_phrases: Vec<String>,
+}
+
+impl Default for Rubric {
+    fn default() -> Self {
+        Rubric {
+            title: "Assignment".to_string(),
+            assignment: String::new(),
+            students: Vec::new(),
+            from: None,
+            to: None,
+            max_ai_share: 0.5,
+            iterative_min_prompts: 3,
+            wholesale_min_lines: 150,
+            assignment_overlap: 0.6,
+            wholesale_phrases: [
+                "solve this",
+                "do this assignment",
+                "complete the assignment",
+                "complete this assignment",
+                "write the whole",
+                "write the entire",
+                "implement everything",
+                "do my homework",
+            ]
+            .iter()
+            .map(|s| s.to_string())
+            .collect(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PromptRow {
+    pub timestamp: DateTime<Utc>,
+    pub model: String,
+    pub prompt: String,
+    pub lines: u32,
+    pub wholesale: bool,
+    /// Share of the assignment text found in the prompt; None when prompt text
+    /// isn't stored (`privacy.prompt_storage = "hash"` or `"none"`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub assignment_overlap: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StudentReport {
+    pub student: String,
+    pub commits: usize,
+    pub ai_lines: u32,
+    pub total_lines: u32,
+    /// AI lines / committed lines, 0.0–1.0.
+    pub ai_share: f64,
+    /// "iterative", "mixed", "wholesale" or "no-ai"
+    pub style: &'static str,
+    pub flags: Vec<String>,
+    pub prompts: Vec<PromptRow>,
+}
+
+#[derive(Serialize)]
+struct AssignmentReport<'a> {
+    title: &'a str,
+    from: Option<&'a str>,
+    to: Option<&'a str>,
+    students: &'a [StudentReport],
+}
+
+// ---------------------------------------------------------------------------
+// Public entry point
+// ---------------------------------------------------------------------------
+
+/// `blameprompt assignment-report`. `students` overrides the rubric's list.
+pub fn run(
+    rubric_path: &str,
+    students: &[String],
+    output_path: &str,
+    format: &str,
+) -> Result<(), String> {
+    let rubric = load_rubric(rubric_path)?;
+    let students = if students.is_empty() {
+        &rubric.students
+    } else {
+        students
+    };
+    if students.is_empty() {
+        return Err(format!(
+            "No students to report on. Add `students = [...]` to {} or pass --students.",
+            rubric_path
+        ));
+    }
+
+    let reports = students
+        .iter()
+        .map(|s| analyze_student(s, &rubric))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let out = if format == "json" {
+        let report = AssignmentReport {
+            title: &rubric.title,
+            from: rubric.from.as_deref(),
+            to: rubric.to.as_deref(),
+            students: &reports,
+        };
+        serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+    } else {
+        render_markdown(&rubric, &reports)
+    };
+    std::fs::write(output_path, out).map_err(|e| format!("Cannot write report: {}", e))?;
+
+    println!("Assignment report written to {}", output_path);
+    for r in &reports {
+        println!(
+            "  {}: {:.0}% AI, {} prompt(s), {}{}",
+            r.student,
+            r.ai_share * 100.0,
+            r.prompts.len(),
+            r.style,
+            if r.flags.is_empty() {
+                String::new()
+            } else {
+                format!(", {} flag(s)", r.flags.len())
+            }
+        );
+    }
+    Ok(())
+}
+
+pub fn load_rubric(path: &str) -> Result<Rubric, String> {
+    let content =
+        std::fs::read_to_string(path).map_err(|e| format!("Cannot read rubric {}: {}", path, e))?;
+    toml::from_str(&content).map_err(|e| format!("Invalid rubric {}: {}", path, e))
+}
+
+// ---------------------------------------------------------------------------
+// Per-student analysis
+// ---------------------------------------------------------------------------
+
+fn analyze_student(student: &str, rubric: &Rubric) -> Result<StudentReport, String> {
+    let entries =
+        audit::collect_audit_entries(rubric.from.as_deref(), rubric.to.as_deref(), Some(student))?;
+    let mut receipts: Vec<&Receipt> = entries.iter().flat_map(|e| &e.receipts).collect();
+    receipts.sort_by_key(|r| r.prompt_submitted_at.unwrap_or(r.timestamp));
+
+    let prompts: Vec<PromptRow> = receipts
+        .iter()
+        .map(|r| {
+            let lines = r.effective_total_additions();
+            let (wholesale, overlap) = classify_prompt(&r.prompt_summary, lines, rubric);
+            PromptRow {
+                timestamp: r.prompt_submitted_at.unwrap_or(r.timestamp),
+                model: r.model.clone(),
+                prompt: truncate(&r.prompt_summary, 200),
+                lines,
+                wholesale,
+                assignment_overlap: overlap,
+            }
+        })
+        .collect();
+
+    let ai_lines: u32 = prompts.iter().map(|p| p.lines).sum();
+    let total_lines = lines_added(student, rubric).max(ai_lines);
+    let ai_share = if total_lines > 0 {
+        ai_lines as f64 / total_lines as f64
+    } else {
+        0.0
+    };
+
+    let mut flags = Vec::new();
+    if ai_share > rubric.max_ai_share {
+        flags.push(format!(
+            "AI share {:.0}% is above the rubric maximum of {:.0}%",
+            ai_share * 100.0,
+            rubric.max_ai_share * 100.0
+        ));
+    }
+    for p in &prompts {
+        if let Some(overlap) = p
+            .assignment_overlap
+            .filter(|o| *o >= rubric.assignment_overlap)
+        {
+            flags.push(format!(
+                "Full assignment in one prompt ({:.0}% of the assignment text) at {}",
+                overlap * 100.0,
+                p.timestamp.format("%Y-%m-%d %H:%M UTC")
+            ));
+        }
+    }
+
+    Ok(StudentReport {
+        student: student.to_string(),
+        commits: entries.len(),
+        ai_lines,
+        total_lines,
+        ai_share,
+        style: problem_solving_style(&prompts, rubric),
+        flags,
+        prompts,
+    })
+}
+
+/// Whether a prompt reads as a wholesale request, and how much of the
+/// assignment text it contains (None when the text isn't stored).
+fn classify_prompt(prompt: &str, lines: u32, rubric: &Rubric) -> (bool, Option<f64>) {
+    let stored = !prompt.is_empty() && !prompt.starts_with("sha256:");
+    let overlap = (stored && !rubric.assignment.is_empty())
+        .then(|| assignment_overlap(&rubric.assignment, prompt));
+    let lower = prompt.to_lowercase();
+    let wholesale = lines > rubric.wholesale_min_lines
+        || overlap.is_some_and(|o| o >= rubric.assignment_overlap)
+        || rubric
+            .wholesale_phrases
+            .iter()
+            .any(|p| lower.contains(&p.to_lowercase()));
+    (wholesale, overlap)
+}
+
+/// Share of the assignment's distinct words (4+ letters) that appear in `prompt`.
+fn assignment_overlap(assignment: &str, prompt: &str) -> f64 {
+    let words = |s: &str| -> HashSet<String> {
+        s.split(|c: char| !c.is_alphanumeric())
+            .filter(|w| w.chars().count() >= 4)
+            .map(|w| w.to_lowercase())
+            .collect()
+    };
+    let wanted = words(assignment);
+    if wanted.is_empty() {
+        return 0.0;
+    }
+    let found = words(prompt);
+    wanted.intersection(&found).count() as f64 / wanted.len() as f64
+}
+
+/// Iterative: enough prompts and most AI lines came from non-wholesale ones.
+/// Wholesale: most AI lines came from wholesale prompts.
+fn problem_solving_style(prompts: &[PromptRow], rubric: &Rubric) -> &'static str {
+    if prompts.is_empty() {
+        return "no-ai";
+    }
+    let total: u32 = prompts.iter().map(|p| p.lines).sum();
+    let wholesale: u32 = prompts
+        .iter()
+        .filter(|p| p.wholesale)
+        .map(|p| p.lines)
+        .sum();
+    let wholesale_count = prompts.iter().filter(|p| p.wholesale).count();
+    let wholesale_share = if total > 0 {
+        wholesale as f64 / total as f64
+    } else {
+        wholesale_count as f64 / prompts.len() as f64
+    };
+
+    if wholesale_share > 0.5 {
+        "wholesale"
+    } else if prompts.len() >= rubric.iterative_min_prompts && wholesale_count == 0 {
+        "iterative"
+    } else {
+        "mixed"
+    }
+}
+
+/// Lines the student added across their commits in the rubric's date range.
+fn lines_added(student: &str, rubric: &Rubric) -> u32 {
+    let mut args = vec![
+        "log".to_string(),
+        "--numstat".to_string(),
+        "--format=".to_string(),
+        format!("--author={}", student),
+    ];
+    if let Some(f) = &rubric.from {
+        args.push(format!("--since={}", f));
+    }
+    if let Some(t) = &rubric.to {
+        args.push(format!("--until={}", t));
+    }
+    std::process::Command::new("git")
+        .args(&args)
+        .output()
+        .ok()
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .filter_map(|l| l.split('\t').next()?.parse::<u32>().ok())
+                .sum()
+        })
+        .unwrap_or(0)
+}
+
+// ---------------------------------------------------------------------------
+// Markdown
+// ---------------------------------------------------------------------------
+
+fn render_markdown(rubric: &Rubric, reports: &[StudentReport]) -> String {
+    let mut md = String::with_capacity(4096);
+    let _ = writeln!(md, "# {} — AI Usage Report", rubric.title);
+    let _ = writeln!(
+        md,
+        "> Generated by BlamePrompt v{}",
+        env!("CARGO_PKG_VERSION")
+    );
+    if rubric.from.is_some() || rubric.to.is_some() {
+        let _ = writeln!(
+            md,
+            "> Period: {} -- {}",
+            rubric.from.as_deref().unwrap_or("start"),
+            rubric.to.as_deref().unwrap_or("now")
+        );
+    }
+    let _ = writeln!(
+        md,
+        "> Report date: {}\n",
+        Utc::now().format("%Y-%m-%d %H:%M UTC")
+    );
+
+    let _ = writeln!(md, "## Summary\n");
+    let _ = writeln!(
+        md,
+        "| Student | AI share | AI lines / total | Prompts | Style | Flags |"
+    );
+    let _ = writeln!(
+        md,
+        "|---------|----------|------------------|---------|-------|-------|"
+    );
+    for r in reports {
+        let _ = writeln!(
+            md,
+            "| {} | {:.0}% | {} / {} | {} | {} | {} |",
+            r.student,
+            r.ai_share * 100.0,
+            r.ai_lines,
+            r.total_lines,
+            r.prompts.len(),
+            r.style,
+            r.flags.len()
+        );
+    }
+    let _ = writeln!(md);
+
+    for r in reports {
+        let _ = writeln!(md, "## {}\n", r.student);
+        let _ = writeln!(
+            md,
+            "{} commit(s), {:.0}% of committed lines from AI, problem-solving style: **{}**.\n",
+            r.commits,
+            r.ai_share * 100.0,
+            r.style
+        );
+        if !r.flags.is_empty() {
+            let _ = writeln!(md, "### Flags\n");
+            for f in &r.flags {
+                let _ = writeln!(md, "- {}", f);
+            }
+            let _ = writeln!(md);
+        }
+        if r.prompts.is_empty() {
+            let _ = writeln!(md, "No AI prompts recorded.\n");
+            continue;
+        }
+        let _ = writeln!(md, "### Prompts\n");
+        let _ = writeln!(md, "| Time | Model | Lines | Kind | Prompt |");
+        let _ = writeln!(md, "|------|-------|-------|------|--------|");
+        for p in &r.prompts {
+            let _ = writeln!(
+                md,
+                "| {} | {} | {} | {} | {} |",
+                p.timestamp.format("%m-%d %H:%M"),
+                p.model,
+                p.lines,
+                if p.wholesale {
+                    "wholesale"
+                } else {
+                    "iterative"
+                },
+                p.prompt.replace('|', "\\|").replace('\n', " ")
+            );
+        }
+        let _ = writeln!(md);
+    }
+
+    let _ = writeln!(md, "---");
+    let _ = writeln!(
+        md,
+        "*Generated by BlamePrompt v{} -- flags are a starting point for a conversation with the student, not a verdict.*",
+        env!("CARGO_PKG_VERSION"),
+    );
+    md
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(lines: u32, wholesale: bool) -> PromptRow {
+        PromptRow {
+            timestamp: Utc::now(),
+            model: "claude-sonnet-4-6".to_string(),
+            prompt: String::new(),
+            lines,
+            wholesale,
+            assignment_overlap: None,
+        }
+    }
+
+    #[test]
+    fn test_parse_rubric_defaults() {
+        let rubric: Rubric = toml::from_str(
+            r#"
+title = "CS101 Lab 3"
+assignment = "Implement a singly linked list"
+students = ["alice@uni.edu"]
+max_ai_share = 0.3
+"#,
+        )
+        .unwrap();
+        assert_eq!(rubric.title, "CS101 Lab 3");
+        assert_eq!(rubric.max_ai_share, 0.3);
+        assert_eq!(rubric.iterative_min_prompts, 3);
+        assert!(!rubric.wholesale_phrases.is_empty());
+    }
+
+    #[test]
+    fn test_pasted_assignment_is_flagged() {
+        let rubric = Rubric {
+            assignment: "Implement a singly linked list with push, pop and reverse \
+                         operations. Include unit tests for every operation."
+                .to_string(),
+            ..Rubric::default()
+        };
+        let pasted = "Implement a singly linked list with push, pop and reverse operations. \
+                      Include unit tests for every operation. Thanks!";
+        let (wholesale, overlap) = classify_prompt(pasted, 40, &rubric);
+        assert!(wholesale);
+        assert!(overlap.unwrap() > 0.9);
+
+        let (wholesale, overlap) =
+            classify_prompt("why does my reverse loop drop the head node?", 12, &rubric);
+        assert!(!wholesale);
+        assert!(overlap.unwrap() < 0.3);
+
+        let (wholesale, overlap) = classify_prompt("sha256:abc", 12, &rubric);
+        assert!(!wholesale);
+        assert!(overlap.is_none());
+
+        let (wholesale, _) = classify_prompt("Solve this for me", 10, &rubric);
+        assert!(wholesale);
+    }
+
+    #[test]
+    fn test_problem_solving_style() {
+        let rubric = Rubric::default();
+        assert_eq!(problem_solving_style(&[], &rubric), "no-ai");
+        let iterative = vec![row(10, false), row(20, false), row(5, false)];
+        assert_eq!(problem_solving_style(&iterative, &rubric), "iterative");
+        let wholesale = vec![row(300, true), row(10, false)];
+        assert_eq!(problem_solving_style(&wholesale, &rubric), "wholesale");
+        let mixed = vec![row(20, true), row(30, false), row(40, false)];
+        assert_eq!(problem_solving_style(&mixed, &rubric), "mixed");
+        let too_few = vec![row(10, false)];
+        assert_eq!(problem_solving_style(&too_few, &rubric), "mixed");
+    }
+}
@@ -0,0 +1,184 @@
+/// `blameprompt attach --dry-run`: what attaching would put on HEAD.
+///
+/// Builds HEAD's note the way `attach` would (merging an amended commit's
+/// receipts), finalizes it in memory and prints the staged receipts it would
+/// add, whether each of their files is part of HEAD, their acceptance stats and
+/// the commit's line origins. No note is written and staging is left as is,
+/// so mis-attribution can be caught before it lands in `refs/notes/blameprompt`.
+use crate::commands::audit;
+use crate::commands::finalize;
+use crate::commands::staging::StagingData;
+use crate::core::receipt::{FileChange, Receipt};
+use crate::core::util;
+use crate::git::notes::{self, HeadNote};
+use std::collections::HashSet;
+use std::process::Command;
+
+pub fn run(staging: &StagingData, held: &[Receipt], format: &str) -> Result<(), String> {
+    let mut note = notes::head_note(staging)?;
+    finalize::finalize_payload(&mut note.payload, &note.head);
+    let staged: Vec<String> = staging.receipts.iter().map(|r| r.id.clone()).collect();
+    if format == "json" {
+        let out = serde_json::json!({
+            "commit": note.head,
+            "amended": note.amended,
+            "attached": staged,
+            "left_staged": held.iter().map(|r| &r.id).collect::<Vec<_>>(),
+            "note": note.payload,
+        });
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&out).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+    print!(
+        "{}",
+        render(&note, &staged, held, &commit_files(&note.head))
+    );
+    Ok(())
+}
+
+/// Paths HEAD changed, relative to the repository root.
+fn commit_files(commit: &str) -> HashSet<String> {
+    Command::new("git")
+        .args([
+            "diff-tree",
+            "--no-commit-id",
+            "--name-only",
+            "-r",
+            "--root",
+            commit,
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+fn in_commit(fc: &FileChange, files: &HashSet<String>) -> bool {
+    files
+        .iter()
+        .any(|p| fc.path == *p || fc.path.ends_with(&format!("/{}", p)))
+}
+
+fn render(note: &HeadNote, staged: &[String], held: &[Receipt], files: &HashSet<String>) -> String {
+    let mut out = String::from("[BlamePrompt] Dry run: no note written, staging kept.\n");
+    let mut carried = String::new();
+    if note.existing > 0 {
+        carried = format!(", {} already attached", note.existing);
+        if let Some(old) = &note.amended {
+            carried.push_str(&format!(
+                " (carried over from amended {})",
+                util::short_sha(old)
+            ));
+        }
+    }
+    out.push_str(&format!(
+        "{} receipt(s) would be attached to {}{}\n",
+        staged.len(),
+        util::short_sha(&note.head),
+        carried
+    ));
+    for r in note
+        .payload
+        .receipts
+        .iter()
+        .filter(|r| staged.contains(&r.id))
+    {
+        out.push_str(&format!(
+            "\n  {}  {} / {}  \"{}\"\n",
+            util::short_sha(&r.id),
+            r.provider,
+            r.model,
+            r.prompt_summary.lines().next().unwrap_or("")
+        ));
+        match (r.accepted_lines, r.overridden_lines) {
+            (Some(accepted), Some(overridden)) => out.push_str(&format!(
+                "    acceptance: {} accepted, {} overridden\n",
+                accepted, overridden
+            )),
+            _ => out.push_str("    acceptance: no captured blob matches a file in this commit\n"),
+        }
+        for fc in &r.files_changed {
+            let mut status = if in_commit(fc, files) {
+                "in this commit".to_string()
+            } else {
+                "not in this commit".to_string()
+            };
+            if !fc.unstaged_ranges.is_empty() {
+                let ranges: Vec<String> = fc
+                    .unstaged_ranges
+                    .iter()
+                    .map(|(s, e)| format!("{}-{}", s, e))
+                    .collect();
+                status.push_str(&format!("; lines {} left unstaged", ranges.join(", ")));
+            }
+            out.push_str(&format!(
+                "    {}  +{} -{}  {}\n",
+                audit::file_change_label(fc),
+                fc.additions,
+                fc.deletions,
+                status
+            ));
+        }
+    }
+    if let Some(origin) = &note.payload.code_origin {
+        out.push_str(&format!(
+            "\nLine origins of the commit: {} AI, {} human-edited, {} human ({:.1}% AI)\n",
+            origin.ai_generated_lines,
+            origin.human_edited_lines,
+            origin.pure_human_lines,
+            origin.ai_generated_pct
+        ));
+    }
+    if !held.is_empty() {
+        let ids: Vec<String> = held.iter().map(|r| util::short_sha(&r.id)).collect();
+        out.push_str(&format!(
+            "\nLeft staged, they belong to an earlier commit ({}); see `blameprompt recover`\n",
+            ids.join(", ")
+        ));
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::NotePayload;
+
+    #[test]
+    fn test_render_preview() {
+        let mut r = Receipt::test("r1-abcdef")
+            .with_prompt("add retries\nmore")
+            .with_file("src/a.rs", (1, 3), 3, 0)
+            .with_file("src/b.rs", (1, 2), 2, 1);
+        r.files_changed[0].unstaged_ranges = vec![(4, 5)];
+        r.accepted_lines = Some(3);
+        r.overridden_lines = Some(0);
+        let mut held = r.clone();
+        held.id = "held-0001".to_string();
+        let note = HeadNote {
+            head: "ad2b98250000".to_string(),
+            amended: Some("1234abcd9999".to_string()),
+            existing: 2,
+            payload: NotePayload::new(vec![r]),
+        };
+        let files = HashSet::from(["src/a.rs".to_string()]);
+        let out = render(&note, &["r1-abcdef".to_string()], &[held], &files);
+        assert!(out.contains(
+            "1 receipt(s) would be attached to ad2b9825, 2 already attached (carried over from amended 1234abcd)"
+        ));
+        assert!(out.contains("r1-abcde  claude / sonnet  \"add retries\""));
+        assert!(out.contains("acceptance: 3 accepted, 0 overridden"));
+        assert!(out.contains("src/a.rs  +3 -0  in this commit; lines 4-5 left unstaged"));
+        assert!(out.contains("src/b.rs  +2 -1  not in this commit"));
+        assert!(out.contains("earlier commit (held-000)"));
+    }
+}
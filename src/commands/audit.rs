@@ -1,7 +1,13 @@
-use crate::core::{receipt::Receipt, util};
+use crate::commands::anonymize_paths;
+use crate::commands::grouping::{self, Group, GroupBy};
+use crate::commands::lifecycle::{self, FileLifecycle};
+use crate::commands::policy_exemptions;
+use crate::core::{
+    receipt::{ChangeType, CodeOriginStats, FileChange, PullRequestLink, Receipt},
+    term, util,
+};
 use crate::git::notes;
 use chrono::Utc;
-use comfy_table::Table;
 use serde::Serialize;
 
 #[derive(Debug, Serialize)]
@@ -13,6 +19,12 @@ pub struct AuditEntry {
     pub receipts: Vec<Receipt>,
     pub total_ai_lines: u32,
     pub total_cost_usd: f64,
+    /// Line-level origin of the commit's added lines, computed at attach.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_origin: Option<CodeOriginStats>,
+    /// The PR the commit landed through, when linked (`link-pr`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<PullRequestLink>,
 }
 
 pub fn collect_audit_entries(
@@ -33,9 +45,23 @@ pub fn collect_audit_entries(
     if let Some(a) = author {
         args.push(format!("--author={}", a));
     }
+    entries_from_log(&args)
+}
 
+/// Entries for the commits in a revision range such as `v1.0..v1.1`.
+pub fn collect_range_entries(range: &str) -> Result<Vec<AuditEntry>, String> {
+    entries_from_log(&[
+        "log".to_string(),
+        "--format=%H|%aI|%an <%ae>|%s".to_string(),
+        range.to_string(),
+    ])
+}
+
+/// Run `git log` with `args` (using the `%H|%aI|%an <%ae>|%s` format) and
+/// keep the commits that carry receipts.
+fn entries_from_log(args: &[String]) -> Result<Vec<AuditEntry>, String> {
     let output = std::process::Command::new("git")
-        .args(&args)
+        .args(args)
         .output()
         .map_err(|e| format!("git log failed: {}", e))?;
 
@@ -67,11 +93,15 @@ pub fn collect_audit_entries(
                 continue;
             }
 
-            let total_ai_lines: u32 = payload
-                .receipts
-                .iter()
-                .map(|r| r.total_lines_changed())
-                .sum();
+            // Prefer the attach-time line classification; older notes only
+            // have the receipts' own line counts.
+            let total_ai_lines: u32 = payload.mapped_ai_lines().unwrap_or_else(|| {
+                payload
+                    .receipts
+                    .iter()
+                    .map(|r| r.total_lines_changed())
+                    .sum()
+            });
             let total_cost_usd: f64 = payload.receipts.iter().map(|r| r.cost_usd).sum();
 
             entries.push(AuditEntry {
@@ -82,6 +112,8 @@ pub fn collect_audit_entries(
                 receipts: payload.receipts,
                 total_ai_lines,
                 total_cost_usd,
+                code_origin: payload.code_origin,
+                pull_request: payload.pull_request,
             });
         }
     }
@@ -111,9 +143,57 @@ pub fn collect_staged_entries() -> Vec<AuditEntry> {
         receipts: staging.receipts,
         total_ai_lines,
         total_cost_usd,
+        code_origin: None,
+        pull_request: None,
     }]
 }
 
+/// AI totals for the commits that landed through one pull request.
+pub struct PullRequestGroup<'a> {
+    pub link: &'a PullRequestLink,
+    pub commits: usize,
+    pub receipts: usize,
+    pub ai_lines: u32,
+    pub cost_usd: f64,
+}
+
+/// Group linked entries by PR number, most recently linked PR first (entries
+/// come newest first from `git log`). Unlinked entries are left out.
+pub fn group_by_pull_request(entries: &[AuditEntry]) -> Vec<PullRequestGroup<'_>> {
+    let mut groups: Vec<PullRequestGroup> = Vec::new();
+    for entry in entries {
+        let Some(link) = entry.pull_request.as_ref() else {
+            continue;
+        };
+        let group = match groups.iter().position(|g| g.link.number == link.number) {
+            Some(i) => &mut groups[i],
+            None => {
+                groups.push(PullRequestGroup {
+                    link,
+                    commits: 0,
+                    receipts: 0,
+                    ai_lines: 0,
+                    cost_usd: 0.0,
+                });
+                groups.last_mut().unwrap()
+            }
+        };
+        group.commits += 1;
+        group.receipts += entry.receipts.len();
+        group.ai_lines += entry.total_ai_lines;
+        group.cost_usd += entry.total_cost_usd;
+    }
+    groups
+}
+
+/// `#12`, as a Markdown link when the PR URL is known.
+pub fn pull_request_markdown(link: &PullRequestLink) -> String {
+    match &link.url {
+        Some(url) => format!("[#{}]({})", link.number, url),
+        None => format!("#{}", link.number),
+    }
+}
+
 /// Collect both committed and (optionally) staged/uncommitted entries.
 pub fn collect_all_entries(
     from: Option<&str>,
@@ -136,6 +216,42 @@ fn truncate_str(s: &str, max_len: usize) -> String {
     }
 }
 
+/// Short table label for a file change: the path, marked for deletions and renames.
+pub fn file_change_label(fc: &FileChange) -> String {
+    let path = relative_path(&fc.path);
+    match (fc.change_type, fc.old_path.as_deref()) {
+        (Some(ChangeType::Deleted), _) => format!("{} (deleted)", path),
+        (Some(ChangeType::Renamed), Some(old)) => format!("{} → {}", relative_path(old), path),
+        (Some(ChangeType::Added), _) => format!("{} (new)", path),
+        _ => path,
+    }
+}
+
+/// Markdown list entry for a file change in the audit report.
+fn file_change_markdown(fc: &FileChange) -> String {
+    let path = relative_path(&fc.path);
+    if fc.is_binary {
+        return format!("`{}` (binary, {:+} bytes)", path, fc.byte_delta());
+    }
+    match (fc.change_type, fc.old_path.as_deref()) {
+        (Some(ChangeType::Deleted), _) => {
+            format!("`{}` (deleted, -{} lines)", path, fc.deletions)
+        }
+        (Some(ChangeType::Renamed), Some(old)) => format!(
+            "`{}` → `{}` (renamed, lines {}-{})",
+            relative_path(old),
+            path,
+            fc.line_range.0,
+            fc.line_range.1
+        ),
+        (Some(ChangeType::Added), _) => format!(
+            "`{}` (new file, lines {}-{})",
+            path, fc.line_range.0, fc.line_range.1
+        ),
+        _ => format!("`{}` (lines {}-{})", path, fc.line_range.0, fc.line_range.1),
+    }
+}
+
 /// Convert absolute file paths to repo-relative paths for cleaner display.
 pub fn relative_path(path: &str) -> String {
     if let Ok(cwd) = std::env::current_dir() {
@@ -154,7 +270,7 @@ pub fn relative_path(path: &str) -> String {
 
 /// Properly escape a value for CSV output: wrap in quotes if it contains
 /// commas, quotes, or newlines, and double any internal quotes.
-fn csv_escape(s: &str) -> String {
+pub fn csv_escape(s: &str) -> String {
     if s.contains(',') || s.contains('"') || s.contains('\n') || s.contains('\r') {
         format!("\"{}\"", s.replace('"', "\"\""))
     } else {
@@ -180,6 +296,12 @@ fn write_receipt_md(md: &mut String, r: &Receipt) {
     if !r.mcp_servers.is_empty() {
         md.push_str(&format!("| MCP Servers | {} |\n", r.mcp_servers.join(", ")));
     }
+    if let Some(env) = &r.environment {
+        md.push_str(&format!(
+            "| Environment | {} |\n",
+            env.describe(&r.provider)
+        ));
+    }
     if !r.agents_spawned.is_empty() {
         md.push_str(&format!(
             "| Agents Spawned | {} |\n",
@@ -188,14 +310,12 @@ fn write_receipt_md(md: &mut String, r: &Receipt) {
     }
     md.push('\n');
     for fc in &file_changes {
-        md.push_str(&format!(
-            "- `{}` (lines {}-{})\n",
-            relative_path(&fc.path),
-            fc.line_range.0,
-            fc.line_range.1
-        ));
+        md.push_str(&format!("- {}\n", file_change_markdown(fc)));
     }
     md.push('\n');
+    if let Some(ref intent) = r.intent_summary {
+        md.push_str(&format!("**Intent:** {}\n\n", intent));
+    }
     md.push_str("**Prompt Summary:**\n");
     md.push_str(&format!("> {}\n\n", r.prompt_summary));
     md.push_str(&format!("**Prompt Hash:** `{}`\n\n", r.prompt_hash));
@@ -232,7 +352,8 @@ fn write_receipt_md(md: &mut String, r: &Receipt) {
     md.push_str("---\n\n");
 }
 
-fn generate_markdown(entries: &[AuditEntry]) -> String {
+/// `exemptions` is the rendered `## Policy Exemptions` section (may be empty).
+fn generate_markdown(entries: &[AuditEntry], exemptions: &str) -> String {
     let now = Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
 
     let committed: Vec<&AuditEntry> = entries
@@ -260,11 +381,39 @@ fn generate_markdown(entries: &[AuditEntry]) -> String {
     md.push_str(&format!("| Total receipts | {} |\n", total_receipts));
     md.push_str(&format!("| Total AI lines | {} |\n", total_lines));
     md.push_str(&format!("| Estimated cost | ${:.2} |\n", total_cost));
+    if let Some(origin) =
+        CodeOriginStats::sum(entries.iter().filter_map(|e| e.code_origin.as_ref()))
+    {
+        md.push_str(&format!(
+            "| Code origin (added lines) | {:.0}% AI-generated, {:.0}% human-edited, {:.0}% human |\n",
+            origin.ai_generated_pct, origin.human_edited_pct, origin.pure_human_pct
+        ));
+    }
     md.push_str(&format!(
         "| Uncommitted receipts | {} |\n\n",
         uncommitted_receipts
     ));
 
+    let groups = group_by_pull_request(entries);
+    if !groups.is_empty() {
+        md.push_str("## Pull Requests\n");
+        md.push_str("| PR | Commits | Receipts | AI lines | Cost |\n");
+        md.push_str("|----|---------|----------|----------|------|\n");
+        for g in &groups {
+            md.push_str(&format!(
+                "| {} | {} | {} | {} | ${:.2} |\n",
+                pull_request_markdown(g.link),
+                g.commits,
+                g.receipts,
+                g.ai_lines,
+                g.cost_usd
+            ));
+        }
+        md.push('\n');
+    }
+
+    md.push_str(exemptions);
+
     if !committed.is_empty() {
         md.push_str("## Committed Changes\n");
         for entry in &committed {
@@ -274,7 +423,14 @@ fn generate_markdown(entries: &[AuditEntry]) -> String {
                 sha_display, entry.commit_message
             ));
             md.push_str(&format!("- **Date**: {}\n", entry.commit_date));
-            md.push_str(&format!("- **Author**: {}\n\n", entry.commit_author));
+            md.push_str(&format!("- **Author**: {}\n", entry.commit_author));
+            if let Some(ref pr) = entry.pull_request {
+                md.push_str(&format!(
+                    "- **Pull request**: {}\n",
+                    pull_request_markdown(pr)
+                ));
+            }
+            md.push('\n');
 
             for r in &entry.receipts {
                 write_receipt_md(&mut md, r);
@@ -294,13 +450,24 @@ fn generate_markdown(entries: &[AuditEntry]) -> String {
     md
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn run(
     from: Option<&str>,
     to: Option<&str>,
     author: Option<&str>,
     format: &str,
     include_uncommitted: bool,
+    group_by: Option<&str>,
+    include_deleted: bool,
+    anonymize_paths: bool,
 ) {
+    let group_by = match group_by.map(str::parse::<GroupBy>).transpose() {
+        Ok(g) => g,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
     let mut entries = match collect_all_entries(from, to, author, include_uncommitted) {
         Ok(e) => e,
         Err(e) => {
@@ -309,6 +476,17 @@ pub fn run(
         }
     };
 
+    if !include_deleted {
+        let dropped = lifecycle::drop_deleted(&mut entries, &FileLifecycle::load());
+        if dropped > 0 {
+            eprintln!(
+                "[BlamePrompt] Left out {} receipt(s) whose files were all deleted later. \
+                 Pass --include-deleted to list them.",
+                dropped
+            );
+        }
+    }
+
     // Auto-include staging data if no committed entries found
     if entries.is_empty() && !include_uncommitted {
         let staged = collect_staged_entries();
@@ -322,6 +500,20 @@ pub fn run(
         return;
     }
 
+    if anonymize_paths {
+        let salt = crate::core::config::load_config().analytics.pseudonym_salt;
+        anonymize_paths::anonymize_entries(&mut entries, &salt);
+    }
+
+    if let Some(by) = group_by {
+        print_groups(&grouping::group(&entries, by), by, format);
+        return;
+    }
+
+    let exemptions = policy_exemptions::load();
+    let policy_cfg = crate::core::config::load_config().policy;
+    let today = Utc::now().date_naive();
+
     match format {
         "json" => {
             println!(
@@ -330,7 +522,7 @@ pub fn run(
             );
         }
         "csv" => {
-            println!("commit_sha,date,author,message,provider,model,session_id,message_count,cost_usd,files,total_lines,prompt_summary,prompt_hash");
+            println!("commit_sha,date,author,message,provider,model,session_id,message_count,cost_usd,files,total_lines,prompt_summary,prompt_hash,pull_request,intent_summary");
             for entry in &entries {
                 for r in &entry.receipts {
                     let sha_display = util::short_sha(&entry.commit_sha);
@@ -339,8 +531,13 @@ pub fn run(
                         .iter()
                         .map(|f| relative_path(f))
                         .collect();
+                    let pr = entry
+                        .pull_request
+                        .as_ref()
+                        .map(|l| l.number.to_string())
+                        .unwrap_or_default();
                     println!(
-                        "{},{},{},{},{},{},{},{},{:.4},{},{},{},{}",
+                        "{},{},{},{},{},{},{},{},{:.4},{},{},{},{},{},{}",
                         csv_escape(&sha_display),
                         csv_escape(&entry.commit_date),
                         csv_escape(&entry.commit_author),
@@ -354,12 +551,17 @@ pub fn run(
                         r.total_lines_changed(),
                         csv_escape(&r.prompt_summary),
                         csv_escape(&r.prompt_hash),
+                        pr,
+                        csv_escape(r.intent_summary.as_deref().unwrap_or_default()),
                     );
                 }
             }
         }
         "md" => {
-            let markdown = generate_markdown(&entries);
+            let markdown = generate_markdown(
+                &entries,
+                &policy_exemptions::to_markdown(&exemptions, &policy_cfg, today),
+            );
             let output_path = "blameprompt-audit.md";
             match std::fs::write(output_path, &markdown) {
                 Ok(_) => println!("Audit markdown written to {}", output_path),
@@ -372,15 +574,15 @@ pub fn run(
             let total_cost: f64 = entries.iter().map(|e| e.total_cost_usd).sum();
             let total_lines: u32 = entries.iter().map(|e| e.total_ai_lines).sum();
 
-            println!("AI Audit Trail");
-            println!("==============");
+            term::status(&term::bold("AI Audit Trail"));
+            term::status("==============");
             println!("Commits with AI code: {}", entries.len());
             println!("Total receipts: {}", total_receipts);
             println!("Total AI lines: {}", total_lines);
             println!("Total estimated cost: ${:.2}", total_cost);
             println!();
 
-            let mut table = Table::new();
+            let mut table = term::table();
             table.set_header(vec![
                 "Commit",
                 "Date",
@@ -404,7 +606,7 @@ pub fn run(
                     };
                     let file_count = r.all_file_changes().len();
                     let files_display = if file_count == 1 {
-                        relative_path(&r.all_file_changes()[0].path)
+                        file_change_label(&r.all_file_changes()[0])
                     } else {
                         format!("{} files", file_count)
                     };
@@ -418,12 +620,125 @@ pub fn run(
                         &format!("${:.4}", r.cost_usd),
                         &files_display,
                         &r.total_lines_changed().to_string(),
-                        &truncate_str(&r.prompt_summary, 40),
+                        &truncate_str(r.intent_summary.as_deref().unwrap_or(&r.prompt_summary), 40),
                     ]);
                 }
             }
 
             println!("{table}");
+
+            let groups = group_by_pull_request(&entries);
+            if !groups.is_empty() {
+                println!();
+                let mut pr_table = term::table();
+                pr_table.set_header(vec!["PR", "Commits", "Receipts", "AI lines", "Cost"]);
+                for g in &groups {
+                    pr_table.add_row(vec![
+                        format!("#{}", g.link.number),
+                        g.commits.to_string(),
+                        g.receipts.to_string(),
+                        g.ai_lines.to_string(),
+                        format!("${:.4}", g.cost_usd),
+                    ]);
+                }
+                println!("{pr_table}");
+            }
+
+            if !exemptions.is_empty() {
+                let active = exemptions
+                    .iter()
+                    .filter(|e| e.status(today, &policy_cfg) == policy_exemptions::Status::Active)
+                    .count();
+                println!();
+                println!(
+                    "Policy exemptions: {} active, {} not applied (see `blameprompt policy exemptions`)",
+                    active,
+                    exemptions.len() - active
+                );
+            }
         }
     }
 }
+
+/// `audit --group-by`: one row per group in the requested format.
+fn print_groups(groups: &[Group], by: GroupBy, format: &str) {
+    match format {
+        "json" => {
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&groups).unwrap_or_default()
+            );
+        }
+        "csv" => {
+            println!(
+                "{},commits,receipts,sessions,files,ai_lines,cost_usd,first_seen,last_seen",
+                by.label()
+            );
+            for g in groups {
+                println!(
+                    "{},{},{},{},{},{},{:.4},{},{}",
+                    csv_escape(&g.key),
+                    g.commits,
+                    g.receipts,
+                    g.sessions,
+                    g.files,
+                    g.ai_lines,
+                    g.cost_usd,
+                    g.first_seen,
+                    g.last_seen
+                );
+            }
+        }
+        "table" => {
+            let mut table = term::table();
+            table.set_header(vec![
+                by.label(),
+                "Commits",
+                "Receipts",
+                "Sessions",
+                "Files",
+                "AI lines",
+                "Cost",
+            ]);
+            for g in groups {
+                table.add_row(vec![
+                    truncate_str(&g.key, 48),
+                    g.commits.to_string(),
+                    g.receipts.to_string(),
+                    g.sessions.to_string(),
+                    g.files.to_string(),
+                    g.ai_lines.to_string(),
+                    format!("${:.4}", g.cost_usd),
+                ]);
+            }
+            println!("{table}");
+        }
+        _ => {
+            let mut md = format!("# BlamePrompt Audit by {}\n\n", by.label());
+            md.push_str(&format!(
+                "| {} | Commits | Receipts | Sessions | Files | AI lines | Cost | First seen | Last seen |\n",
+                by.label()
+            ));
+            md.push_str("|---|---------|----------|----------|-------|----------|------|------------|-----------|\n");
+            for g in groups {
+                md.push_str(&format!(
+                    "| {} | {} | {} | {} | {} | {} | ${:.4} | {} | {} |\n",
+                    g.key.replace('|', "\\|"),
+                    g.commits,
+                    g.receipts,
+                    g.sessions,
+                    g.files,
+                    g.ai_lines,
+                    g.cost_usd,
+                    short_date(&g.first_seen),
+                    short_date(&g.last_seen)
+                ));
+            }
+            print!("{}", md);
+        }
+    }
+}
+
+fn short_date(ts: &str) -> &str {
+    ts.get(..10).unwrap_or(ts)
+}
@@ -0,0 +1,209 @@
+/// Threshold-based labels for AI-assisted changes (`[policy.labels]`).
+///
+/// After `attach`, the commit's share of AI lines (from the note's
+/// `code_origin`, else the receipts) decides its label: `ai-generated`,
+/// `ai-assisted` or `human`. It is written as a commit message trailer
+/// (`AI-Attribution: ai-assisted`) and/or as a label on the branch's open
+/// GitHub PR, where the share covers every commit in the PR.
+use crate::commands::github;
+use crate::core::config::LabelConfig;
+use crate::core::receipt::CodeOriginStats;
+use crate::git::notes;
+use std::process::Command;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AiLabel {
+    AiGenerated,
+    AiAssisted,
+    Human,
+}
+
+impl AiLabel {
+    pub const ALL: [AiLabel; 3] = [AiLabel::AiGenerated, AiLabel::AiAssisted, AiLabel::Human];
+
+    pub fn classify(ai_pct: f64, cfg: &LabelConfig) -> Self {
+        if ai_pct >= cfg.ai_generated_pct {
+            AiLabel::AiGenerated
+        } else if ai_pct >= cfg.ai_assisted_pct {
+            AiLabel::AiAssisted
+        } else {
+            AiLabel::Human
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AiLabel::AiGenerated => "ai-generated",
+            AiLabel::AiAssisted => "ai-assisted",
+            AiLabel::Human => "human",
+        }
+    }
+}
+
+/// Label HEAD (and its PR) per `cfg.apply`. `fallback_pct` is the receipts'
+/// estimate, used when the note has no line-level origin.
+pub fn apply(cfg: &LabelConfig, fallback_pct: f64) {
+    if cfg.apply.is_empty() {
+        return;
+    }
+    let head_pct = notes::read_receipts_for_commit("HEAD")
+        .and_then(|p| p.code_origin)
+        .map(|o| ai_pct(&o))
+        .unwrap_or(fallback_pct);
+
+    if cfg.apply.iter().any(|a| a == "trailer") {
+        let label = AiLabel::classify(head_pct, cfg);
+        match amend_trailer(&cfg.trailer, label.as_str()) {
+            Ok(true) => eprintln!(
+                "[BlamePrompt] Commit labelled {}: {}",
+                cfg.trailer,
+                label.as_str()
+            ),
+            Ok(false) => {}
+            Err(e) => eprintln!("[BlamePrompt] Warning: could not add label trailer: {}", e),
+        }
+    }
+    if cfg.apply.iter().any(|a| a == "github") {
+        let pct = pr_ai_pct().unwrap_or(head_pct);
+        let label = AiLabel::classify(pct, cfg);
+        let others: Vec<&str> = AiLabel::ALL
+            .iter()
+            .filter(|l| **l != label)
+            .map(|l| l.as_str())
+            .collect();
+        match github::label_current_pr(label.as_str(), &others) {
+            Ok(Some(pr)) => eprintln!("[BlamePrompt] PR #{} labelled {}", pr, label.as_str()),
+            Ok(None) => {}
+            Err(e) => eprintln!("[BlamePrompt] Warning: could not label PR: {}", e),
+        }
+    }
+}
+
+/// `apply` for a commit with no receipts, which labels it `human`. Skipped
+/// when HEAD already carries the trailer: that is our own amend re-running
+/// the post-commit hook before post-rewrite has moved the note.
+pub fn apply_untracked(cfg: &LabelConfig) {
+    if cfg.apply.is_empty() {
+        return;
+    }
+    let prefix = format!("{}: ", cfg.trailer.to_lowercase());
+    let labelled =
+        head_message().is_some_and(|m| m.lines().any(|l| l.to_lowercase().starts_with(&prefix)));
+    if !labelled {
+        apply(cfg, 0.0);
+    }
+}
+
+fn head_message() -> Option<String> {
+    Command::new("git")
+        .args(["log", "-1", "--format=%B"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+}
+
+fn ai_pct(origin: &CodeOriginStats) -> f64 {
+    if origin.total_lines() > 0 {
+        origin.ai_lines() as f64 * 100.0 / origin.total_lines() as f64
+    } else {
+        origin.ai_generated_pct + origin.human_edited_pct
+    }
+}
+
+/// AI share over the commits between the PR base and HEAD that have line
+/// counts recorded.
+fn pr_ai_pct() -> Option<f64> {
+    let base = github::detect_base_ref()?;
+    let origins: Vec<CodeOriginStats> = github::commits_between(&base, "HEAD")
+        .iter()
+        .filter_map(|sha| notes::read_receipts_for_commit(sha)?.code_origin)
+        .collect();
+    CodeOriginStats::sum(&origins).map(|o| ai_pct(&o))
+}
+
+/// `message` with trailer `key: value`, replacing an existing `key` trailer.
+pub fn set_trailer(message: &str, key: &str, value: &str) -> String {
+    let message = message.trim_end();
+    let line = format!("{}: {}", key, value);
+    let (body, last) = match message.rsplit_once("\n\n") {
+        Some((body, last)) => (Some(body), last),
+        None => (None, message),
+    };
+    let is_trailer = |l: &str| {
+        l.split_once(": ").is_some_and(|(k, _)| {
+            !k.is_empty() && k.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        })
+    };
+    match body {
+        Some(body) if last.lines().all(is_trailer) => {
+            let prefix = format!("{}: ", key);
+            let mut trailers: Vec<&str> = last
+                .lines()
+                .filter(|l| !l.to_lowercase().starts_with(&prefix.to_lowercase()))
+                .collect();
+            trailers.push(&line);
+            format!("{}\n\n{}\n", body, trailers.join("\n"))
+        }
+        _ => format!("{}\n\n{}\n", message, line),
+    }
+}
+
+/// Amend HEAD's message with the trailer; false when it was already there.
+fn amend_trailer(key: &str, value: &str) -> Result<bool, String> {
+    let message = head_message().ok_or("cannot read the commit message")?;
+    let labelled = set_trailer(&message, key, value);
+    if labelled.trim_end() == message.trim_end() {
+        return Ok(false);
+    }
+    let status = Command::new("git")
+        .args([
+            "commit",
+            "--amend",
+            "--no-verify",
+            "--quiet",
+            "-m",
+            &labelled,
+        ])
+        .status()
+        .map_err(|e| e.to_string())?;
+    if status.success() {
+        Ok(true)
+    } else {
+        Err("git commit --amend failed".to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_and_set_trailer() {
+        let cfg = LabelConfig::default();
+        assert_eq!(AiLabel::classify(85.0, &cfg), AiLabel::AiGenerated);
+        assert_eq!(AiLabel::classify(20.0, &cfg), AiLabel::AiAssisted);
+        assert_eq!(AiLabel::classify(5.0, &cfg), AiLabel::Human);
+
+        assert_eq!(
+            set_trailer("Fix login\n", "AI-Attribution", "human"),
+            "Fix login\n\nAI-Attribution: human\n"
+        );
+        assert_eq!(
+            set_trailer(
+                "Fix login\n\nLonger body.\n\nSigned-off-by: Dana <d@example.com>\nai-attribution: human\n",
+                "AI-Attribution",
+                "ai-assisted"
+            ),
+            "Fix login\n\nLonger body.\n\nSigned-off-by: Dana <d@example.com>\nAI-Attribution: ai-assisted\n"
+        );
+        assert_eq!(
+            set_trailer(
+                "Fix login\n\nExplains why: the docs.",
+                "AI-Attribution",
+                "human"
+            ),
+            "Fix login\n\nExplains why: the docs.\n\nAI-Attribution: human\n"
+        );
+    }
+}
@@ -0,0 +1,185 @@
+/// `blameprompt badge`: a README badge with the repository's AI share.
+///
+/// The message reads "23% AI · 87% receipts": the share of current lines
+/// attributed to AI (counted like `heatmap`) and the share of commits on HEAD
+/// that carry receipts. `--output badge.svg` renders a flat shields-style SVG;
+/// a `.json` output writes a shields.io endpoint document, which
+/// `https://img.shields.io/endpoint?url=<raw URL of the file>` turns into a
+/// badge. The colour follows receipt coverage, since a low AI share with poor
+/// coverage says little.
+use crate::commands::heatmap;
+use crate::git::notes;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::process::Command;
+
+const LABEL: &str = "AI-assisted";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BadgeStats {
+    pub ai_lines: u32,
+    pub total_lines: u32,
+    pub commits: usize,
+    pub commits_with_receipts: usize,
+}
+
+impl BadgeStats {
+    pub fn ai_pct(&self) -> f64 {
+        pct(self.ai_lines as usize, self.total_lines as usize)
+    }
+
+    pub fn coverage_pct(&self) -> f64 {
+        pct(self.commits_with_receipts, self.commits)
+    }
+
+    pub fn message(&self) -> String {
+        format!(
+            "{:.0}% AI · {:.0}% receipts",
+            self.ai_pct(),
+            self.coverage_pct()
+        )
+    }
+
+    /// Shields colour name and its hex value, by receipt coverage.
+    pub fn color(&self) -> (&'static str, &'static str) {
+        match self.coverage_pct() {
+            c if c >= 80.0 => ("brightgreen", "#4c1"),
+            c if c >= 50.0 => ("yellow", "#dfb317"),
+            _ => ("orange", "#fe7d37"),
+        }
+    }
+}
+
+/// shields.io endpoint schema: <https://shields.io/badges/endpoint-badge>.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Endpoint {
+    schema_version: u32,
+    label: &'static str,
+    message: String,
+    color: &'static str,
+}
+
+pub fn run(output: &str) -> Result<(), String> {
+    let stats = collect()?;
+    let content = if output.ends_with(".json") {
+        render_endpoint(&stats)
+    } else {
+        render_svg(&stats)
+    };
+    std::fs::write(output, content).map_err(|e| format!("Cannot write {}: {}", output, e))?;
+    println!(
+        "[BlamePrompt] Badge written to {} ({})",
+        output,
+        stats.message()
+    );
+    Ok(())
+}
+
+fn collect() -> Result<BadgeStats, String> {
+    let files = heatmap::file_line_counts()?;
+    let output = Command::new("git")
+        .args(["rev-list", "HEAD"])
+        .output()
+        .map_err(|e| format!("git rev-list failed: {}", e))?;
+    let history: Vec<String> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .map(String::from)
+        .collect();
+    let with_notes: HashSet<String> = notes::list_commits_with_notes().into_iter().collect();
+    Ok(BadgeStats {
+        ai_lines: files.iter().map(|(_, ai, _)| ai).sum(),
+        total_lines: files.iter().map(|(_, _, total)| total).sum(),
+        commits: history.len(),
+        commits_with_receipts: history.iter().filter(|c| with_notes.contains(*c)).count(),
+    })
+}
+
+pub fn render_endpoint(stats: &BadgeStats) -> String {
+    let endpoint = Endpoint {
+        schema_version: 1,
+        label: LABEL,
+        message: stats.message(),
+        color: stats.color().0,
+    };
+    serde_json::to_string_pretty(&endpoint).unwrap_or_default()
+}
+
+/// Flat badge in the shields.io layout. Text widths are estimated from the
+/// average Verdana 11px glyph width, which is close enough for short labels.
+pub fn render_svg(stats: &BadgeStats) -> String {
+    let message = stats.message();
+    let label_w = text_width(LABEL);
+    let message_w = text_width(&message);
+    let width = label_w + message_w;
+    let (_, color) = stats.color();
+    format!(
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="20" role="img" aria-label="{LABEL}: {message}">
+<title>{LABEL}: {message}</title>
+<linearGradient id="s" x2="0" y2="100%"><stop offset="0" stop-color="#bbb" stop-opacity=".1"/><stop offset="1" stop-opacity=".1"/></linearGradient>
+<clipPath id="r"><rect width="{width}" height="20" rx="3" fill="#fff"/></clipPath>
+<g clip-path="url(#r)">
+<rect width="{label_w}" height="20" fill="#555"/>
+<rect x="{label_w}" width="{message_w}" height="20" fill="{color}"/>
+<rect width="{width}" height="20" fill="url(#s)"/>
+</g>
+<g fill="#fff" text-anchor="middle" font-family="Verdana,Geneva,DejaVu Sans,sans-serif" font-size="11">
+<text x="{label_x}" y="15" fill="#010101" fill-opacity=".3">{LABEL}</text>
+<text x="{label_x}" y="14">{LABEL}</text>
+<text x="{message_x}" y="15" fill="#010101" fill-opacity=".3">{message}</text>
+<text x="{message_x}" y="14">{message}</text>
+</g>
+</svg>
+"##,
+        label_x = label_w / 2,
+        message_x = label_w + message_w / 2,
+    )
+}
+
+fn text_width(text: &str) -> u32 {
+    text.chars().count() as u32 * 7 + 10
+}
+
+fn pct(part: usize, whole: usize) -> f64 {
+    if whole == 0 {
+        0.0
+    } else {
+        part as f64 / whole as f64 * 100.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_badge_rendering() {
+        let stats = BadgeStats {
+            ai_lines: 230,
+            total_lines: 1000,
+            commits: 40,
+            commits_with_receipts: 35,
+        };
+        assert_eq!(stats.message(), "23% AI · 88% receipts");
+        assert_eq!(stats.color().0, "brightgreen");
+
+        let endpoint: serde_json::Value = serde_json::from_str(&render_endpoint(&stats)).unwrap();
+        assert_eq!(endpoint["schemaVersion"], 1);
+        assert_eq!(endpoint["label"], "AI-assisted");
+        assert_eq!(endpoint["message"], "23% AI · 88% receipts");
+
+        let svg = render_svg(&stats);
+        assert!(svg.starts_with("<svg"));
+        assert!(svg.contains(r##"fill="#4c1""##));
+        assert!(svg.contains(">23% AI · 88% receipts</text>"));
+
+        let empty = BadgeStats {
+            ai_lines: 0,
+            total_lines: 0,
+            commits: 0,
+            commits_with_receipts: 0,
+        };
+        assert_eq!(empty.message(), "0% AI · 0% receipts");
+        assert_eq!(empty.color().0, "orange");
+    }
+}
@@ -0,0 +1,302 @@
+/// `blameprompt bisect-report <bad> <good>`: provenance of a bisect culprit.
+///
+/// Once `git bisect` has named the first bad commit, this prints, for every
+/// commit in `good..bad` (normally just the culprit), the lines it added and
+/// where each came from, taken from the note's per-line `file_mappings`:
+///
+/// - `AI-accepted`: committed exactly as the AI wrote it;
+/// - `human-edited`: an AI line a human changed before committing;
+/// - `human`: typed by a person;
+/// - `AI (range)`: inside a receipt's line range on notes attached before
+///   per-line classification existed.
+///
+/// followed by the receipts (prompt, model, conversation excerpt) behind them.
+use crate::commands::github;
+use crate::core::receipt::{CodeOrigin, FileMapping, Receipt};
+use crate::core::util;
+use crate::git::notes;
+use std::process::Command;
+
+/// Conversation turns shown per receipt, and characters per turn.
+const EXCERPT_TURNS: usize = 4;
+const EXCERPT_CHARS: usize = 240;
+
+/// git's empty tree, diffed against for root commits.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineOrigin {
+    AiAccepted,
+    HumanEdited,
+    Human,
+    AiRange,
+}
+
+impl LineOrigin {
+    fn label(self) -> &'static str {
+        match self {
+            LineOrigin::AiAccepted => "AI-accepted",
+            LineOrigin::HumanEdited => "human-edited",
+            LineOrigin::Human => "human",
+            LineOrigin::AiRange => "AI (range)",
+        }
+    }
+}
+
+/// Consecutive added lines with the same origin.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LineSpan {
+    pub start: u32,
+    pub end: u32,
+    pub origin: LineOrigin,
+    pub receipt_id: Option<String>,
+}
+
+pub fn run(bad: &str, good: &str) -> Result<(), String> {
+    let bad_sha = resolve(bad)?;
+    resolve(good)?;
+    let commits = github::commits_between(good, &bad_sha);
+    if commits.is_empty() {
+        return Err(format!("No commits in {}..{}", good, bad));
+    }
+    if commits.len() > 1 {
+        println!(
+            "Note: {}..{} spans {} commits; finish the bisect to narrow it to one.\n",
+            good,
+            bad,
+            commits.len()
+        );
+    }
+    for sha in &commits {
+        report_commit(sha);
+    }
+    Ok(())
+}
+
+fn report_commit(sha: &str) {
+    let header =
+        git(&["log", "-1", "--format=%s%n%an, %ad", "--date=short", sha]).unwrap_or_default();
+    let mut lines = header.lines();
+    println!(
+        "Commit {}  {}",
+        util::short_sha(sha),
+        lines.next().unwrap_or("")
+    );
+    println!("  {}", lines.next().unwrap_or(""));
+
+    let payload = notes::read_receipts_for_commit(sha);
+    let receipts: &[Receipt] = payload.as_ref().map_or(&[], |p| &p.receipts);
+    let mappings: &[FileMapping] = payload
+        .as_ref()
+        .and_then(|p| p.file_mappings.as_deref())
+        .unwrap_or(&[]);
+
+    let parent = git(&["rev-parse", "--verify", "--quiet", &format!("{}^", sha)])
+        .unwrap_or_else(|| EMPTY_TREE.to_string());
+    let diff = git(&["diff", "--unified=0", "--no-color", &parent, sha]).unwrap_or_default();
+    let mut added: Vec<(String, Vec<(u32, u32)>)> =
+        github::parse_added_ranges(&diff).into_iter().collect();
+    added.sort();
+
+    let mut totals = [0u32; 4];
+    let mut files = Vec::new();
+    for (path, ranges) in &added {
+        let mapping = mappings.iter().find(|m| util::paths_match(&m.path, path));
+        let spans = classify_lines(path, ranges, mapping, receipts);
+        for s in &spans {
+            totals[s.origin as usize] += s.end - s.start + 1;
+        }
+        files.push((path, spans));
+    }
+    println!(
+        "  Added lines: {} AI-accepted, {} human-edited, {} human{}",
+        totals[LineOrigin::AiAccepted as usize],
+        totals[LineOrigin::HumanEdited as usize],
+        totals[LineOrigin::Human as usize],
+        match totals[LineOrigin::AiRange as usize] {
+            0 => String::new(),
+            n => format!(", {} in AI receipt ranges", n),
+        }
+    );
+    for (path, spans) in &files {
+        println!("  {}", path);
+        for s in spans {
+            let range = if s.start == s.end {
+                s.start.to_string()
+            } else {
+                format!("{}-{}", s.start, s.end)
+            };
+            let receipt = s
+                .receipt_id
+                .as_deref()
+                .map(|id| format!("  receipt {}", util::short_sha(id)))
+                .unwrap_or_default();
+            println!("    {:<11} {}{}", range, s.origin.label(), receipt);
+        }
+    }
+
+    if receipts.is_empty() {
+        println!("  No receipts: no AI session was recorded for this commit.\n");
+        return;
+    }
+    println!("  Receipts:");
+    for r in receipts {
+        println!(
+            "    [{}] {} / {} ({})",
+            util::short_sha(&r.id),
+            r.provider,
+            r.model,
+            r.user
+        );
+        println!(
+            "      Prompt: {}",
+            one_line(&r.prompt_summary, EXCERPT_CHARS)
+        );
+        for turn in r.conversation.iter().flatten().take(EXCERPT_TURNS) {
+            println!(
+                "      {:>9}: {}",
+                turn.role,
+                one_line(&turn.content, EXCERPT_CHARS)
+            );
+        }
+    }
+    println!();
+}
+
+/// Origin of each line a commit added to `path`, from the note's per-line
+/// mapping when there is one, else from the receipts' line ranges.
+pub fn classify_lines(
+    path: &str,
+    added: &[(u32, u32)],
+    mapping: Option<&FileMapping>,
+    receipts: &[Receipt],
+) -> Vec<LineSpan> {
+    let mut spans: Vec<LineSpan> = Vec::new();
+    for &(start, end) in added {
+        for line in start..=end {
+            let (origin, receipt_id) = match mapping {
+                Some(m) => m
+                    .hunks
+                    .iter()
+                    .find(|h| h.start_line <= line && line <= h.end_line)
+                    .map(|h| {
+                        let origin = match h.origin {
+                            CodeOrigin::AiGenerated => LineOrigin::AiAccepted,
+                            CodeOrigin::HumanEdited => LineOrigin::HumanEdited,
+                            CodeOrigin::PureHuman => LineOrigin::Human,
+                        };
+                        (origin, h.receipt_id.clone())
+                    })
+                    .unwrap_or((LineOrigin::Human, None)),
+                None => receipts
+                    .iter()
+                    .find(|r| {
+                        r.all_file_changes().iter().any(|fc| {
+                            util::paths_match(&fc.path, path)
+                                && fc.line_range.0 <= line
+                                && line <= fc.line_range.1
+                        })
+                    })
+                    .map(|r| (LineOrigin::AiRange, Some(r.id.clone())))
+                    .unwrap_or((LineOrigin::Human, None)),
+            };
+            match spans.last_mut() {
+                Some(s)
+                    if s.end + 1 == line && s.origin == origin && s.receipt_id == receipt_id =>
+                {
+                    s.end = line
+                }
+                _ => spans.push(LineSpan {
+                    start: line,
+                    end: line,
+                    origin,
+                    receipt_id,
+                }),
+            }
+        }
+    }
+    spans
+}
+
+fn one_line(text: &str, max: usize) -> String {
+    let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if flat.chars().count() > max {
+        format!("{}…", flat.chars().take(max).collect::<String>())
+    } else {
+        flat
+    }
+}
+
+fn resolve(rev: &str) -> Result<String, String> {
+    git(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("{}^{{commit}}", rev),
+    ])
+    .ok_or_else(|| format!("Cannot resolve commit: {}", rev))
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::Hunk;
+
+    fn hunk(start: u32, end: u32, origin: CodeOrigin, receipt: Option<&str>) -> Hunk {
+        Hunk {
+            start_line: start,
+            end_line: end,
+            origin,
+            prompt_turn: None,
+            model: None,
+            receipt_id: receipt.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_classify_culprit_lines() {
+        let mapping = FileMapping {
+            path: "src/lib.rs".to_string(),
+            blob_hash: "b".to_string(),
+            previous_blob_hash: None,
+            hunks: vec![
+                hunk(10, 14, CodeOrigin::AiGenerated, Some("r1")),
+                hunk(15, 15, CodeOrigin::HumanEdited, Some("r1")),
+                hunk(16, 20, CodeOrigin::PureHuman, None),
+            ],
+        };
+        let spans = classify_lines("src/lib.rs", &[(12, 17), (30, 30)], Some(&mapping), &[]);
+        let summary: Vec<(u32, u32, LineOrigin)> =
+            spans.iter().map(|s| (s.start, s.end, s.origin)).collect();
+        assert_eq!(
+            summary,
+            vec![
+                (12, 14, LineOrigin::AiAccepted),
+                (15, 15, LineOrigin::HumanEdited),
+                (16, 17, LineOrigin::Human),
+                (30, 30, LineOrigin::Human),
+            ]
+        );
+        assert_eq!(spans[0].receipt_id.as_deref(), Some("r1"));
+
+        // Older notes without mappings fall back to the receipt's line range.
+        let mut r = Receipt::test("r2")
+            .with_model("m")
+            .with_timestamp("2026-01-01T00:00:00Z")
+            .with_user("dev");
+        r.file_path = "src/lib.rs".to_string();
+        r.line_range = (5, 6);
+        let spans = classify_lines("src/lib.rs", &[(4, 6)], None, &[r]);
+        assert_eq!(spans.len(), 2);
+        assert_eq!(spans[1].origin, LineOrigin::AiRange);
+        assert_eq!((spans[1].start, spans[1].end), (5, 6));
+    }
+}
@@ -1,9 +1,11 @@
-use crate::core::receipt::{CodeOrigin, CodeOriginStats};
-use crate::core::util;
-use crate::git::notes;
-use comfy_table::{Cell, Color, Table};
-use serde::Serialize;
-use std::collections::HashMap;
+use crate::core::notebook::{self, NotebookCell};
+use crate::core::receipt::{ChangeType, CodeOrigin, CodeOriginStats};
+use crate::core::{symbols, term, util};
+use crate::git::{notes, partial_clone};
+use comfy_table::{Cell, Color};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
 
 #[derive(Serialize)]
 pub struct BlameLineOutput {
@@ -16,6 +18,10 @@ pub struct BlameLineOutput {
     pub prompt_summary: String,
     pub receipt_id: String,
     pub commit_sha: String,
+    /// Attributed from a staged receipt (`--include-uncommitted`); may change
+    /// once the commit is made.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub provisional: bool,
 }
 
 #[derive(Serialize)]
@@ -24,20 +30,103 @@ pub struct BlameOutput {
     pub total_lines: u32,
     pub ai_lines: u32,
     pub ai_pct: f64,
+    /// AI-written lines a human then changed (from attach-time classification).
+    pub edited_lines: u32,
+    pub edited_pct: f64,
     pub human_pct: f64,
+    /// Per-cell attribution, only for Jupyter notebooks.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cells: Option<Vec<BlameCellOutput>>,
+    /// Per-symbol attribution, only with `--by-symbol`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub symbols: Option<Vec<BlameSymbolOutput>>,
     pub lines: Vec<BlameLineOutput>,
 }
 
+#[derive(Serialize)]
+pub struct BlameCellOutput {
+    pub index: usize,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub cell_type: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    pub ai_lines: u32,
+    pub ai_pct: f64,
+    pub models: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct BlameSymbolOutput {
+    pub kind: String,
+    pub name: String,
+    pub start_line: u32,
+    pub end_line: u32,
+    /// "ai" (every line), "partial" or "human".
+    pub status: String,
+    pub ai_lines: u32,
+    pub edited_lines: u32,
+    pub ai_pct: f64,
+    pub models: Vec<String>,
+}
+
+/// Per-file attribution sidecar written by `blame --format sidecar`, for editor
+/// plugins that highlight AI lines without invoking the CLI per file.
+#[derive(Serialize)]
+pub struct SidecarFile {
+    pub version: u32,
+    pub file: String,
+    /// Blob SHA of the content the ranges were computed against; a plugin should
+    /// ignore the sidecar when the buffer no longer hashes to this.
+    pub blob: String,
+    pub total_lines: u32,
+    pub ai_lines: u32,
+    pub ranges: Vec<SidecarRange>,
+}
+
+/// A run of consecutive non-human lines sharing the same receipt.
+#[derive(Serialize, Debug, PartialEq)]
+pub struct SidecarRange {
+    pub start_line: u32,
+    pub end_line: u32,
+    /// "ai" or "edited".
+    pub source: String,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub receipt_id: String,
+    pub commit_sha: String,
+    pub provider: String,
+    pub model: String,
+    pub prompt_snippet: String,
+}
+
+/// `index.json` in the sidecar directory: every file that has a sidecar.
+#[derive(Serialize, Deserialize, Default)]
+pub struct SidecarIndex {
+    pub version: u32,
+    pub generated_at: String,
+    pub files: BTreeMap<String, SidecarIndexEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SidecarIndexEntry {
+    pub blob: String,
+    pub ai_lines: u32,
+    pub sidecar: String,
+}
+
+const SIDECAR_VERSION: u32 = 1;
+
 pub fn calculate_code_origin(file: &str) -> Option<CodeOriginStats> {
-    let file_content = std::fs::read_to_string(file).ok()?;
+    let (file_content, from_head) = partial_clone::read_tracked(file)?;
     let total_lines = file_content.lines().count() as f64;
     if total_lines == 0.0 {
         return None;
     }
 
     // Run git blame to get commit SHAs per line
+    partial_clone::prefetch_history(&[file.to_string()]);
     let blame_output = std::process::Command::new("git")
-        .args(["blame", "--porcelain", file])
+        .args(blame_args(file, from_head))
         .output()
         .ok()?;
 
@@ -47,18 +136,7 @@ pub fn calculate_code_origin(file: &str) -> Option<CodeOriginStats> {
 
     let stdout = String::from_utf8_lossy(&blame_output.stdout);
     let mut line_commits: HashMap<u32, String> = HashMap::new();
-    for line in stdout.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 3
-            && parts[0].len() == 40
-            && parts[0].chars().all(|c| c.is_ascii_hexdigit())
-        {
-            let sha = parts[0].to_string();
-            if let Ok(final_line) = parts[2].parse::<u32>() {
-                line_commits.insert(final_line, sha);
-            }
-        }
-    }
+    let sha_filenames = parse_porcelain(&stdout, &mut line_commits);
 
     // Collect unique SHAs and fetch receipts
     let unique_shas: Vec<String> = {
@@ -92,7 +170,7 @@ pub fn calculate_code_origin(file: &str) -> Option<CodeOriginStats> {
             if let Some(receipts) = sha_receipts.get(sha) {
                 'line: for r in receipts {
                     for fc in r.all_file_changes() {
-                        if (fc.path == file || file.ends_with(&fc.path) || fc.path.ends_with(file))
+                        if path_matches_at(&fc.path, file, sha_filenames.get(sha))
                             && line_num >= fc.line_range.0
                             && line_num <= fc.line_range.1
                         {
@@ -105,14 +183,87 @@ pub fn calculate_code_origin(file: &str) -> Option<CodeOriginStats> {
         }
     }
 
-    let ai_pct = (ai_lines as f64 / total_lines) * 100.0;
-    let human_pct = 100.0 - ai_pct;
+    // Future: compare blob hashes to detect post-AI edits
+    CodeOriginStats::from_lines(ai_lines, 0, line_count - ai_lines)
+}
+
+/// Parse `git blame --porcelain` into `line -> commit` (filled into `line_commits`)
+/// and returns `commit -> path of the file in that commit`, which differs from the
+/// current path when the file was renamed since.
+fn parse_porcelain(
+    output: &str,
+    line_commits: &mut HashMap<u32, String>,
+) -> HashMap<String, String> {
+    let mut filenames = HashMap::new();
+    let mut current_sha = String::new();
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("filename ") {
+            filenames.insert(current_sha.clone(), name.to_string());
+            continue;
+        }
+        let parts: Vec<&str> = line.split_whitespace().collect();
+        if parts.len() >= 3
+            && parts[0].len() == 40
+            && parts[0].chars().all(|c| c.is_ascii_hexdigit())
+        {
+            current_sha = parts[0].to_string();
+            if let Ok(final_line) = parts[2].parse::<u32>() {
+                line_commits.insert(final_line, current_sha.clone());
+            }
+        }
+    }
+    filenames
+}
+
+/// Does a receipt path refer to `file`, either by its current name or by the
+/// name it had in the blamed commit (before a rename)?
+fn path_matches_at(receipt_path: &str, file: &str, name_at_commit: Option<&String>) -> bool {
+    receipt_path == file
+        || file.ends_with(receipt_path)
+        || receipt_path.ends_with(file)
+        || name_at_commit.is_some_and(|orig| util::paths_match(receipt_path, orig))
+}
 
-    Some(CodeOriginStats {
-        ai_generated_pct: ai_pct,
-        human_edited_pct: 0.0, // Future: compare blob hashes to detect post-AI edits
-        pure_human_pct: human_pct,
-    })
+/// For a path that no longer exists, report the commit that deleted it and
+/// whether an AI receipt recorded the deletion.
+fn explain_deleted(file: &str) {
+    let sha = match std::process::Command::new("git")
+        .args(["log", "-1", "--diff-filter=D", "--format=%H", "--", file])
+        .output()
+    {
+        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout).trim().to_string(),
+        _ => return,
+    };
+    if sha.is_empty() {
+        return;
+    }
+
+    let receipt = notes::read_receipts_for_commit(&sha).and_then(|payload| {
+        payload.receipts.into_iter().find(|r| {
+            r.all_file_changes().iter().any(|fc| {
+                fc.change_type == Some(ChangeType::Deleted) && util::paths_match(&fc.path, file)
+            })
+        })
+    });
+    match receipt {
+        Some(r) => eprintln!(
+            "  Deleted in {} by AI ({} / {}): {}",
+            util::short_sha(&sha),
+            r.provider,
+            r.model,
+            r.prompt_summary.chars().take(60).collect::<String>()
+        ),
+        None => eprintln!("  Deleted in {}", util::short_sha(&sha)),
+    }
+}
+
+/// `git blame --porcelain` for `file`, at HEAD when it isn't in the working tree.
+fn blame_args(file: &str, from_head: bool) -> Vec<&str> {
+    if from_head {
+        vec!["blame", "--porcelain", "HEAD", "--", file]
+    } else {
+        vec!["blame", "--porcelain", "--", file]
+    }
 }
 
 struct LineAttribution {
@@ -122,10 +273,21 @@ struct LineAttribution {
     cost_usd: f64,
     prompt_summary: String,
     receipt_id: String,
+    provisional: bool,
 }
 
+/// Line attribution for `file`. With `include_uncommitted`, lines not
+/// committed yet are matched against the staged receipts (provisionally).
 #[allow(clippy::type_complexity)]
-fn compute_blame(file: &str) -> Option<(Vec<String>, HashMap<u32, String>, Vec<LineAttribution>)> {
+fn compute_blame(
+    file: &str,
+    include_uncommitted: bool,
+) -> Option<(
+    Vec<String>,
+    HashMap<u32, String>,
+    Vec<LineAttribution>,
+    Option<Vec<NotebookCell>>,
+)> {
     // Verify file exists and is tracked
     let output = std::process::Command::new("git")
         .args(["ls-files", file])
@@ -134,6 +296,7 @@ fn compute_blame(file: &str) -> Option<(Vec<String>, HashMap<u32, String>, Vec<L
     match &output {
         Ok(o) if o.stdout.is_empty() => {
             eprintln!("Error: '{}' is not tracked by git", file);
+            explain_deleted(file);
             return None;
         }
         Err(_) => {
@@ -143,9 +306,19 @@ fn compute_blame(file: &str) -> Option<(Vec<String>, HashMap<u32, String>, Vec<L
         _ => {}
     }
 
+    // Files outside a sparse checkout are read and blamed at HEAD.
+    let (file_content, from_head) = match partial_clone::read_tracked(file) {
+        Some(c) => c,
+        None => {
+            eprintln!("Error reading file: {}", file);
+            return None;
+        }
+    };
+
     // Run git blame --porcelain
+    partial_clone::prefetch_history(&[file.to_string()]);
     let blame_output = match std::process::Command::new("git")
-        .args(["blame", "--porcelain", file])
+        .args(blame_args(file, from_head))
         .output()
     {
         Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout).to_string(),
@@ -157,18 +330,7 @@ fn compute_blame(file: &str) -> Option<(Vec<String>, HashMap<u32, String>, Vec<L
 
     // Parse porcelain output: build line_number -> commit_sha map
     let mut line_commits: HashMap<u32, String> = HashMap::new();
-    for line in blame_output.lines() {
-        let parts: Vec<&str> = line.split_whitespace().collect();
-        if parts.len() >= 3
-            && parts[0].len() == 40
-            && parts[0].chars().all(|c| c.is_ascii_hexdigit())
-        {
-            let sha = parts[0].to_string();
-            if let Ok(final_line) = parts[2].parse::<u32>() {
-                line_commits.insert(final_line, sha);
-            }
-        }
-    }
+    let sha_filenames = parse_porcelain(&blame_output, &mut line_commits);
 
     // Collect unique SHAs and fetch receipts
     let unique_shas: Vec<String> = {
@@ -189,19 +351,24 @@ fn compute_blame(file: &str) -> Option<(Vec<String>, HashMap<u32, String>, Vec<L
             }
         }
     }
-
-    // Read file lines
-    let file_content = match std::fs::read_to_string(file) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("Error reading file: {}", e);
-            return None;
+    if include_uncommitted {
+        if let Some(sha) = unique_shas.iter().find(|s| util::is_not_committed(s)) {
+            let staged = crate::commands::staging::read_staging().receipts;
+            sha_receipts.insert(sha.clone(), staged);
         }
-    };
+    }
 
     let lines: Vec<String> = file_content.lines().map(|s| s.to_string()).collect();
     let mut attributions = Vec::new();
 
+    // Notebooks: receipts that recorded which cells they touched only claim
+    // lines inside those cells, not the whole raw-JSON diff range.
+    let cells = if notebook::is_notebook(file) {
+        notebook::parse_cells(&file_content)
+    } else {
+        None
+    };
+
     for (idx, _) in lines.iter().enumerate() {
         let line_num = (idx + 1) as u32;
         let commit_sha = line_commits.get(&line_num);
@@ -214,39 +381,51 @@ fn compute_blame(file: &str) -> Option<(Vec<String>, HashMap<u32, String>, Vec<L
         let mut receipt_id = String::new();
 
         if let Some(sha) = commit_sha {
-            // Check file_mappings first for finer granularity
+            // Line-level classification from attach time is authoritative for
+            // the lines it covers; receipt ranges are only a fallback.
+            let mut classified = false;
             if let Some(mappings) = sha_mappings.get(sha) {
-                for fm in mappings {
-                    if util::paths_match(&fm.path, file) {
-                        for h in &fm.hunks {
-                            if line_num >= h.start_line && line_num <= h.end_line {
-                                match h.origin {
-                                    CodeOrigin::AiGenerated => {
-                                        source = "ai".to_string();
-                                        if let Some(ref m) = h.model {
-                                            model = m.clone();
-                                        }
-                                    }
-                                    CodeOrigin::HumanEdited => source = "edited".to_string(),
-                                    CodeOrigin::PureHuman => source = "human".to_string(),
-                                }
-                                break;
-                            }
-                        }
+                let hunk = mappings
+                    .iter()
+                    .filter(|fm| path_matches_at(&fm.path, file, sha_filenames.get(sha)))
+                    .flat_map(|fm| &fm.hunks)
+                    .find(|h| line_num >= h.start_line && line_num <= h.end_line);
+                if let Some(h) = hunk {
+                    classified = true;
+                    source = match h.origin {
+                        CodeOrigin::AiGenerated => "ai",
+                        CodeOrigin::HumanEdited => "edited",
+                        CodeOrigin::PureHuman => "human",
+                    }
+                    .to_string();
+                    if let Some(ref m) = h.model {
+                        model = m.clone();
+                    }
+                    let receipt = h.receipt_id.as_ref().and_then(|id| {
+                        sha_receipts
+                            .get(sha)
+                            .and_then(|rs| rs.iter().find(|r| &r.id == id))
+                    });
+                    if let Some(r) = receipt {
+                        provider = r.provider.clone();
+                        cost_usd = r.cost_usd;
+                        prompt_summary = r.prompt_summary.clone();
+                        receipt_id = r.id.clone();
                     }
                 }
             }
 
             // Fall back to receipt-level matching
-            if source == "human" {
+            if !classified {
                 if let Some(receipts) = sha_receipts.get(sha) {
                     'receipt: for r in receipts {
                         for fc in r.all_file_changes() {
-                            if (fc.path == file
-                                || file.ends_with(&fc.path)
-                                || fc.path.ends_with(file))
+                            if path_matches_at(&fc.path, file, sha_filenames.get(sha))
                                 && line_num >= fc.line_range.0
                                 && line_num <= fc.line_range.1
+                                && cells.as_ref().is_none_or(|c| {
+                                    notebook::line_in_cells(c, &fc.notebook_cells, line_num)
+                                })
                             {
                                 source = "ai".to_string();
                                 provider = r.provider.clone();
@@ -262,6 +441,7 @@ fn compute_blame(file: &str) -> Option<(Vec<String>, HashMap<u32, String>, Vec<L
             }
         }
 
+        let provisional = source == "ai" && commit_sha.is_some_and(|s| util::is_not_committed(s));
         attributions.push(LineAttribution {
             source,
             provider,
@@ -269,36 +449,101 @@ fn compute_blame(file: &str) -> Option<(Vec<String>, HashMap<u32, String>, Vec<L
             cost_usd,
             prompt_summary,
             receipt_id,
+            provisional,
         });
     }
 
-    Some((lines, line_commits, attributions))
+    Some((lines, line_commits, attributions, cells))
 }
 
-pub fn run(file: &str, format: &str) {
-    let (lines, line_commits, attributions) = match compute_blame(file) {
+fn cell_attribution(
+    cells: &[NotebookCell],
+    attributions: &[LineAttribution],
+) -> Vec<notebook::CellAttribution> {
+    notebook::attribute_cells(
+        cells,
+        attributions
+            .iter()
+            .enumerate()
+            .map(|(idx, a)| ((idx + 1) as u32, a.source == "ai", Some(a.model.as_str()))),
+    )
+}
+
+pub fn run(file: &str, format: &str, include_uncommitted: bool, by_symbol: bool) {
+    if by_symbol && !symbols::is_supported(file) {
+        eprintln!(
+            "[BlamePrompt] --by-symbol supports Rust, Python, JavaScript, TypeScript and Go files"
+        );
+        return;
+    }
+    let (lines, line_commits, attributions, cells) = match compute_blame(file, include_uncommitted)
+    {
         Some(data) => data,
         None => return,
     };
+    let symbol_attr = if by_symbol {
+        match symbols::parse_symbols(file, &lines.join("\n")) {
+            Some(syms) => Some(symbol_attribution(&syms, &attributions)),
+            None => {
+                eprintln!("[BlamePrompt] Could not parse {} for symbols", file);
+                return;
+            }
+        }
+    } else {
+        None
+    };
 
     let total_lines = lines.len() as u32;
     let ai_line_count = attributions.iter().filter(|a| a.source == "ai").count() as u32;
+    let edited_count = attributions.iter().filter(|a| a.source == "edited").count() as u32;
+    let pct = |n: u32| {
+        if total_lines > 0 {
+            n as f64 / total_lines as f64 * 100.0
+        } else {
+            0.0
+        }
+    };
+    let cell_attr = cells.map(|c| cell_attribution(&c, &attributions));
 
     if format == "json" {
         let output = BlameOutput {
             file: file.to_string(),
             total_lines,
             ai_lines: ai_line_count,
-            ai_pct: if total_lines > 0 {
-                (ai_line_count as f64 / total_lines as f64) * 100.0
-            } else {
-                0.0
-            },
-            human_pct: if total_lines > 0 {
-                100.0 - (ai_line_count as f64 / total_lines as f64) * 100.0
-            } else {
-                100.0
-            },
+            ai_pct: pct(ai_line_count),
+            edited_lines: edited_count,
+            edited_pct: pct(edited_count),
+            human_pct: 100.0 - pct(ai_line_count) - pct(edited_count),
+            cells: cell_attr.map(|cells| {
+                cells
+                    .into_iter()
+                    .map(|ca| BlameCellOutput {
+                        index: ca.cell.index,
+                        ai_pct: ca.ai_pct(),
+                        id: ca.cell.id,
+                        cell_type: ca.cell.cell_type,
+                        start_line: ca.cell.source_lines.0,
+                        end_line: ca.cell.source_lines.1,
+                        ai_lines: ca.ai_lines,
+                        models: ca.models,
+                    })
+                    .collect()
+            }),
+            symbols: symbol_attr.map(|syms| {
+                syms.into_iter()
+                    .map(|sa| BlameSymbolOutput {
+                        status: sa.status().to_string(),
+                        ai_pct: sa.ai_pct(),
+                        kind: sa.symbol.kind,
+                        name: sa.symbol.name,
+                        start_line: sa.symbol.start_line,
+                        end_line: sa.symbol.end_line,
+                        ai_lines: sa.ai_lines,
+                        edited_lines: sa.edited_lines,
+                        models: sa.models,
+                    })
+                    .collect()
+            }),
             lines: lines
                 .iter()
                 .enumerate()
@@ -315,6 +560,7 @@ pub fn run(file: &str, format: &str) {
                         prompt_summary: attr.prompt_summary.clone(),
                         receipt_id: attr.receipt_id.clone(),
                         commit_sha: line_commits.get(&line_num).cloned().unwrap_or_default(),
+                        provisional: attr.provisional,
                     }
                 })
                 .collect(),
@@ -323,8 +569,18 @@ pub fn run(file: &str, format: &str) {
         return;
     }
 
+    if let Some(cells) = cell_attr {
+        print_cell_table(&cells);
+        return;
+    }
+
+    if let Some(syms) = symbol_attr {
+        print_symbol_table(&syms);
+        return;
+    }
+
     // Table output (default)
-    let mut table = Table::new();
+    let mut table = term::table();
     table.set_header(vec![
         "Line", "Code", "Source", "Provider", "Model", "Cost", "Prompt",
     ]);
@@ -335,6 +591,7 @@ pub fn run(file: &str, format: &str) {
         let attr = &attributions[idx];
 
         let source_display = match attr.source.as_str() {
+            "ai" if attr.provisional => "AI*",
             "ai" => "AI",
             "edited" => "Edited",
             _ => "Human",
@@ -357,7 +614,7 @@ pub fn run(file: &str, format: &str) {
         table.add_row(vec![
             Cell::new(line_num),
             Cell::new(&code),
-            Cell::new(source_display).fg(source_color),
+            term::colored(source_display, source_color),
             Cell::new(&attr.provider),
             Cell::new(&attr.model),
             Cell::new(&cost_display),
@@ -366,15 +623,348 @@ pub fn run(file: &str, format: &str) {
     }
 
     println!("{table}");
+    let provisional = attributions.iter().filter(|a| a.provisional).count();
+    if provisional > 0 {
+        println!(
+            "* {} uncommitted line(s) attributed from staged receipts: provisional until committed",
+            provisional
+        );
+    }
 
     // Show code origin summary
     if total_lines > 0 {
-        let ai_pct = (ai_line_count as f64 / total_lines as f64) * 100.0;
-        let human_pct = 100.0 - ai_pct;
+        let ai_pct = pct(ai_line_count);
+        let edited_pct = pct(edited_count);
+        println!();
+        if edited_count > 0 {
+            println!(
+                "Code Origin: {:.1}% AI-generated, {:.1}% AI then human-edited, {:.1}% human",
+                ai_pct,
+                edited_pct,
+                100.0 - ai_pct - edited_pct
+            );
+        } else {
+            println!(
+                "Code Origin: {:.1}% AI-generated, {:.1}% human",
+                ai_pct,
+                100.0 - ai_pct
+            );
+        }
+    }
+}
+
+/// Write attribution sidecars to `out_dir`: `<path>.json` for each file with AI
+/// lines plus an `index.json`. With `file`, only that file's sidecar is refreshed;
+/// otherwise every tracked file referenced by a receipt is processed and
+/// sidecars for files that no longer have AI lines are removed.
+pub fn export_sidecars(file: Option<&str>, out_dir: &str) {
+    let candidates: Vec<String> = match file {
+        Some(f) => vec![f.to_string()],
+        None => sidecar_candidates(),
+    };
+    partial_clone::prefetch_history(&candidates);
+
+    let out = Path::new(out_dir);
+    if let Err(e) = std::fs::create_dir_all(out) {
+        eprintln!("[BlamePrompt] Cannot create {}: {}", out_dir, e);
+        std::process::exit(1);
+    }
+    // Sidecars are derived data; keep them out of `git add -A`.
+    let gitignore = out.join(".gitignore");
+    if !gitignore.exists() {
+        let _ = std::fs::write(&gitignore, "*\n");
+    }
+
+    let index_path = out.join("index.json");
+    let mut index: SidecarIndex = std::fs::read_to_string(&index_path)
+        .ok()
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default();
+    let previous: HashSet<String> = if file.is_none() {
+        std::mem::take(&mut index.files).into_keys().collect()
+    } else {
+        HashSet::new()
+    };
+
+    let mut written = 0;
+    for path in &candidates {
+        let Some((lines, line_commits, attributions, _)) = compute_blame(path, false) else {
+            continue;
+        };
+        let ranges = sidecar_ranges(&line_commits, &attributions);
+        let sidecar_rel = format!("{}.json", path);
+        if ranges.is_empty() {
+            if index.files.remove(path).is_some() {
+                let _ = std::fs::remove_file(out.join(&sidecar_rel));
+            }
+            continue;
+        }
+
+        let sidecar = SidecarFile {
+            version: SIDECAR_VERSION,
+            file: path.clone(),
+            blob: hash_object(path).unwrap_or_default(),
+            total_lines: lines.len() as u32,
+            ai_lines: attributions.iter().filter(|a| a.source == "ai").count() as u32,
+            ranges,
+        };
+        let target = out.join(&sidecar_rel);
+        if let Some(parent) = target.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        match std::fs::write(&target, serde_json::to_string_pretty(&sidecar).unwrap()) {
+            Ok(()) => {
+                index.files.insert(
+                    path.clone(),
+                    SidecarIndexEntry {
+                        blob: sidecar.blob,
+                        ai_lines: sidecar.ai_lines,
+                        sidecar: sidecar_rel,
+                    },
+                );
+                written += 1;
+            }
+            Err(e) => eprintln!("[BlamePrompt] Cannot write {}: {}", target.display(), e),
+        }
+    }
+
+    for stale in previous.iter().filter(|p| !index.files.contains_key(*p)) {
+        let _ = std::fs::remove_file(out.join(format!("{}.json", stale)));
+    }
+
+    index.version = SIDECAR_VERSION;
+    index.generated_at = chrono::Utc::now().to_rfc3339();
+    if let Err(e) = std::fs::write(&index_path, serde_json::to_string_pretty(&index).unwrap()) {
+        eprintln!("[BlamePrompt] Cannot write {}: {}", index_path.display(), e);
+        std::process::exit(1);
+    }
+    println!(
+        "[BlamePrompt] Wrote {} attribution sidecar(s) to {} ({} file(s) checked)",
+        written,
+        out_dir,
+        candidates.len()
+    );
+}
+
+/// `(ai_lines, total_lines)` of a tracked file, attributed the same way as `blame`.
+pub fn ai_line_count(file: &str) -> Option<(u32, u32)> {
+    let (lines, _, attributions, _) = compute_blame(file, false)?;
+    let ai = attributions.iter().filter(|a| a.source == "ai").count() as u32;
+    Some((ai, lines.len() as u32))
+}
+
+/// Tracked files that some receipt points at. Files no receipt mentions can't
+/// have AI lines, so this skips running `git blame` over the whole tree.
+pub(crate) fn sidecar_candidates() -> Vec<String> {
+    let mut receipt_paths: HashSet<String> = HashSet::new();
+    for sha in notes::list_commits_with_notes() {
+        let Some(payload) = notes::read_receipts_for_commit(&sha) else {
+            continue;
+        };
+        for r in &payload.receipts {
+            receipt_paths.extend(r.all_file_changes().into_iter().map(|fc| fc.path.clone()));
+        }
+        for fm in payload.file_mappings.unwrap_or_default() {
+            receipt_paths.insert(fm.path);
+        }
+    }
+
+    let tracked = std::process::Command::new("git")
+        .args(["ls-files"])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+    tracked
+        .lines()
+        .filter(|f| receipt_paths.iter().any(|rp| path_matches_at(rp, f, None)))
+        .map(String::from)
+        .collect()
+}
+
+/// Collapse per-line attribution into ranges of consecutive non-human lines
+/// that share a source, receipt, model and commit.
+fn sidecar_ranges(
+    line_commits: &HashMap<u32, String>,
+    attributions: &[LineAttribution],
+) -> Vec<SidecarRange> {
+    let mut ranges: Vec<SidecarRange> = Vec::new();
+    for (idx, attr) in attributions.iter().enumerate() {
+        if attr.source == "human" {
+            continue;
+        }
+        let line = (idx + 1) as u32;
+        let commit_sha = line_commits.get(&line).cloned().unwrap_or_default();
+        if let Some(last) = ranges.last_mut() {
+            if last.end_line + 1 == line
+                && last.source == attr.source
+                && last.receipt_id == attr.receipt_id
+                && last.model == attr.model
+                && last.commit_sha == commit_sha
+            {
+                last.end_line = line;
+                continue;
+            }
+        }
+        ranges.push(SidecarRange {
+            start_line: line,
+            end_line: line,
+            source: attr.source.clone(),
+            receipt_id: attr.receipt_id.clone(),
+            commit_sha,
+            provider: attr.provider.clone(),
+            model: attr.model.clone(),
+            prompt_snippet: attr.prompt_summary.chars().take(200).collect(),
+        });
+    }
+    ranges
+}
+
+fn hash_object(file: &str) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["hash-object", "--", file])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Notebook table output: one row per cell instead of one per raw JSON line.
+fn symbol_attribution(
+    syms: &[symbols::Symbol],
+    attributions: &[LineAttribution],
+) -> Vec<symbols::SymbolAttribution> {
+    symbols::attribute_symbols(
+        syms,
+        attributions
+            .iter()
+            .enumerate()
+            .map(|(idx, a)| ((idx + 1) as u32, a.source.as_str(), Some(a.model.as_str()))),
+    )
+}
+
+fn print_symbol_table(syms: &[symbols::SymbolAttribution]) {
+    let mut table = term::table();
+    table.set_header(vec![
+        "Symbol", "Kind", "Lines", "AI Lines", "Origin", "Model",
+    ]);
+
+    for sa in syms {
+        let (label, color) = match sa.status() {
+            "ai" => ("AI".to_string(), Color::Yellow),
+            "partial" => (format!("Partial ({:.0}%)", sa.ai_pct()), Color::Cyan),
+            _ => ("Human".to_string(), Color::Green),
+        };
+        table.add_row(vec![
+            Cell::new(&sa.symbol.name),
+            Cell::new(&sa.symbol.kind),
+            Cell::new(format!("{}-{}", sa.symbol.start_line, sa.symbol.end_line)),
+            Cell::new(format!("{}/{}", sa.ai_lines, sa.total_lines)),
+            term::colored(label, color),
+            Cell::new(sa.models.join(", ")),
+        ]);
+    }
+
+    println!("{table}");
+
+    if !syms.is_empty() {
+        let ai = syms.iter().filter(|s| s.status() == "ai").count();
+        let partial = syms.iter().filter(|s| s.status() == "partial").count();
         println!();
         println!(
-            "Code Origin: {:.1}% AI-generated, {:.1}% human",
-            ai_pct, human_pct
+            "Code Origin: {} of {} symbols AI-generated, {} partially",
+            ai,
+            syms.len(),
+            partial
+        );
+    }
+}
+
+fn print_cell_table(cells: &[notebook::CellAttribution]) {
+    let mut table = term::table();
+    table.set_header(vec![
+        "Cell", "Id", "Type", "Lines", "AI Lines", "AI %", "Model",
+    ]);
+
+    for ca in cells {
+        let pct = ca.ai_pct();
+        let color = if ca.ai_lines == 0 {
+            Color::Green
+        } else if ca.ai_lines == ca.total_lines {
+            Color::Yellow
+        } else {
+            Color::Cyan
+        };
+        table.add_row(vec![
+            Cell::new(ca.cell.index),
+            Cell::new(ca.cell.id.as_deref().unwrap_or("")),
+            Cell::new(&ca.cell.cell_type),
+            Cell::new(format!(
+                "{}-{}",
+                ca.cell.source_lines.0, ca.cell.source_lines.1
+            )),
+            Cell::new(format!("{}/{}", ca.ai_lines, ca.total_lines)),
+            term::colored(format!("{:.0}%", pct), color),
+            Cell::new(ca.models.join(", ")),
+        ]);
+    }
+
+    println!("{table}");
+
+    let total: u32 = cells.iter().map(|c| c.total_lines).sum();
+    let ai: u32 = cells.iter().map(|c| c.ai_lines).sum();
+    if total > 0 {
+        let ai_pct = (ai as f64 / total as f64) * 100.0;
+        let ai_cells = cells.iter().filter(|c| c.ai_lines > 0).count();
+        println!();
+        println!(
+            "Code Origin: {}/{} cells touched by AI, {:.1}% of cell source AI-generated",
+            ai_cells,
+            cells.len(),
+            ai_pct
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attr(source: &str, receipt_id: &str) -> LineAttribution {
+        LineAttribution {
+            source: source.to_string(),
+            provider: "claude".to_string(),
+            model: "claude-sonnet-4-6".to_string(),
+            cost_usd: 0.0,
+            prompt_summary: "add parser".to_string(),
+            receipt_id: receipt_id.to_string(),
+            provisional: false,
+        }
+    }
+
+    #[test]
+    fn test_sidecar_ranges_merge_consecutive_lines() {
+        let attributions = vec![
+            attr("human", ""),
+            attr("ai", "r1"),
+            attr("ai", "r1"),
+            attr("ai", "r2"),
+            attr("human", ""),
+            attr("ai", "r2"),
+            attr("edited", ""),
+        ];
+        let line_commits: HashMap<u32, String> = (1..=7).map(|l| (l, "c".repeat(40))).collect();
+        let ranges = sidecar_ranges(&line_commits, &attributions);
+        let spans: Vec<_> = ranges
+            .iter()
+            .map(|r| (r.start_line, r.end_line, r.receipt_id.as_str()))
+            .collect();
+        assert_eq!(
+            spans,
+            vec![(2, 3, "r1"), (4, 4, "r2"), (6, 6, "r2"), (7, 7, "")]
         );
+        assert_eq!(ranges[3].source, "edited");
+        assert_eq!(ranges[0].prompt_snippet, "add parser");
     }
 }
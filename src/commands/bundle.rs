@@ -0,0 +1,366 @@
+/// Portable receipt bundles.
+///
+/// `bundle export` writes every stored receipt note (and, with
+/// `--include-cache`, the matching SQLite cache rows) to one gzip-compressed JSON
+/// file. `bundle import` restores it into another clone. Each entry also records
+/// the commit's author time and subject, so notes can be re-attached after a repo
+/// is re-created with different commit SHAs.
+use crate::core::db;
+use crate::core::migrate;
+use crate::core::receipt::NotePayload;
+use crate::git::{notes, storage};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::process::Command;
+
+const BUNDLE_FORMAT: &str = "blameprompt-bundle";
+const BUNDLE_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Bundle {
+    pub format: String,
+    pub version: u32,
+    pub created_at: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_remote: Option<String>,
+    pub entries: Vec<BundleEntry>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BundleEntry {
+    pub commit: String,
+    /// Author time (unix seconds) and subject, used to find the commit again
+    /// when its SHA changed.
+    pub author_time: i64,
+    pub subject: String,
+    /// The note exactly as stored, so fields from newer versions survive the trip.
+    pub note: serde_json::Value,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cache_rows: Vec<serde_json::Map<String, serde_json::Value>>,
+}
+
+pub fn export(path: &str, include_cache: bool) {
+    let commits = notes::list_commits_with_notes();
+    if commits.is_empty() {
+        eprintln!("[BlamePrompt] No receipts found; nothing to export.");
+        std::process::exit(1);
+    }
+
+    let conn = if include_cache {
+        match db::get_connection() {
+            Ok(c) => Some(c),
+            Err(e) => {
+                eprintln!(
+                    "[BlamePrompt] Cannot open cache, exporting notes only: {}",
+                    e
+                );
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    let meta = commit_metadata(&["--all"]);
+    let mut entries = Vec::new();
+    for sha in &commits {
+        let Some(note) = read_raw_note(sha) else {
+            continue;
+        };
+        let (author_time, subject) = meta.get(sha).cloned().unwrap_or_default();
+        let cache_rows = conn
+            .as_ref()
+            .and_then(|c| db::cached_rows_for_commit(c, sha).ok())
+            .unwrap_or_default();
+        entries.push(BundleEntry {
+            commit: sha.clone(),
+            author_time,
+            subject,
+            note,
+            cache_rows,
+        });
+    }
+
+    let bundle = Bundle {
+        format: BUNDLE_FORMAT.to_string(),
+        version: BUNDLE_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        source_remote: git_output(&["remote", "get-url", "origin"]),
+        entries,
+    };
+
+    let bytes = match encode(&bundle) {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("[BlamePrompt] Failed to encode bundle: {}", e);
+            std::process::exit(1);
+        }
+    };
+    if let Err(e) = std::fs::write(path, &bytes) {
+        eprintln!("[BlamePrompt] Failed to write {}: {}", path, e);
+        std::process::exit(1);
+    }
+    let cached: usize = bundle.entries.iter().map(|e| e.cache_rows.len()).sum();
+    println!(
+        "[BlamePrompt] Exported notes for {} commit(s){} to {} ({} bytes)",
+        bundle.entries.len(),
+        if include_cache {
+            format!(" and {} cache row(s)", cached)
+        } else {
+            String::new()
+        },
+        path,
+        bytes.len()
+    );
+}
+
+pub fn import(path: &str, dry_run: bool) {
+    let bundle = match std::fs::read(path)
+        .map_err(|e| e.to_string())
+        .and_then(|b| decode(&b))
+    {
+        Ok(b) => b,
+        Err(e) => {
+            eprintln!("[BlamePrompt] Cannot read bundle {}: {}", path, e);
+            std::process::exit(1);
+        }
+    };
+
+    let meta = commit_metadata(&["--all"]);
+    let by_identity = identity_index(&meta);
+    let conn = if bundle.entries.iter().any(|e| !e.cache_rows.is_empty()) && !dry_run {
+        db::get_connection().ok()
+    } else {
+        None
+    };
+
+    let (mut imported, mut merged, mut remapped, mut skipped) = (0, 0, 0, 0);
+    for entry in &bundle.entries {
+        let Some(target) = resolve_target(entry, &meta, &by_identity) else {
+            skipped += 1;
+            continue;
+        };
+        let existing = notes::read_receipts_for_commit(&target);
+        let counter = if existing.is_some() {
+            &mut merged
+        } else {
+            &mut imported
+        };
+        if dry_run {
+            *counter += 1;
+            remapped += (target != entry.commit) as usize;
+            continue;
+        }
+
+        let result = match existing {
+            None => write_raw_note(&target, &entry.note),
+            Some(local) => match migrate::from_value(entry.note.clone()) {
+                Ok(incoming) => notes::write_receipts_for_commit(&target, &merge(local, incoming)),
+                Err(e) => Err(format!("unreadable note in bundle: {}", e)),
+            },
+        };
+        if let Err(e) = result {
+            eprintln!(
+                "[BlamePrompt] {}: {}",
+                crate::core::util::short_sha(&target),
+                e
+            );
+            continue;
+        }
+        *counter += 1;
+        remapped += (target != entry.commit) as usize;
+        if let Some(c) = &conn {
+            let _ = db::write(c, |tx| {
+                for row in &entry.cache_rows {
+                    db::insert_cached_row(tx, &target, row)?;
+                }
+                Ok(())
+            });
+        }
+    }
+
+    println!(
+        "[BlamePrompt] {}{} new, {} merged into existing notes, {} re-attached to rewritten commits, {} skipped (commit not found)",
+        if dry_run { "Dry run: " } else { "" },
+        imported,
+        merged,
+        remapped,
+        skipped
+    );
+    if remapped > 0 && !dry_run {
+        println!("  Re-attached notes were matched by author time and subject; spot-check with `blameprompt show`.");
+    }
+}
+
+/// The local commit an entry belongs to: the same SHA if it exists, otherwise a
+/// unique commit with the same author time and subject.
+fn resolve_target(
+    entry: &BundleEntry,
+    meta: &HashMap<String, (i64, String)>,
+    by_identity: &HashMap<(i64, String), String>,
+) -> Option<String> {
+    if meta.contains_key(&entry.commit) {
+        return Some(entry.commit.clone());
+    }
+    if entry.subject.is_empty() {
+        return None;
+    }
+    by_identity
+        .get(&(entry.author_time, entry.subject.clone()))
+        .cloned()
+}
+
+/// (author time, subject) → SHA, leaving out identities shared by several
+/// commits so a note is never re-attached to a guess.
+fn identity_index(meta: &HashMap<String, (i64, String)>) -> HashMap<(i64, String), String> {
+    let mut index: HashMap<(i64, String), Option<String>> = HashMap::new();
+    for (sha, identity) in meta {
+        index
+            .entry(identity.clone())
+            .and_modify(|v| *v = None)
+            .or_insert_with(|| Some(sha.clone()));
+    }
+    index
+        .into_iter()
+        .filter_map(|(k, v)| v.map(|sha| (k, sha)))
+        .collect()
+}
+
+/// Union of receipts by id; local receipts win on conflict.
+fn merge(mut local: NotePayload, incoming: NotePayload) -> NotePayload {
+    for r in incoming.receipts {
+        if !local.receipts.iter().any(|l| l.id == r.id) {
+            local.receipts.push(r);
+        }
+    }
+    if local.file_mappings.is_none() {
+        local.file_mappings = incoming.file_mappings;
+    }
+    if local.code_origin.is_none() {
+        local.code_origin = incoming.code_origin;
+    }
+    if local.pull_request.is_none() {
+        local.pull_request = incoming.pull_request;
+    }
+    local
+}
+
+fn encode(bundle: &Bundle) -> Result<Vec<u8>, String> {
+    let json = serde_json::to_vec(bundle).map_err(|e| e.to_string())?;
+    let mut gz = GzEncoder::new(Vec::new(), Compression::best());
+    gz.write_all(&json).map_err(|e| e.to_string())?;
+    gz.finish().map_err(|e| e.to_string())
+}
+
+fn decode(bytes: &[u8]) -> Result<Bundle, String> {
+    let mut json = Vec::new();
+    GzDecoder::new(bytes)
+        .read_to_end(&mut json)
+        .map_err(|e| format!("not a gzip archive ({})", e))?;
+    let bundle: Bundle = serde_json::from_slice(&json).map_err(|e| e.to_string())?;
+    if bundle.format != BUNDLE_FORMAT {
+        return Err(format!("unexpected format '{}'", bundle.format));
+    }
+    if bundle.version > BUNDLE_VERSION {
+        return Err(format!(
+            "bundle version {} is newer than this blameprompt supports ({}); upgrade first",
+            bundle.version, BUNDLE_VERSION
+        ));
+    }
+    Ok(bundle)
+}
+
+/// Map of commit SHA → (author time, subject) for every commit reachable from `revs`.
+fn commit_metadata(revs: &[&str]) -> HashMap<String, (i64, String)> {
+    let mut args = vec!["log", "--format=%H%x00%at%x00%s"];
+    args.extend_from_slice(revs);
+    git_output(&args)
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\0');
+            let sha = parts.next()?.to_string();
+            let time = parts.next()?.parse().ok()?;
+            let subject = parts.next().unwrap_or_default().to_string();
+            Some((sha, (time, subject)))
+        })
+        .collect()
+}
+
+fn read_raw_note(sha: &str) -> Option<serde_json::Value> {
+    serde_json::from_str(&storage::active().read(sha)?).ok()
+}
+
+fn write_raw_note(sha: &str, note: &serde_json::Value) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(note).map_err(|e| e.to_string())?;
+    storage::active().write(sha, &json)
+}
+
+fn git_output(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(commit: &str, time: i64, subject: &str) -> BundleEntry {
+        BundleEntry {
+            commit: commit.to_string(),
+            author_time: time,
+            subject: subject.to_string(),
+            note: serde_json::json!({"blameprompt_version": "1.0.1", "receipts": []}),
+            cache_rows: vec![],
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_roundtrip() {
+        let bundle = Bundle {
+            format: BUNDLE_FORMAT.to_string(),
+            version: BUNDLE_VERSION,
+            created_at: "2026-01-01T00:00:00Z".to_string(),
+            source_remote: None,
+            entries: vec![entry("abc", 1700000000, "Add parser")],
+        };
+        let bytes = encode(&bundle).unwrap();
+        assert_eq!(&bytes[..2], &[0x1f, 0x8b]);
+        let back = decode(&bytes).unwrap();
+        assert_eq!(back.entries.len(), 1);
+        assert_eq!(back.entries[0].subject, "Add parser");
+        assert!(decode(b"not gzip").is_err());
+    }
+
+    #[test]
+    fn test_resolve_target_remaps_rewritten_commits() {
+        let mut meta = HashMap::new();
+        meta.insert("new1".to_string(), (1700000000, "Add parser".to_string()));
+        meta.insert("same".to_string(), (1700000100, "Fix typo".to_string()));
+        meta.insert("dup1".to_string(), (1700000200, "WIP".to_string()));
+        meta.insert("dup2".to_string(), (1700000200, "WIP".to_string()));
+        let by_identity = identity_index(&meta);
+
+        let same = entry("same", 1700000100, "Fix typo");
+        assert_eq!(
+            resolve_target(&same, &meta, &by_identity).as_deref(),
+            Some("same")
+        );
+        let rewritten = entry("old1", 1700000000, "Add parser");
+        assert_eq!(
+            resolve_target(&rewritten, &meta, &by_identity).as_deref(),
+            Some("new1")
+        );
+        let gone = entry("old2", 1600000000, "Removed");
+        assert!(resolve_target(&gone, &meta, &by_identity).is_none());
+        let ambiguous = entry("old3", 1700000200, "WIP");
+        assert!(resolve_target(&ambiguous, &meta, &by_identity).is_none());
+    }
+}
@@ -0,0 +1,154 @@
+/// Capture kill-switch.
+///
+/// Capture is off when any of these hold, checked in order:
+/// `BLAMEPROMPT_DISABLE=1`, `git config blameprompt.enabled false` (set per repo
+/// or globally by `blameprompt disable [--global]`), `capture.enabled = false`
+/// in `.blamepromptrc`, or the repo root matching `capture.exclude_repos`.
+/// The checkpoint entry point consults this before touching staging.
+use crate::core::config::{self, CaptureConfig};
+use std::path::Path;
+use std::process::Command;
+
+const GIT_CONFIG_KEY: &str = "blameprompt.enabled";
+
+/// Why capture is disabled for `cwd`, or None when it is enabled.
+pub fn disabled_reason(cwd: &str) -> Option<String> {
+    if let Ok(v) = std::env::var("BLAMEPROMPT_DISABLE") {
+        if !matches!(v.as_str(), "" | "0" | "false") {
+            return Some("BLAMEPROMPT_DISABLE is set".to_string());
+        }
+    }
+    if git(cwd, &["config", "--bool", GIT_CONFIG_KEY]).as_deref() == Some("false") {
+        return Some(format!("git config {} is false", GIT_CONFIG_KEY));
+    }
+    let cfg = config::load_config().capture;
+    if !cfg.enabled {
+        return Some("capture.enabled = false in .blamepromptrc".to_string());
+    }
+    let root = git(cwd, &["rev-parse", "--show-toplevel"])?;
+    excluded_by(&cfg, &root).map(|p| format!("repo matches capture.exclude_repos \"{}\"", p))
+}
+
+/// `blameprompt disable`: turn capture off for this repo (or everywhere).
+pub fn disable(global: bool) {
+    set_enabled(global, false);
+}
+
+/// `blameprompt enable`: turn capture back on. In a repo this overrides a
+/// global `disable`.
+pub fn enable(global: bool) {
+    set_enabled(global, true);
+}
+
+fn set_enabled(global: bool, enabled: bool) {
+    let scope = if global { "--global" } else { "--local" };
+    let value = if enabled { "true" } else { "false" };
+    let status = Command::new("git")
+        .args(["config", scope, GIT_CONFIG_KEY, value])
+        .status();
+    if !matches!(status, Ok(s) if s.success()) {
+        eprintln!(
+            "[BlamePrompt] Failed to set {}{}",
+            GIT_CONFIG_KEY,
+            if global {
+                ""
+            } else {
+                " (run inside a git repository or pass --global)"
+            }
+        );
+        std::process::exit(1);
+    }
+
+    let target = if global {
+        "all repositories"
+    } else {
+        "this repository"
+    };
+    if enabled {
+        println!("[BlamePrompt] Capture enabled for {}", target);
+        if let Some(reason) = disabled_reason(".") {
+            println!("  Still disabled here: {}", reason);
+        }
+    } else {
+        println!(
+            "[BlamePrompt] Capture disabled for {}. Run `blameprompt enable{}` to resume.",
+            target,
+            if global { " --global" } else { "" }
+        );
+    }
+}
+
+/// First `exclude_repos` pattern matching the repo root.
+fn excluded_by<'a>(cfg: &'a CaptureConfig, root: &str) -> Option<&'a str> {
+    let home = dirs::home_dir().map(|h| h.to_string_lossy().to_string());
+    cfg.exclude_repos
+        .iter()
+        .find(|pattern| {
+            let expanded = match (pattern.strip_prefix("~/"), &home) {
+                (Some(rest), Some(home)) => format!("{}/{}", home, rest),
+                _ => pattern.to_string(),
+            };
+            path_matches(expanded.trim_end_matches('/'), root)
+        })
+        .map(String::as_str)
+}
+
+/// `*` matches any run of characters (including `/`); a pattern without `*`
+/// matches that directory and everything below it.
+fn path_matches(pattern: &str, path: &str) -> bool {
+    if !pattern.contains('*') {
+        return Path::new(path).starts_with(pattern);
+    }
+    let parts: Vec<&str> = pattern.split('*').collect();
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !path.starts_with(first) || path.len() < first.len() + last.len() {
+        return false;
+    }
+    let mut rest = &path[first.len()..];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    rest.ends_with(last)
+}
+
+fn git(cwd: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_path_matches() {
+        assert!(path_matches("/work/secret", "/work/secret"));
+        assert!(path_matches("/work/secret", "/work/secret/api"));
+        assert!(!path_matches("/work/secret", "/work/secrets"));
+        assert!(path_matches("/work/*-private", "/work/billing-private"));
+        assert!(!path_matches("/work/*-private", "/work/billing"));
+        assert!(path_matches("*/client-*", "/home/dev/client-acme"));
+        assert!(!path_matches("/a/*b*c", "/a/xc"));
+    }
+
+    #[test]
+    fn test_excluded_by_config() {
+        let cfg = CaptureConfig {
+            exclude_repos: vec!["/srv/vendor".to_string(), "*/scratch-*".to_string()],
+            ..CaptureConfig::default()
+        };
+        assert_eq!(excluded_by(&cfg, "/srv/vendor/lib"), Some("/srv/vendor"));
+        assert_eq!(excluded_by(&cfg, "/home/me/scratch-1"), Some("*/scratch-*"));
+        assert_eq!(excluded_by(&cfg, "/home/me/app"), None);
+    }
+}
@@ -1,13 +1,19 @@
+use crate::commands::staging;
 /// Check AI provenance for a file or specific line.
 ///
 /// Cross-references `git blame` (line → commit SHA) with blameprompt git notes
 /// (commit SHA → receipts) to show which lines are AI-generated, by which model,
 /// and which receipt they belong to.
-use crate::core::util;
+use crate::core::db;
+use crate::core::notebook::{self, NotebookCell};
+use crate::core::receipt::{NotePayload, Receipt};
+use crate::core::{term, util};
 use crate::git::notes::read_receipts_for_commit;
+use crate::git::storage;
+use serde::{Deserialize, Serialize};
 use std::process::Command;
 
-#[derive(Debug)]
+#[derive(Debug, Serialize, Deserialize)]
 pub struct LineProvenance {
     pub line_number: u32,
     pub content: String,
@@ -35,8 +41,18 @@ pub struct ProvenanceRange {
 }
 
 /// Run provenance check for a file, optionally filtered to a single line.
-pub fn run(file: &str, line_number: Option<u32>) {
-    let provenance = match compute_provenance(file) {
+/// `use_cache` serves results from the per-blob SQLite cache when possible.
+/// `include_uncommitted` matches lines not committed yet against the staged
+/// receipts; those results are provisional and never cached.
+pub fn run(file: &str, line_number: Option<u32>, use_cache: bool, include_uncommitted: bool) {
+    let provenance = if include_uncommitted {
+        provenance_with_staged(file, &staging::read_staging().receipts)
+    } else if use_cache {
+        cached_provenance(file)
+    } else {
+        compute_provenance(file)
+    };
+    let provenance = match provenance {
         Some(p) => p,
         None => {
             eprintln!("[blameprompt] Cannot compute provenance for '{}'. Is this a tracked file in a git repository?", file);
@@ -48,9 +64,25 @@ pub fn run(file: &str, line_number: Option<u32>) {
         // Show single line
         let entry = provenance.iter().find(|p| p.line_number == ln);
         match entry {
-            Some(p) => print_single_line(p),
+            Some(p) => {
+                print_single_line(p);
+                if let Some(cell) = notebook_cells(file)
+                    .and_then(|cells| cells.into_iter().find(|c| c.contains_line(ln)))
+                {
+                    println!(
+                        "  Cell:   {} (index {}, {})",
+                        cell.label(),
+                        cell.index,
+                        cell.cell_type
+                    );
+                    println!();
+                }
+            }
             None => eprintln!("[blameprompt] Line {} not found in '{}'", ln, file),
         }
+    } else if let Some(cells) = notebook_cells(file) {
+        // Notebooks: summarise per cell rather than per raw JSON line range
+        print_cells(file, &cells, &provenance);
     } else {
         // Show range summary
         let ranges = collapse_to_ranges(&provenance);
@@ -58,8 +90,66 @@ pub fn run(file: &str, line_number: Option<u32>) {
     }
 }
 
+fn notebook_cells(file: &str) -> Option<Vec<NotebookCell>> {
+    if !notebook::is_notebook(file) {
+        return None;
+    }
+    std::fs::read_to_string(file)
+        .ok()
+        .and_then(|raw| notebook::parse_cells(&raw))
+}
+
+/// Line provenance for `file`, served from the SQLite cache when the file's
+/// content (blob SHA), the last commit touching it and the stored receipts are
+/// all unchanged since it was computed. Falls back to [`compute_provenance`].
+pub fn cached_provenance(file: &str) -> Option<Vec<LineProvenance>> {
+    let Some((blob, path, context)) = cache_key(file) else {
+        return compute_provenance(file);
+    };
+    let conn = db::get_connection().ok();
+    if let Some(json) = conn
+        .as_ref()
+        .and_then(|c| db::get_cached_provenance(c, &blob, &path, &context))
+    {
+        if let Ok(lines) = serde_json::from_str(&json) {
+            return Some(lines);
+        }
+    }
+
+    let lines = compute_provenance(file)?;
+    if let (Some(c), Ok(json)) = (&conn, serde_json::to_string(&lines)) {
+        let _ = db::put_cached_provenance(c, &blob, &path, &context, &json);
+    }
+    Some(lines)
+}
+
+/// `(blob_sha, absolute_path, context)` identifying a provenance computation.
+fn cache_key(file: &str) -> Option<(String, String, String)> {
+    let git = |args: &[&str]| -> Option<String> {
+        let output = Command::new("git").args(args).output().ok()?;
+        output
+            .status
+            .success()
+            .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+    };
+    let blob = git(&["hash-object", "--", file])?;
+    let last_commit = git(&["log", "-1", "--format=%H", "--", file])?;
+    let path = std::fs::canonicalize(file)
+        .ok()?
+        .to_string_lossy()
+        .to_string();
+    let receipts_version = storage::active().version().unwrap_or_default();
+    Some((blob, path, format!("{}:{}", last_commit, receipts_version)))
+}
+
 /// Parse `git blame --porcelain` for the file and cross-reference with blameprompt notes.
 pub fn compute_provenance(file: &str) -> Option<Vec<LineProvenance>> {
+    provenance_with_staged(file, &[])
+}
+
+/// [`compute_provenance`], also attributing lines not committed yet to the
+/// `staged` receipts that cover them.
+pub fn provenance_with_staged(file: &str, staged: &[Receipt]) -> Option<Vec<LineProvenance>> {
     let output = Command::new("git")
         .args(["blame", "--porcelain", file])
         .output()
@@ -74,12 +164,22 @@ pub fn compute_provenance(file: &str) -> Option<Vec<LineProvenance>> {
 
     // Cache: commit_sha → Option<receipts payload>
     let mut note_cache: std::collections::HashMap<String, bool> = std::collections::HashMap::new();
-    // Cache: commit_sha → Vec<(file, start_line, end_line, model, provider, receipt_id, summary)>
+    // Cache: commit_sha → Vec<(file, start_line, end_line, model, provider, receipt_id, summary, notebook_cells)>
     #[allow(clippy::type_complexity)]
     let mut receipt_cache: std::collections::HashMap<
         String,
-        Vec<(String, u32, u32, String, String, String, String)>,
+        Vec<(
+            String,
+            u32,
+            u32,
+            String,
+            String,
+            String,
+            String,
+            Vec<String>,
+        )>,
     > = std::collections::HashMap::new();
+    let cells = notebook_cells(file).unwrap_or_default();
 
     let mut result = Vec::new();
 
@@ -88,7 +188,12 @@ pub fn compute_provenance(file: &str) -> Option<Vec<LineProvenance>> {
 
         // Populate cache for this commit if not done yet
         if !note_cache.contains_key(&sha) {
-            match read_receipts_for_commit(&sha) {
+            let payload = if util::is_not_committed(&sha) && !staged.is_empty() {
+                Some(NotePayload::new(staged.to_vec()))
+            } else {
+                read_receipts_for_commit(&sha)
+            };
+            match payload {
                 Some(payload) => {
                     note_cache.insert(sha.clone(), true);
                     let mut hits = Vec::new();
@@ -102,6 +207,7 @@ pub fn compute_provenance(file: &str) -> Option<Vec<LineProvenance>> {
                                 receipt.provider.clone(),
                                 receipt.id.clone(),
                                 receipt.prompt_summary.clone(),
+                                fc.notebook_cells.clone(),
                             ));
                         }
                     }
@@ -118,7 +224,13 @@ pub fn compute_provenance(file: &str) -> Option<Vec<LineProvenance>> {
                 if let Some(hits) = receipt_cache.get(&sha) {
                     // Normalize file path for comparison
                     let norm_file = normalize_file_path(file);
-                    find_matching_receipt(hits, &norm_file, entry.line_number)
+                    let found = find_matching_receipt(hits, &norm_file, entry.line_number, &cells);
+                    if !found.0 && !entry.filename.is_empty() && entry.filename != norm_file {
+                        // File was renamed since this commit: receipts use the old path
+                        find_matching_receipt(hits, &entry.filename, entry.line_number, &cells)
+                    } else {
+                        found
+                    }
                 } else {
                     (false, None, None, None, None)
                 }
@@ -143,10 +255,21 @@ pub fn compute_provenance(file: &str) -> Option<Vec<LineProvenance>> {
 }
 
 /// Check if a receipt range covers the given line in the given file.
+#[allow(clippy::type_complexity)]
 fn find_matching_receipt(
-    hits: &[(String, u32, u32, String, String, String, String)],
+    hits: &[(
+        String,
+        u32,
+        u32,
+        String,
+        String,
+        String,
+        String,
+        Vec<String>,
+    )],
     file: &str,
     line: u32,
+    cells: &[NotebookCell],
 ) -> (
     bool,
     Option<String>,
@@ -154,15 +277,17 @@ fn find_matching_receipt(
     Option<String>,
     Option<String>,
 ) {
-    for (path, start, end, model, provider, receipt_id, summary) in hits {
+    for (path, start, end, model, provider, receipt_id, summary, touched_cells) in hits {
         // Match on file path (basename or full)
         let hit_norm = normalize_file_path(path);
         let file_match = hit_norm == file || path.ends_with(file) || file.ends_with(path.as_str());
 
         // If receipt has line range 1..1 treat as "whole file" (no range info)
         let range_match = (*start == 1 && *end <= 1) || (line >= *start && line <= *end);
+        // Notebook receipts that recorded their cells only claim lines in those cells
+        let cell_match = cells.is_empty() || notebook::line_in_cells(cells, touched_cells, line);
 
-        if file_match && range_match {
+        if file_match && range_match && cell_match {
             return (
                 true,
                 Some(model.clone()),
@@ -212,6 +337,15 @@ pub fn collapse_to_ranges(lines: &[LineProvenance]) -> Vec<ProvenanceRange> {
     ranges
 }
 
+/// Short SHA, or "staged*" for AI lines attributed from staged receipts.
+fn commit_label(sha: &str, is_ai: bool) -> String {
+    if is_ai && util::is_not_committed(sha) {
+        "staged*".to_string()
+    } else {
+        util::short_sha(sha)
+    }
+}
+
 fn print_ranges(file: &str, ranges: &[ProvenanceRange]) {
     let ai_lines: u32 = ranges
         .iter()
@@ -239,7 +373,7 @@ fn print_ranges(file: &str, ranges: &[ProvenanceRange]) {
     println!();
 
     for range in ranges {
-        let sha_short = util::short_sha(&range.commit_sha);
+        let sha_short = commit_label(&range.commit_sha, range.is_ai);
         if range.is_ai {
             let model = range.model.as_deref().unwrap_or("unknown");
             let summary = range
@@ -250,13 +384,82 @@ fn print_ranges(file: &str, ranges: &[ProvenanceRange]) {
                 .take(50)
                 .collect::<String>();
             println!(
-                "  \x1b[33m[AI]\x1b[0m  lines {:>5}-{:<5}  {} ({}) — {}",
-                range.start_line, range.end_line, model, sha_short, summary
+                "  {}  lines {:>5}-{:<5}  {} ({}) — {}",
+                term::yellow("[AI]"),
+                range.start_line,
+                range.end_line,
+                model,
+                sha_short,
+                summary
+            );
+        } else {
+            println!(
+                "  {}  lines {:>5}-{:<5}  {} ({})",
+                term::blue("[HU]"),
+                range.start_line,
+                range.end_line,
+                range.author,
+                sha_short
+            );
+        }
+    }
+    if ranges
+        .iter()
+        .any(|r| r.is_ai && util::is_not_committed(&r.commit_sha))
+    {
+        println!();
+        println!("  * from staged receipts, not committed yet: provisional");
+    }
+    println!();
+}
+
+fn print_cells(file: &str, cells: &[NotebookCell], provenance: &[LineProvenance]) {
+    let attributed = notebook::attribute_cells(
+        cells,
+        provenance
+            .iter()
+            .map(|p| (p.line_number, p.is_ai, p.model.as_deref())),
+    );
+    let ai_cells = attributed.iter().filter(|c| c.ai_lines > 0).count();
+
+    println!();
+    println!("  Provenance: {} (notebook)", file);
+    println!("  ─────────────────────────────────────────────");
+    println!(
+        "  AI-touched cells: {}   Human-only cells: {}",
+        ai_cells,
+        attributed.len() - ai_cells
+    );
+    println!();
+
+    for ca in &attributed {
+        let label = format!(
+            "cell {:<3} {:<12} {:<8}",
+            ca.cell.index,
+            ca.cell.label(),
+            ca.cell.cell_type
+        );
+        if ca.ai_lines > 0 {
+            let models = if ca.models.is_empty() {
+                "unknown".to_string()
+            } else {
+                ca.models.join(", ")
+            };
+            println!(
+                "  {}  {}  {:>3.0}% AI ({}/{} lines) — {}",
+                term::yellow("[AI]"),
+                label,
+                ca.ai_pct(),
+                ca.ai_lines,
+                ca.total_lines,
+                models
             );
         } else {
             println!(
-                "  \x1b[34m[HU]\x1b[0m  lines {:>5}-{:<5}  {} ({})",
-                range.start_line, range.end_line, range.author, sha_short
+                "  {}  {}  {} lines",
+                term::blue("[HU]"),
+                label,
+                ca.total_lines
             );
         }
     }
@@ -264,13 +467,18 @@ fn print_ranges(file: &str, ranges: &[ProvenanceRange]) {
 }
 
 fn print_single_line(lp: &LineProvenance) {
-    let sha_short = util::short_sha(&lp.commit_sha);
+    let sha_short = commit_label(&lp.commit_sha, lp.is_ai);
     println!();
     println!("  Line {}: {}", lp.line_number, lp.content.trim_end());
     println!("  Commit: {}", sha_short);
     println!("  Author: {}", lp.author);
-    if lp.is_ai {
-        println!("  Origin: \x1b[33mAI-generated\x1b[0m");
+    if lp.is_ai && util::is_not_committed(&lp.commit_sha) {
+        println!(
+            "  Origin: {} (staged receipt, provisional until committed)",
+            term::yellow("AI-generated")
+        );
+    } else if lp.is_ai {
+        println!("  Origin: {}", term::yellow("AI-generated"));
         if let Some(ref m) = lp.model {
             println!("  Model:  {}", m);
         }
@@ -281,7 +489,7 @@ fn print_single_line(lp: &LineProvenance) {
             println!("  Receipt: {}", id);
         }
     } else {
-        println!("  Origin: \x1b[34mHuman-written\x1b[0m");
+        println!("  Origin: {}", term::blue("Human-written"));
     }
     println!();
 }
@@ -293,6 +501,8 @@ struct BlameEntry {
     line_number: u32,
     author: String,
     content: String,
+    /// Path of the file in `commit_sha` (differs from the current path after a rename).
+    filename: String,
 }
 
 fn parse_blame_porcelain(text: &str) -> Vec<BlameEntry> {
@@ -300,6 +510,7 @@ fn parse_blame_porcelain(text: &str) -> Vec<BlameEntry> {
     let mut current_sha = String::new();
     let mut current_line = 0u32;
     let mut current_author = String::new();
+    let mut current_filename = String::new();
 
     for line in text.lines() {
         if let Some(stripped) = line.strip_prefix('\t') {
@@ -309,9 +520,12 @@ fn parse_blame_porcelain(text: &str) -> Vec<BlameEntry> {
                 line_number: current_line,
                 author: current_author.clone(),
                 content: stripped.to_string(),
+                filename: current_filename.clone(),
             });
         } else if let Some(rest) = line.strip_prefix("author ") {
             current_author = rest.to_string();
+        } else if let Some(rest) = line.strip_prefix("filename ") {
+            current_filename = rest.to_string();
         } else {
             // Lines like "<sha> <orig_line> <final_line> [<count>]"
             let parts: Vec<&str> = line.splitn(4, ' ').collect();
@@ -341,6 +555,14 @@ mod tests {
         assert_eq!(entries[0].line_number, 1);
         assert_eq!(entries[0].author, "Alice");
         assert_eq!(entries[0].content, "let x = 1;");
+        assert_eq!(entries[0].filename, "");
+    }
+
+    #[test]
+    fn test_parse_blame_porcelain_tracks_filename() {
+        let input = "abc1234500000000000000000000000000000000 1 1 1\nauthor Alice\nfilename src/old.rs\n\tlet x = 1;\n";
+        let entries = parse_blame_porcelain(input);
+        assert_eq!(entries[0].filename, "src/old.rs");
     }
 
     #[test]
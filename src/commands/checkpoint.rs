@@ -1,12 +1,18 @@
 use crate::commands::staging;
 use crate::core::{
-    config, pricing, prompt_eval,
-    receipt::{DecisionOption, FileChange, Receipt, SubagentActivity, UserDecision},
-    redact, transcript, util,
+    capture_ignore::{self, CaptureIgnore},
+    config::{self, RemoteConfig},
+    environment::{self, Environment},
+    heartbeat, notebook,
+    path_map::{self, PathMapper},
+    pricing, prompt_eval,
+    receipt::{ChangeType, DecisionOption, FileChange, Receipt, SubagentActivity, UserDecision},
+    redact, ticket, transcript, util,
 };
 use crate::git::notes;
 use chrono::Utc;
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::io::Read;
 use std::path::Path;
 use transcript::{
@@ -30,11 +36,15 @@ struct HookInput {
     cwd: Option<String>,
     hook_event_name: Option<String>,
     tool_name: Option<String>,
+    /// Tool call ID (PostToolUse event), used for `tool_call` capture granularity.
+    tool_use_id: Option<String>,
     /// Prompt text sent directly in the hook payload (UserPromptSubmit event).
     prompt: Option<String>,
     /// All file paths touched by this tool call.
     /// Write/Edit produce one entry; MultiEdit produces one per edit in the edits array.
     file_paths: Vec<String>,
+    /// Notebook cell targeted by a NotebookEdit call (`cell_id` in tool_input).
+    notebook_cell_id: Option<String>,
     /// The AI's final response text (Stop and SubagentStop events).
     last_assistant_message: Option<String>,
     /// Tool execution result (PostToolUse event). Reserved for future use.
@@ -87,12 +97,15 @@ fn parse_hook_input(json_str: &str) -> HookInput {
 
     // Collect all file paths from this tool invocation.
     // Write/Edit/Read: top-level file_path / filePath field.
+    // NotebookEdit: notebook_path.
     // MultiEdit: edits[].file_path array.
     let file_paths = if let Some(fp) = tool_input
         .and_then(|ti| {
             ti.get("file_path")
                 .or_else(|| ti.get("filePath"))
                 .or_else(|| ti.get("path"))
+                .or_else(|| ti.get("notebook_path"))
+                .or_else(|| ti.get("notebookPath"))
         })
         .and_then(|v| v.as_str())
     {
@@ -130,8 +143,12 @@ fn parse_hook_input(json_str: &str) -> HookInput {
         cwd: v.get("cwd").and_then(|v| v.as_str()).map(String::from),
         hook_event_name: get_str(&v, "hook_event_name", "hookEventName").map(String::from),
         tool_name,
+        tool_use_id: get_str(&v, "tool_use_id", "toolUseId").map(String::from),
         prompt: extract_prompt(&v),
         file_paths,
+        notebook_cell_id: tool_input
+            .and_then(|ti| get_str(ti, "cell_id", "cellId"))
+            .map(String::from),
         last_assistant_message: get_str(&v, "last_assistant_message", "lastAssistantMessage")
             .map(String::from),
         tool_response: v
@@ -142,6 +159,34 @@ fn parse_hook_input(json_str: &str) -> HookInput {
     }
 }
 
+/// Rewrite the payload's paths for this side of a devcontainer or SSH mount
+/// (see `path_map`), so the transcript and files can be read.
+fn remap_paths(input: &mut HookInput, cfg: &RemoteConfig) {
+    let local_cwd = std::env::current_dir().ok();
+    let mapper = PathMapper::new(
+        cfg,
+        input.cwd.as_deref(),
+        local_cwd.as_deref(),
+        dirs::home_dir(),
+    );
+    let paths = [
+        &mut input.cwd,
+        &mut input.transcript_path,
+        &mut input.agent_transcript_path,
+    ];
+    for path in paths
+        .into_iter()
+        .flatten()
+        .chain(input.file_paths.iter_mut())
+    {
+        let mapped = mapper.map(path);
+        if mapped != *path {
+            tracing::debug!(from = %path, to = %mapped, env = ?path_map::environment(), "mapped remote path");
+            *path = mapped;
+        }
+    }
+}
+
 /// Map alternate tool names (VS Code, other IDEs) to the canonical names used in the matcher.
 fn normalize_tool_name(name: &str) -> String {
     match name {
@@ -250,6 +295,196 @@ fn get_diff_stats(cwd: &str, file_path: &str) -> (u32, u32) {
     (0, 0)
 }
 
+/// Build the FileChange entry for one touched file. Binary files skip the
+/// line-based diff stats (which are meaningless for them) and record byte sizes.
+fn build_file_change(cwd: &str, rel: &str, notebook_cell_id: Option<&str>) -> FileChange {
+    let blob_hash = get_blob_hash(cwd, rel);
+    let (change_type, old_path) = match get_change_type(cwd, rel, blob_hash.as_deref()) {
+        Some((ct, old)) => (Some(ct), old),
+        None => (None, None),
+    };
+    if let Some((bytes_before, bytes_after)) = get_binary_sizes(cwd, rel) {
+        return FileChange {
+            path: rel.to_string(),
+            line_range: (0, 0),
+            blob_hash,
+            additions: 0,
+            deletions: 0,
+            notebook_cells: vec![],
+            is_binary: true,
+            bytes_before: Some(bytes_before),
+            bytes_after: Some(bytes_after),
+            change_type,
+            old_path,
+            unstaged_ranges: Vec::new(),
+        };
+    }
+
+    let line_range = get_changed_lines(cwd, rel);
+    let (additions, deletions) = get_diff_stats(cwd, rel);
+    let notebook_cells = get_notebook_cells(cwd, rel, line_range, notebook_cell_id);
+    FileChange {
+        path: rel.to_string(),
+        line_range,
+        blob_hash,
+        additions,
+        deletions,
+        notebook_cells,
+        is_binary: false,
+        bytes_before: None,
+        bytes_after: None,
+        change_type,
+        old_path,
+        unstaged_ranges: Vec::new(),
+    }
+}
+
+/// One entry from `git status --porcelain=v1 -z`.
+struct StatusEntry {
+    x: char,
+    y: char,
+    path: String,
+    /// Source path for renames/copies.
+    orig_path: Option<String>,
+}
+
+/// Parse `git status --porcelain=v1 -z` output. Renames are `XY new\0old\0`.
+fn parse_status_z(out: &str) -> Vec<StatusEntry> {
+    let mut entries = Vec::new();
+    let mut fields = out.split('\0');
+    while let Some(field) = fields.next() {
+        let mut chars = field.chars();
+        let (Some(x), Some(y), Some(' ')) = (chars.next(), chars.next(), chars.next()) else {
+            continue;
+        };
+        let path = chars.as_str().to_string();
+        let orig_path = if matches!(x, 'R' | 'C') {
+            fields.next().map(String::from)
+        } else {
+            None
+        };
+        entries.push(StatusEntry {
+            x,
+            y,
+            path,
+            orig_path,
+        });
+    }
+    entries
+}
+
+/// Classify how `path` changed. A new file whose content matches a deleted file's
+/// HEAD blob is reported as a rename — that's what a plain `mv` looks like before
+/// anything is staged.
+fn classify_change(
+    entries: &[StatusEntry],
+    path: &str,
+    blob_hash: Option<&str>,
+    head_blob_of: impl Fn(&str) -> Option<String>,
+) -> Option<(ChangeType, Option<String>)> {
+    let entry = entries.iter().find(|e| util::paths_match(&e.path, path))?;
+    let has = |c: char| entry.x == c || entry.y == c;
+
+    if has('R') {
+        return Some((ChangeType::Renamed, entry.orig_path.clone()));
+    }
+    if has('?') || has('A') {
+        let renamed_from = blob_hash.and_then(|hash| {
+            entries
+                .iter()
+                .filter(|e| e.x == 'D' || e.y == 'D')
+                .find(|e| head_blob_of(&e.path).as_deref() == Some(hash))
+        });
+        return Some(match renamed_from {
+            Some(old) => (ChangeType::Renamed, Some(old.path.clone())),
+            None => (ChangeType::Added, None),
+        });
+    }
+    if has('D') {
+        return Some((ChangeType::Deleted, None));
+    }
+    Some((ChangeType::Modified, None))
+}
+
+fn get_change_type(
+    cwd: &str,
+    file_path: &str,
+    blob_hash: Option<&str>,
+) -> Option<(ChangeType, Option<String>)> {
+    let effective_cwd = if cwd.is_empty() { "." } else { cwd };
+    let output = std::process::Command::new("git")
+        .current_dir(effective_cwd)
+        .args(["status", "--porcelain=v1", "-z", "--untracked-files=all"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    let entries = parse_status_z(&String::from_utf8_lossy(&output.stdout));
+
+    classify_change(&entries, file_path, blob_hash, |p| {
+        // Porcelain paths are relative to the repository root.
+        std::process::Command::new("git")
+            .current_dir(effective_cwd)
+            .args(["rev-parse", &format!("HEAD:{}", p)])
+            .output()
+            .ok()
+            .filter(|o| o.status.success())
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+    })
+}
+
+/// Returns `(bytes_before, bytes_after)` if the file is binary, `None` for text.
+/// "Before" is the HEAD version (0 if new); "after" is the working tree (0 if deleted).
+fn get_binary_sizes(cwd: &str, file_path: &str) -> Option<(u64, u64)> {
+    let effective_cwd = if cwd.is_empty() { "." } else { cwd };
+    let head_blob = std::process::Command::new("git")
+        .current_dir(effective_cwd)
+        .args(["cat-file", "blob", &format!("HEAD:./{}", file_path)])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| o.stdout);
+    let current = std::fs::read(Path::new(effective_cwd).join(file_path)).ok();
+
+    let is_binary = match (&current, &head_blob) {
+        (Some(bytes), _) => util::is_binary_content(bytes),
+        (None, Some(bytes)) => util::is_binary_content(bytes),
+        (None, None) => false,
+    };
+    if !is_binary {
+        return None;
+    }
+    Some((
+        head_blob.map(|b| b.len() as u64).unwrap_or(0),
+        current.map(|b| b.len() as u64).unwrap_or(0),
+    ))
+}
+
+/// For `.ipynb` files, map the changed raw-JSON line range onto notebook cells.
+/// The cell named in the NotebookEdit call is always included, even when the
+/// diff range misses it (e.g. a cell that was just deleted).
+fn get_notebook_cells(
+    cwd: &str,
+    file_path: &str,
+    line_range: (u32, u32),
+    cell_id: Option<&str>,
+) -> Vec<String> {
+    if !notebook::is_notebook(file_path) {
+        return vec![];
+    }
+    let mut cells = std::fs::read_to_string(Path::new(cwd).join(file_path))
+        .ok()
+        .and_then(|raw| notebook::parse_cells(&raw))
+        .filter(|_| line_range.0 > 0)
+        .map(|cells| notebook::cells_in_range(&cells, line_range))
+        .unwrap_or_default();
+    if let Some(id) = cell_id {
+        if !cells.iter().any(|c| c == id) {
+            cells.push(id.to_string());
+        }
+    }
+    cells
+}
+
 /// Try to detect changed lines using multiple git diff strategies.
 fn get_changed_lines(cwd: &str, file_path: &str) -> (u32, u32) {
     let effective_cwd = if cwd.is_empty() { "." } else { cwd };
@@ -311,10 +546,20 @@ fn get_changed_lines(cwd: &str, file_path: &str) -> (u32, u32) {
 }
 
 pub fn run(agent: &str, hook_input_source: &str) {
+    // A panicking hook is invisible to the user; keep a record for `doctor`.
+    let panic_agent = agent.to_string();
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        tracing::error!(agent = %panic_agent, "checkpoint panicked: {}", info);
+        heartbeat::record_panic(&panic_agent, &info.to_string());
+        default_hook(info);
+    }));
+
     // Read from stdin
     let json_str = if hook_input_source == "stdin" {
         let mut buf = String::new();
         if let Err(e) = std::io::stdin().read_to_string(&mut buf) {
+            tracing::error!(agent, "failed to read hook input from stdin: {}", e);
             eprintln!("[BlamePrompt] Failed to read hook input from stdin: {}", e);
             return;
         }
@@ -323,7 +568,28 @@ pub fn run(agent: &str, hook_input_source: &str) {
         hook_input_source.to_string()
     };
 
-    let input = parse_hook_input(&json_str);
+    let mut input = parse_hook_input(&json_str);
+    remap_paths(&mut input, &config::load_config().remote);
+    let _span = tracing::info_span!(
+        "hook",
+        agent,
+        event = input.hook_event_name.as_deref().unwrap_or("-")
+    )
+    .entered();
+    heartbeat::record(agent, input.hook_event_name.as_deref().unwrap_or("unknown"));
+    tracing::debug!(
+        tool = ?input.tool_name,
+        session = ?input.session_id,
+        cwd = ?input.cwd,
+        files = input.file_paths.len(),
+        "hook input"
+    );
+
+    let cwd = input.cwd.as_deref().unwrap_or(".");
+    if let Some(reason) = crate::commands::capture::disabled_reason(cwd) {
+        tracing::info!("capture disabled: {}", reason);
+        return;
+    }
 
     let staging_updated = match input.hook_event_name.as_deref() {
         Some("UserPromptSubmit" | "BeforeTool") => {
@@ -333,7 +599,7 @@ pub fn run(agent: &str, hook_input_source: &str) {
             true
         }
         Some("PostToolUse" | "AfterTool") => match input.tool_name.as_deref() {
-            Some("Write" | "Edit" | "MultiEdit" | "write_file" | "replace") => {
+            Some("Write" | "Edit" | "MultiEdit" | "NotebookEdit" | "write_file" | "replace") => {
                 handle_file_change(agent, &input);
                 true
             }
@@ -348,6 +614,7 @@ pub fn run(agent: &str, hook_input_source: &str) {
                     handle_stop(agent, &input);
                     true
                 } else {
+                    tracing::debug!(tool = ?input.tool_name, "tool not tracked");
                     false
                 }
             }
@@ -356,6 +623,7 @@ pub fn run(agent: &str, hook_input_source: &str) {
             // Finalizes the current prompt's receipt with conversation, tools, and cost.
             // Also creates receipts for any older prompts still missing one.
             handle_stop(agent, &input);
+            finalize_nested_repos(&input);
             true
         }
         Some("SubagentStart") => {
@@ -366,7 +634,11 @@ pub fn run(agent: &str, hook_input_source: &str) {
             handle_subagent_stop(agent, &input);
             true
         }
-        _ => false, // skip all other events
+        _ => {
+            // skip all other events
+            tracing::debug!("event not handled");
+            false
+        }
     };
 
     // Auto-sync to cloud in background every time staging is updated.
@@ -388,14 +660,26 @@ struct TranscriptContext {
 }
 
 fn build_context(input: &HookInput, agent: &str) -> Option<TranscriptContext> {
-    let transcript_path = input.transcript_path.as_ref()?;
+    let Some(transcript_path) = input.transcript_path.as_ref() else {
+        tracing::warn!("hook payload has no transcript_path; cannot build a receipt");
+        return None;
+    };
     let cwd = input.cwd.clone().unwrap_or_else(|| ".".to_string());
 
     let parsed = if agent == "gemini" || agent == "antigravity" {
-        crate::integrations::gemini::parse_gemini_session(Path::new(transcript_path))
-            .map(|s| s.to_transcript_result())?
+        let session = crate::integrations::gemini::parse_gemini_session(Path::new(transcript_path));
+        if session.is_none() {
+            tracing::warn!("cannot parse session file {}", transcript_path);
+        }
+        session.map(|s| s.to_transcript_result())?
     } else {
-        transcript::parse_claude_jsonl(transcript_path).ok()?
+        match transcript::parse_claude_jsonl(transcript_path) {
+            Ok(parsed) => parsed,
+            Err(e) => {
+                tracing::warn!("cannot parse transcript {}: {}", transcript_path, e);
+                return None;
+            }
+        }
     };
     let cfg = config::load_config();
     let model = parsed
@@ -426,24 +710,32 @@ fn build_context(input: &HookInput, agent: &str) -> Option<TranscriptContext> {
 ///
 /// Uses `token_usage_for_prompt()` to sum only the assistant messages within the prompt's
 /// message slice, avoiding the cumulative full-session totals that inflate costs.
-/// Returns (0.0, None) if the JSONL has no usage data for this prompt (e.g. transcript
-/// not yet flushed, or provider doesn't emit usage fields).
+/// When the JSONL has no usage data for this prompt (e.g. transcript not yet flushed, or
+/// provider doesn't emit usage fields), tokens are estimated from the conversation text
+/// and the third value is true.
 fn prompt_cost_and_tokens(
     ctx: &TranscriptContext,
     prompt_number: u32,
-) -> (f64, Option<transcript::TokenUsage>) {
-    if let Some(usage) = token_usage_for_prompt(&ctx.parsed.transcript, prompt_number) {
-        let cost = pricing::cost_from_usage(
-            &ctx.model,
-            usage.input_tokens,
-            usage.output_tokens,
-            usage.cache_read_tokens,
-            usage.cache_creation_tokens,
-        );
-        (cost, Some(usage))
-    } else {
-        (0.0, None)
-    }
+) -> (f64, Option<transcript::TokenUsage>, bool) {
+    let (usage, estimated) = match token_usage_for_prompt(&ctx.parsed.transcript, prompt_number) {
+        Some(usage) => (usage, false),
+        None => match transcript::estimated_usage_for_prompt(&ctx.parsed.transcript, prompt_number)
+        {
+            Some(usage) => {
+                tracing::debug!(prompt_number, "no usage in transcript; estimating tokens");
+                (usage, true)
+            }
+            None => return (0.0, None, false),
+        },
+    };
+    let cost = pricing::cost_from_usage(
+        &ctx.model,
+        usage.input_tokens,
+        usage.output_tokens,
+        usage.cache_read_tokens,
+        usage.cache_creation_tokens,
+    );
+    (cost, Some(usage), estimated)
 }
 
 /// Prefix that Claude Code uses when continuing from a prior session that exhausted its context.
@@ -613,6 +905,7 @@ fn handle_user_prompt_submit(agent: &str, input: &HookInput) {
         .unwrap_or_default();
 
     if prompt_summary.is_empty() {
+        tracing::debug!("empty prompt; nothing staged");
         return;
     }
 
@@ -620,6 +913,7 @@ fn handle_user_prompt_submit(agent: &str, input: &HookInput) {
     let prompt_quality = Some(prompt_eval::evaluate(&prompt_summary));
 
     let user = util::git_user();
+    let ticket = ticket::detect_ticket(&prompt_summary, &cfg.tickets);
 
     // Detect session continuation (context exhaustion → new session)
     let (parent_session_id, continuation_depth) = detect_continuation(input, &cwd);
@@ -670,11 +964,22 @@ fn handle_user_prompt_submit(agent: &str, input: &HookInput) {
         total_deletions: 0,
         tools_used: vec![],
         mcp_servers: vec![],
+        environment: None,
         agents_spawned: vec![],
         subagent_activities: vec![],
         concurrent_tool_calls: None,
         user_decisions: vec![],
         conversation: None, // Conversation populated at Stop time
+        ticket,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated: false,
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
     };
 
     staging::upsert_receipt_in(&receipt, &cwd);
@@ -712,9 +1017,15 @@ fn handle_file_change(agent: &str, input: &HookInput) {
     // Build file change entries for every file touched by this tool call.
     // Write/Edit produce one path; MultiEdit may produce several.
     if input.file_paths.is_empty() {
+        tracing::debug!("tool call has no file paths");
         return;
     }
 
+    // Files inside a submodule or nested repo are staged in that repository so
+    // its own commit picks them up.
+    let cwd_root = util::repo_root_of(Path::new(&ctx.cwd));
+    let ignore = CaptureIgnore::load(&ctx.cwd);
+    let mut nested: BTreeMap<String, Vec<FileChange>> = BTreeMap::new();
     let files_changed: Vec<FileChange> = input
         .file_paths
         .iter()
@@ -724,20 +1035,28 @@ fn handle_file_change(agent: &str, input: &HookInput) {
             if rel.starts_with(".claude/") || rel.contains("/tool-results/") {
                 return None;
             }
-            let line_range = get_changed_lines(&ctx.cwd, &rel);
-            let (additions, deletions) = get_diff_stats(&ctx.cwd, &rel);
-            let blob_hash = get_blob_hash(&ctx.cwd, &rel);
-            Some(FileChange {
-                path: rel,
-                line_range,
-                blob_hash,
-                additions,
-                deletions,
-            })
+            if ignore.is_ignored(&ctx.cwd, &rel) {
+                tracing::debug!(file = %rel, "skipped by {}", capture_ignore::IGNORE_FILE);
+                return None;
+            }
+            let abs = Path::new(&ctx.cwd).join(f);
+            if let Some(root) = util::repo_root_of(&abs).filter(|r| Some(r) != cwd_root.as_ref()) {
+                let rel = util::make_relative(&abs.to_string_lossy(), &root);
+                tracing::debug!(file = %rel, repo = %root, "routing change to nested repository");
+                let fc = build_file_change(&root, &rel, input.notebook_cell_id.as_deref());
+                nested.entry(root).or_default().push(fc);
+                return None;
+            }
+            Some(build_file_change(
+                &ctx.cwd,
+                &rel,
+                input.notebook_cell_id.as_deref(),
+            ))
         })
         .collect();
 
-    if files_changed.is_empty() {
+    if files_changed.is_empty() && nested.is_empty() {
+        tracing::debug!("only agent scratch files touched; nothing staged");
         return;
     }
 
@@ -751,11 +1070,13 @@ fn handle_file_change(agent: &str, input: &HookInput) {
         .unwrap_or_default();
 
     // Per-prompt cost/tokens — avoids the cumulative full-session totals
-    let (prompt_cost, prompt_tokens) = prompt_cost_and_tokens(&ctx, prompt_number);
+    let (prompt_cost, prompt_tokens, cost_estimated) = prompt_cost_and_tokens(&ctx, prompt_number);
 
     // Evaluate prompt quality
     let prompt_quality = Some(prompt_eval::evaluate(&prompt_summary));
 
+    let mcp_servers = extract_mcps_for_prompt(&ctx.parsed.transcript, prompt_number);
+    let environment = prompt_environment(&ctx, &mcp_servers);
     let receipt = Receipt {
         id: Receipt::new_id(),
         provider: agent.to_string(),
@@ -798,7 +1119,8 @@ fn handle_file_change(agent: &str, input: &HookInput) {
         continuation_depth: None,
         prompt_number: Some(prompt_number),
         tools_used: extract_tools_for_prompt(&ctx.parsed.transcript, prompt_number),
-        mcp_servers: extract_mcps_for_prompt(&ctx.parsed.transcript, prompt_number),
+        mcp_servers,
+        environment,
         agents_spawned: extract_agents_for_prompt(&ctx.parsed.transcript, prompt_number),
         subagent_activities: vec![],
         concurrent_tool_calls: None,
@@ -808,9 +1130,97 @@ fn handle_file_change(agent: &str, input: &HookInput) {
         } else {
             Some(conversation_turns)
         },
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated,
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
     };
 
-    staging::upsert_receipt_in(&receipt, &ctx.cwd);
+    // Tool-call granularity: each edit gets its own receipt keyed by the tool call.
+    // Cost, tokens and conversation belong to the prompt and stay on the
+    // prompt-level receipt that Stop finalizes, so they aren't counted per call.
+    let mut receipt = receipt;
+    if ctx.cfg.capture.granularity == "tool_call" && input.tool_use_id.is_some() {
+        receipt.tool_use_id = input.tool_use_id.clone();
+        receipt.cost_usd = 0.0;
+        receipt.input_tokens = None;
+        receipt.output_tokens = None;
+        receipt.cache_read_tokens = None;
+        receipt.cache_creation_tokens = None;
+        receipt.cost_estimated = false;
+        receipt.conversation = None;
+    }
+
+    for (root, files) in nested {
+        let mut nested_receipt = receipt.clone();
+        nested_receipt.id = Receipt::new_id();
+        set_files(&mut nested_receipt, files);
+        staging::upsert_receipt_in(&nested_receipt, &root);
+        staging::note_nested_repo_in(&ctx.cwd, &root);
+    }
+    if !receipt.files_changed.is_empty() {
+        staging::upsert_receipt_in(&receipt, &ctx.cwd);
+    }
+}
+
+/// Agent version, settings and MCP server versions for a receipt that used `mcp_servers`.
+fn prompt_environment(ctx: &TranscriptContext, mcp_servers: &[String]) -> Option<Environment> {
+    environment::capture(
+        ctx.parsed.agent_version.as_deref(),
+        &ctx.parsed.settings,
+        mcp_servers,
+        &ctx.cwd,
+    )
+}
+
+fn set_files(receipt: &mut Receipt, files: Vec<FileChange>) {
+    receipt.file_path = files.first().map(|f| f.path.clone()).unwrap_or_default();
+    receipt.line_range = files.first().map(|f| f.line_range).unwrap_or((0, 0));
+    receipt.total_additions = files.iter().map(|f| f.additions).sum();
+    receipt.total_deletions = files.iter().map(|f| f.deletions).sum();
+    receipt.files_changed = files;
+}
+
+/// After Stop finalizes the session's receipts in `cwd`, copy the prompt-level
+/// fields (response, cost, tokens, conversation, tools) onto the matching
+/// receipts staged in nested repositories, keeping their own file changes.
+fn finalize_nested_repos(input: &HookInput) {
+    let (Some(cwd), Some(session_id)) = (input.cwd.as_deref(), input.session_id.as_deref()) else {
+        return;
+    };
+    let roots = staging::nested_repos_in(Path::new(cwd));
+    if roots.is_empty() {
+        return;
+    }
+    let main = staging::read_staging_in(Path::new(cwd));
+    for root in roots {
+        let nested = staging::read_staging_in(Path::new(&root));
+        for r in nested
+            .receipts
+            .iter()
+            .filter(|r| r.session_id == session_id)
+        {
+            let Some(source) = main.receipts.iter().find(|m| {
+                m.session_id == r.session_id
+                    && m.prompt_number == r.prompt_number
+                    && m.tool_use_id == r.tool_use_id
+            }) else {
+                continue;
+            };
+            let mut patch = source.clone();
+            patch.id = r.id.clone();
+            set_files(&mut patch, Vec::new());
+            patch.file_path = r.file_path.clone();
+            patch.line_range = r.line_range;
+            staging::upsert_receipt_in(&patch, &root);
+        }
+    }
 }
 
 /// Handle PostToolUse for AskUserQuestion — captures questions and options in real-time.
@@ -917,6 +1327,7 @@ fn handle_stop(agent: &str, input: &HookInput) {
 
     let total_prompts = transcript::count_user_prompts(&ctx.parsed.transcript);
     if total_prompts == 0 {
+        tracing::debug!("transcript has no user prompts yet");
         return;
     }
 
@@ -970,6 +1381,7 @@ fn handle_stop(agent: &str, input: &HookInput) {
                     }
                 }
 
+                let ignore = CaptureIgnore::load(&ctx.cwd);
                 let missing_files: Vec<FileChange> = git_modified
                     .iter()
                     .filter(|p| {
@@ -977,19 +1389,9 @@ fn handle_stop(agent: &str, input: &HookInput) {
                             && !p.starts_with(".claude/")
                             && !p.contains("/tool-results/")
                             && !p.starts_with(".blameprompt")
+                            && !ignore.is_ignored(&ctx.cwd, p)
                     })
-                    .map(|p| {
-                        let line_range = get_changed_lines(&ctx.cwd, p);
-                        let (additions, deletions) = get_diff_stats(&ctx.cwd, p);
-                        let blob_hash = get_blob_hash(&ctx.cwd, p);
-                        FileChange {
-                            path: p.clone(),
-                            line_range,
-                            blob_hash,
-                            additions,
-                            deletions,
-                        }
-                    })
+                    .map(|p| build_file_change(&ctx.cwd, p, None))
                     .collect();
 
                 if !missing_files.is_empty() {
@@ -1040,11 +1442,22 @@ fn handle_stop(agent: &str, input: &HookInput) {
                         prompt_number: Some(last_pn),
                         tools_used: vec![],
                         mcp_servers: vec![],
+                        environment: None,
                         agents_spawned: vec![],
                         subagent_activities: vec![],
                         concurrent_tool_calls: None,
                         user_decisions: vec![],
                         conversation: None,
+                        ticket: None,
+                        manual_import: false,
+                        housekeeping_prompts: None,
+                        intent_summary: None,
+                        heuristic: false,
+                        cost_estimated: false,
+                        acceptance_estimated: false,
+                        co_authors: Vec::new(),
+                        plan_summary: None,
+                        tool_use_id: None,
                     };
                     staging::upsert_receipt_in(&patch, &ctx.cwd);
                 }
@@ -1135,7 +1548,7 @@ fn handle_stop(agent: &str, input: &HookInput) {
     }
 
     // Per-prompt cost/tokens — avoids the cumulative full-session totals that inflate costs.
-    let (prompt_cost, prompt_tokens) = prompt_cost_and_tokens(&ctx, current_pn);
+    let (prompt_cost, prompt_tokens, cost_estimated) = prompt_cost_and_tokens(&ctx, current_pn);
 
     // Use the actual prompt timestamp from the JSONL instead of Utc::now().
     // This ensures the receipt shows when the prompt was submitted, not when Stop fired.
@@ -1145,6 +1558,21 @@ fn handle_stop(agent: &str, input: &HookInput) {
     // Evaluate prompt quality for the current prompt
     let current_quality = Some(prompt_eval::evaluate(&current_summary));
 
+    // Plan-mode output (and optionally thinking) — opt-in, capped, and redacted like prompts.
+    let plan_summary = if ctx.cfg.capture.store_plans {
+        transcript::plan_for_prompt(
+            &ctx.parsed.transcript,
+            current_pn,
+            ctx.cfg.capture.store_thinking,
+        )
+        .map(|p| {
+            let truncated: String = p.chars().take(ctx.cfg.capture.max_plan_length).collect();
+            redact::redact_secrets_with_config(&truncated, &ctx.cfg)
+        })
+    } else {
+        None
+    };
+
     let current_receipt = Receipt {
         id: Receipt::new_id(),
         provider: agent.to_string(),
@@ -1181,6 +1609,7 @@ fn handle_stop(agent: &str, input: &HookInput) {
         total_additions: 0,
         total_deletions: 0,
         tools_used: tools.clone(),
+        environment: prompt_environment(&ctx, &mcps),
         mcp_servers: mcps.clone(),
         agents_spawned: agents.clone(),
         subagent_activities: vec![],
@@ -1198,6 +1627,16 @@ fn handle_stop(agent: &str, input: &HookInput) {
         } else {
             Some(current_turns)
         },
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated,
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary,
+        tool_use_id: None,
     };
     staging::upsert_receipt_in(&current_receipt, &ctx.cwd);
 
@@ -1243,12 +1682,13 @@ fn handle_stop(agent: &str, input: &HookInput) {
         }
 
         // Per-prompt cost/tokens for retrospective receipts too
-        let (pn_cost, pn_tokens) = prompt_cost_and_tokens(&ctx, pn);
+        let (pn_cost, pn_tokens, pn_cost_estimated) = prompt_cost_and_tokens(&ctx, pn);
 
         // Use the actual prompt timestamp from the JSONL for backfilled receipts.
         let pn_ts = transcript::timestamp_for_prompt(&ctx.parsed, pn).unwrap_or_else(Utc::now);
 
         let pn_quality = Some(prompt_eval::evaluate(&prompt_summary));
+        let pn_mcps = extract_mcps_for_prompt(&ctx.parsed.transcript, pn);
 
         let receipt = Receipt {
             id: Receipt::new_id(),
@@ -1286,7 +1726,8 @@ fn handle_stop(agent: &str, input: &HookInput) {
             total_additions: 0,
             total_deletions: 0,
             tools_used: extract_tools_for_prompt(&ctx.parsed.transcript, pn),
-            mcp_servers: extract_mcps_for_prompt(&ctx.parsed.transcript, pn),
+            environment: prompt_environment(&ctx, &pn_mcps),
+            mcp_servers: pn_mcps,
             agents_spawned: extract_agents_for_prompt(&ctx.parsed.transcript, pn),
             subagent_activities: vec![],
             concurrent_tool_calls: None,
@@ -1296,6 +1737,16 @@ fn handle_stop(agent: &str, input: &HookInput) {
             } else {
                 Some(pn_turns)
             },
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: pn_cost_estimated,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
         };
 
         staging::upsert_receipt_in(&receipt, &ctx.cwd);
@@ -1334,6 +1785,11 @@ fn handle_subagent_start(input: &HookInput) {
         started_at: Some(Utc::now()),
         completed_at: None,
         tools_used: vec![],
+        model: None,
+        input_tokens: None,
+        output_tokens: None,
+        cost_usd: None,
+        files_touched: vec![],
     };
 
     if let Some(receipt) = data
@@ -1357,7 +1813,8 @@ fn handle_subagent_start(input: &HookInput) {
 }
 
 /// Handle SubagentStop — a Task tool subagent has completed.
-/// Updates the matching SubagentActivity to "completed" and extracts tools used.
+/// Updates the matching SubagentActivity to "completed" and records the tools,
+/// tokens, cost and files from the subagent's own transcript.
 fn handle_subagent_stop(agent: &str, input: &HookInput) {
     let cwd = input.cwd.clone().unwrap_or_else(|| ".".to_string());
     let session_id = match input.session_id.as_ref() {
@@ -1365,20 +1822,15 @@ fn handle_subagent_stop(agent: &str, input: &HookInput) {
         None => return,
     };
 
-    // Parse the subagent's transcript if available to extract tools used
-    let subagent_tools: Vec<String> = input
-        .agent_transcript_path
-        .as_ref()
-        .and_then(|path| {
-            if agent == "gemini" || agent == "antigravity" {
-                crate::integrations::gemini::parse_gemini_session(Path::new(path))
-                    .map(|s| s.to_transcript_result())
-            } else {
-                transcript::parse_claude_jsonl(path).ok()
-            }
-        })
-        .map(|parsed| transcript::extract_tools_used(&parsed.transcript))
-        .unwrap_or_default();
+    // Parse the subagent's transcript if available
+    let parsed = input.agent_transcript_path.as_ref().and_then(|path| {
+        if agent == "gemini" || agent == "antigravity" {
+            crate::integrations::gemini::parse_gemini_session(Path::new(path))
+                .map(|s| s.to_transcript_result())
+        } else {
+            transcript::parse_claude_jsonl(path).ok()
+        }
+    });
 
     let mut data = staging::read_staging_in(Path::new(&cwd));
     // Find the receipt for this session and update the matching activity
@@ -1387,6 +1839,7 @@ fn handle_subagent_stop(agent: &str, input: &HookInput) {
         .iter_mut()
         .filter(|r| r.session_id == session_id)
     {
+        let parent_model = receipt.model.clone();
         let found = if let Some(ref aid) = input.agent_id {
             receipt
                 .subagent_activities
@@ -1404,8 +1857,8 @@ fn handle_subagent_stop(agent: &str, input: &HookInput) {
         if let Some(activity) = found {
             activity.status = "completed".to_string();
             activity.completed_at = Some(Utc::now());
-            if !subagent_tools.is_empty() {
-                activity.tools_used = subagent_tools;
+            if let Some(ref parsed) = parsed {
+                record_subagent_usage(activity, parsed, &parent_model);
             }
             staging::write_staging_data_in(&data, &cwd);
             return;
@@ -1413,6 +1866,36 @@ fn handle_subagent_stop(agent: &str, input: &HookInput) {
     }
 }
 
+/// Fill in what a subagent's transcript says about its work. Subagents that
+/// report no model ran on the parent prompt's model.
+fn record_subagent_usage(
+    activity: &mut SubagentActivity,
+    parsed: &transcript::TranscriptParseResult,
+    parent_model: &str,
+) {
+    let tools = transcript::extract_tools_used(&parsed.transcript);
+    if !tools.is_empty() {
+        activity.tools_used = tools;
+    }
+    let model = parsed
+        .model
+        .clone()
+        .unwrap_or_else(|| parent_model.to_string());
+    if let Some((usage, _estimated)) = transcript::total_token_usage(&parsed.transcript) {
+        activity.input_tokens = Some(usage.input_tokens);
+        activity.output_tokens = Some(usage.output_tokens);
+        activity.cost_usd = Some(pricing::cost_from_usage(
+            &model,
+            usage.input_tokens,
+            usage.output_tokens,
+            usage.cache_read_tokens,
+            usage.cache_creation_tokens,
+        ));
+    }
+    activity.model = Some(model);
+    activity.files_touched = parsed.files_modified.clone();
+}
+
 /// Fire-and-forget auto-sync to BlamePrompt Cloud after each prompt completes.
 /// Runs `blameprompt sync --quiet` as a detached background process so it doesn't
 /// block the hook handler. Only triggers if the user is logged in and auto_sync
@@ -1442,6 +1925,47 @@ fn maybe_auto_sync() {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_status_z() {
+        let out = "R  src/new.rs\0src/old.rs\0 M src/main.rs\0?? notes.md\0 D gone.rs\0";
+        let entries = parse_status_z(out);
+        assert_eq!(entries.len(), 4);
+        assert_eq!(entries[0].path, "src/new.rs");
+        assert_eq!(entries[0].orig_path.as_deref(), Some("src/old.rs"));
+        assert_eq!((entries[1].x, entries[1].y), (' ', 'M'));
+        assert_eq!(entries[3].path, "gone.rs");
+    }
+
+    #[test]
+    fn test_classify_change() {
+        let out =
+            "R  src/new.rs\0src/old.rs\0 M src/main.rs\0?? moved.rs\0 D before.rs\0?? fresh.rs\0";
+        let entries = parse_status_z(out);
+        let head = |p: &str| (p == "before.rs").then(|| "abc".to_string());
+
+        assert_eq!(
+            classify_change(&entries, "src/new.rs", None, head),
+            Some((ChangeType::Renamed, Some("src/old.rs".to_string())))
+        );
+        assert_eq!(
+            classify_change(&entries, "src/main.rs", None, head),
+            Some((ChangeType::Modified, None))
+        );
+        assert_eq!(
+            classify_change(&entries, "moved.rs", Some("abc"), head),
+            Some((ChangeType::Renamed, Some("before.rs".to_string())))
+        );
+        assert_eq!(
+            classify_change(&entries, "fresh.rs", Some("def"), head),
+            Some((ChangeType::Added, None))
+        );
+        assert_eq!(
+            classify_change(&entries, "before.rs", None, head),
+            Some((ChangeType::Deleted, None))
+        );
+        assert_eq!(classify_change(&entries, "untouched.rs", None, head), None);
+    }
+
     #[test]
     fn test_parse_hook_input() {
         let json = r#"{"transcript_path":"/tmp/test.jsonl","cwd":"/tmp","hook_event_name":"PostToolUse","tool_name":"Write","tool_input":{"file_path":"src/main.rs"}}"#;
@@ -1460,6 +1984,14 @@ mod tests {
         assert_eq!(input.file_paths, vec!["src/main.rs", "src/lib.rs"]);
     }
 
+    #[test]
+    fn test_parse_hook_input_notebook_edit() {
+        let json = r#"{"hook_event_name":"PostToolUse","tool_name":"NotebookEdit","tool_input":{"notebook_path":"/repo/analysis.ipynb","cell_id":"load","new_source":"x = 1","edit_mode":"replace"}}"#;
+        let input = parse_hook_input(json);
+        assert_eq!(input.file_paths, vec!["/repo/analysis.ipynb"]);
+        assert_eq!(input.notebook_cell_id.as_deref(), Some("load"));
+    }
+
     #[test]
     fn test_parse_hook_input_user_prompt_submit() {
         let json = r#"{"hook_event_name":"UserPromptSubmit","cwd":"/proj","transcript_path":"/tmp/s.jsonl","prompt":"fix the tests"}"#;
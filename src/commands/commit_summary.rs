@@ -0,0 +1,154 @@
+/// `blameprompt commit-summary`: suggest a commit message body describing the
+/// AI contribution in the staged receipts, one line per model:
+///
+///   Generated with claude-opus-4: add retry logic and refactor the parser, 120 lines, 2 prompts
+///
+/// Meant for prepare-commit-msg hooks, so it prints nothing when nothing is
+/// staged. `--comment` prefixes each line with `# ` so git strips it unless
+/// the author keeps it.
+use crate::commands::staging;
+use crate::core::receipt::Receipt;
+
+/// Prompts quoted per model before the rest are folded into "N more".
+const MAX_ACTIVITIES: usize = 3;
+/// Longest prompt excerpt quoted in the summary, in characters.
+const MAX_ACTIVITY_CHARS: usize = 60;
+
+pub fn run(comment: bool) {
+    let data = staging::read_all_staging();
+    for line in summarize(&data.receipts) {
+        if comment {
+            println!("# {}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+}
+
+/// Build the summary lines for a set of staged receipts, in first-seen model
+/// order.
+pub fn summarize(receipts: &[Receipt]) -> Vec<String> {
+    let mut models: Vec<(&str, Vec<&Receipt>)> = Vec::new();
+    for r in receipts {
+        match models.iter_mut().find(|(m, _)| *m == r.model) {
+            Some((_, rs)) => rs.push(r),
+            None => models.push((&r.model, vec![r])),
+        }
+    }
+
+    models
+        .into_iter()
+        .map(|(model, rs)| {
+            let mut activities: Vec<String> = Vec::new();
+            for r in &rs {
+                let prompt = r.intent_summary.as_deref().unwrap_or(&r.prompt_summary);
+                if let Some(a) = activity(prompt) {
+                    if !activities.contains(&a) {
+                        activities.push(a);
+                    }
+                }
+            }
+            let lines: u32 = rs.iter().map(|r| lines_of(r)).sum();
+            let prompts = rs.len();
+
+            let mut line = format!("Generated with {}", model);
+            if !activities.is_empty() {
+                line.push_str(": ");
+                line.push_str(&join_activities(&activities));
+            }
+            line.push_str(&format!(
+                ", {} line{}, {} prompt{}",
+                lines,
+                if lines == 1 { "" } else { "s" },
+                prompts,
+                if prompts == 1 { "" } else { "s" }
+            ));
+            line
+        })
+        .collect()
+}
+
+/// Lines the receipt added, falling back to the line-range span for receipts
+/// captured without diff stats.
+fn lines_of(r: &Receipt) -> u32 {
+    match r.effective_total_additions() {
+        0 => r.total_lines_changed(),
+        n => n,
+    }
+}
+
+/// First line of a prompt, trimmed and shortened, with the leading capital
+/// lowered so it reads mid-sentence.
+fn activity(prompt: &str) -> Option<String> {
+    let first = prompt.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let first = first.trim_end_matches(['.', '!', '?', ':']);
+    let mut text: String = first.chars().take(MAX_ACTIVITY_CHARS).collect();
+    if first.chars().count() > MAX_ACTIVITY_CHARS {
+        text = format!("{}...", text.trim_end());
+    }
+    let mut chars = text.chars();
+    let head = chars.next()?;
+    // Keep acronyms like "API" or "CI" intact.
+    let lower = match chars.clone().next() {
+        Some(c) if c.is_uppercase() => head.to_string(),
+        _ => head.to_lowercase().to_string(),
+    };
+    Some(lower + chars.as_str())
+}
+
+fn join_activities(activities: &[String]) -> String {
+    let shown = &activities[..activities.len().min(MAX_ACTIVITIES)];
+    let rest = activities.len() - shown.len();
+    if rest > 0 {
+        return format!("{} and {} more", shown.join(", "), rest);
+    }
+    match shown {
+        [one] => one.clone(),
+        [init @ .., last] => format!("{} and {}", init.join(", "), last),
+        [] => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(model: &str, prompt: &str, additions: u32) -> Receipt {
+        let mut r = Receipt::test(&Receipt::new_id())
+            .with_model(model)
+            .with_session("s1")
+            .with_prompt(prompt)
+            .with_timestamp("2026-01-01T00:00:00Z");
+        r.total_additions = additions;
+        r
+    }
+
+    #[test]
+    fn test_summarize_groups_by_model() {
+        let receipts = vec![
+            receipt("claude-opus-4", "Add retry logic to the fetcher.", 80),
+            receipt("claude-opus-4", "Refactor the parser\nkeep the API", 40),
+            receipt("gpt-5", "API docs", 1),
+        ];
+        assert_eq!(
+            summarize(&receipts),
+            vec![
+                "Generated with claude-opus-4: add retry logic to the fetcher and refactor the parser, 120 lines, 2 prompts",
+                "Generated with gpt-5: API docs, 1 line, 1 prompt",
+            ]
+        );
+        assert!(summarize(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_activities_are_capped() {
+        let acts: Vec<String> = ["a", "b", "c", "d", "e"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        assert_eq!(join_activities(&acts), "a, b, c and 2 more");
+        assert_eq!(join_activities(&acts[..3]), "a, b and c");
+        let long = activity(&"x".repeat(100)).unwrap();
+        assert_eq!(long.chars().count(), MAX_ACTIVITY_CHARS + 3);
+    }
+}
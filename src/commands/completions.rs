@@ -0,0 +1,67 @@
+/// Shell completions and man pages generated from the clap command tree.
+///
+/// `blameprompt completions <shell>` prints a completion script to stdout;
+/// the hidden `blameprompt mangen` writes `blameprompt.1` plus one page per
+/// subcommand (`blameprompt-audit.1`, ...) for packagers.
+use clap::Command;
+use clap_complete::Shell;
+use std::io::Write;
+
+pub fn completions(mut cmd: Command, shell: Shell) {
+    let name = cmd.get_name().to_string();
+    // Buffer first: clap_complete panics if stdout closes early (`| head`).
+    let mut buf = Vec::new();
+    clap_complete::generate(shell, &mut cmd, name, &mut buf);
+    let _ = std::io::stdout().write_all(&buf);
+}
+
+pub fn mangen(cmd: Command, out_dir: &str) -> Result<(), String> {
+    std::fs::create_dir_all(out_dir).map_err(|e| format!("Cannot create {}: {}", out_dir, e))?;
+    clap_mangen::generate_to(cmd, out_dir)
+        .map_err(|e| format!("Cannot write man pages to {}: {}", out_dir, e))?;
+    let written = std::fs::read_dir(out_dir)
+        .map(|d| {
+            d.filter_map(Result::ok)
+                .filter(|e| e.path().extension().is_some_and(|x| x == "1"))
+                .count()
+        })
+        .unwrap_or(0);
+    println!("[BlamePrompt] Wrote {} man page(s) to {}", written, out_dir);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::Arg;
+
+    fn sample() -> Command {
+        Command::new("tool")
+            .about("Sample tool")
+            .subcommand(
+                Command::new("audit")
+                    .about("Audit things")
+                    .arg(Arg::new("from").long("from")),
+            )
+            .subcommand(Command::new("secret").hide(true))
+    }
+
+    #[test]
+    fn test_completions_mention_subcommands() {
+        let mut buf = Vec::new();
+        clap_complete::generate(Shell::Bash, &mut sample(), "tool", &mut buf);
+        let script = String::from_utf8(buf).unwrap();
+        assert!(script.contains("audit"));
+        assert!(script.contains("--from"));
+    }
+
+    #[test]
+    fn test_mangen_writes_page_per_visible_subcommand() {
+        let dir = tempfile::tempdir().unwrap();
+        mangen(sample(), dir.path().to_str().unwrap()).unwrap();
+        assert!(dir.path().join("tool.1").exists());
+        let page = std::fs::read_to_string(dir.path().join("tool-audit.1")).unwrap();
+        assert!(page.contains("tool\\-audit"));
+        assert!(!dir.path().join("tool-secret.1").exists());
+    }
+}
@@ -0,0 +1,149 @@
+/// `blameprompt config show [--origin]`: the effective config after layering
+/// system, user, repo, environment and org settings.
+///
+/// Without `--origin` it prints the merged config as TOML, defaults filled
+/// in. With it, one line per setting with the layer that set it, so "why is
+/// redaction off here?" has an answer.
+use crate::core::config::{self, ResolvedConfig};
+
+/// `key = value` lines for every setting in `resolved`, each followed by its
+/// origin.
+pub fn origin_lines(resolved: &ResolvedConfig) -> Result<Vec<String>, String> {
+    let effective = resolved.config()?;
+    let table = toml::Table::try_from(&effective).map_err(|e| e.to_string())?;
+    let mut leaves = Vec::new();
+    flatten(&table, "", &mut leaves);
+    let width = leaves
+        .iter()
+        .map(|(k, v)| k.len() + v.len() + 3)
+        .max()
+        .unwrap_or(0)
+        .min(60);
+    Ok(leaves
+        .into_iter()
+        .map(|(key, value)| {
+            let setting = format!("{} = {}", key, value);
+            format!(
+                "{:<width$}  # {}",
+                setting,
+                resolved.origin(&key),
+                width = width
+            )
+        })
+        .collect())
+}
+
+fn flatten(table: &toml::Table, prefix: &str, out: &mut Vec<(String, String)>) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::Table(t) => flatten(t, &path, out),
+            v => out.push((path, v.to_string())),
+        }
+    }
+}
+
+pub fn run(origin: bool) -> Result<(), String> {
+    let resolved = config::resolve_current();
+    if origin {
+        for line in origin_lines(&resolved)? {
+            println!("{}", line);
+        }
+        return Ok(());
+    }
+    let effective = resolved.config()?;
+    print!(
+        "{}",
+        toml::to_string(&effective).map_err(|e| e.to_string())?
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::{env_layers, resolve, Layer, Origin};
+    use std::path::PathBuf;
+
+    fn layer(origin: Origin, toml: &str) -> Layer {
+        Layer {
+            origin,
+            table: toml.parse().unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_layered_config_origins() {
+        let system = PathBuf::from("/etc/blameprompt/config.toml");
+        let repo = PathBuf::from(".blameprompt/config.toml");
+        let mut layers = vec![
+            layer(
+                Origin::System(system.clone()),
+                "[redaction]\nmode = \"hash\"\n[capture]\nmax_prompt_length = 100\n",
+            ),
+            layer(
+                Origin::User(PathBuf::from("/home/u/.config/blameprompt/config.toml")),
+                "[capture]\nmax_prompt_length = 500\n",
+            ),
+            layer(
+                Origin::Repo(repo.clone()),
+                "[capture]\nstore_plans = true\n",
+            ),
+        ];
+        layers.extend(env_layers(
+            [
+                ("BLAMEPROMPT_CAPTURE__MAX_PROMPT_LENGTH", "900"),
+                ("BLAMEPROMPT_PRIVACY__PROMPT_STORAGE", "none"),
+                ("BLAMEPROMPT_SMTP_PASSWORD", "secret"),
+            ]
+            .into_iter()
+            .map(|(k, v)| (k.to_string(), v.to_string())),
+        ));
+        assert_eq!(layers.len(), 5);
+
+        let resolved = resolve(&layers, None);
+        let cfg = resolved.config().unwrap();
+        assert_eq!(cfg.redaction.mode, "hash");
+        assert_eq!(cfg.capture.max_prompt_length, 900);
+        assert!(cfg.capture.store_plans);
+        assert_eq!(cfg.privacy.prompt_storage, "none");
+        assert_eq!(resolved.origin("redaction.mode"), &Origin::System(system));
+        assert_eq!(resolved.origin("capture.store_plans"), &Origin::Repo(repo));
+        assert_eq!(
+            resolved.origin("capture.max_prompt_length").to_string(),
+            "env (BLAMEPROMPT_CAPTURE__MAX_PROMPT_LENGTH)"
+        );
+        assert_eq!(resolved.origin("logging.level"), &Origin::Default);
+
+        let org: toml::Table =
+            "[org]\nenforce = [\"redaction\"]\n[redaction]\nmode = \"replace\"\n[logging]\nlevel = \"warn\"\n"
+                .parse()
+                .unwrap();
+        let resolved = resolve(&layers, Some(("https://org/bp.toml".to_string(), org)));
+        let cfg = resolved.config().unwrap();
+        assert_eq!(cfg.redaction.mode, "replace");
+        assert_eq!(cfg.logging.level, "warn");
+        assert_eq!(
+            resolved.origin("redaction.mode").to_string(),
+            "org (https://org/bp.toml)"
+        );
+        assert_eq!(
+            resolved.origin("capture.max_prompt_length"),
+            &Origin::Env("BLAMEPROMPT_CAPTURE__MAX_PROMPT_LENGTH".to_string())
+        );
+
+        let lines = origin_lines(&resolved).unwrap();
+        let line = lines
+            .iter()
+            .find(|l| l.starts_with("logging.level = \"warn\""))
+            .unwrap();
+        assert!(line.ends_with("# org (https://org/bp.toml)"));
+        assert!(lines
+            .iter()
+            .any(|l| l.starts_with("logging.max_size_mb = 5") && l.ends_with("# default")));
+    }
+}
@@ -0,0 +1,523 @@
+/// Background sync daemon.
+///
+/// `blameprompt daemon run` loops in the current repository: it fetches and
+/// merges notes from origin, pushes local notes, refreshes the SQLite cache when
+/// the notes ref moved, and imports Cursor/Codex sessions whose files changed
+/// (Codex sessions only when they ran inside this repository).
+/// `daemon install` writes a launchd agent (macOS) or systemd user unit (Linux)
+/// that runs it at login.
+use crate::commands::staging;
+use crate::git::storage::NOTES_REF;
+use crate::integrations::{codex, cursor};
+use chrono::Local;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{Duration, SystemTime};
+
+const REMOTE_NOTES_REF: &str = "refs/notes/blameprompt-remote";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnitFormat {
+    Launchd,
+    Systemd,
+}
+
+impl UnitFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "launchd" => Some(UnitFormat::Launchd),
+            "systemd" => Some(UnitFormat::Systemd),
+            _ => None,
+        }
+    }
+
+    fn native() -> Self {
+        if cfg!(target_os = "macos") {
+            UnitFormat::Launchd
+        } else {
+            UnitFormat::Systemd
+        }
+    }
+}
+
+struct DaemonState {
+    notes_tip: Option<String>,
+    sessions_seen_at: SystemTime,
+    cursor_db_mtime: Option<SystemTime>,
+}
+
+fn log(msg: &str) {
+    tracing::info!(target: "blameprompt::daemon", "{}", msg);
+    println!(
+        "[blameprompt daemon {}] {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S"),
+        msg
+    );
+}
+
+/// Run sync cycles every `interval_secs` until killed (or once with `once`).
+pub fn run(interval_secs: u64, once: bool, push: bool) {
+    if git(&["rev-parse", "--git-dir"]).is_none() {
+        eprintln!("[blameprompt] Not a git repository. Run the daemon from inside a repo.");
+        std::process::exit(1);
+    }
+
+    let interval = Duration::from_secs(interval_secs.max(30));
+    let mut state = DaemonState {
+        notes_tip: None,
+        // First cycle picks up sessions touched during the previous interval.
+        sessions_seen_at: SystemTime::now()
+            .checked_sub(interval)
+            .unwrap_or(SystemTime::UNIX_EPOCH),
+        cursor_db_mtime: None,
+    };
+
+    log(&format!(
+        "started in {} (every {}s)",
+        std::env::current_dir()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default(),
+        interval.as_secs()
+    ));
+    loop {
+        run_cycle(&mut state, push);
+        if once {
+            break;
+        }
+        std::thread::sleep(interval);
+    }
+}
+
+fn run_cycle(state: &mut DaemonState, push: bool) {
+    // Only the notes backend is synced here; branch/sidecar receipts move
+    // with `blameprompt push` or ordinary commits.
+    let notes_backend = crate::git::storage::active().name() == "notes";
+    if notes_backend {
+        if let Err(e) = pull_notes() {
+            log(&format!("pull failed: {}", e));
+        }
+    }
+    if push && notes_backend {
+        if let Err(e) = push_notes() {
+            log(&format!("push failed: {}", e));
+        }
+    }
+
+    let tip = git(&["rev-parse", "--verify", "--quiet", NOTES_REF]);
+    if tip.is_some() && tip != state.notes_tip {
+        match crate::core::db::sync_from_notes() {
+            Ok(()) => state.notes_tip = tip,
+            Err(e) => log(&format!("cache refresh failed: {}", e)),
+        }
+    }
+
+    import_codex_sessions(state);
+    import_cursor_sessions(state);
+}
+
+/// Fetch origin's notes into a side ref, then fast-forward or merge them into
+/// the local ref. On conflicting notes for the same commit the local note wins.
+fn pull_notes() -> Result<(), String> {
+    if git(&["remote", "get-url", "origin"]).is_none() {
+        return Ok(());
+    }
+    let output = Command::new("git")
+        .args([
+            "fetch",
+            "--quiet",
+            "origin",
+            &format!("+{}:{}", NOTES_REF, REMOTE_NOTES_REF),
+        ])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("couldn't find remote ref") {
+            return Ok(());
+        }
+        return Err(stderr.trim().to_string());
+    }
+
+    let Some(remote) = git(&["rev-parse", REMOTE_NOTES_REF]) else {
+        return Ok(());
+    };
+    let Some(local) = git(&["rev-parse", "--verify", "--quiet", NOTES_REF]) else {
+        git(&["update-ref", NOTES_REF, &remote]).ok_or("cannot create notes ref")?;
+        log("fetched notes from origin");
+        return Ok(());
+    };
+    if local == remote || is_ancestor(&remote, &local) {
+        return Ok(());
+    }
+    if is_ancestor(&local, &remote) {
+        git(&["update-ref", NOTES_REF, &remote, &local]).ok_or("cannot fast-forward notes")?;
+        log("fast-forwarded notes from origin");
+        return Ok(());
+    }
+    git(&[
+        "notes",
+        "--ref=blameprompt",
+        "merge",
+        "--quiet",
+        "-s",
+        "ours",
+        REMOTE_NOTES_REF,
+    ])
+    .ok_or("notes merge failed")?;
+    log("merged notes from origin");
+    Ok(())
+}
+
+/// Push local notes when they are ahead of what was last fetched.
+fn push_notes() -> Result<(), String> {
+    let Some(local) = git(&["rev-parse", "--verify", "--quiet", NOTES_REF]) else {
+        return Ok(());
+    };
+    if git(&["remote", "get-url", "origin"]).is_none()
+        || git(&["rev-parse", "--verify", "--quiet", REMOTE_NOTES_REF]).as_deref()
+            == Some(local.as_str())
+    {
+        return Ok(());
+    }
+    let output = Command::new("git")
+        .args(["push", "--quiet", "origin", NOTES_REF])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    git(&["update-ref", REMOTE_NOTES_REF, &local]);
+    log("pushed notes to origin");
+    Ok(())
+}
+
+/// Stage the Codex sessions that changed since the last cycle and ran in
+/// this repository; sessions from other projects are left to their own daemon.
+fn import_codex_sessions(state: &mut DaemonState) {
+    let started = SystemTime::now();
+    let Some(root) = git(&["rev-parse", "--show-toplevel"]).map(PathBuf::from) else {
+        return;
+    };
+    let mut count = 0;
+    let mut dedup = None;
+    for dir in codex::find_sessions_dirs() {
+        for file in codex::list_session_files(&dir) {
+            if modified(&file).is_none_or(|m| m <= state.sessions_seen_at) {
+                // Newest first, so everything after this is older.
+                break;
+            }
+            let Some(session) = codex::parse_codex_session(&file) else {
+                continue;
+            };
+            if !codex::session_in(&session, &root) {
+                continue;
+            }
+            let receipt = codex::receipt_from_session(session);
+            let dedup = dedup.get_or_insert_with(|| staging::ImportDeduper::new(false));
+            if dedup.stage(&receipt) {
+                count += 1;
+            }
+        }
+    }
+    state.sessions_seen_at = started;
+    if count > 0 {
+        log(&format!("imported {} Codex session(s)", count));
+    }
+}
+
+/// Cursor's session parsing lives in `record-cursor`; re-run it whenever the
+/// workspace database changes.
+fn import_cursor_sessions(state: &mut DaemonState) {
+    let Some(db) = cursor::find_db_for_current_workspace() else {
+        return;
+    };
+    let mtime = modified(&db);
+    if mtime.is_none() || mtime == state.cursor_db_mtime {
+        return;
+    }
+    state.cursor_db_mtime = mtime;
+    let Ok(exe) = std::env::current_exe() else {
+        return;
+    };
+    match Command::new(exe).args(["record-cursor", "--all"]).output() {
+        Ok(o) if o.status.success() => {
+            let stdout = String::from_utf8_lossy(&o.stdout);
+            if let Some(line) = stdout.lines().find(|l| l.contains("Recorded")) {
+                log(line.trim());
+            }
+        }
+        Ok(_) | Err(_) => log("Cursor import failed; run `blameprompt record-cursor` to see why"),
+    }
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    std::fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+fn is_ancestor(ancestor: &str, descendant: &str) -> bool {
+    Command::new("git")
+        .args(["merge-base", "--is-ancestor", ancestor, descendant])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Print a service definition for the current repository to stdout.
+pub fn print_unit(interval_secs: u64, format: Option<UnitFormat>) {
+    let Some(spec) = unit_spec(interval_secs) else {
+        std::process::exit(1);
+    };
+    print!(
+        "{}",
+        render_unit(&spec, format.unwrap_or_else(UnitFormat::native))
+    );
+}
+
+/// Write the service definition for the current repository and print the
+/// command that activates it.
+pub fn install(interval_secs: u64) {
+    let Some(spec) = unit_spec(interval_secs) else {
+        std::process::exit(1);
+    };
+    let format = UnitFormat::native();
+    let Some(path) = unit_path(&spec.name, format) else {
+        eprintln!("[blameprompt] Cannot determine home directory.");
+        std::process::exit(1);
+    };
+    if let Some(parent) = path.parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Some(parent) = Path::new(&spec.log_path).parent() {
+        let _ = std::fs::create_dir_all(parent);
+    }
+    if let Err(e) = std::fs::write(&path, render_unit(&spec, format)) {
+        eprintln!("[blameprompt] Failed to write {}: {}", path.display(), e);
+        std::process::exit(1);
+    }
+    println!("[blameprompt] Wrote {}", path.display());
+    println!("  Start it with:");
+    match format {
+        UnitFormat::Launchd => println!("    launchctl load -w {}", path.display()),
+        UnitFormat::Systemd => {
+            println!("    systemctl --user daemon-reload");
+            println!(
+                "    systemctl --user enable --now {}",
+                path.file_name().unwrap_or_default().to_string_lossy()
+            );
+        }
+    }
+    println!("  Logs: {}", spec.log_path);
+}
+
+/// Remove the service definition written by `install` for the current repository.
+pub fn uninstall() {
+    let Some(spec) = unit_spec(0) else {
+        std::process::exit(1);
+    };
+    let format = UnitFormat::native();
+    let Some(path) = unit_path(&spec.name, format) else {
+        return;
+    };
+    if !path.exists() {
+        println!("[blameprompt] No daemon installed for this repository.");
+        return;
+    }
+    match format {
+        UnitFormat::Launchd => println!(
+            "  Stop it first if running: launchctl unload {}",
+            path.display()
+        ),
+        UnitFormat::Systemd => println!(
+            "  Stop it first if running: systemctl --user disable --now {}",
+            path.file_name().unwrap_or_default().to_string_lossy()
+        ),
+    }
+    match std::fs::remove_file(&path) {
+        Ok(()) => println!("[blameprompt] Removed {}", path.display()),
+        Err(e) => eprintln!("[blameprompt] Failed to remove {}: {}", path.display(), e),
+    }
+}
+
+struct UnitSpec {
+    name: String,
+    exe: String,
+    repo: String,
+    interval_secs: u64,
+    log_path: String,
+}
+
+fn unit_spec(interval_secs: u64) -> Option<UnitSpec> {
+    let Some(repo) = git(&["rev-parse", "--show-toplevel"]) else {
+        eprintln!("[blameprompt] Not a git repository. Run from inside the repo to sync.");
+        return None;
+    };
+    let exe = std::env::current_exe()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_else(|_| "blameprompt".to_string());
+    let name = unit_name(&repo);
+    let log_path = dirs::home_dir()
+        .unwrap_or_default()
+        .join(".blameprompt")
+        .join("logs")
+        .join(format!("{}.log", name))
+        .to_string_lossy()
+        .to_string();
+    Some(UnitSpec {
+        name,
+        exe,
+        repo,
+        interval_secs,
+        log_path,
+    })
+}
+
+/// Stable per-repository service name: directory name plus a short path hash,
+/// so two checkouts of the same project get separate daemons.
+fn unit_name(repo: &str) -> String {
+    use sha2::{Digest, Sha256};
+    let dir: String = Path::new(repo)
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    let hash = format!("{:x}", Sha256::digest(repo.as_bytes()));
+    format!("blameprompt-daemon-{}-{}", dir, &hash[..8])
+}
+
+fn unit_path(name: &str, format: UnitFormat) -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+    Some(match format {
+        UnitFormat::Launchd => home
+            .join("Library")
+            .join("LaunchAgents")
+            .join(format!("dev.{}.plist", name)),
+        UnitFormat::Systemd => home
+            .join(".config")
+            .join("systemd")
+            .join("user")
+            .join(format!("{}.service", name)),
+    })
+}
+
+fn xml_escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+fn render_unit(spec: &UnitSpec, format: UnitFormat) -> String {
+    match format {
+        UnitFormat::Systemd => format!(
+            "[Unit]\n\
+             Description=BlamePrompt sync daemon for {repo}\n\
+             After=network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=simple\n\
+             WorkingDirectory={repo}\n\
+             ExecStart=\"{exe}\" daemon run --interval {interval}\n\
+             Restart=on-failure\n\
+             RestartSec=60\n\
+             StandardOutput=append:{log}\n\
+             StandardError=append:{log}\n\
+             \n\
+             [Install]\n\
+             WantedBy=default.target\n",
+            repo = spec.repo,
+            exe = spec.exe,
+            interval = spec.interval_secs,
+            log = spec.log_path,
+        ),
+        UnitFormat::Launchd => format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <!DOCTYPE plist PUBLIC \"-//Apple//DTD PLIST 1.0//EN\" \"http://www.apple.com/DTDs/PropertyList-1.0.dtd\">\n\
+             <plist version=\"1.0\">\n\
+             <dict>\n\
+             \x20   <key>Label</key>\n\
+             \x20   <string>dev.{name}</string>\n\
+             \x20   <key>ProgramArguments</key>\n\
+             \x20   <array>\n\
+             \x20       <string>{exe}</string>\n\
+             \x20       <string>daemon</string>\n\
+             \x20       <string>run</string>\n\
+             \x20       <string>--interval</string>\n\
+             \x20       <string>{interval}</string>\n\
+             \x20   </array>\n\
+             \x20   <key>WorkingDirectory</key>\n\
+             \x20   <string>{repo}</string>\n\
+             \x20   <key>RunAtLoad</key>\n\
+             \x20   <true/>\n\
+             \x20   <key>KeepAlive</key>\n\
+             \x20   <true/>\n\
+             \x20   <key>StandardOutPath</key>\n\
+             \x20   <string>{log}</string>\n\
+             \x20   <key>StandardErrorPath</key>\n\
+             \x20   <string>{log}</string>\n\
+             </dict>\n\
+             </plist>\n",
+            name = spec.name,
+            exe = xml_escape(&spec.exe),
+            interval = spec.interval_secs,
+            repo = xml_escape(&spec.repo),
+            log = xml_escape(&spec.log_path),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> UnitSpec {
+        UnitSpec {
+            name: unit_name("/home/dev/my app"),
+            exe: "/usr/local/bin/blameprompt".to_string(),
+            repo: "/home/dev/my app".to_string(),
+            interval_secs: 600,
+            log_path: "/home/dev/.blameprompt/logs/x.log".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_unit_name_is_stable_and_sanitized() {
+        let a = unit_name("/home/dev/my app");
+        assert!(a.starts_with("blameprompt-daemon-my-app-"));
+        assert_eq!(a, unit_name("/home/dev/my app"));
+        assert_ne!(a, unit_name("/srv/my app"));
+    }
+
+    #[test]
+    fn test_render_systemd_unit() {
+        let unit = render_unit(&spec(), UnitFormat::Systemd);
+        assert!(unit.contains("WorkingDirectory=/home/dev/my app\n"));
+        assert!(
+            unit.contains("ExecStart=\"/usr/local/bin/blameprompt\" daemon run --interval 600\n")
+        );
+        assert!(unit.contains("WantedBy=default.target"));
+    }
+
+    #[test]
+    fn test_render_launchd_plist() {
+        let plist = render_unit(&spec(), UnitFormat::Launchd);
+        assert!(plist.contains("<string>dev.blameprompt-daemon-my-app-"));
+        assert!(plist.contains("    <string>run</string>\n"));
+        assert!(plist.contains("<string>600</string>"));
+        assert!(plist.contains("<key>KeepAlive</key>"));
+    }
+}
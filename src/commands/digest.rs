@@ -0,0 +1,515 @@
+/// `blameprompt digest`: a weekly (or monthly) summary of the team's AI
+/// activity for people who don't run the CLI.
+///
+/// Covers spend, AI commits and lines, the models used, the most expensive
+/// prompts, anomalies, and month-to-date spend against `[digest]
+/// monthly_budget_usd`. `--format email` renders an HTML email body;
+/// `--send` mails it to `[digest] to` over SMTP. Per-author figures appear
+/// only when `[analytics] per_author` is on, under the same identities
+/// `analytics` uses.
+use crate::commands::{analytics, anomalies, audit};
+use crate::core::config::{self, BlamePromptConfig};
+use crate::core::receipt::Receipt;
+use crate::core::{model_classifier, smtp, util};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// Rows in each "top" list.
+const TOP: usize = 5;
+
+#[derive(Debug, Serialize)]
+pub struct Digest {
+    pub period: String,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub ai_commits: u32,
+    pub prompts: u32,
+    pub sessions: u32,
+    pub ai_lines: u32,
+    pub cost_usd: f64,
+    /// (model, prompts, cost), most expensive first.
+    pub models: Vec<(String, u32, f64)>,
+    /// (author, prompts, cost); empty unless `[analytics] per_author`.
+    pub authors: Vec<(String, u32, f64)>,
+    pub top_prompts: Vec<TopPrompt>,
+    pub anomalies: Vec<DigestAnomaly>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub budget: Option<BudgetStatus>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TopPrompt {
+    pub prompt: String,
+    pub model: String,
+    pub cost_usd: f64,
+    pub commit: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DigestAnomaly {
+    pub severity: String,
+    pub category: String,
+    pub description: String,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct BudgetStatus {
+    pub monthly_budget_usd: f64,
+    pub month_to_date_usd: f64,
+    /// Month-to-date spend extended to the whole month at the same daily rate.
+    pub projected_usd: f64,
+    /// "on track", "at risk" (projected over budget) or "over budget".
+    pub status: String,
+}
+
+impl BudgetStatus {
+    pub fn new(budget: f64, spent: f64, today: NaiveDate) -> Self {
+        let days_in_month = days_in_month(today);
+        let projected = spent / today.day() as f64 * days_in_month as f64;
+        let status = if spent > budget {
+            "over budget"
+        } else if projected > budget {
+            "at risk"
+        } else {
+            "on track"
+        };
+        BudgetStatus {
+            monthly_budget_usd: budget,
+            month_to_date_usd: spent,
+            projected_usd: projected,
+            status: status.to_string(),
+        }
+    }
+
+    pub fn used_pct(&self) -> f64 {
+        self.month_to_date_usd * 100.0 / self.monthly_budget_usd
+    }
+}
+
+fn days_in_month(day: NaiveDate) -> u32 {
+    let first = day.with_day(1).unwrap_or(day);
+    let next = first
+        .checked_add_months(chrono::Months::new(1))
+        .unwrap_or(first);
+    (next - first).num_days() as u32
+}
+
+pub fn build(period: &str, today: NaiveDate, cfg: &BlamePromptConfig) -> Result<Digest, String> {
+    let days = match period {
+        "week" => 7,
+        "month" => 30,
+        other => return Err(format!("Unknown period '{}': use week or month", other)),
+    };
+    let from = today - Duration::days(days - 1);
+    let from_arg = from.format("%Y-%m-%d").to_string();
+    let to_arg = today.format("%Y-%m-%d").to_string();
+    let entries = audit::collect_all_entries(Some(&from_arg), Some(&to_arg), None, false)?;
+    let receipts: Vec<&Receipt> = entries.iter().flat_map(|e| &e.receipts).collect();
+
+    let mut models: HashMap<String, (u32, f64)> = HashMap::new();
+    let mut authors: HashMap<String, (u32, f64)> = HashMap::new();
+    let mut prompts: Vec<TopPrompt> = Vec::new();
+    for entry in &entries {
+        let author = analytics::display_author(&entry.commit_author, &cfg.analytics);
+        for r in &entry.receipts {
+            let model = models
+                .entry(model_classifier::display_name(&r.model))
+                .or_default();
+            model.0 += 1;
+            model.1 += r.cost_usd;
+            let by_author = authors.entry(author.clone()).or_default();
+            by_author.0 += 1;
+            by_author.1 += r.cost_usd;
+            prompts.push(TopPrompt {
+                prompt: r
+                    .intent_summary
+                    .as_deref()
+                    .unwrap_or(&r.prompt_summary)
+                    .split_whitespace()
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                model: model_classifier::display_name(&r.model),
+                cost_usd: r.cost_usd,
+                commit: util::short_sha(&entry.commit_sha),
+            });
+        }
+    }
+    prompts.sort_by(|a, b| b.cost_usd.total_cmp(&a.cost_usd));
+    prompts.truncate(TOP);
+
+    let log_args = [
+        format!("--since={}", from_arg),
+        format!("--until={}", to_arg),
+    ];
+    let timeline = anomalies::build_timeline(
+        &receipts,
+        DateTime::<Utc>::MIN_UTC,
+        DateTime::<Utc>::MAX_UTC,
+    );
+    let mut flags = anomalies::detect_anomalies(
+        &receipts,
+        &entries,
+        &timeline,
+        None,
+        &log_args,
+        &cfg.anomalies,
+    );
+    flags.sort_by_key(|a| std::cmp::Reverse(a.severity.weight() as u32));
+
+    let budget = if cfg.digest.monthly_budget_usd > 0.0 {
+        let month_start = today
+            .with_day(1)
+            .unwrap_or(today)
+            .format("%Y-%m-%d")
+            .to_string();
+        let spent: f64 =
+            audit::collect_all_entries(Some(&month_start), Some(&to_arg), None, false)?
+                .iter()
+                .flat_map(|e| &e.receipts)
+                .map(|r| r.cost_usd)
+                .sum();
+        Some(BudgetStatus::new(
+            cfg.digest.monthly_budget_usd,
+            spent,
+            today,
+        ))
+    } else {
+        None
+    };
+
+    let mut sessions: Vec<&str> = receipts.iter().map(|r| r.session_id.as_str()).collect();
+    sessions.sort();
+    sessions.dedup();
+    Ok(Digest {
+        period: period.to_string(),
+        from,
+        to: today,
+        ai_commits: entries.len() as u32,
+        prompts: receipts.len() as u32,
+        sessions: sessions.len() as u32,
+        ai_lines: entries.iter().map(|e| e.total_ai_lines).sum(),
+        cost_usd: receipts.iter().map(|r| r.cost_usd).sum(),
+        models: ranked(models),
+        authors: if cfg.analytics.per_author {
+            ranked(authors)
+        } else {
+            Vec::new()
+        },
+        top_prompts: prompts,
+        anomalies: flags
+            .into_iter()
+            .map(|a| DigestAnomaly {
+                severity: a.severity.label().to_string(),
+                category: a.category,
+                description: a.description,
+            })
+            .collect(),
+        budget,
+    })
+}
+
+/// The `TOP` most expensive entries.
+fn ranked(map: HashMap<String, (u32, f64)>) -> Vec<(String, u32, f64)> {
+    let mut rows: Vec<(String, u32, f64)> = map.into_iter().map(|(k, (n, c))| (k, n, c)).collect();
+    rows.sort_by(|a, b| b.2.total_cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    rows.truncate(TOP);
+    rows
+}
+
+impl Digest {
+    pub fn subject(&self) -> String {
+        format!(
+            "AI activity digest, {} to {}: ${:.2}, {} AI commit(s)",
+            self.from, self.to, self.cost_usd, self.ai_commits
+        )
+    }
+}
+
+pub fn run(period: &str, format: &str, output: Option<&str>, send: bool) -> Result<(), String> {
+    let cfg = config::load_config();
+    let digest = build(period, Utc::now().date_naive(), &cfg)?;
+    let rendered = match format {
+        "email" | "html" => render_html(&digest),
+        "json" => serde_json::to_string_pretty(&digest).map_err(|e| e.to_string())?,
+        "text" => render_text(&digest),
+        other => {
+            return Err(format!(
+                "Unknown format '{}': use text, email or json",
+                other
+            ))
+        }
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(path, &rendered).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+            println!("[BlamePrompt] Digest written to {}", path);
+        }
+        None if !send => println!("{}", rendered),
+        None => {}
+    }
+    if send {
+        send_email(&digest, &cfg.digest)?;
+    }
+    Ok(())
+}
+
+fn send_email(digest: &Digest, cfg: &config::DigestConfig) -> Result<(), String> {
+    if cfg.to.is_empty() || cfg.smtp_host.is_empty() || cfg.from.is_empty() {
+        return Err(
+            "Set `[digest] to`, `from` and `smtp_host` in .blamepromptrc to send the digest"
+                .to_string(),
+        );
+    }
+    let password = std::env::var(&cfg.smtp_password_env).unwrap_or_default();
+    if !cfg.smtp_user.is_empty() && password.is_empty() {
+        return Err(format!(
+            "SMTP password not set: export {}",
+            cfg.smtp_password_env
+        ));
+    }
+    let html = render_html(digest);
+    smtp::send(
+        &smtp::Server {
+            host: &cfg.smtp_host,
+            port: cfg.smtp_port,
+            user: &cfg.smtp_user,
+            password: &password,
+        },
+        &smtp::Mail {
+            from: &cfg.from,
+            to: &cfg.to,
+            subject: &digest.subject(),
+            html: &html,
+        },
+    )?;
+    println!("[BlamePrompt] Digest sent to {}", cfg.to.join(", "));
+    Ok(())
+}
+
+pub fn render_text(d: &Digest) -> String {
+    let mut out = String::new();
+    out.push_str(&format!(
+        "AI activity digest: {} to {} ({})\n\n",
+        d.from, d.to, d.period
+    ));
+    out.push_str(&format!(
+        "  Spend:       ${:.2}\n  AI commits:  {}\n  Prompts:     {} in {} session(s)\n  AI lines:    {}\n",
+        d.cost_usd, d.ai_commits, d.prompts, d.sessions, d.ai_lines
+    ));
+    if let Some(b) = &d.budget {
+        out.push_str(&format!(
+            "  Budget:      ${:.2} of ${:.2} this month ({:.0}%), projected ${:.2}: {}\n",
+            b.month_to_date_usd,
+            b.monthly_budget_usd,
+            b.used_pct(),
+            b.projected_usd,
+            b.status
+        ));
+    }
+    let list = |out: &mut String, title: &str, rows: &[(String, u32, f64)]| {
+        if rows.is_empty() {
+            return;
+        }
+        out.push_str(&format!("\n{}\n", title));
+        for (name, n, cost) in rows {
+            out.push_str(&format!(
+                "  {:<32} {:>4} prompt(s)  ${:.2}\n",
+                name, n, cost
+            ));
+        }
+    };
+    list(&mut out, "Models", &d.models);
+    list(&mut out, "Authors", &d.authors);
+    if !d.top_prompts.is_empty() {
+        out.push_str("\nTop prompts by cost\n");
+        for p in &d.top_prompts {
+            out.push_str(&format!(
+                "  ${:<7.2} {}  {}  {}\n",
+                p.cost_usd,
+                p.commit,
+                p.model,
+                anomalies::truncate(&p.prompt, 80)
+            ));
+        }
+    }
+    out.push_str(&format!("\nAnomalies: {}\n", d.anomalies.len()));
+    for a in d.anomalies.iter().take(TOP) {
+        out.push_str(&format!(
+            "  [{}] {}: {}\n",
+            a.severity, a.category, a.description
+        ));
+    }
+    out
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Self-contained HTML with inline styles, which is what email clients render.
+pub fn render_html(d: &Digest) -> String {
+    const CELL: &str = "padding:4px 12px 4px 0;border-bottom:1px solid #eee;";
+    let mut html = String::new();
+    html.push_str(
+        "<!DOCTYPE html>\n<html><body style=\"font-family:-apple-system,Segoe UI,Helvetica,Arial,sans-serif;color:#222;max-width:640px;\">\n",
+    );
+    html.push_str(&format!(
+        "<h2 style=\"margin-bottom:4px;\">AI activity digest</h2>\n<p style=\"color:#666;margin-top:0;\">{} to {}</p>\n",
+        d.from, d.to
+    ));
+
+    html.push_str("<table style=\"border-collapse:collapse;margin-bottom:16px;\">\n");
+    let stats = [
+        ("Spend", format!("${:.2}", d.cost_usd)),
+        ("AI commits", d.ai_commits.to_string()),
+        (
+            "Prompts",
+            format!("{} in {} session(s)", d.prompts, d.sessions),
+        ),
+        ("AI lines", d.ai_lines.to_string()),
+    ];
+    for (label, value) in &stats {
+        html.push_str(&format!(
+            "<tr><td style=\"{}color:#666;\">{}</td><td style=\"{}font-weight:bold;\">{}</td></tr>\n",
+            CELL, label, CELL, value
+        ));
+    }
+    html.push_str("</table>\n");
+
+    if let Some(b) = &d.budget {
+        let colour = match b.status.as_str() {
+            "on track" => "#2e7d32",
+            "at risk" => "#ef6c00",
+            _ => "#c62828",
+        };
+        html.push_str(&format!(
+            "<h3>Budget</h3>\n<p><span style=\"color:{};font-weight:bold;\">{}</span>: ${:.2} of ${:.2} spent this month ({:.0}%), projected ${:.2}.</p>\n",
+            colour,
+            escape(&b.status),
+            b.month_to_date_usd,
+            b.monthly_budget_usd,
+            b.used_pct(),
+            b.projected_usd
+        ));
+    }
+
+    let table = |html: &mut String, title: &str, rows: &[(String, u32, f64)]| {
+        if rows.is_empty() {
+            return;
+        }
+        html.push_str(&format!(
+            "<h3>{}</h3>\n<table style=\"border-collapse:collapse;\">\n",
+            title
+        ));
+        for (name, n, cost) in rows {
+            html.push_str(&format!(
+                "<tr><td style=\"{}\">{}</td><td style=\"{}\">{} prompt(s)</td><td style=\"{}text-align:right;\">${:.2}</td></tr>\n",
+                CELL,
+                escape(name),
+                CELL,
+                n,
+                CELL,
+                cost
+            ));
+        }
+        html.push_str("</table>\n");
+    };
+    table(&mut html, "Models", &d.models);
+    table(&mut html, "Authors", &d.authors);
+
+    if !d.top_prompts.is_empty() {
+        html.push_str(
+            "<h3>Top prompts by cost</h3>\n<table style=\"border-collapse:collapse;\">\n",
+        );
+        for p in &d.top_prompts {
+            html.push_str(&format!(
+                "<tr><td style=\"{}text-align:right;\">${:.2}</td><td style=\"{}\">{}</td><td style=\"{}font-family:monospace;\">{}</td><td style=\"{}\">{}</td></tr>\n",
+                CELL,
+                p.cost_usd,
+                CELL,
+                escape(&anomalies::truncate(&p.prompt, 120)),
+                CELL,
+                escape(&p.commit),
+                CELL,
+                escape(&p.model)
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+
+    html.push_str("<h3>Anomalies</h3>\n");
+    if d.anomalies.is_empty() {
+        html.push_str("<p>None detected.</p>\n");
+    } else {
+        html.push_str("<ul>\n");
+        for a in d.anomalies.iter().take(TOP) {
+            html.push_str(&format!(
+                "<li><b>{}</b> {}: {}</li>\n",
+                escape(&a.severity),
+                escape(&a.category),
+                escape(&a.description)
+            ));
+        }
+        html.push_str("</ul>\n");
+        if d.anomalies.len() > TOP {
+            html.push_str(&format!(
+                "<p style=\"color:#666;\">{} more: run <code>blameprompt anomalies</code>.</p>\n",
+                d.anomalies.len() - TOP
+            ));
+        }
+    }
+    html.push_str(
+        "<p style=\"color:#999;font-size:12px;margin-top:24px;\">Generated by BlamePrompt from the repository's AI receipts.</p>\n</body></html>\n",
+    );
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_budget_status_and_html_escaping() {
+        let day = |d| NaiveDate::from_ymd_opt(2026, 2, d).unwrap();
+        assert_eq!(days_in_month(day(10)), 28);
+        assert_eq!(BudgetStatus::new(100.0, 20.0, day(14)).status, "on track");
+        let at_risk = BudgetStatus::new(100.0, 60.0, day(14));
+        assert_eq!(at_risk.status, "at risk");
+        assert_eq!(at_risk.projected_usd, 120.0);
+        assert_eq!(
+            BudgetStatus::new(100.0, 101.0, day(28)).status,
+            "over budget"
+        );
+
+        let digest = Digest {
+            period: "week".to_string(),
+            from: day(8),
+            to: day(14),
+            ai_commits: 2,
+            prompts: 3,
+            sessions: 1,
+            ai_lines: 40,
+            cost_usd: 1.5,
+            models: vec![("Claude Sonnet 4".to_string(), 3, 1.5)],
+            authors: Vec::new(),
+            top_prompts: vec![TopPrompt {
+                prompt: "render <script> tags & escape them".to_string(),
+                model: "Claude Sonnet 4".to_string(),
+                cost_usd: 1.0,
+                commit: "abc12345".to_string(),
+            }],
+            anomalies: Vec::new(),
+            budget: Some(at_risk),
+        };
+        let html = render_html(&digest);
+        assert!(html.contains("render &lt;script&gt; tags &amp; escape them"));
+        assert!(!html.contains("<script>"));
+        assert!(html.contains("at risk"));
+        assert!(!html.contains("<h3>Authors</h3>"));
+        assert!(render_text(&digest).contains("$60.00 of $100.00 this month (60%)"));
+    }
+}
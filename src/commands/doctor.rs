@@ -1,3 +1,6 @@
+use crate::core::heartbeat;
+use crate::integrations::claude_hooks;
+use std::io::{IsTerminal, Write};
 use std::process::Command;
 
 const GREEN: &str = "\x1b[1;32m";
@@ -52,7 +55,26 @@ fn check_inside_git_repo() -> CheckResult {
     }
 }
 
+fn check_capture_enabled() -> CheckResult {
+    match crate::commands::capture::disabled_reason(".") {
+        None => CheckResult::pass("Prompt capture enabled"),
+        Some(reason) => CheckResult::fail(format!(
+            "Prompt capture disabled ({}); run `blameprompt enable` to resume",
+            reason
+        )),
+    }
+}
+
 fn check_notes_namespace() -> CheckResult {
+    let store = crate::git::storage::active();
+    if store.name() != "notes" {
+        return CheckResult::pass(format!(
+            "Receipts stored in the {} backend ({} commit(s))",
+            store.name(),
+            store.list().len()
+        ));
+    }
+
     // Check if the blameprompt notes ref exists
     match Command::new("git")
         .args(["notes", "--ref=refs/notes/blameprompt", "list"])
@@ -160,6 +182,50 @@ fn check_claude_hooks() -> CheckResult {
     }
 }
 
+fn check_hook_heartbeat() -> CheckResult {
+    if let Some(stale) = heartbeat::check_claude() {
+        return CheckResult::fail(stale.describe());
+    }
+    match heartbeat::load().agents.get("claude") {
+        Some(beat) => {
+            let mut label = format!(
+                "Claude Code hook events arriving (last: {} at {})",
+                beat.last_event,
+                beat.last_event_at.format("%Y-%m-%d %H:%M UTC")
+            );
+            if let Some(ref p) = beat.last_panic {
+                label.push_str(&format!(
+                    "; last checkpoint panic {}: {}",
+                    p.at.format("%Y-%m-%d %H:%M UTC"),
+                    p.message.lines().next().unwrap_or("")
+                ));
+            }
+            CheckResult::pass(label)
+        }
+        None => CheckResult::pass("No Claude Code hook events yet (no recent sessions)"),
+    }
+}
+
+/// Replace the Claude Code hooks after asking, when they are missing or silent.
+fn offer_hook_reinstall() {
+    if !std::io::stdin().is_terminal() {
+        println!("  {DIM}Run `blameprompt init --global` to re-install the hooks.{RESET}");
+        return;
+    }
+    print!("  Re-install Claude Code hooks now? [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    if std::io::stdin().read_line(&mut answer).is_err()
+        || !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+    {
+        return;
+    }
+    match claude_hooks::uninstall().and_then(|_| claude_hooks::install()) {
+        Ok(()) => println!("  {GREEN}\u{2713}{RESET} Claude Code hooks re-installed"),
+        Err(e) => println!("  {RED}\u{2717}{RESET} Re-install failed: {}", e),
+    }
+}
+
 fn check_sqlite_cache() -> CheckResult {
     let db_path = match dirs::home_dir() {
         Some(h) => h.join(".blameprompt").join("prompts.db"),
@@ -213,12 +279,17 @@ pub fn run() {
     println!("  {BOLD}BlamePrompt Doctor{RESET} {DIM}v{version}{RESET}");
     println!();
 
+    let claude_hooks = check_claude_hooks();
+    let hook_heartbeat = check_hook_heartbeat();
+    let hooks_broken = !claude_hooks.passed || !hook_heartbeat.passed;
     let checks = vec![
         check_git_available(),
         check_inside_git_repo(),
+        check_capture_enabled(),
         check_notes_namespace(),
         check_git_hooks_installed(),
-        check_claude_hooks(),
+        claude_hooks,
+        hook_heartbeat,
         check_sqlite_cache(),
         check_logged_in(),
     ];
@@ -235,6 +306,14 @@ pub fn run() {
         }
     }
 
+    if let Some(env) = crate::core::path_map::environment() {
+        let mapped = crate::core::config::load_config().remote.path_map.len();
+        println!(
+            "  {DIM}Remote environment: {} ({} [[remote.path_map]] rule(s)){RESET}",
+            env, mapped
+        );
+    }
+
     println!();
     if passed == total {
         println!("  {GREEN}{passed}/{total} checks passed{RESET} {DIM}\u{2014} all good!{RESET}");
@@ -242,4 +321,8 @@ pub fn run() {
         println!("  {CYAN}{passed}/{total} checks passed{RESET}");
     }
     println!();
+    if hooks_broken {
+        offer_hook_reinstall();
+        println!();
+    }
 }
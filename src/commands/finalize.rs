@@ -0,0 +1,305 @@
+/// `blameprompt finalize`: the slow half of `attach`.
+///
+/// The post-commit hook writes the staged receipts to the commit's note and
+/// returns. Scoring them needs a diff of the commit and blob reads for every
+/// file the AI touched, so a detached `blameprompt finalize <sha>` does that
+/// afterwards: acceptance stats for the receipts the note lists as pending,
+/// then the line-origin classification of the commit. Until it has run, the
+/// note reads like one attached before those existed. Running it again is
+/// harmless; a note with nothing pending is left alone.
+use crate::commands::{line_origin, partial_staging};
+use crate::core::receipt::{NotePayload, Receipt};
+use crate::git::{blob_reader, notes, storage};
+use std::process::{Command, Stdio};
+
+/// Finalize the note of `commit` (HEAD by default).
+pub fn run(commit: Option<&str>) -> Result<(), String> {
+    let rev = commit.unwrap_or("HEAD");
+    let sha = rev_parse(rev).ok_or_else(|| format!("Unknown commit: {}", rev))?;
+    let short = &sha[..sha.len().min(8)];
+    let Some(mut scored) = notes::read_receipts_for_commit(&sha) else {
+        // An amend or rebase moved the note to the rewritten commit, which
+        // gets its own finalize run.
+        println!(
+            "[BlamePrompt] {} carries no receipts; nothing to finalize",
+            short
+        );
+        return Ok(());
+    };
+    if scored.pending_finalize.is_empty() {
+        println!("[BlamePrompt] Nothing to finalize on {}", short);
+        return Ok(());
+    }
+    let pending = scored.pending_finalize.clone();
+    finalize_payload(&mut scored, &sha);
+
+    // The note may have changed while scoring (`link-pr`, another attach, an
+    // amend moving it): merge into the note as it is now, and write only if
+    // it is still unchanged.
+    let store = storage::active();
+    for _ in 0..5 {
+        let version = store.version();
+        let Some(mut current) = notes::read_receipts_for_commit(&sha) else {
+            println!(
+                "[BlamePrompt] {} no longer carries receipts; nothing to finalize",
+                short
+            );
+            return Ok(());
+        };
+        apply_finalized(&mut current, &scored, &pending);
+        let json = serde_json::to_string_pretty(&current)
+            .map_err(|e| format!("Failed to serialize: {}", e))?;
+        match store.write_if_unchanged(&sha, &json, version.as_deref()) {
+            Ok(true) => {
+                println!(
+                    "[BlamePrompt] Finalized {} receipt(s) on {}",
+                    pending.len(),
+                    short
+                );
+                return Ok(());
+            }
+            Ok(false) => {}
+            Err(e) => {
+                tracing::warn!(commit = short, "finalize write failed: {}", e);
+                std::thread::sleep(std::time::Duration::from_millis(200));
+            }
+        }
+    }
+    tracing::error!(commit = short, "finalize gave up: the note kept changing");
+    Err(format!(
+        "The note of {} kept changing; run `blameprompt finalize {}` again",
+        short, short
+    ))
+}
+
+/// Copy what finalize computed in `scored` onto `current`, the note as it is
+/// now: the scores of the `pending` receipts it still has, and the line
+/// origins when it still holds exactly the receipts they were computed from.
+fn apply_finalized(current: &mut NotePayload, scored: &NotePayload, pending: &[String]) {
+    for r in current
+        .receipts
+        .iter_mut()
+        .filter(|r| pending.contains(&r.id))
+    {
+        let Some(s) = scored.receipts.iter().find(|s| s.id == r.id) else {
+            continue;
+        };
+        r.accepted_lines = s.accepted_lines;
+        r.overridden_lines = s.overridden_lines;
+        for fc in r.files_changed.iter_mut() {
+            if let Some(sf) = s.files_changed.iter().find(|f| f.path == fc.path) {
+                fc.unstaged_ranges = sf.unstaged_ranges.clone();
+            }
+        }
+    }
+    current.pending_finalize.retain(|id| !pending.contains(id));
+    let same_receipts = current.receipts.len() == scored.receipts.len()
+        && current
+            .receipts
+            .iter()
+            .all(|r| scored.receipts.iter().any(|s| s.id == r.id));
+    if same_receipts {
+        if scored.file_mappings.is_some() {
+            current.file_mappings = scored.file_mappings.clone();
+        }
+        if scored.code_origin.is_some() {
+            current.code_origin = scored.code_origin.clone();
+        }
+    }
+}
+
+/// List the staged `receipts` as pending on `payload`.
+pub fn mark_pending(payload: &mut NotePayload, receipts: &[Receipt]) {
+    for r in receipts {
+        if !payload.pending_finalize.contains(&r.id) {
+            payload.pending_finalize.push(r.id.clone());
+        }
+    }
+}
+
+/// Score the pending receipts of `payload`, the note of `commit`, and
+/// classify the lines the commit added.
+pub fn finalize_payload(payload: &mut NotePayload, commit: &str) {
+    let pending = std::mem::take(&mut payload.pending_finalize);
+    // Unstaged AI lines can only be told apart while the commit is checked out.
+    let worktree = rev_parse("HEAD").as_deref() == Some(commit);
+    for r in payload
+        .receipts
+        .iter_mut()
+        .filter(|r| pending.contains(&r.id))
+    {
+        score_acceptance(r, commit, worktree);
+    }
+    let (file_mappings, code_origin) = line_origin::classify_commit(commit, &payload.receipts);
+    if !file_mappings.is_empty() {
+        payload.file_mappings = Some(file_mappings);
+    }
+    payload.code_origin = code_origin.or(payload.code_origin.take());
+}
+
+/// Set `accepted_lines` / `overridden_lines` by comparing the blobs captured
+/// at PostToolUse time against the blobs committed in `commit`. AI lines that
+/// are missing from the commit but still in the working tree were left
+/// unstaged (`git add -p`); they are recorded as `unstaged_ranges` and not
+/// counted as overridden.
+fn score_acceptance(receipt: &mut Receipt, commit: &str, worktree: bool) {
+    let mut total_accepted = 0u32;
+    let mut total_overridden = 0u32;
+    let mut has_data = false;
+
+    for fc in receipt.files_changed.iter_mut() {
+        let Some(ref staging_blob) = fc.blob_hash else {
+            continue;
+        };
+        let Some(committed) =
+            blob_reader::read(&format!("{}:{}", commit, fc.path)).filter(|o| o.kind == "blob")
+        else {
+            continue;
+        };
+        has_data = true;
+        if committed.oid == *staging_blob {
+            // File unchanged between AI write and commit — all additions accepted
+            total_accepted += fc.additions;
+            continue;
+        }
+        let committed: Vec<String> = String::from_utf8_lossy(&committed.data)
+            .lines()
+            .map(String::from)
+            .collect();
+        let worktree_lines = if worktree {
+            partial_staging::worktree_lines(&fc.path)
+        } else {
+            Vec::new()
+        };
+        let split = partial_staging::split_lines(
+            &blob_reader::blob_lines(staging_blob),
+            &committed,
+            &worktree_lines,
+        );
+        total_accepted += split.accepted;
+        total_overridden += split.overridden;
+        fc.unstaged_ranges = split.unstaged;
+    }
+
+    if has_data {
+        receipt.accepted_lines = Some(total_accepted);
+        receipt.overridden_lines = Some(total_overridden);
+    }
+}
+
+/// Start `blameprompt finalize <commit>` detached from the hook, so the commit
+/// returns without waiting for it. False when it couldn't be started.
+pub fn spawn_detached(commit: &str) -> bool {
+    let Ok(exe) = std::env::current_exe() else {
+        return false;
+    };
+    let mut cmd = Command::new(exe);
+    cmd.args(["finalize", commit])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Its own process group, so a Ctrl-C right after the commit doesn't reach it.
+        cmd.process_group(0);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const DETACHED_PROCESS: u32 = 0x0000_0008;
+        cmd.creation_flags(DETACHED_PROCESS);
+    }
+    cmd.spawn().is_ok()
+}
+
+fn rev_parse(rev: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::PullRequestLink;
+
+    #[test]
+    fn test_pending_receipts_roundtrip_and_dedupe() {
+        let receipt = Receipt::test;
+        let mut payload = NotePayload::new(vec![receipt("r1")]);
+        let json = serde_json::to_string(&payload).unwrap();
+        assert!(!json.contains("pending_finalize"));
+
+        mark_pending(&mut payload, &[receipt("r1")]);
+        mark_pending(&mut payload, &[receipt("r1"), receipt("r2")]);
+        assert_eq!(payload.pending_finalize, vec!["r1", "r2"]);
+        let json = serde_json::to_string(&payload).unwrap();
+        let back: NotePayload = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.pending_finalize, vec!["r1", "r2"]);
+
+        let older = NotePayload {
+            pending_finalize: vec!["r3".to_string(), "r2".to_string()],
+            ..NotePayload::new(Vec::new())
+        };
+        let merged = notes::merge_payloads(back, older);
+        assert_eq!(merged.pending_finalize, vec!["r1", "r2", "r3"]);
+    }
+
+    #[test]
+    fn test_apply_finalized_merges_into_current_note() {
+        let receipt = |id: &str| Receipt::test(id).with_file("a.rs", (1, 3), 3, 0);
+        let mut scored = NotePayload::new(vec![receipt("r1")]);
+        scored.receipts[0].accepted_lines = Some(2);
+        scored.receipts[0].overridden_lines = Some(1);
+        scored.receipts[0].files_changed[0].unstaged_ranges = vec![(3, 3)];
+        scored.code_origin = Some(
+            serde_json::from_value(serde_json::json!({
+                "ai_generated_pct": 66.7, "human_edited_pct": 0.0, "pure_human_pct": 33.3
+            }))
+            .unwrap(),
+        );
+        let pending = vec!["r1".to_string()];
+
+        // `link-pr` ran meanwhile: its field survives, the scores land.
+        let mut current = NotePayload::new(vec![receipt("r1")]);
+        current.pending_finalize = pending.clone();
+        current.pull_request = Some(PullRequestLink {
+            number: 1,
+            url: None,
+            merge_commit: None,
+            comment_id: None,
+        });
+        apply_finalized(&mut current, &scored, &pending);
+        assert_eq!(current.receipts[0].accepted_lines, Some(2));
+        assert_eq!(current.receipts[0].overridden_lines, Some(1));
+        assert_eq!(
+            current.receipts[0].files_changed[0].unstaged_ranges,
+            vec![(3, 3)]
+        );
+        assert!(current.pending_finalize.is_empty());
+        assert!(current.code_origin.is_some());
+        assert_eq!(current.pull_request.map(|p| p.number), Some(1));
+
+        // Another attach added r2, still pending: r1 is scored, but the line
+        // origins were computed without r2 and are left to r2's finalize.
+        let mut current = NotePayload::new(vec![receipt("r1"), receipt("r2")]);
+        current.pending_finalize = vec!["r1".to_string(), "r2".to_string()];
+        apply_finalized(&mut current, &scored, &pending);
+        assert_eq!(current.receipts[0].accepted_lines, Some(2));
+        assert_eq!(current.receipts[1].accepted_lines, None);
+        assert_eq!(current.pending_finalize, vec!["r2"]);
+        assert!(current.code_origin.is_none());
+    }
+}
@@ -0,0 +1,398 @@
+/// `blameprompt forget`: erase one person's or one session's receipts.
+///
+/// Covers every place receipts live on this machine: the configured receipt
+/// store (all commits), the repository's staging file and the SQLite cache.
+/// Matching receipts are removed, or with `--anonymize` kept for the totals
+/// (cost, tokens, lines) but stripped of the author and every piece of prompt
+/// and response text. Dry run unless `--write`.
+///
+/// Each `--write` run appends one line to `~/.blameprompt/forget-log.jsonl`
+/// recording what was erased. The log names the subject only by its SHA-256,
+/// so it does not keep the data it documents. With the notes and branch
+/// backends the history of their ref is squashed as well, because old
+/// versions would otherwise still hold the erased text.
+use crate::commands::staging;
+use crate::core::receipt::{NotePayload, Receipt};
+use crate::core::{db, util};
+use crate::git::{notes, storage};
+use chrono::Utc;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Placeholder for the author and prompt of anonymized receipts.
+const FORGOTTEN: &str = "[forgotten]";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subject {
+    /// A person, by email address (`Name <email>` and bare emails match),
+    /// as the receipt's user or one of its co-authors.
+    Author(String),
+    Session(String),
+}
+
+impl Subject {
+    fn kind(&self) -> &'static str {
+        match self {
+            Subject::Author(_) => "author",
+            Subject::Session(_) => "session",
+        }
+    }
+
+    fn value(&self) -> &str {
+        match self {
+            Subject::Author(v) | Subject::Session(v) => v,
+        }
+    }
+
+    pub fn matches(&self, r: &Receipt) -> bool {
+        match self {
+            Subject::Author(email) => {
+                let email = email.trim().to_lowercase();
+                !email.is_empty()
+                    && std::iter::once(&r.user)
+                        .chain(&r.co_authors)
+                        .any(|person| email_of(person) == email)
+            }
+            Subject::Session(id) => r.session_id == *id || r.parent_session_id.as_ref() == Some(id),
+        }
+    }
+}
+
+/// The lowercased email of `Name <email>`, or the whole value when it has no
+/// angle brackets.
+fn email_of(person: &str) -> String {
+    let person = person.to_lowercase();
+    match (person.rfind('<'), person.rfind('>')) {
+        (Some(start), Some(end)) if start < end => person[start + 1..end].to_string(),
+        _ => person.trim().to_string(),
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct ErasedReceipt {
+    /// Commit SHA, or "staged" for receipts not yet attached.
+    commit: String,
+    receipt_id: String,
+}
+
+#[derive(Debug, Serialize)]
+struct LogEntry {
+    timestamp: String,
+    subject: &'static str,
+    subject_sha256: String,
+    action: &'static str,
+    repository: String,
+    operator: String,
+    receipts: Vec<ErasedReceipt>,
+    cache_rows: usize,
+}
+
+pub fn run(subject: Subject, anonymize: bool, write: bool) -> Result<(), String> {
+    if subject.value().trim().is_empty() {
+        return Err("Pass a non-empty --author or --session".to_string());
+    }
+    let root = repo_root().ok_or("Not inside a git repository")?;
+    let action = if anonymize { "anonymized" } else { "removed" };
+    let mut erased: Vec<ErasedReceipt> = Vec::new();
+    let mut ids: Vec<String> = Vec::new();
+
+    // Receipt store: every commit with receipts.
+    let mut commits = 0;
+    for sha in notes::list_commits_with_notes() {
+        let Some(mut payload) = notes::read_receipts_for_commit(&sha) else {
+            continue;
+        };
+        let hit = forget_in_payload(&mut payload, &subject, anonymize);
+        if hit.is_empty() {
+            continue;
+        }
+        if write {
+            notes::write_receipts_for_commit(&sha, &payload)?;
+        }
+        commits += 1;
+        for id in hit {
+            erased.push(ErasedReceipt {
+                commit: sha.clone(),
+                receipt_id: id.clone(),
+            });
+            ids.push(id);
+        }
+    }
+
+    // Staged receipts of the repository.
+    let mut staged = staging::read_staging_in(&root);
+    let hit = forget_in(&mut staged.receipts, &subject, anonymize);
+    if write && !hit.is_empty() {
+        staging::write_staging_data_in(&staged, &root.to_string_lossy());
+    }
+    let staged_count = hit.len();
+    for id in hit {
+        erased.push(ErasedReceipt {
+            commit: "staged".to_string(),
+            receipt_id: id.clone(),
+        });
+        ids.push(id);
+    }
+
+    if erased.is_empty() {
+        println!("No receipts match {} {}.", subject.kind(), subject.value());
+        return Ok(());
+    }
+
+    let cache_rows = if write {
+        let conn = db::get_connection()?;
+        db::forget_receipts(&conn, &ids)?
+    } else {
+        0
+    };
+
+    println!(
+        "{} {} receipt(s): {} in {} commit(s), {} staged.",
+        if write { "Erased" } else { "Would erase" },
+        erased.len(),
+        erased.len() - staged_count,
+        commits,
+        staged_count
+    );
+    if !write {
+        println!(
+            "Run again with --write to {} them.",
+            if anonymize { "anonymize" } else { "remove" }
+        );
+        return Ok(());
+    }
+    if cache_rows > 0 {
+        println!("Dropped {} row(s) from the local cache.", cache_rows);
+    }
+
+    let store = storage::active();
+    if let Some(r) = store.sync_ref().filter(|_| commits > 0) {
+        squash_history(&r)?;
+        println!(
+            "Squashed the history of {} so earlier receipt versions are unreachable.",
+            r
+        );
+    }
+
+    let entry = LogEntry {
+        timestamp: Utc::now().to_rfc3339(),
+        subject: subject.kind(),
+        subject_sha256: format!("{:x}", Sha256::digest(subject.value().trim().as_bytes())),
+        action,
+        repository: root.to_string_lossy().to_string(),
+        operator: util::git_user(),
+        receipts: erased,
+        cache_rows,
+    };
+    let log = append_log(&entry)?;
+    println!("Logged to {}", log.display());
+
+    match store.sync_ref() {
+        Some(r) if commits > 0 => println!(
+            "\nClones and the remote still have the old receipts. Publish the change with \
+             `git push --force origin {r}`, have collaborators re-fetch it, and run \
+             `git reflog expire --expire=now --all && git gc --prune=now` to drop the old \
+             objects locally."
+        ),
+        None if commits > 0 => println!(
+            "\nThe {} backend keeps receipts in files of the working tree: versions already \
+             committed remain in the repository's history until it is rewritten.",
+            store.name()
+        ),
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Erase matching receipts from a note, and drop their links from the
+/// per-line hunks. Returns the IDs of the affected receipts.
+pub fn forget_in_payload(
+    payload: &mut NotePayload,
+    subject: &Subject,
+    anonymize: bool,
+) -> Vec<String> {
+    let hit = forget_in(&mut payload.receipts, subject, anonymize);
+    if !anonymize {
+        for mapping in payload.file_mappings.iter_mut().flatten() {
+            for hunk in &mut mapping.hunks {
+                if hunk.receipt_id.as_ref().is_some_and(|id| hit.contains(id)) {
+                    hunk.receipt_id = None;
+                    hunk.prompt_turn = None;
+                }
+            }
+        }
+    }
+    hit
+}
+
+/// Remove or anonymize the receipts `subject` matches.
+pub fn forget_in(receipts: &mut Vec<Receipt>, subject: &Subject, anonymize: bool) -> Vec<String> {
+    let hit: Vec<String> = receipts
+        .iter()
+        .filter(|r| subject.matches(r))
+        .map(|r| r.id.clone())
+        .collect();
+    if anonymize {
+        for r in receipts.iter_mut().filter(|r| hit.contains(&r.id)) {
+            anonymize_receipt(r);
+        }
+    } else {
+        receipts.retain(|r| !hit.contains(&r.id));
+    }
+    hit
+}
+
+/// Keep the numbers, drop who asked and what was said.
+fn anonymize_receipt(r: &mut Receipt) {
+    r.user = FORGOTTEN.to_string();
+    // One placeholder per co-author, so pairing counts still add up.
+    for co_author in &mut r.co_authors {
+        *co_author = FORGOTTEN.to_string();
+    }
+    r.prompt_summary = FORGOTTEN.to_string();
+    r.prompt_hash = String::new();
+    r.intent_summary = None;
+    r.response_summary = None;
+    r.plan_summary = None;
+    r.conversation = None;
+    r.prompt_quality = None;
+    r.user_decisions.clear();
+    for activity in &mut r.subagent_activities {
+        activity.description = None;
+    }
+}
+
+/// Replace `git_ref` (the notes ref or the receipts branch) with a single
+/// parentless commit of its current tree.
+fn squash_history(git_ref: &str) -> Result<(), String> {
+    let here = Path::new(".");
+    let tree = storage::git_with_stdin(here, &["rev-parse", &format!("{}^{{tree}}", git_ref)], "")?;
+    let commit = storage::git_with_stdin(
+        here,
+        &[
+            "commit-tree",
+            &tree,
+            "-m",
+            "blameprompt: squash history after forget",
+        ],
+        "",
+    )?;
+    storage::git_with_stdin(here, &["update-ref", git_ref, &commit], "")
+        .map(|_| ())
+        .map_err(|e| format!("Cannot rewrite {}: {}", git_ref, e))
+}
+
+fn append_log(entry: &LogEntry) -> Result<PathBuf, String> {
+    let dir = dirs::home_dir()
+        .ok_or("Cannot determine home directory")?
+        .join(".blameprompt");
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    let path = dir.join("forget-log.jsonl");
+    let line = serde_json::to_string(entry).map_err(|e| e.to_string())?;
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| writeln!(f, "{}", line))
+        .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    Ok(path)
+}
+
+fn repo_root() -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::{CodeOrigin, FileMapping, Hunk};
+
+    fn receipt(id: &str, user: &str, session: &str) -> Receipt {
+        Receipt::test(id)
+            .with_model("m")
+            .with_session(session)
+            .with_prompt("fix the bug Alice reported")
+            .with_cost(0.5)
+            .with_timestamp("2026-01-01T00:00:00Z")
+            .with_user(user)
+    }
+
+    #[test]
+    fn test_forget_removes_or_anonymizes() {
+        let mut receipts = vec![
+            receipt("r1", "Alice <Alice@Example.com>", "s1"),
+            receipt("r2", "Bob <bob@example.com>", "s2"),
+            receipt("r3", "alice@example.com", "s3"),
+        ];
+        let alice = Subject::Author("alice@example.com".to_string());
+
+        let mut removed = receipts.clone();
+        assert_eq!(forget_in(&mut removed, &alice, false), vec!["r1", "r3"]);
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed[0].id, "r2");
+
+        receipts[0].intent_summary = Some("Fix the bug".to_string());
+        forget_in(&mut receipts, &alice, true);
+        assert_eq!(receipts.len(), 3);
+        assert_eq!(receipts[0].user, FORGOTTEN);
+        assert_eq!(receipts[0].prompt_summary, FORGOTTEN);
+        assert_eq!(receipts[0].intent_summary, None);
+        assert!((receipts[0].cost_usd - 0.5).abs() < 1e-9);
+        assert_eq!(receipts[1].user, "Bob <bob@example.com>");
+
+        // A co-author is matched too, and anonymized with the user.
+        receipts[1].co_authors = vec![
+            "Carol <carol@example.com>".to_string(),
+            "Dan <dan@example.com>".to_string(),
+        ];
+        let carol = Subject::Author("CAROL@example.com".to_string());
+        assert!(carol.matches(&receipts[1]));
+        assert!(!carol.matches(&receipts[2]));
+        let mut removed = receipts.clone();
+        assert_eq!(forget_in(&mut removed, &carol, false), vec!["r2"]);
+        assert_eq!(removed.len(), 2);
+        let mut anonymized = receipts.clone();
+        forget_in(&mut anonymized, &carol, true);
+        assert_eq!(anonymized[1].user, FORGOTTEN);
+        assert_eq!(anonymized[1].co_authors, vec![FORGOTTEN, FORGOTTEN]);
+
+        let session = Subject::Session("s2".to_string());
+        assert!(session.matches(&receipts[1]));
+        assert!(!session.matches(&receipts[0]));
+    }
+
+    #[test]
+    fn test_forget_unlinks_hunks() {
+        let mut payload = NotePayload::with_file_mappings(
+            vec![receipt("r1", "a@x.io", "s1")],
+            vec![FileMapping {
+                path: "a.rs".to_string(),
+                blob_hash: "b".to_string(),
+                previous_blob_hash: None,
+                hunks: vec![Hunk {
+                    start_line: 1,
+                    end_line: 3,
+                    origin: CodeOrigin::AiGenerated,
+                    prompt_turn: Some(1),
+                    model: Some("m".to_string()),
+                    receipt_id: Some("r1".to_string()),
+                }],
+            }],
+        );
+        let hit = forget_in_payload(&mut payload, &Subject::Author("a@x.io".to_string()), false);
+        assert_eq!(hit, vec!["r1"]);
+        assert!(payload.receipts.is_empty());
+        let hunk = &payload.file_mappings.as_ref().unwrap()[0].hunks[0];
+        assert_eq!((hunk.receipt_id.as_deref(), hunk.prompt_turn), (None, None));
+    }
+}
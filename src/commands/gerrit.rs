@@ -0,0 +1,289 @@
+/// Gerrit review integration.
+///
+/// Posts an AI attribution summary and per-hunk comments on a Gerrit change via
+/// the REST API (`POST /a/changes/{change}/revisions/{revision}/review`), and can
+/// vote on a custom label (e.g. `AI-Provenance +1`) when receipt coverage meets
+/// the `[policy]` threshold. Uses curl, like the GitHub integration.
+use crate::commands::github::{self, AiHunk};
+use crate::commands::policy::{self, Level, PolicyReport};
+use crate::core::util;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+pub struct GerritOptions<'a> {
+    pub url: Option<&'a str>,
+    pub change: Option<&'a str>,
+    pub revision: Option<&'a str>,
+    pub label: Option<&'a str>,
+    pub label_value: i32,
+}
+
+/// Post the review. Connection details fall back to the variables Gerrit
+/// Trigger / Zuul export: `GERRIT_URL`, `GERRIT_CHANGE_NUMBER`,
+/// `GERRIT_PATCHSET_REVISION`; credentials come from `GERRIT_USERNAME` and
+/// `GERRIT_HTTP_PASSWORD`.
+pub fn run(opts: &GerritOptions) {
+    let env = |key: &str| std::env::var(key).ok().filter(|v| !v.is_empty());
+
+    let Some(base_url) = opts.url.map(String::from).or_else(|| env("GERRIT_URL")) else {
+        eprintln!("[blameprompt] Gerrit URL not set. Pass --url or export GERRIT_URL.");
+        std::process::exit(1);
+    };
+    let Some(change) = opts
+        .change
+        .map(String::from)
+        .or_else(|| env("GERRIT_CHANGE_NUMBER"))
+    else {
+        eprintln!(
+            "[blameprompt] Gerrit change not set. Pass --change or export GERRIT_CHANGE_NUMBER."
+        );
+        std::process::exit(1);
+    };
+    let revision = opts
+        .revision
+        .map(String::from)
+        .or_else(|| env("GERRIT_PATCHSET_REVISION"))
+        .unwrap_or_else(|| "current".to_string());
+    let (Some(user), Some(password)) = (env("GERRIT_USERNAME"), env("GERRIT_HTTP_PASSWORD")) else {
+        eprintln!("[blameprompt] GERRIT_USERNAME and GERRIT_HTTP_PASSWORD env vars must be set.");
+        std::process::exit(1);
+    };
+
+    // A Gerrit change is a single commit: the patchset revision if we have it
+    // locally, otherwise HEAD.
+    let Some(sha) = resolve_commit(&revision).or_else(|| resolve_commit("HEAD")) else {
+        eprintln!(
+            "[blameprompt] Cannot resolve the patchset commit. Run inside the checked-out change."
+        );
+        std::process::exit(1);
+    };
+    let commits = vec![sha];
+
+    let cfg = crate::core::config::load_config().policy;
+    let report = policy::evaluate(&commits, &cfg);
+    let hunks = github::collect_ai_hunks(&commits);
+    let review = build_review_input(&report, &hunks, opts.label, opts.label_value);
+
+    let url = format!(
+        "{}/a/changes/{}/revisions/{}/review",
+        base_url.trim_end_matches('/'),
+        change,
+        revision
+    );
+    match gerrit_post(&url, &review.to_string(), &user, &password) {
+        Ok((_, 200)) => {
+            println!("[blameprompt] Gerrit review posted on change {}", change);
+            if let Some(label) = opts.label {
+                if report.coverage_passed() {
+                    println!("[blameprompt] Voted {} {:+}", label, opts.label_value);
+                } else {
+                    println!(
+                        "[blameprompt] Receipt coverage {:.0}% is below the policy minimum {:.0}%; {} not set",
+                        report.receipt_coverage * 100.0,
+                        report.min_receipt_coverage * 100.0,
+                        label
+                    );
+                }
+            }
+        }
+        Ok((body, status)) => {
+            eprintln!(
+                "[blameprompt] Failed to post Gerrit review: HTTP {} — {}",
+                status,
+                strip_xssi(&body).chars().take(200).collect::<String>()
+            );
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("[blameprompt] Failed to post Gerrit review: {}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+fn resolve_commit(rev: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args([
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ])
+        .output()
+        .ok()?;
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !sha.is_empty()).then_some(sha)
+}
+
+/// Build a Gerrit `ReviewInput`: a summary message, one comment per AI hunk and
+/// per located policy finding, and the label vote when coverage passes.
+fn build_review_input(
+    report: &PolicyReport,
+    hunks: &[AiHunk],
+    label: Option<&str>,
+    label_value: i32,
+) -> serde_json::Value {
+    let mut message = String::from("BlamePrompt AI attribution\n\n");
+    message.push_str(&report.summary_markdown().replace("**", ""));
+    let unlocated: Vec<_> = report
+        .findings
+        .iter()
+        .filter(|f| f.path.is_none())
+        .collect();
+    if !unlocated.is_empty() {
+        message.push('\n');
+        for f in unlocated {
+            message.push_str(&format!("* {}: {}\n", f.title, f.message));
+        }
+    }
+
+    let mut comments: BTreeMap<String, Vec<serde_json::Value>> = BTreeMap::new();
+    for h in hunks {
+        let prompt: String = h.prompt.chars().take(200).collect();
+        comments
+            .entry(h.path.clone())
+            .or_default()
+            .push(serde_json::json!({
+                "line": h.start,
+                "message": format!(
+                    "AI-generated (lines {}-{}) by {}\n\n> {}\n\nreceipt {}",
+                    h.start,
+                    h.end,
+                    h.model,
+                    prompt.replace('\n', " "),
+                    util::short_sha(&h.receipt_id)
+                ),
+                "unresolved": false,
+            }));
+    }
+    for f in &report.findings {
+        let (Some(path), Some(line)) = (&f.path, f.line) else {
+            continue;
+        };
+        comments
+            .entry(path.clone())
+            .or_default()
+            .push(serde_json::json!({
+                "line": line,
+                "message": format!("[{}] {}\n\n{}", f.rule, f.title, f.message),
+                "unresolved": f.level == Level::Failure,
+            }));
+    }
+
+    let mut review = serde_json::json!({
+        "message": message,
+        "tag": "autogenerated:blameprompt",
+        "comments": comments,
+    });
+    if let Some(label) = label {
+        if report.coverage_passed() {
+            review["labels"] = serde_json::json!({ label: label_value });
+        }
+    }
+    review
+}
+
+/// POST JSON to Gerrit with HTTP basic auth; returns `(body, http_status)`.
+fn gerrit_post(
+    url: &str,
+    json_body: &str,
+    user: &str,
+    password: &str,
+) -> Result<(String, u32), String> {
+    let output = Command::new("curl")
+        .args([
+            "-s",
+            "-w",
+            "\n%{http_code}",
+            "-X",
+            "POST",
+            "-u",
+            &format!("{}:{}", user, password),
+            "-H",
+            "Content-Type: application/json; charset=UTF-8",
+            "-d",
+            json_body,
+            url,
+        ])
+        .output()
+        .map_err(|e| format!("curl failed: {}", e))?;
+    let raw = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = github::split_curl_response(&raw);
+    Ok((body.to_string(), status))
+}
+
+/// Gerrit prefixes JSON responses with `)]}'` to defeat XSSI.
+fn strip_xssi(body: &str) -> &str {
+    body.strip_prefix(")]}'")
+        .map(|b| b.trim_start())
+        .unwrap_or(body)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::policy::Finding;
+
+    fn report(coverage: f64, min: f64, findings: Vec<Finding>) -> PolicyReport {
+        PolicyReport {
+            commits_total: 1,
+            commits_with_receipts: if coverage > 0.0 { 1 } else { 0 },
+            receipt_coverage: coverage,
+            min_receipt_coverage: min,
+            ai_lines: 3,
+            exempted: 0,
+            findings,
+        }
+    }
+
+    fn hunk() -> AiHunk {
+        AiHunk {
+            path: "src/lib.rs".to_string(),
+            start: 4,
+            end: 9,
+            model: "claude-sonnet-4-6".to_string(),
+            prompt: "add parser".to_string(),
+            receipt_id: "0123456789abcdef".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_review_input_sets_label_when_coverage_passes() {
+        let finding = Finding {
+            rule: "secret".to_string(),
+            level: Level::Failure,
+            path: Some("src/lib.rs".to_string()),
+            line: Some(5),
+            title: "AWS Access Key in AI-generated code".to_string(),
+            message: "m".to_string(),
+        };
+        let review = build_review_input(
+            &report(1.0, 0.5, vec![finding]),
+            &[hunk()],
+            Some("AI-Provenance"),
+            1,
+        );
+        assert_eq!(review["labels"]["AI-Provenance"], 1);
+        let comments = review["comments"]["src/lib.rs"].as_array().unwrap();
+        assert_eq!(comments.len(), 2);
+        assert_eq!(comments[0]["line"], 4);
+        assert_eq!(comments[1]["line"], 5);
+        assert_eq!(comments[1]["unresolved"], true);
+        assert!(review["message"]
+            .as_str()
+            .unwrap()
+            .contains("Receipt coverage"));
+    }
+
+    #[test]
+    fn test_review_input_skips_label_below_threshold() {
+        let review = build_review_input(&report(0.0, 0.5, vec![]), &[], Some("AI-Provenance"), 1);
+        assert!(review.get("labels").is_none());
+    }
+
+    #[test]
+    fn test_strip_xssi() {
+        assert_eq!(strip_xssi(")]}'\n{\"a\":1}"), "{\"a\":1}");
+        assert_eq!(strip_xssi("{}"), "{}");
+    }
+}
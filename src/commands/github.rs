@@ -1,19 +1,102 @@
 /// GitHub PR comment integration.
 ///
 /// Posts an AI attribution summary as a pull request comment using the GitHub API.
+/// The comment id is recorded on the PR's notes (see `link_pr`) so reruns edit
+/// it rather than post again, and commits linked to another PR are left out.
+/// Files the PR changes are labelled "Mostly AI", "AI-assisted" or "Human" by
+/// their share of AI lines (thresholds in `[github_comment]`), and files without
+/// AI lines are folded away so large PRs stay skimmable.
+/// Optionally also posts per-file review comments on AI-generated hunks and writes
+/// a job summary for GitHub Actions (`$GITHUB_STEP_SUMMARY`), and reports policy
+/// results as a Check Run with inline annotations. The hackathon report reads
+/// push times from the repository activity API to cross-check commit dates.
 /// Uses curl (no reqwest/ureq dependency) to match the existing HTTP pattern.
+use crate::commands::link_pr;
+use crate::commands::policy::{self, Finding, Level};
+use crate::core::config::{self, GithubCommentConfig};
+use crate::core::receipt::{ChangeType, CodeOrigin, CodeOriginStats, PullRequestLink};
 use crate::core::util;
 use crate::git::notes::list_commits_with_notes;
 use crate::git::notes::read_receipts_for_commit;
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
 use std::process::Command;
 
+/// An AI-attributed line range in one file, with the receipt that produced it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AiHunk {
+    pub path: String,
+    pub start: u32,
+    pub end: u32,
+    pub model: String,
+    pub prompt: String,
+    pub receipt_id: String,
+}
+
+/// AI share of the lines a PR adds to one file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FileAttribution {
+    pub path: String,
+    pub added_lines: u32,
+    pub ai_lines: u32,
+}
+
+impl FileAttribution {
+    pub fn ai_pct(&self) -> f64 {
+        if self.added_lines == 0 {
+            0.0
+        } else {
+            self.ai_lines as f64 / self.added_lines as f64 * 100.0
+        }
+    }
+
+    pub fn label(&self, cfg: &GithubCommentConfig) -> &'static str {
+        let pct = self.ai_pct();
+        if self.ai_lines > 0 && pct >= cfg.mostly_ai_pct {
+            "Mostly AI"
+        } else if self.ai_lines > 0 && pct >= cfg.ai_assisted_pct {
+            "AI-assisted"
+        } else {
+            "Human"
+        }
+    }
+}
+
 /// Post an AI attribution summary as a GitHub PR comment.
 ///
 /// `pr_number` — override the auto-detected PR number.
 /// `repo` — override the "owner/repo" (default: detected from `git remote get-url origin`).
-pub fn run(pr_number: Option<u32>, repo: Option<&str>) {
+/// `per_file` — also post a review with one comment per AI-generated hunk.
+/// `summary_file` — append the summary to this file; an empty string means
+/// `$GITHUB_STEP_SUMMARY`.
+pub fn run(pr_number: Option<u32>, repo: Option<&str>, per_file: bool, summary_file: Option<&str>) {
+    let mut wrote_summary = false;
+    if let Some(path) = summary_file {
+        let path = if path.is_empty() {
+            std::env::var("GITHUB_STEP_SUMMARY").unwrap_or_default()
+        } else {
+            path.to_string()
+        };
+        if path.is_empty() {
+            eprintln!("[blameprompt] --summary-file given without a path and $GITHUB_STEP_SUMMARY is not set.");
+            std::process::exit(1);
+        }
+        match write_step_summary(&path) {
+            Ok(()) => {
+                println!("[blameprompt] Job summary written to {}", path);
+                wrote_summary = true;
+            }
+            Err(e) => {
+                eprintln!("[blameprompt] Failed to write job summary: {}", e);
+                std::process::exit(1);
+            }
+        }
+    }
+
     let token = match std::env::var("GITHUB_TOKEN").ok() {
         Some(t) if !t.is_empty() => t,
+        // A summary-only run (e.g. push builds without a PR) doesn't need the API.
+        _ if wrote_summary => return,
         _ => {
             eprintln!("[blameprompt] GITHUB_TOKEN env var not set. Export it and retry.");
             std::process::exit(1);
@@ -42,19 +125,630 @@ pub fn run(pr_number: Option<u32>, repo: Option<&str>) {
         },
     };
 
-    let body = generate_pr_body();
-    match post_pr_comment(&repo_slug, pr, &body, &token) {
-        Ok(url) => println!("[blameprompt] PR comment posted: {}", url),
+    // Commits already linked to another PR were reported there.
+    let commits = pr_note_commits(Some(pr));
+    let body = generate_pr_body(&commits);
+    let branch_commits = detect_base_ref()
+        .map(|base| commits_since(&base))
+        .unwrap_or_default();
+    match post_pr_comment(
+        &repo_slug,
+        pr,
+        &body,
+        recorded_comment_id(&commits, pr),
+        &token,
+    ) {
+        Ok((url, comment_id)) => {
+            println!("[blameprompt] PR comment posted: {}", url);
+            // Remember the comment so the next run edits it instead of posting again.
+            let link = PullRequestLink {
+                number: pr,
+                url: Some(format!("https://github.com/{}/pull/{}", repo_slug, pr)),
+                merge_commit: None,
+                comment_id,
+            };
+            if let Err(e) = link_pr::link_commits(&branch_commits, &link, false) {
+                eprintln!("[blameprompt] Could not record the PR link: {}", e);
+            }
+        }
         Err(e) => {
             eprintln!("[blameprompt] Failed to post PR comment: {}", e);
             std::process::exit(1);
         }
     }
+
+    if per_file {
+        post_per_file_review(&repo_slug, pr, &token);
+    }
+}
+
+/// Create a "BlamePrompt" Check Run on the PR head commit carrying the policy
+/// results (coverage, secrets, injection patterns) as inline annotations.
+///
+/// `repo` — override the "owner/repo" (default: detected from the origin remote).
+/// `sha` — commit to attach the check to (default: the PR head, see `detect_head_sha`).
+/// Exits non-zero when the policy fails so the job status matches the check.
+pub fn run_check(repo: Option<&str>, sha: Option<&str>) {
+    let token = match std::env::var("GITHUB_TOKEN").ok() {
+        Some(t) if !t.is_empty() => t,
+        _ => {
+            eprintln!("[blameprompt] GITHUB_TOKEN env var not set. Export it and retry.");
+            std::process::exit(1);
+        }
+    };
+
+    let repo_slug = match repo {
+        Some(r) => r.to_string(),
+        None => match detect_repo_slug() {
+            Some(r) => r,
+            None => {
+                eprintln!("[blameprompt] Cannot detect GitHub repo. Pass --repo owner/repo.");
+                std::process::exit(1);
+            }
+        },
+    };
+
+    let head_sha = match sha.map(|s| s.to_string()).or_else(detect_head_sha) {
+        Some(s) => s,
+        None => {
+            eprintln!("[blameprompt] Cannot resolve the head commit. Pass --sha <commit>.");
+            std::process::exit(1);
+        }
+    };
+
+    let commits = match detect_base_ref() {
+        Some(base) => commits_between(&base, &head_sha),
+        None => {
+            eprintln!("[blameprompt] Cannot determine PR base (set GITHUB_BASE_REF); checking the head commit only.");
+            vec![head_sha.clone()]
+        }
+    };
+
+    let cfg = crate::core::config::load_config().policy;
+    let report = policy::evaluate(&commits, &cfg);
+    let annotations: Vec<serde_json::Value> =
+        report.findings.iter().map(check_annotation).collect();
+    let conclusion = if report.passed() {
+        "success"
+    } else {
+        "failure"
+    };
+    let title = if report.passed() {
+        "AI provenance policy passed".to_string()
+    } else {
+        format!(
+            "AI provenance policy failed: {} violation(s)",
+            report.count(Level::Failure)
+        )
+    };
+    let summary = report.summary_markdown();
+
+    // The API accepts at most 50 annotations per request; the rest are
+    // appended by updating the same check run.
+    let mut batches = annotations.chunks(CHECK_ANNOTATION_LIMIT);
+    let payload = serde_json::json!({
+        "name": "BlamePrompt",
+        "head_sha": head_sha,
+        "status": "completed",
+        "conclusion": conclusion,
+        "output": {
+            "title": title,
+            "summary": summary,
+            "annotations": batches.next().unwrap_or(&[]),
+        },
+    });
+    let url = format!("https://api.github.com/repos/{}/check-runs", repo_slug);
+    let check_id = match github_post(&url, &payload.to_string(), &token) {
+        Ok((body, 201)) => serde_json::from_str::<serde_json::Value>(&body)
+            .ok()
+            .and_then(|v| v["id"].as_u64()),
+        Ok((body, status)) => {
+            eprintln!(
+                "[blameprompt] Failed to create check run: GitHub API returned HTTP {} — {}",
+                status,
+                body.chars().take(200).collect::<String>()
+            );
+            std::process::exit(1);
+        }
+        Err(e) => {
+            eprintln!("[blameprompt] Failed to create check run: {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    if let Some(id) = check_id {
+        let url = format!(
+            "https://api.github.com/repos/{}/check-runs/{}",
+            repo_slug, id
+        );
+        for batch in batches {
+            let payload = serde_json::json!({
+                "output": { "title": title, "summary": summary, "annotations": batch },
+            });
+            match github_request("PATCH", &url, &payload.to_string(), &token) {
+                Ok((_, 200)) => {}
+                Ok((body, status)) => eprintln!(
+                    "[blameprompt] Failed to add annotations: GitHub API returned HTTP {} — {}",
+                    status,
+                    body.chars().take(200).collect::<String>()
+                ),
+                Err(e) => eprintln!("[blameprompt] Failed to add annotations: {}", e),
+            }
+        }
+    }
+
+    println!(
+        "[blameprompt] Check run created on {} ({}, {} annotation(s))",
+        &head_sha[..head_sha.len().min(8)],
+        conclusion,
+        annotations.len()
+    );
+    if !report.passed() {
+        std::process::exit(1);
+    }
+}
+
+const CHECK_ANNOTATION_LIMIT: usize = 50;
+
+/// The commit to attach a check run to. In `pull_request` workflows HEAD is a
+/// synthetic merge commit, so its second parent (the PR head) is used instead.
+fn detect_head_sha() -> Option<String> {
+    let rev_parse = |rev: &str| -> Option<String> {
+        let output = Command::new("git")
+            .args(["rev-parse", "--verify", "--quiet", rev])
+            .output()
+            .ok()?;
+        let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !sha.is_empty()).then_some(sha)
+    };
+    let is_pr_event = std::env::var("GITHUB_EVENT_NAME")
+        .map(|e| e.starts_with("pull_request"))
+        .unwrap_or(false);
+    if is_pr_event {
+        if let Some(sha) = rev_parse("HEAD^2") {
+            return Some(sha);
+        }
+    }
+    rev_parse("HEAD")
+}
+
+/// Map a policy finding to a check-run annotation. Findings without a file
+/// location (coverage, secrets in prompts) are pinned to the top of `.blamepromptrc`
+/// or, failing that, the first line of the repository's README.
+fn check_annotation(f: &Finding) -> serde_json::Value {
+    let path = f.path.clone().unwrap_or_else(|| {
+        [".blamepromptrc", "README.md"]
+            .iter()
+            .find(|p| std::path::Path::new(p).exists())
+            .unwrap_or(&".blamepromptrc")
+            .to_string()
+    });
+    let line = f.line.unwrap_or(1);
+    serde_json::json!({
+        "path": path,
+        "start_line": line,
+        "end_line": line,
+        "annotation_level": f.level,
+        "title": f.title,
+        "message": f.message,
+    })
+}
+
+/// Post one review with a comment on each AI-generated hunk that is part of the PR diff.
+/// GitHub rejects the whole review if any comment targets a line outside the diff,
+/// so hunks are clipped to the lines the PR actually adds.
+fn post_per_file_review(repo: &str, pr: u32, token: &str) {
+    let base = match detect_base_ref() {
+        Some(b) => b,
+        None => {
+            eprintln!("[blameprompt] Cannot determine PR base (set GITHUB_BASE_REF); skipping per-file comments.");
+            return;
+        }
+    };
+
+    let diff = Command::new("git")
+        .args(["diff", "--unified=0", &format!("{}...HEAD", base)])
+        .output()
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+    let added = parse_added_ranges(&diff);
+
+    let mut comments = Vec::new();
+    for hunk in collect_ai_hunks(&commits_since(&base)) {
+        let Some(ranges) = added.get(&hunk.path) else {
+            continue;
+        };
+        for (start, end) in clip_to_ranges((hunk.start, hunk.end), ranges) {
+            let mut c = serde_json::json!({
+                "path": hunk.path,
+                "line": end,
+                "side": "RIGHT",
+                "body": review_comment_body(&hunk),
+            });
+            if start < end {
+                c["start_line"] = start.into();
+                c["start_side"] = "RIGHT".into();
+            }
+            comments.push(c);
+        }
+    }
+
+    if comments.is_empty() {
+        println!(
+            "[blameprompt] No AI-generated hunks in this PR's diff; no review comments posted."
+        );
+        return;
+    }
+
+    let count = comments.len();
+    let payload = serde_json::json!({
+        "event": "COMMENT",
+        "body": "<!-- blameprompt-review -->\nAI-generated hunks, annotated by blameprompt.",
+        "comments": comments,
+    });
+    let url = format!("https://api.github.com/repos/{}/pulls/{}/reviews", repo, pr);
+    match github_post(&url, &payload.to_string(), token) {
+        Ok((_, 200 | 201)) => println!("[blameprompt] Posted {} per-file review comment(s)", count),
+        Ok((body, status)) => eprintln!(
+            "[blameprompt] Failed to post review: GitHub API returned HTTP {} — {}",
+            status,
+            body.chars().take(200).collect::<String>()
+        ),
+        Err(e) => eprintln!("[blameprompt] Failed to post review: {}", e),
+    }
+}
+
+/// POST a JSON payload to the GitHub API; returns `(body, http_status)`.
+fn github_post(url: &str, json_body: &str, token: &str) -> Result<(String, u32), String> {
+    github_request("POST", url, json_body, token)
+}
+
+/// Send a JSON payload to the GitHub API with `method`; returns `(body, http_status)`.
+fn github_request(
+    method: &str,
+    url: &str,
+    json_body: &str,
+    token: &str,
+) -> Result<(String, u32), String> {
+    let output = Command::new("curl")
+        .args([
+            "-s",
+            "-w",
+            "\n%{http_code}",
+            "-X",
+            method,
+            "-H",
+            "Accept: application/vnd.github+json",
+            "-H",
+            &format!("Authorization: Bearer {}", token),
+            "-H",
+            "X-GitHub-Api-Version: 2022-11-28",
+            "-H",
+            "Content-Type: application/json",
+            "-d",
+            json_body,
+            url,
+        ])
+        .output()
+        .map_err(|e| format!("curl failed: {}", e))?;
+    let raw = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = split_curl_response(&raw);
+    Ok((body.to_string(), status))
+}
+
+/// GET a GitHub API endpoint; returns `(body, http_status)`.
+fn github_get(url: &str, token: &str) -> Result<(String, u32), String> {
+    let output = Command::new("curl")
+        .args([
+            "-s",
+            "-w",
+            "\n%{http_code}",
+            "-H",
+            "Accept: application/vnd.github+json",
+            "-H",
+            &format!("Authorization: Bearer {}", token),
+            "-H",
+            "X-GitHub-Api-Version: 2022-11-28",
+            url,
+        ])
+        .output()
+        .map_err(|e| format!("curl failed: {}", e))?;
+    let raw = String::from_utf8_lossy(&output.stdout);
+    let (body, status) = split_curl_response(&raw);
+    Ok((body.to_string(), status))
+}
+
+/// When each commit first reached `repo` on GitHub, keyed by sha. Reads the 100
+/// most recent pushes from the repository activity API and resolves each push's
+/// `before..after` range against local history, so the commits must be fetched.
+pub fn fetch_push_times(repo: &str, token: &str) -> Result<HashMap<String, DateTime<Utc>>, String> {
+    let url = format!(
+        "https://api.github.com/repos/{}/activity?activity_type=push&per_page=100",
+        repo
+    );
+    let (body, status) = github_get(&url, token)?;
+    if status != 200 {
+        return Err(format!(
+            "GitHub API returned HTTP {} — {}",
+            status,
+            body.chars().take(200).collect::<String>()
+        ));
+    }
+    let pushes: Vec<serde_json::Value> =
+        serde_json::from_str(&body).map_err(|e| format!("Unexpected activity response: {}", e))?;
+
+    let mut times: HashMap<String, DateTime<Utc>> = HashMap::new();
+    for push in &pushes {
+        let (Some(before), Some(after), Some(timestamp)) = (
+            push["before"].as_str(),
+            push["after"].as_str(),
+            push["timestamp"].as_str(),
+        ) else {
+            continue;
+        };
+        let Ok(pushed) = DateTime::parse_from_rfc3339(timestamp) else {
+            continue;
+        };
+        let pushed = pushed.with_timezone(&Utc);
+        for sha in pushed_commits(before, after) {
+            times
+                .entry(sha)
+                .and_modify(|t| *t = (*t).min(pushed))
+                .or_insert(pushed);
+        }
+    }
+    Ok(times)
+}
+
+/// Commits introduced by a push from `before` to `after`. A new branch has an
+/// all-zero `before`; its history is capped since older commits were usually
+/// pushed earlier and keep that earlier time.
+fn pushed_commits(before: &str, after: &str) -> Vec<String> {
+    let range: Vec<String> = if before.chars().all(|c| c == '0') {
+        vec![after.to_string(), "--max-count=250".to_string()]
+    } else {
+        vec![format!("{}..{}", before, after)]
+    };
+    Command::new("git")
+        .arg("rev-list")
+        .args(&range)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The PR base as a ref git can diff against: `origin/$GITHUB_BASE_REF` in Actions,
+/// otherwise the remote's default branch.
+pub fn detect_base_ref() -> Option<String> {
+    if let Ok(base) = std::env::var("GITHUB_BASE_REF") {
+        if !base.is_empty() {
+            return Some(format!("origin/{}", base));
+        }
+    }
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "origin/HEAD"])
+        .output()
+        .ok()?;
+    let r = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (output.status.success() && !r.is_empty()).then_some(r)
+}
+
+/// Commits on HEAD that are not on `base`.
+fn commits_since(base: &str) -> Vec<String> {
+    commits_between(base, "HEAD")
+}
+
+/// Commits reachable from `head` that are not on `base`.
+pub fn commits_between(base: &str, head: &str) -> Vec<String> {
+    Command::new("git")
+        .args(["rev-list", &format!("{}..{}", base, head)])
+        .output()
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(|l| l.trim().to_string())
+                .filter(|l| !l.is_empty())
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// AI line ranges of `commits`: the AI-generated and human-edited hunks of
+/// the notes' line mappings, else the receipts' own ranges. Binary files and
+/// deletions have no lines to annotate and are skipped.
+pub fn collect_ai_hunks(commits: &[String]) -> Vec<AiHunk> {
+    let mut hunks = Vec::new();
+    for sha in commits {
+        let Some(payload) = read_receipts_for_commit(sha) else {
+            continue;
+        };
+        // Line mappings from attach time say exactly which committed lines
+        // came from AI; receipt ranges cover the files they don't.
+        let mappings = payload.file_mappings.as_deref().unwrap_or(&[]);
+        for m in mappings {
+            for h in &m.hunks {
+                if h.origin == CodeOrigin::PureHuman {
+                    continue;
+                }
+                let receipt = payload
+                    .receipts
+                    .iter()
+                    .find(|r| Some(&r.id) == h.receipt_id.as_ref());
+                let hunk = AiHunk {
+                    path: m.path.clone(),
+                    start: h.start_line,
+                    end: h.end_line,
+                    model: h
+                        .model
+                        .clone()
+                        .or_else(|| receipt.map(|r| r.model.clone()))
+                        .unwrap_or_default(),
+                    prompt: receipt
+                        .map(|r| r.prompt_summary.clone())
+                        .unwrap_or_default(),
+                    receipt_id: h.receipt_id.clone().unwrap_or_default(),
+                };
+                if !hunks.contains(&hunk) {
+                    hunks.push(hunk);
+                }
+            }
+        }
+        for r in &payload.receipts {
+            for fc in r.all_file_changes() {
+                if mappings.iter().any(|m| m.path == fc.path) {
+                    continue;
+                }
+                if fc.is_binary
+                    || fc.change_type == Some(ChangeType::Deleted)
+                    || fc.line_range.0 == 0
+                {
+                    continue;
+                }
+                let hunk = AiHunk {
+                    path: fc.path.clone(),
+                    start: fc.line_range.0,
+                    end: fc.line_range.1.max(fc.line_range.0),
+                    model: r.model.clone(),
+                    prompt: r.prompt_summary.clone(),
+                    receipt_id: r.id.clone(),
+                };
+                if !hunks.contains(&hunk) {
+                    hunks.push(hunk);
+                }
+            }
+        }
+    }
+    hunks
 }
 
-/// Generate a Markdown body summarizing AI receipts for commits in the current branch.
-fn generate_pr_body() -> String {
-    let commits = list_commits_with_notes();
+/// Parse `git diff --unified=0` into `path -> added line ranges` (new-file side).
+pub fn parse_added_ranges(diff: &str) -> std::collections::HashMap<String, Vec<(u32, u32)>> {
+    let mut out: std::collections::HashMap<String, Vec<(u32, u32)>> =
+        std::collections::HashMap::new();
+    let mut current: Option<String> = None;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            current = path.strip_prefix("b/").map(String::from);
+        } else if line.starts_with("@@") {
+            let Some(path) = current.as_ref() else {
+                continue;
+            };
+            // "@@ -a,b +c,d @@" — count defaults to 1; 0 means a pure deletion
+            let Some(new) = line.split_whitespace().find(|t| t.starts_with('+')) else {
+                continue;
+            };
+            let mut parts = new[1..].splitn(2, ',');
+            let start: u32 = parts.next().and_then(|s| s.parse().ok()).unwrap_or(0);
+            let count: u32 = parts.next().and_then(|s| s.parse().ok()).unwrap_or(1);
+            if start > 0 && count > 0 {
+                out.entry(path.clone())
+                    .or_default()
+                    .push((start, start + count - 1));
+            }
+        }
+    }
+    out
+}
+
+/// Intersect `(start, end)` with each of `ranges`.
+fn clip_to_ranges(hunk: (u32, u32), ranges: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    ranges
+        .iter()
+        .filter_map(|&(a, b)| {
+            let start = hunk.0.max(a);
+            let end = hunk.1.min(b);
+            (start <= end).then_some((start, end))
+        })
+        .collect()
+}
+
+fn review_comment_body(hunk: &AiHunk) -> String {
+    let prompt: String = hunk.prompt.chars().take(200).collect();
+    let ellipsis = if hunk.prompt.chars().count() > 200 {
+        "…"
+    } else {
+        ""
+    };
+    format!(
+        "\u{1f916} **AI-generated** by `{}`\n\n> {}{}\n\n<sub>receipt `{}` · blameprompt</sub>",
+        hunk.model,
+        prompt.replace('\n', " "),
+        ellipsis,
+        util::short_sha(&hunk.receipt_id)
+    )
+}
+
+/// Append the PR summary plus a per-file hunk table to a job summary file.
+fn write_step_summary(path: &str) -> Result<(), String> {
+    use std::io::Write;
+
+    let mut md = generate_pr_body(&pr_note_commits(None)).replace("<!-- blameprompt -->\n", "");
+    let hunks = match detect_base_ref() {
+        Some(base) => collect_ai_hunks(&commits_since(&base)),
+        None => collect_ai_hunks(&list_commits_with_notes()),
+    };
+    md.push_str(&step_summary_files_section(&hunks));
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .map_err(|e| format!("cannot open {}: {}", path, e))?;
+    writeln!(file, "{}", md).map_err(|e| format!("cannot write {}: {}", path, e))
+}
+
+fn step_summary_files_section(hunks: &[AiHunk]) -> String {
+    if hunks.is_empty() {
+        return String::new();
+    }
+    let mut md = String::from("\n### AI-generated hunks by file\n\n");
+    md.push_str("| File | Lines | Model | Prompt |\n");
+    md.push_str("|------|-------|-------|--------|\n");
+    for h in hunks {
+        let prompt: String = h.prompt.chars().take(60).collect();
+        md.push_str(&format!(
+            "| `{}` | {}-{} | {} | {} |\n",
+            h.path,
+            h.start,
+            h.end,
+            h.model,
+            prompt.replace('|', "\\|").replace('\n', " ")
+        ));
+    }
+    md
+}
+
+/// Commits with receipts to report on PR `pr`: all of them except those whose
+/// note links a different PR.
+fn pr_note_commits(pr: Option<u32>) -> Vec<String> {
+    list_commits_with_notes()
+        .into_iter()
+        .filter(|sha| match (pr, read_receipts_for_commit(sha)) {
+            (Some(n), Some(payload)) => payload.pull_request.is_none_or(|l| l.number == n),
+            _ => true,
+        })
+        .collect()
+}
+
+/// The attribution comment already posted on PR `pr`, as recorded on its commits.
+fn recorded_comment_id(commits: &[String], pr: u32) -> Option<u64> {
+    commits.iter().find_map(|sha| {
+        read_receipts_for_commit(sha)?
+            .pull_request
+            .filter(|l| l.number == pr)?
+            .comment_id
+    })
+}
+
+/// Generate a Markdown body summarizing AI receipts for `commits`.
+fn generate_pr_body(commits: &[String]) -> String {
     if commits.is_empty() {
         return "<!-- blameprompt -->\n**No AI receipts found** for this branch.\n".to_string();
     }
@@ -68,15 +762,21 @@ fn generate_pr_body() -> String {
 
     let mut total_receipts = 0u32;
     let mut total_lines = 0u32;
+    let mut origins = Vec::new();
 
-    for sha in &commits {
+    for sha in commits {
         let short = util::short_sha(sha);
         if let Some(payload) = read_receipts_for_commit(sha) {
+            let mut receipt_lines = 0u32;
             for receipt in &payload.receipts {
                 let files = receipt.files_changed.len();
                 let lines = receipt.effective_total_additions();
-                let summary = receipt.prompt_summary.chars().take(60).collect::<String>();
-                let summary = if receipt.prompt_summary.len() > 60 {
+                let prompt = receipt
+                    .intent_summary
+                    .as_deref()
+                    .unwrap_or(&receipt.prompt_summary);
+                let summary = prompt.chars().take(60).collect::<String>();
+                let summary = if prompt.len() > 60 {
                     format!("{}…", summary)
                 } else {
                     summary
@@ -86,8 +786,12 @@ fn generate_pr_body() -> String {
                     short, receipt.model, summary, files, lines,
                 ));
                 total_receipts += 1;
-                total_lines += lines;
+                receipt_lines += lines;
             }
+            // The attach-time line classification counts what was committed,
+            // not what the AI wrote along the way.
+            total_lines += payload.mapped_ai_lines().unwrap_or(receipt_lines);
+            origins.extend(payload.code_origin);
         }
     }
 
@@ -96,12 +800,112 @@ fn generate_pr_body() -> String {
         "**Total**: {} receipt(s) · {} AI-generated lines\n",
         total_receipts, total_lines
     ));
+    if let Some(origin) = CodeOriginStats::sum(&origins) {
+        md.push_str(&format!(
+            "**Code origin**: {:.0}% AI-generated · {:.0}% human-edited · {:.0}% human ({} added lines)\n",
+            origin.ai_generated_pct,
+            origin.human_edited_pct,
+            origin.pure_human_pct,
+            origin.total_lines()
+        ));
+    }
+
+    if let Some(base) = detect_base_ref() {
+        let diff = Command::new("git")
+            .args([
+                "diff",
+                "--unified=0",
+                "--no-color",
+                &format!("{}...HEAD", base),
+            ])
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+            .unwrap_or_default();
+        let files = attribute_files(&parse_added_ranges(&diff), &collect_ai_hunks(commits));
+        md.push_str(&files_section(
+            &files,
+            &config::load_config().github_comment,
+        ));
+    }
 
     md
 }
 
-/// Post a comment to a GitHub PR via curl.
-fn post_pr_comment(repo: &str, pr: u32, body: &str, token: &str) -> Result<String, String> {
+/// AI lines per file among the lines the PR adds, most AI-heavy first.
+pub fn attribute_files(
+    added: &HashMap<String, Vec<(u32, u32)>>,
+    hunks: &[AiHunk],
+) -> Vec<FileAttribution> {
+    let mut files: Vec<FileAttribution> = added
+        .iter()
+        .map(|(path, ranges)| {
+            let mut ai = std::collections::BTreeSet::new();
+            for h in hunks.iter().filter(|h| h.path == *path) {
+                for (start, end) in clip_to_ranges((h.start, h.end), ranges) {
+                    ai.extend(start..=end);
+                }
+            }
+            FileAttribution {
+                path: path.clone(),
+                added_lines: ranges.iter().map(|(a, b)| b - a + 1).sum(),
+                ai_lines: ai.len() as u32,
+            }
+        })
+        .collect();
+    files.sort_by(|a, b| {
+        b.ai_pct()
+            .total_cmp(&a.ai_pct())
+            .then(b.ai_lines.cmp(&a.ai_lines))
+            .then(a.path.cmp(&b.path))
+    });
+    files
+}
+
+/// Per-file label table; files without AI lines go into a collapsed list.
+fn files_section(files: &[FileAttribution], cfg: &GithubCommentConfig) -> String {
+    if files.is_empty() {
+        return String::new();
+    }
+    let (shown, folded): (Vec<&FileAttribution>, Vec<&FileAttribution>) = files
+        .iter()
+        .partition(|f| f.ai_lines > 0 || !cfg.collapse_human);
+    let mut md = String::from("\n### Files\n\n");
+    if !shown.is_empty() {
+        md.push_str("| File | AI lines | AI share | Label |\n");
+        md.push_str("|------|----------|----------|-------|\n");
+        for f in &shown {
+            md.push_str(&format!(
+                "| `{}` | {} / {} | {:.0}% | {} |\n",
+                f.path,
+                f.ai_lines,
+                f.added_lines,
+                f.ai_pct(),
+                f.label(cfg)
+            ));
+        }
+    }
+    if !folded.is_empty() {
+        md.push_str(&format!(
+            "\n<details><summary>{} file(s) without AI lines</summary>\n\n",
+            folded.len()
+        ));
+        for f in &folded {
+            md.push_str(&format!("- `{}` ({} lines)\n", f.path, f.added_lines));
+        }
+        md.push_str("\n</details>\n");
+    }
+    md
+}
+
+/// Post a comment to a GitHub PR via curl, or edit `existing` when it is still
+/// there. Returns the comment URL and id.
+fn post_pr_comment(
+    repo: &str,
+    pr: u32,
+    body: &str,
+    existing: Option<u64>,
+    token: &str,
+) -> Result<(String, Option<u64>), String> {
     // Escape body for JSON: replace backslash, double-quote, and newlines
     let escaped = body
         .replace('\\', "\\\\")
@@ -115,36 +919,29 @@ fn post_pr_comment(repo: &str, pr: u32, body: &str, token: &str) -> Result<Strin
         repo, pr
     );
 
-    let output = Command::new("curl")
-        .args([
-            "-s",
-            "-w",
-            "\n%{http_code}",
-            "-X",
-            "POST",
-            "-H",
-            "Accept: application/vnd.github+json",
-            "-H",
-            &format!("Authorization: Bearer {}", token),
-            "-H",
-            "X-GitHub-Api-Version: 2022-11-28",
-            "-H",
-            "Content-Type: application/json",
-            "-d",
-            &json_body,
-            &url,
-        ])
-        .output()
-        .map_err(|e| format!("curl failed: {}", e))?;
+    if let Some(id) = existing {
+        let edit_url = format!(
+            "https://api.github.com/repos/{}/issues/comments/{}",
+            repo, id
+        );
+        let (response_body, status_code) = github_request("PATCH", &edit_url, &json_body, token)?;
+        if status_code == 200 {
+            let comment_url = extract_json_string(&response_body, "html_url").unwrap_or(edit_url);
+            return Ok((comment_url, Some(id)));
+        }
+        // The comment was deleted (404) or is not ours to edit: post a new one.
+    }
 
-    let raw = String::from_utf8_lossy(&output.stdout);
-    let (response_body, status_code) = split_curl_response(&raw);
+    let (response_body, status_code) = github_post(&url, &json_body, token)?;
 
     if status_code == 201 {
-        // Extract the html_url from the response
+        // Extract the html_url and id from the response
         let comment_url =
-            extract_json_string(response_body, "html_url").unwrap_or_else(|| url.clone());
-        Ok(comment_url)
+            extract_json_string(&response_body, "html_url").unwrap_or_else(|| url.clone());
+        let comment_id = serde_json::from_str::<serde_json::Value>(&response_body)
+            .ok()
+            .and_then(|v| v["id"].as_u64());
+        Ok((comment_url, comment_id))
     } else {
         Err(format!(
             "GitHub API returned HTTP {} — {}",
@@ -154,8 +951,59 @@ fn post_pr_comment(repo: &str, pr: u32, body: &str, token: &str) -> Result<Strin
     }
 }
 
+/// Attach `content` to the GitHub Release for `tag` as asset `name`, replacing
+/// an existing asset of that name. Returns the asset's download URL.
+pub fn upload_release_asset(
+    repo: &str,
+    tag: &str,
+    name: &str,
+    content: &str,
+    token: &str,
+) -> Result<String, String> {
+    let url = format!(
+        "https://api.github.com/repos/{}/releases/tags/{}",
+        repo, tag
+    );
+    let (body, status) = github_get(&url, token)?;
+    if status != 200 {
+        return Err(format!(
+            "No GitHub Release for tag {} (HTTP {}). Create the release first.",
+            tag, status
+        ));
+    }
+    let release: serde_json::Value =
+        serde_json::from_str(&body).map_err(|e| format!("Bad release JSON: {}", e))?;
+    let id = release["id"].as_u64().ok_or("Release has no id")?;
+
+    let existing = release["assets"]
+        .as_array()
+        .and_then(|assets| assets.iter().find(|a| a["name"].as_str() == Some(name)))
+        .and_then(|a| a["id"].as_u64());
+    if let Some(asset_id) = existing {
+        let delete_url = format!(
+            "https://api.github.com/repos/{}/releases/assets/{}",
+            repo, asset_id
+        );
+        github_request("DELETE", &delete_url, "", token)?;
+    }
+
+    let upload_url = format!(
+        "https://uploads.github.com/repos/{}/releases/{}/assets?name={}",
+        repo, id, name
+    );
+    let (body, status) = github_post(&upload_url, content, token)?;
+    if status != 201 {
+        return Err(format!(
+            "GitHub API returned HTTP {} — {}",
+            status,
+            body.chars().take(200).collect::<String>()
+        ));
+    }
+    Ok(extract_json_string(&body, "browser_download_url").unwrap_or(upload_url))
+}
+
 /// Detect "owner/repo" from `git remote get-url origin`.
-fn detect_repo_slug() -> Option<String> {
+pub fn detect_repo_slug() -> Option<String> {
     let output = Command::new("git")
         .args(["remote", "get-url", "origin"])
         .output()
@@ -182,9 +1030,34 @@ fn parse_github_slug(url: &str) -> Option<String> {
     None
 }
 
+/// Put `label` on the current branch's open PR and take `others` off it.
+/// `Ok(None)` when there is no token, GitHub remote or open PR.
+pub fn label_current_pr(label: &str, others: &[&str]) -> Result<Option<u32>, String> {
+    let Some(token) = std::env::var("GITHUB_TOKEN").ok().filter(|t| !t.is_empty()) else {
+        return Ok(None);
+    };
+    let Some(repo) = detect_repo_slug() else {
+        return Ok(None);
+    };
+    let Some(pr) = find_pr_for_current_branch(&repo, &token) else {
+        return Ok(None);
+    };
+    let issue = format!("https://api.github.com/repos/{}/issues/{}/labels", repo, pr);
+    for other in others {
+        // 404 when the label isn't on the PR.
+        github_request("DELETE", &format!("{}/{}", issue, other), "", &token)?;
+    }
+    let body = serde_json::json!({ "labels": [label] }).to_string();
+    let (response, status) = github_post(&issue, &body, &token)?;
+    if status != 200 {
+        return Err(format!("GitHub API returned {}: {}", status, response));
+    }
+    Ok(Some(pr))
+}
+
 /// Find the open PR number for the current branch using the GitHub API.
 fn find_pr_for_current_branch(repo: &str, token: &str) -> Option<u32> {
-    let branch = current_branch()?;
+    let branch = util::current_branch()?;
     let url = format!(
         "https://api.github.com/repos/{}/pulls?state=open&head={}:{}&per_page=1",
         repo,
@@ -220,25 +1093,8 @@ fn find_pr_for_current_branch(repo: &str, token: &str) -> Option<u32> {
     extract_first_pr_number(body)
 }
 
-fn current_branch() -> Option<String> {
-    let output = Command::new("git")
-        .args(["rev-parse", "--abbrev-ref", "HEAD"])
-        .output()
-        .ok()?;
-    if output.status.success() {
-        let b = String::from_utf8_lossy(&output.stdout).trim().to_string();
-        if b != "HEAD" {
-            Some(b)
-        } else {
-            None
-        }
-    } else {
-        None
-    }
-}
-
 /// Split curl response with `-w "\n%{http_code}"` suffix.
-fn split_curl_response(raw: &str) -> (&str, u32) {
+pub fn split_curl_response(raw: &str) -> (&str, u32) {
     if let Some(pos) = raw.rfind('\n') {
         let status_str = raw[pos + 1..].trim();
         let status: u32 = status_str.parse().unwrap_or(0);
@@ -316,6 +1172,38 @@ mod tests {
         assert_eq!(extract_first_pr_number(json), Some(42));
     }
 
+    #[test]
+    fn test_parse_added_ranges() {
+        let diff = "diff --git a/src/a.rs b/src/a.rs\n--- a/src/a.rs\n+++ b/src/a.rs\n@@ -3,0 +4,3 @@\n+x\n@@ -10 +13 @@\n-y\n+z\n@@ -20,2 +22,0 @@\n-gone\ndiff --git a/new.rs b/new.rs\n--- /dev/null\n+++ b/new.rs\n@@ -0,0 +1,5 @@\n";
+        let ranges = parse_added_ranges(diff);
+        assert_eq!(ranges["src/a.rs"], vec![(4, 6), (13, 13)]);
+        assert_eq!(ranges["new.rs"], vec![(1, 5)]);
+    }
+
+    #[test]
+    fn test_clip_to_ranges() {
+        assert_eq!(
+            clip_to_ranges((5, 20), &[(1, 6), (10, 12), (30, 40)]),
+            vec![(5, 6), (10, 12)]
+        );
+        assert!(clip_to_ranges((5, 8), &[(9, 10)]).is_empty());
+    }
+
+    #[test]
+    fn test_step_summary_files_section() {
+        let hunks = vec![AiHunk {
+            path: "src/auth.rs".to_string(),
+            start: 3,
+            end: 9,
+            model: "claude-sonnet-4-6".to_string(),
+            prompt: "add a | pipe".to_string(),
+            receipt_id: "abc".to_string(),
+        }];
+        let md = step_summary_files_section(&hunks);
+        assert!(md.contains("| `src/auth.rs` | 3-9 | claude-sonnet-4-6 | add a \\| pipe |"));
+        assert!(step_summary_files_section(&[]).is_empty());
+    }
+
     #[test]
     fn test_split_curl_response() {
         let raw = "{\"id\":1}\n201";
@@ -323,4 +1211,49 @@ mod tests {
         assert_eq!(body, "{\"id\":1}");
         assert_eq!(status, 201);
     }
+
+    #[test]
+    fn test_file_labels_and_collapsed_section() {
+        let mut added = HashMap::new();
+        added.insert("src/gen.rs".to_string(), vec![(1, 10)]);
+        added.insert("src/mixed.rs".to_string(), vec![(1, 10), (20, 29)]);
+        added.insert("src/tweak.rs".to_string(), vec![(1, 100)]);
+        added.insert("README.md".to_string(), vec![(1, 5)]);
+        let hunk = |path: &str, start, end| AiHunk {
+            path: path.to_string(),
+            start,
+            end,
+            model: "m".to_string(),
+            prompt: "p".to_string(),
+            receipt_id: "r".to_string(),
+        };
+        let hunks = vec![
+            hunk("src/gen.rs", 1, 10),
+            hunk("src/gen.rs", 5, 12),
+            hunk("src/mixed.rs", 5, 24),
+            hunk("src/tweak.rs", 50, 52),
+        ];
+        let files = attribute_files(&added, &hunks);
+        let cfg = GithubCommentConfig::default();
+        let summary: Vec<(&str, u32, &str)> = files
+            .iter()
+            .map(|f| (f.path.as_str(), f.ai_lines, f.label(&cfg)))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("src/gen.rs", 10, "Mostly AI"),
+                ("src/mixed.rs", 11, "AI-assisted"),
+                ("src/tweak.rs", 3, "Human"),
+                ("README.md", 0, "Human"),
+            ]
+        );
+
+        let md = files_section(&files, &cfg);
+        assert!(md.contains("| `src/gen.rs` | 10 / 10 | 100% | Mostly AI |"));
+        assert!(md.contains("| `src/tweak.rs` | 3 / 100 | 3% | Human |"));
+        assert!(md.contains("<summary>1 file(s) without AI lines</summary>"));
+        assert!(md.contains("- `README.md` (5 lines)"));
+        assert!(!md.contains("| `README.md`"));
+    }
 }
@@ -0,0 +1,285 @@
+/// `blameprompt graph`: sessions, prompts, files and commits as a graph.
+///
+/// Emits Graphviz DOT or a Mermaid flowchart for architecture reviews and
+/// incident write-ups. Each session points to its prompts. Each prompt points
+/// to the files it wrote and the commit it landed in. Continuations are drawn
+/// as dashed edges: a session resumed from another, or a prompt that carries
+/// on from an earlier one.
+use crate::commands::anomalies::truncate;
+use crate::commands::audit::{self, AuditEntry};
+use crate::core::util;
+use std::collections::HashMap;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum NodeKind {
+    Session,
+    Prompt,
+    File,
+    Commit,
+}
+
+#[derive(Debug)]
+pub struct Node {
+    pub id: String,
+    pub kind: NodeKind,
+    pub label: String,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Edge {
+    pub from: String,
+    pub to: String,
+    pub label: &'static str,
+    pub continuation: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Graph {
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+    ids: HashMap<String, String>,
+}
+
+impl Graph {
+    /// The node for `key`, added with `label` the first time it is seen.
+    fn node(&mut self, key: String, kind: NodeKind, label: impl FnOnce() -> String) -> String {
+        if let Some(id) = self.ids.get(&key) {
+            return id.clone();
+        }
+        let id = format!("n{}", self.nodes.len());
+        self.nodes.push(Node {
+            id: id.clone(),
+            kind,
+            label: label(),
+        });
+        self.ids.insert(key, id.clone());
+        id
+    }
+
+    fn edge(&mut self, from: &str, to: &str, label: &'static str, continuation: bool) {
+        let edge = Edge {
+            from: from.to_string(),
+            to: to.to_string(),
+            label,
+            continuation,
+        };
+        if !self.edges.contains(&edge) {
+            self.edges.push(edge);
+        }
+    }
+}
+
+pub fn build(entries: &[AuditEntry]) -> Graph {
+    let mut g = Graph::default();
+    let mut continued: Vec<(String, String)> = Vec::new();
+    for entry in entries {
+        let subject = entry.commit_message.lines().next().unwrap_or_default();
+        let commit = g.node(
+            format!("commit:{}", entry.commit_sha),
+            NodeKind::Commit,
+            || {
+                format!(
+                    "{} {}",
+                    util::short_sha(&entry.commit_sha),
+                    truncate(subject, 40)
+                )
+            },
+        );
+        for r in &entry.receipts {
+            let session = g.node(
+                format!("session:{}", r.session_id),
+                NodeKind::Session,
+                || {
+                    format!(
+                        "session {}\n{} / {}",
+                        util::short_sha(&r.session_id),
+                        r.provider,
+                        r.model
+                    )
+                },
+            );
+            let prompt = g.node(format!("prompt:{}", r.id), NodeKind::Prompt, || {
+                let summary = r.intent_summary.as_deref().unwrap_or(&r.prompt_summary);
+                let summary = summary.split_whitespace().collect::<Vec<_>>().join(" ");
+                match r.prompt_number {
+                    Some(n) => format!("#{} {}", n, truncate(&summary, 50)),
+                    None => truncate(&summary, 50),
+                }
+            });
+            g.edge(&session, &prompt, "prompt", false);
+            g.edge(&prompt, &commit, "committed in", false);
+            for path in r.all_file_paths() {
+                let path = audit::relative_path(&path);
+                let file = g.node(format!("file:{}", path), NodeKind::File, || path.clone());
+                g.edge(&prompt, &file, "wrote", false);
+            }
+            if let Some(parent) = &r.parent_session_id {
+                let parent = g.node(format!("session:{}", parent), NodeKind::Session, || {
+                    format!("session {}", util::short_sha(parent))
+                });
+                g.edge(&parent, &session, "continued in", true);
+            }
+            if let Some(parent) = &r.parent_receipt_id {
+                continued.push((format!("prompt:{}", parent), prompt));
+            }
+        }
+    }
+    // Only link prompts that are in the graph themselves.
+    for (parent, prompt) in continued {
+        if let Some(parent) = g.ids.get(&parent).cloned() {
+            g.edge(&parent, &prompt, "continued by", true);
+        }
+    }
+    g
+}
+
+fn escape_dot(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+pub fn render_dot(g: &Graph) -> String {
+    let mut out = String::from("digraph blameprompt {\n  rankdir=LR;\n  node [fontname=\"Helvetica\", fontsize=10];\n  edge [fontname=\"Helvetica\", fontsize=9];\n");
+    for n in &g.nodes {
+        let shape = match n.kind {
+            NodeKind::Session => "ellipse",
+            NodeKind::Prompt => "box",
+            NodeKind::File => "note",
+            NodeKind::Commit => "hexagon",
+        };
+        out.push_str(&format!(
+            "  {} [label=\"{}\", shape={}];\n",
+            n.id,
+            escape_dot(&n.label),
+            shape
+        ));
+    }
+    for e in &g.edges {
+        let style = if e.continuation { ", style=dashed" } else { "" };
+        out.push_str(&format!(
+            "  {} -> {} [label=\"{}\"{}];\n",
+            e.from, e.to, e.label, style
+        ));
+    }
+    out.push_str("}\n");
+    out
+}
+
+fn escape_mermaid(s: &str) -> String {
+    s.replace('"', "#quot;").replace('\n', "<br/>")
+}
+
+pub fn render_mermaid(g: &Graph) -> String {
+    let mut out = String::from("flowchart LR\n");
+    for n in &g.nodes {
+        let label = escape_mermaid(&n.label);
+        let node = match n.kind {
+            NodeKind::Session => format!("([\"{}\"])", label),
+            NodeKind::Prompt => format!("[\"{}\"]", label),
+            NodeKind::File => format!(">\"{}\"]", label),
+            NodeKind::Commit => format!("{{{{\"{}\"}}}}", label),
+        };
+        out.push_str(&format!("  {}{}\n", n.id, node));
+    }
+    for e in &g.edges {
+        let arrow = if e.continuation { "-.->" } else { "-->" };
+        out.push_str(&format!("  {} {}|{}| {}\n", e.from, arrow, e.label, e.to));
+    }
+    out
+}
+
+pub fn run(
+    format: &str,
+    from: Option<&str>,
+    to: Option<&str>,
+    output: Option<&str>,
+) -> Result<(), String> {
+    let entries = audit::collect_all_entries(from, to, None, false)?;
+    if entries.is_empty() {
+        return Err("No receipts found for the selected commits".to_string());
+    }
+    let graph = build(&entries);
+    let rendered = match format {
+        "dot" => render_dot(&graph),
+        "mermaid" => render_mermaid(&graph),
+        other => {
+            return Err(format!(
+                "Unknown format '{}': expected dot or mermaid",
+                other
+            ))
+        }
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(path, &rendered).map_err(|e| format!("Cannot write {}: {}", path, e))?;
+            println!(
+                "Graph written to {} ({} nodes, {} edges)",
+                path,
+                graph.nodes.len(),
+                graph.edges.len()
+            );
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::Receipt;
+
+    fn receipt(id: &str, session: &str, files: &[&str]) -> Receipt {
+        files.iter().fold(
+            Receipt::test(id)
+                .with_session(session)
+                .with_prompt("add \"login\" page"),
+            |r, f| r.with_file(f, (1, 2), 0, 0),
+        )
+    }
+
+    fn entry(sha: &str, receipts: Vec<Receipt>) -> AuditEntry {
+        AuditEntry {
+            commit_sha: sha.to_string(),
+            commit_date: "2026-03-01".to_string(),
+            commit_author: "Dana".to_string(),
+            commit_message: "Add login\n\nbody".to_string(),
+            receipts,
+            total_ai_lines: 0,
+            total_cost_usd: 0.0,
+            code_origin: None,
+            pull_request: None,
+        }
+    }
+
+    #[test]
+    fn test_build_and_render_graph() {
+        let first = receipt("r1", "s1", &["src/login.rs", "src/lib.rs"]);
+        let mut second = receipt("r2", "s2", &["src/login.rs"]);
+        second.parent_session_id = Some("s1".to_string());
+        second.parent_receipt_id = Some("r1".to_string());
+        let entries = vec![
+            entry("aaaaaaaa11", vec![first]),
+            entry("bbbbbbbb22", vec![second]),
+        ];
+        let g = build(&entries);
+        // 2 commits, 2 sessions, 2 prompts, 2 files.
+        assert_eq!(g.nodes.len(), 8);
+        let count = |kind| g.nodes.iter().filter(|n| n.kind == kind).count();
+        assert_eq!(count(NodeKind::File), 2);
+        assert_eq!(g.edges.iter().filter(|e| e.continuation).count(), 2);
+        assert_eq!(g.edges.iter().filter(|e| e.label == "wrote").count(), 3);
+
+        let dot = render_dot(&g);
+        assert!(dot.starts_with("digraph blameprompt {"));
+        assert!(dot.contains("add \\\"login\\\" page"));
+        assert!(dot.contains("style=dashed"));
+
+        let mermaid = render_mermaid(&g);
+        assert!(mermaid.starts_with("flowchart LR\n"));
+        assert!(mermaid.contains("add #quot;login#quot; page"));
+        assert!(mermaid.contains("-.->|continued in|"));
+        assert!(mermaid.contains("{{\"aaaaaaaa Add login\"}}"));
+    }
+}
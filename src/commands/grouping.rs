@@ -0,0 +1,302 @@
+/// Re-aggregation of audit data by session, author, day, model or file.
+///
+/// Shared by `audit --group-by`, the report's per-user and per-model tables and
+/// `analytics`, so every view counts lines and cost the same way. Author is the
+/// developer who ran the AI session (`Receipt.user`), falling back to the commit
+/// author for receipts without one. When grouping by file, a receipt's cost is
+/// split across its files in proportion to the lines each one got.
+use crate::commands::audit::{self, AuditEntry};
+use crate::core::receipt::Receipt;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    Session,
+    Author,
+    Day,
+    Model,
+    File,
+}
+
+impl GroupBy {
+    pub fn label(self) -> &'static str {
+        match self {
+            GroupBy::Session => "session",
+            GroupBy::Author => "author",
+            GroupBy::Day => "day",
+            GroupBy::Model => "model",
+            GroupBy::File => "file",
+        }
+    }
+}
+
+impl FromStr for GroupBy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "session" => Ok(GroupBy::Session),
+            "author" | "user" => Ok(GroupBy::Author),
+            "day" | "date" => Ok(GroupBy::Day),
+            "model" => Ok(GroupBy::Model),
+            "file" => Ok(GroupBy::File),
+            other => Err(format!(
+                "Unknown --group-by '{}' (expected session, author, day, model or file)",
+                other
+            )),
+        }
+    }
+}
+
+/// Totals for one group key.
+#[derive(Debug, Serialize, Default, Clone, PartialEq)]
+pub struct Group {
+    pub key: String,
+    pub commits: usize,
+    pub receipts: usize,
+    pub sessions: usize,
+    pub files: usize,
+    pub ai_lines: u32,
+    pub cost_usd: f64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+#[derive(Default)]
+struct Acc {
+    group: Group,
+    commits: HashSet<String>,
+    receipts: HashSet<String>,
+    sessions: HashSet<String>,
+    files: HashSet<String>,
+}
+
+/// Group the receipts of audit entries. Days are sorted oldest first, every
+/// other key by cost and then lines, largest first.
+pub fn group(entries: &[AuditEntry], by: GroupBy) -> Vec<Group> {
+    let items: Vec<(Option<&AuditEntry>, &Receipt)> = entries
+        .iter()
+        .flat_map(|e| e.receipts.iter().map(move |r| (Some(e), r)))
+        .collect();
+    aggregate(&items, by)
+}
+
+/// Group bare receipts, for callers that already flattened the entries.
+/// `commits` is 0 for every group.
+pub fn group_receipts(receipts: &[&Receipt], by: GroupBy) -> Vec<Group> {
+    let items: Vec<(Option<&AuditEntry>, &Receipt)> = receipts.iter().map(|r| (None, *r)).collect();
+    aggregate(&items, by)
+}
+
+/// Lines a receipt added, falling back to the line-range span for receipts
+/// captured without diff stats.
+pub fn receipt_lines(r: &Receipt) -> u32 {
+    match r.effective_total_additions() {
+        0 => r.total_lines_changed(),
+        n => n,
+    }
+}
+
+fn aggregate(items: &[(Option<&AuditEntry>, &Receipt)], by: GroupBy) -> Vec<Group> {
+    let mut accs: Vec<Acc> = Vec::new();
+    for (entry, r) in items {
+        for (key, lines, cost) in keys(*entry, r, by) {
+            let i = match accs.iter().position(|a| a.group.key == key) {
+                Some(i) => i,
+                None => {
+                    accs.push(Acc {
+                        group: Group {
+                            key,
+                            ..Group::default()
+                        },
+                        ..Acc::default()
+                    });
+                    accs.len() - 1
+                }
+            };
+            let acc = &mut accs[i];
+            if let Some(e) = entry {
+                acc.commits.insert(e.commit_sha.clone());
+            }
+            acc.receipts.insert(r.id.clone());
+            acc.sessions.insert(r.session_id.clone());
+            if by == GroupBy::File {
+                acc.files.insert(acc.group.key.clone());
+            } else {
+                for fc in r.all_file_changes() {
+                    acc.files.insert(audit::relative_path(&fc.path));
+                }
+            }
+            acc.group.ai_lines += lines;
+            acc.group.cost_usd += cost;
+            let ts = r.timestamp.to_rfc3339();
+            if acc.group.first_seen.is_empty() || ts < acc.group.first_seen {
+                acc.group.first_seen = ts.clone();
+            }
+            if ts > acc.group.last_seen {
+                acc.group.last_seen = ts;
+            }
+        }
+    }
+
+    let mut groups: Vec<Group> = accs
+        .into_iter()
+        .map(|a| Group {
+            commits: a.commits.len(),
+            receipts: a.receipts.len(),
+            sessions: a.sessions.len(),
+            files: a.files.len(),
+            ..a.group
+        })
+        .collect();
+    if by == GroupBy::Day {
+        groups.sort_by(|a, b| a.key.cmp(&b.key));
+    } else {
+        groups.sort_by(|a, b| {
+            b.cost_usd
+                .partial_cmp(&a.cost_usd)
+                .unwrap_or(std::cmp::Ordering::Equal)
+                .then(b.ai_lines.cmp(&a.ai_lines))
+                .then(a.key.cmp(&b.key))
+        });
+    }
+    groups
+}
+
+/// The `(key, lines, cost)` contributions of one receipt.
+fn keys(entry: Option<&AuditEntry>, r: &Receipt, by: GroupBy) -> Vec<(String, u32, f64)> {
+    let lines = receipt_lines(r);
+    let single = |key: String| vec![(key, lines, r.cost_usd)];
+    match by {
+        GroupBy::Session => single(r.session_id.clone()),
+        GroupBy::Author => single(if r.user.is_empty() {
+            entry.map(|e| e.commit_author.clone()).unwrap_or_default()
+        } else {
+            r.user.clone()
+        }),
+        GroupBy::Day => single(r.timestamp.format("%Y-%m-%d").to_string()),
+        GroupBy::Model => single(r.model.clone()),
+        GroupBy::File => {
+            let files: Vec<(String, u32)> = r
+                .all_file_changes()
+                .into_iter()
+                .filter(|fc| !fc.is_binary)
+                .map(|fc| {
+                    let n = match fc.additions {
+                        0 => fc.line_range.1.saturating_sub(fc.line_range.0) + 1,
+                        n => n,
+                    };
+                    (audit::relative_path(&fc.path), n)
+                })
+                .collect();
+            let total: u32 = files.iter().map(|(_, n)| n).sum();
+            files
+                .into_iter()
+                .map(|(path, n)| {
+                    let share = if total > 0 {
+                        n as f64 / total as f64
+                    } else {
+                        0.0
+                    };
+                    (path, n, r.cost_usd * share)
+                })
+                .collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::FileChange;
+
+    fn receipt(id: &str, session: &str, model: &str, ts: &str, cost: f64) -> Receipt {
+        Receipt::test(id)
+            .with_session(session)
+            .with_model(model)
+            .with_timestamp(ts)
+            .with_cost(cost)
+            .with_user("alice")
+    }
+
+    fn file(path: &str, additions: u32) -> FileChange {
+        FileChange::test(path, (1, additions), additions, 0)
+    }
+
+    fn entry(sha: &str, receipts: Vec<Receipt>) -> AuditEntry {
+        AuditEntry {
+            commit_sha: sha.to_string(),
+            commit_date: String::new(),
+            commit_author: "Alice <a@example.com>".to_string(),
+            commit_message: String::new(),
+            receipts,
+            total_ai_lines: 0,
+            total_cost_usd: 0.0,
+            code_origin: None,
+            pull_request: None,
+        }
+    }
+
+    fn sample() -> Vec<AuditEntry> {
+        let mut a = receipt("r1", "s1", "opus", "2026-03-01T10:00:00Z", 0.30);
+        a.files_changed = vec![file("src/a.rs", 20), file("src/b.rs", 10)];
+        let mut b = receipt("r2", "s1", "sonnet", "2026-03-02T09:00:00Z", 0.10);
+        b.files_changed = vec![file("src/a.rs", 5)];
+        let mut c = receipt("r3", "s2", "opus", "2026-03-01T12:00:00Z", 0.20);
+        c.files_changed = vec![file("src/c.rs", 8)];
+        c.user = String::new();
+        vec![entry("c1", vec![a, b]), entry("c2", vec![c])]
+    }
+
+    #[test]
+    fn test_group_by_session_model_and_day() {
+        let entries = sample();
+
+        let sessions = group(&entries, GroupBy::Session);
+        assert_eq!(sessions[0].key, "s1");
+        assert_eq!(
+            (
+                sessions[0].receipts,
+                sessions[0].commits,
+                sessions[0].ai_lines
+            ),
+            (2, 1, 35)
+        );
+
+        let models = group(&entries, GroupBy::Model);
+        assert_eq!(models[0].key, "opus");
+        assert_eq!((models[0].commits, models[0].files), (2, 3));
+        assert!((models[0].cost_usd - 0.5).abs() < 1e-9);
+        assert_eq!(models[0].first_seen, "2026-03-01T10:00:00+00:00");
+        assert_eq!(models[0].last_seen, "2026-03-01T12:00:00+00:00");
+
+        let days: Vec<String> = group(&entries, GroupBy::Day)
+            .into_iter()
+            .map(|g| g.key)
+            .collect();
+        assert_eq!(days, vec!["2026-03-01", "2026-03-02"]);
+
+        let authors: Vec<String> = group(&entries, GroupBy::Author)
+            .into_iter()
+            .map(|g| g.key)
+            .collect();
+        assert_eq!(authors, vec!["alice", "Alice <a@example.com>"]);
+    }
+
+    #[test]
+    fn test_group_by_file_splits_cost() {
+        let files = group(&sample(), GroupBy::File);
+        let a = files.iter().find(|g| g.key == "src/a.rs").unwrap();
+        assert_eq!((a.ai_lines, a.receipts), (25, 2));
+        assert!((a.cost_usd - 0.30).abs() < 1e-9);
+        let b = files.iter().find(|g| g.key == "src/b.rs").unwrap();
+        assert!((b.cost_usd - 0.10).abs() < 1e-9);
+        let total: f64 = files.iter().map(|g| g.cost_usd).sum();
+        assert!((total - 0.60).abs() < 1e-9);
+
+        assert_eq!("Model".parse::<GroupBy>(), Ok(GroupBy::Model));
+        assert!("repo".parse::<GroupBy>().is_err());
+    }
+}
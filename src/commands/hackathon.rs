@@ -1,5 +1,10 @@
-use crate::commands::audit;
-use crate::core::{model_classifier, receipt::Receipt, session_stats, util};
+use crate::commands::anomalies::{
+    build_timeline, calculate_integrity_score, commit_author_dates, detect_anomalies,
+    detect_push_time_discrepancies, make_rel, score_label, AnomalyFlag, Severity, TimelineEntry,
+};
+use crate::commands::audit::{self, AuditEntry};
+use crate::commands::github;
+use crate::core::{config, receipt::Receipt, session_stats};
 use chrono::{DateTime, Utc};
 use std::collections::{HashMap, HashSet};
 use std::fmt::Write;
@@ -8,38 +13,6 @@ use std::fmt::Write;
 // Data structures
 // ---------------------------------------------------------------------------
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-enum Severity {
-    Info,
-    Warning,
-    Critical,
-}
-
-impl Severity {
-    fn label(&self) -> &'static str {
-        match self {
-            Severity::Info => "INFO",
-            Severity::Warning => "WARNING",
-            Severity::Critical => "CRITICAL",
-        }
-    }
-
-    fn weight(&self) -> f64 {
-        match self {
-            Severity::Info => 2.0,
-            Severity::Warning => 8.0,
-            Severity::Critical => 20.0,
-        }
-    }
-}
-
-struct AnomalyFlag {
-    severity: Severity,
-    category: String,
-    description: String,
-    evidence: String,
-}
-
 struct FileAttribution {
     path: String,
     total_lines: u32,
@@ -48,19 +21,37 @@ struct FileAttribution {
     first_touched: Option<DateTime<Utc>>,
 }
 
-struct TimelineEntry {
-    timestamp: DateTime<Utc>,
-    prompt_summary: String,
-    duration_secs: Option<u64>,
-    model: String,
-    files_touched: Vec<String>,
-    additions: u32,
-    deletions: u32,
-    within_window: bool,
+/// One participant's analysis: the entries it was built from plus the derived
+/// timeline, anomalies and score.
+struct ParticipantResult {
+    entries: Vec<AuditEntry>,
+    timeline: Vec<TimelineEntry>,
+    anomalies: Vec<AnomalyFlag>,
+    score: u32,
+}
+
+impl ParticipantResult {
+    fn receipts(&self) -> Vec<&Receipt> {
+        self.entries.iter().flat_map(|e| &e.receipts).collect()
+    }
+}
+
+/// A row of the team summary table and the leaderboard CSV.
+struct Standing {
+    participant: String,
+    score: u32,
+    prompts: usize,
+    prompts_in_window: usize,
+    ai_lines: u32,
+    files: usize,
+    cost_usd: f64,
+    critical: usize,
+    warning: usize,
+    info: usize,
 }
 
 // ---------------------------------------------------------------------------
-// Public entry point
+// Public entry points
 // ---------------------------------------------------------------------------
 
 pub fn generate_hackathon_report(
@@ -69,23 +60,10 @@ pub fn generate_hackathon_report(
     output_path: &str,
     author: Option<&str>,
     include_uncommitted: bool,
+    github_repo: Option<&str>,
 ) -> Result<(), String> {
-    let hackathon_start = parse_datetime(start_str).ok_or_else(|| {
-        format!(
-            "Invalid --start: \"{}\". Use ISO 8601 (e.g. 2026-02-26T09:00:00Z).",
-            start_str
-        )
-    })?;
-    let hackathon_end = parse_datetime(end_str).ok_or_else(|| {
-        format!(
-            "Invalid --end: \"{}\". Use ISO 8601 (e.g. 2026-02-26T21:00:00Z).",
-            end_str
-        )
-    })?;
-
-    if hackathon_end <= hackathon_start {
-        return Err("--end must be after --start".to_string());
-    }
+    let (hackathon_start, hackathon_end) = parse_window(start_str, end_str)?;
+    let pushes = github_repo.map(fetch_push_times).transpose()?;
 
     // Collect ALL entries (not time-filtered) — we need out-of-window activity for anomaly detection.
     let mut entries = audit::collect_all_entries(None, None, author, include_uncommitted)?;
@@ -96,484 +74,280 @@ pub fn generate_hackathon_report(
         }
     }
 
-    let all_receipts: Vec<&Receipt> = entries.iter().flat_map(|e| &e.receipts).collect();
-
-    if all_receipts.is_empty() {
-        return Err(
-            "No AI receipts found. Is BlamePrompt installed and have you used AI coding tools?"
-                .to_string(),
-        );
-    }
-
-    let timeline = build_timeline(&all_receipts, hackathon_start, hackathon_end);
-    let anomalies = detect_anomalies(
-        &all_receipts,
-        &entries,
-        &timeline,
+    let result = analyze(
+        entries,
         hackathon_start,
         hackathon_end,
-    );
-    let integrity_score = calculate_integrity_score(&anomalies);
-    let file_attribution = build_file_attribution(&all_receipts);
+        author,
+        pushes.as_ref(),
+    )
+    .ok_or_else(|| {
+        "No AI receipts found. Is BlamePrompt installed and have you used AI coding tools?"
+            .to_string()
+    })?;
 
     let mut md = String::with_capacity(8192);
     write_header(&mut md, hackathon_start, hackathon_end, author);
-    write_summary(
-        &mut md,
-        &all_receipts,
-        &timeline,
-        &anomalies,
-        integrity_score,
-    );
-    write_timeline(&mut md, &timeline);
-    write_code_attribution(&mut md, &all_receipts, &file_attribution);
-    write_anomaly_flags(&mut md, &anomalies);
-    write_integrity_assessment(&mut md, &anomalies, integrity_score);
+    write_participant(&mut md, &result);
     write_footer(&mut md);
 
     std::fs::write(output_path, &md).map_err(|e| format!("Cannot write report: {}", e))?;
     println!("Hackathon report written to {}", output_path);
     println!(
         "  Integrity score: {}/100 ({})",
-        integrity_score,
-        score_label(integrity_score)
+        result.score,
+        score_label(result.score)
     );
     Ok(())
 }
 
-// ---------------------------------------------------------------------------
-// Timeline builder
-// ---------------------------------------------------------------------------
-
-fn build_timeline(
-    receipts: &[&Receipt],
-    start: DateTime<Utc>,
-    end: DateTime<Utc>,
-) -> Vec<TimelineEntry> {
-    let mut timeline: Vec<TimelineEntry> = receipts
-        .iter()
-        .map(|r| {
-            let ts = r.prompt_submitted_at.unwrap_or(r.timestamp);
-            let files: Vec<String> = r.all_file_paths().iter().map(|f| make_rel(f)).collect();
-            TimelineEntry {
-                timestamp: ts,
-                prompt_summary: r.prompt_summary.chars().take(200).collect(),
-                duration_secs: r.prompt_duration_secs,
-                model: model_classifier::display_name(&r.model),
-                files_touched: files,
-                additions: r.effective_total_additions(),
-                deletions: r.effective_total_deletions(),
-                within_window: ts >= start && ts <= end,
-            }
-        })
-        .collect();
-
-    timeline.sort_by_key(|t| t.timestamp);
-    timeline
-}
-
-// ---------------------------------------------------------------------------
-// Anomaly detection — orchestrator + 7 detectors
-// ---------------------------------------------------------------------------
-
-fn detect_anomalies(
-    receipts: &[&Receipt],
-    entries: &[audit::AuditEntry],
-    timeline: &[TimelineEntry],
-    start: DateTime<Utc>,
-    end: DateTime<Utc>,
-) -> Vec<AnomalyFlag> {
-    let mut flags = Vec::new();
-    flags.extend(detect_time_window_violations(timeline, start, end));
-    flags.extend(detect_pre_written_code(receipts));
-    flags.extend(detect_untracked_files(receipts));
-    flags.extend(detect_duplicate_prompt_hashes(receipts));
-    flags.extend(detect_batch_commits(entries, receipts));
-    flags.extend(detect_unusual_session_patterns(receipts, timeline));
-    flags.extend(detect_time_gaps(timeline, start));
-    flags
-}
-
-/// Detector 1: Prompts submitted outside the hackathon time window.
-fn detect_time_window_violations(
-    timeline: &[TimelineEntry],
-    start: DateTime<Utc>,
-    end: DateTime<Utc>,
-) -> Vec<AnomalyFlag> {
-    let outside: Vec<&TimelineEntry> = timeline.iter().filter(|t| !t.within_window).collect();
-    if outside.is_empty() {
-        return vec![];
-    }
-
-    let before_count = outside.iter().filter(|t| t.timestamp < start).count();
-    let after_count = outside.iter().filter(|t| t.timestamp > end).count();
-    let additions_outside: u32 = outside.iter().map(|t| t.additions).sum();
+/// `hackathon-report --team`: one combined report with a comparative summary
+/// table followed by a section per participant, plus an optional leaderboard CSV.
+/// Each participant is matched against commit authors like `--author`, and
+/// against the receipt's `user` for uncommitted work.
+pub fn generate_team_report(
+    start_str: &str,
+    end_str: &str,
+    output_path: &str,
+    team: &[String],
+    leaderboard_path: Option<&str>,
+    include_uncommitted: bool,
+    github_repo: Option<&str>,
+) -> Result<(), String> {
+    let (hackathon_start, hackathon_end) = parse_window(start_str, end_str)?;
+    let pushes = github_repo.map(fetch_push_times).transpose()?;
 
-    let severity = if additions_outside > 100 {
-        Severity::Critical
+    let staged = if include_uncommitted {
+        audit::collect_staged_entries()
     } else {
-        Severity::Warning
+        Vec::new()
     };
 
-    vec![AnomalyFlag {
-        severity,
-        category: "Time Window Violation".into(),
-        description: format!(
-            "{} prompt(s) submitted outside the hackathon window ({} before start, {} after end)",
-            outside.len(),
-            before_count,
-            after_count,
-        ),
-        evidence: format!(
-            "{} lines added outside window. Earliest: {}. Latest: {}",
-            additions_outside,
-            outside
-                .first()
-                .map(|t| t.timestamp.to_rfc3339())
-                .unwrap_or_default(),
-            outside
-                .last()
-                .map(|t| t.timestamp.to_rfc3339())
-                .unwrap_or_default(),
-        ),
-    }]
-}
-
-/// Detector 2: File appears fully-formed in a single prompt with no iterative history.
-fn detect_pre_written_code(receipts: &[&Receipt]) -> Vec<AnomalyFlag> {
-    let mut flags = Vec::new();
-
-    // Build per-file history: (timestamp, additions, prompt_summary)
-    let mut file_history: HashMap<String, Vec<(DateTime<Utc>, u32, String)>> = HashMap::new();
-    for r in receipts {
-        let ts = r.prompt_submitted_at.unwrap_or(r.timestamp);
-        for fc in r.all_file_changes() {
-            file_history.entry(make_rel(&fc.path)).or_default().push((
-                ts,
-                fc.additions,
-                r.prompt_summary.clone(),
-            ));
+    let mut results: Vec<(String, ParticipantResult)> = Vec::new();
+    let mut missing: Vec<&str> = Vec::new();
+    for participant in team {
+        let mut entries = audit::collect_audit_entries(None, None, Some(participant))?;
+        entries.extend(staged_for(&staged, participant));
+        match analyze(
+            entries,
+            hackathon_start,
+            hackathon_end,
+            Some(participant),
+            pushes.as_ref(),
+        ) {
+            Some(result) => results.push((participant.clone(), result)),
+            None => missing.push(participant),
         }
     }
-
-    for (file, mut history) in file_history {
-        history.sort_by_key(|(ts, _, _)| *ts);
-        if let Some((_, first_additions, prompt_summary)) = history.first() {
-            let total_touches = history.len();
-            let total_additions: u32 = history.iter().map(|(_, a, _)| a).sum();
-
-            // Heuristic: first touch >80 lines, <=2 total touches, >70% of all additions
-            if *first_additions > 80
-                && total_touches <= 2
-                && (*first_additions as f64 / total_additions.max(1) as f64) > 0.7
-            {
-                let severity = if *first_additions > 200 {
-                    Severity::Critical
-                } else {
-                    Severity::Warning
-                };
-
-                flags.push(AnomalyFlag {
-                    severity,
-                    category: "Pre-written Code Suspected".into(),
-                    description: format!(
-                        "`{}` appeared with {} lines in a single prompt ({} total touches)",
-                        file, first_additions, total_touches,
-                    ),
-                    evidence: format!(
-                        "Prompt: \"{}\". {} of {} total lines in first touch ({:.0}%)",
-                        truncate(prompt_summary, 100),
-                        first_additions,
-                        total_additions,
-                        (*first_additions as f64 / total_additions.max(1) as f64) * 100.0,
-                    ),
-                });
-            }
-        }
+    if results.is_empty() {
+        return Err("No AI receipts found for any participant in --team.".to_string());
     }
 
-    flags
-}
-
-/// Detector 3: Source files committed with zero receipt trail.
-fn detect_untracked_files(receipts: &[&Receipt]) -> Vec<AnomalyFlag> {
-    let receipted_files: HashSet<String> = receipts
-        .iter()
-        .flat_map(|r| r.all_file_paths())
-        .map(|f| make_rel(&f))
-        .collect();
-
-    let recently_added = get_recently_added_files();
-    let untracked: Vec<String> = recently_added
-        .into_iter()
-        .filter(|f| !receipted_files.contains(f) && is_source_file(f))
-        .collect();
+    let mut standings: Vec<Standing> = results.iter().map(|(p, r)| standing(p, r)).collect();
+    rank(&mut standings);
 
-    if untracked.is_empty() {
-        return vec![];
+    let mut md = String::with_capacity(8192 * results.len());
+    write_header(&mut md, hackathon_start, hackathon_end, None);
+    write_team_summary(&mut md, &standings, &missing);
+    for (participant, result) in &results {
+        let _ = writeln!(md, "---\n");
+        let _ = writeln!(md, "## Participant: {}\n", participant);
+        let mut section = String::new();
+        write_participant(&mut section, result);
+        md.push_str(&demote_headings(&section));
     }
+    write_footer(&mut md);
 
-    let severity = if untracked.len() > 5 {
-        Severity::Critical
-    } else {
-        Severity::Warning
-    };
-
-    vec![AnomalyFlag {
-        severity,
-        category: "Files Without Receipt Trail".into(),
-        description: format!(
-            "{} source file(s) were added with no AI receipt or iterative history",
-            untracked.len(),
-        ),
-        evidence: format!(
-            "Files: {}",
-            untracked
-                .iter()
-                .take(10)
-                .cloned()
-                .collect::<Vec<_>>()
-                .join(", "),
-        ),
-    }]
-}
-
-/// Detector 4: Same prompt_hash submitted multiple times (rehearsed prompts).
-fn detect_duplicate_prompt_hashes(receipts: &[&Receipt]) -> Vec<AnomalyFlag> {
-    let mut hash_counts: HashMap<&str, usize> = HashMap::new();
-    for r in receipts {
-        if !r.prompt_hash.is_empty() {
-            *hash_counts.entry(r.prompt_hash.as_str()).or_insert(0) += 1;
-        }
+    std::fs::write(output_path, &md).map_err(|e| format!("Cannot write report: {}", e))?;
+    println!(
+        "Hackathon team report written to {} ({} participants)",
+        output_path,
+        results.len()
+    );
+    if let Some(path) = leaderboard_path {
+        std::fs::write(path, leaderboard_csv(&standings))
+            .map_err(|e| format!("Cannot write leaderboard: {}", e))?;
+        println!("  Leaderboard written to {}", path);
     }
-
-    let duplicates: Vec<(&str, usize)> = hash_counts
-        .into_iter()
-        .filter(|(_, count)| *count > 1)
-        .collect();
-
-    if duplicates.is_empty() {
-        return vec![];
+    for name in &missing {
+        println!("  No AI receipts found for {}", name);
     }
-
-    let total_dupes: usize = duplicates.iter().map(|(_, c)| c).sum();
-    let severity = if total_dupes > 10 {
-        Severity::Critical
-    } else if total_dupes > 3 {
-        Severity::Warning
-    } else {
-        Severity::Info
-    };
-
-    vec![AnomalyFlag {
-        severity,
-        category: "Duplicate Prompt Hashes".into(),
-        description: format!(
-            "{} unique prompt(s) submitted more than once ({} total duplicated)",
-            duplicates.len(),
-            total_dupes,
-        ),
-        evidence: format!(
-            "Hashes: {}",
-            duplicates
-                .iter()
-                .take(5)
-                .map(|(h, c)| format!("{}.. ({}x)", util::short_sha(h), c))
-                .collect::<Vec<_>>()
-                .join(", "),
-        ),
-    }]
+    Ok(())
 }
 
-/// Detector 5: Commit touches many files but few have receipt coverage.
-fn detect_batch_commits(entries: &[audit::AuditEntry], receipts: &[&Receipt]) -> Vec<AnomalyFlag> {
-    let mut flags = Vec::new();
-    let _ = receipts; // receipts available if needed in future
-
-    for entry in entries {
-        if entry.commit_sha == "uncommitted" {
-            continue;
-        }
-
-        let files_in_commit = count_files_in_commit(&entry.commit_sha);
-        let receipted_files: HashSet<String> = entry
-            .receipts
-            .iter()
-            .flat_map(|r| r.all_file_paths())
-            .map(|f| make_rel(&f))
-            .collect();
-
-        let coverage = if files_in_commit > 0 {
-            receipted_files.len() as f64 / files_in_commit as f64
-        } else {
-            1.0
-        };
-
-        if files_in_commit > 5 && coverage < 0.30 {
-            let severity = if files_in_commit > 15 && coverage < 0.10 {
-                Severity::Critical
-            } else {
-                Severity::Warning
-            };
-
-            flags.push(AnomalyFlag {
-                severity,
-                category: "Batch Commit".into(),
-                description: format!(
-                    "Commit {} changed {} files but only {} ({:.0}%) have receipt coverage",
-                    util::short_sha(&entry.commit_sha),
-                    files_in_commit,
-                    receipted_files.len(),
-                    coverage * 100.0,
-                ),
-                evidence: format!(
-                    "Commit: \"{}\". Date: {}",
-                    entry.commit_message, entry.commit_date,
-                ),
-            });
-        }
-    }
-
-    flags
+/// Push times for `--github-repo`; needs `GITHUB_TOKEN`.
+fn fetch_push_times(repo: &str) -> Result<HashMap<String, DateTime<Utc>>, String> {
+    let token = std::env::var("GITHUB_TOKEN")
+        .ok()
+        .filter(|t| !t.is_empty())
+        .ok_or("--github-repo needs the GITHUB_TOKEN env var to read push times.")?;
+    github::fetch_push_times(repo, &token)
+        .map_err(|e| format!("Cannot read push times for {}: {}", repo, e))
 }
 
-/// Detector 6: Very short prompt producing disproportionately large output.
-fn detect_unusual_session_patterns(
-    receipts: &[&Receipt],
-    timeline: &[TimelineEntry],
-) -> Vec<AnomalyFlag> {
-    let mut flags = Vec::new();
-
-    // Per-receipt: <60s duration producing >50 lines at >2 lines/sec
-    for r in receipts {
-        let duration = match r.prompt_duration_secs {
-            Some(d) if d > 0 => d,
-            _ => continue,
-        };
-        let additions = r.effective_total_additions();
-
-        if duration < 60 && additions > 50 {
-            let lps = additions as f64 / duration as f64;
-            if lps > 2.0 {
-                flags.push(AnomalyFlag {
-                    severity: Severity::Warning,
-                    category: "Unusual Session Pattern".into(),
-                    description: format!(
-                        "Prompt produced {} lines in {}s ({:.1} lines/sec)",
-                        additions, duration, lps,
-                    ),
-                    evidence: format!(
-                        "Prompt: \"{}\". Model: {}",
-                        truncate(&r.prompt_summary, 100),
-                        model_classifier::display_name(&r.model),
-                    ),
-                });
-            }
-        }
-    }
+fn parse_window(start_str: &str, end_str: &str) -> Result<(DateTime<Utc>, DateTime<Utc>), String> {
+    let hackathon_start = parse_datetime(start_str).ok_or_else(|| {
+        format!(
+            "Invalid --start: \"{}\". Use ISO 8601 (e.g. 2026-02-26T09:00:00Z).",
+            start_str
+        )
+    })?;
+    let hackathon_end = parse_datetime(end_str).ok_or_else(|| {
+        format!(
+            "Invalid --end: \"{}\". Use ISO 8601 (e.g. 2026-02-26T21:00:00Z).",
+            end_str
+        )
+    })?;
 
-    // Aggregate: very few prompts with very large total output
-    let total_prompts = timeline.len();
-    let total_additions: u32 = timeline.iter().map(|t| t.additions).sum();
-    if total_prompts > 0 && total_prompts <= 3 && total_additions > 500 {
-        flags.push(AnomalyFlag {
-            severity: Severity::Warning,
-            category: "Unusual Session Pattern".into(),
-            description: format!(
-                "Only {} prompt(s) produced {} total lines",
-                total_prompts, total_additions,
-            ),
-            evidence: format!(
-                "Average: {:.0} lines per prompt",
-                total_additions as f64 / total_prompts as f64,
-            ),
-        });
+    if hackathon_end <= hackathon_start {
+        return Err("--end must be after --start".to_string());
     }
-
-    flags
+    Ok((hackathon_start, hackathon_end))
 }
 
-/// Detector 7: Suspiciously long gaps between consecutive prompts during the hackathon.
-fn detect_time_gaps(timeline: &[TimelineEntry], start: DateTime<Utc>) -> Vec<AnomalyFlag> {
-    let mut flags = Vec::new();
-
-    let within: Vec<&TimelineEntry> = timeline.iter().filter(|t| t.within_window).collect();
-    if within.len() < 2 {
-        return flags;
-    }
-
-    // Gap between hackathon start and first prompt
-    if let Some(first) = within.first() {
-        let gap = (first.timestamp - start).num_seconds().max(0) as u64;
-        if gap > 7200 {
-            flags.push(AnomalyFlag {
-                severity: Severity::Info,
-                category: "Late Start".into(),
-                description: format!(
-                    "First AI prompt was {} after hackathon start",
-                    session_stats::format_duration(gap),
-                ),
-                evidence: format!(
-                    "Hackathon start: {}. First prompt: {}",
-                    start.format("%H:%M:%S UTC"),
-                    first.timestamp.format("%H:%M:%S UTC"),
-                ),
-            });
-        }
+fn analyze(
+    entries: Vec<AuditEntry>,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    author: Option<&str>,
+    pushes: Option<&HashMap<String, DateTime<Utc>>>,
+) -> Option<ParticipantResult> {
+    let receipts: Vec<&Receipt> = entries.iter().flat_map(|e| &e.receipts).collect();
+    if receipts.is_empty() {
+        return None;
     }
 
-    // Inter-prompt gaps
-    for window in within.windows(2) {
-        let gap = (window[1].timestamp - window[0].timestamp)
-            .num_seconds()
-            .max(0) as u64;
-
-        if gap > 5400 {
-            let severity = if gap > 10800 {
-                Severity::Warning
-            } else {
-                Severity::Info
-            };
-
-            flags.push(AnomalyFlag {
-                severity,
-                category: "Activity Gap".into(),
-                description: format!(
-                    "{} gap between prompts during hackathon window",
-                    session_stats::format_duration(gap),
-                ),
-                evidence: format!(
-                    "From {} to {}. Prompt before gap: \"{}\"",
-                    window[0].timestamp.format("%H:%M:%S"),
-                    window[1].timestamp.format("%H:%M:%S"),
-                    truncate(&window[0].prompt_summary, 60),
-                ),
-            });
-        }
+    let mut log_args = vec!["-50".to_string()];
+    if let Some(a) = author {
+        log_args.push(format!("--author={}", a));
     }
-
-    flags
+    let cfg = config::load_config().anomalies;
+    let timeline = build_timeline(&receipts, start, end);
+    let mut anomalies = detect_anomalies(
+        &receipts,
+        &entries,
+        &timeline,
+        Some((start, end)),
+        &log_args,
+        &cfg,
+    );
+    if let Some(pushes) = pushes {
+        anomalies.extend(detect_push_time_discrepancies(
+            &commit_author_dates(&log_args),
+            pushes,
+            Some((start, end)),
+            &cfg,
+        ));
+    }
+    let score = calculate_integrity_score(&anomalies);
+    Some(ParticipantResult {
+        entries,
+        timeline,
+        anomalies,
+        score,
+    })
+}
+
+/// Uncommitted receipts recorded by `participant` (matched on the receipt's user).
+fn staged_for(staged: &[AuditEntry], participant: &str) -> Option<AuditEntry> {
+    let needle = participant.to_lowercase();
+    let entry = staged.first()?;
+    let receipts: Vec<Receipt> = entry
+        .receipts
+        .iter()
+        .filter(|r| r.user.to_lowercase().contains(&needle))
+        .cloned()
+        .collect();
+    if receipts.is_empty() {
+        return None;
+    }
+    Some(AuditEntry {
+        commit_sha: entry.commit_sha.clone(),
+        commit_date: entry.commit_date.clone(),
+        commit_author: entry.commit_author.clone(),
+        commit_message: entry.commit_message.clone(),
+        total_ai_lines: receipts.iter().map(|r| r.total_lines_changed()).sum(),
+        total_cost_usd: receipts.iter().map(|r| r.cost_usd).sum(),
+        receipts,
+        code_origin: None,
+        pull_request: entry.pull_request.clone(),
+    })
+}
+
+fn standing(participant: &str, result: &ParticipantResult) -> Standing {
+    let receipts = result.receipts();
+    let count = |severity: Severity| {
+        result
+            .anomalies
+            .iter()
+            .filter(|a| a.severity == severity)
+            .count()
+    };
+    let files: HashSet<String> = receipts
+        .iter()
+        .flat_map(|r| r.all_file_paths().into_iter().map(|f| make_rel(&f)))
+        .collect();
+    Standing {
+        participant: participant.to_string(),
+        score: result.score,
+        prompts: result.timeline.len(),
+        prompts_in_window: result.timeline.iter().filter(|t| t.within_window).count(),
+        ai_lines: receipts.iter().map(|r| r.effective_total_additions()).sum(),
+        files: files.len(),
+        cost_usd: receipts.iter().map(|r| r.cost_usd).sum(),
+        critical: count(Severity::Critical),
+        warning: count(Severity::Warning),
+        info: count(Severity::Info),
+    }
+}
+
+/// Highest integrity score first; ties go to fewer critical anomalies, then name.
+fn rank(standings: &mut [Standing]) {
+    standings.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(a.critical.cmp(&b.critical))
+            .then_with(|| a.participant.cmp(&b.participant))
+    });
 }
 
-// ---------------------------------------------------------------------------
-// Integrity score
-// ---------------------------------------------------------------------------
-
-fn calculate_integrity_score(anomalies: &[AnomalyFlag]) -> u32 {
-    let total_deduction: f64 = anomalies.iter().map(|a| a.severity.weight()).sum();
-    // Exponential decay: score = 100 * e^(-deduction/50)
-    let raw = 100.0 * (-total_deduction / 50.0_f64).exp();
-    raw.round().clamp(0.0, 100.0) as u32
+fn leaderboard_csv(standings: &[Standing]) -> String {
+    let mut csv = String::from(
+        "rank,participant,integrity_score,verdict,prompts,prompts_in_window,ai_lines,files,cost_usd,critical,warning,info\n",
+    );
+    for (i, s) in standings.iter().enumerate() {
+        let _ = writeln!(
+            csv,
+            "{},{},{},{},{},{},{},{},{:.4},{},{},{}",
+            i + 1,
+            audit::csv_escape(&s.participant),
+            s.score,
+            score_label(s.score),
+            s.prompts,
+            s.prompts_in_window,
+            s.ai_lines,
+            s.files,
+            s.cost_usd,
+            s.critical,
+            s.warning,
+            s.info,
+        );
+    }
+    csv
 }
 
-fn score_label(score: u32) -> &'static str {
-    if score >= 80 {
-        "PASS"
-    } else if score >= 50 {
-        "REVIEW"
-    } else {
-        "FAIL"
+/// Push every markdown heading one level down so per-participant reports nest
+/// under their `## Participant:` heading.
+fn demote_headings(md: &str) -> String {
+    let mut out = String::with_capacity(md.len() + 64);
+    for line in md.lines() {
+        if line.starts_with('#') {
+            out.push('#');
+        }
+        out.push_str(line);
+        out.push('\n');
     }
+    out
 }
 
 // ---------------------------------------------------------------------------
@@ -641,6 +415,56 @@ fn write_header(md: &mut String, start: DateTime<Utc>, end: DateTime<Utc>, autho
     );
 }
 
+fn write_participant(md: &mut String, result: &ParticipantResult) {
+    let receipts = result.receipts();
+    let file_attribution = build_file_attribution(&receipts);
+    write_summary(
+        md,
+        &receipts,
+        &result.timeline,
+        &result.anomalies,
+        result.score,
+    );
+    write_timeline(md, &result.timeline);
+    write_code_attribution(md, &receipts, &file_attribution);
+    write_anomaly_flags(md, &result.anomalies);
+    write_integrity_assessment(md, &result.anomalies, result.score);
+}
+
+fn write_team_summary(md: &mut String, standings: &[Standing], missing: &[&str]) {
+    let _ = writeln!(md, "## Team Summary\n");
+    let _ = writeln!(
+        md,
+        "| Rank | Participant | Integrity Score | Verdict | Prompts (in window) | AI lines | Files | Cost | Anomalies (C/W/I) |"
+    );
+    let _ = writeln!(
+        md,
+        "|------|-------------|-----------------|---------|---------------------|----------|-------|------|-------------------|"
+    );
+    for (i, s) in standings.iter().enumerate() {
+        let _ = writeln!(
+            md,
+            "| {} | {} | {}/100 | {} | {} ({}) | {} | {} | ${:.2} | {}/{}/{} |",
+            i + 1,
+            s.participant,
+            s.score,
+            score_label(s.score),
+            s.prompts,
+            s.prompts_in_window,
+            s.ai_lines,
+            s.files,
+            s.cost_usd,
+            s.critical,
+            s.warning,
+            s.info,
+        );
+    }
+    let _ = writeln!(md);
+    if !missing.is_empty() {
+        let _ = writeln!(md, "No AI receipts found for: {}\n", missing.join(", "));
+    }
+}
+
 fn write_summary(
     md: &mut String,
     receipts: &[&Receipt],
@@ -959,63 +783,6 @@ fn parse_datetime(s: &str) -> Option<DateTime<Utc>> {
     None
 }
 
-fn truncate(s: &str, max: usize) -> String {
-    if s.chars().count() <= max {
-        s.to_string()
-    } else {
-        let truncated: String = s.chars().take(max.saturating_sub(3)).collect();
-        format!("{}...", truncated)
-    }
-}
-
-fn make_rel(path: &str) -> String {
-    let cwd = std::env::current_dir()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
-    util::make_relative(path, &cwd)
-}
-
-fn is_source_file(path: &str) -> bool {
-    let extensions = [
-        ".rs", ".py", ".js", ".ts", ".tsx", ".jsx", ".go", ".java", ".c", ".cpp", ".h", ".hpp",
-        ".rb", ".swift", ".kt", ".cs", ".php", ".scala", ".ex", ".exs", ".zig", ".vue", ".svelte",
-    ];
-    extensions.iter().any(|ext| path.ends_with(ext))
-}
-
-fn get_recently_added_files() -> Vec<String> {
-    std::process::Command::new("git")
-        .args([
-            "log",
-            "--diff-filter=A",
-            "--name-only",
-            "--pretty=format:",
-            "-50",
-        ])
-        .output()
-        .ok()
-        .and_then(|o| String::from_utf8(o.stdout).ok())
-        .map(|s| {
-            s.lines()
-                .filter(|l| !l.is_empty())
-                .map(String::from)
-                .collect::<HashSet<_>>()
-                .into_iter()
-                .collect()
-        })
-        .unwrap_or_default()
-}
-
-fn count_files_in_commit(sha: &str) -> usize {
-    std::process::Command::new("git")
-        .args(["diff-tree", "--no-commit-id", "--name-only", "-r", sha])
-        .output()
-        .ok()
-        .and_then(|o| String::from_utf8(o.stdout).ok())
-        .map(|s| s.lines().filter(|l| !l.is_empty()).count())
-        .unwrap_or(0)
-}
-
 // ---------------------------------------------------------------------------
 // Tests
 // ---------------------------------------------------------------------------
@@ -1024,396 +791,6 @@ fn count_files_in_commit(sha: &str) -> usize {
 mod tests {
     use super::*;
 
-    fn make_anomaly(severity: Severity) -> AnomalyFlag {
-        AnomalyFlag {
-            severity,
-            category: "Test".into(),
-            description: "test".into(),
-            evidence: "test".into(),
-        }
-    }
-
-    #[test]
-    fn test_integrity_score_no_anomalies() {
-        assert_eq!(calculate_integrity_score(&[]), 100);
-    }
-
-    #[test]
-    fn test_integrity_score_one_warning() {
-        let anomalies = vec![make_anomaly(Severity::Warning)];
-        let score = calculate_integrity_score(&anomalies);
-        assert!(score >= 80 && score <= 90, "score={}", score);
-    }
-
-    #[test]
-    fn test_integrity_score_one_critical() {
-        let anomalies = vec![make_anomaly(Severity::Critical)];
-        let score = calculate_integrity_score(&anomalies);
-        assert!(score >= 60 && score <= 75, "score={}", score);
-    }
-
-    #[test]
-    fn test_integrity_score_multiple_criticals() {
-        let anomalies = vec![
-            make_anomaly(Severity::Critical),
-            make_anomaly(Severity::Critical),
-        ];
-        let score = calculate_integrity_score(&anomalies);
-        assert!(score < 50, "score={}", score);
-    }
-
-    #[test]
-    fn test_time_window_violations() {
-        let start = Utc::now() - chrono::Duration::hours(2);
-        let end = Utc::now() - chrono::Duration::hours(1);
-
-        let timeline = vec![TimelineEntry {
-            timestamp: Utc::now(), // AFTER the window
-            prompt_summary: "test".into(),
-            duration_secs: None,
-            model: "test".into(),
-            files_touched: vec![],
-            additions: 50,
-            deletions: 0,
-            within_window: false,
-        }];
-
-        let flags = detect_time_window_violations(&timeline, start, end);
-        assert_eq!(flags.len(), 1);
-        assert_eq!(flags[0].severity, Severity::Warning);
-    }
-
-    #[test]
-    fn test_pre_written_code_detection() {
-        let now = Utc::now();
-        let r = Receipt {
-            id: "test".into(),
-            provider: "claude".into(),
-            model: "opus".into(),
-            session_id: "s1".into(),
-            prompt_summary: "Create the entire app".into(),
-            response_summary: None,
-            prompt_hash: "hash1".into(),
-            message_count: 2,
-            cost_usd: 0.1,
-            input_tokens: None,
-            output_tokens: None,
-            cache_read_tokens: None,
-            cache_creation_tokens: None,
-            timestamp: now,
-            session_start: None,
-            session_end: None,
-            session_duration_secs: None,
-            ai_response_time_secs: None,
-            prompt_submitted_at: Some(now),
-            prompt_duration_secs: Some(120),
-            accepted_lines: None,
-            overridden_lines: None,
-            prompt_quality: None,
-            user: "test".into(),
-            file_path: String::new(),
-            line_range: (0, 0),
-            files_changed: vec![crate::core::receipt::FileChange {
-                path: "src/big_file.rs".into(),
-                line_range: (1, 250),
-                blob_hash: None,
-                additions: 250,
-                deletions: 0,
-            }],
-            parent_receipt_id: None,
-            parent_session_id: None,
-            is_continuation: None,
-            continuation_depth: None,
-            prompt_number: Some(1),
-            total_additions: 250,
-            total_deletions: 0,
-            tools_used: vec![],
-            mcp_servers: vec![],
-            agents_spawned: vec![],
-            subagent_activities: vec![],
-            concurrent_tool_calls: None,
-            user_decisions: vec![],
-            conversation: None,
-        };
-
-        let receipts = vec![&r];
-        let flags = detect_pre_written_code(&receipts);
-        assert!(!flags.is_empty(), "Should flag 250-line single-touch file");
-        assert_eq!(flags[0].severity, Severity::Critical);
-    }
-
-    #[test]
-    fn test_no_false_positive_on_small_file() {
-        let now = Utc::now();
-        let r = Receipt {
-            id: "test".into(),
-            provider: "claude".into(),
-            model: "opus".into(),
-            session_id: "s1".into(),
-            prompt_summary: "Add config".into(),
-            response_summary: None,
-            prompt_hash: "hash1".into(),
-            message_count: 2,
-            cost_usd: 0.01,
-            input_tokens: None,
-            output_tokens: None,
-            cache_read_tokens: None,
-            cache_creation_tokens: None,
-            timestamp: now,
-            session_start: None,
-            session_end: None,
-            session_duration_secs: None,
-            ai_response_time_secs: None,
-            prompt_submitted_at: Some(now),
-            prompt_duration_secs: Some(60),
-            accepted_lines: None,
-            overridden_lines: None,
-            prompt_quality: None,
-            user: "test".into(),
-            file_path: String::new(),
-            line_range: (0, 0),
-            files_changed: vec![crate::core::receipt::FileChange {
-                path: "src/config.rs".into(),
-                line_range: (1, 30),
-                blob_hash: None,
-                additions: 30,
-                deletions: 0,
-            }],
-            parent_receipt_id: None,
-            parent_session_id: None,
-            is_continuation: None,
-            continuation_depth: None,
-            prompt_number: Some(1),
-            total_additions: 30,
-            total_deletions: 0,
-            tools_used: vec![],
-            mcp_servers: vec![],
-            agents_spawned: vec![],
-            subagent_activities: vec![],
-            concurrent_tool_calls: None,
-            user_decisions: vec![],
-            conversation: None,
-        };
-
-        let receipts = vec![&r];
-        let flags = detect_pre_written_code(&receipts);
-        assert!(flags.is_empty(), "30-line file should NOT be flagged");
-    }
-
-    #[test]
-    fn test_duplicate_prompt_hashes() {
-        let now = Utc::now();
-        let make_receipt = |hash: &str| Receipt {
-            id: Receipt::new_id(),
-            provider: "claude".into(),
-            model: "opus".into(),
-            session_id: "s1".into(),
-            prompt_summary: "test".into(),
-            response_summary: None,
-            prompt_hash: hash.into(),
-            message_count: 1,
-            cost_usd: 0.0,
-            input_tokens: None,
-            output_tokens: None,
-            cache_read_tokens: None,
-            cache_creation_tokens: None,
-            timestamp: now,
-            session_start: None,
-            session_end: None,
-            session_duration_secs: None,
-            ai_response_time_secs: None,
-            prompt_submitted_at: None,
-            prompt_duration_secs: None,
-            accepted_lines: None,
-            overridden_lines: None,
-            prompt_quality: None,
-            user: "test".into(),
-            file_path: String::new(),
-            line_range: (0, 0),
-            files_changed: vec![],
-            parent_receipt_id: None,
-            parent_session_id: None,
-            is_continuation: None,
-            continuation_depth: None,
-            prompt_number: Some(1),
-            total_additions: 0,
-            total_deletions: 0,
-            tools_used: vec![],
-            mcp_servers: vec![],
-            agents_spawned: vec![],
-            subagent_activities: vec![],
-            concurrent_tool_calls: None,
-            user_decisions: vec![],
-            conversation: None,
-        };
-
-        let r1 = make_receipt("sha256:abc123");
-        let r2 = make_receipt("sha256:abc123");
-        let r3 = make_receipt("sha256:abc123");
-        let r4 = make_receipt("sha256:different");
-
-        let receipts = vec![&r1, &r2, &r3, &r4];
-        let flags = detect_duplicate_prompt_hashes(&receipts);
-        assert_eq!(flags.len(), 1);
-        assert_eq!(flags[0].severity, Severity::Info); // 3 dupes → INFO (threshold: >3 for WARNING)
-    }
-
-    #[test]
-    fn test_unusual_session_pattern() {
-        let now = Utc::now();
-        let r = Receipt {
-            id: "test".into(),
-            provider: "claude".into(),
-            model: "opus".into(),
-            session_id: "s1".into(),
-            prompt_summary: "Do everything".into(),
-            response_summary: None,
-            prompt_hash: "hash".into(),
-            message_count: 2,
-            cost_usd: 0.0,
-            input_tokens: None,
-            output_tokens: None,
-            cache_read_tokens: None,
-            cache_creation_tokens: None,
-            timestamp: now,
-            session_start: None,
-            session_end: None,
-            session_duration_secs: None,
-            ai_response_time_secs: None,
-            prompt_submitted_at: Some(now),
-            prompt_duration_secs: Some(30), // 30 seconds
-            accepted_lines: None,
-            overridden_lines: None,
-            prompt_quality: None,
-            user: "test".into(),
-            file_path: String::new(),
-            line_range: (0, 0),
-            files_changed: vec![],
-            parent_receipt_id: None,
-            parent_session_id: None,
-            is_continuation: None,
-            continuation_depth: None,
-            prompt_number: Some(1),
-            total_additions: 100, // 100 lines in 30s = 3.3 lines/sec
-            total_deletions: 0,
-            tools_used: vec![],
-            mcp_servers: vec![],
-            agents_spawned: vec![],
-            subagent_activities: vec![],
-            concurrent_tool_calls: None,
-            user_decisions: vec![],
-            conversation: None,
-        };
-
-        let timeline = vec![]; // empty timeline so aggregate check doesn't fire
-        let flags = detect_unusual_session_patterns(&[&r], &timeline);
-        assert!(!flags.is_empty(), "100 lines in 30s should be flagged");
-    }
-
-    #[test]
-    fn test_timeline_ordering() {
-        let now = Utc::now();
-        let start = now - chrono::Duration::hours(2);
-        let end = now + chrono::Duration::hours(2);
-
-        let make_receipt = |mins_ago: i64| Receipt {
-            id: Receipt::new_id(),
-            provider: "claude".into(),
-            model: "opus".into(),
-            session_id: "s1".into(),
-            prompt_summary: format!("prompt at -{}", mins_ago),
-            response_summary: None,
-            prompt_hash: "h".into(),
-            message_count: 1,
-            cost_usd: 0.0,
-            input_tokens: None,
-            output_tokens: None,
-            cache_read_tokens: None,
-            cache_creation_tokens: None,
-            timestamp: now - chrono::Duration::minutes(mins_ago),
-            session_start: None,
-            session_end: None,
-            session_duration_secs: None,
-            ai_response_time_secs: None,
-            prompt_submitted_at: Some(now - chrono::Duration::minutes(mins_ago)),
-            prompt_duration_secs: None,
-            accepted_lines: None,
-            overridden_lines: None,
-            prompt_quality: None,
-            user: "test".into(),
-            file_path: String::new(),
-            line_range: (0, 0),
-            files_changed: vec![],
-            parent_receipt_id: None,
-            parent_session_id: None,
-            is_continuation: None,
-            continuation_depth: None,
-            prompt_number: Some(1),
-            total_additions: 0,
-            total_deletions: 0,
-            tools_used: vec![],
-            mcp_servers: vec![],
-            agents_spawned: vec![],
-            subagent_activities: vec![],
-            concurrent_tool_calls: None,
-            user_decisions: vec![],
-            conversation: None,
-        };
-
-        let r1 = make_receipt(30); // 30 min ago
-        let r2 = make_receipt(60); // 60 min ago
-        let r3 = make_receipt(10); // 10 min ago
-
-        let receipts = vec![&r1, &r2, &r3];
-        let timeline = build_timeline(&receipts, start, end);
-
-        // Should be sorted chronologically (oldest first)
-        assert!(timeline[0].timestamp <= timeline[1].timestamp);
-        assert!(timeline[1].timestamp <= timeline[2].timestamp);
-    }
-
-    #[test]
-    fn test_is_source_file() {
-        assert!(is_source_file("src/main.rs"));
-        assert!(is_source_file("app.py"));
-        assert!(is_source_file("index.tsx"));
-        assert!(!is_source_file("README.md"));
-        assert!(!is_source_file("config.json"));
-        assert!(!is_source_file("image.png"));
-    }
-
-    #[test]
-    fn test_time_gap_detection() {
-        let now = Utc::now();
-        let start = now - chrono::Duration::hours(4);
-
-        let timeline = vec![
-            TimelineEntry {
-                timestamp: now - chrono::Duration::hours(3),
-                prompt_summary: "first".into(),
-                duration_secs: None,
-                model: "test".into(),
-                files_touched: vec![],
-                additions: 10,
-                deletions: 0,
-                within_window: true,
-            },
-            TimelineEntry {
-                timestamp: now, // 3 hour gap
-                prompt_summary: "second".into(),
-                duration_secs: None,
-                model: "test".into(),
-                files_touched: vec![],
-                additions: 10,
-                deletions: 0,
-                within_window: true,
-            },
-        ];
-
-        let flags = detect_time_gaps(&timeline, start);
-        assert!(!flags.is_empty(), "3-hour gap should be flagged");
-    }
-
     #[test]
     fn test_parse_datetime_rfc3339() {
         use chrono::Timelike;
@@ -1429,4 +806,44 @@ mod tests {
         assert!(dt.is_some());
         assert_eq!(dt.unwrap().hour(), 0);
     }
+
+    fn standing(participant: &str, score: u32, critical: usize) -> Standing {
+        Standing {
+            participant: participant.to_string(),
+            score,
+            prompts: 4,
+            prompts_in_window: 3,
+            ai_lines: 120,
+            files: 2,
+            cost_usd: 0.5,
+            critical,
+            warning: 1,
+            info: 0,
+        }
+    }
+
+    #[test]
+    fn test_leaderboard_ranks_by_score() {
+        let mut standings = vec![
+            standing("Team, Red", 70, 0),
+            standing("blue", 95, 0),
+            standing("green", 70, 1),
+        ];
+        rank(&mut standings);
+        let csv = leaderboard_csv(&standings);
+        let lines: Vec<&str> = csv.lines().collect();
+        assert!(lines[0].starts_with("rank,participant,integrity_score"));
+        assert_eq!(lines[1], "1,blue,95,PASS,4,3,120,2,0.5000,0,1,0");
+        assert!(lines[2].starts_with("2,\"Team, Red\",70,REVIEW"));
+        assert!(lines[3].starts_with("3,green,70,REVIEW"));
+    }
+
+    #[test]
+    fn test_demote_headings() {
+        let md = "## 1. Summary\n\n| a | b |\n### Conclusion\ntext #1\n";
+        assert_eq!(
+            demote_headings(md),
+            "### 1. Summary\n\n| a | b |\n#### Conclusion\ntext #1\n"
+        );
+    }
 }
@@ -0,0 +1,329 @@
+/// `blameprompt heatmap`: AI-line density per directory and file.
+///
+/// Every tracked text file is counted; files some receipt mentions are run
+/// through `git blame` attribution (see `blame::ai_line_count`), the rest count
+/// as human. The result is written as a JSON tree (`--output heatmap.json`) or
+/// rendered as a treemap SVG (`--output heatmap.svg`) where area is file size
+/// and colour is the AI share.
+use crate::commands::blame;
+use crate::core::util;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write;
+
+const SVG_WIDTH: f64 = 1200.0;
+const SVG_HEIGHT: f64 = 800.0;
+/// Height reserved for a directory's name above its children.
+const DIR_HEADER: f64 = 14.0;
+
+#[derive(Debug, Serialize)]
+pub struct HeatNode {
+    pub name: String,
+    pub path: String,
+    pub total_lines: u32,
+    pub ai_lines: u32,
+    pub ai_density: f64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<HeatNode>,
+}
+
+impl HeatNode {
+    fn dir(name: &str, path: &str) -> Self {
+        HeatNode {
+            name: name.to_string(),
+            path: path.to_string(),
+            total_lines: 0,
+            ai_lines: 0,
+            ai_density: 0.0,
+            children: Vec::new(),
+        }
+    }
+
+    fn is_file(&self) -> bool {
+        self.children.is_empty()
+    }
+}
+
+pub fn run(output: &str) -> Result<(), String> {
+    let files = file_line_counts()?;
+    let root_name = std::env::current_dir()
+        .ok()
+        .and_then(|p| p.file_name().map(|n| n.to_string_lossy().to_string()))
+        .unwrap_or_else(|| ".".to_string());
+    let tree = build_tree(&root_name, &files);
+
+    let content = if output.ends_with(".svg") {
+        render_svg(&tree)
+    } else {
+        serde_json::to_string_pretty(&tree).map_err(|e| e.to_string())?
+    };
+    std::fs::write(output, content).map_err(|e| format!("Cannot write {}: {}", output, e))?;
+    println!(
+        "[BlamePrompt] Heat map written to {} ({} files, {:.1}% AI lines)",
+        output,
+        files.len(),
+        tree.ai_density * 100.0
+    );
+    Ok(())
+}
+
+/// `(path, ai_lines, total_lines)` for every tracked text file.
+pub fn file_line_counts() -> Result<Vec<(String, u32, u32)>, String> {
+    let tracked = std::process::Command::new("git")
+        .args(["ls-files", "-z"])
+        .output()
+        .map_err(|e| format!("git ls-files failed: {}", e))?;
+    if !tracked.status.success() {
+        return Err("Not in a git repository.".to_string());
+    }
+    let candidates = blame::sidecar_candidates();
+    crate::git::partial_clone::prefetch_history(&candidates);
+    let with_receipts: HashSet<String> = candidates.into_iter().collect();
+
+    let mut files: Vec<(String, u32, u32)> = Vec::new();
+    for path in String::from_utf8_lossy(&tracked.stdout).split('\0') {
+        if path.is_empty() {
+            continue;
+        }
+        let Ok(bytes) = std::fs::read(path) else {
+            continue;
+        };
+        if util::is_binary_content(&bytes) {
+            continue;
+        }
+        let counts = if with_receipts.contains(path) {
+            blame::ai_line_count(path)
+        } else {
+            None
+        };
+        let (ai, total) = counts.unwrap_or_else(|| (0, line_count(&bytes)));
+        files.push((path.to_string(), ai, total));
+    }
+    Ok(files)
+}
+
+fn line_count(bytes: &[u8]) -> u32 {
+    let newlines = bytes.iter().filter(|b| **b == b'\n').count() as u32;
+    newlines + u32::from(!bytes.is_empty() && !bytes.ends_with(b"\n"))
+}
+
+/// Fold `(path, ai_lines, total_lines)` into a directory tree with totals and
+/// densities at every level; children are sorted largest first.
+pub fn build_tree(root_name: &str, files: &[(String, u32, u32)]) -> HeatNode {
+    let mut root = HeatNode::dir(root_name, "");
+    for (path, ai, total) in files {
+        let mut node = &mut root;
+        let mut prefix = String::new();
+        for part in path.split('/') {
+            if !prefix.is_empty() {
+                prefix.push('/');
+            }
+            prefix.push_str(part);
+            let i = match node.children.iter().position(|c| c.name == part) {
+                Some(i) => i,
+                None => {
+                    node.children.push(HeatNode::dir(part, &prefix));
+                    node.children.len() - 1
+                }
+            };
+            node = &mut node.children[i];
+        }
+        node.ai_lines = *ai;
+        node.total_lines = *total;
+    }
+    finish(&mut root);
+    root
+}
+
+fn finish(node: &mut HeatNode) {
+    if !node.is_file() {
+        for c in &mut node.children {
+            finish(c);
+        }
+        node.children.retain(|c| c.total_lines > 0);
+        node.children
+            .sort_by(|a, b| b.total_lines.cmp(&a.total_lines).then(a.name.cmp(&b.name)));
+        node.total_lines = node.children.iter().map(|c| c.total_lines).sum();
+        node.ai_lines = node.children.iter().map(|c| c.ai_lines).sum();
+    }
+    node.ai_density = if node.total_lines > 0 {
+        node.ai_lines as f64 / node.total_lines as f64
+    } else {
+        0.0
+    };
+}
+
+/// Treemap SVG: each level is sliced along its longer side, so rectangles stay
+/// roughly square without a full squarified layout.
+pub fn render_svg(tree: &HeatNode) -> String {
+    let mut svg = String::new();
+    writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{w}" height="{h}" viewBox="0 0 {w} {h}" font-family="sans-serif" font-size="10">"#,
+        w = SVG_WIDTH,
+        h = SVG_HEIGHT + 20.0
+    )
+    .ok();
+    writeln!(
+        svg,
+        r#"<text x="4" y="14" font-size="12">{} — {:.1}% AI ({} of {} lines)</text>"#,
+        escape(&tree.name),
+        tree.ai_density * 100.0,
+        tree.ai_lines,
+        tree.total_lines
+    )
+    .ok();
+    layout(tree, 0.0, 20.0, SVG_WIDTH, SVG_HEIGHT, true, &mut svg);
+    svg.push_str("</svg>\n");
+    svg
+}
+
+fn layout(node: &HeatNode, x: f64, y: f64, w: f64, h: f64, root: bool, svg: &mut String) {
+    if w < 1.0 || h < 1.0 {
+        return;
+    }
+    let title = format!(
+        "{} — {:.1}% AI ({} of {} lines)",
+        if node.path.is_empty() {
+            &node.name
+        } else {
+            &node.path
+        },
+        node.ai_density * 100.0,
+        node.ai_lines,
+        node.total_lines
+    );
+
+    if node.is_file() {
+        writeln!(
+            svg,
+            r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="{}" stroke="#fff" stroke-width="0.5"><title>{}</title></rect>"##,
+            x,
+            y,
+            w,
+            h,
+            color(node.ai_density),
+            escape(&title)
+        )
+        .ok();
+        label(&node.name, x, y, w, h, svg);
+        return;
+    }
+
+    let (mut cx, mut cy, mut cw, mut ch) = (x, y, w, h);
+    if !root {
+        writeln!(
+            svg,
+            r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="#f5f5f5" stroke="#999" stroke-width="0.5"><title>{}</title></rect>"##,
+            x,
+            y,
+            w,
+            h,
+            escape(&title)
+        )
+        .ok();
+        if h > DIR_HEADER * 2.0 {
+            label(&format!("{}/", node.name), x, y, w, DIR_HEADER, svg);
+            cy += DIR_HEADER;
+            ch -= DIR_HEADER;
+        }
+        cx += 1.0;
+        cw -= 2.0;
+        ch -= 1.0;
+    }
+
+    let total = node.total_lines.max(1) as f64;
+    let horizontal = cw >= ch;
+    for child in &node.children {
+        let share = child.total_lines as f64 / total;
+        if horizontal {
+            let cw_child = cw * share;
+            layout(child, cx, cy, cw_child, ch, false, svg);
+            cx += cw_child;
+        } else {
+            let ch_child = ch * share;
+            layout(child, cx, cy, cw, ch_child, false, svg);
+            cy += ch_child;
+        }
+    }
+}
+
+fn label(text: &str, x: f64, y: f64, w: f64, h: f64, svg: &mut String) {
+    let fits = (w / 6.0) as usize;
+    if fits < 3 || h < 12.0 {
+        return;
+    }
+    let text: String = if text.chars().count() > fits {
+        text.chars().take(fits - 1).chain(['…']).collect()
+    } else {
+        text.to_string()
+    };
+    writeln!(
+        svg,
+        r#"<text x="{:.1}" y="{:.1}" pointer-events="none">{}</text>"#,
+        x + 3.0,
+        y + 11.0,
+        escape(&text)
+    )
+    .ok();
+}
+
+/// Light grey for human-only code through to red for fully AI-generated code.
+fn color(density: f64) -> String {
+    let d = density.clamp(0.0, 1.0);
+    let lerp = |a: f64, b: f64| (a + (b - a) * d).round() as u8;
+    format!(
+        "#{:02x}{:02x}{:02x}",
+        lerp(230.0, 211.0),
+        lerp(234.0, 47.0),
+        lerp(238.0, 47.0)
+    )
+}
+
+fn escape(s: &str) -> String {
+    s.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> HeatNode {
+        build_tree(
+            "repo",
+            &[
+                ("src/main.rs".to_string(), 30, 100),
+                ("src/core/a.rs".to_string(), 50, 50),
+                ("README.md".to_string(), 0, 50),
+                ("empty.txt".to_string(), 0, 0),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_build_tree_aggregates_directories() {
+        let tree = sample();
+        assert_eq!((tree.ai_lines, tree.total_lines), (80, 200));
+        assert!((tree.ai_density - 0.4).abs() < 1e-9);
+        // Empty files are dropped; largest first.
+        let names: Vec<&str> = tree.children.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, vec!["src", "README.md"]);
+        let src = &tree.children[0];
+        assert_eq!((src.ai_lines, src.total_lines), (80, 150));
+        assert_eq!(src.children[1].path, "src/core");
+        assert!((src.children[1].ai_density - 1.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_render_svg_has_a_rect_per_file() {
+        let svg = render_svg(&sample());
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<title>src/").count(), 3);
+        assert!(svg.contains(&color(1.0)));
+        assert!(svg.contains("40.0% AI"));
+        assert_eq!(color(0.0), "#e6eaee");
+    }
+}
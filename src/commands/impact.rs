@@ -0,0 +1,339 @@
+/// `blameprompt impact <file[:lines]>`: who and what produced code before you
+/// delete or rewrite it.
+///
+/// For the AI lines in the file (or in `file:10-40`), lists the prompts and
+/// sessions that wrote them, whether their commits were human-reviewed
+/// (`blameprompt review`), and every other file those same prompts wrote:
+/// code that likely depends on, or was written alongside, what you are about
+/// to change.
+use crate::commands::check_provenance::{self, ProvenanceRange};
+use crate::commands::review::{self, ReviewAnnotation, ReviewStatus};
+use crate::core::receipt::Receipt;
+use crate::core::{term, util};
+use crate::git::notes;
+use serde::Serialize;
+use std::collections::HashMap;
+
+/// One prompt behind part of the target, with the lines it wrote there.
+#[derive(Debug, Serialize)]
+pub struct PromptImpact {
+    pub receipt_id: String,
+    pub commit_sha: String,
+    pub ranges: Vec<(u32, u32)>,
+    pub lines: u32,
+    pub provider: String,
+    pub model: String,
+    pub session_id: String,
+    pub user: String,
+    pub timestamp: String,
+    pub prompt: String,
+    /// `reviewed`, `flagged` or `unreviewed`.
+    pub review: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub review_detail: Option<String>,
+    /// Files other than the target the same prompt wrote.
+    pub other_files: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImpactReport {
+    pub file: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub lines: Option<(u32, u32)>,
+    pub total_lines: u32,
+    pub ai_lines: u32,
+    pub human_lines: u32,
+    pub prompts: Vec<PromptImpact>,
+    /// Union of `other_files` over every prompt.
+    pub related_files: Vec<String>,
+}
+
+/// Split `src/a.rs:10-40` (or `src/a.rs:10`) into the file and line range.
+/// A target that names an existing file is taken whole.
+pub fn parse_target(target: &str) -> Result<(String, Option<(u32, u32)>), String> {
+    if std::path::Path::new(target).exists() {
+        return Ok((target.to_string(), None));
+    }
+    let Some((file, lines)) = target.rsplit_once(':') else {
+        return Ok((target.to_string(), None));
+    };
+    let parse = |n: &str| {
+        n.trim()
+            .parse::<u32>()
+            .ok()
+            .filter(|n| *n > 0)
+            .ok_or_else(|| format!("Invalid line range '{}': expected N or N-M", lines))
+    };
+    let range = match lines.split_once('-') {
+        Some((start, end)) => (parse(start)?, parse(end)?),
+        None => (parse(lines)?, parse(lines)?),
+    };
+    if range.0 > range.1 {
+        return Err(format!(
+            "Invalid line range '{}': start is after end",
+            lines
+        ));
+    }
+    Ok((file.to_string(), Some(range)))
+}
+
+/// (commit, receipt id, line spans) for one prompt's lines in the target.
+type ReceiptSpans = (String, String, Vec<(u32, u32)>);
+
+/// AI ranges grouped by (commit, receipt), in order of first appearance.
+pub fn group_by_receipt(ranges: &[ProvenanceRange]) -> Vec<ReceiptSpans> {
+    let mut groups: Vec<ReceiptSpans> = Vec::new();
+    for r in ranges.iter().filter(|r| r.is_ai) {
+        let Some(id) = &r.receipt_id else {
+            continue;
+        };
+        let span = (r.start_line, r.end_line);
+        match groups
+            .iter_mut()
+            .find(|(sha, rid, _)| *sha == r.commit_sha && rid == id)
+        {
+            Some(group) => group.2.push(span),
+            None => groups.push((r.commit_sha.clone(), id.clone(), vec![span])),
+        }
+    }
+    groups
+}
+
+pub fn build_report(file: &str, lines: Option<(u32, u32)>) -> Result<ImpactReport, String> {
+    let provenance = check_provenance::cached_provenance(file)
+        .ok_or_else(|| format!("Cannot blame '{}': is it tracked by git?", file))?;
+    let selected: Vec<_> = provenance
+        .into_iter()
+        .filter(|lp| lines.is_none_or(|(start, end)| (start..=end).contains(&lp.line_number)))
+        .collect();
+    if selected.is_empty() {
+        return Err(format!("No lines selected in '{}'", file));
+    }
+    let ai_lines = selected.iter().filter(|lp| lp.is_ai).count() as u32;
+    let ranges = check_provenance::collapse_to_ranges(&selected);
+
+    let annotations = review::read_annotations();
+    let mut payloads: HashMap<String, Vec<Receipt>> = HashMap::new();
+    let mut prompts = Vec::new();
+    for (sha, receipt_id, spans) in group_by_receipt(&ranges) {
+        let receipts = payloads.entry(sha.clone()).or_insert_with(|| {
+            notes::read_receipts_for_commit(&sha)
+                .map(|p| p.receipts)
+                .unwrap_or_default()
+        });
+        let Some(receipt) = receipts.iter().find(|r| r.id == receipt_id) else {
+            continue;
+        };
+        let mut other_files: Vec<String> = receipt
+            .all_file_changes()
+            .iter()
+            .map(|fc| fc.path.clone())
+            .filter(|p| !util::paths_match(p, file))
+            .collect();
+        other_files.sort();
+        other_files.dedup();
+        let (review, review_detail) = describe_review(annotations.get(&sha));
+        prompts.push(PromptImpact {
+            receipt_id,
+            commit_sha: sha,
+            lines: spans.iter().map(|(s, e)| e - s + 1).sum(),
+            ranges: spans,
+            provider: receipt.provider.clone(),
+            model: receipt.model.clone(),
+            session_id: receipt.session_id.clone(),
+            user: receipt.user.clone(),
+            timestamp: receipt.timestamp.format("%Y-%m-%d").to_string(),
+            prompt: receipt
+                .intent_summary
+                .as_deref()
+                .unwrap_or(&receipt.prompt_summary)
+                .split_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            review,
+            review_detail,
+            other_files,
+        });
+    }
+
+    let mut related_files: Vec<String> = prompts
+        .iter()
+        .flat_map(|p| p.other_files.iter().cloned())
+        .collect();
+    related_files.sort();
+    related_files.dedup();
+    Ok(ImpactReport {
+        file: file.to_string(),
+        lines,
+        total_lines: selected.len() as u32,
+        ai_lines,
+        human_lines: selected.len() as u32 - ai_lines,
+        prompts,
+        related_files,
+    })
+}
+
+fn describe_review(annotation: Option<&ReviewAnnotation>) -> (String, Option<String>) {
+    match annotation {
+        None => ("unreviewed".to_string(), None),
+        Some(a) => {
+            let status = match a.status {
+                ReviewStatus::Reviewed => "reviewed",
+                ReviewStatus::Flagged => "flagged",
+            };
+            let detail = format!(
+                "by {} on {}{}",
+                a.reviewer,
+                a.reviewed_at.format("%Y-%m-%d"),
+                a.comment
+                    .as_deref()
+                    .map(|c| format!(": {}", c))
+                    .unwrap_or_default()
+            );
+            (status.to_string(), Some(detail))
+        }
+    }
+}
+
+pub fn run(target: &str, format: &str) -> Result<(), String> {
+    let (file, lines) = parse_target(target)?;
+    let report = build_report(&file, lines)?;
+    if format == "json" {
+        let json = serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?;
+        println!("{}", json);
+        return Ok(());
+    }
+    print_report(&report);
+    Ok(())
+}
+
+fn format_spans(spans: &[(u32, u32)]) -> String {
+    spans
+        .iter()
+        .map(|(s, e)| {
+            if s == e {
+                s.to_string()
+            } else {
+                format!("{}-{}", s, e)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_report(report: &ImpactReport) {
+    println!();
+    match report.lines {
+        Some(range) => println!("  Impact: {} lines {}", report.file, format_spans(&[range])),
+        None => println!("  Impact: {}", report.file),
+    }
+    println!("  ─────────────────────────────────────────────");
+    println!(
+        "  {} line(s): {} AI from {} prompt(s), {} human",
+        report.total_lines,
+        report.ai_lines,
+        report.prompts.len(),
+        report.human_lines
+    );
+    if report.prompts.is_empty() {
+        println!("\n  No AI receipts cover these lines.\n");
+        return;
+    }
+
+    for p in &report.prompts {
+        println!();
+        println!(
+            "  [{}] {} / {} · {} · {}",
+            util::short_sha(&p.receipt_id),
+            p.provider,
+            p.model,
+            p.timestamp,
+            p.user
+        );
+        println!("    Prompt:   {}", p.prompt);
+        println!("    Session:  {}", p.session_id);
+        println!(
+            "    Lines:    {} ({} line(s)), commit {}",
+            format_spans(&p.ranges),
+            p.lines,
+            util::short_sha(&p.commit_sha)
+        );
+        match &p.review_detail {
+            Some(detail) => println!("    Review:   {} {}", p.review, detail),
+            None => println!("    Review:   {}", term::yellow("not reviewed")),
+        }
+        if !p.other_files.is_empty() {
+            println!("    Also wrote: {}", p.other_files.join(", "));
+        }
+    }
+
+    println!();
+    if report.related_files.is_empty() {
+        println!("  These prompts wrote no other files.");
+    } else {
+        println!("  Other files from the same prompts (check them before changing this code):");
+        for f in &report.related_files {
+            println!("    {}", f);
+        }
+    }
+    let unreviewed = report
+        .prompts
+        .iter()
+        .filter(|p| p.review == "unreviewed")
+        .count();
+    if unreviewed > 0 {
+        println!(
+            "\n  {} of {} prompt(s) come from commits no one has reviewed (see `blameprompt review`).",
+            unreviewed,
+            report.prompts.len()
+        );
+    }
+    println!();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn range(start: u32, end: u32, ai: bool, sha: &str, id: Option<&str>) -> ProvenanceRange {
+        ProvenanceRange {
+            start_line: start,
+            end_line: end,
+            is_ai: ai,
+            model: None,
+            provider: None,
+            receipt_id: id.map(String::from),
+            prompt_summary: None,
+            commit_sha: sha.to_string(),
+            author: "Dana".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_target_and_group_by_receipt() {
+        assert_eq!(
+            parse_target("src/missing.rs").unwrap(),
+            ("src/missing.rs".to_string(), None)
+        );
+        assert_eq!(
+            parse_target("src/missing.rs:10-40").unwrap(),
+            ("src/missing.rs".to_string(), Some((10, 40)))
+        );
+        assert_eq!(parse_target("src/missing.rs:7").unwrap().1, Some((7, 7)));
+        assert!(parse_target("src/missing.rs:40-10").is_err());
+        assert!(parse_target("src/missing.rs:abc").is_err());
+
+        let ranges = vec![
+            range(1, 4, true, "c1", Some("r1")),
+            range(5, 6, false, "c2", None),
+            range(7, 9, true, "c3", Some("r2")),
+            range(10, 12, true, "c1", Some("r1")),
+        ];
+        let groups = group_by_receipt(&ranges);
+        assert_eq!(groups.len(), 2);
+        assert_eq!(groups[0].1, "r1");
+        assert_eq!(groups[0].2, vec![(1, 4), (10, 12)]);
+        assert_eq!(groups[1].2, vec![(7, 9)]);
+        assert_eq!(format_spans(&groups[0].2), "1-4, 10-12");
+    }
+}
@@ -1,5 +1,7 @@
 use crate::commands::audit;
+use crate::core::config;
 use crate::core::model_classifier::{self, ModelLicense};
+use crate::core::scan_exclude::{ScanExclusions, Skipped};
 use chrono::Utc;
 use std::collections::HashMap;
 
@@ -99,8 +101,15 @@ pub fn run(output: &str) {
     let mut model_issues: Vec<(String, String, String, String, String, Vec<String>)> = Vec::new();
     let mut models_seen: HashMap<String, Vec<String>> = HashMap::new();
 
+    let cfg = config::load_config();
+    let exclusions = ScanExclusions::load(&cfg.scan, &cfg.scan.license);
+    let mut skipped = Skipped::default();
     for r in &all_receipts {
         for f in r.all_file_paths() {
+            if let Some(reason) = exclusions.reason(&f) {
+                skipped.add(relative_path(&f), reason);
+                continue;
+            }
             models_seen
                 .entry(r.model.clone())
                 .or_default()
@@ -175,6 +184,7 @@ pub fn run(output: &str) {
         all_receipts.len()
     ));
     md.push_str(&format!("| Unique models used | {} |\n", models_seen.len()));
+    md.push_str(&format!("| Files excluded | {} |\n", skipped.len()));
     md.push_str(&format!("| License issues flagged | {} |\n", flagged_count));
     md.push_str(&format!(
         "| Open-source model warnings | {} |\n\n",
@@ -223,6 +233,8 @@ pub fn run(output: &str) {
     }
     md.push('\n');
 
+    md.push_str(&skipped.to_markdown());
+
     // Warnings
     if !warnings.is_empty() {
         md.push_str("## Open-Source Model Warnings\n\n");
@@ -0,0 +1,190 @@
+/// Receipt lifecycle: whether the files a receipt wrote still exist.
+///
+/// A receipt is *deleted* once every file it changed has been removed from
+/// the repository by a later commit. Its code cannot be in the tree any more,
+/// so `audit` and `analytics` leave it out unless `--include-deleted`, and
+/// `show` says which commit removed the last file. Renames are not deletions:
+/// a path that is gone from HEAD without a recorded deletion (renamed, or
+/// never committed) keeps its receipts live. Receipts that only deleted files
+/// are never stale.
+use crate::commands::audit::AuditEntry;
+use crate::core::receipt::{ChangeType, Receipt};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+
+pub struct FileLifecycle {
+    root: Option<String>,
+    tracked: HashSet<String>,
+    deletions: RefCell<HashMap<String, Option<String>>>,
+}
+
+impl FileLifecycle {
+    /// Snapshot of the files tracked at HEAD.
+    pub fn load() -> Self {
+        let root = git(&["rev-parse", "--show-toplevel"]);
+        let tracked = root
+            .as_deref()
+            .and_then(|r| git(&["-C", r, "ls-files"]))
+            .map(|out| out.lines().map(String::from).collect())
+            .unwrap_or_default();
+        FileLifecycle {
+            root,
+            tracked,
+            deletions: RefCell::new(HashMap::new()),
+        }
+    }
+
+    /// The commit that deleted `path`, or None while it is tracked (or was
+    /// never deleted).
+    pub fn deleted_by(&self, path: &str) -> Option<String> {
+        let path = self.repo_path(path);
+        if self.tracked.contains(&path) {
+            return None;
+        }
+        if let Some(known) = self.deletions.borrow().get(&path) {
+            return known.clone();
+        }
+        let sha = self.root.as_deref().and_then(|root| {
+            git(&[
+                "-C",
+                root,
+                "log",
+                "-1",
+                "--format=%H",
+                "--diff-filter=D",
+                "--",
+                &path,
+            ])
+        });
+        self.deletions.borrow_mut().insert(path, sha.clone());
+        sha
+    }
+
+    /// A commit that deleted the receipt's files, when all of them are gone
+    /// (with several, the one that deleted its last listed file).
+    pub fn receipt_deleted_by(&self, r: &Receipt) -> Option<String> {
+        all_deleted(r, |p| self.deleted_by(p))
+    }
+
+    fn repo_path(&self, path: &str) -> String {
+        self.root
+            .as_deref()
+            .and_then(|root| path.strip_prefix(root))
+            .map(|rel| rel.trim_start_matches('/').to_string())
+            .unwrap_or_else(|| path.to_string())
+    }
+}
+
+/// Core of [`FileLifecycle::receipt_deleted_by`]: the deleting commit of the
+/// receipt's last file. Deletions the receipt itself made don't count.
+pub fn all_deleted(r: &Receipt, deleted_by: impl Fn(&str) -> Option<String>) -> Option<String> {
+    let paths: Vec<String> = r
+        .all_file_changes()
+        .into_iter()
+        .filter(|fc| fc.change_type != Some(ChangeType::Deleted))
+        .map(|fc| fc.path.clone())
+        .collect();
+    let mut last = None;
+    for path in &paths {
+        last = Some(deleted_by(path)?);
+    }
+    last
+}
+
+/// Drop deleted receipts (and entries left without receipts) and fix the
+/// entry totals. Returns how many receipts were dropped.
+pub fn drop_deleted(entries: &mut Vec<AuditEntry>, lifecycle: &FileLifecycle) -> usize {
+    drop_deleted_with(entries, |r| lifecycle.receipt_deleted_by(r).is_some())
+}
+
+fn drop_deleted_with(
+    entries: &mut Vec<AuditEntry>,
+    is_deleted: impl Fn(&Receipt) -> bool,
+) -> usize {
+    let mut dropped = 0;
+    for entry in entries.iter_mut() {
+        let before = entry.receipts.len();
+        entry.receipts.retain(|r| !is_deleted(r));
+        if entry.receipts.len() < before {
+            dropped += before - entry.receipts.len();
+            entry.total_ai_lines = entry.receipts.iter().map(|r| r.total_lines_changed()).sum();
+            entry.total_cost_usd = entry.receipts.iter().map(|r| r.cost_usd).sum();
+        }
+    }
+    entries.retain(|e| !e.receipts.is_empty());
+    dropped
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::FileChange;
+
+    fn receipt(id: &str, files: &[(&str, Option<ChangeType>)]) -> Receipt {
+        let r = Receipt::test(id)
+            .with_model("m")
+            .with_cost(1.0)
+            .with_timestamp("2026-01-01T00:00:00Z")
+            .with_user("dev");
+        files.iter().fold(r, |r, (path, change)| {
+            r.with_change(FileChange {
+                change_type: *change,
+                ..FileChange::test(path, (1, 10), 0, 0)
+            })
+        })
+    }
+
+    #[test]
+    fn test_deleted_receipts() {
+        let gone = |p: &str| (p.starts_with("old/")).then(|| format!("del-{}", p));
+        let all_gone = receipt("r1", &[("old/a.rs", None), ("old/b.rs", None)]);
+        let partly = receipt("r2", &[("old/a.rs", None), ("src/live.rs", None)]);
+        let deleter = receipt("r3", &[("src/x.rs", Some(ChangeType::Deleted))]);
+        assert_eq!(
+            all_deleted(&all_gone, gone),
+            Some("del-old/b.rs".to_string())
+        );
+        assert_eq!(all_deleted(&partly, gone), None);
+        assert_eq!(all_deleted(&deleter, |_| Some("x".to_string())), None);
+
+        let mut entries = vec![
+            AuditEntry {
+                commit_sha: "c1".to_string(),
+                commit_date: String::new(),
+                commit_author: String::new(),
+                commit_message: String::new(),
+                receipts: vec![all_gone.clone(), partly],
+                total_ai_lines: 20,
+                total_cost_usd: 2.0,
+                code_origin: None,
+                pull_request: None,
+            },
+            AuditEntry {
+                commit_sha: "c2".to_string(),
+                commit_date: String::new(),
+                commit_author: String::new(),
+                commit_message: String::new(),
+                receipts: vec![all_gone],
+                total_ai_lines: 10,
+                total_cost_usd: 1.0,
+                code_origin: None,
+                pull_request: None,
+            },
+        ];
+        let dropped = drop_deleted_with(&mut entries, |r| all_deleted(r, gone).is_some());
+        assert_eq!(dropped, 2);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].receipts[0].id, "r2");
+        assert!((entries[0].total_cost_usd - 1.0).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,335 @@
+/// Three-way line classification at attach time (interleaved authorship).
+///
+/// For each file a receipt touched, every line the commit added is
+/// compared with the blobs captured right after each AI edit
+/// (`FileChange.blob_hash`):
+///
+/// - `ai_generated`: the line is exactly as an AI edit wrote it;
+/// - `human_edited`: it replaces an AI-written line that did not survive to
+///   the commit and still shares most of its tokens (a human tweak of AI code);
+/// - `pure_human`: anything else.
+///
+/// AI-written lines are the lines of an AI blob that the parent commit did not
+/// have, so a line a human typed before an AI edit that kept it counts as AI.
+/// The result is stored as `FileMapping` hunks, which `blame` prefers over the
+/// receipt-level line ranges.
+use crate::commands::github;
+use crate::core::notebook;
+use crate::core::receipt::{CodeOrigin, CodeOriginStats, FileMapping, Hunk, LineCounts, Receipt};
+use crate::git::blob_reader::{self, blob_lines};
+use std::collections::{HashMap, HashSet};
+use std::process::Command;
+
+/// Share of tokens a human-edited line must keep from the AI line it replaced.
+const EDIT_SIMILARITY: f64 = 0.5;
+
+/// git's empty tree, diffed against for root commits.
+const EMPTY_TREE: &str = "4b825dc642cb6eb9a060e54bf8d69288fbee4904";
+
+/// Classify the lines `commit` added in every file `receipts` have blobs for.
+pub fn classify_commit(
+    commit: &str,
+    receipts: &[Receipt],
+) -> (Vec<FileMapping>, Option<CodeOriginStats>) {
+    let first_parent = format!("{}~1", commit);
+    let parent = if rev_parse(&first_parent).is_some() {
+        first_parent.as_str()
+    } else {
+        EMPTY_TREE
+    };
+    let diff = Command::new("git")
+        .args(["diff", "--unified=0", "--no-color", parent, commit])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default();
+    let added = github::parse_added_ranges(&diff);
+
+    // AI blobs per file, oldest edit first so later edits win.
+    let mut ordered: Vec<&Receipt> = receipts.iter().collect();
+    ordered.sort_by_key(|r| r.timestamp);
+    let mut ai_blobs: Vec<(String, Vec<(String, &Receipt)>)> = Vec::new();
+    for r in ordered {
+        for fc in &r.files_changed {
+            let Some(blob) = fc.blob_hash.as_ref() else {
+                continue;
+            };
+            if fc.is_binary || notebook::is_notebook(&fc.path) {
+                continue;
+            }
+            match ai_blobs.iter_mut().find(|(p, _)| *p == fc.path) {
+                Some((_, blobs)) => blobs.push((blob.clone(), r)),
+                None => ai_blobs.push((fc.path.clone(), vec![(blob.clone(), r)])),
+            }
+        }
+    }
+
+    let mut mappings = Vec::new();
+    let mut counts = [0u32; 3];
+    let mut normalized = [0u32; 3];
+    for (path, blobs) in ai_blobs {
+        let Some(ranges) = added.get(&path) else {
+            continue;
+        };
+        let Some(head) = blob_reader::read(&format!("{}:{}", commit, path)) else {
+            continue;
+        };
+        let previous = if parent == EMPTY_TREE {
+            None
+        } else {
+            blob_reader::read(&format!("{}:{}", parent, path))
+        };
+        let lines_of = |o: &blob_reader::Object| -> Vec<String> {
+            String::from_utf8_lossy(&o.data)
+                .lines()
+                .map(String::from)
+                .collect()
+        };
+        let (head_blob, head) = (head.oid.clone(), lines_of(&head));
+        let previous_blob = previous.as_ref().map(|o| o.oid.clone());
+        let before = previous.as_ref().map(lines_of).unwrap_or_default();
+        let ai: Vec<(Vec<String>, &Receipt)> = blobs
+            .iter()
+            .map(|(blob, r)| (blob_lines(blob), *r))
+            .collect();
+
+        let lines = classify(&head, ranges, &before, &ai);
+        for (n, origin, _) in &lines {
+            counts[origin_index(origin)] += 1;
+            if !is_trivial_line(&head[*n as usize - 1]) {
+                normalized[origin_index(origin)] += 1;
+            }
+        }
+        mappings.push(FileMapping {
+            path,
+            blob_hash: head_blob,
+            previous_blob_hash: previous_blob,
+            hunks: to_hunks(&lines),
+        });
+    }
+
+    // Lines added to files no AI edit touched are human-written too.
+    let has_ai_lines = mappings.iter().any(|m| !m.hunks.is_empty());
+    for (path, ranges) in added
+        .iter()
+        .filter(|(path, _)| !mappings.iter().any(|m| m.path == **path))
+    {
+        counts[2] += ranges
+            .iter()
+            .map(|&(start, end)| end + 1 - start)
+            .sum::<u32>();
+        if has_ai_lines {
+            let head = blob_lines(&format!("{}:{}", commit, path));
+            normalized[2] += ranges
+                .iter()
+                .flat_map(|&(start, end)| start..=end)
+                .filter(|&n| {
+                    head.get(n as usize - 1)
+                        .is_some_and(|l| !is_trivial_line(l))
+                })
+                .count() as u32;
+        }
+    }
+    let stats = has_ai_lines
+        .then(|| CodeOriginStats::from_lines(counts[0], counts[1], counts[2]))
+        .flatten()
+        .map(|mut stats| {
+            stats.normalized = Some(LineCounts {
+                ai_generated: normalized[0],
+                human_edited: normalized[1],
+                pure_human: normalized[2],
+            });
+            stats
+        });
+    (mappings, stats)
+}
+
+/// Origin of each added line of `head` (1-based, within `ranges`), given the
+/// parent version `before` and the AI blobs in edit order.
+fn classify<'a>(
+    head: &[String],
+    ranges: &[(u32, u32)],
+    before: &[String],
+    ai: &[(Vec<String>, &'a Receipt)],
+) -> Vec<(u32, CodeOrigin, Option<&'a Receipt>)> {
+    let before: HashSet<&str> = before.iter().map(String::as_str).collect();
+    let mut written: HashMap<&str, &Receipt> = HashMap::new();
+    for (lines, r) in ai {
+        for line in lines {
+            if !before.contains(line.as_str()) {
+                written.insert(line.as_str(), *r);
+            }
+        }
+    }
+    let in_head: HashSet<&str> = head.iter().map(String::as_str).collect();
+    let overridden: Vec<(&str, &Receipt)> = written
+        .iter()
+        .filter(|(line, _)| !in_head.contains(*line) && !line.trim().is_empty())
+        .map(|(line, r)| (*line, *r))
+        .collect();
+
+    let mut out = Vec::new();
+    for &(start, end) in ranges {
+        for n in start..=end {
+            let Some(text) = head.get(n as usize - 1) else {
+                continue;
+            };
+            let (origin, receipt) = if let Some(r) = written.get(text.as_str()) {
+                (CodeOrigin::AiGenerated, Some(*r))
+            } else if let Some((_, r)) = overridden
+                .iter()
+                .find(|(ai_line, _)| similarity(ai_line, text) >= EDIT_SIMILARITY)
+            {
+                (CodeOrigin::HumanEdited, Some(*r))
+            } else {
+                (CodeOrigin::PureHuman, None)
+            };
+            out.push((n, origin, receipt));
+        }
+    }
+    out
+}
+
+/// Lines that carry no code on their own: blank, or only brackets and
+/// separators (`}`, `});`, `],`). Normalized line counts leave them out.
+pub fn is_trivial_line(line: &str) -> bool {
+    line.trim()
+        .chars()
+        .all(|c| c.is_whitespace() || "{}()[];,".contains(c))
+}
+
+/// Jaccard similarity of the identifier/number tokens of two lines.
+fn similarity(a: &str, b: &str) -> f64 {
+    let tokens = |s: &str| -> HashSet<String> {
+        s.split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|t| !t.is_empty())
+            .map(str::to_lowercase)
+            .collect()
+    };
+    let (a, b) = (tokens(a), tokens(b));
+    let union = a.union(&b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(&b).count() as f64 / union as f64
+}
+
+/// Merge consecutive lines with the same origin and receipt into hunks.
+fn to_hunks(lines: &[(u32, CodeOrigin, Option<&Receipt>)]) -> Vec<Hunk> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    for (n, origin, receipt) in lines {
+        let receipt_id = receipt.map(|r| r.id.clone());
+        if let Some(last) = hunks.last_mut() {
+            if last.end_line + 1 == *n && last.origin == *origin && last.receipt_id == receipt_id {
+                last.end_line = *n;
+                continue;
+            }
+        }
+        hunks.push(Hunk {
+            start_line: *n,
+            end_line: *n,
+            origin: origin.clone(),
+            prompt_turn: receipt.and_then(|r| r.prompt_number),
+            model: receipt.map(|r| r.model.clone()),
+            receipt_id,
+        });
+    }
+    hunks
+}
+
+fn origin_index(origin: &CodeOrigin) -> usize {
+    match origin {
+        CodeOrigin::AiGenerated => 0,
+        CodeOrigin::HumanEdited => 1,
+        CodeOrigin::PureHuman => 2,
+    }
+}
+
+fn rev_parse(spec: &str) -> Option<String> {
+    Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", spec])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(id: &str) -> Receipt {
+        Receipt::test(id)
+            .with_model("claude-sonnet-4-6")
+            .with_session("s1")
+            .with_timestamp("2026-01-01T00:00:00Z")
+    }
+
+    fn lines(s: &[&str]) -> Vec<String> {
+        s.iter().map(|l| l.to_string()).collect()
+    }
+
+    #[test]
+    fn test_classify_three_ways() {
+        let r = receipt("r1");
+        let before = lines(&["fn main() {", "}"]);
+        let ai = vec![(
+            lines(&[
+                "fn main() {",
+                "    let retries = 3;",
+                "    fetch(url, retries);",
+                "}",
+            ]),
+            &r,
+        )];
+        // The human tweaked the fetch call and added a log line.
+        let head = lines(&[
+            "fn main() {",
+            "    let retries = 3;",
+            "    fetch(url, retries, timeout);",
+            "    println!(\"done\");",
+            "}",
+        ]);
+        let out = classify(&head, &[(2, 4)], &before, &ai);
+        let origins: Vec<&CodeOrigin> = out.iter().map(|(_, o, _)| o).collect();
+        assert_eq!(
+            origins,
+            vec![
+                &CodeOrigin::AiGenerated,
+                &CodeOrigin::HumanEdited,
+                &CodeOrigin::PureHuman
+            ]
+        );
+        assert_eq!(out[1].2.map(|r| r.id.as_str()), Some("r1"));
+    }
+
+    #[test]
+    fn test_to_hunks_merges_runs() {
+        let r = receipt("r1");
+        let lines = vec![
+            (1, CodeOrigin::AiGenerated, Some(&r)),
+            (2, CodeOrigin::AiGenerated, Some(&r)),
+            (3, CodeOrigin::PureHuman, None),
+            (5, CodeOrigin::PureHuman, None),
+        ];
+        let hunks = to_hunks(&lines);
+        assert_eq!(hunks.len(), 3);
+        assert_eq!((hunks[0].start_line, hunks[0].end_line), (1, 2));
+        assert_eq!(hunks[0].receipt_id.as_deref(), Some("r1"));
+        assert_eq!(hunks[1].model, None);
+        assert!(similarity("a(b, c)", "a(b, c, d)") >= EDIT_SIMILARITY);
+        assert!(similarity("let x = 1;", "return y;") < EDIT_SIMILARITY);
+    }
+
+    #[test]
+    fn test_is_trivial_line() {
+        for line in ["", "   ", "}", "  });", "],", "{", ")"] {
+            assert!(is_trivial_line(line), "{:?}", line);
+        }
+        for line in ["} else {", "end", "x", "// }"] {
+            assert!(!is_trivial_line(line), "{:?}", line);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+/// `blameprompt link-pr`: record which pull request a commit landed through.
+///
+/// With a PR number or URL, links the branch's commits (or the given
+/// `--commit`s) to it. Without one, scans first-parent history for merge
+/// commits ("Merge pull request #12 from ...") and squash merges
+/// ("Title (#12)") and links the commits each one brought in; the post-merge
+/// hook runs this after every pull. Only commits that carry receipts are
+/// touched, and an existing link to a different PR is kept unless `--force`.
+use crate::commands::github;
+use crate::core::receipt::PullRequestLink;
+use crate::core::util;
+use crate::git::notes::{read_receipts_for_commit, write_receipts_for_commit};
+use std::process::Command;
+
+pub fn run(pr: Option<&str>, commits: &[String], limit: usize, force: bool) -> Result<(), String> {
+    let Some(pr) = pr else {
+        return detect(limit, force);
+    };
+
+    let (number, url) = parse_pr_arg(pr)?;
+    let url = url.or_else(|| {
+        github::detect_repo_slug()
+            .map(|slug| format!("https://github.com/{}/pull/{}", slug, number))
+    });
+    let commits = if commits.is_empty() {
+        let base = github::detect_base_ref()
+            .ok_or("Cannot detect the PR base branch. Pass --commit <rev> for each commit.")?;
+        github::commits_between(&base, "HEAD")
+    } else {
+        commits
+            .iter()
+            .map(|c| rev_parse(c).ok_or(format!("Unknown revision '{}'", c)))
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let link = PullRequestLink {
+        number,
+        url,
+        merge_commit: None,
+        comment_id: None,
+    };
+    let linked = link_commits(&commits, &link, force)?;
+    println!(
+        "[BlamePrompt] Linked {} commit(s) with receipts to PR #{}",
+        linked, number
+    );
+    Ok(())
+}
+
+/// Link merged PRs found in the last `limit` first-parent commits of HEAD.
+fn detect(limit: usize, force: bool) -> Result<(), String> {
+    let output = Command::new("git")
+        .args([
+            "log",
+            "--first-parent",
+            &format!("--max-count={}", limit),
+            "--format=%H%x1f%P%x1f%s",
+            "HEAD",
+        ])
+        .output()
+        .map_err(|e| format!("git log failed: {}", e))?;
+    if !output.status.success() {
+        return Ok(());
+    }
+
+    let slug = github::detect_repo_slug();
+    let mut prs = 0;
+    let mut linked = 0;
+    for line in String::from_utf8_lossy(&output.stdout).lines() {
+        let parts: Vec<&str> = line.split('\x1f').collect();
+        let [sha, parents, subject] = parts[..] else {
+            continue;
+        };
+        let Some(number) = pr_from_subject(subject) else {
+            continue;
+        };
+        // A merge brings in its second parent's side; a squash is the commit itself.
+        let parents: Vec<&str> = parents.split_whitespace().collect();
+        let mut commits = match parents[..] {
+            [first, second, ..] => github::commits_between(first, second),
+            _ => Vec::new(),
+        };
+        commits.push(sha.to_string());
+
+        let link = PullRequestLink {
+            number,
+            url: slug
+                .as_ref()
+                .map(|s| format!("https://github.com/{}/pull/{}", s, number)),
+            merge_commit: Some(sha.to_string()),
+            comment_id: None,
+        };
+        let n = link_commits(&commits, &link, force)?;
+        if n > 0 {
+            prs += 1;
+            linked += n;
+        }
+    }
+    println!(
+        "[BlamePrompt] Linked {} commit(s) with receipts to {} merged PR(s)",
+        linked, prs
+    );
+    Ok(())
+}
+
+/// Record `link` on each commit that has receipts; returns how many notes changed.
+pub fn link_commits(
+    commits: &[String],
+    link: &PullRequestLink,
+    force: bool,
+) -> Result<usize, String> {
+    let mut changed = 0;
+    for sha in commits {
+        let Some(mut payload) = read_receipts_for_commit(sha) else {
+            continue;
+        };
+        let Some(updated) = merge_link(payload.pull_request.as_ref(), link, force) else {
+            continue;
+        };
+        payload.pull_request = Some(updated);
+        write_receipts_for_commit(sha, &payload)
+            .map_err(|e| format!("{}: {}", util::short_sha(sha), e))?;
+        changed += 1;
+    }
+    Ok(changed)
+}
+
+/// The link to store given the one already on the note, or `None` when
+/// nothing changes. Details for the same PR are merged; a link to another PR
+/// is only replaced with `force`.
+fn merge_link(
+    existing: Option<&PullRequestLink>,
+    new: &PullRequestLink,
+    force: bool,
+) -> Option<PullRequestLink> {
+    let updated = match existing {
+        Some(old) if old.number == new.number => PullRequestLink {
+            number: new.number,
+            url: new.url.clone().or(old.url.clone()),
+            merge_commit: new.merge_commit.clone().or(old.merge_commit.clone()),
+            comment_id: new.comment_id.or(old.comment_id),
+        },
+        Some(_) if !force => return None,
+        _ => new.clone(),
+    };
+    (existing != Some(&updated)).then_some(updated)
+}
+
+/// Accept `12`, `#12`, or a PR/MR URL (GitHub, GitLab, Bitbucket).
+fn parse_pr_arg(arg: &str) -> Result<(u32, Option<String>), String> {
+    let arg = arg.trim();
+    if let Ok(n) = arg.trim_start_matches('#').parse() {
+        return Ok((n, None));
+    }
+    let segments: Vec<&str> = arg.trim_end_matches('/').split('/').collect();
+    segments
+        .windows(2)
+        .find(|w| matches!(w[0], "pull" | "merge_requests" | "pull-requests"))
+        .and_then(|w| w[1].parse().ok())
+        .map(|n| (n, Some(arg.to_string())))
+        .ok_or(format!("'{}' is not a PR number or URL", arg))
+}
+
+/// PR number from a GitHub merge ("Merge pull request #12 from ...") or
+/// squash ("Title (#12)") commit subject.
+fn pr_from_subject(subject: &str) -> Option<u32> {
+    if let Some(rest) = subject.strip_prefix("Merge pull request #") {
+        return rest.split_whitespace().next()?.parse().ok();
+    }
+    let inner = subject.trim_end().strip_suffix(')')?;
+    let (_, number) = inner.rsplit_once("(#")?;
+    number.parse().ok()
+}
+
+fn rev_parse(rev: &str) -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", &format!("{}^{{commit}}", rev)])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(number: u32, comment_id: Option<u64>) -> PullRequestLink {
+        PullRequestLink {
+            number,
+            url: None,
+            merge_commit: None,
+            comment_id,
+        }
+    }
+
+    #[test]
+    fn test_pr_detection_from_args_and_subjects() {
+        assert_eq!(parse_pr_arg("#42").unwrap(), (42, None));
+        assert_eq!(
+            parse_pr_arg("https://github.com/o/r/pull/7/files")
+                .unwrap()
+                .0,
+            7
+        );
+        assert_eq!(
+            parse_pr_arg("https://gitlab.com/g/p/-/merge_requests/9")
+                .unwrap()
+                .0,
+            9
+        );
+        assert!(parse_pr_arg("main").is_err());
+
+        assert_eq!(
+            pr_from_subject("Merge pull request #12 from alice/feature"),
+            Some(12)
+        );
+        assert_eq!(pr_from_subject("Add retries to fetcher (#315)"), Some(315));
+        assert_eq!(pr_from_subject("Fix (flaky) test"), None);
+    }
+
+    #[test]
+    fn test_merge_link() {
+        // New link, and a no-op when nothing changes.
+        assert_eq!(merge_link(None, &link(1, None), false), Some(link(1, None)));
+        assert_eq!(
+            merge_link(Some(&link(1, None)), &link(1, None), false),
+            None
+        );
+        // Same PR: the recorded comment id survives a relink without one.
+        assert_eq!(
+            merge_link(Some(&link(1, Some(5))), &link(1, None), false),
+            None
+        );
+        assert_eq!(
+            merge_link(Some(&link(1, None)), &link(1, Some(5)), false),
+            Some(link(1, Some(5)))
+        );
+        // Another PR is only replaced with force.
+        assert_eq!(
+            merge_link(Some(&link(1, None)), &link(2, None), false),
+            None
+        );
+        assert_eq!(
+            merge_link(Some(&link(1, None)), &link(2, None), true),
+            Some(link(2, None))
+        );
+    }
+}
@@ -0,0 +1,436 @@
+/// `blameprompt lint-notes`: integrity check for every stored note.
+///
+/// Checks that each note parses (recovering notes that a `cat_sort_uniq`
+/// notes merge concatenated), is on a commit that exists, has unique receipt
+/// ids, well-formed and resolvable blob hashes, plausible timestamps, and
+/// continuation parents that exist in some note. With `--fix`, notes with
+/// repairable problems are rewritten; everything else is reported.
+use crate::core::migrate;
+use crate::core::receipt::{NotePayload, Receipt};
+use crate::core::util;
+use crate::git::{notes, storage};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug)]
+struct Problem {
+    severity: Severity,
+    message: String,
+    /// `--fix` can repair this one.
+    fixable: bool,
+}
+
+impl Problem {
+    fn error(message: String, fixable: bool) -> Self {
+        Problem {
+            severity: Severity::Error,
+            message,
+            fixable,
+        }
+    }
+
+    fn warning(message: String) -> Self {
+        Problem {
+            severity: Severity::Warning,
+            message,
+            fixable: false,
+        }
+    }
+}
+
+/// Repository facts the per-note checks need, gathered up front.
+struct Context {
+    /// Objects (commits and blobs) that exist in the object store.
+    existing: HashSet<String>,
+    /// Receipt ids across all notes.
+    receipt_ids: HashSet<String>,
+    now: DateTime<Utc>,
+}
+
+struct Note {
+    sha: String,
+    parsed: Result<(NotePayload, bool), String>,
+}
+
+pub fn run(fix: bool) -> Result<(), String> {
+    let store = storage::active();
+    let notes_found: Vec<Note> = notes::list_commits_with_notes()
+        .into_iter()
+        .filter_map(|sha| {
+            let raw = store.read(&sha)?;
+            Some(Note {
+                parsed: parse_lenient(&raw),
+                sha,
+            })
+        })
+        .collect();
+    if notes_found.is_empty() {
+        println!("No BlamePrompt notes found.");
+        return Ok(());
+    }
+
+    let mut objects: Vec<String> = Vec::new();
+    let mut receipt_ids = HashSet::new();
+    for note in &notes_found {
+        objects.push(note.sha.clone());
+        if let Ok((payload, _)) = &note.parsed {
+            objects.extend(blob_hashes(payload).into_iter().filter(|h| is_hex_id(h)));
+            receipt_ids.extend(payload.receipts.iter().map(|r| r.id.clone()));
+        }
+    }
+    let ctx = Context {
+        existing: existing_objects(&objects),
+        receipt_ids,
+        now: Utc::now(),
+    };
+
+    let (mut errors, mut warnings, mut fixed, mut fixable, mut affected) = (0, 0, 0, 0, 0);
+    for note in notes_found {
+        let short = util::short_sha(&note.sha);
+        let (problems, repaired) = match note.parsed {
+            Ok((payload, salvaged)) => lint_note(&note.sha, payload, salvaged, &ctx),
+            Err(e) => (
+                vec![Problem::error(format!("unparseable: {}", e), false)],
+                None,
+            ),
+        };
+        if problems.is_empty() {
+            continue;
+        }
+        affected += 1;
+        let repaired = repaired.filter(|_| fix && problems.iter().any(|p| p.fixable));
+        let written = match &repaired {
+            Some(payload) => match notes::write_receipts_for_commit(&note.sha, payload) {
+                Ok(()) => true,
+                Err(e) => {
+                    eprintln!("[BlamePrompt] {}: could not rewrite note: {}", short, e);
+                    false
+                }
+            },
+            None => false,
+        };
+        for p in &problems {
+            let label = match p.severity {
+                Severity::Error => "error",
+                Severity::Warning => "warning",
+            };
+            let status = match (p.fixable, written) {
+                (true, true) => " [fixed]",
+                (true, false) => " [fixable]",
+                _ => "",
+            };
+            println!("{}  {}: {}{}", short, label, p.message, status);
+            fixable += (p.fixable && !written) as usize;
+            match p.severity {
+                Severity::Error if written && p.fixable => fixed += 1,
+                Severity::Error => errors += 1,
+                Severity::Warning => warnings += 1,
+            }
+        }
+    }
+
+    println!();
+    if affected == 0 {
+        println!("All notes look healthy.");
+        return Ok(());
+    }
+    println!(
+        "{} note(s) with problems: {} error(s), {} warning(s), {} fixed",
+        affected, errors, warnings, fixed
+    );
+    if fixable > 0 {
+        println!("Run `blameprompt lint-notes --fix` to repair the [fixable] ones.");
+    }
+    if errors > 0 {
+        return Err(format!("{} note problem(s) need attention", errors));
+    }
+    Ok(())
+}
+
+/// Parse a note, upgrading old schemas. Falls back to reading several JSON
+/// documents back to back (what `git notes merge -s cat_sort_uniq` produces
+/// from two JSON notes) and merging their receipts; the flag is true then.
+fn parse_lenient(raw: &str) -> Result<(NotePayload, bool), String> {
+    let strict_err = match migrate::parse(raw) {
+        Ok(p) => return Ok((p, false)),
+        Err(e) => e,
+    };
+    let docs: Vec<Value> = serde_json::Deserializer::from_str(raw)
+        .into_iter::<Value>()
+        .collect::<Result<_, _>>()
+        .map_err(|_| strict_err.clone())?;
+    if docs.len() < 2 {
+        return Err(strict_err);
+    }
+    let mut docs = docs.into_iter().map(migrate::from_value);
+    let mut merged = docs.next().ok_or_else(|| strict_err.clone())??;
+    for doc in docs {
+        for r in doc?.receipts {
+            if !merged.receipts.iter().any(|m| m.id == r.id) {
+                merged.receipts.push(r);
+            }
+        }
+    }
+    Ok((merged, true))
+}
+
+/// Problems in one note, and the repaired payload when any are fixable.
+fn lint_note(
+    sha: &str,
+    mut payload: NotePayload,
+    salvaged: bool,
+    ctx: &Context,
+) -> (Vec<Problem>, Option<NotePayload>) {
+    let mut problems = Vec::new();
+    let mut changed = salvaged;
+    if salvaged {
+        problems.push(Problem::error(
+            "several JSON documents concatenated (bad notes merge)".to_string(),
+            true,
+        ));
+    }
+    if !ctx.existing.contains(sha) {
+        problems.push(Problem::error(
+            "annotated commit does not exist (run `git notes --ref refs/notes/blameprompt prune`)"
+                .to_string(),
+            false,
+        ));
+    }
+
+    let mut seen = HashSet::new();
+    let before = payload.receipts.len();
+    payload.receipts.retain(|r| seen.insert(r.id.clone()));
+    if payload.receipts.len() < before {
+        problems.push(Problem::error(
+            format!(
+                "{} duplicate receipt id(s)",
+                before - payload.receipts.len()
+            ),
+            true,
+        ));
+        changed = true;
+    }
+
+    for r in &mut payload.receipts {
+        for fc in &mut r.files_changed {
+            let Some(hash) = fc.blob_hash.clone() else {
+                continue;
+            };
+            if !is_hex_id(&hash) {
+                problems.push(Problem::error(
+                    format!("{}: malformed blob hash '{}' for {}", r.id, hash, fc.path),
+                    true,
+                ));
+                fc.blob_hash = None;
+                changed = true;
+            } else if !ctx.existing.contains(&hash) {
+                // Snapshots of the working tree: absent when the file was
+                // edited again before committing, so only worth a warning.
+                problems.push(Problem::warning(format!(
+                    "{}: blob {} for {} is not in the object store",
+                    r.id,
+                    util::short_sha(&hash),
+                    fc.path
+                )));
+            }
+        }
+        problems.extend(check_receipt(r, ctx));
+    }
+    for m in payload.file_mappings.iter().flatten() {
+        for hash in std::iter::once(&m.blob_hash).chain(m.previous_blob_hash.iter()) {
+            if !ctx.existing.contains(hash) {
+                problems.push(Problem::error(
+                    format!(
+                        "file mapping for {} references missing blob {}",
+                        m.path,
+                        util::short_sha(hash)
+                    ),
+                    false,
+                ));
+            }
+        }
+    }
+
+    (problems, changed.then_some(payload))
+}
+
+/// Timestamp and continuation checks for one receipt (report only).
+fn check_receipt(r: &Receipt, ctx: &Context) -> Vec<Problem> {
+    let mut problems = Vec::new();
+    let earliest = Utc.with_ymd_and_hms(2020, 1, 1, 0, 0, 0).unwrap();
+    if r.timestamp > ctx.now + Duration::days(1) {
+        problems.push(Problem::error(
+            format!("{}: timestamp {} is in the future", r.id, r.timestamp),
+            false,
+        ));
+    } else if r.timestamp < earliest {
+        problems.push(Problem::error(
+            format!("{}: implausible timestamp {}", r.id, r.timestamp),
+            false,
+        ));
+    }
+    if let (Some(start), Some(end)) = (r.session_start, r.session_end) {
+        if start > end {
+            problems.push(Problem::error(
+                format!("{}: session_start is after session_end", r.id),
+                false,
+            ));
+        }
+    }
+    if let Some(parent) = &r.parent_receipt_id {
+        if !ctx.receipt_ids.contains(parent) {
+            problems.push(Problem::warning(format!(
+                "{}: continuation parent {} not found in any note (run `blameprompt pull`?)",
+                r.id, parent
+            )));
+        }
+    }
+    problems
+}
+
+fn blob_hashes(payload: &NotePayload) -> Vec<String> {
+    let from_receipts = payload
+        .receipts
+        .iter()
+        .flat_map(|r| r.files_changed.iter())
+        .filter_map(|fc| fc.blob_hash.clone());
+    let from_mappings = payload
+        .file_mappings
+        .iter()
+        .flatten()
+        .flat_map(|m| std::iter::once(m.blob_hash.clone()).chain(m.previous_blob_hash.clone()));
+    from_receipts.chain(from_mappings).collect()
+}
+
+fn is_hex_id(s: &str) -> bool {
+    matches!(s.len(), 40 | 64) && s.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// The subset of `ids` present in the object store, in one
+/// `git cat-file --batch-check` call.
+fn existing_objects(ids: &[String]) -> HashSet<String> {
+    let Ok(mut child) = Command::new("git")
+        .args(["cat-file", "--batch-check=%(objectname)"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+    else {
+        return HashSet::new();
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        let input: String = ids.iter().map(|id| format!("{}\n", id)).collect();
+        // Written from a thread so a large batch can't deadlock on a full pipe.
+        std::thread::spawn(move || {
+            let _ = stdin.write_all(input.as_bytes());
+        });
+    }
+    let Ok(output) = child.wait_with_output() else {
+        return HashSet::new();
+    };
+    // Missing objects print "<id> missing"; found ones print the bare id.
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter(|l| !l.ends_with(" missing"))
+        .map(|l| l.trim().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+    const BLOB: &str = "89abcdef0123456789abcdef0123456789abcdef";
+
+    fn receipt(id: &str, extra: Value) -> Value {
+        let mut r = serde_json::to_value(
+            Receipt::test(id)
+                .with_model("claude-sonnet-4")
+                .with_session("s1")
+                .with_prompt("add a test")
+                .with_cost(0.01)
+                .with_timestamp("2026-01-05T10:00:00Z")
+                .with_user("dev <dev@example.com>"),
+        )
+        .unwrap();
+        for (k, v) in extra.as_object().unwrap() {
+            r[k] = v.clone();
+        }
+        r
+    }
+
+    fn note(receipts: Vec<Value>) -> String {
+        serde_json::json!({ "blameprompt_version": "1.0.0", "receipts": receipts }).to_string()
+    }
+
+    fn ctx() -> Context {
+        Context {
+            existing: [COMMIT, BLOB].iter().map(|s| s.to_string()).collect(),
+            receipt_ids: ["r1", "r2"].iter().map(|s| s.to_string()).collect(),
+            now: DateTime::parse_from_rfc3339("2026-02-01T00:00:00Z")
+                .unwrap()
+                .with_timezone(&Utc),
+        }
+    }
+
+    #[test]
+    fn test_parse_lenient_merges_concatenated_notes() {
+        let a = note(vec![receipt("r1", serde_json::json!({}))]);
+        let b = note(vec![
+            receipt("r1", serde_json::json!({})),
+            receipt("r2", serde_json::json!({})),
+        ]);
+        let (payload, salvaged) = parse_lenient(&format!("{}\n\n{}", a, b)).unwrap();
+        assert!(salvaged);
+        assert_eq!(payload.receipts.len(), 2);
+
+        assert!(!parse_lenient(&a).unwrap().1);
+        assert!(parse_lenient("{ not json").is_err());
+    }
+
+    #[test]
+    fn test_lint_note_finds_and_repairs() {
+        let healthy = note(vec![receipt(
+            "r1",
+            serde_json::json!({ "files_changed": [{ "path": "a.rs", "line_range": [1, 2], "blob_hash": BLOB }] }),
+        )]);
+        let (payload, _) = parse_lenient(&healthy).unwrap();
+        let (problems, repaired) = lint_note(COMMIT, payload, false, &ctx());
+        assert!(problems.is_empty(), "{:?}", problems);
+        assert!(repaired.is_none());
+
+        let broken = note(vec![
+            receipt(
+                "r2",
+                serde_json::json!({
+                    "files_changed": [{ "path": "a.rs", "line_range": [1, 2], "blob_hash": "zzz" }],
+                    "parent_receipt_id": "gone",
+                    "timestamp": "2027-01-01T00:00:00Z",
+                }),
+            ),
+            receipt("r2", serde_json::json!({})),
+        ]);
+        let (payload, _) = parse_lenient(&broken).unwrap();
+        let (problems, repaired) = lint_note("deadbeef", payload, false, &ctx());
+        let messages: Vec<&str> = problems.iter().map(|p| p.message.as_str()).collect();
+        assert!(messages.iter().any(|m| m.contains("does not exist")));
+        assert!(messages.iter().any(|m| m.contains("duplicate")));
+        assert!(messages.iter().any(|m| m.contains("malformed blob hash")));
+        assert!(messages.iter().any(|m| m.contains("in the future")));
+        assert!(messages.iter().any(|m| m.contains("continuation parent")));
+
+        let repaired = repaired.unwrap();
+        assert_eq!(repaired.receipts.len(), 1);
+        assert!(repaired.receipts[0].files_changed[0].blob_hash.is_none());
+    }
+}
@@ -0,0 +1,114 @@
+/// `blameprompt logs`: read the log file written by `core::logging`.
+use crate::core::logging;
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+use std::time::Duration;
+
+const LEVELS: [&str; 5] = ["TRACE", "DEBUG", "INFO", "WARN", "ERROR"];
+
+pub fn path() {
+    match logging::log_path() {
+        Some(p) => println!("{}", p.display()),
+        None => {
+            eprintln!("[BlamePrompt] Cannot determine home directory");
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Print the last `lines` lines at or above `min_level`, then keep printing
+/// new ones with `follow`.
+pub fn tail(lines: usize, follow: bool, min_level: Option<&str>) -> Result<(), String> {
+    let min_rank = match min_level {
+        Some(l) => Some(level_rank(&l.to_uppercase()).ok_or_else(|| {
+            format!(
+                "Unknown level '{}'. Use one of: trace, debug, info, warn, error",
+                l
+            )
+        })?),
+        None => None,
+    };
+    let path = logging::log_path().ok_or("Cannot determine home directory")?;
+    if !path.exists() && !follow {
+        return Err(format!(
+            "No log file at {} yet. Hooks write to it once BlamePrompt is set up; \
+             set [logging] level = \"debug\" in .blamepromptrc for more detail.",
+            path.display()
+        ));
+    }
+
+    let content = std::fs::read_to_string(&path).unwrap_or_default();
+    let shown: Vec<&str> = content.lines().filter(|l| keep(l, min_rank)).collect();
+    for line in &shown[shown.len().saturating_sub(lines)..] {
+        println!("{}", line);
+    }
+    if follow {
+        follow_file(&path, content.len() as u64, min_rank);
+    }
+    Ok(())
+}
+
+/// Poll for appended lines; starts over when the file is rotated or truncated.
+fn follow_file(path: &Path, mut offset: u64, min_rank: Option<usize>) -> ! {
+    let mut partial = String::new();
+    loop {
+        std::thread::sleep(Duration::from_millis(500));
+        let Ok(mut file) = File::open(path) else {
+            continue;
+        };
+        let len = file.metadata().map(|m| m.len()).unwrap_or(0);
+        if len < offset {
+            offset = 0;
+            partial.clear();
+        }
+        if len == offset || file.seek(SeekFrom::Start(offset)).is_err() {
+            continue;
+        }
+        let mut chunk = String::new();
+        if file.read_to_string(&mut chunk).is_err() {
+            continue;
+        }
+        offset += chunk.len() as u64;
+        partial.push_str(&chunk);
+        while let Some(pos) = partial.find('\n') {
+            let line: String = partial.drain(..=pos).collect();
+            let line = line.trim_end();
+            if keep(line, min_rank) {
+                println!("{}", line);
+            }
+        }
+    }
+}
+
+fn level_rank(level: &str) -> Option<usize> {
+    LEVELS.iter().position(|l| *l == level)
+}
+
+/// Lines look like `2026-01-05T10:00:00.123Z  INFO hook{...}: message`; lines
+/// without a level (continuations) are kept.
+fn keep(line: &str, min_rank: Option<usize>) -> bool {
+    let Some(min) = min_rank else {
+        return true;
+    };
+    line.split_whitespace()
+        .nth(1)
+        .and_then(level_rank)
+        .is_none_or(|rank| rank >= min)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keep_filters_by_level() {
+        let warn = Some(level_rank("WARN").unwrap());
+        assert!(keep("2026-01-05T10:00:00Z  WARN hook: no transcript", warn));
+        assert!(keep("2026-01-05T10:00:00Z ERROR attach failed", warn));
+        assert!(!keep("2026-01-05T10:00:00Z  INFO hook: event", warn));
+        assert!(!keep("2026-01-05T10:00:00Z DEBUG skipped", warn));
+        assert!(keep("    continuation line", warn));
+        assert!(keep("2026-01-05T10:00:00Z DEBUG skipped", None));
+    }
+}
@@ -0,0 +1,270 @@
+/// `blameprompt mcp-serve`: provenance as a Model Context Protocol server.
+///
+/// Speaks JSON-RPC 2.0 over stdio, one message per line, so an agent can ask
+/// about earlier AI work in the repository while it works:
+///
+/// - `get_receipts_for_file`: receipts that touched a file, plus the line
+///   ranges currently attributed to AI
+/// - `search_prompts`: the same matching as `blameprompt search`
+/// - `get_commit_provenance`: every receipt of one commit
+///
+/// Register it with an agent, e.g. `claude mcp add blameprompt -- blameprompt mcp-serve`.
+/// Stdout carries only protocol messages; diagnostics go to stderr.
+use crate::commands::check_provenance;
+use crate::commands::search::{self, SearchResult};
+use crate::core::util;
+use crate::git::notes;
+use serde_json::{json, Value};
+use std::io::{BufRead, Write};
+use std::process::Command;
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const DEFAULT_LIMIT: usize = 20;
+
+pub fn run() -> Result<(), String> {
+    let stdin = std::io::stdin();
+    let mut stdout = std::io::stdout();
+    for line in stdin.lock().lines() {
+        let line = line.map_err(|e| format!("Cannot read stdin: {}", e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Value>(&line) {
+            Ok(request) => handle(&request),
+            Err(e) => Some(error_response(
+                Value::Null,
+                -32700,
+                &format!("Parse error: {}", e),
+            )),
+        };
+        if let Some(response) = response {
+            writeln!(stdout, "{}", response)
+                .and_then(|_| stdout.flush())
+                .map_err(|e| format!("Cannot write stdout: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// The response to one JSON-RPC message; None for notifications.
+pub fn handle(request: &Value) -> Option<Value> {
+    let id = request.get("id").cloned()?;
+    let method = request.get("method").and_then(Value::as_str).unwrap_or("");
+    let params = request.get("params").cloned().unwrap_or(Value::Null);
+    let result = match method {
+        "initialize" => json!({
+            "protocolVersion": params
+                .get("protocolVersion")
+                .and_then(Value::as_str)
+                .unwrap_or(PROTOCOL_VERSION),
+            "capabilities": { "tools": {} },
+            "serverInfo": { "name": "blameprompt", "version": env!("CARGO_PKG_VERSION") },
+        }),
+        "ping" => json!({}),
+        "tools/list" => json!({ "tools": tool_definitions() }),
+        "tools/call" => {
+            let name = params.get("name").and_then(Value::as_str).unwrap_or("");
+            let args = params.get("arguments").cloned().unwrap_or(json!({}));
+            match call_tool(name, &args) {
+                Ok(value) => tool_result(&value, false),
+                Err(e) => tool_result(&json!({ "error": e }), true),
+            }
+        }
+        other => {
+            return Some(error_response(
+                id,
+                -32601,
+                &format!("Method not found: {}", other),
+            ))
+        }
+    };
+    Some(json!({ "jsonrpc": "2.0", "id": id, "result": result }))
+}
+
+fn tool_definitions() -> Value {
+    json!([
+        {
+            "name": "get_receipts_for_file",
+            "description": "AI receipts (prompt, model, author, commit) that changed a file, newest first, and the line ranges of the file currently attributed to AI.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "path": { "type": "string", "description": "File path relative to the repository root" },
+                    "limit": { "type": "integer", "description": "Maximum receipts to return (default 20)" }
+                },
+                "required": ["path"]
+            }
+        },
+        {
+            "name": "search_prompts",
+            "description": "Search earlier AI prompts in this repository by text, file path, model or provider (case-insensitive substring).",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "query": { "type": "string" },
+                    "limit": { "type": "integer", "description": "Maximum results (default 20)" }
+                },
+                "required": ["query"]
+            }
+        },
+        {
+            "name": "get_commit_provenance",
+            "description": "Every AI receipt attached to a commit: prompts, models, files, line ranges, cost and decisions. Conversation transcripts are left out.",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "commit": { "type": "string", "description": "Commit SHA or ref, e.g. HEAD~2" }
+                },
+                "required": ["commit"]
+            }
+        }
+    ])
+}
+
+fn call_tool(name: &str, args: &Value) -> Result<Value, String> {
+    let arg = |key: &str| -> Result<String, String> {
+        args.get(key)
+            .and_then(Value::as_str)
+            .map(String::from)
+            .ok_or_else(|| format!("Missing argument: {}", key))
+    };
+    let limit = args
+        .get("limit")
+        .and_then(Value::as_u64)
+        .map_or(DEFAULT_LIMIT, |n| n as usize);
+    match name {
+        "get_receipts_for_file" => receipts_for_file(&arg("path")?, limit),
+        "search_prompts" => {
+            let query = arg("query")?;
+            let commits = notes::list_commits_with_notes();
+            let results: Vec<SearchResult> = search::find_matches(&commits, &query, limit)
+                .iter()
+                .map(|(sha, r)| SearchResult::new(sha, r))
+                .collect();
+            Ok(json!({ "query": query, "total_matches": results.len(), "results": results }))
+        }
+        "get_commit_provenance" => commit_provenance(&arg("commit")?),
+        other => Err(format!("Unknown tool: {}", other)),
+    }
+}
+
+fn receipts_for_file(path: &str, limit: usize) -> Result<Value, String> {
+    let mut receipts: Vec<SearchResult> = Vec::new();
+    for sha in notes::list_commits_with_notes() {
+        let Some(payload) = notes::read_receipts_for_commit(&sha) else {
+            continue;
+        };
+        for r in &payload.receipts {
+            if r.all_file_paths()
+                .iter()
+                .any(|f| util::paths_match(f, path))
+            {
+                receipts.push(SearchResult::new(&sha, r));
+            }
+        }
+    }
+    receipts.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+    receipts.truncate(limit);
+
+    let ai_ranges: Vec<Value> = check_provenance::cached_provenance(path)
+        .map(|lines| check_provenance::collapse_to_ranges(&lines))
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|r| r.is_ai)
+        .map(|r| {
+            json!({
+                "start_line": r.start_line,
+                "end_line": r.end_line,
+                "model": r.model,
+                "receipt_id": r.receipt_id,
+                "commit_sha": r.commit_sha,
+                "prompt_summary": r.prompt_summary,
+            })
+        })
+        .collect();
+    Ok(json!({ "file": path, "ai_ranges": ai_ranges, "receipts": receipts }))
+}
+
+fn commit_provenance(commit: &str) -> Result<Value, String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--verify", &format!("{}^{{commit}}", commit)])
+        .output()
+        .map_err(|e| format!("git rev-parse failed: {}", e))?;
+    if !output.status.success() {
+        return Err(format!("Cannot resolve commit: {}", commit));
+    }
+    let sha = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    let mut receipts = notes::read_receipts_for_commit(&sha)
+        .map(|p| p.receipts)
+        .unwrap_or_default();
+    for r in &mut receipts {
+        r.conversation = None;
+    }
+    Ok(json!({ "commit": sha, "receipts": receipts }))
+}
+
+fn tool_result(value: &Value, is_error: bool) -> Value {
+    let text = serde_json::to_string_pretty(value).unwrap_or_default();
+    json!({ "content": [{ "type": "text", "text": text }], "isError": is_error })
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mcp_handshake_and_tools() {
+        let init = handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "initialize",
+            "params": { "protocolVersion": "2025-03-26" }
+        }))
+        .unwrap();
+        assert_eq!(init["id"], 1);
+        assert_eq!(init["result"]["protocolVersion"], "2025-03-26");
+        assert_eq!(init["result"]["serverInfo"]["name"], "blameprompt");
+
+        // Notifications get no response.
+        assert!(
+            handle(&json!({ "jsonrpc": "2.0", "method": "notifications/initialized" })).is_none()
+        );
+
+        let list = handle(&json!({ "jsonrpc": "2.0", "id": 2, "method": "tools/list" })).unwrap();
+        let names: Vec<&str> = list["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            vec![
+                "get_receipts_for_file",
+                "search_prompts",
+                "get_commit_provenance"
+            ]
+        );
+
+        let bad = handle(&json!({
+            "jsonrpc": "2.0",
+            "id": 3,
+            "method": "tools/call",
+            "params": { "name": "search_prompts", "arguments": {} }
+        }))
+        .unwrap();
+        assert_eq!(bad["result"]["isError"], true);
+        assert!(bad["result"]["content"][0]["text"]
+            .as_str()
+            .unwrap()
+            .contains("Missing argument: query"));
+
+        let unknown =
+            handle(&json!({ "jsonrpc": "2.0", "id": 4, "method": "resources/list" })).unwrap();
+        assert_eq!(unknown["error"]["code"], -32601);
+    }
+}
@@ -0,0 +1,387 @@
+/// DORA-style metrics for AI-assisted changes.
+///
+/// - Lead time: prompt submitted → commit → merged into the base branch.
+/// - Change failure proxy: AI commits whose files were touched by a revert or
+///   fix commit within `failure_window_days`.
+/// - Rework rate: share of AI-written lines modified or deleted within
+///   `rework_window_days` of the commit (tracked by `rework`).
+///
+/// Thresholds live in `[metrics]` in `.blamepromptrc`.
+use crate::commands::anomalies::make_rel;
+use crate::commands::{audit, github, rework};
+use crate::core::config::{self, MetricsConfig};
+use crate::core::util;
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::process::Command;
+
+#[derive(Debug, Serialize, Default, PartialEq)]
+pub struct DurationStats {
+    pub samples: usize,
+    pub median_hours: Option<f64>,
+    pub p90_hours: Option<f64>,
+    pub mean_hours: Option<f64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct LeadTime {
+    pub prompt_to_commit: DurationStats,
+    pub commit_to_merge: DurationStats,
+    pub prompt_to_merge: DurationStats,
+    pub merged_commits: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FollowUp {
+    pub ai_commit: String,
+    pub follow_up: String,
+    pub subject: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ChangeFailure {
+    pub window_days: i64,
+    pub ai_commits: usize,
+    pub failed_commits: usize,
+    pub rate_pct: f64,
+    pub follow_ups: Vec<FollowUp>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Rework {
+    pub window_days: i64,
+    pub ai_lines: u32,
+    pub reworked_lines: u32,
+    pub rate_pct: f64,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MetricsReport {
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub base: String,
+    pub ai_commits: usize,
+    pub lead_time: LeadTime,
+    pub change_failure: ChangeFailure,
+    pub rework: Rework,
+}
+
+// ---------------------------------------------------------------------------
+// Public entry point
+// ---------------------------------------------------------------------------
+
+pub fn run(
+    from: Option<&str>,
+    to: Option<&str>,
+    base: Option<&str>,
+    format: &str,
+) -> Result<(), String> {
+    let report = generate(from, to, base, &config::load_config().metrics)?;
+    if format == "json" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).map_err(|e| e.to_string())?
+        );
+        return Ok(());
+    }
+    print_report(&report);
+    Ok(())
+}
+
+pub fn generate(
+    from: Option<&str>,
+    to: Option<&str>,
+    base: Option<&str>,
+    cfg: &MetricsConfig,
+) -> Result<MetricsReport, String> {
+    let base = base
+        .map(String::from)
+        .or_else(default_base)
+        .unwrap_or_else(|| "HEAD".to_string());
+    let fix_patterns = rework::fix_patterns(cfg)?;
+
+    let entries = audit::collect_audit_entries(from, to, None)?;
+    let first_parent: HashSet<String> = git_lines(&["rev-list", "--first-parent", &base])
+        .into_iter()
+        .collect();
+
+    let mut prompt_to_commit = Vec::new();
+    let mut commit_to_merge = Vec::new();
+    let mut prompt_to_merge = Vec::new();
+    let mut follow_ups = Vec::new();
+    let mut failed = 0;
+
+    for entry in &entries {
+        let Some(committed) = commit_time(&entry.commit_sha) else {
+            continue;
+        };
+        let prompted = entry
+            .receipts
+            .iter()
+            .map(|r| r.prompt_submitted_at.unwrap_or(r.timestamp))
+            .min();
+        if let Some(prompted) = prompted.filter(|p| *p <= committed) {
+            prompt_to_commit.push(hours(committed - prompted));
+        }
+        if let Some(merged) = merged_at(&entry.commit_sha, &base, &first_parent) {
+            commit_to_merge.push(hours(merged - committed));
+            if let Some(prompted) = prompted.filter(|p| *p <= merged) {
+                prompt_to_merge.push(hours(merged - prompted));
+            }
+        }
+
+        let files: Vec<String> = entry
+            .receipts
+            .iter()
+            .flat_map(|r| r.all_file_changes())
+            .filter(|fc| !fc.is_binary)
+            .map(|fc| make_rel(&fc.path))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let fixes = fix_commits(
+            &entry.commit_sha,
+            committed,
+            &files,
+            cfg.failure_window_days,
+            &fix_patterns,
+        );
+        if !fixes.is_empty() {
+            failed += 1;
+        }
+        follow_ups.extend(fixes.into_iter().map(|(sha, subject)| FollowUp {
+            ai_commit: util::short_sha(&entry.commit_sha),
+            follow_up: util::short_sha(&sha),
+            subject,
+        }));
+    }
+
+    let rework = rework::analyze(&entries, cfg)?;
+    let pct = |n: f64, d: f64| if d > 0.0 { n / d * 100.0 } else { 0.0 };
+    Ok(MetricsReport {
+        from: from.map(String::from),
+        to: to.map(String::from),
+        base,
+        ai_commits: entries.len(),
+        lead_time: LeadTime {
+            merged_commits: commit_to_merge.len(),
+            prompt_to_commit: duration_stats(prompt_to_commit),
+            commit_to_merge: duration_stats(commit_to_merge),
+            prompt_to_merge: duration_stats(prompt_to_merge),
+        },
+        change_failure: ChangeFailure {
+            window_days: cfg.failure_window_days,
+            ai_commits: entries.len(),
+            failed_commits: failed,
+            rate_pct: pct(failed as f64, entries.len() as f64),
+            follow_ups,
+        },
+        rework: Rework {
+            window_days: cfg.rework_window_days,
+            ai_lines: rework.ai_lines,
+            reworked_lines: rework.modified_lines,
+            rate_pct: pct(rework.modified_lines as f64, rework.ai_lines as f64),
+        },
+    })
+}
+
+fn print_report(report: &MetricsReport) {
+    println!("AI DELIVERY METRICS");
+    println!("===================");
+    println!(
+        "AI commits: {} (base branch: {})",
+        report.ai_commits, report.base
+    );
+    println!();
+
+    println!("LEAD TIME (hours)");
+    println!("=================");
+    let mut table = comfy_table::Table::new();
+    table.set_header(vec!["Stage", "Samples", "Median", "P90", "Mean"]);
+    let fmt = |v: Option<f64>| v.map(|h| format!("{:.1}", h)).unwrap_or("-".into());
+    for (label, stats) in [
+        ("Prompt -> commit", &report.lead_time.prompt_to_commit),
+        ("Commit -> merge", &report.lead_time.commit_to_merge),
+        ("Prompt -> merge", &report.lead_time.prompt_to_merge),
+    ] {
+        table.add_row(vec![
+            label.to_string(),
+            stats.samples.to_string(),
+            fmt(stats.median_hours),
+            fmt(stats.p90_hours),
+            fmt(stats.mean_hours),
+        ]);
+    }
+    println!("{table}");
+    println!();
+
+    let cf = &report.change_failure;
+    println!("CHANGE FAILURE PROXY");
+    println!("====================");
+    println!(
+        "{}/{} AI commits followed by a revert or fix within {} days ({:.1}%)",
+        cf.failed_commits, cf.ai_commits, cf.window_days, cf.rate_pct
+    );
+    for f in cf.follow_ups.iter().take(10) {
+        println!("  {} <- {} {}", f.ai_commit, f.follow_up, f.subject);
+    }
+    if cf.follow_ups.len() > 10 {
+        println!("  ... and {} more", cf.follow_ups.len() - 10);
+    }
+    println!();
+
+    let rw = &report.rework;
+    println!("REWORK RATE");
+    println!("===========");
+    println!(
+        "{}/{} AI lines modified within {} days ({:.1}%)",
+        rw.reworked_lines, rw.ai_lines, rw.window_days, rw.rate_pct
+    );
+}
+
+// ---------------------------------------------------------------------------
+// Helpers
+// ---------------------------------------------------------------------------
+
+/// The PR base in CI, else the remote default branch, else a local main/master.
+pub fn default_base() -> Option<String> {
+    github::detect_base_ref().or_else(|| {
+        ["main", "master"].into_iter().map(String::from).find(|b| {
+            Command::new("git")
+                .args(["rev-parse", "--verify", "--quiet", b])
+                .output()
+                .is_ok_and(|o| o.status.success())
+        })
+    })
+}
+
+pub fn hours(d: Duration) -> f64 {
+    d.num_seconds() as f64 / 3600.0
+}
+
+pub fn duration_stats(mut samples: Vec<f64>) -> DurationStats {
+    if samples.is_empty() {
+        return DurationStats::default();
+    }
+    samples.sort_by(|a, b| a.total_cmp(b));
+    let n = samples.len();
+    let median = if n.is_multiple_of(2) {
+        (samples[n / 2 - 1] + samples[n / 2]) / 2.0
+    } else {
+        samples[n / 2]
+    };
+    let p90 = samples[((n as f64 * 0.9).ceil() as usize).clamp(1, n) - 1];
+    DurationStats {
+        samples: n,
+        median_hours: Some(median),
+        p90_hours: Some(p90),
+        mean_hours: Some(samples.iter().sum::<f64>() / n as f64),
+    }
+}
+
+fn commit_time(sha: &str) -> Option<DateTime<Utc>> {
+    let out = git_stdout(&["show", "-s", "--format=%cI", sha]);
+    DateTime::parse_from_rfc3339(out.trim())
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+/// When `sha` landed on `base`: its own commit time if it sits on base's
+/// first-parent history (direct push, fast-forward), otherwise the time of the
+/// first-parent commit (usually a merge) that brought it in.
+pub fn merged_at(sha: &str, base: &str, first_parent: &HashSet<String>) -> Option<DateTime<Utc>> {
+    if first_parent.contains(sha) {
+        return commit_time(sha);
+    }
+    let is_ancestor = Command::new("git")
+        .args(["merge-base", "--is-ancestor", sha, base])
+        .status()
+        .is_ok_and(|s| s.success());
+    if !is_ancestor {
+        return None;
+    }
+    let range = format!("{}..{}", sha, base);
+    let merge = git_lines(&[
+        "rev-list",
+        "--first-parent",
+        "--ancestry-path",
+        "--reverse",
+        &range,
+    ])
+    .into_iter()
+    .next()?;
+    commit_time(&merge)
+}
+
+/// Commits touching `files` within `window_days` after `committed` whose
+/// subject matches one of the fix/revert patterns.
+fn fix_commits(
+    sha: &str,
+    committed: DateTime<Utc>,
+    files: &[String],
+    window_days: i64,
+    patterns: &[Regex],
+) -> Vec<(String, String)> {
+    if files.is_empty() {
+        return Vec::new();
+    }
+    let since = committed.to_rfc3339();
+    let until = (committed + Duration::days(window_days)).to_rfc3339();
+    let mut args = vec![
+        "log".to_string(),
+        "--format=%H|%s".to_string(),
+        format!("--since={}", since),
+        format!("--until={}", until),
+        format!("{}..HEAD", sha),
+        "--".to_string(),
+    ];
+    args.extend(files.iter().cloned());
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    git_lines(&args)
+        .into_iter()
+        .filter_map(|l| {
+            let (sha, subject) = l.split_once('|')?;
+            patterns
+                .iter()
+                .any(|p| p.is_match(subject))
+                .then(|| (sha.to_string(), subject.to_string()))
+        })
+        .collect()
+}
+
+fn git_stdout(args: &[&str]) -> String {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default()
+}
+
+pub fn git_lines(args: &[&str]) -> Vec<String> {
+    git_stdout(args)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duration_stats() {
+        assert_eq!(duration_stats(vec![]), DurationStats::default());
+        let stats = duration_stats(vec![4.0, 1.0, 3.0, 2.0]);
+        assert_eq!(stats.samples, 4);
+        assert_eq!(stats.median_hours, Some(2.5));
+        assert_eq!(stats.p90_hours, Some(4.0));
+        assert_eq!(stats.mean_hours, Some(2.5));
+        let stats = duration_stats((1..=10).map(f64::from).collect());
+        assert_eq!(stats.p90_hours, Some(9.0));
+    }
+}
@@ -0,0 +1,98 @@
+/// `blameprompt migrate`: bring stored notes up to the current schema.
+///
+/// Dry run by default: reports how many notes are current, how many would be
+/// upgraded (by source version), and which ones cannot be handled — written by
+/// a newer BlamePrompt or not valid JSON. `--write` rewrites the upgradable
+/// notes in place through the configured storage backend.
+use crate::core::migrate;
+use crate::core::receipt::SCHEMA_VERSION;
+use crate::core::util;
+use crate::git::{notes, storage};
+use std::collections::BTreeMap;
+
+pub fn run(write: bool) -> Result<(), String> {
+    let store = storage::active();
+    let commits = notes::list_commits_with_notes();
+    if commits.is_empty() {
+        println!("No BlamePrompt notes found.");
+        return Ok(());
+    }
+
+    let mut current = 0;
+    let mut upgraded: BTreeMap<u32, usize> = BTreeMap::new();
+    let mut unknown: Vec<(String, u32)> = Vec::new();
+    let mut unreadable: Vec<(String, String)> = Vec::new();
+
+    for sha in &commits {
+        let Some(raw) = store.read(sha) else {
+            continue;
+        };
+        let mut note: serde_json::Value = match serde_json::from_str(&raw) {
+            Ok(v) => v,
+            Err(e) => {
+                unreadable.push((sha.clone(), e.to_string()));
+                continue;
+            }
+        };
+        let from = match migrate::upgrade(&mut note) {
+            Ok(v) if v == SCHEMA_VERSION => {
+                current += 1;
+                continue;
+            }
+            Ok(v) => v,
+            Err(_) => {
+                unknown.push((sha.clone(), migrate::schema_version_of(&note)));
+                continue;
+            }
+        };
+        // Make sure the upgraded note still deserializes before counting it.
+        let payload = match migrate::from_value(note) {
+            Ok(p) => p,
+            Err(e) => {
+                unreadable.push((sha.clone(), e));
+                continue;
+            }
+        };
+        if write {
+            if let Err(e) = notes::write_receipts_for_commit(sha, &payload) {
+                unreadable.push((sha.clone(), format!("write failed: {}", e)));
+                continue;
+            }
+        }
+        *upgraded.entry(from).or_default() += 1;
+    }
+
+    let total_upgraded: usize = upgraded.values().sum();
+    println!(
+        "{} note(s) at schema_version {}: {} current, {} {}",
+        commits.len(),
+        SCHEMA_VERSION,
+        current,
+        total_upgraded,
+        if write { "upgraded" } else { "need upgrading" }
+    );
+    for (from, n) in &upgraded {
+        println!("  from schema_version {}: {}", from, n);
+    }
+    if !unknown.is_empty() {
+        println!(
+            "\n{} note(s) use a newer schema than this blameprompt understands (upgrade blameprompt):",
+            unknown.len()
+        );
+        for (sha, v) in &unknown {
+            println!("  {}  schema_version {}", util::short_sha(sha), v);
+        }
+    }
+    if !unreadable.is_empty() {
+        println!("\n{} note(s) could not be read:", unreadable.len());
+        for (sha, e) in &unreadable {
+            println!("  {}  {}", util::short_sha(sha), e);
+        }
+    }
+    if !write && total_upgraded > 0 {
+        println!(
+            "\nRun `blameprompt migrate --write` to rewrite them, then `blameprompt push` to share."
+        );
+    }
+    Ok(())
+}
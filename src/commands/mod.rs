@@ -1,28 +1,71 @@
+pub mod acceptance_backfill;
 pub mod analytics;
+pub mod anomalies;
+pub mod anonymize_paths;
+pub mod assignment;
+pub mod attach_preview;
 pub mod audit;
+pub mod auto_label;
+pub mod badge;
+pub mod bisect_report;
 pub mod blame;
+pub mod bundle;
+pub mod capture;
 pub mod check_provenance;
 pub mod checkpoint;
+pub mod commit_summary;
+pub mod completions;
+pub mod config_show;
+pub mod daemon;
 pub mod dash;
 pub mod diff;
+pub mod digest;
 pub mod doctor;
+pub mod finalize;
+pub mod forget;
+pub mod gerrit;
 pub mod github;
+pub mod graph;
+pub mod grouping;
 pub mod hackathon;
+pub mod heatmap;
+pub mod impact;
 pub mod license_scan;
+pub mod lifecycle;
+pub mod line_origin;
+pub mod link_pr;
+pub mod lint_notes;
 pub mod login;
+pub mod logs;
+pub mod mcp;
+pub mod metrics;
+pub mod migrate;
+pub mod partial_staging;
+pub mod policy;
+pub mod policy_exemptions;
 pub mod profile;
 pub mod prompt_injection;
+pub mod prompts;
 pub mod rebase_notes;
 pub mod record;
+pub mod recover;
 pub mod redact_test;
+pub mod release_summary;
 pub mod report;
+pub mod review;
+pub mod rework;
 pub mod search;
 pub mod secret_rotation;
+pub mod server_hook;
+pub mod setup;
+pub mod shell_capture;
 pub mod show;
 pub mod staging;
+pub mod storage_stats;
 pub mod supply_chain;
 pub mod sync;
 pub mod sync_cloud;
 pub mod uninstall;
 pub mod update;
 pub mod vuln_scan;
+pub mod watch_files;
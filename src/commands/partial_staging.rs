@@ -0,0 +1,79 @@
+/// Partial staging awareness (`git add -p`) for acceptance stats.
+///
+/// When only some hunks of an AI-written file are staged, the lines of the AI
+/// blob that are missing from the commit are not necessarily rewritten by a
+/// human: they may still sit, unchanged, in the working tree. At attach time
+/// each line of the AI blob is therefore sorted three ways against the
+/// committed blob and the working-tree file: *accepted* (in the commit),
+/// *unstaged* (only in the working tree) or *overridden* (in neither). Unstaged
+/// lines are recorded on the file change as `unstaged_ranges` and count
+/// neither as accepted nor overridden.
+use std::collections::HashSet;
+
+#[derive(Debug, Default, PartialEq)]
+pub struct LineSplit {
+    pub accepted: u32,
+    pub overridden: u32,
+    /// 1-based inclusive line ranges of the AI blob left unstaged.
+    pub unstaged: Vec<(u32, u32)>,
+}
+
+impl LineSplit {
+    pub fn unstaged_lines(&self) -> u32 {
+        self.unstaged.iter().map(|(s, e)| e - s + 1).sum()
+    }
+}
+
+/// Sort the lines of the AI-written version of a file by where they ended up.
+pub fn split_lines(ai: &[String], committed: &[String], worktree: &[String]) -> LineSplit {
+    let committed: HashSet<&str> = committed.iter().map(String::as_str).collect();
+    let worktree: HashSet<&str> = worktree.iter().map(String::as_str).collect();
+    let mut split = LineSplit::default();
+    for (i, line) in ai.iter().enumerate() {
+        let n = i as u32 + 1;
+        if committed.contains(line.as_str()) {
+            split.accepted += 1;
+        } else if worktree.contains(line.as_str()) {
+            match split.unstaged.last_mut() {
+                Some((_, end)) if *end + 1 == n => *end = n,
+                _ => split.unstaged.push((n, n)),
+            }
+        } else {
+            split.overridden += 1;
+        }
+    }
+    split
+}
+
+/// Lines of the working-tree copy of `path`; empty when it no longer exists.
+pub fn worktree_lines(path: &str) -> Vec<String> {
+    std::fs::read_to_string(path)
+        .map(|s| s.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(s: &str) -> Vec<String> {
+        s.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_split_partially_staged_file() {
+        let ai = lines("fn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\nfn e() {}");
+        // Hunks b and c were left unstaged; a human rewrote e before committing.
+        let committed = lines("fn a() {}\nfn d() {}\nfn e2() {}");
+        let worktree = lines("fn a() {}\nfn b() {}\nfn c() {}\nfn d() {}\nfn e2() {}");
+        let split = split_lines(&ai, &committed, &worktree);
+        assert_eq!(split.accepted, 2);
+        assert_eq!(split.overridden, 1);
+        assert_eq!(split.unstaged, vec![(2, 3)]);
+        assert_eq!(split.unstaged_lines(), 2);
+
+        // Fully staged: nothing pending.
+        let split = split_lines(&ai, &ai, &ai);
+        assert_eq!((split.accepted, split.unstaged_lines()), (5, 0));
+    }
+}
@@ -1,4 +1,6 @@
 use crate::commands::audit;
+use crate::core::config;
+use crate::core::scan_exclude::{ScanExclusions, Skipped};
 use chrono::Utc;
 use regex::Regex;
 
@@ -85,6 +87,15 @@ const INJECTION_PATTERNS: &[InjectionPattern] = &[
     },
 ];
 
+/// Injection patterns that match `line`, as `(name, severity, description)`.
+pub fn scan_line(line: &str) -> Vec<(&'static str, &'static str, &'static str)> {
+    INJECTION_PATTERNS
+        .iter()
+        .filter(|p| Regex::new(p.pattern).is_ok_and(|re| re.is_match(line)))
+        .map(|p| (p.name, p.severity, p.description))
+        .collect()
+}
+
 struct Detection {
     file: String,
     line_number: u32,
@@ -114,6 +125,9 @@ pub fn run(output: &str) {
     let all_receipts: Vec<_> = entries.iter().flat_map(|e| &e.receipts).collect();
     let now = Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
 
+    let cfg = config::load_config();
+    let exclusions = ScanExclusions::load(&cfg.scan, &cfg.scan.prompt_injection);
+    let mut skipped = Skipped::default();
     let mut detections: Vec<Detection> = Vec::new();
     let mut files_scanned = 0;
     let mut lines_scanned: u32 = 0;
@@ -122,6 +136,10 @@ pub fn run(output: &str) {
     for r in &all_receipts {
         for fc in r.all_file_changes() {
             let file_path = &fc.path;
+            if let Some(reason) = exclusions.reason(file_path) {
+                skipped.add(relative_path(file_path), reason);
+                continue;
+            }
             let content = match std::fs::read_to_string(file_path) {
                 Ok(c) => c,
                 Err(_) => continue,
@@ -141,21 +159,17 @@ pub fn run(output: &str) {
                 let line_num = (start + i + 1) as u32;
                 lines_scanned += 1;
 
-                for pattern in INJECTION_PATTERNS {
-                    if let Ok(re) = Regex::new(pattern.pattern) {
-                        if re.is_match(line) {
-                            detections.push(Detection {
-                                file: relative_path(file_path),
-                                line_number: line_num,
-                                line_content: line.trim().chars().take(120).collect(),
-                                pattern_name: pattern.name.to_string(),
-                                severity: pattern.severity.to_string(),
-                                description: pattern.description.to_string(),
-                                model: r.model.clone(),
-                                receipt_id: r.id.clone(),
-                            });
-                        }
-                    }
+                for (name, severity, description) in scan_line(line) {
+                    detections.push(Detection {
+                        file: relative_path(file_path),
+                        line_number: line_num,
+                        line_content: line.trim().chars().take(120).collect(),
+                        pattern_name: name.to_string(),
+                        severity: severity.to_string(),
+                        description: description.to_string(),
+                        model: r.model.clone(),
+                        receipt_id: r.id.clone(),
+                    });
                 }
             }
         }
@@ -210,6 +224,7 @@ pub fn run(output: &str) {
     md.push_str("| Metric | Value |\n");
     md.push_str("|--------|-------|\n");
     md.push_str(&format!("| Files scanned | {} |\n", files_scanned));
+    md.push_str(&format!("| Files excluded | {} |\n", skipped.len()));
     md.push_str(&format!(
         "| AI-generated lines scanned | {} |\n",
         lines_scanned
@@ -257,6 +272,8 @@ pub fn run(output: &str) {
         );
     }
 
+    md.push_str(&skipped.to_markdown());
+
     // Suspicious AI responses
     if !prompt_flags.is_empty() {
         md.push_str("## Suspicious AI Responses\n\n");
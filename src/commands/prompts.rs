@@ -0,0 +1,350 @@
+/// `blameprompt prompts top`: the prompt patterns a team reuses most.
+///
+/// Prompts are normalized (lowercased; paths, numbers and hashes replaced by
+/// placeholders) and compared by MinHash over word 3-grams, so "fix the failing
+/// test in src/a.rs" and "Fix the failing test in src/b.rs" land in one
+/// cluster. Each cluster reports how often it was used, by how many people, and
+/// the acceptance rate of the lines it produced (lines committed unchanged vs
+/// lines a human rewrote before committing), which is what makes a pattern
+/// worth putting in a prompt library.
+use crate::commands::{audit, grouping};
+use crate::core::receipt::Receipt;
+use comfy_table::Table;
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+/// MinHash signature length; the similarity estimate's error is about 1/sqrt(64).
+const NUM_HASHES: usize = 64;
+const SHINGLE_WORDS: usize = 3;
+
+#[derive(Debug, Serialize)]
+pub struct PromptPattern {
+    /// Normalized text of the cluster's first prompt.
+    pub pattern: String,
+    /// The most common original wording.
+    pub example: String,
+    pub uses: usize,
+    pub sessions: usize,
+    pub authors: usize,
+    pub ai_lines: u32,
+    pub accepted_lines: u32,
+    pub overridden_lines: u32,
+    pub acceptance_rate: Option<f64>,
+    pub avg_cost_usd: f64,
+    pub last_used: String,
+}
+
+pub fn run_top(limit: usize, min_uses: usize, similarity: f64, format: &str) {
+    let entries = match audit::collect_audit_entries(None, None, None) {
+        Ok(e) => e,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    let receipts: Vec<&Receipt> = entries.iter().flat_map(|e| &e.receipts).collect();
+    let mut patterns = cluster(&receipts, similarity);
+    patterns.retain(|p| p.uses >= min_uses);
+    patterns.truncate(limit);
+
+    if format == "json" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&patterns).unwrap_or_default()
+        );
+        return;
+    }
+    if patterns.is_empty() {
+        println!(
+            "No prompt pattern was used {} or more times ({} prompt(s) checked).",
+            min_uses,
+            receipts.len()
+        );
+        return;
+    }
+    println!("TOP PROMPT PATTERNS");
+    println!("===================");
+    let mut table = Table::new();
+    table.set_header(vec![
+        "Example",
+        "Uses",
+        "Sessions",
+        "Authors",
+        "AI Lines",
+        "Acceptance",
+        "Avg Cost",
+    ]);
+    for p in &patterns {
+        table.add_row(vec![
+            truncate(&p.example, 60),
+            p.uses.to_string(),
+            p.sessions.to_string(),
+            p.authors.to_string(),
+            p.ai_lines.to_string(),
+            p.acceptance_rate
+                .map_or("-".to_string(), |r| format!("{:.0}%", r)),
+            format!("${:.4}", p.avg_cost_usd),
+        ]);
+    }
+    println!("{table}");
+    println!(
+        "{} pattern(s) from {} prompt(s); acceptance = committed AI lines kept unchanged",
+        patterns.len(),
+        receipts.len()
+    );
+}
+
+/// Cluster receipts by prompt similarity (estimated Jaccard over word
+/// 3-grams, at least `similarity`), largest cluster first.
+pub fn cluster(receipts: &[&Receipt], similarity: f64) -> Vec<PromptPattern> {
+    struct Cluster<'a> {
+        pattern: String,
+        signature: Vec<u64>,
+        members: Vec<&'a Receipt>,
+    }
+
+    let mut clusters: Vec<Cluster> = Vec::new();
+    let mut by_text: HashMap<String, usize> = HashMap::new();
+    for r in receipts {
+        let normalized = normalize(&r.prompt_summary);
+        if normalized.is_empty() {
+            continue;
+        }
+        if let Some(&i) = by_text.get(&normalized) {
+            clusters[i].members.push(r);
+            continue;
+        }
+        let signature = minhash(&shingles(&normalized));
+        let i = match clusters
+            .iter()
+            .position(|c| estimate_similarity(&c.signature, &signature) >= similarity)
+        {
+            Some(i) => {
+                clusters[i].members.push(r);
+                i
+            }
+            None => {
+                clusters.push(Cluster {
+                    pattern: normalized.clone(),
+                    signature,
+                    members: vec![r],
+                });
+                clusters.len() - 1
+            }
+        };
+        by_text.insert(normalized, i);
+    }
+
+    let mut patterns: Vec<PromptPattern> = clusters
+        .into_iter()
+        .map(|c| {
+            let mut wordings: HashMap<&str, usize> = HashMap::new();
+            for r in &c.members {
+                *wordings.entry(r.prompt_summary.trim()).or_default() += 1;
+            }
+            let example = wordings
+                .into_iter()
+                .max_by(|a, b| {
+                    a.1.cmp(&b.1)
+                        .then(b.0.len().cmp(&a.0.len()))
+                        .then(b.0.cmp(a.0))
+                })
+                .map(|(w, _)| w.to_string())
+                .unwrap_or_default();
+            let accepted: u32 = c.members.iter().filter_map(|r| r.accepted_lines).sum();
+            let overridden: u32 = c.members.iter().filter_map(|r| r.overridden_lines).sum();
+            let cost: f64 = c.members.iter().map(|r| r.cost_usd).sum();
+            PromptPattern {
+                pattern: c.pattern,
+                example,
+                uses: c.members.len(),
+                sessions: c
+                    .members
+                    .iter()
+                    .map(|r| r.session_id.as_str())
+                    .collect::<HashSet<_>>()
+                    .len(),
+                authors: c
+                    .members
+                    .iter()
+                    .map(|r| r.user.as_str())
+                    .collect::<HashSet<_>>()
+                    .len(),
+                ai_lines: c.members.iter().map(|r| grouping::receipt_lines(r)).sum(),
+                accepted_lines: accepted,
+                overridden_lines: overridden,
+                acceptance_rate: (accepted + overridden > 0)
+                    .then(|| accepted as f64 / (accepted + overridden) as f64 * 100.0),
+                avg_cost_usd: cost / c.members.len() as f64,
+                last_used: c
+                    .members
+                    .iter()
+                    .map(|r| r.timestamp)
+                    .max()
+                    .map(|t| t.to_rfc3339())
+                    .unwrap_or_default(),
+            }
+        })
+        .collect();
+    patterns.sort_by(|a, b| {
+        b.uses
+            .cmp(&a.uses)
+            .then(b.authors.cmp(&a.authors))
+            .then(a.pattern.cmp(&b.pattern))
+    });
+    patterns
+}
+
+/// Lowercase words with paths, numbers and hashes replaced by placeholders,
+/// so prompts that differ only in what they point at compare equal.
+pub fn normalize(prompt: &str) -> String {
+    prompt
+        .split_whitespace()
+        .filter_map(|raw| {
+            let word = raw
+                .trim_matches(|c: char| !c.is_alphanumeric() && !"/._-<>".contains(c))
+                .trim_end_matches(['.', '<', '>'])
+                .to_lowercase();
+            if word.is_empty() {
+                return None;
+            }
+            let placeholder = if word.contains('/') || has_file_extension(&word) {
+                "<path>"
+            } else if word
+                .chars()
+                .all(|c| c.is_ascii_digit() || c == '.' || c == ',')
+            {
+                "<n>"
+            } else if word.len() >= 7
+                && word.chars().all(|c| c.is_ascii_hexdigit())
+                && word.chars().any(|c| c.is_ascii_digit())
+            {
+                "<sha>"
+            } else {
+                return Some(word);
+            };
+            Some(placeholder.to_string())
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn has_file_extension(word: &str) -> bool {
+    match word.rsplit_once('.') {
+        Some((stem, ext)) => {
+            !stem.is_empty()
+                && (1..=4).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+                && ext.chars().any(|c| c.is_ascii_alphabetic())
+        }
+        None => false,
+    }
+}
+
+fn shingles(normalized: &str) -> Vec<String> {
+    let words: Vec<&str> = normalized.split(' ').collect();
+    if words.len() <= SHINGLE_WORDS {
+        return vec![normalized.to_string()];
+    }
+    words.windows(SHINGLE_WORDS).map(|w| w.join(" ")).collect()
+}
+
+fn minhash(shingles: &[String]) -> Vec<u64> {
+    (0..NUM_HASHES as u64)
+        .map(|seed| {
+            shingles
+                .iter()
+                .map(|s| hash64(seed, s))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+fn estimate_similarity(a: &[u64], b: &[u64]) -> f64 {
+    let same = a.iter().zip(b).filter(|(x, y)| x == y).count();
+    same as f64 / a.len().max(1) as f64
+}
+
+/// FNV-1a seeded per signature slot, finished with a splitmix64 mix; stable
+/// across runs and platforms.
+fn hash64(seed: u64, s: &str) -> u64 {
+    let mut h: u64 = 0xcbf2_9ce4_8422_2325 ^ seed.wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    for b in s.bytes() {
+        h ^= b as u64;
+        h = h.wrapping_mul(0x0100_0000_01b3);
+    }
+    h ^= h >> 30;
+    h = h.wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    h ^= h >> 27;
+    h = h.wrapping_mul(0x94d0_49bb_1331_11eb);
+    h ^ (h >> 31)
+}
+
+fn truncate(s: &str, max: usize) -> String {
+    if s.chars().count() <= max {
+        s.to_string()
+    } else {
+        format!("{}...", s.chars().take(max - 3).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(id: &str, prompt: &str, user: &str, accepted: u32, overridden: u32) -> Receipt {
+        let mut r = Receipt::test(id)
+            .with_model("m")
+            .with_session(&format!("s-{}", id))
+            .with_prompt(prompt)
+            .with_cost(0.1)
+            .with_timestamp("2026-04-01T00:00:00Z")
+            .with_user(user);
+        r.accepted_lines = Some(accepted);
+        r.overridden_lines = Some(overridden);
+        r
+    }
+
+    #[test]
+    fn test_normalize_prompt() {
+        assert_eq!(
+            normalize("Fix the failing test in src/auth.rs (line 42), see a1b2c3d4!"),
+            "fix the failing test in <path> line <n> see <sha>"
+        );
+        assert_eq!(normalize("  ...  "), "");
+    }
+
+    #[test]
+    fn test_cluster_similar_prompts() {
+        let receipts = [
+            receipt("1", "Fix the failing test in src/a.rs", "alice", 8, 2),
+            receipt("2", "fix the failing test in src/b.rs", "bob", 10, 0),
+            receipt(
+                "3",
+                "Fix the failing test in tests/api.rs please",
+                "alice",
+                0,
+                0,
+            ),
+            receipt(
+                "4",
+                "Write a README section about deployment",
+                "carol",
+                5,
+                5,
+            ),
+        ];
+        let refs: Vec<&Receipt> = receipts.iter().collect();
+        let patterns = cluster(&refs, 0.5);
+        assert_eq!(patterns.len(), 2);
+        let top = &patterns[0];
+        assert_eq!((top.uses, top.authors, top.sessions), (3, 2, 3));
+        assert_eq!(top.pattern, "fix the failing test in <path>");
+        assert_eq!(top.example, "Fix the failing test in src/a.rs");
+        assert_eq!((top.accepted_lines, top.overridden_lines), (18, 2));
+        assert!((top.acceptance_rate.unwrap() - 90.0).abs() < 1e-9);
+        assert!((top.avg_cost_usd - 0.1).abs() < 1e-9);
+        assert_eq!(patterns[1].uses, 1);
+    }
+}
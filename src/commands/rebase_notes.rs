@@ -26,7 +26,11 @@ pub fn remap(old_sha: &str, new_sha: &str) {
         None => return,
     };
 
-    let remapped = remap_line_offsets(payload, old_sha, new_sha);
+    let mut remapped = remap_line_offsets(payload, old_sha, new_sha);
+    // An amend may already have attached newly staged receipts to the new commit.
+    if let Some(current) = notes::read_receipts_for_commit(new_sha) {
+        remapped = notes::merge_payloads(current, remapped);
+    }
 
     if let Err(e) = write_note(new_sha, &remapped) {
         eprintln!(
@@ -50,6 +54,12 @@ pub fn remap(old_sha: &str, new_sha: &str) {
         .stdout(Stdio::null())
         .stderr(Stdio::null())
         .status();
+
+    // Receipts still waiting on their acceptance stats (the commit was
+    // amended before its finalize ran) are scored against the new commit.
+    if !remapped.pending_finalize.is_empty() {
+        crate::commands::finalize::spawn_detached(new_sha);
+    }
 }
 
 /// Adjust `file_mappings` hunk line numbers to account for context changes between
@@ -206,6 +216,7 @@ fn write_note(sha: &str, payload: &NotePayload) -> Result<(), String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::receipt::Receipt;
 
     #[test]
     fn test_parse_hunk_header() {
@@ -224,4 +235,28 @@ mod tests {
         // net delta = 5 - 2 = 3 added lines, apply at line 15
         assert_eq!(offsets[0], (15, 3));
     }
+
+    fn payload(ids: &[&str]) -> NotePayload {
+        let receipts = ids
+            .iter()
+            .map(|id| Receipt::test(id).with_model("m").with_user("dev"))
+            .collect();
+        NotePayload::new(receipts)
+    }
+
+    #[test]
+    fn test_merge_amended_payloads() {
+        // Receipts staged for the amend plus the ones the amended commit had.
+        let mut older = payload(&["a", "b"]);
+        older.pull_request = Some(
+            serde_json::from_value(serde_json::json!({
+                "number": 7
+            }))
+            .unwrap(),
+        );
+        let merged = notes::merge_payloads(payload(&["b", "c"]), older);
+        let ids: Vec<&str> = merged.receipts.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, vec!["b", "c", "a"]);
+        assert_eq!(merged.pull_request.map(|p| p.number), Some(7));
+    }
 }
@@ -1,10 +1,10 @@
-use crate::commands::staging;
-use crate::core::{config, pricing, receipt::Receipt, redact, transcript, util};
+use crate::commands::{acceptance_backfill, staging};
+use crate::core::{config, environment, pricing, receipt::Receipt, redact, transcript, util};
 use chrono::Utc;
 use sha2::{Digest, Sha256};
 use transcript::{extract_agents_spawned, extract_mcp_servers, extract_tools_used};
 
-pub fn run(session_path: &str, provider: Option<&str>) {
+pub fn run(session_path: &str, provider: Option<&str>, force: bool) {
     let provider = provider.unwrap_or("claude");
 
     let parsed = match transcript::parse_claude_jsonl(session_path) {
@@ -49,6 +49,7 @@ pub fn run(session_path: &str, provider: Option<&str>) {
             transcript::Message::User { text, .. } => text.len(),
             transcript::Message::Assistant { text, .. } => text.len(),
             transcript::Message::ToolUse { .. } => 0,
+            transcript::Message::Thinking { text } => text.len(),
         })
         .sum();
     let estimated_tokens = pricing::estimate_tokens_from_chars(total_chars);
@@ -84,10 +85,18 @@ pub fn run(session_path: &str, provider: Option<&str>) {
             blob_hash: None,
             additions: 0,
             deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
         })
         .collect();
 
-    let receipt = Receipt {
+    let mcp_servers = extract_mcp_servers(&parsed.transcript);
+    let mut receipt = Receipt {
         id: Receipt::new_id(),
         provider: provider.to_string(),
         model: model.clone(),
@@ -121,7 +130,13 @@ pub fn run(session_path: &str, provider: Option<&str>) {
         total_additions: 0,
         total_deletions: 0,
         tools_used: extract_tools_used(&parsed.transcript),
-        mcp_servers: extract_mcp_servers(&parsed.transcript),
+        mcp_servers: mcp_servers.clone(),
+        environment: environment::capture(
+            parsed.agent_version.as_deref(),
+            &parsed.settings,
+            &mcp_servers,
+            &cwd,
+        ),
         agents_spawned: extract_agents_spawned(&parsed.transcript),
         subagent_activities: vec![],
         concurrent_tool_calls: None,
@@ -136,9 +151,24 @@ pub fn run(session_path: &str, provider: Option<&str>) {
         accepted_lines: None,
         overridden_lines: None,
         prompt_quality: Some(crate::core::prompt_eval::evaluate(&prompt_summary)),
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated: true,
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
     };
+    acceptance_backfill::backfill(&mut receipt, Some(&parsed.transcript));
 
-    staging::upsert_receipt(&receipt);
+    let mut dedup = staging::ImportDeduper::new(force);
+    if !dedup.stage(&receipt) {
+        dedup.report("[BlamePrompt]");
+        return;
+    }
     let receipt_count = 1;
 
     println!(
@@ -150,5 +180,11 @@ pub fn run(session_path: &str, provider: Option<&str>) {
     println!("  Messages: {}", message_count);
     println!("  Files: {}", parsed.files_modified.join(", "));
     println!("  Est. cost: ${:.4}", cost);
+    if let (Some(accepted), Some(overridden)) = (receipt.accepted_lines, receipt.overridden_lines) {
+        println!(
+            "  Acceptance (estimated): {} accepted, {} overridden",
+            accepted, overridden
+        );
+    }
     println!("\nReceipts added to staging. They will be attached on next commit.");
 }
@@ -0,0 +1,309 @@
+/// `blameprompt recover`: attach receipts stranded in staging to the commits
+/// they belong to.
+///
+/// A commit made while the post-commit hook did not run (`git commit
+/// --no-verify` with hooks chained through other tools, a GUI client, a
+/// hooks path switched off) leaves its receipts staged, and the next attach
+/// would put them on the wrong commit. Staged receipts are matched against
+/// recent commits on HEAD: a commit that introduced a blob the AI wrote is an
+/// exact match; otherwise the first commit after the receipt that touched its
+/// files. The plan is shown and confirmed before any note is written.
+use crate::commands::staging::{self, StagingData};
+use crate::core::receipt::{NotePayload, Receipt};
+use crate::core::util;
+use crate::git::notes;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::io::{IsTerminal, Write};
+use std::process::Command;
+
+/// How far back in HEAD's history stranded receipts are looked for.
+const RECENT_COMMITS: usize = 100;
+/// Clock skew tolerated between a receipt and the commit that holds it.
+const SKEW_SECONDS: i64 = 60;
+
+/// A commit on HEAD and the blobs it wrote, by path.
+#[derive(Debug, Clone)]
+pub struct CommitInfo {
+    pub sha: String,
+    pub time: DateTime<Utc>,
+    pub subject: String,
+    /// (path, new blob) for each file the commit added or modified.
+    pub changes: Vec<(String, String)>,
+}
+
+/// Staged receipts that belong to one commit.
+#[derive(Debug)]
+pub struct Placement {
+    pub commit: String,
+    pub subject: String,
+    /// Every receipt here matched by blob hash, not just by time and file.
+    pub exact: bool,
+    pub receipts: Vec<Receipt>,
+}
+
+/// Where each of `receipts` belongs among `commits` (oldest first). Receipts
+/// no commit accounts for are left out: they are still waiting for theirs.
+pub fn place(receipts: &[Receipt], commits: &[CommitInfo]) -> Vec<Placement> {
+    let mut placements: Vec<Placement> = Vec::new();
+    for r in receipts {
+        let since = r.timestamp - Duration::seconds(SKEW_SECONDS);
+        let candidates: Vec<&CommitInfo> = commits.iter().filter(|c| c.time >= since).collect();
+        let changes = r.all_file_changes();
+        let exact = candidates.iter().find(|c| {
+            changes.iter().any(|fc| {
+                fc.blob_hash.as_ref().is_some_and(|blob| {
+                    c.changes
+                        .iter()
+                        .any(|(path, b)| b == blob && util::paths_match(path, &fc.path))
+                })
+            })
+        });
+        let by_time = || {
+            candidates.iter().find(|c| {
+                changes.iter().any(|fc| {
+                    c.changes
+                        .iter()
+                        .any(|(path, _)| util::paths_match(path, &fc.path))
+                })
+            })
+        };
+        let Some(commit) = exact.or_else(by_time) else {
+            continue;
+        };
+        match placements.iter_mut().find(|p| p.commit == commit.sha) {
+            Some(p) => {
+                p.exact &= exact.is_some();
+                p.receipts.push(r.clone());
+            }
+            None => placements.push(Placement {
+                commit: commit.sha.clone(),
+                subject: commit.subject.clone(),
+                exact: exact.is_some(),
+                receipts: vec![r.clone()],
+            }),
+        }
+    }
+    let order = |sha: &str| commits.iter().position(|c| c.sha == sha);
+    placements.sort_by_key(|p| order(&p.commit));
+    placements
+}
+
+/// The last `RECENT_COMMITS` commits on HEAD, oldest first.
+pub fn recent_commits() -> Vec<CommitInfo> {
+    let output = Command::new("git")
+        .args([
+            "log",
+            "-n",
+            &RECENT_COMMITS.to_string(),
+            "--raw",
+            "--no-abbrev",
+            "--no-renames",
+            "--format=%x1e%H|%ct|%s",
+            "HEAD",
+        ])
+        .output();
+    let Some(output) = output.ok().filter(|o| o.status.success()) else {
+        return Vec::new();
+    };
+    let mut commits: Vec<CommitInfo> = String::from_utf8_lossy(&output.stdout)
+        .split('\x1e')
+        .filter_map(parse_log_record)
+        .collect();
+    commits.reverse();
+    commits
+}
+
+fn parse_log_record(record: &str) -> Option<CommitInfo> {
+    let mut lines = record.lines();
+    let mut header = lines.next()?.splitn(3, '|');
+    let sha = header.next()?.to_string();
+    let time = Utc
+        .timestamp_opt(header.next()?.parse().ok()?, 0)
+        .single()?;
+    let subject = header.next().unwrap_or_default().to_string();
+    // `:100644 100644 <old> <new> M\tpath`
+    let changes = lines
+        .filter_map(|l| {
+            let (meta, path) = l.strip_prefix(':')?.split_once('\t')?;
+            let fields: Vec<&str> = meta.split_whitespace().collect();
+            (fields.len() == 5 && fields[4] != "D")
+                .then(|| (path.to_string(), fields[3].to_string()))
+        })
+        .collect();
+    Some(CommitInfo {
+        sha,
+        time,
+        subject,
+        changes,
+    })
+}
+
+/// Staged receipts that an earlier commit than HEAD introduced, by blob
+/// hash. `attach` keeps these staged instead of putting them on HEAD.
+pub fn stranded_before_head(receipts: &[Receipt]) -> Vec<String> {
+    let commits = recent_commits();
+    let Some(head) = commits.last() else {
+        return Vec::new();
+    };
+    place(receipts, &commits)
+        .into_iter()
+        .filter(|p| p.exact && p.commit != head.sha)
+        .flat_map(|p| p.receipts.into_iter().map(|r| r.id))
+        .collect()
+}
+
+/// One-line hint when staged receipts belong to commits already made; for
+/// commands people run every day.
+pub fn warn_if_stranded() {
+    let data = staging::read_all_staging();
+    if data.receipts.is_empty() {
+        return;
+    }
+    let placements = place(&data.receipts, &recent_commits());
+    let stranded: usize = placements.iter().map(|p| p.receipts.len()).sum();
+    if stranded > 0 {
+        eprintln!(
+            "[BlamePrompt] {} staged receipt(s) belong to {} commit(s) already made (post-commit hook skipped?). Attach them with: blameprompt recover",
+            stranded,
+            placements.len()
+        );
+    }
+}
+
+pub fn run(yes: bool) -> Result<(), String> {
+    let data = staging::read_all_staging();
+    if data.receipts.is_empty() {
+        println!("[BlamePrompt] No staged receipts.");
+        return Ok(());
+    }
+    let placements = place(&data.receipts, &recent_commits());
+    if placements.is_empty() {
+        println!(
+            "[BlamePrompt] {} staged receipt(s), none matching a commit already made.",
+            data.receipts.len()
+        );
+        return Ok(());
+    }
+
+    println!("Staged receipts that belong to earlier commits:");
+    for p in &placements {
+        println!(
+            "  {}  {}  {} receipt(s), matched by {}",
+            util::short_sha(&p.commit),
+            p.subject,
+            p.receipts.len(),
+            if p.exact {
+                "blob hash"
+            } else {
+                "time and file"
+            }
+        );
+    }
+    if !yes && !confirm()? {
+        println!("Nothing written.");
+        return Ok(());
+    }
+
+    let mut attached: Vec<Receipt> = Vec::new();
+    for p in &placements {
+        let mut payload = notes::read_receipts_for_commit(&p.commit)
+            .unwrap_or_else(|| NotePayload::new(Vec::new()));
+        for r in &p.receipts {
+            if !payload.receipts.iter().any(|e| e.id == r.id) {
+                payload.receipts.push(r.clone());
+            }
+        }
+        notes::write_receipts_for_commit(&p.commit, &payload)?;
+        attached.extend(p.receipts.iter().cloned());
+    }
+
+    let remaining: Vec<Receipt> = data
+        .receipts
+        .into_iter()
+        .filter(|r| !attached.iter().any(|a| a.id == r.id))
+        .collect();
+    staging::record_committed_prompts(&attached);
+    staging::clear_staging();
+    if !remaining.is_empty() {
+        staging::write_staging_data_in(
+            &StagingData {
+                receipts: remaining,
+            },
+            ".",
+        );
+    }
+    println!(
+        "[BlamePrompt] {} receipt(s) attached to {} commit(s).",
+        attached.len(),
+        placements.len()
+    );
+    Ok(())
+}
+
+fn confirm() -> Result<bool, String> {
+    if !std::io::stdin().is_terminal() {
+        return Err("Not a terminal: pass --yes to attach without asking.".to_string());
+    }
+    print!("Attach them? [y/N] ");
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    std::io::stdin()
+        .read_line(&mut answer)
+        .map_err(|e| e.to_string())?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::FileChange;
+
+    fn receipt(id: &str, file: &str, blob: Option<&str>, ts: &str) -> Receipt {
+        let mut r = Receipt::test(id)
+            .with_model("m")
+            .with_timestamp(ts)
+            .with_change(FileChange {
+                blob_hash: blob.map(String::from),
+                ..FileChange::test(file, (1, 2), 0, 0)
+            });
+        r.file_path = file.to_string();
+        r
+    }
+
+    #[test]
+    fn test_place_stranded_receipts() {
+        let log = "\x1ea1|1767261600|first\n\n:100644 100644 0000 b1 M\tsrc/a.rs\n\
+                   \x1ea2|1767265200|second\n\n:100644 100644 b1 b2 M\tsrc/a.rs\n:000000 100644 0000 c1 A\tsrc/b.rs\n\
+                   \x1ea3|1767268800|third\n\n:100644 000000 c1 0000 D\tsrc/b.rs\n";
+        let commits: Vec<CommitInfo> = log.split('\x1e').filter_map(parse_log_record).collect();
+        assert_eq!(commits.len(), 3);
+        assert_eq!(commits[1].changes.len(), 2);
+        assert!(commits[2].changes.is_empty());
+
+        // 2026-01-01T10:00Z is a1's time; blob b2 was introduced by a2.
+        let receipts = vec![
+            receipt(
+                "exact",
+                "/repo/src/a.rs",
+                Some("b2"),
+                "2026-01-01T09:59:30Z",
+            ),
+            receipt("by-time", "src/b.rs", None, "2026-01-01T10:30:00Z"),
+            receipt("pending", "src/c.rs", Some("zz"), "2026-01-01T09:00:00Z"),
+            receipt("too-late", "src/a.rs", None, "2026-01-02T00:00:00Z"),
+        ];
+        let placements = place(&receipts, &commits);
+        assert_eq!(placements.len(), 1);
+        assert_eq!(placements[0].commit, "a2");
+        assert!(!placements[0].exact);
+        let ids: Vec<&str> = placements[0]
+            .receipts
+            .iter()
+            .map(|r| r.id.as_str())
+            .collect();
+        assert_eq!(ids, ["exact", "by-time"]);
+
+        let exact = place(&receipts[..1], &commits);
+        assert!(exact[0].exact);
+    }
+}
@@ -0,0 +1,251 @@
+/// `blameprompt release-summary <tag>`: release-level provenance summary.
+///
+/// Aggregates the receipts of every commit between the previous tag (or
+/// `--from`) and `<tag>`, and stores the result as a note on the tag under
+/// `refs/notes/blameprompt-releases`. Release notes live apart from commit
+/// receipts, so a lightweight tag (which resolves to its commit) never
+/// overwrites that commit's note. `--upload` also attaches the summary to the
+/// GitHub Release for the tag as `blameprompt-provenance-<tag>.json`.
+use crate::commands::audit::{self, AuditEntry};
+use crate::commands::github;
+use crate::commands::grouping::{self, Group, GroupBy};
+use crate::git::storage;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fmt::Write;
+use std::path::Path;
+use std::process::Command;
+
+pub const RELEASE_NOTES_REF: &str = "refs/notes/blameprompt-releases";
+
+#[derive(Debug, Serialize)]
+pub struct ReleaseSummary {
+    pub tag: String,
+    pub previous_tag: Option<String>,
+    pub target_commit: String,
+    pub generated_at: DateTime<Utc>,
+    pub commits: usize,
+    pub ai_commits: usize,
+    pub ai_commit_pct: f64,
+    pub receipts: usize,
+    pub sessions: usize,
+    pub ai_lines: u32,
+    pub cost_usd: f64,
+    pub by_model: Vec<Group>,
+    pub by_author: Vec<Group>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pull_requests: Vec<u32>,
+    pub ai_commit_shas: Vec<String>,
+}
+
+pub fn run(tag: &str, from: Option<&str>, format: &str, upload: bool) -> Result<(), String> {
+    let tag_object = git(&[
+        "rev-parse",
+        "--verify",
+        "--quiet",
+        &format!("refs/tags/{}", tag),
+    ])
+    .ok_or_else(|| format!("Unknown tag: {}", tag))?;
+    let target = git(&["rev-parse", &format!("{}^{{commit}}", tag_object)])
+        .ok_or_else(|| format!("Tag {} does not point at a commit", tag))?;
+    let previous = match from {
+        Some(f) => Some(f.to_string()),
+        None => git(&["describe", "--tags", "--abbrev=0", &format!("{}^", target)]),
+    };
+    let range = match &previous {
+        Some(p) => format!("{}..{}", p, target),
+        None => target.clone(),
+    };
+    let commits = git(&["rev-list", "--count", &range])
+        .and_then(|n| n.parse().ok())
+        .ok_or_else(|| format!("Cannot list commits in {}", range))?;
+    let entries = audit::collect_range_entries(&range)?;
+
+    let summary = summarize(tag, previous.as_deref(), &target, commits, &entries);
+    let json = serde_json::to_string_pretty(&summary).map_err(|e| e.to_string())?;
+    storage::git_with_stdin(
+        Path::new("."),
+        &[
+            "notes",
+            "--ref",
+            RELEASE_NOTES_REF,
+            "add",
+            "-f",
+            "-F",
+            "-",
+            &tag_object,
+        ],
+        &json,
+    )
+    .map_err(|e| format!("git notes add failed: {}", e))?;
+
+    match format {
+        "json" => println!("{}", json),
+        _ => print!("{}", render_markdown(&summary)),
+    }
+    eprintln!(
+        "[BlamePrompt] Summary attached to tag {} ({}). Share it with: git push origin {}",
+        tag, RELEASE_NOTES_REF, RELEASE_NOTES_REF
+    );
+
+    if upload {
+        let repo = github::detect_repo_slug()
+            .ok_or("Cannot detect the GitHub repository from the origin remote")?;
+        let token = std::env::var("GITHUB_TOKEN")
+            .map_err(|_| "GITHUB_TOKEN env var not set. Export it and retry.".to_string())?;
+        let name = format!("blameprompt-provenance-{}.json", tag.replace('/', "-"));
+        let url = github::upload_release_asset(&repo, tag, &name, &json, &token)?;
+        eprintln!("[BlamePrompt] Uploaded {}", url);
+    }
+    Ok(())
+}
+
+/// Release totals for the AI entries of a range that has `commits` commits.
+pub fn summarize(
+    tag: &str,
+    previous_tag: Option<&str>,
+    target_commit: &str,
+    commits: usize,
+    entries: &[AuditEntry],
+) -> ReleaseSummary {
+    let receipts = entries.iter().map(|e| e.receipts.len()).sum();
+    let sessions: HashSet<&str> = entries
+        .iter()
+        .flat_map(|e| e.receipts.iter().map(|r| r.session_id.as_str()))
+        .collect();
+    let mut pull_requests: Vec<u32> = entries
+        .iter()
+        .filter_map(|e| e.pull_request.as_ref().map(|pr| pr.number))
+        .collect();
+    pull_requests.sort_unstable();
+    pull_requests.dedup();
+
+    ReleaseSummary {
+        tag: tag.to_string(),
+        previous_tag: previous_tag.map(String::from),
+        target_commit: target_commit.to_string(),
+        generated_at: Utc::now(),
+        commits,
+        ai_commits: entries.len(),
+        ai_commit_pct: if commits > 0 {
+            entries.len() as f64 / commits as f64 * 100.0
+        } else {
+            0.0
+        },
+        receipts,
+        sessions: sessions.len(),
+        ai_lines: entries
+            .iter()
+            .flat_map(|e| &e.receipts)
+            .map(grouping::receipt_lines)
+            .sum(),
+        cost_usd: entries.iter().map(|e| e.total_cost_usd).sum(),
+        by_model: grouping::group(entries, GroupBy::Model),
+        by_author: grouping::group(entries, GroupBy::Author),
+        pull_requests,
+        ai_commit_shas: entries.iter().map(|e| e.commit_sha.clone()).collect(),
+    }
+}
+
+pub fn render_markdown(s: &ReleaseSummary) -> String {
+    let mut md = String::new();
+    writeln!(md, "# AI provenance for {}\n", s.tag).ok();
+    writeln!(
+        md,
+        "- Commits since {}: {} ({} with AI receipts, {:.1}%)",
+        s.previous_tag.as_deref().unwrap_or("the first commit"),
+        s.commits,
+        s.ai_commits,
+        s.ai_commit_pct
+    )
+    .ok();
+    writeln!(
+        md,
+        "- Receipts: {} in {} session(s), {} AI lines, ${:.2} estimated cost",
+        s.receipts, s.sessions, s.ai_lines, s.cost_usd
+    )
+    .ok();
+    if !s.pull_requests.is_empty() {
+        let prs: Vec<String> = s.pull_requests.iter().map(|n| format!("#{}", n)).collect();
+        writeln!(md, "- Pull requests: {}", prs.join(", ")).ok();
+    }
+    for (title, groups) in [("Model", &s.by_model), ("Author", &s.by_author)] {
+        if groups.is_empty() {
+            continue;
+        }
+        writeln!(md, "\n| {} | Commits | Receipts | AI lines | Cost |", title).ok();
+        writeln!(md, "|---|---------|----------|----------|------|").ok();
+        for g in groups {
+            writeln!(
+                md,
+                "| {} | {} | {} | {} | ${:.2} |",
+                g.key, g.commits, g.receipts, g.ai_lines, g.cost_usd
+            )
+            .ok();
+        }
+    }
+    md
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::{PullRequestLink, Receipt};
+
+    fn entry(sha: &str, model: &str, cost: f64, pr: Option<u32>) -> AuditEntry {
+        let mut r = Receipt::test(&format!("r-{}", sha))
+            .with_model(model)
+            .with_session(&format!("s-{}", sha))
+            .with_cost(cost)
+            .with_timestamp("2026-05-01T00:00:00Z")
+            .with_user("dev");
+        r.total_additions = 10;
+        AuditEntry {
+            commit_sha: sha.to_string(),
+            commit_date: String::new(),
+            commit_author: "Dev <dev@example.com>".to_string(),
+            commit_message: String::new(),
+            receipts: vec![r],
+            total_ai_lines: 10,
+            total_cost_usd: cost,
+            code_origin: None,
+            pull_request: pr.map(|number| PullRequestLink {
+                number,
+                url: None,
+                merge_commit: None,
+                comment_id: None,
+            }),
+        }
+    }
+
+    #[test]
+    fn test_summarize_release() {
+        let entries = vec![
+            entry("c3", "opus", 0.5, Some(12)),
+            entry("c2", "opus", 0.25, Some(12)),
+            entry("c1", "sonnet", 0.25, None),
+        ];
+        let s = summarize("v1.2.0", Some("v1.1.0"), "c3", 8, &entries);
+        assert_eq!((s.ai_commits, s.receipts, s.sessions), (3, 3, 3));
+        assert_eq!(s.ai_lines, 30);
+        assert!((s.ai_commit_pct - 37.5).abs() < 1e-9);
+        assert!((s.cost_usd - 1.0).abs() < 1e-9);
+        assert_eq!(s.by_model[0].key, "opus");
+        assert_eq!(s.by_model[0].commits, 2);
+        assert_eq!(s.pull_requests, vec![12]);
+
+        let md = render_markdown(&s);
+        assert!(md.contains("- Commits since v1.1.0: 8 (3 with AI receipts, 37.5%)"));
+        assert!(md.contains("| opus | 2 | 2 | 20 | $0.75 |"));
+    }
+}
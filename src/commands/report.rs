@@ -1,5 +1,10 @@
+use crate::commands::analytics;
+use crate::commands::anonymize_paths;
 use crate::commands::audit;
 use crate::commands::audit::relative_path;
+use crate::commands::grouping::{self, GroupBy};
+use crate::commands::rework::{self, ReworkLink};
+use crate::core::config;
 use crate::core::prompt_eval;
 use crate::core::receipt::Receipt;
 use crate::core::{model_classifier, redact, session_stats};
@@ -22,6 +27,7 @@ pub fn generate_report(
     to: Option<&str>,
     author: Option<&str>,
     include_uncommitted: bool,
+    anonymize_paths: bool,
 ) -> Result<(), String> {
     let mut entries = audit::collect_all_entries(from, to, author, include_uncommitted)?;
 
@@ -33,6 +39,15 @@ pub fn generate_report(
         }
     }
 
+    // Rework looks files up in git, so it runs before paths are anonymized.
+    let cfg = config::load_config();
+    let mut rework = rework::analyze(&entries, &cfg.metrics)?;
+    if anonymize_paths {
+        let salt = &cfg.analytics.pseudonym_salt;
+        anonymize_paths::anonymize_entries(&mut entries, salt);
+        anonymize_paths::anonymize_rework(&mut rework.links, salt);
+    }
+
     // Collect all receipts
     let all_receipts: Vec<&Receipt> = entries.iter().flat_map(|e| &e.receipts).collect();
 
@@ -75,6 +90,9 @@ pub fn generate_report(
     // Section 4: User Contribution Matrix
     write_user_contributions(&mut md, &all_receipts);
 
+    // Section 4b: Pull requests (only when commits are linked)
+    write_pull_requests(&mut md, &entries);
+
     // Section 5: Time & Generation Speed Analysis
     write_time_analysis(&mut md, &all_receipts);
 
@@ -87,16 +105,22 @@ pub fn generate_report(
     // Section 8: File-Level Heatmap
     write_file_heatmap(&mut md, &all_receipts);
 
-    // Section 9: Session Deep Dive
+    // Section 9: AI code that required rework
+    write_rework(&mut md, &rework.links);
+
+    // Section 10: Session Deep Dive
     write_session_analysis(&mut md, &all_receipts);
 
-    // Section 10: Prompt Details
+    // Section 10b: Human decisions (oversight evidence)
+    write_human_decisions(&mut md, &all_receipts);
+
+    // Section 11: Prompt Details
     write_prompt_details(&mut md, &entries);
 
-    // Section 11: Prompt Effectiveness
+    // Section 12: Prompt Effectiveness
     write_prompt_effectiveness(&mut md, &all_receipts);
 
-    // Section 12: Recommendations
+    // Section 13: Recommendations
     write_recommendations(&mut md, &all_receipts, &classifications, &security_findings);
 
     // Footer
@@ -180,6 +204,15 @@ fn write_executive_summary(
     )
     .ok();
     writeln!(md, "| Total AI-generated lines | {} |", total_lines).ok();
+    let share = analytics::line_share(entries, &config::load_config().analytics.line_counting);
+    for (label, share) in share.ordered() {
+        writeln!(
+            md,
+            "| AI share of added lines ({}) | {:.1}% ({} of {}) |",
+            label, share.ai_share_pct, share.ai_lines, share.total_lines
+        )
+        .ok();
+    }
     writeln!(md, "| Estimated total AI cost | ${:.2} |", total_cost).ok();
     writeln!(md, "| Unique contributors | {} |", unique_users.len()).ok();
     writeln!(md, "| AI tools used | {} |", tools.join(", ")).ok();
@@ -325,34 +358,22 @@ fn write_cost_analysis(md: &mut String, receipts: &[&Receipt], entries: &[audit:
     }
     writeln!(md).ok();
 
-    // Cost by model
-    let mut by_model: HashMap<String, (u32, f64)> = HashMap::new();
-    for r in receipts {
-        let entry = by_model.entry(r.model.clone()).or_insert((0, 0.0));
-        entry.0 += 1;
-        entry.1 += r.cost_usd;
-    }
+    let models = grouping::group_receipts(receipts, GroupBy::Model);
 
     writeln!(md, "### Cost by Model").ok();
     writeln!(md, "| Model | Receipts | Est. Cost | % of Total |").ok();
     writeln!(md, "|-------|----------|-----------|------------|").ok();
-    let mut models: Vec<_> = by_model.into_iter().collect();
-    models.sort_by(|a, b| {
-        b.1 .1
-            .partial_cmp(&a.1 .1)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    for (model, (count, cost)) in &models {
-        let display = model_classifier::display_name(model);
+    for g in &models {
+        let display = model_classifier::display_name(&g.key);
         let pct = if total_cost > 0.0 {
-            cost / total_cost * 100.0
+            g.cost_usd / total_cost * 100.0
         } else {
             0.0
         };
         writeln!(
             md,
             "| {} | {} | ${:.2} | {:.1}% |",
-            display, count, cost, pct
+            display, g.receipts, g.cost_usd, pct
         )
         .ok();
     }
@@ -362,25 +383,38 @@ fn write_cost_analysis(md: &mut String, receipts: &[&Receipt], entries: &[audit:
 fn write_user_contributions(md: &mut String, receipts: &[&Receipt]) {
     writeln!(md, "## User Contributions\n").ok();
 
-    let mut by_user: HashMap<String, (u32, u32, f64)> = HashMap::new();
-    for r in receipts {
-        let lines = r.total_lines_changed();
-        let entry = by_user.entry(r.user.clone()).or_insert((0, 0, 0.0));
-        entry.0 += 1; // sessions
-        entry.1 += lines;
-        entry.2 += r.cost_usd;
-    }
-
     writeln!(md, "| User | AI Sessions | AI Lines | Est. Cost |").ok();
     writeln!(md, "|------|-------------|----------|-----------|").ok();
-    let mut users: Vec<_> = by_user.into_iter().collect();
-    users.sort_by(|a, b| {
-        b.1 .2
-            .partial_cmp(&a.1 .2)
-            .unwrap_or(std::cmp::Ordering::Equal)
-    });
-    for (user, (sessions, lines, cost)) in &users {
-        writeln!(md, "| {} | {} | {} | ${:.2} |", user, sessions, lines, cost).ok();
+    for g in grouping::group_receipts(receipts, GroupBy::Author) {
+        writeln!(
+            md,
+            "| {} | {} | {} | ${:.2} |",
+            g.key, g.sessions, g.ai_lines, g.cost_usd
+        )
+        .ok();
+    }
+    writeln!(md).ok();
+}
+
+fn write_pull_requests(md: &mut String, entries: &[audit::AuditEntry]) {
+    let groups = audit::group_by_pull_request(entries);
+    if groups.is_empty() {
+        return;
+    }
+    writeln!(md, "## Pull Requests\n").ok();
+    writeln!(md, "| PR | Commits | Receipts | AI Lines | Est. Cost |").ok();
+    writeln!(md, "|----|---------|----------|----------|-----------|").ok();
+    for g in &groups {
+        writeln!(
+            md,
+            "| {} | {} | {} | {} | ${:.2} |",
+            audit::pull_request_markdown(g.link),
+            g.commits,
+            g.receipts,
+            g.ai_lines,
+            g.cost_usd
+        )
+        .ok();
     }
     writeln!(md).ok();
 }
@@ -677,6 +711,45 @@ fn write_file_heatmap(md: &mut String, receipts: &[&Receipt]) {
     writeln!(md).ok();
 }
 
+fn write_rework(md: &mut String, links: &[ReworkLink]) {
+    if links.is_empty() {
+        return;
+    }
+    writeln!(md, "## AI Code That Required Rework\n").ok();
+    writeln!(
+        md,
+        "Follow-up commits that reverted, fixed or heavily rewrote AI-generated lines soon after they landed.\n"
+    )
+    .ok();
+    writeln!(
+        md,
+        "| AI Commit | Prompt | File | Follow-up | Kind | Lines Changed | Days Later |"
+    )
+    .ok();
+    writeln!(
+        md,
+        "|-----------|--------|------|-----------|------|---------------|------------|"
+    )
+    .ok();
+    for link in links {
+        writeln!(
+            md,
+            "| `{}` | {} | {} | `{}` {} | {} | {}/{} | {:.1} |",
+            link.ai_commit,
+            link.prompt.replace('|', "\\|").replace('\n', " "),
+            link.file,
+            link.follow_up,
+            link.follow_up_subject.replace('|', "\\|"),
+            link.kind,
+            link.lines_modified,
+            link.ai_lines,
+            link.days_after
+        )
+        .ok();
+    }
+    writeln!(md).ok();
+}
+
 fn write_session_analysis(md: &mut String, receipts: &[&Receipt]) {
     writeln!(md, "## Session Analysis\n").ok();
 
@@ -769,6 +842,39 @@ fn write_session_analysis(md: &mut String, receipts: &[&Receipt]) {
     writeln!(md).ok();
 }
 
+fn write_human_decisions(md: &mut String, receipts: &[&Receipt]) {
+    let d = analytics::decision_stats(receipts);
+    writeln!(md, "## Human Oversight: Decisions\n").ok();
+    if d.questions == 0 {
+        writeln!(
+            md,
+            "The AI did not stop to ask for a decision in any of the {} session(s).\n",
+            d.sessions
+        )
+        .ok();
+        return;
+    }
+    writeln!(md, "| Metric | Value |").ok();
+    writeln!(md, "|--------|-------|").ok();
+    writeln!(md, "| Questions asked by the AI | {} |", d.questions).ok();
+    writeln!(md, "| Questions answered by a human | {} |", d.answered).ok();
+    writeln!(
+        md,
+        "| Sessions with explicit human decisions | {} of {} ({:.1}%) |",
+        d.sessions_with_decisions, d.sessions, d.sessions_with_decisions_pct
+    )
+    .ok();
+    writeln!(md).ok();
+
+    writeln!(md, "### Most Common Decision Categories").ok();
+    writeln!(md, "| Category | Questions | Answered |").ok();
+    writeln!(md, "|----------|-----------|----------|").ok();
+    for c in d.categories.iter().take(10) {
+        writeln!(md, "| {} | {} | {} |", c.header, c.questions, c.answered).ok();
+    }
+    writeln!(md).ok();
+}
+
 fn write_prompt_details(md: &mut String, entries: &[audit::AuditEntry]) {
     writeln!(md, "## Prompt Details\n").ok();
     writeln!(md, "Full prompt context for each AI-assisted change.\n").ok();
@@ -832,6 +938,9 @@ fn write_prompt_details(md: &mut String, entries: &[audit::AuditEntry]) {
             if !r.mcp_servers.is_empty() {
                 writeln!(md, "| MCP Servers | {} |", r.mcp_servers.join(", ")).ok();
             }
+            if let Some(env) = &r.environment {
+                writeln!(md, "| Environment | {} |", env.describe(&r.provider)).ok();
+            }
             if !r.agents_spawned.is_empty() {
                 writeln!(md, "| Agents Spawned | {} |", r.agents_spawned.join("; ")).ok();
             }
@@ -932,8 +1041,15 @@ fn write_prompt_details(md: &mut String, entries: &[audit::AuditEntry]) {
                 };
                 writeln!(
                     md,
-                    "- Acceptance rate: {:.0}% ({} accepted, {} overridden by human)",
-                    accept_pct, accepted, overridden
+                    "- Acceptance rate: {:.0}% ({} accepted, {} overridden by human){}",
+                    accept_pct,
+                    accepted,
+                    overridden,
+                    if r.acceptance_estimated {
+                        ", estimated from an imported session"
+                    } else {
+                        ""
+                    }
                 )
                 .ok();
             } else if let Some(accepted) = r.accepted_lines {
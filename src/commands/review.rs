@@ -0,0 +1,337 @@
+/// `blameprompt review`: a shared queue of AI commits awaiting human review.
+///
+/// Every commit on HEAD with receipts but no review annotation is in the
+/// queue. The reviewer steps through them oldest first, seeing the prompts
+/// and the diff, and answers with one key: `r` reviewed, `f` flag (with an
+/// optional comment), `s` skip, `q` quit. Answers are stored as JSON notes
+/// under `refs/notes/blameprompt-reviews`, apart from the receipts, and travel
+/// with `blameprompt push` / `pull`, so the whole team works one queue.
+use crate::core::util;
+use crate::git::{notes, storage};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::io::{BufRead, Write};
+use std::path::Path;
+use std::process::Command;
+
+pub const REVIEWS_REF: &str = "refs/notes/blameprompt-reviews";
+/// Side ref origin's reviews are fetched into before merging.
+const REMOTE_REVIEWS_REF: &str = "refs/notes/blameprompt-reviews-remote";
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReviewStatus {
+    Reviewed,
+    Flagged,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReviewAnnotation {
+    pub status: ReviewStatus,
+    pub reviewer: String,
+    pub reviewed_at: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+enum Action {
+    Mark(ReviewStatus),
+    Skip,
+    Quit,
+}
+
+fn parse_action(input: &str) -> Option<Action> {
+    match input.trim().to_lowercase().as_str() {
+        "r" | "reviewed" => Some(Action::Mark(ReviewStatus::Reviewed)),
+        "f" | "flag" | "flagged" => Some(Action::Mark(ReviewStatus::Flagged)),
+        "s" | "skip" | "" => Some(Action::Skip),
+        "q" | "quit" => Some(Action::Quit),
+        _ => None,
+    }
+}
+
+pub fn run(list: bool, flagged: bool) -> Result<(), String> {
+    if let Err(e) = fetch_reviews() {
+        eprintln!(
+            "[BlamePrompt] Could not fetch reviews from origin ({}); using local state.",
+            e
+        );
+    }
+    let annotations = read_annotations();
+    let queue = queue(&ai_commits_on_head(), &annotations, flagged);
+    if queue.is_empty() {
+        println!(
+            "[BlamePrompt] Review queue is empty: every AI commit on HEAD has been {}.",
+            if flagged {
+                "reviewed"
+            } else {
+                "reviewed or flagged"
+            }
+        );
+        return Ok(());
+    }
+    if list {
+        println!("{} AI commit(s) awaiting review:", queue.len());
+        for sha in &queue {
+            let status = match annotations.get(sha) {
+                Some(a) if a.status == ReviewStatus::Flagged => " [flagged]",
+                _ => "",
+            };
+            println!(
+                "  {}  {}{}",
+                util::short_sha(sha),
+                git(&["log", "-1", "--format=%s (%an, %ad)", "--date=short", sha])
+                    .unwrap_or_default(),
+                status
+            );
+        }
+        return Ok(());
+    }
+
+    let reviewer = util::git_user();
+    let stdin = std::io::stdin();
+    let mut input = stdin.lock();
+    let mut marked = 0;
+    for (i, sha) in queue.iter().enumerate() {
+        show_commit(sha, i + 1, queue.len(), annotations.get(sha));
+        let action = loop {
+            print!("[r]eviewed  [f]lag  [s]kip  [q]uit > ");
+            std::io::stdout().flush().ok();
+            let mut line = String::new();
+            if input.read_line(&mut line).map_err(|e| e.to_string())? == 0 {
+                break Action::Quit;
+            }
+            match parse_action(&line) {
+                Some(a) => break a,
+                None => println!("Answer r, f, s or q."),
+            }
+        };
+        let status = match action {
+            Action::Mark(status) => status,
+            Action::Skip => continue,
+            Action::Quit => break,
+        };
+        let comment = if status == ReviewStatus::Flagged {
+            print!("Comment (optional) > ");
+            std::io::stdout().flush().ok();
+            let mut line = String::new();
+            input.read_line(&mut line).map_err(|e| e.to_string())?;
+            Some(line.trim().to_string()).filter(|c| !c.is_empty())
+        } else {
+            None
+        };
+        write_annotation(
+            sha,
+            &ReviewAnnotation {
+                status,
+                reviewer: reviewer.clone(),
+                reviewed_at: Utc::now(),
+                comment,
+            },
+        )?;
+        marked += 1;
+    }
+    if marked > 0 {
+        println!(
+            "[BlamePrompt] {} commit(s) marked. Share them with: blameprompt push",
+            marked
+        );
+    }
+    Ok(())
+}
+
+/// Commits still to review, oldest first: no annotation, or (with
+/// `include_flagged`) a flag that nobody has cleared yet.
+pub fn queue(
+    commits: &[String],
+    annotations: &HashMap<String, ReviewAnnotation>,
+    include_flagged: bool,
+) -> Vec<String> {
+    commits
+        .iter()
+        .filter(|sha| match annotations.get(*sha) {
+            None => true,
+            Some(a) => include_flagged && a.status == ReviewStatus::Flagged,
+        })
+        .cloned()
+        .collect()
+}
+
+/// Commits on HEAD that carry receipts, oldest first.
+fn ai_commits_on_head() -> Vec<String> {
+    let with_notes: HashSet<String> = notes::list_commits_with_notes().into_iter().collect();
+    git(&["rev-list", "--reverse", "HEAD"])
+        .unwrap_or_default()
+        .lines()
+        .filter(|sha| with_notes.contains(*sha))
+        .map(String::from)
+        .collect()
+}
+
+fn show_commit(sha: &str, n: usize, total: usize, previous: Option<&ReviewAnnotation>) {
+    println!(
+        "\n[{}/{}] ----------------------------------------",
+        n, total
+    );
+    let _ = Command::new("git")
+        .args([
+            "--no-pager",
+            "show",
+            "--stat",
+            "--patch",
+            "--format=fuller",
+            sha,
+        ])
+        .status();
+    if let Some(payload) = notes::read_receipts_for_commit(sha) {
+        println!("\nPrompts:");
+        for r in &payload.receipts {
+            let prompt = r.intent_summary.as_deref().unwrap_or(&r.prompt_summary);
+            println!(
+                "  [{}] {} · {}",
+                util::short_sha(&r.id),
+                r.model,
+                prompt.split_whitespace().collect::<Vec<_>>().join(" ")
+            );
+        }
+    }
+    if let Some(a) = previous {
+        println!(
+            "\nFlagged by {} on {}{}",
+            a.reviewer,
+            a.reviewed_at.format("%Y-%m-%d"),
+            a.comment
+                .as_deref()
+                .map(|c| format!(": {}", c))
+                .unwrap_or_default()
+        );
+    }
+}
+
+/// Review annotations by commit SHA.
+pub fn read_annotations() -> HashMap<String, ReviewAnnotation> {
+    let mut out = HashMap::new();
+    for line in git(&["notes", "--ref", REVIEWS_REF, "list"])
+        .unwrap_or_default()
+        .lines()
+    {
+        let Some((_, commit)) = line.split_once(' ') else {
+            continue;
+        };
+        if let Some(a) = git(&["notes", "--ref", REVIEWS_REF, "show", commit])
+            .and_then(|json| serde_json::from_str(&json).ok())
+        {
+            out.insert(commit.to_string(), a);
+        }
+    }
+    out
+}
+
+fn write_annotation(sha: &str, annotation: &ReviewAnnotation) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(annotation).map_err(|e| e.to_string())?;
+    storage::git_with_stdin(
+        Path::new("."),
+        &["notes", "--ref", REVIEWS_REF, "add", "-f", "-F", "-", sha],
+        &json,
+    )
+    .map(|_| ())
+    .map_err(|e| format!("git notes add failed: {}", e))
+}
+
+/// Fetch origin's reviews and merge them into the local ref; for a commit
+/// reviewed on both sides the local answer wins. No-op without an origin or
+/// when origin has no reviews yet.
+pub fn fetch_reviews() -> Result<(), String> {
+    if git(&["remote", "get-url", "origin"]).is_none() {
+        return Ok(());
+    }
+    let output = Command::new("git")
+        .args([
+            "fetch",
+            "--quiet",
+            "origin",
+            &format!("+{}:{}", REVIEWS_REF, REMOTE_REVIEWS_REF),
+        ])
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        if stderr.contains("couldn't find remote ref") {
+            return Ok(());
+        }
+        return Err(stderr.trim().to_string());
+    }
+    let remote = git(&["rev-parse", REMOTE_REVIEWS_REF]).ok_or("cannot read fetched reviews")?;
+    let Some(local) = git(&["rev-parse", "--verify", "--quiet", REVIEWS_REF]) else {
+        git(&["update-ref", REVIEWS_REF, &remote]).ok_or("cannot create reviews ref")?;
+        return Ok(());
+    };
+    if local == remote || is_ancestor(&remote, &local) {
+        return Ok(());
+    }
+    if is_ancestor(&local, &remote) {
+        git(&["update-ref", REVIEWS_REF, &remote, &local]).ok_or("cannot fast-forward reviews")?;
+        return Ok(());
+    }
+    git(&[
+        "notes",
+        "--ref",
+        REVIEWS_REF,
+        "merge",
+        "--quiet",
+        "-s",
+        "ours",
+        REMOTE_REVIEWS_REF,
+    ])
+    .map(|_| ())
+    .ok_or_else(|| "notes merge failed".to_string())
+}
+
+fn is_ancestor(ancestor: &str, descendant: &str) -> bool {
+    Command::new("git")
+        .args(["merge-base", "--is-ancestor", ancestor, descendant])
+        .status()
+        .map(|s| s.success())
+        .unwrap_or(false)
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_review_queue() {
+        let commits: Vec<String> = ["a", "b", "c"].iter().map(|s| s.to_string()).collect();
+        let annotation = |status| ReviewAnnotation {
+            status,
+            reviewer: "dev <dev@example.com>".to_string(),
+            reviewed_at: Utc::now(),
+            comment: None,
+        };
+        let mut annotations = HashMap::new();
+        annotations.insert("a".to_string(), annotation(ReviewStatus::Reviewed));
+        annotations.insert("b".to_string(), annotation(ReviewStatus::Flagged));
+        assert_eq!(queue(&commits, &annotations, false), vec!["c"]);
+        assert_eq!(queue(&commits, &annotations, true), vec!["b", "c"]);
+
+        assert_eq!(
+            parse_action(" F\n"),
+            Some(Action::Mark(ReviewStatus::Flagged))
+        );
+        assert_eq!(parse_action("\n"), Some(Action::Skip));
+        assert_eq!(parse_action("x"), None);
+
+        let json = serde_json::to_string(&annotation(ReviewStatus::Flagged)).unwrap();
+        assert!(json.contains("\"status\":\"flagged\""));
+    }
+}
@@ -0,0 +1,351 @@
+/// Revert and fix-up correlation.
+///
+/// Follows each AI-attributed line range forward through the commits that
+/// touch its file within `[metrics] rework_window_days`, shifting the range as
+/// earlier hunks move it. A follow-up commit is linked back to the receipt when
+/// it reverts the AI commit, matches a fix pattern and touches the AI lines, or
+/// rewrites at least `rework_min_share` of them. Used by `metrics` (rework
+/// rate), `analytics` and `report`.
+use crate::commands::anomalies::{make_rel, truncate};
+use crate::commands::audit::AuditEntry;
+use crate::core::config::MetricsConfig;
+use crate::core::receipt::Receipt;
+use crate::core::util;
+use chrono::{DateTime, Duration, Utc};
+use regex::Regex;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+/// A follow-up commit that reworked AI-written lines.
+#[derive(Debug, Serialize, Clone)]
+pub struct ReworkLink {
+    pub ai_commit: String,
+    pub receipt_id: String,
+    pub model: String,
+    pub prompt: String,
+    pub file: String,
+    pub follow_up: String,
+    pub follow_up_subject: String,
+    /// "revert", "fix" or "rework"
+    pub kind: &'static str,
+    pub lines_modified: u32,
+    pub ai_lines: u32,
+    pub days_after: f64,
+}
+
+#[derive(Debug, Default)]
+pub struct ReworkAnalysis {
+    /// AI-written lines followed.
+    pub ai_lines: u32,
+    /// Of those, lines modified or deleted within the window.
+    pub modified_lines: u32,
+    pub links: Vec<ReworkLink>,
+}
+
+impl ReworkAnalysis {
+    /// Distinct AI commits with at least one linked follow-up.
+    pub fn commits_reworked(&self) -> usize {
+        let mut shas: Vec<&str> = self.links.iter().map(|l| l.ai_commit.as_str()).collect();
+        shas.sort_unstable();
+        shas.dedup();
+        shas.len()
+    }
+}
+
+/// A `-U0` hunk: old start/count, new start/count.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Hunk {
+    old_start: i64,
+    old_count: i64,
+    new_start: i64,
+    new_count: i64,
+}
+
+struct FollowUp {
+    sha: String,
+    committed: DateTime<Utc>,
+    subject: String,
+    reverts: Vec<String>,
+}
+
+/// One AI range being followed through later commits.
+struct Tracked<'a> {
+    receipt: &'a Receipt,
+    range: Option<(i64, i64)>,
+    written: u32,
+    modified: u32,
+}
+
+pub fn analyze(entries: &[AuditEntry], cfg: &MetricsConfig) -> Result<ReworkAnalysis, String> {
+    let fix_patterns = fix_patterns(cfg)?;
+    let mut analysis = ReworkAnalysis::default();
+
+    for entry in entries.iter().filter(|e| e.commit_sha != "uncommitted") {
+        let Some(committed) = commit_time(&entry.commit_sha) else {
+            continue;
+        };
+        let until = committed + Duration::days(cfg.rework_window_days);
+
+        let mut by_file: BTreeMap<String, Vec<Tracked>> = BTreeMap::new();
+        for r in &entry.receipts {
+            for fc in r.all_file_changes().iter().filter(|fc| !fc.is_binary) {
+                let (start, end) = fc.line_range;
+                if start == 0 || end < start {
+                    continue;
+                }
+                let span = end - start + 1;
+                let written = if fc.additions > 0 {
+                    fc.additions.min(span)
+                } else {
+                    span
+                };
+                by_file
+                    .entry(make_rel(&fc.path))
+                    .or_default()
+                    .push(Tracked {
+                        receipt: r,
+                        range: Some((start as i64, end as i64)),
+                        written,
+                        modified: 0,
+                    });
+            }
+        }
+
+        for (path, tracked) in &mut by_file {
+            for follow_up in follow_ups(&entry.commit_sha, committed, until, path) {
+                let hunks = parse_hunks(&git_stdout(&[
+                    "show",
+                    "-U0",
+                    "--format=",
+                    &follow_up.sha,
+                    "--",
+                    path,
+                ]));
+                let reverts = follow_up
+                    .reverts
+                    .iter()
+                    .any(|s| entry.commit_sha.starts_with(s.as_str()));
+                let is_fix = fix_patterns.iter().any(|p| p.is_match(&follow_up.subject));
+
+                for t in tracked.iter_mut() {
+                    let Some(range) = t.range else {
+                        continue;
+                    };
+                    let touched = (overlap(&hunks, range) as u32).min(t.written - t.modified);
+                    t.modified += touched;
+                    t.range = shift_range(&hunks, range);
+
+                    let kind = if reverts {
+                        "revert"
+                    } else if is_fix && touched > 0 {
+                        "fix"
+                    } else if touched > 0
+                        && touched as f64 >= t.written as f64 * cfg.rework_min_share
+                    {
+                        "rework"
+                    } else {
+                        continue;
+                    };
+                    analysis.links.push(ReworkLink {
+                        ai_commit: util::short_sha(&entry.commit_sha),
+                        receipt_id: t.receipt.id.clone(),
+                        model: t.receipt.model.clone(),
+                        prompt: truncate(&t.receipt.prompt_summary, 80),
+                        file: path.clone(),
+                        follow_up: util::short_sha(&follow_up.sha),
+                        follow_up_subject: follow_up.subject.clone(),
+                        kind,
+                        lines_modified: touched,
+                        ai_lines: t.written,
+                        days_after: (follow_up.committed - committed).num_minutes() as f64 / 1440.0,
+                    });
+                }
+            }
+            for t in tracked.iter() {
+                analysis.ai_lines += t.written;
+                analysis.modified_lines += t.modified;
+            }
+        }
+    }
+    Ok(analysis)
+}
+
+/// Compiled `[metrics] fix_patterns` (case-insensitive).
+pub fn fix_patterns(cfg: &MetricsConfig) -> Result<Vec<Regex>, String> {
+    cfg.fix_patterns
+        .iter()
+        .map(|p| Regex::new(&format!("(?i){}", p)))
+        .collect::<Result<_, _>>()
+        .map_err(|e| format!("Invalid [metrics] fix_patterns: {}", e))
+}
+
+/// Non-merge commits after `sha` on HEAD touching `path`, oldest first.
+fn follow_ups(
+    sha: &str,
+    committed: DateTime<Utc>,
+    until: DateTime<Utc>,
+    path: &str,
+) -> Vec<FollowUp> {
+    let revert_re = Regex::new(r"This reverts commit ([0-9a-f]{7,40})").unwrap();
+    let out = git_stdout(&[
+        "log",
+        "--reverse",
+        "--no-merges",
+        "--format=%H%x1f%cI%x1f%s%x1f%b%x1e",
+        &format!("--since={}", committed.to_rfc3339()),
+        &format!("--until={}", until.to_rfc3339()),
+        &format!("{}..HEAD", sha),
+        "--",
+        path,
+    ]);
+    out.split('\x1e')
+        .filter_map(|record| {
+            let mut fields = record.trim_start_matches('\n').split('\x1f');
+            let sha = fields.next()?.to_string();
+            let committed = DateTime::parse_from_rfc3339(fields.next()?).ok()?;
+            let subject = fields.next()?.to_string();
+            let body = fields.next().unwrap_or("");
+            Some(FollowUp {
+                sha,
+                committed: committed.with_timezone(&Utc),
+                subject,
+                reverts: revert_re
+                    .captures_iter(body)
+                    .map(|c| c[1].to_string())
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+fn parse_hunks(diff: &str) -> Vec<Hunk> {
+    let parse = |spec: &str| -> Option<(i64, i64)> {
+        let mut parts = spec.split(',');
+        let start = parts.next()?.parse().ok()?;
+        let count = parts.next().map_or(Some(1), |c| c.parse().ok())?;
+        Some((start, count))
+    };
+    diff.lines()
+        .filter(|l| l.starts_with("@@"))
+        .filter_map(|l| {
+            let mut words = l.split_whitespace().skip(1);
+            let (old_start, old_count) = parse(words.next()?.strip_prefix('-')?)?;
+            let (new_start, new_count) = parse(words.next()?.strip_prefix('+')?)?;
+            Some(Hunk {
+                old_start,
+                old_count,
+                new_start,
+                new_count,
+            })
+        })
+        .collect()
+}
+
+/// Old-side lines of `range` that the hunks remove or rewrite.
+fn overlap(hunks: &[Hunk], (start, end): (i64, i64)) -> i64 {
+    hunks
+        .iter()
+        .filter(|h| h.old_count > 0)
+        .map(|h| {
+            let lo = h.old_start.max(start);
+            let hi = (h.old_start + h.old_count - 1).min(end);
+            (hi - lo + 1).max(0)
+        })
+        .sum()
+}
+
+/// Where `range` sits after the hunks are applied; None once every line is gone.
+fn shift_range(hunks: &[Hunk], (start, end): (i64, i64)) -> Option<(i64, i64)> {
+    let map = |line: i64, is_end: bool| -> i64 {
+        let mut delta = 0;
+        for h in hunks {
+            let old_end = h.old_start + h.old_count - 1;
+            if h.old_count > 0 && line >= h.old_start && line <= old_end {
+                // The line itself was rewritten: pin it to the replacement.
+                return if is_end {
+                    h.new_start + h.new_count - 1
+                } else {
+                    h.new_start + if h.new_count == 0 { 1 } else { 0 }
+                };
+            }
+            let before = if h.old_count == 0 {
+                h.old_start < line
+            } else {
+                old_end < line
+            };
+            if before {
+                delta += h.new_count - h.old_count;
+            }
+        }
+        line + delta
+    };
+    let (s, e) = (map(start, false), map(end, true));
+    (s <= e).then_some((s, e))
+}
+
+fn commit_time(sha: &str) -> Option<DateTime<Utc>> {
+    let out = git_stdout(&["show", "-s", "--format=%cI", sha]);
+    DateTime::parse_from_rfc3339(out.trim())
+        .ok()
+        .map(|d| d.with_timezone(&Utc))
+}
+
+fn git_stdout(args: &[&str]) -> String {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hunk(old_start: i64, old_count: i64, new_start: i64, new_count: i64) -> Hunk {
+        Hunk {
+            old_start,
+            old_count,
+            new_start,
+            new_count,
+        }
+    }
+
+    #[test]
+    fn test_parse_hunks() {
+        let diff = "diff --git a/x.rs b/x.rs\n\
+                    @@ -3,2 +3,4 @@ fn a()\n\
+                    @@ -10 +12 @@\n\
+                    @@ -20,0 +23,5 @@\n";
+        assert_eq!(
+            parse_hunks(diff),
+            vec![hunk(3, 2, 3, 4), hunk(10, 1, 12, 1), hunk(20, 0, 23, 5)]
+        );
+    }
+
+    #[test]
+    fn test_overlap_counts_rewritten_lines_only() {
+        let hunks = vec![hunk(3, 2, 3, 4), hunk(10, 1, 12, 1), hunk(20, 0, 23, 5)];
+        assert_eq!(overlap(&hunks, (1, 5)), 2);
+        assert_eq!(overlap(&hunks, (4, 12)), 2);
+        // Pure insertions don't modify existing lines.
+        assert_eq!(overlap(&hunks, (15, 30)), 0);
+    }
+
+    #[test]
+    fn test_shift_range_follows_edits_above() {
+        // 3 lines inserted above the range push it down.
+        assert_eq!(shift_range(&[hunk(2, 0, 3, 3)], (10, 20)), Some((13, 23)));
+        // 2 lines deleted above pull it up.
+        assert_eq!(shift_range(&[hunk(4, 2, 3, 0)], (10, 20)), Some((8, 18)));
+        // Edits below leave it alone.
+        assert_eq!(shift_range(&[hunk(30, 1, 30, 4)], (10, 20)), Some((10, 20)));
+        // Growing a line inside the range stretches its end.
+        assert_eq!(shift_range(&[hunk(12, 1, 12, 3)], (10, 20)), Some((10, 22)));
+        // Deleting the whole range drops it.
+        assert_eq!(shift_range(&[hunk(10, 11, 9, 0)], (10, 20)), None);
+    }
+}
@@ -1,7 +1,10 @@
-use crate::commands::audit;
-use crate::core::util;
+use crate::commands::{audit, staging};
+use crate::core::receipt::Receipt;
+use crate::core::{db, term, util};
 use crate::git::notes;
-use comfy_table::Table;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use globset::{Glob, GlobMatcher};
+use regex::{Regex, RegexBuilder};
 use serde::Serialize;
 
 #[derive(Serialize)]
@@ -20,6 +23,43 @@ pub struct SearchResult {
     pub session_id: String,
     pub message_count: u32,
     pub has_conversation: bool,
+    /// The first match in the prompt, response or conversation, in context.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<String>,
+    /// A staged receipt (`--include-uncommitted`), not in any commit yet.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub provisional: bool,
+}
+
+impl SearchResult {
+    pub fn new(sha: &str, r: &Receipt) -> Self {
+        SearchResult {
+            commit_sha: sha.to_string(),
+            receipt_id: r.id.clone(),
+            provider: r.provider.clone(),
+            model: r.model.clone(),
+            file_path: r
+                .all_file_paths()
+                .first()
+                .map(|f| audit::relative_path(f))
+                .unwrap_or_default(),
+            line_range: r
+                .all_file_changes()
+                .first()
+                .map(|fc| fc.line_range)
+                .unwrap_or((0, 0)),
+            files_changed: r.all_file_changes(),
+            cost_usd: r.cost_usd,
+            prompt_summary: r.prompt_summary.clone(),
+            timestamp: r.timestamp.to_rfc3339(),
+            user: r.user.clone(),
+            session_id: r.session_id.clone(),
+            message_count: r.message_count,
+            has_conversation: r.conversation.is_some(),
+            snippet: None,
+            provisional: sha == UNCOMMITTED,
+        }
+    }
 }
 
 #[derive(Serialize)]
@@ -29,10 +69,323 @@ pub struct SearchOutput {
     pub results: Vec<SearchResult>,
 }
 
-pub fn run(query: &str, limit: usize, format: &str) {
+/// Structured filters, combined with the text query; a receipt must pass all.
+#[derive(Debug, Default)]
+pub struct SearchFilter {
+    /// Substring of the model name.
+    pub model: Option<String>,
+    /// Provider name, exact.
+    pub provider: Option<String>,
+    /// Substring of the author (`Name <email>`).
+    pub author: Option<String>,
+    /// Glob over the changed files' repo-relative paths.
+    pub file: Option<GlobMatcher>,
+    pub from: Option<DateTime<Utc>>,
+    /// Exclusive upper bound.
+    pub to: Option<DateTime<Utc>>,
+    pub min_cost: Option<f64>,
+}
+
+impl SearchFilter {
+    /// Filters from command-line values. Dates are `YYYY-MM-DD` (a whole day,
+    /// so `--to` includes that day) or RFC 3339.
+    pub fn parse(
+        model: Option<String>,
+        provider: Option<String>,
+        author: Option<String>,
+        file: Option<&str>,
+        from: Option<&str>,
+        to: Option<&str>,
+        min_cost: Option<f64>,
+    ) -> Result<Self, String> {
+        let file = file
+            .map(|g| {
+                Glob::new(g)
+                    .map(|g| g.compile_matcher())
+                    .map_err(|e| format!("Invalid --file glob: {}", e))
+            })
+            .transpose()?;
+        Ok(SearchFilter {
+            model,
+            provider,
+            author,
+            file,
+            from: from.map(|d| parse_date(d, false)).transpose()?,
+            to: to.map(|d| parse_date(d, true)).transpose()?,
+            min_cost,
+        })
+    }
+
+    pub fn matches(&self, r: &Receipt) -> bool {
+        let contains = |haystack: &str, needle: &Option<String>| {
+            needle
+                .as_ref()
+                .is_none_or(|n| haystack.to_lowercase().contains(&n.to_lowercase()))
+        };
+        contains(&r.model, &self.model)
+            && contains(&r.user, &self.author)
+            && self
+                .provider
+                .as_ref()
+                .is_none_or(|p| r.provider.eq_ignore_ascii_case(p))
+            && self.from.is_none_or(|from| r.timestamp >= from)
+            && self.to.is_none_or(|to| r.timestamp < to)
+            && self.min_cost.is_none_or(|min| r.cost_usd >= min)
+            && self.file.as_ref().is_none_or(|glob| {
+                r.all_file_paths()
+                    .iter()
+                    .any(|f| glob.is_match(audit::relative_path(f)))
+            })
+    }
+
+    fn cache_query(&self) -> db::ReceiptQuery<'_> {
+        db::ReceiptQuery {
+            provider: self.provider.as_deref(),
+            model: self.model.as_deref(),
+            author: self.author.as_deref(),
+            from: self.from.map(|t| t.to_rfc3339()),
+            to: self.to.map(|t| t.to_rfc3339()),
+            min_cost: self.min_cost,
+        }
+    }
+}
+
+fn parse_date(value: &str, end_of_day: bool) -> Result<DateTime<Utc>, String> {
+    if let Ok(t) = DateTime::parse_from_rfc3339(value) {
+        return Ok(t.with_timezone(&Utc));
+    }
+    let day = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| format!("Invalid date {:?}: use YYYY-MM-DD", value))?;
+    let day = if end_of_day {
+        day.succ_opt().unwrap_or(day)
+    } else {
+        day
+    };
+    Ok(day.and_time(NaiveTime::MIN).and_utc())
+}
+
+/// How hits are ordered: best text match, newest, or most expensive first.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortOrder {
+    Relevance,
+    Date,
+    Cost,
+}
+
+impl SortOrder {
+    pub fn parse(s: &str) -> Self {
+        match s {
+            "date" => SortOrder::Date,
+            "cost" => SortOrder::Cost,
+            _ => SortOrder::Relevance,
+        }
+    }
+}
+
+/// The text query: a literal or (with `--regex`) a regular expression,
+/// case-insensitive unless `--case-sensitive`. An empty query matches
+/// everything.
+#[derive(Debug, Clone)]
+pub struct QueryPattern {
+    re: Option<Regex>,
+}
+
+/// Characters of context shown either side of a match.
+const SNIPPET_CONTEXT: usize = 30;
+
+/// A match in context: the text before it, the match, and the text after.
+#[derive(Debug, PartialEq)]
+pub struct Snippet {
+    pub before: String,
+    pub matched: String,
+    pub after: String,
+}
+
+impl Snippet {
+    /// One line, with the match highlighted (bold) when color is on.
+    pub fn highlighted(&self) -> String {
+        format!("{}{}{}", self.before, term::bold(&self.matched), self.after)
+    }
+}
+
+impl std::fmt::Display for Snippet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.before, self.matched, self.after)
+    }
+}
+
+impl QueryPattern {
+    pub fn new(query: &str, regex: bool, case_sensitive: bool) -> Result<Self, String> {
+        if query.is_empty() {
+            return Ok(QueryPattern { re: None });
+        }
+        let source = if regex {
+            query.to_string()
+        } else {
+            regex::escape(query)
+        };
+        RegexBuilder::new(&source)
+            .case_insensitive(!case_sensitive)
+            .build()
+            .map(|re| QueryPattern { re: Some(re) })
+            .map_err(|e| format!("Invalid regex: {}", e))
+    }
+
+    fn hits(&self, text: &str) -> usize {
+        self.re
+            .as_ref()
+            .map_or(0, |re| re.find_iter(text).filter(|m| !m.is_empty()).count())
+    }
+
+    /// How well `r` matches: prompt hits weigh most, then responses and
+    /// files, then conversation turns, model and provider. 0 means no match;
+    /// an empty query matches everything with score 1.
+    pub fn relevance(&self, r: &Receipt) -> usize {
+        if self.re.is_none() {
+            return 1;
+        }
+        let conversation: usize = r
+            .conversation
+            .iter()
+            .flatten()
+            .map(|t| self.hits(&t.content))
+            .sum();
+        3 * self.hits(&r.prompt_summary)
+            + 2 * r.response_summary.as_deref().map_or(0, |s| self.hits(s))
+            + 2 * r
+                .all_file_paths()
+                .iter()
+                .map(|f| self.hits(f))
+                .sum::<usize>()
+            + conversation
+            + self.hits(&r.model)
+            + self.hits(&r.provider)
+    }
+
+    /// The first match in the prompt, then the response, then the
+    /// conversation, with whitespace flattened and up to `SNIPPET_CONTEXT`
+    /// characters either side.
+    pub fn snippet(&self, r: &Receipt) -> Option<Snippet> {
+        let re = self.re.as_ref()?;
+        let texts = std::iter::once(r.prompt_summary.as_str())
+            .chain(r.response_summary.as_deref())
+            .chain(r.conversation.iter().flatten().map(|t| t.content.as_str()));
+        texts.into_iter().find_map(|text| {
+            let flat = text.split_whitespace().collect::<Vec<_>>().join(" ");
+            let m = re.find_iter(&flat).find(|m| !m.is_empty())?;
+            let before: Vec<char> = flat[..m.start()].chars().collect();
+            let skip = before.len().saturating_sub(SNIPPET_CONTEXT);
+            let after: Vec<char> = flat[m.end()..].chars().collect();
+            Some(Snippet {
+                before: format!(
+                    "{}{}",
+                    if skip > 0 { "..." } else { "" },
+                    before[skip..].iter().collect::<String>()
+                ),
+                matched: m.as_str().to_string(),
+                after: format!(
+                    "{}{}",
+                    after.iter().take(SNIPPET_CONTEXT).collect::<String>(),
+                    if after.len() > SNIPPET_CONTEXT {
+                        "..."
+                    } else {
+                        ""
+                    }
+                ),
+            })
+        })
+    }
+}
+
+/// Receipts whose prompt, response, conversation, files, model or provider
+/// contain `query` (case-insensitive), with their commit SHAs; at most `limit`.
+pub fn find_matches(commits: &[String], query: &str, limit: usize) -> Vec<(String, Receipt)> {
+    let Ok(pattern) = QueryPattern::new(query, false, false) else {
+        return Vec::new();
+    };
+    search(
+        commits,
+        &[],
+        &pattern,
+        &SearchFilter::default(),
+        SortOrder::Relevance,
+        limit,
+    )
+}
+
+/// Commit SHA reported for staged receipts, as in `audit --include-uncommitted`.
+pub const UNCOMMITTED: &str = "uncommitted";
+
+/// Receipts matching `pattern` and `filter`, ordered by `sort`; at most `limit`.
+/// The SQLite cache narrows the commits to read when it is in sync; the notes
+/// themselves decide what matches. `staged` receipts compete under the
+/// `UNCOMMITTED` SHA.
+pub fn search(
+    commits: &[String],
+    staged: &[Receipt],
+    pattern: &QueryPattern,
+    filter: &SearchFilter,
+    sort: SortOrder,
+    limit: usize,
+) -> Vec<(String, Receipt)> {
+    let candidates = db::candidate_commits(commits, &filter.cache_query());
+    let mut matches: Vec<(usize, String, Receipt)> = Vec::new();
+    for sha in commits {
+        if candidates.as_ref().is_some_and(|c| !c.contains(sha)) {
+            continue;
+        }
+        let Some(payload) = notes::read_receipts_for_commit(sha) else {
+            continue;
+        };
+        for r in payload.receipts {
+            let score = pattern.relevance(&r);
+            if score > 0 && filter.matches(&r) {
+                matches.push((score, sha.clone(), r));
+            }
+        }
+    }
+    for r in staged {
+        let score = pattern.relevance(r);
+        if score > 0 && filter.matches(r) {
+            matches.push((score, UNCOMMITTED.to_string(), r.clone()));
+        }
+    }
+    sort_matches(&mut matches, sort);
+    matches
+        .into_iter()
+        .take(limit)
+        .map(|(_, sha, r)| (sha, r))
+        .collect()
+}
+
+fn sort_matches(matches: &mut [(usize, String, Receipt)], sort: SortOrder) {
+    match sort {
+        SortOrder::Relevance => matches.sort_by(|a, b| {
+            b.0.cmp(&a.0)
+                .then_with(|| b.2.timestamp.cmp(&a.2.timestamp))
+        }),
+        SortOrder::Date => matches.sort_by_key(|m| std::cmp::Reverse(m.2.timestamp)),
+        SortOrder::Cost => matches.sort_by(|a, b| b.2.cost_usd.total_cmp(&a.2.cost_usd)),
+    }
+}
+
+pub fn run(
+    query: &str,
+    pattern: &QueryPattern,
+    filter: &SearchFilter,
+    sort: SortOrder,
+    limit: usize,
+    format: &str,
+    include_uncommitted: bool,
+) {
     let commits = notes::list_commits_with_notes();
+    let staged = if include_uncommitted {
+        staging::read_staging().receipts
+    } else {
+        Vec::new()
+    };
 
-    if commits.is_empty() {
+    if commits.is_empty() && staged.is_empty() {
         if format == "json" {
             println!(
                 "{{\"query\":\"{}\",\"total_matches\":0,\"results\":[]}}",
@@ -44,32 +397,7 @@ pub fn run(query: &str, limit: usize, format: &str) {
         return;
     }
 
-    let query_lower = query.to_lowercase();
-    let mut matches = Vec::new();
-
-    for sha in &commits {
-        if let Some(payload) = notes::read_receipts_for_commit(sha) {
-            for r in &payload.receipts {
-                let file_match = r
-                    .all_file_paths()
-                    .iter()
-                    .any(|f| f.to_lowercase().contains(&query_lower));
-                if r.prompt_summary.to_lowercase().contains(&query_lower)
-                    || file_match
-                    || r.model.to_lowercase().contains(&query_lower)
-                    || r.provider.to_lowercase().contains(&query_lower)
-                {
-                    matches.push((sha.clone(), r.clone()));
-                }
-                if matches.len() >= limit {
-                    break;
-                }
-            }
-        }
-        if matches.len() >= limit {
-            break;
-        }
-    }
+    let matches = search(&commits, &staged, pattern, filter, sort, limit);
 
     if matches.is_empty() {
         if format == "json" {
@@ -78,7 +406,7 @@ pub fn run(query: &str, limit: usize, format: &str) {
                 query
             );
         } else {
-            println!("No receipts matching \"{}\"", query);
+            println!("No receipts matching {}", describe(query));
         }
         return;
     }
@@ -91,28 +419,8 @@ pub fn run(query: &str, limit: usize, format: &str) {
             results: matches
                 .iter()
                 .map(|(sha, r)| SearchResult {
-                    commit_sha: sha.clone(),
-                    receipt_id: r.id.clone(),
-                    provider: r.provider.clone(),
-                    model: r.model.clone(),
-                    file_path: r
-                        .all_file_paths()
-                        .first()
-                        .map(|f| audit::relative_path(f))
-                        .unwrap_or_default(),
-                    line_range: r
-                        .all_file_changes()
-                        .first()
-                        .map(|fc| fc.line_range)
-                        .unwrap_or((0, 0)),
-                    files_changed: r.all_file_changes(),
-                    cost_usd: r.cost_usd,
-                    prompt_summary: r.prompt_summary.clone(),
-                    timestamp: r.timestamp.to_rfc3339(),
-                    user: r.user.clone(),
-                    session_id: r.session_id.clone(),
-                    message_count: r.message_count,
-                    has_conversation: r.conversation.is_some(),
+                    snippet: pattern.snippet(r).map(|s| s.to_string()),
+                    ..SearchResult::new(sha, r)
                 })
                 .collect(),
         };
@@ -122,25 +430,34 @@ pub fn run(query: &str, limit: usize, format: &str) {
 
     // Table output (default)
     println!(
-        "Search results for \"{}\": {} match(es)",
-        query,
+        "Search results for {}: {} match(es)",
+        describe(query),
         matches.len()
     );
     println!();
 
-    let mut table = Table::new();
-    table.set_header(vec![
+    let mut table = term::table();
+    let mut header = vec![
         "Commit",
+        "Date",
         "Provider",
         "Model",
         "File",
         "Lines",
         "Cost",
         "Prompt Summary",
-    ]);
+    ];
+    if !query.is_empty() {
+        header.push("Match");
+    }
+    table.set_header(header);
 
     for (sha, r) in &matches {
-        let sha_short = util::short_sha(sha);
+        let sha_short = if sha == UNCOMMITTED {
+            "staged*".to_string()
+        } else {
+            util::short_sha(sha)
+        };
         let prompt: String = r.prompt_summary.chars().take(50).collect();
         let file_changes = r.all_file_changes();
         let files_display = if file_changes.len() == 1 {
@@ -149,18 +466,32 @@ pub fn run(query: &str, limit: usize, format: &str) {
             format!("{} files", file_changes.len())
         };
 
-        table.add_row(vec![
-            sha_short.as_str(),
-            &r.provider,
-            &r.model,
-            &files_display,
-            &r.total_lines_changed().to_string(),
-            &format!("${:.4}", r.cost_usd),
-            &prompt,
-        ]);
+        let mut row = vec![
+            sha_short,
+            r.timestamp.format("%Y-%m-%d").to_string(),
+            r.provider.clone(),
+            r.model.clone(),
+            files_display,
+            r.total_lines_changed().to_string(),
+            format!("${:.4}", r.cost_usd),
+            prompt,
+        ];
+        if !query.is_empty() {
+            // Matches in the model, provider or file name leave no snippet.
+            row.push(
+                pattern
+                    .snippet(r)
+                    .map(|s| s.highlighted())
+                    .unwrap_or_default(),
+            );
+        }
+        table.add_row(row);
     }
 
     println!("{table}");
+    if matches.iter().any(|(sha, _)| sha == UNCOMMITTED) {
+        println!("* staged receipt, not committed yet: provisional until the commit lands");
+    }
 
     if matches.len() >= limit {
         println!(
@@ -169,3 +500,128 @@ pub fn run(query: &str, limit: usize, format: &str) {
         );
     }
 }
+
+fn describe(query: &str) -> String {
+    if query.is_empty() {
+        "the given filters".to_string()
+    } else {
+        format!("\"{}\"", query)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn receipt(id: &str, model: &str, prompt: &str, cost: f64, ts: &str) -> Receipt {
+        let mut r = Receipt::test(id)
+            .with_model(model)
+            .with_prompt(prompt)
+            .with_cost(cost)
+            .with_timestamp(ts)
+            .with_user("Dana <dana@example.com>");
+        r.file_path = "src/auth/jwt.rs".to_string();
+        r.line_range = (1, 5);
+        r
+    }
+
+    #[test]
+    fn test_search_filters_and_sorting() {
+        let a = receipt(
+            "a",
+            "claude-sonnet-4",
+            "add JWT refresh",
+            0.50,
+            "2026-03-01T10:00:00Z",
+        );
+        let b = receipt(
+            "b",
+            "gpt-4o",
+            "fix jwt expiry, jwt tests",
+            0.05,
+            "2026-03-05T10:00:00Z",
+        );
+
+        let filter = SearchFilter::parse(
+            Some("SONNET".into()),
+            Some("Claude".into()),
+            Some("dana@".into()),
+            Some("src/**/*.rs"),
+            Some("2026-03-01"),
+            Some("2026-03-01"),
+            Some(0.1),
+        )
+        .unwrap();
+        assert!(filter.matches(&a));
+        assert!(!filter.matches(&b));
+        let later =
+            SearchFilter::parse(None, None, None, None, None, Some("2026-02-28"), None).unwrap();
+        assert!(!later.matches(&a));
+        let docs = SearchFilter::parse(None, None, None, Some("*.md"), None, None, None).unwrap();
+        assert!(!docs.matches(&a));
+        assert!(SearchFilter::parse(None, None, None, None, Some("March"), None, None).is_err());
+
+        let plain = |q: &str| QueryPattern::new(q, false, false).unwrap();
+        assert_eq!(plain("jwt").relevance(&a), 3 + 2);
+        assert_eq!(plain("jwt").relevance(&b), 6 + 2);
+        assert_eq!(plain("nothing").relevance(&a), 0);
+        assert_eq!(plain("").relevance(&a), 1);
+
+        let mut hits = vec![
+            (plain("jwt").relevance(&a), "1".to_string(), a.clone()),
+            (plain("jwt").relevance(&b), "2".to_string(), b.clone()),
+        ];
+        sort_matches(&mut hits, SortOrder::Relevance);
+        assert_eq!(hits[0].2.id, "b");
+        sort_matches(&mut hits, SortOrder::Cost);
+        assert_eq!(hits[0].2.id, "a");
+        sort_matches(&mut hits, SortOrder::Date);
+        assert_eq!(hits[0].2.id, "b");
+
+        assert!(SearchResult::new(UNCOMMITTED, &a).provisional);
+        assert!(!SearchResult::new("1", &a).provisional);
+    }
+
+    #[test]
+    fn test_search_regex_case_and_snippets() {
+        let mut r = receipt(
+            "a",
+            "gpt-4o",
+            "add JWT refresh",
+            0.1,
+            "2026-03-01T10:00:00Z",
+        );
+        r.response_summary = Some("Added a refresh_token endpoint".to_string());
+        r.conversation = Some(vec![crate::core::receipt::ConversationTurn {
+            turn: 1,
+            role: "user".to_string(),
+            content: "also rotate   the\nsigning key every 24h please".to_string(),
+            tool_name: None,
+            files_touched: None,
+        }]);
+
+        let pattern = |q: &str, regex, case| QueryPattern::new(q, regex, case).unwrap();
+        assert_eq!(pattern("jwt", false, false).relevance(&r), 3 + 2);
+        assert_eq!(pattern("jwt", false, true).relevance(&r), 2);
+        assert_eq!(pattern("JWT", false, true).relevance(&r), 3);
+        assert_eq!(pattern("refresh.token", false, false).relevance(&r), 0);
+        assert_eq!(pattern("refresh.token", true, false).relevance(&r), 2);
+        assert_eq!(pattern(r"\d+h", true, false).relevance(&r), 1);
+        assert!(QueryPattern::new("(unclosed", true, false).is_err());
+        assert!(QueryPattern::new("(unclosed", false, false).is_ok());
+
+        let snip = pattern(r"\d+h", true, false).snippet(&r).unwrap();
+        assert_eq!(snip.matched, "24h");
+        assert_eq!(snip.before, "... rotate the signing key every ");
+        assert_eq!(snip.after, " please");
+        assert_eq!(
+            pattern("refresh", false, false)
+                .snippet(&r)
+                .unwrap()
+                .to_string(),
+            "add JWT refresh"
+        );
+        assert!(pattern("gpt", false, false).snippet(&r).is_none());
+        assert!(pattern("", false, false).snippet(&r).is_none());
+    }
+}
@@ -0,0 +1,305 @@
+/// `blameprompt server-hook`: a pre-receive helper for the server side.
+///
+/// Reads `<old> <new> <ref>` lines from stdin (the pre-receive protocol) and
+/// validates every note added or changed on the refs listed in
+/// `[server_hook] refs`: path is a commit id, blob within `max_note_bytes`,
+/// valid receipt JSON with at most `max_receipts` receipts, a
+/// `blameprompt_version` no older than `min_version` and no newer than this
+/// binary understands. With `require_signed`, each new commit on the notes ref
+/// must pass `git verify-commit`. Any failure rejects the whole push.
+///
+/// Works in bare repositories; nothing outside the pushed objects is read.
+use crate::core::config::{self, ServerHookConfig};
+use crate::core::receipt::{NotePayload, SCHEMA_VERSION};
+use std::io::BufRead;
+use std::process::Command;
+
+const ZERO_SHA_CHAR: char = '0';
+
+struct RefUpdate {
+    old: String,
+    new: String,
+    name: String,
+}
+
+pub fn run() -> Result<(), String> {
+    let cfg = config::load_config().server_hook;
+    let updates: Vec<RefUpdate> = std::io::stdin()
+        .lock()
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|l| parse_update(&l))
+        .collect();
+
+    let mut rejected = 0;
+    for update in updates.iter().filter(|u| cfg.refs.contains(&u.name)) {
+        let errors = check_update(update, &cfg);
+        if errors.is_empty() {
+            continue;
+        }
+        rejected += 1;
+        eprintln!("[blameprompt] Rejected {}:", update.name);
+        for e in errors.iter().take(20) {
+            eprintln!("[blameprompt]   {}", e);
+        }
+        if errors.len() > 20 {
+            eprintln!("[blameprompt]   ... and {} more", errors.len() - 20);
+        }
+    }
+    if rejected > 0 {
+        return Err(format!(
+            "{} notes ref update(s) rejected by blameprompt server-hook",
+            rejected
+        ));
+    }
+    Ok(())
+}
+
+fn parse_update(line: &str) -> Option<RefUpdate> {
+    let mut parts = line.split_whitespace();
+    Some(RefUpdate {
+        old: parts.next()?.to_string(),
+        new: parts.next()?.to_string(),
+        name: parts.next()?.to_string(),
+    })
+}
+
+fn is_zero(sha: &str) -> bool {
+    sha.chars().all(|c| c == ZERO_SHA_CHAR)
+}
+
+fn check_update(update: &RefUpdate, cfg: &ServerHookConfig) -> Vec<String> {
+    if is_zero(&update.new) {
+        return if cfg.allow_delete {
+            Vec::new()
+        } else {
+            vec!["deleting the notes ref is not allowed".to_string()]
+        };
+    }
+
+    let mut errors = Vec::new();
+    if cfg.require_signed {
+        for sha in git_lines(&["rev-list", &update.new, "--not", "--all"]) {
+            let signed = Command::new("git")
+                .args(["verify-commit", &sha])
+                .output()
+                .is_ok_and(|o| o.status.success());
+            if !signed {
+                errors.push(format!(
+                    "commit {} is not signed by a trusted key",
+                    &sha[..12.min(sha.len())]
+                ));
+            }
+        }
+    }
+
+    for (path, blob) in changed_blobs(update) {
+        let size = git_stdout(&["cat-file", "-s", &blob])
+            .trim()
+            .parse::<u64>()
+            .unwrap_or(0);
+        if size > cfg.max_note_bytes {
+            errors.push(format!(
+                "{}: note is {} bytes (limit {})",
+                path, size, cfg.max_note_bytes
+            ));
+            continue;
+        }
+        let content = Command::new("git")
+            .args(["cat-file", "blob", &blob])
+            .output()
+            .map(|o| o.stdout)
+            .unwrap_or_default();
+        errors.extend(validate_note(&path, &content, cfg));
+    }
+    errors
+}
+
+/// (path, blob) of notes added or modified by the update.
+fn changed_blobs(update: &RefUpdate) -> Vec<(String, String)> {
+    if is_zero(&update.old) {
+        return git_lines(&["ls-tree", "-r", &update.new])
+            .iter()
+            .filter_map(|l| {
+                let (meta, path) = l.split_once('\t')?;
+                let blob = meta.split_whitespace().nth(2)?;
+                Some((path.to_string(), blob.to_string()))
+            })
+            .collect();
+    }
+    git_lines(&["diff-tree", "-r", "--no-renames", &update.old, &update.new])
+        .iter()
+        .filter_map(|l| {
+            let (meta, path) = l.split_once('\t')?;
+            let fields: Vec<&str> = meta.split_whitespace().collect();
+            // :old_mode new_mode old_sha new_sha status
+            let (blob, status) = (fields.get(3)?, fields.get(4)?);
+            (!status.starts_with('D')).then(|| (path.to_string(), blob.to_string()))
+        })
+        .collect()
+}
+
+/// Problems with one note blob at `path` in the notes tree.
+fn validate_note(path: &str, content: &[u8], cfg: &ServerHookConfig) -> Vec<String> {
+    let mut errors = Vec::new();
+    let annotated: String = path.chars().filter(|c| *c != '/').collect();
+    if !matches!(annotated.len(), 40 | 64) || !annotated.chars().all(|c| c.is_ascii_hexdigit()) {
+        errors.push(format!("{}: not a commit id path", path));
+    }
+    if content.len() as u64 > cfg.max_note_bytes {
+        errors.push(format!(
+            "{}: note is {} bytes (limit {})",
+            path,
+            content.len(),
+            cfg.max_note_bytes
+        ));
+        return errors;
+    }
+
+    let payload: NotePayload = match serde_json::from_slice(content) {
+        Ok(p) => p,
+        Err(e) => {
+            errors.push(format!("{}: not a BlamePrompt note ({})", path, e));
+            return errors;
+        }
+    };
+    if payload.schema_version > SCHEMA_VERSION {
+        errors.push(format!(
+            "{}: schema_version {} is newer than this server understands ({})",
+            path, payload.schema_version, SCHEMA_VERSION
+        ));
+    }
+    match parse_version(&payload.blameprompt_version) {
+        None => errors.push(format!(
+            "{}: invalid blameprompt_version '{}'",
+            path, payload.blameprompt_version
+        )),
+        Some(v) => {
+            let (ours, _, _) = parse_version(env!("CARGO_PKG_VERSION")).unwrap_or((0, 0, 0));
+            if v.0 > ours {
+                errors.push(format!(
+                    "{}: schema version {} is newer than this server understands ({})",
+                    path,
+                    payload.blameprompt_version,
+                    env!("CARGO_PKG_VERSION")
+                ));
+            }
+            if let Some(min) = parse_version(&cfg.min_version) {
+                if v < min {
+                    errors.push(format!(
+                        "{}: schema version {} is older than the required {}",
+                        path, payload.blameprompt_version, cfg.min_version
+                    ));
+                }
+            }
+        }
+    }
+    if payload.receipts.len() > cfg.max_receipts {
+        errors.push(format!(
+            "{}: {} receipts (limit {})",
+            path,
+            payload.receipts.len(),
+            cfg.max_receipts
+        ));
+    }
+    if payload.receipts.iter().any(|r| r.id.trim().is_empty()) {
+        errors.push(format!("{}: receipt without an id", path));
+    }
+    errors
+}
+
+fn parse_version(v: &str) -> Option<(u64, u64, u64)> {
+    let mut parts = v.trim().trim_start_matches('v').splitn(3, '.');
+    let major = parts.next()?.parse().ok()?;
+    let minor = parts.next().unwrap_or("0").parse().ok()?;
+    let patch = parts
+        .next()
+        .unwrap_or("0")
+        .split(|c: char| !c.is_ascii_digit())
+        .next()?
+        .parse()
+        .ok()?;
+    Some((major, minor, patch))
+}
+
+fn git_stdout(args: &[&str]) -> String {
+    Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+        .unwrap_or_default()
+}
+
+fn git_lines(args: &[&str]) -> Vec<String> {
+    git_stdout(args)
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::Receipt;
+
+    const COMMIT: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    fn note(version: &str, receipts: usize) -> Vec<u8> {
+        let receipts: Vec<Receipt> = (0..receipts)
+            .map(|i| {
+                Receipt::test(&format!("r{}", i))
+                    .with_model("claude-sonnet-4")
+                    .with_session("s1")
+                    .with_prompt("add a test")
+                    .with_cost(0.01)
+                    .with_timestamp("2026-01-05T10:00:00Z")
+                    .with_user("dev <dev@example.com>")
+            })
+            .collect();
+        serde_json::to_vec(&serde_json::json!({
+            "blameprompt_version": version,
+            "receipts": receipts,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("1.0.1"), Some((1, 0, 1)));
+        assert_eq!(parse_version("v2.3"), Some((2, 3, 0)));
+        assert_eq!(parse_version("1.2.3-beta.1"), Some((1, 2, 3)));
+        assert_eq!(parse_version("latest"), None);
+    }
+
+    #[test]
+    fn test_validate_note() {
+        let cfg = ServerHookConfig {
+            max_receipts: 2,
+            min_version: "0.9.0".to_string(),
+            ..Default::default()
+        };
+        // Fanout paths (ab/cdef...) are fine.
+        let fanout = format!("{}/{}", &COMMIT[..2], &COMMIT[2..]);
+        assert!(validate_note(&fanout, &note("1.0.0", 1), &cfg).is_empty());
+
+        assert_eq!(validate_note("README", &note("1.0.0", 1), &cfg).len(), 1);
+        assert_eq!(validate_note(COMMIT, b"not json", &cfg).len(), 1);
+        assert_eq!(validate_note(COMMIT, &note("0.5.0", 1), &cfg).len(), 1);
+        assert_eq!(validate_note(COMMIT, &note("99.0.0", 1), &cfg).len(), 1);
+        assert_eq!(validate_note(COMMIT, &note("1.0.0", 3), &cfg).len(), 1);
+
+        let mut future: serde_json::Value = serde_json::from_slice(&note("1.0.0", 1)).unwrap();
+        future["schema_version"] = serde_json::json!(SCHEMA_VERSION + 1);
+        let future = serde_json::to_vec(&future).unwrap();
+        assert!(validate_note(COMMIT, &future, &cfg)[0].contains("schema_version"));
+
+        let tiny = ServerHookConfig {
+            max_note_bytes: 10,
+            ..Default::default()
+        };
+        assert!(validate_note(COMMIT, &note("1.0.0", 1), &tiny)[0].contains("limit 10"));
+    }
+}
@@ -0,0 +1,346 @@
+/// `blameprompt setup`: interactive onboarding for a repository.
+///
+/// Detects the AI tools on this machine, proposes which integrations to
+/// enable, asks for a privacy level and how notes are shared, optionally
+/// installs the git wrapper, then installs the repo hooks and writes
+/// `.blamepromptrc`. `--yes` accepts every proposal without asking.
+use crate::core::term;
+use crate::git::{hooks, init_hooks, wrap};
+use crate::integrations::{claude_hooks, codex, copilot, cursor};
+use std::io::{IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use toml::Table;
+
+/// An AI tool `setup` can wire up.
+pub struct Agent {
+    pub name: &'static str,
+    pub detected: bool,
+    install: fn() -> Result<(), String>,
+}
+
+/// Privacy presets: (name, description, `[privacy] prompt_storage`, `[redaction] mode`).
+const PRIVACY_LEVELS: [(&str, &str, &str, &str); 3] = [
+    ("strict", "no prompt text, secrets hashed", "none", "hash"),
+    (
+        "standard",
+        "prompts kept with secrets redacted",
+        "redacted",
+        "replace",
+    ),
+    ("full", "full prompts, secrets redacted", "full", "replace"),
+];
+
+/// Notes sharing: (`[storage] backend`, description).
+const STORAGE_BACKENDS: [(&str, &str); 3] = [
+    ("notes", "git notes, pushed and fetched with the branch"),
+    (
+        "branch",
+        "an orphan branch, for remotes that reject refs/notes/*",
+    ),
+    ("sidecar", "JSON files committed with your changes"),
+];
+
+/// What the wizard settled on.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Choices {
+    pub agents: Vec<&'static str>,
+    pub prompt_storage: String,
+    pub redaction_mode: String,
+    pub storage_backend: String,
+    pub fetch_notes: bool,
+    pub wrapper: bool,
+}
+
+pub fn detect_agents() -> Vec<Agent> {
+    let home = dirs::home_dir().unwrap_or_default();
+    let copilot_extension = std::fs::read_dir(home.join(".vscode").join("extensions"))
+        .map(|entries| {
+            entries.flatten().any(|e| {
+                e.file_name()
+                    .to_string_lossy()
+                    .starts_with("github.copilot")
+            })
+        })
+        .unwrap_or(false);
+    vec![
+        Agent {
+            name: "Claude Code",
+            detected: home.join(".claude").exists() || on_path("claude"),
+            install: claude_hooks::install,
+        },
+        Agent {
+            name: "Cursor",
+            detected: home.join(".cursor").exists(),
+            install: cursor::install_hooks,
+        },
+        Agent {
+            name: "Codex",
+            detected: codex::codex_home().is_some() || on_path("codex"),
+            install: codex::install_hooks,
+        },
+        Agent {
+            name: "Copilot",
+            detected: copilot_extension || home.join(".config").join("github-copilot").exists(),
+            install: copilot::install_hooks,
+        },
+    ]
+}
+
+fn on_path(binary: &str) -> bool {
+    std::env::var_os("PATH")
+        .is_some_and(|paths| std::env::split_paths(&paths).any(|p| p.join(binary).is_file()))
+}
+
+pub fn run(yes: bool) -> Result<(), String> {
+    let root = repo_root()?;
+    if !yes && !std::io::stdin().is_terminal() {
+        return Err("Not a terminal: pass --yes to accept the proposed setup.".to_string());
+    }
+    let ask = Prompter { yes };
+    let config_path = root.join(".blamepromptrc");
+    let existing = std::fs::read_to_string(&config_path).unwrap_or_default();
+
+    println!("{}", term::bold("BlamePrompt setup"));
+    println!();
+    println!("{}", term::bold("AI tools"));
+    let agents = detect_agents();
+    let mut enabled = Vec::new();
+    for agent in &agents {
+        let question = if agent.detected {
+            format!("{} detected. Enable its integration?", agent.name)
+        } else {
+            format!(
+                "{} not detected. Enable its integration anyway?",
+                agent.name
+            )
+        };
+        if ask.confirm(&question, agent.detected)? {
+            enabled.push(agent.name);
+        }
+    }
+
+    println!();
+    println!("{}", term::bold("Privacy"));
+    let level = ask.choose(
+        "How much of each prompt should receipts keep?",
+        &PRIVACY_LEVELS.map(|(name, desc, _, _)| (name, desc)),
+        1,
+    )?;
+    let (_, _, prompt_storage, redaction_mode) = PRIVACY_LEVELS[level];
+
+    println!();
+    println!("{}", term::bold("Sharing receipts"));
+    let backend = ask.choose("Where should receipts be stored?", &STORAGE_BACKENDS, 0)?;
+    let storage_backend = STORAGE_BACKENDS[backend].0;
+    let has_origin = std::process::Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .is_ok_and(|o| o.status.success());
+    let fetch_notes = storage_backend == "notes"
+        && has_origin
+        && ask.confirm(
+            "Fetch teammates' notes from origin on every `git fetch`?",
+            true,
+        )?;
+
+    println!();
+    println!("{}", term::bold("Git wrapper"));
+    let wrapper = ask.confirm(
+        "Install the git wrapper (attaches receipts even where hooks are bypassed)?",
+        false,
+    )?;
+
+    let choices = Choices {
+        agents: enabled,
+        prompt_storage: prompt_storage.to_string(),
+        redaction_mode: redaction_mode.to_string(),
+        storage_backend: storage_backend.to_string(),
+        fetch_notes,
+        wrapper,
+    };
+    let config = render_config(&choices, &existing)?;
+
+    println!();
+    // The wizard's answers replace the install-everything first-run setup.
+    init_hooks::remove_uninstall_marker();
+    init_hooks::mark_setup_done();
+    for agent in agents.iter().filter(|a| choices.agents.contains(&a.name)) {
+        match (agent.install)() {
+            Ok(()) => println!(
+                "  {} {} hooks installed",
+                term::success("[done]"),
+                agent.name
+            ),
+            Err(e) => println!("  [skip] {}: {}", agent.name, e),
+        }
+    }
+    init_hooks::auto_init_blameprompt(&root.to_string_lossy())?;
+    hooks::install_hooks()?;
+    println!("  {} Git hooks installed", term::success("[done]"));
+    if choices.fetch_notes && crate::commands::sync::ensure_notes_fetch_refspec("origin") {
+        println!(
+            "  {} Notes refspec added to remote.origin.fetch",
+            term::success("[done]")
+        );
+    }
+    if choices.wrapper {
+        match wrap::install() {
+            Ok(path) => println!(
+                "  {} Git wrapper installed {}",
+                term::success("[done]"),
+                term::dim(&format!("{} {}", term::arrow(), path.display()))
+            ),
+            Err(e) => println!("  [skip] Git wrapper: {}", e),
+        }
+    }
+    std::fs::write(&config_path, config)
+        .map_err(|e| format!("Cannot write {}: {}", config_path.display(), e))?;
+    println!(
+        "  {} Config written {}",
+        term::success("[done]"),
+        term::dim(&format!("{} {}", term::arrow(), config_path.display()))
+    );
+    println!();
+    println!(
+        "Commit {} so teammates share these settings.",
+        term::cyan(".blamepromptrc")
+    );
+    Ok(())
+}
+
+fn repo_root() -> Result<PathBuf, String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .output()
+        .map_err(|e| format!("git failed: {}", e))?;
+    if !output.status.success() {
+        return Err("Not inside a git repository. Run 'git init' first.".to_string());
+    }
+    Ok(Path::new(String::from_utf8_lossy(&output.stdout).trim()).to_path_buf())
+}
+
+/// `.blamepromptrc` with the wizard's settings written over `existing`; any
+/// other sections already there are kept.
+pub fn render_config(choices: &Choices, existing: &str) -> Result<String, String> {
+    let mut config: Table = existing
+        .parse()
+        .map_err(|e| format!("Existing .blamepromptrc is not valid TOML: {}", e))?;
+    let mut set = |section: &str, key: &str, value: &str| {
+        let table = config
+            .entry(section)
+            .or_insert_with(|| toml::Value::Table(Table::new()));
+        if let toml::Value::Table(table) = table {
+            table.insert(key.to_string(), toml::Value::String(value.to_string()));
+        }
+    };
+    set("privacy", "prompt_storage", &choices.prompt_storage);
+    set("redaction", "mode", &choices.redaction_mode);
+    set("storage", "backend", &choices.storage_backend);
+    let body = toml::to_string(&config).map_err(|e| e.to_string())?;
+    Ok(format!(
+        "# BlamePrompt config, written by `blameprompt setup`.\n\
+         # Integrations enabled: {}.\n\n{}",
+        if choices.agents.is_empty() {
+            "none".to_string()
+        } else {
+            choices.agents.join(", ")
+        },
+        body
+    ))
+}
+
+struct Prompter {
+    yes: bool,
+}
+
+impl Prompter {
+    fn read_answer(&self) -> Result<String, String> {
+        let _ = std::io::stdout().flush();
+        let mut answer = String::new();
+        std::io::stdin()
+            .read_line(&mut answer)
+            .map_err(|e| e.to_string())?;
+        Ok(answer.trim().to_lowercase())
+    }
+
+    fn confirm(&self, question: &str, default: bool) -> Result<bool, String> {
+        let hint = if default { "[Y/n]" } else { "[y/N]" };
+        if self.yes {
+            println!(
+                "  {} {} {}",
+                question,
+                hint,
+                if default { "y" } else { "n" }
+            );
+            return Ok(default);
+        }
+        print!("  {} {} ", question, hint);
+        Ok(match self.read_answer()?.as_str() {
+            "" => default,
+            answer => matches!(answer, "y" | "yes"),
+        })
+    }
+
+    /// Index of the picked option; `default` on an empty answer.
+    fn choose(
+        &self,
+        question: &str,
+        options: &[(&str, &str)],
+        default: usize,
+    ) -> Result<usize, String> {
+        println!("  {}", question);
+        for (i, (name, desc)) in options.iter().enumerate() {
+            println!("    {}) {:<9} {}", i + 1, name, term::dim(desc));
+        }
+        if self.yes {
+            println!("  Choice [{}]: {}", default + 1, default + 1);
+            return Ok(default);
+        }
+        loop {
+            print!("  Choice [{}]: ", default + 1);
+            let answer = self.read_answer()?;
+            if answer.is_empty() {
+                return Ok(default);
+            }
+            let picked = answer
+                .parse::<usize>()
+                .ok()
+                .and_then(|n| n.checked_sub(1))
+                .or_else(|| options.iter().position(|(name, _)| *name == answer));
+            match picked {
+                Some(i) if i < options.len() => return Ok(i),
+                _ => println!("  Answer 1-{} or an option name.", options.len()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_config_keeps_other_sections() {
+        let choices = Choices {
+            agents: vec!["Claude Code", "Cursor"],
+            prompt_storage: "none".to_string(),
+            redaction_mode: "hash".to_string(),
+            storage_backend: "branch".to_string(),
+            fetch_notes: false,
+            wrapper: false,
+        };
+        let existing =
+            "[privacy]\nprompt_storage = \"full\"\n\n[policy]\nmin_receipt_coverage = 0.5\n";
+        let rendered = render_config(&choices, existing).unwrap();
+        assert!(rendered.starts_with("# BlamePrompt config"));
+        assert!(rendered.contains("Integrations enabled: Claude Code, Cursor."));
+
+        let config: crate::core::config::BlamePromptConfig = toml::from_str(&rendered).unwrap();
+        assert_eq!(config.privacy.prompt_storage, "none");
+        assert_eq!(config.redaction.mode, "hash");
+        assert_eq!(config.storage.backend, "branch");
+        assert_eq!(config.policy.min_receipt_coverage, 0.5);
+
+        assert!(render_config(&choices, "[privacy").is_err());
+    }
+}
@@ -0,0 +1,343 @@
+/// Terminal AI capture: `gh copilot`, `sgpt`, `aichat` and friends.
+///
+/// These tools suggest shell commands and run them on request, so no agent
+/// hook ever fires. `blameprompt shell-integration` prints a snippet for
+/// `~/.bashrc` / `~/.zshrc` that wraps each tool in a shell function. The
+/// wrapper snapshots the tracked files git reports as changed, runs the tool,
+/// then calls `shell-capture record`. When the command changed tracked files,
+/// a provisional receipt (`heuristic: true`) is staged for those files. The
+/// receipt is tagged with the terminal provider, and the command line becomes
+/// its prompt.
+///
+/// Snapshots store the changed files' contents as git blobs under
+/// `.blameprompt/shell-snapshots/`, so edits to already-dirty files are
+/// measured from where they were, not from HEAD.
+use crate::commands::staging;
+use crate::core::capture_ignore::CaptureIgnore;
+use crate::core::receipt::{ChangeType, FileChange, Receipt};
+use crate::core::{config, redact, util};
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const SNAPSHOT_DIR: &str = ".blameprompt/shell-snapshots";
+
+/// Wrapped tools: shell function, provider tag, and the leading argument that
+/// marks an AI invocation (`gh copilot ...`, not every `gh` call).
+const TOOLS: [(&str, &str, Option<&str>); 4] = [
+    ("gh", "gh-copilot", Some("copilot")),
+    ("sgpt", "shell-gpt", None),
+    ("aichat", "aichat", None),
+    ("llm", "llm-cli", None),
+];
+
+/// The snippet to source from the shell's rc file. Bash and zsh share it.
+pub fn integration_script(shell: &str) -> String {
+    let mut script = format!(
+        r#"# BlamePrompt shell integration ({shell}): records files changed by terminal AI tools.
+# Add to your shell rc: eval "$(blameprompt shell-integration {shell})"
+_blameprompt_ai() {{
+  local provider="$1"; shift
+  local snapshot
+  snapshot=$(command blameprompt shell-capture snapshot 2>/dev/null)
+  command "$@"
+  local rc=$?
+  if [ -n "$snapshot" ]; then
+    command blameprompt shell-capture record --provider "$provider" --snapshot "$snapshot" -- "$@" >/dev/null 2>&1
+  fi
+  return $rc
+}}
+"#
+    );
+    for (name, provider, subcommand) in TOOLS {
+        let body = match subcommand {
+            Some(sub) => format!(
+                "if [ \"$1\" = {sub} ]; then _blameprompt_ai {provider} {name} \"$@\"; else command {name} \"$@\"; fi"
+            ),
+            None => format!("_blameprompt_ai {provider} {name} \"$@\""),
+        };
+        script.push_str(&format!("{name}() {{ {body}; }}\n"));
+    }
+    script
+}
+
+/// Save the current contents of the changed tracked files and print the
+/// snapshot path (nothing outside a repository).
+pub fn snapshot() -> Result<(), String> {
+    let root = repo_root()?;
+    let files: BTreeMap<String, String> = changed_tracked_files(&root)
+        .into_iter()
+        .filter_map(|path| Some((path.clone(), store_blob(&root, &path)?)))
+        .collect();
+    let dir = root.join(SNAPSHOT_DIR);
+    std::fs::create_dir_all(&dir).map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+    let path = dir.join(format!("{}.json", Receipt::new_id()));
+    let json = serde_json::to_string(&files).map_err(|e| e.to_string())?;
+    std::fs::write(&path, json).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    println!("{}", path.display());
+    Ok(())
+}
+
+/// Compare the working tree with `snapshot_path` and stage a heuristic receipt
+/// for the tracked files `command` changed.
+pub fn record(provider: &str, snapshot_path: &str, command: &[String]) -> Result<(), String> {
+    let root = repo_root()?;
+    let before: BTreeMap<String, String> = std::fs::read_to_string(snapshot_path)
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .ok_or_else(|| format!("Cannot read snapshot {}", snapshot_path))?;
+    let _ = std::fs::remove_file(snapshot_path);
+
+    let mut paths: BTreeSet<String> = changed_tracked_files(&root).into_iter().collect();
+    paths.extend(before.keys().cloned());
+    let after: BTreeMap<String, Option<String>> = paths
+        .into_iter()
+        .map(|p| {
+            let blob = store_blob(&root, &p);
+            (p, blob)
+        })
+        .collect();
+    let ignore = CaptureIgnore::load(&root.to_string_lossy());
+    let files: Vec<FileChange> = changed_files(&before, &after, |p| head_blob(&root, p))
+        .into_iter()
+        .filter(|(path, _, _)| !ignore.is_ignored(&root.to_string_lossy(), path))
+        .map(|(path, old, new)| file_change(&root, &path, old.as_deref(), new.as_deref()))
+        .collect();
+    if files.is_empty() {
+        return Ok(());
+    }
+
+    let cfg = config::load_config();
+    let receipt = build_receipt(
+        provider,
+        &redact::redact_secrets_with_config(&command.join(" "), &cfg),
+        files,
+    );
+    staging::upsert_receipt_in(&receipt, &root.to_string_lossy());
+    Ok(())
+}
+
+/// Files whose blob differs between the snapshot and now, with the old blob
+/// (from the snapshot, else HEAD) and the new one (None when deleted).
+pub fn changed_files(
+    before: &BTreeMap<String, String>,
+    after: &BTreeMap<String, Option<String>>,
+    head_blob: impl Fn(&str) -> Option<String>,
+) -> Vec<(String, Option<String>, Option<String>)> {
+    after
+        .iter()
+        .filter_map(|(path, new)| {
+            let old = before.get(path).cloned().or_else(|| head_blob(path));
+            (old != *new).then(|| (path.clone(), old, new.clone()))
+        })
+        .collect()
+}
+
+fn file_change(root: &Path, path: &str, old: Option<&str>, new: Option<&str>) -> FileChange {
+    let (additions, deletions, line_range) = match (old, new) {
+        (Some(old), Some(new)) => blob_diff(root, old, new),
+        (None, Some(new)) => {
+            let lines = git(root, &["cat-file", "-p", new])
+                .map(|s| s.lines().count() as u32)
+                .unwrap_or(0);
+            (lines, 0, (1, lines.max(1)))
+        }
+        _ => (0, 0, (0, 0)),
+    };
+    FileChange {
+        path: path.to_string(),
+        line_range,
+        blob_hash: new.map(String::from),
+        additions,
+        deletions,
+        notebook_cells: vec![],
+        is_binary: false,
+        bytes_before: None,
+        bytes_after: None,
+        change_type: Some(match (old, new) {
+            (None, _) => ChangeType::Added,
+            (_, None) => ChangeType::Deleted,
+            _ => ChangeType::Modified,
+        }),
+        old_path: None,
+        unstaged_ranges: Vec::new(),
+    }
+}
+
+/// Added/deleted line counts and the span of added lines between two blobs.
+fn blob_diff(root: &Path, old: &str, new: &str) -> (u32, u32, (u32, u32)) {
+    let diff = git(root, &["diff", "--unified=0", "--no-color", old, new]).unwrap_or_default();
+    let (mut additions, mut deletions) = (0, 0);
+    let (mut first, mut last) = (u32::MAX, 0);
+    for line in diff.lines() {
+        if line.starts_with("@@") {
+            let (start, end) = util::parse_hunk_range(line);
+            if end >= start && start > 0 {
+                first = first.min(start);
+                last = last.max(end);
+            }
+        } else if line.starts_with('+') && !line.starts_with("+++") {
+            additions += 1;
+        } else if line.starts_with('-') && !line.starts_with("---") {
+            deletions += 1;
+        }
+    }
+    let range = if last == 0 { (0, 0) } else { (first, last) };
+    (additions, deletions, range)
+}
+
+fn build_receipt(provider: &str, command_line: &str, files_changed: Vec<FileChange>) -> Receipt {
+    let now = Utc::now();
+    let prompt_summary = format!("(terminal AI command) {}", command_line);
+    let mut hasher = Sha256::new();
+    hasher.update(prompt_summary.as_bytes());
+    hasher.update(now.to_rfc3339().as_bytes());
+    let total_additions = files_changed.iter().map(|f| f.additions).sum();
+    let total_deletions = files_changed.iter().map(|f| f.deletions).sum();
+
+    Receipt {
+        id: Receipt::new_id(),
+        provider: provider.to_string(),
+        model: "unknown".to_string(),
+        session_id: format!("shell-{}", Receipt::new_id()),
+        prompt_summary,
+        response_summary: None,
+        plan_summary: None,
+        prompt_hash: format!("sha256:{:x}", hasher.finalize()),
+        message_count: 0,
+        cost_usd: 0.0,
+        input_tokens: None,
+        output_tokens: None,
+        cache_read_tokens: None,
+        cache_creation_tokens: None,
+        cost_estimated: false,
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        timestamp: now,
+        session_start: Some(now),
+        session_end: Some(now),
+        session_duration_secs: None,
+        ai_response_time_secs: None,
+        prompt_submitted_at: None,
+        prompt_duration_secs: None,
+        accepted_lines: None,
+        overridden_lines: None,
+        user: util::git_user(),
+        file_path: files_changed
+            .first()
+            .map(|f| f.path.clone())
+            .unwrap_or_default(),
+        line_range: files_changed
+            .first()
+            .map(|f| f.line_range)
+            .unwrap_or((1, 1)),
+        files_changed,
+        parent_receipt_id: None,
+        parent_session_id: None,
+        is_continuation: None,
+        continuation_depth: None,
+        prompt_number: Some(1),
+        tool_use_id: None,
+        total_additions,
+        total_deletions,
+        tools_used: vec![],
+        mcp_servers: vec![],
+        environment: None,
+        agents_spawned: vec![],
+        subagent_activities: vec![],
+        concurrent_tool_calls: None,
+        user_decisions: vec![],
+        conversation: None,
+        prompt_quality: None,
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: true,
+    }
+}
+
+fn repo_root() -> Result<PathBuf, String> {
+    let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+    util::repo_root_of(&cwd)
+        .map(PathBuf::from)
+        .ok_or_else(|| "Not inside a git repository".to_string())
+}
+
+/// Tracked files that differ from HEAD, staged or not.
+fn changed_tracked_files(root: &Path) -> Vec<String> {
+    git(root, &["diff", "--name-only", "HEAD"])
+        .unwrap_or_default()
+        .lines()
+        .filter(|l| !l.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Write the working-tree file to the object store; None when it is gone.
+fn store_blob(root: &Path, path: &str) -> Option<String> {
+    if !root.join(path).is_file() {
+        return None;
+    }
+    git(root, &["hash-object", "-w", "--", path])
+}
+
+fn head_blob(root: &Path, path: &str) -> Option<String> {
+    git(
+        root,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("HEAD:{}", path),
+        ],
+    )
+}
+
+fn git(root: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .current_dir(root)
+        .args(args)
+        .output()
+        .ok()?;
+    output.status.success().then(|| {
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_capture_detects_changed_files() {
+        let before: BTreeMap<String, String> = [("dirty.rs".to_string(), "b1".to_string())]
+            .into_iter()
+            .collect();
+        let after: BTreeMap<String, Option<String>> = [
+            ("dirty.rs".to_string(), Some("b2".to_string())),
+            ("same.rs".to_string(), Some("h-same".to_string())),
+            ("edited.rs".to_string(), Some("new".to_string())),
+            ("gone.rs".to_string(), None),
+        ]
+        .into_iter()
+        .collect();
+        let head = |p: &str| Some(format!("h-{}", p.trim_end_matches(".rs")));
+        let changed: Vec<String> = changed_files(&before, &after, head)
+            .into_iter()
+            .map(|(p, _, _)| p)
+            .collect();
+        assert_eq!(changed, vec!["dirty.rs", "edited.rs", "gone.rs"]);
+
+        let script = integration_script("zsh");
+        assert!(script.contains("eval \"$(blameprompt shell-integration zsh)\""));
+        assert!(script.contains(
+            "gh() { if [ \"$1\" = copilot ]; then _blameprompt_ai gh-copilot gh \"$@\"; else command gh \"$@\"; fi; }"
+        ));
+        assert!(script.contains("sgpt() { _blameprompt_ai shell-gpt sgpt \"$@\"; }"));
+    }
+}
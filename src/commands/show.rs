@@ -1,7 +1,7 @@
 use crate::commands::audit;
-use crate::core::{prompt_eval, util};
+use crate::commands::lifecycle::FileLifecycle;
+use crate::core::{prompt_eval, term, util};
 use crate::git::notes;
-use comfy_table::Table;
 
 fn resolve_sha(input: &str) -> Result<String, String> {
     let output = std::process::Command::new("git")
@@ -16,6 +16,121 @@ fn resolve_sha(input: &str) -> Result<String, String> {
     Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
+/// `show --subagents`: every subagent of the commit's receipts with the
+/// tokens, cost and files from its own transcript.
+pub fn run_subagents(commit: &str, format: &str) {
+    let sha = match resolve_sha(commit) {
+        Ok(s) => s,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    let Some(payload) = notes::read_receipts_for_commit(&sha) else {
+        println!(
+            "No BlamePrompt receipts found for commit {}",
+            util::short_sha(&sha)
+        );
+        return;
+    };
+
+    if format == "json" {
+        let rows: Vec<serde_json::Value> = payload
+            .receipts
+            .iter()
+            .flat_map(|r| {
+                r.subagent_activities.iter().map(move |a| {
+                    serde_json::json!({
+                        "receipt_id": r.id,
+                        "session_id": r.session_id,
+                        "subagent": a,
+                    })
+                })
+            })
+            .collect();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&rows).unwrap_or_default()
+        );
+        return;
+    }
+
+    let prompt_cost: f64 = payload.receipts.iter().map(|r| r.cost_usd).sum();
+    let subagent_cost: f64 = payload.receipts.iter().map(|r| r.subagent_cost_usd()).sum();
+    let mut table = term::table();
+    table.set_header(vec![
+        "Receipt",
+        "Type",
+        "Status",
+        "Model",
+        "Tokens In",
+        "Tokens Out",
+        "Cost",
+        "Files",
+        "Duration",
+    ]);
+    let mut runs = 0;
+    for r in &payload.receipts {
+        for a in &r.subagent_activities {
+            runs += 1;
+            let duration = match (a.started_at, a.completed_at) {
+                (Some(start), Some(end)) => format!("{}s", (end - start).num_seconds().max(0)),
+                _ => "-".to_string(),
+            };
+            let files: Vec<String> = a
+                .files_touched
+                .iter()
+                .map(|f| audit::relative_path(f))
+                .collect();
+            let opt = |v: Option<u64>| v.map(|n| n.to_string()).unwrap_or_else(|| "-".into());
+            table.add_row(vec![
+                util::short_sha(&r.id),
+                a.agent_type
+                    .clone()
+                    .unwrap_or_else(|| "unknown".to_string()),
+                a.status.clone(),
+                a.model.clone().unwrap_or_else(|| "-".to_string()),
+                opt(a.input_tokens),
+                opt(a.output_tokens),
+                a.cost_usd
+                    .map(|c| format!("${:.4}", c))
+                    .unwrap_or_else(|| "-".to_string()),
+                if files.is_empty() {
+                    "-".to_string()
+                } else {
+                    files.join(", ")
+                },
+                duration,
+            ]);
+        }
+    }
+    if runs == 0 {
+        println!(
+            "No subagent activity recorded for commit {}",
+            util::short_sha(&sha)
+        );
+        return;
+    }
+    term::status(&format!(
+        "{} {}",
+        term::bold("Subagents for commit"),
+        util::short_sha(&sha)
+    ));
+    println!("{table}");
+    let total = prompt_cost + subagent_cost;
+    println!(
+        "{} subagent run(s): ${:.4} of ${:.4} total ({:.1}%)",
+        runs,
+        subagent_cost,
+        total,
+        if total > 0.0 {
+            subagent_cost / total * 100.0
+        } else {
+            0.0
+        }
+    );
+}
+
 pub fn run(commit: &str, format: &str) {
     let sha = match resolve_sha(commit) {
         Ok(s) => s,
@@ -66,12 +181,16 @@ pub fn run(commit: &str, format: &str) {
 
     // Table output (default)
     let sha_short = util::short_sha(&sha);
-    println!("BlamePrompt receipts for commit {}", sha_short);
-    println!("Schema version: {}", payload.blameprompt_version);
-    println!("Total receipts: {}", payload.receipts.len());
-    println!();
+    term::status(&format!(
+        "{} {}",
+        term::bold("BlamePrompt receipts for commit"),
+        sha_short
+    ));
+    term::status(&format!("Schema version: {}", payload.blameprompt_version));
+    term::status(&format!("Total receipts: {}", payload.receipts.len()));
+    term::status("");
 
-    let mut table = Table::new();
+    let mut table = term::table();
     table.set_header(vec![
         "ID",
         "Provider",
@@ -94,7 +213,13 @@ pub fn run(commit: &str, format: &str) {
             &r.session_id
         };
         let ts = r.timestamp.format("%Y-%m-%d %H:%M").to_string();
-        let prompt: String = r.prompt_summary.chars().take(40).collect();
+        let prompt: String = r
+            .intent_summary
+            .as_deref()
+            .unwrap_or(&r.prompt_summary)
+            .chars()
+            .take(40)
+            .collect();
         let quality_badge = r
             .prompt_quality
             .as_ref()
@@ -103,7 +228,7 @@ pub fn run(commit: &str, format: &str) {
 
         let file_changes = r.all_file_changes();
         let files_display = if file_changes.len() == 1 {
-            audit::relative_path(&file_changes[0].path)
+            audit::file_change_label(&file_changes[0])
         } else {
             format!("{} files", file_changes.len())
         };
@@ -113,7 +238,11 @@ pub fn run(commit: &str, format: &str) {
             &r.model,
             session_short,
             &r.message_count.to_string(),
-            &format!("${:.4}", r.cost_usd),
+            &format!(
+                "${:.4}{}",
+                r.cost_usd,
+                if r.cost_estimated { "~" } else { "" }
+            ),
             &quality_badge,
             &files_display,
             &r.total_lines_changed().to_string(),
@@ -124,6 +253,37 @@ pub fn run(commit: &str, format: &str) {
 
     println!("{table}");
 
+    for r in &payload.receipts {
+        for fc in r
+            .files_changed
+            .iter()
+            .filter(|fc| !fc.unstaged_ranges.is_empty())
+        {
+            let ranges: Vec<String> = fc
+                .unstaged_ranges
+                .iter()
+                .map(|(s, e)| format!("{}-{}", s, e))
+                .collect();
+            println!(
+                "Note: receipt {} left AI lines {} of {} unstaged; they are not in this commit.",
+                util::short_sha(&r.id),
+                ranges.join(", "),
+                audit::relative_path(&fc.path)
+            );
+        }
+    }
+
+    let lifecycle = FileLifecycle::load();
+    for r in &payload.receipts {
+        if let Some(deleted_in) = lifecycle.receipt_deleted_by(r) {
+            println!(
+                "Note: every file of receipt {} was deleted later (in {}); its code is no longer in the tree.",
+                util::short_sha(&r.id),
+                util::short_sha(&deleted_in)
+            );
+        }
+    }
+
     // Show file mappings if present
     if let Some(ref mappings) = payload.file_mappings {
         println!("\nFile Mappings:");
@@ -150,10 +310,29 @@ pub fn run(commit: &str, format: &str) {
 
     // Show code origin stats if present
     if let Some(ref origin) = payload.code_origin {
+        let lines = |n: u32| {
+            if origin.total_lines() > 0 {
+                format!(" ({} line(s))", n)
+            } else {
+                String::new()
+            }
+        };
         println!("\nCode Origin:");
-        println!("  AI Generated: {:.1}%", origin.ai_generated_pct);
-        println!("  Human Edited: {:.1}%", origin.human_edited_pct);
-        println!("  Pure Human:   {:.1}%", origin.pure_human_pct);
+        println!(
+            "  AI Generated: {:.1}%{}",
+            origin.ai_generated_pct,
+            lines(origin.ai_generated_lines)
+        );
+        println!(
+            "  Human Edited: {:.1}%{}",
+            origin.human_edited_pct,
+            lines(origin.human_edited_lines)
+        );
+        println!(
+            "  Pure Human:   {:.1}%{}",
+            origin.pure_human_pct,
+            lines(origin.pure_human_lines)
+        );
     }
 
     // Show parent receipt chains
@@ -209,7 +388,14 @@ pub fn run(commit: &str, format: &str) {
                 } else {
                     format!(" (tools: {})", a.tools_used.join(", "))
                 };
-                println!("  [{}] \"{}\" -- {}{}", agent_type, desc, a.status, tools);
+                let cost = a
+                    .cost_usd
+                    .map(|c| format!(" ${:.4}", c))
+                    .unwrap_or_default();
+                println!(
+                    "  [{}] \"{}\" -- {}{}{}",
+                    agent_type, desc, a.status, cost, tools
+                );
             }
             if let Some(max_concurrent) = r.concurrent_tool_calls {
                 println!("  Max concurrent tool calls: {}", max_concurrent);
@@ -235,6 +421,18 @@ pub fn run(commit: &str, format: &str) {
         }
     }
 
+    // Show captured plans (intent) so reviewers can compare against what was changed
+    for r in &payload.receipts {
+        if let Some(ref plan) = r.plan_summary {
+            let id_short = util::short_sha(&r.id);
+            println!("\nPlan for receipt {}:", id_short);
+            println!("{}", "-".repeat(60));
+            for line in plan.lines() {
+                println!("  {}", line);
+            }
+        }
+    }
+
     // Show conversation chain of thought
     for r in &payload.receipts {
         if let Some(ref turns) = r.conversation {
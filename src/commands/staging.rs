@@ -1,4 +1,5 @@
-use crate::core::receipt::Receipt;
+use crate::core::config;
+use crate::core::receipt::{ChangeType, Receipt};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
@@ -30,29 +31,71 @@ fn ensure_staging_dir_in(base: &Path) {
         let _ = std::fs::create_dir_all(&dir);
     }
 
-    // Only add to local .gitignore if .blameprompt/ is not already covered by
-    // any gitignore source (local, global core.excludesFile, or .git/info/exclude).
-    if !is_blameprompt_ignored(base) {
-        let gitignore = base.join(".gitignore");
-        let mut file = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&gitignore)
-            .ok();
-        if let Some(ref mut f) = file {
-            use std::io::Write;
-            let _ = writeln!(f, "\n# BlamePrompt staging (auto-generated)\n.blameprompt/");
-        }
+    let _ = ensure_gitignored(base);
+}
+
+/// The per-machine files BlamePrompt keeps in a repository's `.blameprompt/`,
+/// as gitignore patterns. The directory also holds files meant to be
+/// committed (`config.toml`, `ignore`, sidecar receipts), so only these are
+/// ignored.
+pub const LOCAL_STATE: &[&str] = &[
+    ".blameprompt/staging*",
+    ".blameprompt/committed.json",
+    ".blameprompt/nested-repos",
+    ".blameprompt/shell-snapshots/",
+];
+
+const GITIGNORE_HEADER: &str = "# BlamePrompt local state (auto-generated)";
+/// What earlier versions appended: the whole directory.
+const LEGACY_GITIGNORE: &str = "# BlamePrompt staging (auto-generated)\n.blameprompt/";
+
+/// The block appended to `.gitignore`.
+pub fn gitignore_block() -> String {
+    format!("{}\n{}", GITIGNORE_HEADER, LOCAL_STATE.join("\n"))
+}
+
+/// Whether `line` of a `.gitignore` was written by BlamePrompt.
+pub fn is_gitignore_line(line: &str) -> bool {
+    let line = line.trim();
+    line == GITIGNORE_HEADER
+        || line == ".blameprompt"
+        || LEGACY_GITIGNORE.lines().any(|l| l == line)
+        || LOCAL_STATE.contains(&line)
+}
+
+/// Make git ignore the local state files of the repository at `base`, unless
+/// an ignore source already does. A whole-directory entry written by an
+/// earlier version is narrowed to those files.
+pub fn ensure_gitignored(base: &Path) -> std::io::Result<()> {
+    let gitignore = base.join(".gitignore");
+    let content = std::fs::read_to_string(&gitignore).unwrap_or_default();
+    if content.contains(LEGACY_GITIGNORE) {
+        return std::fs::write(
+            &gitignore,
+            content.replacen(LEGACY_GITIGNORE, &gitignore_block(), 1),
+        );
+    }
+    if is_blameprompt_ignored(base) {
+        return Ok(());
     }
+    use std::io::Write;
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&gitignore)?;
+    writeln!(file, "\n{}", gitignore_block())
 }
 
-/// Returns true if `.blameprompt` or `.blameprompt/` is already covered by any
-/// gitignore source: local `.gitignore`, global excludesFile, or `.git/info/exclude`.
+/// Returns true if the staging files are already covered by any gitignore
+/// source: local `.gitignore`, global excludesFile, or `.git/info/exclude`.
 pub(crate) fn is_blameprompt_ignored(base: &Path) -> bool {
     let matches_pattern = |content: &str| {
-        content
-            .lines()
-            .any(|l| matches!(l.trim(), ".blameprompt" | ".blameprompt/"))
+        content.lines().any(|l| {
+            matches!(
+                l.trim(),
+                ".blameprompt" | ".blameprompt/" | ".blameprompt/staging*"
+            )
+        })
     };
 
     // 1. Local .gitignore
@@ -111,13 +154,38 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
     let tmp_path = staging_dir_in(base).join("staging.json.tmp");
 
     let mut data = read_staging_in(base);
-
-    // Look for an existing receipt with same (session_id, prompt_number)
-    if let Some(existing) = data
-        .receipts
-        .iter_mut()
-        .find(|r| r.session_id == receipt.session_id && r.prompt_number == receipt.prompt_number)
-    {
+    tracing::debug!(
+        session = %receipt.session_id,
+        prompt = ?receipt.prompt_number,
+        files = receipt.files_changed.len(),
+        "staging receipt in {}",
+        path.display()
+    );
+
+    let cfg = config::load_config();
+    let mut receipt = receipt.clone();
+    // Summarize once per prompt: later updates keep the staged summary.
+    let summarized = data.receipts.iter().any(|r| {
+        r.session_id == receipt.session_id
+            && r.prompt_number == receipt.prompt_number
+            && r.tool_use_id == receipt.tool_use_id
+            && r.intent_summary.is_some()
+    });
+    if receipt.intent_summary.is_none() && !summarized && !receipt.prompt_summary.is_empty() {
+        receipt.intent_summary =
+            crate::core::summarize::intent_summary(&receipt.prompt_summary, &cfg.summarizer);
+    }
+    crate::core::pairing::add_co_authors(&mut receipt, &cfg.pairing.co_authors);
+    crate::core::privacy::apply(&mut receipt, &cfg.privacy);
+    let receipt = &receipt;
+
+    // Look for an existing receipt with same (session_id, prompt_number, tool_use_id).
+    // tool_use_id is only set under `capture.granularity = "tool_call"`.
+    if let Some(existing) = data.receipts.iter_mut().find(|r| {
+        r.session_id == receipt.session_id
+            && r.prompt_number == receipt.prompt_number
+            && r.tool_use_id == receipt.tool_use_id
+    }) {
         let original_id = existing.id.clone();
         let original_parent = existing.parent_receipt_id.clone();
 
@@ -125,8 +193,26 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
         let mut merged_files = existing.files_changed.clone();
         for fc in &receipt.files_changed {
             if let Some(pos) = merged_files.iter().position(|f| f.path == fc.path) {
-                // Update existing file's line range
+                // Update existing file's line range, keeping earlier notebook cells
+                let mut cells = merged_files[pos].notebook_cells.clone();
+                for c in &fc.notebook_cells {
+                    if !cells.contains(c) {
+                        cells.push(c.clone());
+                    }
+                }
+                // A file first seen as added/renamed stays that way when later
+                // edits in the same prompt report it as merely modified.
+                let prev = &merged_files[pos];
+                let (change_type, old_path) = match fc.change_type {
+                    None | Some(ChangeType::Modified) if prev.change_type.is_some() => {
+                        (prev.change_type, prev.old_path.clone())
+                    }
+                    _ => (fc.change_type, fc.old_path.clone()),
+                };
                 merged_files[pos] = fc.clone();
+                merged_files[pos].notebook_cells = cells;
+                merged_files[pos].change_type = change_type;
+                merged_files[pos].old_path = old_path;
             } else {
                 merged_files.push(fc.clone());
             }
@@ -155,6 +241,7 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
         } else {
             receipt.mcp_servers.clone()
         };
+        let keep_environment = existing.environment.clone();
         let keep_agents = if receipt.agents_spawned.is_empty() {
             existing.agents_spawned.clone()
         } else {
@@ -172,6 +259,12 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
             existing.response_summary.clone()
         };
         // Preserve token usage: use incoming if present, otherwise keep existing.
+        // The estimated flag travels with the token counts it describes.
+        let keep_cost_estimated = if receipt.input_tokens.is_some() {
+            receipt.cost_estimated
+        } else {
+            existing.cost_estimated
+        };
         let keep_input_tokens = receipt.input_tokens.or(existing.input_tokens);
         let keep_output_tokens = receipt.output_tokens.or(existing.output_tokens);
         let keep_cache_read = receipt.cache_read_tokens.or(existing.cache_read_tokens);
@@ -271,6 +364,18 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
             .prompt_quality
             .clone()
             .or(receipt.prompt_quality.clone());
+        // Preserve ticket: detected once at UserPromptSubmit from the branch/prompt.
+        let keep_ticket = existing.ticket.clone().or(receipt.ticket.clone());
+        let keep_intent_summary = receipt
+            .intent_summary
+            .clone()
+            .or(existing.intent_summary.clone());
+        let keep_manual_import = existing.manual_import || receipt.manual_import;
+        // Plan summary is refined at Stop time; keep the newest one.
+        let keep_plan_summary = receipt
+            .plan_summary
+            .clone()
+            .or(existing.plan_summary.clone());
         // Preserve session timing fields: use incoming if present, otherwise keep existing.
         let keep_session_start = receipt.session_start.or(existing.session_start);
         let keep_session_duration = receipt
@@ -280,6 +385,14 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
             .ai_response_time_secs
             .or(existing.ai_response_time_secs);
 
+        // Co-authors accumulate: someone who joined mid-prompt stays listed.
+        let mut keep_co_authors = existing.co_authors.clone();
+        for c in &receipt.co_authors {
+            if !keep_co_authors.contains(c) {
+                keep_co_authors.push(c.clone());
+            }
+        }
+
         // Update the receipt in place
         *existing = receipt.clone();
         existing.id = original_id;
@@ -290,12 +403,14 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
         existing.conversation = keep_conversation;
         existing.tools_used = keep_tools;
         existing.mcp_servers = keep_mcps;
+        existing.environment = receipt.environment.clone().or(keep_environment);
         existing.agents_spawned = keep_agents;
         existing.cost_usd = keep_cost;
         existing.input_tokens = keep_input_tokens;
         existing.output_tokens = keep_output_tokens;
         existing.cache_read_tokens = keep_cache_read;
         existing.cache_creation_tokens = keep_cache_creation;
+        existing.cost_estimated = keep_cost_estimated;
         existing.session_end = keep_session_end;
         existing.prompt_submitted_at = keep_prompt_submitted_at;
         existing.prompt_duration_secs = keep_prompt_duration_secs;
@@ -303,6 +418,7 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
         existing.total_deletions = keep_total_deletions;
         existing.accepted_lines = keep_accepted_lines;
         existing.overridden_lines = keep_overridden_lines;
+        existing.acceptance_estimated |= receipt.acceptance_estimated;
         existing.parent_session_id = keep_parent_session_id;
         existing.is_continuation = keep_is_continuation;
         existing.continuation_depth = keep_continuation_depth;
@@ -310,9 +426,14 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
         existing.concurrent_tool_calls = keep_concurrent_tool_calls;
         existing.user_decisions = keep_user_decisions;
         existing.prompt_quality = keep_prompt_quality;
+        existing.ticket = keep_ticket;
+        existing.intent_summary = keep_intent_summary;
+        existing.manual_import = keep_manual_import;
+        existing.plan_summary = keep_plan_summary;
         existing.session_start = keep_session_start;
         existing.session_duration_secs = keep_session_duration;
         existing.ai_response_time_secs = keep_ai_response_time;
+        existing.co_authors = keep_co_authors;
 
         // Keep legacy fields pointing at first file
         if let Some(first) = existing.files_changed.first() {
@@ -329,6 +450,154 @@ pub fn upsert_receipt_in(receipt: &Receipt, base_dir: &str) {
     write_staging_data(&data, &path, &tmp_path);
 }
 
+/// Fold the per-prompt receipts of each session into one receipt, for
+/// `capture.granularity = "session"`. Costs, tokens and line counts are summed
+/// and file changes merged; the first prompt is kept as the summary and
+/// conversations are dropped.
+pub fn collapse_sessions(receipts: Vec<Receipt>) -> Vec<Receipt> {
+    let mut out: Vec<Receipt> = Vec::new();
+    for r in receipts {
+        match out.iter_mut().find(|a| a.session_id == r.session_id) {
+            Some(acc) => fold_into_session(acc, r),
+            None => {
+                let mut first = r;
+                first.conversation = None;
+                first.tool_use_id = None;
+                out.push(first);
+            }
+        }
+    }
+    out
+}
+
+/// Merge runs of at least `min_run` consecutive housekeeping receipts (same
+/// session, no file changes, cost at most `max_cost_usd`) into one receipt
+/// summarized as "N housekeeping prompts", for `[compaction]`. Receipts with
+/// file changes are kept as they are, and so is the order.
+pub fn compact_housekeeping(
+    receipts: Vec<Receipt>,
+    cfg: &config::CompactionConfig,
+) -> Vec<Receipt> {
+    let is_housekeeping =
+        |r: &Receipt| r.all_file_changes().is_empty() && r.cost_usd <= cfg.max_cost_usd;
+
+    let mut out: Vec<Receipt> = Vec::new();
+    let mut run: Vec<Receipt> = Vec::new();
+    let flush = |run: &mut Vec<Receipt>, out: &mut Vec<Receipt>| {
+        if run.len() < cfg.min_run.max(2) {
+            out.append(run);
+            return;
+        }
+        let count = run.len() as u32;
+        let mut rest = std::mem::take(run).into_iter();
+        let Some(mut acc) = rest.next() else {
+            return;
+        };
+        for r in rest {
+            fold_into_session(&mut acc, r);
+        }
+        acc.prompt_summary = format!("{} housekeeping prompts", count);
+        acc.intent_summary = None;
+        acc.response_summary = None;
+        acc.prompt_quality = None;
+        acc.conversation = None;
+        acc.housekeeping_prompts = Some(count);
+        out.push(acc);
+    };
+
+    for r in receipts {
+        let continues = run
+            .last()
+            .is_some_and(|last| last.session_id == r.session_id);
+        if !is_housekeeping(&r) {
+            flush(&mut run, &mut out);
+            out.push(r);
+        } else if continues || run.is_empty() {
+            run.push(r);
+        } else {
+            flush(&mut run, &mut out);
+            run.push(r);
+        }
+    }
+    flush(&mut run, &mut out);
+    out
+}
+
+fn fold_into_session(acc: &mut Receipt, r: Receipt) {
+    fn add<T: std::ops::Add<Output = T> + Default>(a: Option<T>, b: Option<T>) -> Option<T> {
+        match (a, b) {
+            (None, None) => None,
+            (a, b) => Some(a.unwrap_or_default() + b.unwrap_or_default()),
+        }
+    }
+    fn union(into: &mut Vec<String>, from: Vec<String>) {
+        for item in from {
+            if !into.contains(&item) {
+                into.push(item);
+            }
+        }
+    }
+
+    if acc.prompt_summary.is_empty() {
+        acc.prompt_summary = r.prompt_summary;
+        acc.prompt_quality = r.prompt_quality;
+    }
+    acc.response_summary = r.response_summary.or(acc.response_summary.take());
+    acc.plan_summary = r.plan_summary.or(acc.plan_summary.take());
+    acc.cost_usd += r.cost_usd;
+    acc.cost_estimated |= r.cost_estimated;
+    acc.input_tokens = add(acc.input_tokens, r.input_tokens);
+    acc.output_tokens = add(acc.output_tokens, r.output_tokens);
+    acc.cache_read_tokens = add(acc.cache_read_tokens, r.cache_read_tokens);
+    acc.cache_creation_tokens = add(acc.cache_creation_tokens, r.cache_creation_tokens);
+    acc.prompt_duration_secs = add(acc.prompt_duration_secs, r.prompt_duration_secs);
+    acc.accepted_lines = add(acc.accepted_lines, r.accepted_lines);
+    acc.overridden_lines = add(acc.overridden_lines, r.overridden_lines);
+    acc.acceptance_estimated |= r.acceptance_estimated;
+    acc.message_count = acc.message_count.max(r.message_count);
+    acc.timestamp = acc.timestamp.min(r.timestamp);
+    acc.session_end = acc.session_end.max(r.session_end);
+    acc.session_duration_secs = acc.session_duration_secs.max(r.session_duration_secs);
+    acc.prompt_number = acc.prompt_number.max(r.prompt_number);
+    acc.concurrent_tool_calls = acc.concurrent_tool_calls.max(r.concurrent_tool_calls);
+    acc.ticket = acc.ticket.take().or(r.ticket);
+
+    for fc in r.files_changed {
+        match acc.files_changed.iter_mut().find(|f| f.path == fc.path) {
+            Some(f) => {
+                f.line_range = (
+                    f.line_range.0.min(fc.line_range.0),
+                    f.line_range.1.max(fc.line_range.1),
+                );
+                f.additions += fc.additions;
+                f.deletions += fc.deletions;
+                f.blob_hash = fc.blob_hash.or(f.blob_hash.take());
+                f.bytes_after = fc.bytes_after.or(f.bytes_after);
+                union(&mut f.notebook_cells, fc.notebook_cells);
+            }
+            None => acc.files_changed.push(fc),
+        }
+    }
+    acc.total_additions += r.total_additions;
+    acc.total_deletions += r.total_deletions;
+    if let Some(first) = acc.files_changed.first() {
+        acc.file_path = first.path.clone();
+        acc.line_range = first.line_range;
+    }
+
+    union(&mut acc.co_authors, r.co_authors);
+    union(&mut acc.tools_used, r.tools_used);
+    union(&mut acc.mcp_servers, r.mcp_servers);
+    if let Some(env) = r.environment {
+        acc.environment
+            .get_or_insert_with(Default::default)
+            .merge(env);
+    }
+    union(&mut acc.agents_spawned, r.agents_spawned);
+    acc.subagent_activities.extend(r.subagent_activities);
+    acc.user_decisions.extend(r.user_decisions);
+}
+
 /// Insert or update a receipt using the current working directory.
 pub fn upsert_receipt(receipt: &Receipt) {
     upsert_receipt_in(receipt, ".");
@@ -338,6 +607,7 @@ fn write_staging_data(data: &StagingData, path: &Path, tmp_path: &Path) {
     match serde_json::to_string_pretty(data) {
         Ok(json) => {
             if let Err(e) = std::fs::write(tmp_path, &json) {
+                tracing::error!(path = %path.display(), "failed to write staging file: {}", e);
                 eprintln!("[blameprompt] Failed to write staging file: {}", e);
                 return;
             }
@@ -348,10 +618,12 @@ fn write_staging_data(data: &StagingData, path: &Path, tmp_path: &Path) {
                 }
             }
             if let Err(e) = std::fs::rename(tmp_path, path) {
+                tracing::error!(path = %path.display(), "failed to rename staging file: {}", e);
                 eprintln!("[blameprompt] Failed to rename staging file: {}", e);
             }
         }
         Err(e) => {
+            tracing::error!(path = %path.display(), "failed to serialize staging data: {}", e);
             eprintln!("[blameprompt] Failed to serialize staging data: {}", e);
         }
     }
@@ -366,6 +638,33 @@ pub fn write_staging_data_in(data: &StagingData, base_dir: &str) {
     write_staging_data(data, &path, &tmp_path);
 }
 
+fn nested_repos_path_in(base: &Path) -> PathBuf {
+    staging_dir_in(base).join("nested-repos")
+}
+
+/// Remember that a session working in `base` staged receipts in the nested
+/// repository (submodule) at `root`, so Stop can finalize them there too.
+pub fn note_nested_repo_in(base: &str, root: &str) {
+    let base = Path::new(base);
+    let mut roots = nested_repos_in(base);
+    if roots.iter().any(|r| r == root) {
+        return;
+    }
+    roots.push(root.to_string());
+    ensure_staging_dir_in(base);
+    let _ = std::fs::write(nested_repos_path_in(base), roots.join("\n") + "\n");
+}
+
+/// Nested repositories that received receipts from sessions in `base`.
+pub fn nested_repos_in(base: &Path) -> Vec<String> {
+    std::fs::read_to_string(nested_repos_path_in(base))
+        .unwrap_or_default()
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .map(String::from)
+        .collect()
+}
+
 pub fn read_staging_in(base: &Path) -> StagingData {
     let path = staging_path_in(base);
     match std::fs::read_to_string(&path) {
@@ -378,6 +677,78 @@ pub fn read_staging() -> StagingData {
     read_staging_in(Path::new("."))
 }
 
+/// Identity of an imported prompt: same session, same prompt, same text.
+type ImportKey = (String, Option<u32>, String);
+
+fn import_key(r: &Receipt) -> ImportKey {
+    (r.session_id.clone(), r.prompt_number, r.prompt_hash.clone())
+}
+
+/// Makes `record*` imports idempotent: receipts whose (session_id,
+/// prompt_number, prompt_hash) is already staged or attached to a commit are
+/// skipped unless `force` is set.
+pub struct ImportDeduper {
+    seen: std::collections::HashSet<ImportKey>,
+    force: bool,
+    pub skipped: usize,
+}
+
+impl ImportDeduper {
+    pub fn new(force: bool) -> Self {
+        let mut seen = std::collections::HashSet::new();
+        if !force {
+            seen.extend(read_staging().receipts.iter().map(import_key));
+            for sha in crate::git::notes::list_commits_with_notes() {
+                if let Some(payload) = crate::git::notes::read_receipts_for_commit(&sha) {
+                    seen.extend(payload.receipts.iter().map(import_key));
+                }
+            }
+        }
+        ImportDeduper {
+            seen,
+            force,
+            skipped: 0,
+        }
+    }
+
+    #[cfg(test)]
+    fn with_existing(existing: &[Receipt]) -> Self {
+        ImportDeduper {
+            seen: existing.iter().map(import_key).collect(),
+            force: false,
+            skipped: 0,
+        }
+    }
+
+    /// True if `receipt` has not been imported before (or `force` is set).
+    fn admit(&mut self, receipt: &Receipt) -> bool {
+        if !self.force && !self.seen.insert(import_key(receipt)) {
+            self.skipped += 1;
+            return false;
+        }
+        true
+    }
+
+    /// Stage `receipt` unless it is a duplicate. Returns true if it was staged.
+    pub fn stage(&mut self, receipt: &Receipt) -> bool {
+        if !self.admit(receipt) {
+            return false;
+        }
+        upsert_receipt(receipt);
+        true
+    }
+
+    /// Print how many duplicates were skipped, prefixed with the importer's tag.
+    pub fn report(&self, tag: &str) {
+        if self.skipped > 0 {
+            println!(
+                "{} Skipped {} duplicate receipt(s) already staged or attached to a commit (use --force to re-import)",
+                tag, self.skipped
+            );
+        }
+    }
+}
+
 /// Recursively find all `.blameprompt/staging.json` files under `root`.
 fn discover_staging_files(root: &Path) -> Vec<PathBuf> {
     let mut results = Vec::new();
@@ -404,6 +775,11 @@ fn discover_staging_files(root: &Path) -> Vec<PathBuf> {
             if name_str.starts_with('.') || name_str == "node_modules" || name_str == "target" {
                 continue;
             }
+            // Submodules and nested repos keep their own staging, attached
+            // by their own commits.
+            if path.join(".git").exists() {
+                continue;
+            }
             // Check if this subdirectory has a .blameprompt/staging.json
             let sub_staging = staging_path_in(&path);
             if sub_staging.exists() {
@@ -566,6 +942,7 @@ mod tests {
             total_deletions: 0,
             tools_used: vec![],
             mcp_servers: vec![],
+            environment: None,
             agents_spawned: vec![],
             subagent_activities: vec![],
             concurrent_tool_calls: None,
@@ -576,9 +953,69 @@ mod tests {
             accepted_lines: None,
             overridden_lines: None,
             prompt_quality: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
         }
     }
 
+    #[test]
+    fn test_import_deduper_skips_known_prompts() {
+        let attached = make_receipt("s1", 1);
+        let mut dedup = ImportDeduper::with_existing(std::slice::from_ref(&attached));
+
+        // Same session/prompt/text imported again, under a fresh receipt id.
+        assert!(!dedup.admit(&make_receipt("s1", 1)));
+        // A new prompt in the same session, or edited prompt text, is new.
+        assert!(dedup.admit(&make_receipt("s1", 2)));
+        let mut edited = make_receipt("s1", 1);
+        edited.prompt_hash = "h2".to_string();
+        assert!(dedup.admit(&edited));
+        // Duplicates within one import run are caught too.
+        assert!(!dedup.admit(&make_receipt("s1", 2)));
+        assert_eq!(dedup.skipped, 2);
+
+        let mut forced = ImportDeduper {
+            force: true,
+            ..ImportDeduper::with_existing(&[attached])
+        };
+        assert!(forced.admit(&make_receipt("s1", 1)));
+        assert_eq!(forced.skipped, 0);
+    }
+
+    #[test]
+    fn test_gitignore_covers_only_local_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let gitignore = dir.path().join(".gitignore");
+        ensure_gitignored(dir.path()).unwrap();
+        ensure_gitignored(dir.path()).unwrap();
+        let content = std::fs::read_to_string(&gitignore).unwrap();
+        assert_eq!(content.matches(GITIGNORE_HEADER).count(), 1);
+        assert!(content.contains("\n.blameprompt/staging*\n"));
+        assert!(!content.lines().any(|l| l == ".blameprompt/"));
+
+        // The whole-directory entry of earlier versions is narrowed.
+        std::fs::write(
+            &gitignore,
+            "target/\n\n# BlamePrompt staging (auto-generated)\n.blameprompt/\n",
+        )
+        .unwrap();
+        ensure_gitignored(dir.path()).unwrap();
+        let content = std::fs::read_to_string(&gitignore).unwrap();
+        assert_eq!(content, format!("target/\n\n{}\n", gitignore_block()));
+
+        let kept: Vec<&str> = content.lines().filter(|l| !is_gitignore_line(l)).collect();
+        assert_eq!(kept, vec!["target/", ""]);
+        assert!(!is_gitignore_line(".blameprompt/config.toml"));
+    }
+
     #[test]
     fn test_staging_roundtrip() {
         let data = StagingData::empty();
@@ -606,6 +1043,13 @@ mod tests {
             blob_hash: None,
             additions: 5,
             deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
         }];
         upsert_receipt_in(&patch, dir);
 
@@ -631,6 +1075,13 @@ mod tests {
             blob_hash: None,
             additions: 10,
             deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
         }];
         r.total_additions = 10;
         upsert_receipt_in(&r, dir);
@@ -653,6 +1104,112 @@ mod tests {
         assert_eq!(receipt.total_additions, 10);
     }
 
+    fn file_change(path: &str, range: (u32, u32), additions: u32) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            line_range: range,
+            blob_hash: None,
+            additions,
+            deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_upsert_keeps_tool_calls_separate() {
+        let tmp = tempfile::tempdir().unwrap();
+        let dir = tmp.path().to_str().unwrap();
+
+        upsert_receipt_in(&make_receipt("s1", 1), dir);
+        for (id, path) in [("toolu_1", "a.rs"), ("toolu_2", "b.rs")] {
+            let mut call = make_receipt("s1", 1);
+            call.tool_use_id = Some(id.to_string());
+            call.files_changed = vec![file_change(path, (1, 3), 3)];
+            upsert_receipt_in(&call, dir);
+        }
+        // Stop carries no tool_use_id and lands on the prompt-level receipt.
+        let mut stop = make_receipt("s1", 1);
+        stop.cost_usd = 0.2;
+        upsert_receipt_in(&stop, dir);
+
+        let data = read_staging_in(tmp.path());
+        assert_eq!(data.receipts.len(), 3);
+        assert!(data.receipts[0].tool_use_id.is_none());
+        assert!((data.receipts[0].cost_usd - 0.2).abs() < 1e-9);
+        assert_eq!(data.receipts[1].files_changed[0].path, "a.rs");
+        assert_eq!(data.receipts[2].files_changed[0].path, "b.rs");
+    }
+
+    #[test]
+    fn test_collapse_sessions() {
+        let mut p1 = make_receipt("s1", 1);
+        p1.prompt_summary = "first".to_string();
+        p1.cost_usd = 0.1;
+        p1.input_tokens = Some(100);
+        p1.files_changed = vec![file_change("a.rs", (5, 9), 5)];
+        p1.total_additions = 5;
+        p1.tools_used = vec!["Edit".to_string()];
+        let mut p2 = make_receipt("s1", 2);
+        p2.prompt_summary = "second".to_string();
+        p2.cost_usd = 0.2;
+        p2.input_tokens = Some(50);
+        p2.files_changed = vec![
+            file_change("a.rs", (1, 6), 2),
+            file_change("b.rs", (1, 1), 1),
+        ];
+        p2.total_additions = 3;
+        p2.tools_used = vec!["Edit".to_string(), "Bash".to_string()];
+        let other = make_receipt("s2", 1);
+
+        let out = collapse_sessions(vec![p1, other, p2]);
+        assert_eq!(out.len(), 2);
+        let s1 = &out[0];
+        assert_eq!(s1.prompt_summary, "first");
+        assert_eq!(s1.prompt_number, Some(2));
+        assert!((s1.cost_usd - 0.3).abs() < 1e-9);
+        assert_eq!(s1.input_tokens, Some(150));
+        assert_eq!(s1.total_additions, 8);
+        assert_eq!(s1.files_changed.len(), 2);
+        assert_eq!(s1.files_changed[0].line_range, (1, 9));
+        assert_eq!(s1.files_changed[0].additions, 7);
+        assert_eq!(s1.tools_used, vec!["Edit", "Bash"]);
+        assert_eq!(out[1].session_id, "s2");
+    }
+
+    #[test]
+    fn test_compact_housekeeping() {
+        let cfg = config::CompactionConfig {
+            enabled: true,
+            ..config::CompactionConfig::default()
+        };
+        let mut edit = make_receipt("s1", 4);
+        edit.files_changed = vec![file_change("a.rs", (1, 2), 2)];
+        let receipts = vec![
+            make_receipt("s1", 1),
+            make_receipt("s1", 2),
+            make_receipt("s1", 3),
+            edit,
+            make_receipt("s1", 5),
+            make_receipt("s1", 6),
+        ];
+
+        let out = compact_housekeeping(receipts, &cfg);
+        assert_eq!(out.len(), 4);
+        assert_eq!(out[0].prompt_summary, "3 housekeeping prompts");
+        assert_eq!(out[0].housekeeping_prompts, Some(3));
+        assert_eq!(out[0].prompt_number, Some(3));
+        assert_eq!(out[1].files_changed.len(), 1);
+        // A run shorter than min_run keeps full detail.
+        assert_eq!(out[2].housekeeping_prompts, None);
+        assert_eq!(out[3].prompt_number, Some(6));
+    }
+
     #[test]
     fn test_upsert_preserves_continuation_fields() {
         let tmp = tempfile::tempdir().unwrap();
@@ -698,6 +1255,11 @@ mod tests {
             started_at: None,
             completed_at: None,
             tools_used: vec![],
+            model: None,
+            input_tokens: None,
+            output_tokens: None,
+            cost_usd: None,
+            files_touched: vec![],
         }];
         upsert_receipt_in(&r, dir);
 
@@ -712,6 +1274,11 @@ mod tests {
                 started_at: None,
                 completed_at: None,
                 tools_used: vec!["Glob".to_string(), "Read".to_string()],
+                model: None,
+                input_tokens: None,
+                output_tokens: None,
+                cost_usd: None,
+                files_touched: vec![],
             },
             SubagentActivity {
                 agent_id: Some("a2".to_string()),
@@ -721,6 +1288,11 @@ mod tests {
                 started_at: None,
                 completed_at: None,
                 tools_used: vec![],
+                model: None,
+                input_tokens: None,
+                output_tokens: None,
+                cost_usd: None,
+                files_touched: vec![],
             },
         ];
         upsert_receipt_in(&patch, dir);
@@ -841,6 +1413,29 @@ mod tests {
         assert!(summaries.contains(&"backend prompt 2"));
     }
 
+    #[test]
+    fn test_read_all_staging_skips_nested_repos() {
+        let tmp = tempfile::tempdir().unwrap();
+        let root = tmp.path();
+        upsert_receipt_in(&make_receipt("parent", 1), root.to_str().unwrap());
+
+        // A submodule checkout has a `.git` file pointing into the superproject.
+        let sub = root.join("vendor").join("lib");
+        std::fs::create_dir_all(&sub).unwrap();
+        std::fs::write(sub.join(".git"), "gitdir: ../../.git/modules/lib\n").unwrap();
+        upsert_receipt_in(&make_receipt("parent", 1), sub.to_str().unwrap());
+
+        let merged = read_all_staging_in(root);
+        assert_eq!(merged.receipts.len(), 1);
+
+        note_nested_repo_in(root.to_str().unwrap(), sub.to_str().unwrap());
+        note_nested_repo_in(root.to_str().unwrap(), sub.to_str().unwrap());
+        assert_eq!(
+            nested_repos_in(root),
+            vec![sub.to_str().unwrap().to_string()]
+        );
+    }
+
     #[test]
     fn test_clear_all_staging_clears_subdirectories() {
         let tmp = tempfile::tempdir().unwrap();
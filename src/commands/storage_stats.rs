@@ -0,0 +1,325 @@
+/// `blameprompt storage-stats`: how much room receipts take, and where.
+///
+/// Reports the size of every stored payload (raw JSON and, for the notes and
+/// branch backends, the packed size of the ref's history), the largest
+/// payloads, how much of it is stored conversations, the local SQLite cache,
+/// and growth over the last 90 days projected forward. Recommendations point
+/// at the settings and commands that shrink whatever dominates.
+use crate::core::{config, db, util};
+use crate::git::storage;
+use chrono::{DateTime, Duration, Utc};
+use serde_json::Value;
+use std::path::Path;
+use std::process::Command;
+
+/// Window growth is measured over, in days.
+const GROWTH_WINDOW_DAYS: i64 = 90;
+
+#[derive(Debug, Clone)]
+pub struct PayloadSize {
+    pub commit: String,
+    pub bytes: u64,
+    pub conversation_bytes: u64,
+    pub receipts: usize,
+    /// Newest receipt timestamp in the payload.
+    pub latest: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Default)]
+pub struct StorageStats {
+    pub payloads: Vec<PayloadSize>,
+    /// Packed size of the sync ref's history, when git can tell.
+    pub ref_disk_bytes: Option<u64>,
+    pub loose_objects: u64,
+    /// Payloads whose commit no longer exists.
+    pub orphaned: usize,
+    pub cache_bytes: u64,
+}
+
+impl StorageStats {
+    pub fn total_bytes(&self) -> u64 {
+        self.payloads.iter().map(|p| p.bytes).sum()
+    }
+
+    pub fn conversation_bytes(&self) -> u64 {
+        self.payloads.iter().map(|p| p.conversation_bytes).sum()
+    }
+
+    pub fn receipts(&self) -> usize {
+        self.payloads.iter().map(|p| p.receipts).sum()
+    }
+
+    /// Bytes of payloads written in the last `GROWTH_WINDOW_DAYS`, per day.
+    pub fn bytes_per_day(&self, now: DateTime<Utc>) -> f64 {
+        let since = now - Duration::days(GROWTH_WINDOW_DAYS);
+        let recent: u64 = self
+            .payloads
+            .iter()
+            .filter(|p| p.latest.is_some_and(|t| t >= since))
+            .map(|p| p.bytes)
+            .sum();
+        recent as f64 / GROWTH_WINDOW_DAYS as f64
+    }
+}
+
+pub fn run(top: usize) -> Result<(), String> {
+    let store = storage::active();
+    let commits = store.list();
+    let payloads: Vec<PayloadSize> = commits
+        .iter()
+        .filter_map(|sha| Some(measure(sha, &store.read(sha)?)))
+        .collect();
+    if payloads.is_empty() {
+        println!("No BlamePrompt receipts stored yet.");
+        return Ok(());
+    }
+
+    let sync_ref = store.sync_ref();
+    let stats = StorageStats {
+        ref_disk_bytes: sync_ref.as_deref().and_then(ref_disk_usage),
+        loose_objects: loose_object_count(),
+        orphaned: count_missing(&commits),
+        cache_bytes: std::fs::metadata(db::db_path())
+            .map(|m| m.len())
+            .unwrap_or(0),
+        payloads,
+    };
+    let cfg = config::load_config();
+    print_report(&stats, store.name(), sync_ref.as_deref(), top);
+    let advice = recommendations(&stats, &cfg);
+    if !advice.is_empty() {
+        println!("\nRecommendations:");
+        for line in &advice {
+            println!("  - {}", line);
+        }
+    }
+    Ok(())
+}
+
+/// Size of one payload, and of the conversations stored in it.
+pub fn measure(commit: &str, json: &str) -> PayloadSize {
+    let value: Value = serde_json::from_str(json).unwrap_or(Value::Null);
+    let receipts = value["receipts"]
+        .as_array()
+        .map(Vec::as_slice)
+        .unwrap_or(&[]);
+    let conversation_bytes = receipts
+        .iter()
+        .filter_map(|r| r.get("conversation").filter(|c| !c.is_null()))
+        .map(|c| c.to_string().len() as u64)
+        .sum();
+    let latest = receipts
+        .iter()
+        .filter_map(|r| r["timestamp"].as_str())
+        .filter_map(|t| DateTime::parse_from_rfc3339(t).ok())
+        .map(|t| t.with_timezone(&Utc))
+        .max();
+    PayloadSize {
+        commit: commit.to_string(),
+        bytes: json.len() as u64,
+        conversation_bytes,
+        receipts: receipts.len(),
+        latest,
+    }
+}
+
+fn print_report(stats: &StorageStats, backend: &str, sync_ref: Option<&str>, top: usize) {
+    let total = stats.total_bytes();
+    println!("BlamePrompt storage ({} backend)", backend);
+    println!(
+        "  Payloads:       {} on {} commits, {} receipts",
+        format_bytes(total),
+        stats.payloads.len(),
+        stats.receipts()
+    );
+    if let (Some(r), Some(bytes)) = (sync_ref, stats.ref_disk_bytes) {
+        println!("  On disk:        {} ({} history)", format_bytes(bytes), r);
+    }
+    println!(
+        "  Conversations:  {} ({:.0}% of payloads)",
+        format_bytes(stats.conversation_bytes()),
+        share(stats.conversation_bytes(), total)
+    );
+    if stats.orphaned > 0 {
+        println!(
+            "  Orphaned:       {} payload(s) on commits that no longer exist",
+            stats.orphaned
+        );
+    }
+    println!("  Local cache:    {}", format_bytes(stats.cache_bytes));
+
+    let per_day = stats.bytes_per_day(Utc::now());
+    println!(
+        "  Growth:         {}/day over the last {} days; +{} in 30 days, +{} in a year",
+        format_bytes(per_day as u64),
+        GROWTH_WINDOW_DAYS,
+        format_bytes((per_day * 30.0) as u64),
+        format_bytes((per_day * 365.0) as u64)
+    );
+
+    let mut largest: Vec<&PayloadSize> = stats.payloads.iter().collect();
+    largest.sort_by_key(|p| std::cmp::Reverse(p.bytes));
+    if top > 0 {
+        println!("\nLargest payloads:");
+        for p in largest.iter().take(top) {
+            println!(
+                "  {}  {:>9}  {:>3} receipt(s)  {:>3.0}% conversation",
+                util::short_sha(&p.commit),
+                format_bytes(p.bytes),
+                p.receipts,
+                share(p.conversation_bytes, p.bytes)
+            );
+        }
+    }
+}
+
+/// What to change, given where the bytes are.
+pub fn recommendations(stats: &StorageStats, cfg: &config::BlamePromptConfig) -> Vec<String> {
+    let total = stats.total_bytes();
+    let mut out = Vec::new();
+    if share(stats.conversation_bytes(), total) >= 50.0 {
+        out.push(if cfg.capture.store_full_conversation {
+            "Conversations are most of the payload: set `capture.store_full_conversation = false` so new receipts keep only the prompt summary.".to_string()
+        } else {
+            "Conversations are most of the payload, from receipts captured before `capture.store_full_conversation` was turned off; `blameprompt forget` can drop sessions you no longer need.".to_string()
+        });
+    }
+    if stats.loose_objects >= 1000 {
+        out.push(format!(
+            "{} loose objects: run `git gc` to pack and compress them (receipt JSON compresses well).",
+            stats.loose_objects
+        ));
+    }
+    if stats.orphaned > 0 {
+        out.push(format!(
+            "{} payload(s) belong to commits that no longer exist: run `blameprompt push --prune-remote` to drop them locally and on origin.",
+            stats.orphaned
+        ));
+    }
+    let per_note = stats.receipts() as f64 / stats.payloads.len().max(1) as f64;
+    if !cfg.compaction.enabled && per_note >= 20.0 {
+        out.push(format!(
+            "Commits carry {:.0} receipts on average: enable `[compaction]` to fold housekeeping prompts into one receipt.",
+            per_note
+        ));
+    }
+    if stats.cache_bytes >= 100 * 1024 * 1024 {
+        out.push(
+            "The local cache is large: delete ~/.blameprompt/prompts.db and run `blameprompt cache sync` to rebuild it."
+                .to_string(),
+        );
+    }
+    out
+}
+
+fn share(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 * 100.0 / total as f64
+    }
+}
+
+pub fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+/// Packed size of every object reachable from `sync_ref`.
+fn ref_disk_usage(sync_ref: &str) -> Option<u64> {
+    git(&["rev-list", "--objects", "--disk-usage", sync_ref])?
+        .trim()
+        .parse()
+        .ok()
+}
+
+fn loose_object_count() -> u64 {
+    git(&["count-objects", "-v"])
+        .unwrap_or_default()
+        .lines()
+        .find_map(|l| l.strip_prefix("count: "))
+        .and_then(|n| n.trim().parse().ok())
+        .unwrap_or(0)
+}
+
+/// Commits among `commits` missing from the object store.
+fn count_missing(commits: &[String]) -> usize {
+    if commits.is_empty() {
+        return 0;
+    }
+    storage::git_with_stdin(
+        Path::new("."),
+        &["cat-file", "--batch-check"],
+        &format!("{}\n", commits.join("\n")),
+    )
+    .unwrap_or_default()
+    .lines()
+    .filter(|l| l.ends_with(" missing"))
+    .count()
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_storage_stats_and_recommendations() {
+        let json = serde_json::json!({
+            "receipts": [
+                {"timestamp": "2026-01-10T00:00:00Z", "conversation": [{"role": "user", "content": "x".repeat(400)}]},
+                {"timestamp": "2026-01-12T00:00:00Z", "conversation": null},
+            ]
+        })
+        .to_string();
+        let p = measure("abc", &json);
+        assert_eq!(p.receipts, 2);
+        assert_eq!(p.bytes, json.len() as u64);
+        assert!(p.conversation_bytes > 400 && p.conversation_bytes < p.bytes);
+        assert_eq!(p.latest.unwrap().to_rfc3339(), "2026-01-12T00:00:00+00:00");
+
+        let now = DateTime::parse_from_rfc3339("2026-02-01T00:00:00Z")
+            .unwrap()
+            .with_timezone(&Utc);
+        let old = PayloadSize {
+            latest: Some(now - Duration::days(200)),
+            ..p.clone()
+        };
+        let stats = StorageStats {
+            payloads: vec![p, old],
+            orphaned: 1,
+            loose_objects: 5000,
+            ..Default::default()
+        };
+        let expected = json.len() as f64 / GROWTH_WINDOW_DAYS as f64;
+        assert!((stats.bytes_per_day(now) - expected).abs() < 1e-9);
+
+        let mut cfg = config::BlamePromptConfig::default();
+        cfg.capture.store_full_conversation = true;
+        let advice = recommendations(&stats, &cfg);
+        assert_eq!(advice.len(), 3);
+        assert!(advice[0].contains("store_full_conversation = false"));
+        assert!(advice[1].contains("git gc"));
+        assert!(advice[2].contains("--prune-remote"));
+
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 * 1024 * 1024), "3.0 MB");
+    }
+}
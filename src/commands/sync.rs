@@ -1,104 +1,337 @@
+/// `blameprompt push` / `pull`: share receipts through the remote.
+///
+/// Pushes go out in batches: the notes ref's history is pushed a slice at a
+/// time (each slice a fast-forward of the remote ref), with a short pause
+/// between slices. A push cut off by a flaky connection keeps what already
+/// landed, so the next attempt, whether a retry or a new run, only sends the
+/// rest. Transient failures are retried with exponential backoff; rejections
+/// (e.g. the remote notes diverged) are not. Review annotations
+/// (`refs/notes/blameprompt-reviews`) are merged with origin's and pushed along.
+use crate::commands::review;
+use crate::git::storage;
 use std::process::Command;
+use std::time::Duration;
 
-pub fn push() {
-    // Check if remote exists
-    let remote_check = Command::new("git")
-        .args(["remote", "get-url", "origin"])
-        .output();
+pub const DEFAULT_BATCH_SIZE: usize = 500;
+pub const DEFAULT_RETRIES: u32 = 3;
+/// Pause between batches so a large push doesn't hammer the server.
+const BATCH_PAUSE: Duration = Duration::from_millis(250);
 
-    match remote_check {
-        Ok(o) if o.status.success() => {}
-        _ => {
-            eprintln!("Error: No remote 'origin' configured.");
-            eprintln!("  Add a remote first: git remote add origin <url>");
-            return;
+pub struct SyncOptions {
+    /// Notes commits per `git push`.
+    pub batch_size: usize,
+    /// Retries of a failed transfer before giving up.
+    pub retries: u32,
+    /// Drop notes of commits that no longer exist before pushing, so the
+    /// remote stops serving them.
+    pub prune_remote: bool,
+}
+
+impl Default for SyncOptions {
+    fn default() -> Self {
+        SyncOptions {
+            batch_size: DEFAULT_BATCH_SIZE,
+            retries: DEFAULT_RETRIES,
+            prune_remote: false,
         }
     }
+}
+
+pub fn push(opts: &SyncOptions) {
+    let store = storage::active();
+    let Some(sync_ref) = store.sync_ref() else {
+        println!(
+            "[BlamePrompt] The {} storage backend keeps receipts in tracked files; they are pushed with your commits.",
+            store.name()
+        );
+        return;
+    };
+
+    if !has_origin() {
+        return;
+    }
+
+    if opts.prune_remote {
+        prune_local_notes(&sync_ref);
+    }
+
+    let Some(local_tip) = git(&["rev-parse", "--verify", "--quiet", &sync_ref]) else {
+        println!(
+            "[BlamePrompt] No notes to push ({} does not exist yet).",
+            sync_ref
+        );
+        println!("  Create some commits with AI receipts first.");
+        return;
+    };
 
     println!("Pushing BlamePrompt notes to origin...");
-    let output = Command::new("git")
-        .args(["push", "origin", "refs/notes/blameprompt"])
-        .output();
+    let remote_tip = match git_retry(&["ls-remote", "origin", &sync_ref], opts.retries) {
+        Ok(out) => out.split_whitespace().next().map(String::from),
+        Err(e) => {
+            eprintln!("Error pushing notes: {}", e);
+            return;
+        }
+    };
+    if remote_tip.as_deref() == Some(local_tip.as_str()) {
+        println!("[BlamePrompt] Notes on origin are already up to date.");
+        push_reviews(opts.retries);
+        return;
+    }
+
+    // Notes commits the remote lacks, oldest first. When the remote tip is
+    // unknown here (someone else pushed), push in one go and let git decide.
+    let known_remote = remote_tip
+        .as_deref()
+        .filter(|tip| git(&["cat-file", "-e", &format!("{}^{{commit}}", tip)]).is_some());
+    let pending: Vec<String> = match (&remote_tip, known_remote) {
+        (Some(_), None) => vec![local_tip.clone()],
+        _ => {
+            let range =
+                known_remote.map_or(local_tip.clone(), |tip| format!("{}..{}", tip, local_tip));
+            git(&["rev-list", "--reverse", "--first-parent", &range])
+                .map(|out| out.lines().map(String::from).collect())
+                .filter(|p: &Vec<String>| !p.is_empty())
+                .unwrap_or_else(|| vec![local_tip.clone()])
+        }
+    };
 
-    match output {
-        Ok(o) if o.status.success() => {
-            println!("[BlamePrompt] Notes pushed to origin successfully.");
+    let points = batch_points(pending.len(), opts.batch_size);
+    for (i, &end) in points.iter().enumerate() {
+        if i > 0 {
+            std::thread::sleep(BATCH_PAUSE);
         }
-        Ok(o) => {
-            let stderr = String::from_utf8_lossy(&o.stderr);
-            if stderr.contains("does not match any") {
-                println!(
-                    "[BlamePrompt] No notes to push (refs/notes/blameprompt does not exist yet)."
+        let refspec = format!("{}:{}", pending[end - 1], sync_ref);
+        if let Err(e) = git_retry(&["push", "origin", &refspec], opts.retries) {
+            eprintln!("Error pushing notes: {}", e);
+            if i > 0 {
+                eprintln!(
+                    "  {} of {} notes commits reached origin; run `blameprompt push` again to send the rest.",
+                    points[i - 1],
+                    pending.len()
                 );
-                println!("  Create some commits with AI receipts first.");
-            } else {
-                eprintln!("Error pushing notes: {}", stderr);
             }
+            return;
         }
-        Err(e) => {
-            eprintln!("Error: git push failed: {}", e);
+        if points.len() > 1 {
+            println!(
+                "  [{}/{}] {} of {} notes commits pushed",
+                i + 1,
+                points.len(),
+                end,
+                pending.len()
+            );
         }
     }
+    println!("[BlamePrompt] Notes pushed to origin successfully.");
+    push_reviews(opts.retries);
+}
+
+/// Push the review annotations (`blameprompt review`), merging origin's first
+/// so reviewers working in parallel don't reject each other's pushes.
+fn push_reviews(retries: u32) {
+    if git(&["rev-parse", "--verify", "--quiet", review::REVIEWS_REF]).is_none() {
+        return;
+    }
+    if let Err(e) = review::fetch_reviews() {
+        eprintln!("Error fetching reviews: {}", e);
+        return;
+    }
+    match git_retry(&["push", "origin", review::REVIEWS_REF], retries) {
+        Ok(_) => println!("[BlamePrompt] Review annotations pushed to origin."),
+        Err(e) => eprintln!("Error pushing reviews: {}", e),
+    }
 }
 
-pub fn pull() {
-    // Check if remote exists
-    let remote_check = Command::new("git")
-        .args(["remote", "get-url", "origin"])
-        .output();
+pub fn pull(opts: &SyncOptions) {
+    let store = storage::active();
+    let Some(sync_ref) = store.sync_ref() else {
+        println!(
+            "[BlamePrompt] The {} storage backend keeps receipts in tracked files; `git pull` fetches them.",
+            store.name()
+        );
+        return;
+    };
 
-    match remote_check {
-        Ok(o) if o.status.success() => {}
-        _ => {
-            eprintln!("Error: No remote 'origin' configured.");
-            eprintln!("  Add a remote first: git remote add origin <url>");
-            return;
-        }
+    if !has_origin() {
+        return;
     }
 
     println!("Fetching BlamePrompt notes from origin...");
-    let output = Command::new("git")
-        .args([
-            "fetch",
-            "origin",
-            "refs/notes/blameprompt:refs/notes/blameprompt",
-        ])
-        .output();
-
-    match output {
-        Ok(o) if o.status.success() => {
-            println!("[BlamePrompt] Notes fetched from origin successfully.");
+    let before = git(&["rev-parse", "--verify", "--quiet", &sync_ref]);
+    match git_retry(
+        &["fetch", "origin", &format!("{}:{}", sync_ref, sync_ref)],
+        opts.retries,
+    ) {
+        Ok(_) => {
+            let after = git(&["rev-parse", "--verify", "--quiet", &sync_ref]);
+            let new_commits = match (&before, &after) {
+                (Some(b), Some(a)) if a != b => {
+                    git(&["rev-list", "--count", &format!("{}..{}", b, a)])
+                }
+                (None, Some(a)) => git(&["rev-list", "--count", a]),
+                _ => None,
+            };
+            match new_commits {
+                Some(n) => println!(
+                    "[BlamePrompt] Notes fetched from origin successfully ({} new notes commits).",
+                    n
+                ),
+                None => println!("[BlamePrompt] Notes fetched from origin successfully."),
+            }
 
             // Configure auto-fetch for future pulls if not already set
-            let existing_fetch = Command::new("git")
-                .args(["config", "--get-all", "remote.origin.fetch"])
-                .output()
-                .ok()
-                .and_then(|o| String::from_utf8(o.stdout).ok())
-                .unwrap_or_default();
-
-            if !existing_fetch.contains("refs/notes/blameprompt") {
-                let _ = Command::new("git")
-                    .args([
-                        "config",
-                        "--add",
-                        "remote.origin.fetch",
-                        "+refs/notes/blameprompt:refs/notes/blameprompt",
-                    ])
-                    .output();
+            if sync_ref == storage::NOTES_REF {
+                ensure_notes_fetch_refspec("origin");
             }
-        }
-        Ok(o) => {
-            let stderr = String::from_utf8_lossy(&o.stderr);
-            if stderr.contains("couldn't find remote ref") {
-                println!("[BlamePrompt] No notes found on origin.");
-                println!("  Someone needs to push notes first: blameprompt push");
-            } else {
-                eprintln!("Error fetching notes: {}", stderr);
+            if let Err(e) = review::fetch_reviews() {
+                eprintln!("Error fetching reviews: {}", e);
             }
         }
+        Err(e) if e.contains("couldn't find remote ref") => {
+            println!("[BlamePrompt] No notes found on origin.");
+            println!("  Someone needs to push notes first: blameprompt push");
+        }
         Err(e) => {
-            eprintln!("Error: git fetch failed: {}", e);
+            eprintln!("Error fetching notes: {}", e);
+        }
+    }
+}
+
+fn has_origin() -> bool {
+    if git(&["remote", "get-url", "origin"]).is_some() {
+        return true;
+    }
+    eprintln!("Error: No remote 'origin' configured.");
+    eprintln!("  Add a remote first: git remote add origin <url>");
+    false
+}
+
+/// `git notes prune` on the notes ref; other sync refs have nothing to prune.
+fn prune_local_notes(sync_ref: &str) {
+    if sync_ref != storage::NOTES_REF {
+        println!(
+            "[BlamePrompt] --prune-remote only applies to the notes backend; skipping {}.",
+            sync_ref
+        );
+        return;
+    }
+    match git(&["notes", "--ref", sync_ref, "prune", "-v"]) {
+        Some(out) if !out.is_empty() => println!(
+            "[BlamePrompt] Pruned {} note(s) of commits that no longer exist.",
+            out.lines().count()
+        ),
+        Some(_) => println!("[BlamePrompt] No stale notes to prune."),
+        None => eprintln!("[BlamePrompt] git notes prune failed; pushing as is."),
+    }
+}
+
+/// End positions (exclusive) of each batch when pushing `total` commits
+/// `batch_size` at a time; the last batch ends at `total`.
+pub fn batch_points(total: usize, batch_size: usize) -> Vec<usize> {
+    let size = batch_size.max(1);
+    let mut points: Vec<usize> = (1..=total / size).map(|i| i * size).collect();
+    if !total.is_multiple_of(size) {
+        points.push(total);
+    }
+    points
+}
+
+/// Failures worth retrying: network trouble rather than a rejected update or
+/// a missing ref.
+pub fn is_transient(stderr: &str) -> bool {
+    const PERMANENT: [&str; 5] = [
+        "rejected",
+        "does not match any",
+        "couldn't find remote ref",
+        "non-fast-forward",
+        "Permission denied",
+    ];
+    !PERMANENT.iter().any(|p| stderr.contains(p))
+}
+
+/// Run git, retrying transient failures with backoff (1s, 2s, 4s, ...).
+fn git_retry(args: &[&str], retries: u32) -> Result<String, String> {
+    let mut attempt = 0;
+    loop {
+        let output = Command::new("git")
+            .args(args)
+            .output()
+            .map_err(|e| format!("git {} failed: {}", args[0], e))?;
+        if output.status.success() {
+            return Ok(String::from_utf8_lossy(&output.stdout).trim().to_string());
         }
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        if attempt >= retries || !is_transient(&stderr) {
+            return Err(stderr);
+        }
+        let wait = Duration::from_secs(1 << attempt.min(5));
+        eprintln!(
+            "[BlamePrompt] git {} failed ({}); retrying in {}s ({}/{})",
+            args[0],
+            stderr.lines().last().unwrap_or(""),
+            wait.as_secs(),
+            attempt + 1,
+            retries
+        );
+        std::thread::sleep(wait);
+        attempt += 1;
+    }
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Add the notes refspec to `remote.<remote>.fetch` so plain `git fetch`/`git pull`
+/// keep receipts up to date. Returns true if it was added (false if already present).
+pub fn ensure_notes_fetch_refspec(remote: &str) -> bool {
+    let key = format!("remote.{}.fetch", remote);
+    let existing_fetch = Command::new("git")
+        .args(["config", "--get-all", &key])
+        .output()
+        .ok()
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .unwrap_or_default();
+
+    if existing_fetch.contains("refs/notes/blameprompt") {
+        return false;
+    }
+    Command::new("git")
+        .args([
+            "config",
+            "--add",
+            &key,
+            "+refs/notes/blameprompt:refs/notes/blameprompt",
+        ])
+        .output()
+        .is_ok_and(|o| o.status.success())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_push_batches_and_retry_policy() {
+        assert_eq!(batch_points(1200, 500), vec![500, 1000, 1200]);
+        assert_eq!(batch_points(1000, 500), vec![500, 1000]);
+        assert_eq!(batch_points(3, 500), vec![3]);
+        assert!(batch_points(0, 500).is_empty());
+        assert_eq!(batch_points(2, 0), vec![1, 2]);
+
+        assert!(is_transient(
+            "fatal: unable to access 'https://example.com/': Connection timed out"
+        ));
+        assert!(is_transient("error: RPC failed; curl 56 GnuTLS recv error"));
+        assert!(!is_transient(
+            " ! [rejected]        refs/notes/blameprompt (fetch first)"
+        ));
+        assert!(!is_transient(
+            "fatal: couldn't find remote ref refs/notes/blameprompt"
+        ));
     }
 }
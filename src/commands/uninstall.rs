@@ -1,7 +1,144 @@
+use crate::commands::staging;
+use crate::core::term;
 use std::io::Write;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Parts of an installation `--component` can remove on their own.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    /// Agent hooks, git hooks (this repo, the hooks multiplexer, the git template).
+    Hooks,
+    /// The git shim and its PATH entry in the shell RC file.
+    Wrapper,
+    /// The SQLite cache (~/.blameprompt/prompts.db).
+    Cache,
+    /// This repository's receipt history (refs/notes/blameprompt).
+    Notes,
+}
+
+impl FromStr for Component {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hooks" => Ok(Component::Hooks),
+            "wrapper" | "wrap" => Ok(Component::Wrapper),
+            "cache" | "db" => Ok(Component::Cache),
+            "notes" => Ok(Component::Notes),
+            other => Err(format!(
+                "Unknown --component '{}' (expected hooks, wrapper, cache or notes)",
+                other
+            )),
+        }
+    }
+}
+
+/// One removal, in the order uninstall performs them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step {
+    AgentHooks,
+    RepoHooks,
+    HooksPath,
+    Staging,
+    Gitignore,
+    GitTemplate,
+    GitShim,
+    ShellPath,
+    Cache,
+    GlobalData,
+    Notes,
+}
+
+/// The steps to run. Without components this is the full uninstall, which
+/// removes all of `~/.blameprompt/` and keeps notes unless `purge`.
+fn steps(components: &[Component], keep_notes: bool, purge: bool) -> Vec<Step> {
+    if components.is_empty() {
+        let mut steps = vec![
+            Step::AgentHooks,
+            Step::RepoHooks,
+            Step::GitShim,
+            Step::HooksPath,
+            Step::Staging,
+            Step::Gitignore,
+            Step::GitTemplate,
+            Step::ShellPath,
+            Step::GlobalData,
+        ];
+        if purge && !keep_notes {
+            steps.push(Step::Notes);
+        }
+        return steps;
+    }
+    let mut steps = Vec::new();
+    if components.contains(&Component::Hooks) {
+        steps.extend([
+            Step::AgentHooks,
+            Step::RepoHooks,
+            Step::HooksPath,
+            Step::GitTemplate,
+        ]);
+    }
+    if components.contains(&Component::Wrapper) {
+        steps.extend([Step::GitShim, Step::ShellPath]);
+    }
+    if components.contains(&Component::Cache) {
+        steps.push(Step::Cache);
+    }
+    if components.contains(&Component::Notes) && !keep_notes {
+        steps.push(Step::Notes);
+    }
+    steps
+}
 
-pub fn run(keep_notes: bool, purge: bool) -> Result<(), String> {
+/// What a step would remove right now, or None when there is nothing to remove.
+fn describe(step: Step) -> Option<String> {
+    let home = dirs::home_dir();
+    let exists = |rel: &str| home.as_ref().is_some_and(|h| h.join(rel).exists());
+    match step {
+        Step::AgentHooks => home
+            .as_ref()
+            .and_then(|h| std::fs::read_to_string(h.join(".claude").join("settings.json")).ok())
+            .filter(|c| c.contains("blameprompt"))
+            .map(|_| "Claude Code hooks in ~/.claude/settings.json".to_string()),
+        Step::RepoHooks => {
+            let names = crate::git::hooks::installed_hook_names();
+            (!names.is_empty())
+                .then(|| format!("BlamePrompt sections of .git/hooks/{{{}}}", names.join(",")))
+        }
+        Step::HooksPath => crate::git::hooks_path::is_installed()
+            .then(|| "global core.hooksPath multiplexer (~/.blameprompt/hooks)".to_string()),
+        Step::Staging => (!staging_files().is_empty())
+            .then(|| "staging files in .blameprompt/ of this repo".to_string()),
+        Step::Gitignore => std::fs::read_to_string(".gitignore")
+            .ok()
+            .filter(|c| c.lines().any(staging::is_gitignore_line))
+            .map(|_| "BlamePrompt entries in .gitignore".to_string()),
+        Step::GitTemplate => global_template_dir()
+            .filter(|t| t.contains(".blameprompt"))
+            .map(|t| format!("git config --global init.templateDir ({})", t)),
+        Step::GitShim => {
+            crate::git::wrap::is_installed().then(|| "git shim ~/.blameprompt/bin/git".to_string())
+        }
+        Step::ShellPath => crate::git::wrap::shell_rc_with_path()
+            .map(|(rc, _)| format!("PATH entry for ~/.blameprompt/bin in ~/{}", rc)),
+        Step::Cache => exists(".blameprompt/prompts.db")
+            .then(|| "SQLite cache ~/.blameprompt/prompts.db".to_string()),
+        Step::GlobalData => exists(".blameprompt")
+            .then(|| "~/.blameprompt/ (cache, git template, logs, setup marker)".to_string()),
+        Step::Notes => match count_git_notes() {
+            0 => None,
+            n => Some(format!("{} Git Note(s) in refs/notes/blameprompt", n)),
+        },
+    }
+}
+
+pub fn run(
+    keep_notes: bool,
+    purge: bool,
+    components: &[Component],
+    dry_run: bool,
+) -> Result<(), String> {
     // ANSI color shortcuts
     let bg = "\x1b[1;32m"; // bold green
     let br = "\x1b[1;31m"; // bold red
@@ -12,6 +149,52 @@ pub fn run(keep_notes: bool, purge: bool) -> Result<(), String> {
     let d = "\x1b[2m"; // dim
     let r = "\x1b[0m"; // reset
 
+    let steps = steps(components, keep_notes, purge);
+
+    if dry_run {
+        println!();
+        println!("  {bw}blameprompt uninstall would remove:{r}");
+        let mut any = false;
+        for step in &steps {
+            if let Some(what) = describe(*step) {
+                println!("    {d}-{r} {}", what);
+                any = true;
+            }
+        }
+        if !any {
+            println!("    {d}(nothing: no matching BlamePrompt files are installed){r}");
+        }
+        if components.is_empty() && !steps.contains(&Step::Notes) {
+            println!("  {by}[kept]{r} Git Notes {d}(refs/notes/blameprompt){r}");
+        }
+        println!();
+        println!("  {d}Nothing was changed (dry run).{r}");
+        return Ok(());
+    }
+
+    if !components.is_empty() {
+        let names: Vec<&str> = components
+            .iter()
+            .map(|c| match c {
+                Component::Hooks => "hooks",
+                Component::Wrapper => "wrapper",
+                Component::Cache => "cache",
+                Component::Notes => "notes",
+            })
+            .collect();
+        println!();
+        println!("  {bw}Removing BlamePrompt {}...{r}", names.join(", "));
+        println!();
+        if steps.contains(&Step::Notes) && !confirm_notes_removal() {
+            return Ok(());
+        }
+        for step in steps {
+            run_step(step)?;
+        }
+        println!();
+        return Ok(());
+    }
+
     println!();
     println!("  {bw}Uninstalling BlamePrompt...{r}");
     println!();
@@ -28,56 +211,28 @@ pub fn run(keep_notes: bool, purge: bool) -> Result<(), String> {
         println!("    {d}-{r} All hooks {d}(Claude Code + git, globally and in this repo){r}");
         println!("    {d}-{r} Git template directory");
         println!();
-        print!("  {b}Continue?{r} {d}[y/N]{r} ");
-        std::io::stdout().flush().ok();
-        let mut input = String::new();
-        std::io::stdin().read_line(&mut input).unwrap_or(0);
-        if !input.trim().eq_ignore_ascii_case("y") {
-            println!("  {d}Aborted.{r}");
+        if !confirm() {
             return Ok(());
         }
         println!();
     }
 
-    // 1. Remove Claude Code hooks from ~/.claude/settings.json
-    crate::integrations::claude_hooks::uninstall()?;
-
-    // 2. Remove git hooks from current repo
-    crate::git::hooks::uninstall_hooks()?;
-
-    // 2a. Remove the transparent git shim (~/.blameprompt/bin/git)
-    crate::git::wrap::uninstall()?;
-
-    // 3. Remove staging directory from current repo
-    remove_staging_dir()?;
-
-    // 4. Remove .gitignore entry from current repo
-    remove_gitignore_entry()?;
-
-    // 5. Reset git init.templateDir (stops future repos from getting hooks)
-    remove_git_template()?;
-
-    // 5a. Remove the PATH injection added to the user's shell RC file.
-    //     Must happen before we delete ~/.blameprompt/ so the content check still works.
-    crate::git::wrap::remove_path_from_shell_rc();
-
-    // 5b. Write an explicit uninstall marker so auto_setup() won't quietly reinstall
-    //     everything the next time any blameprompt command is invoked. This MUST happen
-    //     before remove_global_data() removes ~/.blameprompt/ (and .setup-done with it).
-    write_uninstall_marker();
-
-    // 6. Remove ~/.blameprompt/ (SQLite cache, git template, setup marker)
-    remove_global_data()?;
+    for step in steps {
+        // Write an explicit uninstall marker so auto_setup() won't quietly reinstall
+        // everything the next time any blameprompt command is invoked. This MUST happen
+        // before ~/.blameprompt/ (and .setup-done with it) is removed.
+        if step == Step::GlobalData {
+            write_uninstall_marker();
+        }
+        run_step(step)?;
+    }
 
-    // 7. Remove Git Notes (only with --purge)
-    if purge && !keep_notes {
-        remove_git_notes()?;
-    } else {
+    if !purge || keep_notes {
         println!("  {by}[kept]{r} Git Notes {d}(refs/notes/blameprompt){r}");
         println!("         {d}→ To remove:{r} {bc}blameprompt uninstall --purge{r}");
     }
 
-    // 8. Show binary removal instructions (only with --purge)
+    // Show binary removal instructions (only with --purge)
     if purge {
         remove_binary()?;
     }
@@ -109,13 +264,131 @@ pub fn run(keep_notes: bool, purge: bool) -> Result<(), String> {
     Ok(())
 }
 
+fn run_step(step: Step) -> Result<(), String> {
+    match step {
+        // Claude Code hooks in ~/.claude/settings.json
+        Step::AgentHooks => crate::integrations::claude_hooks::uninstall(),
+        Step::RepoHooks => crate::git::hooks::uninstall_hooks(),
+        // Restore the global core.hooksPath replaced by the hooks multiplexer
+        Step::HooksPath => crate::git::hooks_path::uninstall(),
+        Step::Staging => remove_staging_dir(),
+        Step::Gitignore => remove_gitignore_entry(),
+        // Reset git init.templateDir (stops future repos from getting hooks)
+        Step::GitTemplate => remove_git_template(),
+        // The transparent git shim (~/.blameprompt/bin/git)
+        Step::GitShim => crate::git::wrap::uninstall(),
+        // The PATH injection added to the user's shell RC file. Must happen
+        // before ~/.blameprompt/ is deleted so the content check still works.
+        Step::ShellPath => {
+            crate::git::wrap::remove_path_from_shell_rc();
+            Ok(())
+        }
+        Step::Cache => remove_cache(),
+        // ~/.blameprompt/ (SQLite cache, git template, setup marker)
+        Step::GlobalData => remove_global_data(),
+        Step::Notes => remove_git_notes(),
+    }
+}
+
+fn confirm() -> bool {
+    print!("  {} {} ", term::bold("Continue?"), term::dim("[y/N]"));
+    std::io::stdout().flush().ok();
+    let mut input = String::new();
+    std::io::stdin().read_line(&mut input).unwrap_or(0);
+    if !input.trim().eq_ignore_ascii_case("y") {
+        println!("  {}", term::dim("Aborted."));
+        return false;
+    }
+    true
+}
+
+fn confirm_notes_removal() -> bool {
+    println!(
+        "  {} {}",
+        term::bold(&term::red("WARNING:")),
+        term::bold(&format!(
+            "This will permanently delete {} Git Note(s) (receipt history).",
+            count_git_notes()
+        ))
+    );
+    confirm()
+}
+
+fn remove_cache() -> Result<(), String> {
+    let home = dirs::home_dir().ok_or("Cannot find home directory")?;
+    let db = home.join(".blameprompt").join("prompts.db");
+    if !db.exists() {
+        return Ok(());
+    }
+    for suffix in ["", "-wal", "-shm", "-journal"] {
+        let path = PathBuf::from(format!("{}{}", db.display(), suffix));
+        if path.exists() {
+            std::fs::remove_file(&path)
+                .map_err(|e| format!("Cannot remove {}: {}", path.display(), e))?;
+        }
+    }
+    println!(
+        "  {} Removed SQLite cache {}",
+        term::success("[done]"),
+        term::dim("(~/.blameprompt/prompts.db)")
+    );
+    term::status(&format!(
+        "         {} {}",
+        term::dim(&format!("{} Rebuild it any time with:", term::arrow())),
+        term::cyan("blameprompt sync")
+    ));
+    Ok(())
+}
+
+fn global_template_dir() -> Option<String> {
+    let out = std::process::Command::new("git")
+        .args(["config", "--global", "--get", "init.templateDir"])
+        .output()
+        .ok()?;
+    Some(String::from_utf8_lossy(&out.stdout).trim().to_string()).filter(|s| !s.is_empty())
+}
+
+/// The local state files of this repo's `.blameprompt/` (see
+/// `staging::LOCAL_STATE`); its shared files are not among them.
+fn staging_files() -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(".blameprompt") else {
+        return Vec::new();
+    };
+    entries
+        .flatten()
+        .filter(|e| {
+            let name = e.file_name().to_string_lossy().to_string();
+            staging::LOCAL_STATE.iter().any(|pattern| {
+                let pattern = pattern.trim_start_matches(".blameprompt/");
+                match pattern.strip_suffix('*') {
+                    Some(prefix) => name.starts_with(prefix),
+                    None => name == pattern.trim_end_matches('/'),
+                }
+            })
+        })
+        .map(|e| e.path())
+        .collect()
+}
+
+/// Delete the staging files, keeping `config.toml`, `ignore` and sidecar
+/// receipts; the directory goes only if nothing else is left in it.
 fn remove_staging_dir() -> Result<(), String> {
-    let staging = Path::new(".blameprompt");
-    if staging.exists() {
-        std::fs::remove_dir_all(staging)
-            .map_err(|e| format!("Cannot remove .blameprompt/: {}", e))?;
-        println!("  \x1b[1;32m[done]\x1b[0m Removed .blameprompt/ directory");
+    let files = staging_files();
+    for path in &files {
+        let removed = if path.is_dir() {
+            std::fs::remove_dir_all(path)
+        } else {
+            std::fs::remove_file(path)
+        };
+        removed.map_err(|e| format!("Cannot remove {}: {}", path.display(), e))?;
     }
+    if !files.is_empty() {
+        println!(
+            "  {} Removed staging files from .blameprompt/",
+            term::success("[done]")
+        );
+    }
+    let _ = std::fs::remove_dir(".blameprompt");
     Ok(())
 }
 
@@ -130,12 +403,7 @@ fn remove_gitignore_entry() -> Result<(), String> {
 
     let cleaned: Vec<&str> = content
         .lines()
-        .filter(|line| {
-            let trimmed = line.trim();
-            trimmed != ".blameprompt/"
-                && trimmed != ".blameprompt"
-                && !trimmed.contains("# BlamePrompt staging")
-        })
+        .filter(|line| !staging::is_gitignore_line(line))
         .collect();
 
     if cleaned.len() < content.lines().count() {
@@ -255,3 +523,30 @@ fn count_git_notes() -> usize {
         _ => 0,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_uninstall_steps() {
+        let full = steps(&[], false, false);
+        assert!(full.contains(&Step::GlobalData));
+        assert!(!full.contains(&Step::Notes));
+        // The PATH entry has to go before ~/.blameprompt/ does.
+        let pos = |s: Step| full.iter().position(|x| *x == s).unwrap();
+        assert!(pos(Step::ShellPath) < pos(Step::GlobalData));
+        assert!(steps(&[], false, true).contains(&Step::Notes));
+        assert!(!steps(&[], true, true).contains(&Step::Notes));
+
+        assert_eq!(
+            steps(&[Component::Wrapper], false, false),
+            vec![Step::GitShim, Step::ShellPath]
+        );
+        let cache_notes = steps(&[Component::Notes, Component::Cache], false, false);
+        assert_eq!(cache_notes, vec![Step::Cache, Step::Notes]);
+
+        assert_eq!("wrapper".parse::<Component>(), Ok(Component::Wrapper));
+        assert!("binary".parse::<Component>().is_err());
+    }
+}
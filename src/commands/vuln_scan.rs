@@ -1,4 +1,6 @@
 use crate::commands::audit;
+use crate::core::config;
+use crate::core::scan_exclude::{ScanExclusions, Skipped};
 use chrono::Utc;
 use regex::Regex;
 use std::collections::HashMap;
@@ -156,6 +158,9 @@ pub fn run(output: &str) {
     let all_receipts: Vec<_> = entries.iter().flat_map(|e| &e.receipts).collect();
     let now = Utc::now().format("%Y-%m-%d %H:%M:%S UTC").to_string();
 
+    let cfg = config::load_config();
+    let exclusions = ScanExclusions::load(&cfg.scan, &cfg.scan.vuln);
+    let mut skipped = Skipped::default();
     let mut findings: Vec<Finding> = Vec::new();
     let mut files_scanned = 0;
     let mut lines_scanned: u32 = 0;
@@ -164,6 +169,10 @@ pub fn run(output: &str) {
     for r in &all_receipts {
         for fc in r.all_file_changes() {
             let file_path = &fc.path;
+            if let Some(reason) = exclusions.reason(file_path) {
+                skipped.add(relative_path(file_path), reason);
+                continue;
+            }
             let content = match std::fs::read_to_string(file_path) {
                 Ok(c) => c,
                 Err(_) => continue,
@@ -228,6 +237,7 @@ pub fn run(output: &str) {
     md.push_str("| Metric | Value |\n");
     md.push_str("|--------|-------|\n");
     md.push_str(&format!("| Files scanned | {} |\n", files_scanned));
+    md.push_str(&format!("| Files excluded | {} |\n", skipped.len()));
     md.push_str(&format!(
         "| AI-generated lines scanned | {} |\n",
         lines_scanned
@@ -277,6 +287,8 @@ pub fn run(output: &str) {
         md.push_str("## Findings\n\nNo vulnerabilities detected in AI-generated code.\n\n");
     }
 
+    md.push_str(&skipped.to_markdown());
+
     // CWE breakdown
     if !findings.is_empty() {
         md.push_str("## CWE Distribution\n\n");
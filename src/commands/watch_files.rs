@@ -0,0 +1,481 @@
+/// `blameprompt watch-files`: last-resort capture for AI tools without hooks.
+///
+/// Watches the working tree and stages a provisional receipt (`heuristic:
+/// true`) for every burst of machine-speed edits. A change to a file counts
+/// when it inserts at least `min_lines` lines and either
+/// - inserts text faster than `max_human_cps` characters per second, measured
+///   from the file's previous write (or from watcher start),
+/// - creates the file, or
+/// - lands within the burst window of another machine-speed change (agents
+///   write several files back to back).
+///
+/// Changes made by git itself (checkout, pull, stash) are ignored, as are
+/// files git ignores. Formatters and large pastes can still trigger it, and a
+/// human save after a long typing session won't: receipts from here are
+/// clearly marked so reports can keep them apart.
+use crate::commands::staging;
+use crate::core::receipt::{FileChange, Receipt};
+use crate::core::util;
+use chrono::{DateTime, Utc};
+use notify::{EventKind, RecursiveMode, Watcher};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+/// Files above this size are never read.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+/// Editors write in several steps (truncate, write, rename); wait this long
+/// after the last event on a file before reading it.
+const SETTLE: Duration = Duration::from_millis(100);
+
+pub struct WatchOptions {
+    pub provider: String,
+    pub model: Option<String>,
+    pub min_lines: u32,
+    pub max_human_cps: f64,
+    /// A burst ends after this long without machine-speed changes.
+    pub burst_window: Duration,
+}
+
+/// Lines inserted by one change.
+#[derive(Debug, PartialEq)]
+struct Insertion {
+    lines: u32,
+    chars: usize,
+    /// 1-based first and last line of the new content that was inserted.
+    range: (u32, u32),
+}
+
+struct FileState {
+    lines: Vec<String>,
+    changed_at: Instant,
+}
+
+/// Machine-speed changes waiting to become one receipt.
+#[derive(Default)]
+struct Burst {
+    files: BTreeMap<String, FileChange>,
+    started_at: Option<DateTime<Utc>>,
+    last_at: Option<Instant>,
+}
+
+struct Detector {
+    root: PathBuf,
+    opts: WatchOptions,
+    started: Instant,
+    files: HashMap<PathBuf, FileState>,
+    ignored: HashMap<PathBuf, bool>,
+    head: Option<String>,
+    burst: Burst,
+}
+
+pub fn run(opts: WatchOptions) -> Result<(), String> {
+    let cwd = std::env::current_dir().map_err(|e| e.to_string())?;
+    let root = util::repo_root_of(&cwd).ok_or("Not inside a git repository")?;
+    let root = PathBuf::from(root);
+
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |res| {
+        let _ = tx.send(res);
+    })
+    .map_err(|e| format!("Cannot start file watcher: {}", e))?;
+    watcher
+        .watch(&root, RecursiveMode::Recursive)
+        .map_err(|e| format!("Cannot watch {}: {}", root.display(), e))?;
+
+    let session_id = format!("watch-{}", Receipt::new_id());
+    println!(
+        "[BlamePrompt] Watching {} for machine-speed edits (provider: {}). Ctrl-C to stop.",
+        root.display(),
+        opts.provider
+    );
+    let mut detector = Detector::new(root.clone(), opts);
+    // path -> (last event, created during this run)
+    let mut dirty: HashMap<PathBuf, (Instant, bool)> = HashMap::new();
+    let mut prompt_number = 0u32;
+
+    loop {
+        match rx.recv_timeout(Duration::from_millis(50)) {
+            Ok(Ok(event)) => {
+                let created = matches!(event.kind, EventKind::Create(_));
+                if created || matches!(event.kind, EventKind::Modify(_)) {
+                    for path in event.paths {
+                        let entry = dirty.entry(path).or_insert((Instant::now(), false));
+                        *entry = (Instant::now(), entry.1 || created);
+                    }
+                }
+            }
+            Ok(Err(e)) => tracing::warn!("watch error: {}", e),
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+
+        let now = Instant::now();
+        let settled: Vec<PathBuf> = dirty
+            .iter()
+            .filter(|(_, (t, _))| now.duration_since(*t) >= SETTLE)
+            .map(|(p, _)| p.clone())
+            .collect();
+        for path in settled {
+            let (at, created) = dirty.remove(&path).unwrap_or((now, false));
+            detector.observe(&path, at, created);
+        }
+
+        if let Some(burst) = detector.take_finished(now) {
+            prompt_number += 1;
+            let receipt = build_receipt(&detector.opts, &session_id, prompt_number, burst);
+            println!(
+                "[BlamePrompt] Provisional receipt: {} line(s) in {}",
+                receipt.total_additions,
+                receipt.all_file_paths().join(", ")
+            );
+            staging::upsert_receipt_in(&receipt, &root.to_string_lossy());
+        }
+    }
+    Ok(())
+}
+
+impl Detector {
+    fn new(root: PathBuf, opts: WatchOptions) -> Self {
+        let head = git_head(&root);
+        Detector {
+            root,
+            opts,
+            started: Instant::now(),
+            files: HashMap::new(),
+            ignored: HashMap::new(),
+            head,
+            burst: Burst::default(),
+        }
+    }
+
+    fn observe(&mut self, path: &Path, at: Instant, created: bool) {
+        let Ok(rel) = path.strip_prefix(&self.root) else {
+            return;
+        };
+        let rel_str = rel.to_string_lossy().to_string();
+        if rel_str.is_empty()
+            || rel.starts_with(".git")
+            || rel.starts_with(".blameprompt")
+            || self.is_ignored(path)
+        {
+            return;
+        }
+        let Some(content) = read_text(path) else {
+            return;
+        };
+        let new_lines: Vec<String> = content.lines().map(String::from).collect();
+
+        // git rewrote the tree (checkout, pull, stash): take the new content
+        // as the baseline without attributing it.
+        let head = git_head(&self.root);
+        if head != self.head || self.root.join(".git").join("index.lock").exists() {
+            self.head = head;
+            self.files.clear();
+            self.files.insert(
+                path.to_path_buf(),
+                FileState {
+                    lines: new_lines,
+                    changed_at: at,
+                },
+            );
+            return;
+        }
+
+        let (old_lines, since, is_new) = match self.files.get(path) {
+            Some(state) => (state.lines.clone(), state.changed_at, false),
+            None => match index_lines(&self.root, &rel_str) {
+                Some(lines) => (lines, self.started, false),
+                None if created => (Vec::new(), self.started, true),
+                // Untracked file that existed before the watcher started: its
+                // earlier content is unknown, so this write is the baseline.
+                None => (new_lines.clone(), self.started, false),
+            },
+        };
+        self.files.insert(
+            path.to_path_buf(),
+            FileState {
+                lines: new_lines.clone(),
+                changed_at: at,
+            },
+        );
+
+        let Some(ins) = inserted_lines(&old_lines, &new_lines) else {
+            return;
+        };
+        let in_burst = self
+            .burst
+            .last_at
+            .is_some_and(|t| at.duration_since(t) <= self.opts.burst_window);
+        let elapsed = at.duration_since(since).as_secs_f64().max(0.001);
+        if !is_machine_speed(&ins, elapsed, is_new, in_burst, &self.opts) {
+            tracing::debug!(file = %rel_str, lines = ins.lines, elapsed, "human-speed edit");
+            return;
+        }
+
+        tracing::info!(file = %rel_str, lines = ins.lines, "machine-speed edit");
+        self.burst.started_at.get_or_insert_with(Utc::now);
+        self.burst.last_at = Some(at);
+        let entry = self
+            .burst
+            .files
+            .entry(rel_str.clone())
+            .or_insert_with(|| FileChange {
+                path: rel_str,
+                line_range: ins.range,
+                blob_hash: None,
+                additions: 0,
+                deletions: 0,
+                notebook_cells: vec![],
+                is_binary: false,
+                bytes_before: None,
+                bytes_after: None,
+                change_type: None,
+                old_path: None,
+                unstaged_ranges: Vec::new(),
+            });
+        entry.additions += ins.lines;
+        entry.line_range = (
+            entry.line_range.0.min(ins.range.0),
+            entry.line_range.1.max(ins.range.1),
+        );
+    }
+
+    /// The pending burst, once it has been quiet for the burst window.
+    fn take_finished(&mut self, now: Instant) -> Option<Burst> {
+        let last = self.burst.last_at?;
+        if now.duration_since(last) < self.opts.burst_window {
+            return None;
+        }
+        Some(std::mem::take(&mut self.burst))
+    }
+
+    fn is_ignored(&mut self, path: &Path) -> bool {
+        if let Some(ignored) = self.ignored.get(path) {
+            return *ignored;
+        }
+        let ignored = std::process::Command::new("git")
+            .current_dir(&self.root)
+            .args(["check-ignore", "-q"])
+            .arg(path)
+            .status()
+            .is_ok_and(|s| s.success());
+        self.ignored.insert(path.to_path_buf(), ignored);
+        ignored
+    }
+}
+
+fn is_machine_speed(
+    ins: &Insertion,
+    elapsed_secs: f64,
+    is_new: bool,
+    in_burst: bool,
+    opts: &WatchOptions,
+) -> bool {
+    if ins.lines < opts.min_lines {
+        return false;
+    }
+    is_new || in_burst || ins.chars as f64 / elapsed_secs > opts.max_human_cps
+}
+
+/// Lines of `new` that are not in `old` (as a multiset), or None when nothing
+/// was inserted.
+fn inserted_lines(old: &[String], new: &[String]) -> Option<Insertion> {
+    let mut remaining: HashMap<&str, usize> = HashMap::new();
+    for line in old {
+        *remaining.entry(line.as_str()).or_default() += 1;
+    }
+    let mut ins = Insertion {
+        lines: 0,
+        chars: 0,
+        range: (u32::MAX, 0),
+    };
+    for (i, line) in new.iter().enumerate() {
+        match remaining.get_mut(line.as_str()) {
+            Some(n) if *n > 0 => *n -= 1,
+            _ => {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let n = i as u32 + 1;
+                ins.lines += 1;
+                ins.chars += line.len();
+                ins.range = (ins.range.0.min(n), ins.range.1.max(n));
+            }
+        }
+    }
+    (ins.lines > 0).then_some(ins)
+}
+
+fn build_receipt(
+    opts: &WatchOptions,
+    session_id: &str,
+    prompt_number: u32,
+    burst: Burst,
+) -> Receipt {
+    let files_changed: Vec<FileChange> = burst.files.into_values().collect();
+    let total_additions: u32 = files_changed.iter().map(|f| f.additions).sum();
+    let summary = format!(
+        "(heuristic capture: {} line(s) written at machine speed in {} file(s))",
+        total_additions,
+        files_changed.len()
+    );
+    let mut hasher = Sha256::new();
+    hasher.update(session_id.as_bytes());
+    hasher.update(prompt_number.to_le_bytes());
+    let timestamp = burst.started_at.unwrap_or_else(Utc::now);
+
+    Receipt {
+        id: Receipt::new_id(),
+        provider: opts.provider.clone(),
+        model: opts.model.clone().unwrap_or_else(|| "unknown".to_string()),
+        session_id: session_id.to_string(),
+        prompt_summary: summary,
+        response_summary: None,
+        plan_summary: None,
+        prompt_hash: format!("sha256:{:x}", hasher.finalize()),
+        message_count: 0,
+        cost_usd: 0.0,
+        input_tokens: None,
+        output_tokens: None,
+        cache_read_tokens: None,
+        cache_creation_tokens: None,
+        cost_estimated: false,
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        timestamp,
+        session_start: Some(timestamp),
+        session_end: Some(Utc::now()),
+        session_duration_secs: None,
+        ai_response_time_secs: None,
+        prompt_submitted_at: None,
+        prompt_duration_secs: None,
+        accepted_lines: None,
+        overridden_lines: None,
+        user: util::git_user(),
+        file_path: files_changed
+            .first()
+            .map(|f| f.path.clone())
+            .unwrap_or_default(),
+        line_range: files_changed
+            .first()
+            .map(|f| f.line_range)
+            .unwrap_or((1, 1)),
+        files_changed,
+        parent_receipt_id: None,
+        parent_session_id: None,
+        is_continuation: None,
+        continuation_depth: None,
+        prompt_number: Some(prompt_number),
+        tool_use_id: None,
+        total_additions,
+        total_deletions: 0,
+        tools_used: vec![],
+        mcp_servers: vec![],
+        environment: None,
+        agents_spawned: vec![],
+        subagent_activities: vec![],
+        concurrent_tool_calls: None,
+        user_decisions: vec![],
+        conversation: None,
+        prompt_quality: None,
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: true,
+    }
+}
+
+fn read_text(path: &Path) -> Option<String> {
+    let meta = std::fs::metadata(path).ok()?;
+    if !meta.is_file() || meta.len() > MAX_FILE_BYTES {
+        return None;
+    }
+    let bytes = std::fs::read(path).ok()?;
+    if util::is_binary_content(&bytes) {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+/// Lines of the staged (index) version of `rel`, or None when untracked.
+fn index_lines(root: &Path, rel: &str) -> Option<Vec<String>> {
+    let out = std::process::Command::new("git")
+        .current_dir(root)
+        .args(["show", &format!(":{}", rel)])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    Some(
+        String::from_utf8_lossy(&out.stdout)
+            .lines()
+            .map(String::from)
+            .collect(),
+    )
+}
+
+fn git_head(root: &Path) -> Option<String> {
+    let out = std::process::Command::new("git")
+        .current_dir(root)
+        .args(["rev-parse", "HEAD"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())?;
+    Some(String::from_utf8_lossy(&out.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(s: &str) -> Vec<String> {
+        s.lines().map(String::from).collect()
+    }
+
+    fn opts() -> WatchOptions {
+        WatchOptions {
+            provider: "aider".to_string(),
+            model: None,
+            min_lines: 3,
+            max_human_cps: 30.0,
+            burst_window: Duration::from_secs(2),
+        }
+    }
+
+    #[test]
+    fn test_inserted_lines() {
+        let old = lines("fn a() {}\n\nfn b() {}");
+        let new = lines("fn a() {}\n\nfn c() {\n    todo!()\n}\nfn b() {}");
+        let ins = inserted_lines(&old, &new).unwrap();
+        assert_eq!(ins.lines, 3);
+        assert_eq!(ins.range, (3, 5));
+        // Reordering or deleting lines inserts nothing.
+        assert!(inserted_lines(&new, &lines("fn b() {}\nfn a() {}")).is_none());
+    }
+
+    #[test]
+    fn test_is_machine_speed() {
+        let big = Insertion {
+            lines: 40,
+            chars: 1200,
+            range: (1, 40),
+        };
+        let small = Insertion {
+            lines: 2,
+            chars: 5000,
+            range: (1, 2),
+        };
+        // 1200 chars within a second of the last write: not typed by hand.
+        assert!(is_machine_speed(&big, 0.5, false, false, &opts()));
+        // The same amount after ten minutes of editing looks human.
+        assert!(!is_machine_speed(&big, 600.0, false, false, &opts()));
+        // ...unless the file is new or another AI write just happened.
+        assert!(is_machine_speed(&big, 600.0, true, false, &opts()));
+        assert!(is_machine_speed(&big, 600.0, false, true, &opts()));
+        // Below min_lines never counts.
+        assert!(!is_machine_speed(&small, 0.1, true, true, &opts()));
+    }
+}
@@ -0,0 +1,93 @@
+/// `.blameprompt/ignore`: files that never appear in receipts.
+///
+/// Gitignore syntax, read from the repository root. Generated files an agent
+/// rewrites wholesale (lockfiles, `dist/`, snapshots) would otherwise dominate
+/// the AI line counts, e.g.:
+///
+/// ```text
+/// package-lock.json
+/// dist/
+/// **/__snapshots__/
+/// ```
+///
+/// Consulted by the checkpoint handlers when an edit is staged and by the Stop
+/// sweep that picks up files changed outside tracked tool calls.
+use crate::core::util;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+
+pub const IGNORE_FILE: &str = ".blameprompt/ignore";
+
+pub struct CaptureIgnore {
+    root: PathBuf,
+    matcher: Option<Gitignore>,
+}
+
+impl CaptureIgnore {
+    /// The ignore file of the repository containing `cwd` (or of `cwd` itself
+    /// outside a repository). A missing or unreadable file ignores nothing.
+    pub fn load(cwd: &str) -> Self {
+        let cwd = if cwd.is_empty() { "." } else { cwd };
+        let root = util::repo_root_of(Path::new(cwd)).unwrap_or_else(|| cwd.to_string());
+        let patterns = std::fs::read_to_string(Path::new(&root).join(IGNORE_FILE)).ok();
+        Self::from_patterns(Path::new(&root), patterns.as_deref().unwrap_or(""))
+    }
+
+    pub fn from_patterns(root: &Path, patterns: &str) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for line in patterns.lines() {
+            if let Err(e) = builder.add_line(None, line) {
+                tracing::warn!(pattern = line, error = %e, "invalid pattern in {}", IGNORE_FILE);
+            }
+        }
+        let matcher = builder.build().ok().filter(|m| !m.is_empty());
+        CaptureIgnore {
+            root: root.to_path_buf(),
+            matcher,
+        }
+    }
+
+    /// Whether `path` (relative to `cwd`, or absolute) matches a pattern.
+    /// Paths outside the repository are never ignored.
+    pub fn is_ignored(&self, cwd: &str, path: &str) -> bool {
+        let Some(matcher) = &self.matcher else {
+            return false;
+        };
+        let base = if cwd.is_empty() {
+            std::env::current_dir().unwrap_or_default()
+        } else {
+            PathBuf::from(cwd)
+        };
+        let abs = base.join(path);
+        let Ok(rel) = abs.strip_prefix(&self.root) else {
+            return false;
+        };
+        matcher.matched_path_or_any_parents(rel, false).is_ignore()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capture_ignore_patterns() {
+        let root = Path::new("/repo");
+        let ignore = CaptureIgnore::from_patterns(
+            root,
+            "# generated\npackage-lock.json\ndist/\n**/__snapshots__/\n*.min.js\n!keep.min.js\n",
+        );
+        assert!(ignore.is_ignored("/repo", "package-lock.json"));
+        assert!(ignore.is_ignored("/repo", "web/package-lock.json"));
+        assert!(ignore.is_ignored("/repo", "dist/app.js"));
+        assert!(ignore.is_ignored("/repo/web", "dist/app.js"));
+        assert!(ignore.is_ignored("/repo", "src/__snapshots__/a.snap"));
+        assert!(ignore.is_ignored("/repo", "/repo/vendor/x.min.js"));
+        assert!(!ignore.is_ignored("/repo", "vendor/keep.min.js"));
+        assert!(!ignore.is_ignored("/repo", "src/main.rs"));
+        assert!(!ignore.is_ignored("/elsewhere", "package.json"));
+
+        let empty = CaptureIgnore::from_patterns(root, "");
+        assert!(!empty.is_ignored("/repo", "package-lock.json"));
+    }
+}
@@ -1,7 +1,8 @@
-use serde::Deserialize;
-use std::path::{Path, PathBuf};
+use crate::core::org_config;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[allow(dead_code)]
 pub struct BlamePromptConfig {
     #[serde(default)]
@@ -10,9 +11,428 @@ pub struct BlamePromptConfig {
     pub capture: CaptureConfig,
     #[serde(default)]
     pub cloud: CloudConfig,
+    #[serde(default)]
+    pub analytics: AnalyticsConfig,
+    #[serde(default)]
+    pub tickets: TicketConfig,
+    #[serde(default)]
+    pub policy: PolicyConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub privacy: PrivacyConfig,
+    #[serde(default)]
+    pub anomalies: AnomalyConfig,
+    #[serde(default)]
+    pub metrics: MetricsConfig,
+    #[serde(default)]
+    pub logging: LoggingConfig,
+    #[serde(default)]
+    pub server_hook: ServerHookConfig,
+    #[serde(default)]
+    pub summarizer: SummarizerConfig,
+    #[serde(default)]
+    pub compaction: CompactionConfig,
+    #[serde(default)]
+    pub github_comment: GithubCommentConfig,
+    #[serde(default)]
+    pub org: OrgConfig,
+    #[serde(default)]
+    pub digest: DigestConfig,
+    #[serde(default)]
+    pub scan: ScanConfig,
+    #[serde(default)]
+    pub remote: RemoteConfig,
+    #[serde(default)]
+    pub pairing: PairingConfig,
+    #[serde(default)]
+    pub attach: AttachConfig,
+}
+
+/// Pair and mob programming: who else is at the keyboard.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct PairingConfig {
+    /// `Name <email>` of everyone besides the git user, recorded on each
+    /// receipt captured while set. Usually set for the length of a session,
+    /// e.g. `BLAMEPROMPT_PAIRING__CO_AUTHORS='["Jo <jo@example.com>"]'`.
+    pub co_authors: Vec<String>,
+}
+
+/// The post-commit `attach` hook.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AttachConfig {
+    /// Where acceptance stats and line origins are computed: "async" (default,
+    /// a detached `blameprompt finalize` run after the note is written) or
+    /// "sync" (inside the hook, before the commit returns).
+    pub finalize: String,
+    /// Log a warning when the hook takes longer than this.
+    pub budget_ms: u64,
+}
+
+impl Default for AttachConfig {
+    fn default() -> Self {
+        AttachConfig {
+            finalize: "async".to_string(),
+            budget_ms: 300,
+        }
+    }
+}
+
+/// Hook payloads from an agent running in a devcontainer or over SSH carry
+/// that machine's paths; these map them to paths this binary can read.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct RemoteConfig {
+    /// Map paths that don't exist here on a best guess: the payload's `cwd`
+    /// to the hook's working directory, and `.../.claude/...` to this home.
+    pub auto: bool,
+    /// `[[remote.path_map]]` prefixes, tried before the guesses.
+    pub path_map: Vec<PathMapping>,
+}
+
+impl Default for RemoteConfig {
+    fn default() -> Self {
+        RemoteConfig {
+            auto: true,
+            path_map: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PathMapping {
+    /// Path prefix as the agent sees it, e.g. `/workspaces/app`.
+    pub remote: String,
+    /// The same directory as seen here.
+    pub local: String,
+}
+
+/// Paths `vuln-scan`, `license-scan` and `prompt-injection` skip: vendored
+/// directories and generated files are not code anyone reviews line by line.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ScanConfig {
+    /// Gitignore-style patterns, relative to the repository root.
+    pub exclude: Vec<String>,
+    /// Files whose first lines contain one of these are generated.
+    pub generated_markers: Vec<String>,
+    /// `[scan.vuln]`: overrides for `vuln-scan`.
+    pub vuln: ScannerExclusions,
+    /// `[scan.license]`: overrides for `license-scan`.
+    pub license: ScannerExclusions,
+    /// `[scan.prompt_injection]`: overrides for `prompt-injection`.
+    pub prompt_injection: ScannerExclusions,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        ScanConfig {
+            exclude: ["vendor/", "node_modules/", "third_party/"]
+                .map(String::from)
+                .to_vec(),
+            generated_markers: ["@generated", "DO NOT EDIT"].map(String::from).to_vec(),
+            vuln: ScannerExclusions::default(),
+            license: ScannerExclusions::default(),
+            prompt_injection: ScannerExclusions::default(),
+        }
+    }
+}
+
+/// One scanner's overrides of `[scan]`; unset lists inherit it.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ScannerExclusions {
+    /// Replaces `[scan] exclude` (an empty list scans everything).
+    pub exclude: Option<Vec<String>>,
+    /// Added to the shared `exclude` patterns.
+    pub extra_exclude: Vec<String>,
+    /// Replaces `[scan] generated_markers`.
+    pub generated_markers: Option<Vec<String>>,
+}
+
+/// `blameprompt digest`: the AI spend budget it reports against and the SMTP
+/// settings `--send` uses.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct DigestConfig {
+    /// Monthly AI spend budget in USD; 0 leaves budget status out.
+    pub monthly_budget_usd: f64,
+    /// Recipients of `digest --send`.
+    pub to: Vec<String>,
+    pub from: String,
+    pub smtp_host: String,
+    /// 465 connects over TLS; any other port must offer STARTTLS.
+    pub smtp_port: u16,
+    pub smtp_user: String,
+    /// Environment variable holding the SMTP password, so it stays out of
+    /// the config file.
+    pub smtp_password_env: String,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        DigestConfig {
+            monthly_budget_usd: 0.0,
+            to: Vec::new(),
+            from: String::new(),
+            smtp_host: String::new(),
+            smtp_port: 587,
+            smtp_user: String::new(),
+            smtp_password_env: "BLAMEPROMPT_SMTP_PASSWORD".to_string(),
+        }
+    }
+}
+
+/// Where the organization's central config comes from (see `org_config`).
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct OrgConfig {
+    /// HTTPS URL, `<git repo>#<path>`, or local file path.
+    pub source: String,
+    /// Base64 Ed25519 key the org file's `.sig` must verify against.
+    pub public_key: String,
+    /// Accept an org file without a signature when no key is set.
+    pub allow_unsigned: bool,
+    /// In the org file: sections that local config cannot override.
+    pub enforce: Vec<String>,
+}
+
+/// Per-file labels in the `github-comment` PR comment.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct GithubCommentConfig {
+    /// Files with at least this share (percent) of AI lines are "Mostly AI".
+    pub mostly_ai_pct: f64,
+    /// Files with at least this share (percent) of AI lines are "AI-assisted";
+    /// below it they are "Human".
+    pub ai_assisted_pct: f64,
+    /// Fold files without AI lines into a collapsed section.
+    pub collapse_human: bool,
+}
+
+/// Attach-time merging of low-impact receipts from long agentic sessions.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct CompactionConfig {
+    pub enabled: bool,
+    /// Receipts without file changes that cost at most this much count as
+    /// housekeeping.
+    pub max_cost_usd: f64,
+    /// Shortest run of consecutive housekeeping receipts that gets merged.
+    pub min_run: usize,
+}
+
+/// One-line intent summaries stored next to the truncated prompt.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct SummarizerConfig {
+    /// "off" (default), "extractive" (local heuristic, no model) or "ollama"
+    pub mode: String,
+    /// Base URL of the Ollama server used by the "ollama" mode.
+    pub endpoint: String,
+    /// Ollama model name.
+    pub model: String,
+    /// Give up on the model after this long and use the extractive summary.
+    /// Summaries are made while hooks run, so keep this short.
+    pub timeout_secs: u64,
+    /// Longest summary kept, in characters.
+    pub max_chars: usize,
+}
+
+/// Limits enforced by `blameprompt server-hook` on pushed notes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct ServerHookConfig {
+    /// Refs whose updates are validated; other refs pass through untouched.
+    pub refs: Vec<String>,
+    /// Largest accepted note blob.
+    pub max_note_bytes: u64,
+    /// Most receipts a single note may carry.
+    pub max_receipts: usize,
+    /// Oldest accepted `blameprompt_version` in a note (empty: any).
+    pub min_version: String,
+    /// Every new commit on a notes ref must carry a valid GPG/SSH signature
+    /// (`git verify-commit`).
+    pub require_signed: bool,
+    /// Allow deleting a notes ref.
+    pub allow_delete: bool,
+}
+
+/// Log file under `~/.blameprompt/logs/` (see `blameprompt logs tail`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LoggingConfig {
+    /// Write a log file at all. Hooks run without a terminal, so this is the
+    /// only place their decisions show up.
+    pub file: bool,
+    /// "error", "warn", "info", "debug" or "trace", or a full filter such as
+    /// "info,blameprompt::commands::checkpoint=debug". `BLAMEPROMPT_LOG` overrides it.
+    pub level: String,
+    /// Rotate `blameprompt.log` once it reaches this size.
+    pub max_size_mb: u64,
+    /// Rotated files kept (`blameprompt.log.1` ... `.N`).
+    pub keep: usize,
+}
+
+/// Windows for `blameprompt metrics`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct MetricsConfig {
+    /// Days after an AI commit in which a matching fix/revert counts as a failure.
+    pub failure_window_days: i64,
+    /// Days after an AI commit in which changes to its lines count as rework.
+    pub rework_window_days: i64,
+    /// A single follow-up rewriting at least this share (0.0–1.0) of a receipt's
+    /// lines is flagged as rework even without a fix/revert subject.
+    pub rework_min_share: f64,
+    /// Case-insensitive regexes on commit subjects that mark a fix or revert.
+    pub fix_patterns: Vec<String>,
+}
+
+/// Detector thresholds for `blameprompt anomalies` and the hackathon report.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    /// A file whose first receipt adds more than this many lines looks pre-written
+    /// (critical at 2.5x).
+    pub pre_written_min_lines: u32,
+    /// ...as long as it was touched at most this many times in total.
+    pub pre_written_max_touches: usize,
+    /// Commits touching more files than this are checked for receipt coverage.
+    pub batch_commit_min_files: usize,
+    /// ...and flagged when fewer than this share (0.0–1.0) of the files have receipts.
+    pub batch_commit_max_coverage: f64,
+    /// Flag a prompt hash seen at least this many times.
+    pub duplicate_prompt_min: usize,
+    /// A sub-minute prompt producing more than this many lines...
+    pub burst_min_lines: u32,
+    /// ...at more than this many lines per second is flagged.
+    pub burst_lines_per_sec: f64,
+    /// Gap between prompts (minutes) flagged inside a hackathon window (warning at 2x).
+    pub activity_gap_mins: u64,
+    /// Clock-skew allowance (minutes) when comparing commit dates with GitHub push
+    /// times (`hackathon-report --github-repo`).
+    pub push_grace_mins: i64,
+}
+
+/// How much prompt/response text receipts keep.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PrivacyConfig {
+    /// "none", "hash", "redacted" (default) or "full"
+    #[serde(default = "default_prompt_storage")]
+    pub prompt_storage: String,
+}
+
+/// Where receipts are stored. `notes` (default) uses `refs/notes/blameprompt`;
+/// `branch` commits them to an orphan branch and `sidecar` writes them into a
+/// tracked directory, for remotes that reject `refs/notes/*` pushes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct StorageConfig {
+    /// "notes", "branch" or "sidecar"
+    #[serde(default = "default_storage_backend")]
+    pub backend: String,
+    /// Orphan branch used by the `branch` backend.
+    #[serde(default = "default_storage_branch")]
+    pub branch: String,
+    /// Directory (relative to the repo root) used by the `sidecar` backend.
+    #[serde(default = "default_sidecar_dir")]
+    pub sidecar_dir: String,
+}
+
+/// Pass/fail thresholds for CI policy checks (`github-check`, `gerrit-comment`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PolicyConfig {
+    /// Share of commits (0.0–1.0) in the checked range that must carry receipts.
+    #[serde(default)]
+    pub min_receipt_coverage: f64,
+    /// Fail when a secret appears in AI-generated lines.
+    #[serde(default = "default_true")]
+    pub fail_on_secrets: bool,
+    /// Lowest injection-pattern severity that fails: "CRITICAL", "HIGH", "MEDIUM" or "none".
+    #[serde(default = "default_fail_on_injection")]
+    pub fail_on_injection: String,
+    /// Labels `attach` applies from each commit's AI share.
+    #[serde(default)]
+    pub labels: LabelConfig,
+    /// Base64 Ed25519 public keys whose signatures make a policy exemption
+    /// (`.blameprompt-exemptions.toml`) count.
+    #[serde(default)]
+    pub exemption_keys: Vec<String>,
+    /// Honor unsigned exemptions. Off by default, so a bypass needs a key.
+    #[serde(default)]
+    pub allow_unsigned_exemptions: bool,
+}
+
+/// `[policy.labels]`: tag commits and PRs `ai-generated`, `ai-assisted` or
+/// `human` by their share of AI lines, for automation that doesn't read notes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct LabelConfig {
+    /// Where to apply the label: "trailer" (commit message trailer) and/or
+    /// "github" (label on the branch's open PR; needs GITHUB_TOKEN).
+    pub apply: Vec<String>,
+    /// AI share (percent) from which a change is `ai-generated`.
+    pub ai_generated_pct: f64,
+    /// AI share (percent) from which a change is `ai-assisted`; below it, `human`.
+    pub ai_assisted_pct: f64,
+    /// Trailer key, e.g. `AI-Attribution: ai-assisted`.
+    pub trailer: String,
+}
+
+impl Default for LabelConfig {
+    fn default() -> Self {
+        LabelConfig {
+            apply: Vec::new(),
+            ai_generated_pct: 80.0,
+            ai_assisted_pct: 20.0,
+            trailer: "AI-Attribution".to_string(),
+        }
+    }
+}
+
+/// Ticket/issue ID extraction used for cost allocation (`analytics --by-ticket`).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TicketConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Regex matched against the branch name first, then the prompt text.
+    #[serde(default = "default_ticket_pattern")]
+    pub pattern: String,
+    /// Prefixes that look like tickets but never are (e.g. `UTF-8`, `SHA-256`).
+    #[serde(default = "default_ticket_ignore_prefixes")]
+    pub ignore_prefixes: Vec<String>,
+}
+
+/// Controls developer-level reporting. Per-author breakdowns are off unless a
+/// team explicitly opts in, and identities are pseudonymized by default.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnalyticsConfig {
+    #[serde(default)]
+    pub per_author: bool,
+    /// "pseudonym" (stable hashed IDs) or "full" (names and emails as committed)
+    #[serde(default = "default_author_identity")]
+    pub author_identity: String,
+    /// Mixed into the pseudonym hash so IDs cannot be matched across organisations
+    #[serde(default)]
+    pub pseudonym_salt: String,
+    /// Team name -> authors (substrings of `Name <email>`), for per-team
+    /// rollups such as suggestion-to-merge latency.
+    #[serde(default)]
+    pub teams: std::collections::BTreeMap<String, Vec<String>>,
+    /// Which AI share leads in `analytics` and `report`: "raw" (every added
+    /// line) or "normalized" (without blank and brace-only lines). Both are
+    /// reported either way.
+    #[serde(default = "default_line_counting")]
+    pub line_counting: String,
+    /// How `--by-author` credits receipts with co-authors: "split" (cost and
+    /// lines divided among the people) or "share" (everyone gets full credit).
+    #[serde(default = "default_co_author_credit")]
+    pub co_author_credit: String,
 }
 
-#[derive(Debug, Deserialize, Clone, Default)]
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[allow(dead_code)]
 pub struct CloudConfig {
     #[serde(default = "default_api_url")]
@@ -25,13 +445,13 @@ fn default_api_url() -> String {
     "https://api.blameprompt.com".to_string()
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CustomPattern {
     pub pattern: String,
     pub replacement: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct RedactionConfig {
     #[serde(default)]
     pub custom_patterns: Vec<CustomPattern>,
@@ -41,12 +461,39 @@ pub struct RedactionConfig {
     pub mode: String,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CaptureConfig {
     #[serde(default = "default_max_prompt_length")]
     pub max_prompt_length: usize,
     #[serde(default)]
     pub store_full_conversation: bool,
+    /// Store Claude plan-mode output (ExitPlanMode) as `plan_summary` on receipts.
+    #[serde(default)]
+    pub store_plans: bool,
+    /// Also append extended-thinking blocks to `plan_summary` (requires store_plans).
+    #[serde(default)]
+    pub store_thinking: bool,
+    #[serde(default = "default_max_plan_length")]
+    pub max_plan_length: usize,
+    /// How receipts are grouped: "session" (one per session per commit),
+    /// "prompt" (default) or "tool_call" (file changes recorded per tool call).
+    #[serde(default = "default_granularity")]
+    pub granularity: String,
+    /// Set to false to turn capture off (see `blameprompt disable`).
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Repo roots where capture is off. `*` matches anything; `~/` expands to
+    /// the home directory; a plain path also covers its subdirectories.
+    #[serde(default)]
+    pub exclude_repos: Vec<String>,
+    /// Warn when Claude sessions ran for this many days without any hook event
+    /// reaching BlamePrompt (0 turns the check off).
+    #[serde(default = "default_heartbeat_warn_days")]
+    pub heartbeat_warn_days: u64,
+}
+
+fn default_heartbeat_warn_days() -> u64 {
+    3
 }
 
 fn default_redaction_mode() -> String {
@@ -57,6 +504,63 @@ fn default_max_prompt_length() -> usize {
     2000
 }
 
+fn default_max_plan_length() -> usize {
+    4000
+}
+
+fn default_prompt_storage() -> String {
+    "redacted".to_string()
+}
+
+fn default_granularity() -> String {
+    "prompt".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_ticket_pattern() -> String {
+    r"\b[A-Z][A-Z0-9]{1,9}-[0-9]+\b".to_string()
+}
+
+fn default_ticket_ignore_prefixes() -> Vec<String> {
+    [
+        "UTF", "SHA", "GPT", "ISO", "RFC", "CVE", "CWE", "AES", "HTTP", "TLS",
+    ]
+    .iter()
+    .map(|s| s.to_string())
+    .collect()
+}
+
+fn default_fail_on_injection() -> String {
+    "CRITICAL".to_string()
+}
+
+fn default_storage_backend() -> String {
+    "notes".to_string()
+}
+
+fn default_storage_branch() -> String {
+    "blameprompt/receipts".to_string()
+}
+
+fn default_sidecar_dir() -> String {
+    ".blameprompt/receipts".to_string()
+}
+
+fn default_author_identity() -> String {
+    "pseudonym".to_string()
+}
+
+fn default_co_author_credit() -> String {
+    "split".to_string()
+}
+
+fn default_line_counting() -> String {
+    "raw".to_string()
+}
+
 impl Default for RedactionConfig {
     fn default() -> Self {
         RedactionConfig {
@@ -67,55 +571,379 @@ impl Default for RedactionConfig {
     }
 }
 
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            pre_written_min_lines: 80,
+            pre_written_max_touches: 2,
+            batch_commit_min_files: 5,
+            batch_commit_max_coverage: 0.30,
+            duplicate_prompt_min: 2,
+            burst_min_lines: 50,
+            burst_lines_per_sec: 2.0,
+            activity_gap_mins: 90,
+            push_grace_mins: 15,
+        }
+    }
+}
+
+impl Default for MetricsConfig {
+    fn default() -> Self {
+        MetricsConfig {
+            failure_window_days: 14,
+            rework_window_days: 21,
+            rework_min_share: 0.5,
+            fix_patterns: vec![
+                r"^revert\b".to_string(),
+                r"\b(fix|fixes|fixed|hotfix|bugfix)\b".to_string(),
+            ],
+        }
+    }
+}
+
+impl Default for ServerHookConfig {
+    fn default() -> Self {
+        ServerHookConfig {
+            refs: vec!["refs/notes/blameprompt".to_string()],
+            max_note_bytes: 1024 * 1024,
+            max_receipts: 500,
+            min_version: String::new(),
+            require_signed: false,
+            allow_delete: false,
+        }
+    }
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        SummarizerConfig {
+            mode: "off".to_string(),
+            endpoint: "http://localhost:11434".to_string(),
+            model: "llama3.2".to_string(),
+            timeout_secs: 5,
+            max_chars: 80,
+        }
+    }
+}
+
+impl Default for GithubCommentConfig {
+    fn default() -> Self {
+        GithubCommentConfig {
+            mostly_ai_pct: 70.0,
+            ai_assisted_pct: 10.0,
+            collapse_human: true,
+        }
+    }
+}
+
+impl Default for CompactionConfig {
+    fn default() -> Self {
+        CompactionConfig {
+            enabled: false,
+            max_cost_usd: 0.01,
+            min_run: 3,
+        }
+    }
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        LoggingConfig {
+            file: true,
+            level: "info".to_string(),
+            max_size_mb: 5,
+            keep: 3,
+        }
+    }
+}
+
+impl Default for PrivacyConfig {
+    fn default() -> Self {
+        PrivacyConfig {
+            prompt_storage: default_prompt_storage(),
+        }
+    }
+}
+
 impl Default for CaptureConfig {
     fn default() -> Self {
         CaptureConfig {
             max_prompt_length: default_max_prompt_length(),
             store_full_conversation: false,
+            store_plans: false,
+            store_thinking: false,
+            max_plan_length: default_max_plan_length(),
+            granularity: default_granularity(),
+            enabled: true,
+            exclude_repos: Vec::new(),
+            heartbeat_warn_days: default_heartbeat_warn_days(),
         }
     }
 }
 
-fn find_config_file() -> Option<PathBuf> {
-    // Check repo root first
-    let repo_config = Path::new(".blamepromptrc");
-    if repo_config.exists() {
-        return Some(repo_config.to_path_buf());
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        AnalyticsConfig {
+            per_author: false,
+            author_identity: default_author_identity(),
+            pseudonym_salt: String::new(),
+            teams: Default::default(),
+            line_counting: default_line_counting(),
+            co_author_credit: default_co_author_credit(),
+        }
     }
+}
 
-    // Fall back to home directory
-    if let Some(home) = dirs::home_dir() {
-        let home_config = home.join(".blamepromptrc");
-        if home_config.exists() {
-            return Some(home_config);
+impl Default for PolicyConfig {
+    fn default() -> Self {
+        PolicyConfig {
+            min_receipt_coverage: 0.0,
+            fail_on_secrets: true,
+            fail_on_injection: default_fail_on_injection(),
+            labels: LabelConfig::default(),
+            exemption_keys: Vec::new(),
+            allow_unsigned_exemptions: false,
         }
     }
+}
 
-    None
+impl Default for StorageConfig {
+    fn default() -> Self {
+        StorageConfig {
+            backend: default_storage_backend(),
+            branch: default_storage_branch(),
+            sidecar_dir: default_sidecar_dir(),
+        }
+    }
 }
 
-pub fn load_config() -> BlamePromptConfig {
-    match find_config_file() {
-        Some(path) => match std::fs::read_to_string(&path) {
-            Ok(content) => toml::from_str(&content).unwrap_or_else(|e| {
-                eprintln!(
-                    "[BlamePrompt] Warning: Failed to parse {}: {}",
-                    path.display(),
-                    e
-                );
-                BlamePromptConfig::default()
-            }),
-            Err(e) => {
-                eprintln!(
-                    "[BlamePrompt] Warning: Failed to read {}: {}",
-                    path.display(),
-                    e
+impl Default for TicketConfig {
+    fn default() -> Self {
+        TicketConfig {
+            enabled: true,
+            pattern: default_ticket_pattern(),
+            ignore_prefixes: default_ticket_ignore_prefixes(),
+        }
+    }
+}
+
+/// Where a config value was set. Layers are listed lowest precedence first.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Origin {
+    Default,
+    /// The synced org config (`[org] source`); its enforced sections are
+    /// applied last instead.
+    Org(String),
+    System(PathBuf),
+    User(PathBuf),
+    Repo(PathBuf),
+    /// A `BLAMEPROMPT_<SECTION>__<KEY>` environment variable.
+    Env(String),
+}
+
+impl std::fmt::Display for Origin {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Origin::Default => write!(f, "default"),
+            Origin::Org(source) => write!(f, "org ({})", source),
+            Origin::System(path) => write!(f, "system ({})", path.display()),
+            Origin::User(path) => write!(f, "user ({})", path.display()),
+            Origin::Repo(path) => write!(f, "repo ({})", path.display()),
+            Origin::Env(var) => write!(f, "env ({})", var),
+        }
+    }
+}
+
+/// One source of settings.
+#[derive(Debug, Clone)]
+pub struct Layer {
+    pub origin: Origin,
+    pub table: toml::Table,
+}
+
+pub const SYSTEM_CONFIG: &str = "/etc/blameprompt/config.toml";
+pub const REPO_CONFIG: &str = ".blameprompt/config.toml";
+const ENV_PREFIX: &str = "BLAMEPROMPT_";
+
+/// The config files that apply here, lowest precedence first: system, user
+/// (`~/.blamepromptrc`, then `~/.config/blameprompt/config.toml`) and repo
+/// (`.blamepromptrc`, then `.blameprompt/config.toml`).
+fn config_files() -> Vec<Origin> {
+    let mut files = vec![Origin::System(PathBuf::from(SYSTEM_CONFIG))];
+    if let Some(home) = dirs::home_dir() {
+        files.push(Origin::User(home.join(".blamepromptrc")));
+        files.push(Origin::User(home.join(".config/blameprompt/config.toml")));
+    }
+    files.push(Origin::Repo(PathBuf::from(".blamepromptrc")));
+    files.push(Origin::Repo(PathBuf::from(REPO_CONFIG)));
+    files
+}
+
+/// Every layer in effect, lowest precedence first: the config files that
+/// exist, then environment overrides. Unreadable files are skipped with a
+/// warning.
+pub fn load_layers() -> Vec<Layer> {
+    let mut layers = Vec::new();
+    for origin in config_files() {
+        let (Origin::System(path) | Origin::User(path) | Origin::Repo(path)) = &origin else {
+            continue;
+        };
+        if !path.exists() {
+            continue;
+        }
+        let parsed = std::fs::read_to_string(path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| content.parse::<toml::Table>().map_err(|e| e.to_string()));
+        match parsed {
+            Ok(table) => layers.push(Layer { origin, table }),
+            Err(e) => eprintln!(
+                "[BlamePrompt] Warning: Failed to parse {}: {}",
+                path.display(),
+                e
+            ),
+        }
+    }
+    layers.extend(env_layers(std::env::vars()));
+    layers
+}
+
+/// `BLAMEPROMPT_REDACTION__MODE=hash` sets `[redaction] mode`; `__` separates
+/// the parts of the key. Values are read as TOML (`true`, `5`, `["a"]`) and
+/// fall back to plain strings. Variables without `__` are not config.
+pub fn env_layers(vars: impl Iterator<Item = (String, String)>) -> Vec<Layer> {
+    let mut vars: Vec<(String, String)> = vars
+        .filter(|(name, _)| name.starts_with(ENV_PREFIX) && name.contains("__"))
+        .collect();
+    vars.sort();
+    vars.into_iter()
+        .filter_map(|(name, raw)| {
+            let path: Vec<String> = name[ENV_PREFIX.len()..]
+                .split("__")
+                .map(str::to_lowercase)
+                .collect();
+            if path.iter().any(String::is_empty) {
+                return None;
+            }
+            let value = format!("v = {}", raw)
+                .parse::<toml::Table>()
+                .ok()
+                .and_then(|mut t| t.remove("v"))
+                .unwrap_or(toml::Value::String(raw));
+            let table = path.iter().rev().fold(None, |inner, key| {
+                let mut t = toml::Table::new();
+                t.insert(
+                    key.clone(),
+                    inner.map(toml::Value::Table).unwrap_or(value.clone()),
                 );
-                BlamePromptConfig::default()
+                Some(t)
+            })?;
+            Some(Layer {
+                origin: Origin::Env(name),
+                table,
+            })
+        })
+        .collect()
+}
+
+/// The effective settings from `layers`, and which layer set each key
+/// (dotted path to a non-table value). When the merged layers name an
+/// `[org] source` that has been synced, the org config goes underneath them
+/// and its enforced sections on top.
+pub fn resolve(layers: &[Layer], org: Option<(String, toml::Table)>) -> ResolvedConfig {
+    let mut table = toml::Table::new();
+    let mut origins = std::collections::BTreeMap::new();
+    for layer in layers {
+        record_origins(&layer.table, "", &layer.origin, &mut origins);
+        org_config::overlay(&mut table, layer.table.clone());
+    }
+    if let Some((source, org)) = org {
+        let origin = Origin::Org(source);
+        let mut from_org = std::collections::BTreeMap::new();
+        record_origins(&org, "", &origin, &mut from_org);
+        let enforced = org_config::enforced_sections(&org);
+        table = org_config::merge(org, table);
+        // Enforced sections come from the org file whole; local keys in them
+        // no longer apply.
+        origins.retain(|key: &String, _| {
+            let section = key.split('.').next().unwrap_or_default();
+            section == "org" || !enforced.iter().any(|e| e == section)
+        });
+        for (key, o) in from_org {
+            let section = key.split('.').next().unwrap_or_default();
+            if section == "org" {
+                continue;
             }
-        },
-        None => BlamePromptConfig::default(),
+            if enforced.iter().any(|e| e == section) || !origins.contains_key(&key) {
+                origins.insert(key, o);
+            }
+        }
     }
+    ResolvedConfig { table, origins }
+}
+
+fn record_origins(
+    table: &toml::Table,
+    prefix: &str,
+    origin: &Origin,
+    origins: &mut std::collections::BTreeMap<String, Origin>,
+) {
+    for (key, value) in table {
+        let path = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::Table(t) => record_origins(t, &path, origin, origins),
+            _ => {
+                origins.insert(path, origin.clone());
+            }
+        }
+    }
+}
+
+pub struct ResolvedConfig {
+    pub table: toml::Table,
+    /// Dotted key -> the layer that set it; keys not listed are defaults.
+    pub origins: std::collections::BTreeMap<String, Origin>,
+}
+
+impl ResolvedConfig {
+    pub fn config(&self) -> Result<BlamePromptConfig, String> {
+        toml::Value::Table(self.table.clone())
+            .try_into()
+            .map_err(|e: toml::de::Error| e.to_string())
+    }
+
+    pub fn origin(&self, key: &str) -> &Origin {
+        self.origins.get(key).unwrap_or(&Origin::Default)
+    }
+}
+
+/// Every layer in effect here, with the synced org config when one is named.
+pub fn resolve_current() -> ResolvedConfig {
+    let layers = load_layers();
+    let local = resolve(&layers, None);
+    let org = local
+        .table
+        .get("org")
+        .and_then(|o| o.get("source"))
+        .and_then(|s| s.as_str())
+        .and_then(|source| Some((source.to_string(), org_config::cached(source)?)));
+    match org {
+        Some(org) => resolve(&layers, Some(org)),
+        None => local,
+    }
+}
+
+/// The config in effect: system, user and repo config files and environment
+/// overrides, each layered over the last, over the synced org config when
+/// `[org] source` names one.
+pub fn load_config() -> BlamePromptConfig {
+    resolve_current().config().unwrap_or_else(|e| {
+        eprintln!("[BlamePrompt] Warning: Invalid config: {}", e);
+        BlamePromptConfig::default()
+    })
 }
 
 #[cfg(test)]
@@ -127,9 +955,134 @@ mod tests {
         let config = BlamePromptConfig::default();
         assert_eq!(config.capture.max_prompt_length, 2000);
         assert!(!config.capture.store_full_conversation);
+        assert!(!config.capture.store_plans);
+        assert!(!config.capture.store_thinking);
+        assert_eq!(config.capture.max_plan_length, 4000);
+        assert_eq!(config.capture.granularity, "prompt");
+        assert!(config.capture.enabled);
+        assert!(config.capture.exclude_repos.is_empty());
+        assert_eq!(config.privacy.prompt_storage, "redacted");
         assert_eq!(config.redaction.mode, "replace");
         assert!(config.redaction.custom_patterns.is_empty());
         assert!(config.redaction.disable_patterns.is_empty());
+        assert_eq!(config.policy.min_receipt_coverage, 0.0);
+        assert!(config.policy.fail_on_secrets);
+        assert_eq!(config.policy.fail_on_injection, "CRITICAL");
+        assert_eq!(config.storage.backend, "notes");
+        assert_eq!(config.storage.sidecar_dir, ".blameprompt/receipts");
+    }
+
+    #[test]
+    fn test_parse_anomaly_config() {
+        let toml_str = r#"
+[anomalies]
+pre_written_min_lines = 150
+batch_commit_max_coverage = 0.5
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.anomalies.pre_written_min_lines, 150);
+        assert_eq!(config.anomalies.batch_commit_max_coverage, 0.5);
+        assert_eq!(config.anomalies.duplicate_prompt_min, 2);
+        assert_eq!(config.anomalies.activity_gap_mins, 90);
+        assert_eq!(config.anomalies.push_grace_mins, 15);
+    }
+
+    #[test]
+    fn test_parse_metrics_config() {
+        let toml_str = r#"
+[metrics]
+rework_window_days = 7
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.metrics.rework_window_days, 7);
+        assert_eq!(config.metrics.failure_window_days, 14);
+        assert_eq!(config.metrics.fix_patterns.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_logging_config() {
+        let toml_str = r#"
+[logging]
+level = "debug"
+keep = 1
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.logging.level, "debug");
+        assert_eq!(config.logging.keep, 1);
+        assert!(config.logging.file);
+        assert_eq!(config.logging.max_size_mb, 5);
+    }
+
+    #[test]
+    fn test_parse_server_hook_config() {
+        let toml_str = r#"
+[server_hook]
+max_note_bytes = 65536
+require_signed = true
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.server_hook.max_note_bytes, 65536);
+        assert!(config.server_hook.require_signed);
+        assert_eq!(config.server_hook.refs, vec!["refs/notes/blameprompt"]);
+        assert_eq!(config.server_hook.max_receipts, 500);
+    }
+
+    #[test]
+    fn test_parse_summarizer_config() {
+        let toml_str = r#"
+[summarizer]
+mode = "ollama"
+model = "qwen2.5:3b"
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.summarizer.mode, "ollama");
+        assert_eq!(config.summarizer.model, "qwen2.5:3b");
+        assert_eq!(config.summarizer.endpoint, "http://localhost:11434");
+        assert_eq!(config.summarizer.max_chars, 80);
+        assert_eq!(BlamePromptConfig::default().summarizer.mode, "off");
+    }
+
+    #[test]
+    fn test_parse_compaction_config() {
+        let toml_str = r#"
+[compaction]
+enabled = true
+max_cost_usd = 0.05
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.compaction.enabled);
+        assert_eq!(config.compaction.max_cost_usd, 0.05);
+        assert_eq!(config.compaction.min_run, 3);
+        assert!(!BlamePromptConfig::default().compaction.enabled);
+    }
+
+    #[test]
+    fn test_parse_storage_config() {
+        let toml_str = r#"
+[storage]
+backend = "branch"
+branch = "provenance"
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.storage.backend, "branch");
+        assert_eq!(config.storage.branch, "provenance");
+        assert_eq!(config.storage.sidecar_dir, ".blameprompt/receipts");
+    }
+
+    #[test]
+    fn test_parse_policy_config() {
+        let toml_str = r#"
+[policy]
+min_receipt_coverage = 0.8
+fail_on_injection = "HIGH"
+exemption_keys = ["MCowBQYDK2VwAyEA"]
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.policy.min_receipt_coverage, 0.8);
+        assert!(config.policy.fail_on_secrets);
+        assert_eq!(config.policy.fail_on_injection, "HIGH");
+        assert_eq!(config.policy.exemption_keys, vec!["MCowBQYDK2VwAyEA"]);
+        assert!(!config.policy.allow_unsigned_exemptions);
     }
 
     #[test]
@@ -167,5 +1120,165 @@ max_prompt_length = 1000
         let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
         assert_eq!(config.capture.max_prompt_length, 1000);
         assert_eq!(config.redaction.mode, "replace");
+        assert!(!config.analytics.per_author);
+        assert_eq!(config.analytics.author_identity, "pseudonym");
+        assert_eq!(config.analytics.line_counting, "raw");
+    }
+
+    #[test]
+    fn test_parse_analytics_config() {
+        let toml_str = r#"
+[analytics]
+per_author = true
+author_identity = "full"
+pseudonym_salt = "acme"
+line_counting = "normalized"
+
+[analytics.teams]
+platform = ["dana@example.com", "lee@"]
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.analytics.per_author);
+        assert_eq!(config.analytics.author_identity, "full");
+        assert_eq!(config.analytics.pseudonym_salt, "acme");
+        assert_eq!(config.analytics.teams["platform"].len(), 2);
+        assert_eq!(config.analytics.line_counting, "normalized");
+    }
+
+    #[test]
+    fn test_parse_label_config() {
+        let toml_str = r#"
+[policy]
+min_receipt_coverage = 0.5
+
+[policy.labels]
+apply = ["trailer", "github"]
+ai_generated_pct = 90.0
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.policy.labels.apply, ["trailer", "github"]);
+        assert_eq!(config.policy.labels.ai_generated_pct, 90.0);
+        assert_eq!(config.policy.labels.ai_assisted_pct, 20.0);
+        assert_eq!(config.policy.labels.trailer, "AI-Attribution");
+        assert!(BlamePromptConfig::default().policy.labels.apply.is_empty());
+    }
+
+    #[test]
+    fn test_parse_org_config() {
+        let toml_str = r#"
+[org]
+source = "git@example.com:acme/policy.git#blameprompt.toml"
+public_key = "AAAA"
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.org.source.ends_with("#blameprompt.toml"));
+        assert_eq!(config.org.public_key, "AAAA");
+        assert!(!config.org.allow_unsigned);
+        assert!(BlamePromptConfig::default().org.enforce.is_empty());
+    }
+
+    #[test]
+    fn test_parse_digest_config() {
+        let toml_str = r#"
+[digest]
+monthly_budget_usd = 500.0
+to = ["eng-leads@example.com"]
+smtp_host = "smtp.example.com"
+smtp_port = 465
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.digest.monthly_budget_usd, 500.0);
+        assert_eq!(config.digest.to, ["eng-leads@example.com"]);
+        assert_eq!(config.digest.smtp_port, 465);
+        assert_eq!(config.digest.smtp_password_env, "BLAMEPROMPT_SMTP_PASSWORD");
+        assert_eq!(BlamePromptConfig::default().digest.smtp_port, 587);
+    }
+
+    #[test]
+    fn test_parse_scan_config() {
+        let toml_str = r#"
+[scan]
+exclude = ["vendor/", "gen/"]
+
+[scan.vuln]
+extra_exclude = ["fixtures/"]
+
+[scan.license]
+exclude = []
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.scan.exclude, ["vendor/", "gen/"]);
+        assert_eq!(config.scan.generated_markers, ["@generated", "DO NOT EDIT"]);
+        assert_eq!(config.scan.vuln.extra_exclude, ["fixtures/"]);
+        assert_eq!(config.scan.license.exclude, Some(Vec::new()));
+        assert!(config.scan.prompt_injection.exclude.is_none());
+        assert!(BlamePromptConfig::default()
+            .scan
+            .exclude
+            .contains(&"node_modules/".to_string()));
+    }
+
+    #[test]
+    fn test_parse_remote_config() {
+        let toml_str = r#"
+[[remote.path_map]]
+remote = "/workspaces/app"
+local = "/home/dana/src/app"
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.remote.auto);
+        assert_eq!(config.remote.path_map.len(), 1);
+        assert_eq!(config.remote.path_map[0].remote, "/workspaces/app");
+        assert_eq!(config.remote.path_map[0].local, "/home/dana/src/app");
+    }
+
+    #[test]
+    fn test_parse_pairing_config() {
+        let toml_str = r#"
+[pairing]
+co_authors = ["Jo Park <jo@example.com>"]
+
+[analytics]
+co_author_credit = "share"
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.pairing.co_authors, vec!["Jo Park <jo@example.com>"]);
+        assert_eq!(config.analytics.co_author_credit, "share");
+        let config: BlamePromptConfig = toml::from_str("").unwrap();
+        assert!(config.pairing.co_authors.is_empty());
+        assert_eq!(config.analytics.co_author_credit, "split");
+    }
+
+    #[test]
+    fn test_parse_attach_config() {
+        let config: BlamePromptConfig = toml::from_str("[attach]\nfinalize = \"sync\"\n").unwrap();
+        assert_eq!(config.attach.finalize, "sync");
+        assert_eq!(config.attach.budget_ms, 300);
+        let config: BlamePromptConfig = toml::from_str("").unwrap();
+        assert_eq!(config.attach.finalize, "async");
+    }
+
+    #[test]
+    fn test_parse_ticket_config() {
+        let toml_str = r##"
+[tickets]
+pattern = "#[0-9]+"
+"##;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert!(config.tickets.enabled);
+        assert_eq!(config.tickets.pattern, "#[0-9]+");
+        assert!(config.tickets.ignore_prefixes.contains(&"UTF".to_string()));
+    }
+
+    #[test]
+    fn test_parse_github_comment_config() {
+        let toml_str = r#"
+[github_comment]
+mostly_ai_pct = 80
+"#;
+        let config: BlamePromptConfig = toml::from_str(toml_str).unwrap();
+        assert_eq!(config.github_comment.mostly_ai_pct, 80.0);
+        assert_eq!(config.github_comment.ai_assisted_pct, 10.0);
+        assert!(config.github_comment.collapse_human);
     }
 }
@@ -1,9 +1,26 @@
+/// Local SQLite cache of receipts (`~/.blameprompt/prompts.db`).
+///
+/// The daemon, `cache sync`, `check-provenance` and friends may open it at the
+/// same time, so connections use WAL mode (readers never wait on a writer)
+/// and a busy timeout, and every write goes through [`write`], which queues
+/// this process's writers and runs each batch in one transaction. The cache
+/// is only an accelerator: callers fall back to reading notes when it is
+/// locked or unavailable.
 use crate::core::receipt::Receipt;
 use crate::git::notes;
-use rusqlite::{params, Connection};
-use std::path::PathBuf;
+use rusqlite::{params, Connection, Transaction, TransactionBehavior};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
 
-fn db_path() -> PathBuf {
+/// How long a statement waits for another connection's write lock.
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Queue for this process's writers, so threads wait their turn here rather
+/// than all contending for SQLite's lock.
+static WRITE_QUEUE: Mutex<()> = Mutex::new(());
+
+pub fn db_path() -> PathBuf {
     let home = dirs::home_dir().unwrap_or_else(|| PathBuf::from("."));
     let dir = home.join(".blameprompt");
     let _ = std::fs::create_dir_all(&dir);
@@ -11,8 +28,18 @@ fn db_path() -> PathBuf {
 }
 
 pub fn get_connection() -> Result<Connection, String> {
-    let path = db_path();
-    let conn = Connection::open(&path).map_err(|e| format!("Cannot open database: {}", e))?;
+    open(&db_path())
+}
+
+fn open(path: &Path) -> Result<Connection, String> {
+    let conn = Connection::open(path).map_err(|e| format!("Cannot open database: {}", e))?;
+    conn.busy_timeout(BUSY_TIMEOUT)
+        .map_err(|e| format!("Cannot configure database: {}", e))?;
+    // Switching to WAL needs the write lock once; if another process holds
+    // it, a later connection makes the switch.
+    let _ =
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0));
+    let _ = conn.pragma_update(None, "synchronous", "NORMAL");
 
     conn.execute_batch(
         "CREATE TABLE IF NOT EXISTS receipts (
@@ -42,9 +69,163 @@ pub fn get_connection() -> Result<Connection, String> {
     )
     .map_err(|e| format!("Cannot create table: {}", e))?;
 
+    conn.execute_batch(
+        "CREATE TABLE IF NOT EXISTS provenance_cache (
+            blob_sha TEXT NOT NULL,
+            path TEXT NOT NULL,
+            context TEXT NOT NULL,
+            lines_json TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (blob_sha, path)
+        );",
+    )
+    .map_err(|e| format!("Cannot create table: {}", e))?;
+
+    // Indexes for `search` filters. Creating them takes the write lock once;
+    // if another process holds it, a later connection creates them.
+    let _ = conn.execute_batch(
+        "CREATE INDEX IF NOT EXISTS idx_receipts_commit ON receipts (commit_sha);
+         CREATE INDEX IF NOT EXISTS idx_receipts_provider ON receipts (provider COLLATE NOCASE);
+         CREATE INDEX IF NOT EXISTS idx_receipts_timestamp ON receipts (timestamp);
+         CREATE INDEX IF NOT EXISTS idx_receipts_cost ON receipts (cost_usd);",
+    );
+
     Ok(conn)
 }
 
+/// Receipt filters the cache can answer; see [`candidate_commits`].
+#[derive(Debug, Default)]
+pub struct ReceiptQuery<'a> {
+    /// Exact, case-insensitive.
+    pub provider: Option<&'a str>,
+    /// Substrings, case-insensitive.
+    pub model: Option<&'a str>,
+    pub author: Option<&'a str>,
+    /// RFC 3339 bounds, `from` inclusive and `to` exclusive.
+    pub from: Option<String>,
+    pub to: Option<String>,
+    pub min_cost: Option<f64>,
+}
+
+/// Commits with at least one cached receipt matching `query`. None when the
+/// cache is unavailable or misses some of `commits` (not synced since they
+/// were attached), in which case the caller should scan the notes instead.
+pub fn candidate_commits(
+    commits: &[String],
+    query: &ReceiptQuery,
+) -> Option<std::collections::HashSet<String>> {
+    use rusqlite::types::Value;
+
+    let conn = get_connection().ok()?;
+    let cached: std::collections::HashSet<String> = conn
+        .prepare("SELECT DISTINCT commit_sha FROM receipts")
+        .ok()?
+        .query_map([], |row| row.get(0))
+        .ok()?
+        .collect::<Result<_, _>>()
+        .ok()?;
+    if cached.is_empty() || commits.iter().any(|c| !cached.contains(c)) {
+        return None;
+    }
+
+    let mut clauses = Vec::new();
+    let mut values: Vec<Value> = Vec::new();
+    let mut add = |clause: &str, value: Value| {
+        values.push(value);
+        clauses.push(clause.replace('?', &format!("?{}", values.len())));
+    };
+    if let Some(p) = query.provider {
+        add("provider = ? COLLATE NOCASE", Value::Text(p.to_string()));
+    }
+    if let Some(m) = query.model {
+        add("model LIKE ?", Value::Text(format!("%{}%", m)));
+    }
+    if let Some(a) = query.author {
+        add("user LIKE ?", Value::Text(format!("%{}%", a)));
+    }
+    if let Some(from) = &query.from {
+        add("timestamp >= ?", Value::Text(from.clone()));
+    }
+    if let Some(to) = &query.to {
+        add("timestamp < ?", Value::Text(to.clone()));
+    }
+    if let Some(cost) = query.min_cost {
+        add("cost_usd >= ?", Value::Real(cost));
+    }
+    let mut sql = "SELECT DISTINCT commit_sha FROM receipts".to_string();
+    if !clauses.is_empty() {
+        sql.push_str(" WHERE ");
+        sql.push_str(&clauses.join(" AND "));
+    }
+    let mut stmt = conn.prepare(&sql).ok()?;
+    let rows = stmt
+        .query_map(rusqlite::params_from_iter(values), |row| row.get(0))
+        .ok()?;
+    rows.collect::<Result<_, _>>().ok()
+}
+
+/// Run `f` in one write transaction, after this process's earlier writes.
+/// Waits up to the busy timeout for other processes; the error then says the
+/// cache is locked (see [`is_locked`]) and nothing is written.
+pub fn write<T>(
+    conn: &Connection,
+    f: impl FnOnce(&Transaction) -> Result<T, String>,
+) -> Result<T, String> {
+    let _turn = WRITE_QUEUE.lock().unwrap_or_else(|e| e.into_inner());
+    let tx = Transaction::new_unchecked(conn, TransactionBehavior::Immediate)
+        .map_err(|e| format!("Cannot write cache: {}", e))?;
+    let out = f(&tx)?;
+    tx.commit()
+        .map_err(|e| format!("Cannot write cache: {}", e))?;
+    Ok(out)
+}
+
+/// Whether a cache error means another process held the lock too long.
+pub fn is_locked(err: &str) -> bool {
+    err.contains("database is locked") || err.contains("database table is locked")
+}
+
+/// Cached line provenance for a file blob, if it was computed under the same
+/// `context` (receipt store version + last commit touching the file).
+pub fn get_cached_provenance(
+    conn: &Connection,
+    blob_sha: &str,
+    path: &str,
+    context: &str,
+) -> Option<String> {
+    conn.query_row(
+        "SELECT lines_json FROM provenance_cache WHERE blob_sha = ?1 AND path = ?2 AND context = ?3",
+        params![blob_sha, path, context],
+        |row| row.get(0),
+    )
+    .ok()
+}
+
+/// Store line provenance for a file blob, replacing any stale entry, and drop
+/// entries that have not been refreshed for 30 days.
+pub fn put_cached_provenance(
+    conn: &Connection,
+    blob_sha: &str,
+    path: &str,
+    context: &str,
+    lines_json: &str,
+) -> Result<(), String> {
+    let now = chrono::Utc::now();
+    write(conn, |tx| {
+        tx.execute(
+            "INSERT OR REPLACE INTO provenance_cache (blob_sha, path, context, lines_json, created_at) VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![blob_sha, path, context, lines_json, now.to_rfc3339()],
+        )
+        .map_err(|e| format!("Cannot write provenance cache: {}", e))?;
+        tx.execute(
+            "DELETE FROM provenance_cache WHERE created_at < ?1",
+            params![(now - chrono::Duration::days(30)).to_rfc3339()],
+        )
+        .map_err(|e| format!("Cannot prune provenance cache: {}", e))?;
+        Ok(())
+    })
+}
+
 pub fn insert_receipt(conn: &Connection, commit_sha: &str, r: &Receipt) -> Result<(), String> {
     conn.execute(
         "INSERT OR REPLACE INTO receipts (id, commit_sha, provider, model, session_id, prompt_summary, prompt_hash, message_count, cost_usd, timestamp, session_start, session_end, session_duration_secs, ai_response_time_secs, user, file_path, line_start, line_end, parent_receipt_id, parent_session_id, is_continuation, continuation_depth) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18, ?19, ?20, ?21, ?22)",
@@ -77,6 +258,115 @@ pub fn insert_receipt(conn: &Connection, commit_sha: &str, r: &Receipt) -> Resul
     Ok(())
 }
 
+const RECEIPT_COLUMNS: &[&str] = &[
+    "id",
+    "commit_sha",
+    "provider",
+    "model",
+    "session_id",
+    "prompt_summary",
+    "prompt_hash",
+    "message_count",
+    "cost_usd",
+    "timestamp",
+    "session_start",
+    "session_end",
+    "session_duration_secs",
+    "ai_response_time_secs",
+    "user",
+    "file_path",
+    "line_start",
+    "line_end",
+    "parent_receipt_id",
+    "parent_session_id",
+    "is_continuation",
+    "continuation_depth",
+];
+
+/// Raw cache rows for one commit, as column → value maps (used by `bundle export`).
+pub fn cached_rows_for_commit(
+    conn: &Connection,
+    commit_sha: &str,
+) -> Result<Vec<serde_json::Map<String, serde_json::Value>>, String> {
+    use rusqlite::types::ValueRef;
+
+    let sql = format!(
+        "SELECT {} FROM receipts WHERE commit_sha = ?1",
+        RECEIPT_COLUMNS.join(", ")
+    );
+    let mut stmt = conn
+        .prepare(&sql)
+        .map_err(|e| format!("Cannot query cache: {}", e))?;
+    let rows = stmt
+        .query_map(params![commit_sha], |row| {
+            let mut map = serde_json::Map::new();
+            for (i, col) in RECEIPT_COLUMNS.iter().enumerate() {
+                let value = match row.get_ref(i)? {
+                    ValueRef::Null | ValueRef::Blob(_) => serde_json::Value::Null,
+                    ValueRef::Integer(n) => n.into(),
+                    ValueRef::Real(f) => f.into(),
+                    ValueRef::Text(t) => String::from_utf8_lossy(t).into(),
+                };
+                map.insert(col.to_string(), value);
+            }
+            Ok(map)
+        })
+        .map_err(|e| format!("Cannot query cache: {}", e))?;
+    rows.collect::<Result<_, _>>()
+        .map_err(|e| format!("Cannot read cache row: {}", e))
+}
+
+/// Insert a raw cache row produced by [`cached_rows_for_commit`] under `commit_sha`.
+/// Unknown columns are ignored.
+pub fn insert_cached_row(
+    conn: &Connection,
+    commit_sha: &str,
+    row: &serde_json::Map<String, serde_json::Value>,
+) -> Result<(), String> {
+    use rusqlite::types::Value;
+
+    let mut cols = vec!["commit_sha"];
+    let mut values = vec![Value::Text(commit_sha.to_string())];
+    for col in RECEIPT_COLUMNS.iter().filter(|c| **c != "commit_sha") {
+        let value = match row.get(*col) {
+            Some(serde_json::Value::String(s)) => Value::Text(s.clone()),
+            Some(serde_json::Value::Number(n)) => match n.as_i64() {
+                Some(i) => Value::Integer(i),
+                None => Value::Real(n.as_f64().unwrap_or_default()),
+            },
+            Some(serde_json::Value::Bool(b)) => Value::Integer(*b as i64),
+            _ => continue,
+        };
+        cols.push(col);
+        values.push(value);
+    }
+    let placeholders: Vec<String> = (1..=cols.len()).map(|i| format!("?{}", i)).collect();
+    let sql = format!(
+        "INSERT OR REPLACE INTO receipts ({}) VALUES ({})",
+        cols.join(", "),
+        placeholders.join(", ")
+    );
+    conn.execute(&sql, rusqlite::params_from_iter(values))
+        .map_err(|e| format!("Cannot insert receipt: {}", e))?;
+    Ok(())
+}
+
+/// Delete cached receipts by ID and drop the provenance cache, whose entries
+/// may still link lines to them. Returns the number of receipt rows deleted.
+pub fn forget_receipts(conn: &Connection, ids: &[String]) -> Result<usize, String> {
+    write(conn, |tx| {
+        let mut deleted = 0;
+        for id in ids {
+            deleted += tx
+                .execute("DELETE FROM receipts WHERE id = ?1", params![id])
+                .map_err(|e| format!("Cannot delete cached receipt: {}", e))?;
+        }
+        tx.execute("DELETE FROM provenance_cache", [])
+            .map_err(|e| format!("Cannot clear provenance cache: {}", e))?;
+        Ok(deleted)
+    })
+}
+
 /// Sync all Git Notes into the SQLite cache.
 pub fn sync_from_notes() -> Result<(), String> {
     let conn = get_connection()?;
@@ -87,15 +377,31 @@ pub fn sync_from_notes() -> Result<(), String> {
         return Ok(());
     }
 
-    let mut count = 0;
-    for sha in &commits {
-        if let Some(payload) = notes::read_receipts_for_commit(sha) {
+    // Read the notes first so the write lock is held only for the inserts.
+    let payloads: Vec<_> = commits
+        .iter()
+        .filter_map(|sha| Some((sha, notes::read_receipts_for_commit(sha)?)))
+        .collect();
+    let count = write(&conn, |tx| {
+        let mut count = 0;
+        for (sha, payload) in &payloads {
             for receipt in &payload.receipts {
-                insert_receipt(&conn, sha, receipt)?;
+                insert_receipt(tx, sha, receipt)?;
                 count += 1;
             }
         }
-    }
+        Ok(count)
+    })
+    .map_err(|e| {
+        if is_locked(&e) {
+            format!(
+                "{} (another BlamePrompt process is writing the cache; try again shortly, commands read notes meanwhile)",
+                e
+            )
+        } else {
+            e
+        }
+    })?;
 
     println!(
         "[BlamePrompt] Cached {} receipt(s) from {} commit(s) into SQLite.",
@@ -173,6 +479,7 @@ pub fn search_prompts(query: &str, limit: usize) -> Result<Vec<(String, Receipt)
                     total_deletions: 0,
                     tools_used: vec![],
                     mcp_servers: vec![],
+                    environment: None,
                     agents_spawned: vec![],
                     subagent_activities: vec![],
                     concurrent_tool_calls: None,
@@ -184,6 +491,16 @@ pub fn search_prompts(query: &str, limit: usize) -> Result<Vec<(String, Receipt)
                     accepted_lines: None,
                     overridden_lines: None,
                     prompt_quality: None,
+                    ticket: None,
+                    manual_import: false,
+                    housekeeping_prompts: None,
+                    intent_summary: None,
+                    heuristic: false,
+                    cost_estimated: false,
+                    acceptance_estimated: false,
+                    co_authors: Vec::new(),
+                    plan_summary: None,
+                    tool_use_id: None,
                 },
             ))
         })
@@ -196,3 +513,45 @@ pub fn search_prompts(query: &str, limit: usize) -> Result<Vec<(String, Receipt)
 
     Ok(results)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_reads_during_write_and_reports_lock() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prompts.db");
+        let writer = open(&path).unwrap();
+        let reader = open(&path).unwrap();
+        let mode: String = reader
+            .query_row("PRAGMA journal_mode", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(mode, "wal");
+
+        put_cached_provenance(&writer, "b1", "a.rs", "ctx", "[]").unwrap();
+        writer.execute_batch("BEGIN IMMEDIATE").unwrap();
+        writer
+            .execute(
+                "INSERT INTO provenance_cache VALUES ('b2', 'b.rs', 'ctx', '[]', 'now')",
+                [],
+            )
+            .unwrap();
+
+        // WAL: the committed row stays readable while the other write is open.
+        assert_eq!(
+            get_cached_provenance(&reader, "b1", "a.rs", "ctx").as_deref(),
+            Some("[]")
+        );
+        reader.busy_timeout(Duration::from_millis(50)).unwrap();
+        let err = put_cached_provenance(&reader, "b3", "c.rs", "ctx", "[]").unwrap_err();
+        assert!(is_locked(&err), "{}", err);
+
+        writer.execute_batch("COMMIT").unwrap();
+        put_cached_provenance(&reader, "b3", "c.rs", "ctx", "[]").unwrap();
+        let rows: i64 = reader
+            .query_row("SELECT COUNT(*) FROM provenance_cache", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(rows, 3);
+    }
+}
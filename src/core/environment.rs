@@ -0,0 +1,256 @@
+/// Reproducibility context for a receipt: which agent version wrote the code,
+/// with which generation settings, through which MCP server versions.
+///
+/// Claude Code stamps its version on every transcript entry. Sampling settings
+/// are recorded when anything exposes them: request fields a proxy left in the
+/// transcript (`temperature`, `top_p`, `top_k`, `max_tokens`), Claude Code's
+/// `thinkingMetadata`, and the `MAX_THINKING_TOKENS` /
+/// `CLAUDE_CODE_MAX_OUTPUT_TOKENS` variables the hook inherits. MCP servers
+/// don't report a version in the transcript, so the version is the one their
+/// launch command pins in `.mcp.json` or `~/.claude.json` (`pkg@1.2.3`,
+/// `pkg==1.2.3`, `image:tag`), or `unpinned`.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+const SAMPLING_FIELDS: &[&str] = &["temperature", "top_p", "top_k", "max_tokens"];
+const SETTING_VARS: &[(&str, &str)] = &[
+    ("MAX_THINKING_TOKENS", "max_thinking_tokens"),
+    ("CLAUDE_CODE_MAX_OUTPUT_TOKENS", "max_output_tokens"),
+];
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct Environment {
+    /// Version of the coding agent (e.g. Claude Code `2.0.14`).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub agent_version: Option<String>,
+    /// Generation settings in effect, e.g. `temperature`, `thinking.level`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub settings: BTreeMap<String, String>,
+    /// MCP server name -> pinned version, or `unpinned`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub mcp_servers: BTreeMap<String, String>,
+}
+
+impl Environment {
+    pub fn is_empty(&self) -> bool {
+        self.agent_version.is_none() && self.settings.is_empty() && self.mcp_servers.is_empty()
+    }
+
+    /// Fold a later prompt's environment into this one; later values win.
+    pub fn merge(&mut self, later: Environment) {
+        if later.agent_version.is_some() {
+            self.agent_version = later.agent_version;
+        }
+        self.settings.extend(later.settings);
+        self.mcp_servers.extend(later.mcp_servers);
+    }
+
+    /// One line for reports: `Claude Code 2.0.14; temperature=0.2; github@0.6.2`.
+    pub fn describe(&self, provider: &str) -> String {
+        let mut parts = Vec::new();
+        if let Some(v) = &self.agent_version {
+            let agent = if provider == "claude" {
+                "Claude Code"
+            } else {
+                provider
+            };
+            parts.push(format!("{} {}", agent, v));
+        }
+        parts.extend(self.settings.iter().map(|(k, v)| format!("{}={}", k, v)));
+        parts.extend(
+            self.mcp_servers
+                .iter()
+                .map(|(name, v)| format!("{}@{}", name, v)),
+        );
+        parts.join("; ")
+    }
+}
+
+/// Record the settings visible on one transcript entry into `settings`.
+pub fn collect_settings(entry: &serde_json::Value, settings: &mut BTreeMap<String, String>) {
+    if let Some(message) = entry.get("message") {
+        for field in SAMPLING_FIELDS {
+            if let Some(v) = message.get(*field).and_then(scalar) {
+                settings.insert(field.to_string(), v);
+            }
+        }
+    }
+    if let Some(thinking) = entry.get("thinkingMetadata").and_then(|t| t.as_object()) {
+        for (key, value) in thinking {
+            if let Some(v) = scalar(value) {
+                settings.insert(format!("thinking.{}", key), v);
+            }
+        }
+    }
+}
+
+fn scalar(value: &serde_json::Value) -> Option<String> {
+    match value {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+        serde_json::Value::Number(n) => Some(n.to_string()),
+        serde_json::Value::Bool(b) => Some(b.to_string()),
+        _ => None,
+    }
+}
+
+/// The environment for a receipt that used `mcp_servers`, launched from `cwd`.
+/// `None` when nothing is known.
+pub fn capture(
+    agent_version: Option<&str>,
+    transcript_settings: &BTreeMap<String, String>,
+    mcp_servers: &[String],
+    cwd: &str,
+) -> Option<Environment> {
+    let mut settings = transcript_settings.clone();
+    for (var, key) in SETTING_VARS {
+        if let Ok(v) = std::env::var(var) {
+            if !v.trim().is_empty() {
+                settings.insert(key.to_string(), v.trim().to_string());
+            }
+        }
+    }
+    let configs = mcp_configs(cwd);
+    let env = Environment {
+        agent_version: agent_version.map(String::from),
+        settings,
+        mcp_servers: mcp_servers
+            .iter()
+            .map(|name| {
+                let version = configs
+                    .iter()
+                    .find_map(|c| c.get(name))
+                    .and_then(pinned_version)
+                    .unwrap_or_else(|| "unpinned".to_string());
+                (name.clone(), version)
+            })
+            .collect(),
+    };
+    (!env.is_empty()).then_some(env)
+}
+
+/// `mcpServers` tables in lookup order: the project's `.mcp.json`, then the
+/// project and user entries of `~/.claude.json`.
+fn mcp_configs(cwd: &str) -> Vec<serde_json::Map<String, serde_json::Value>> {
+    let read = |path: &Path| -> Option<serde_json::Value> {
+        serde_json::from_str(&std::fs::read_to_string(path).ok()?).ok()
+    };
+    let servers = |v: Option<&serde_json::Value>| {
+        v.and_then(|v| v.get("mcpServers"))
+            .and_then(|s| s.as_object())
+            .cloned()
+    };
+    let mut configs = Vec::new();
+    configs.extend(servers(read(&Path::new(cwd).join(".mcp.json")).as_ref()));
+    if let Some(user) = dirs::home_dir().and_then(|h| read(&h.join(".claude.json"))) {
+        let project = std::fs::canonicalize(cwd).ok().and_then(|p| {
+            user.get("projects")?
+                .get(p.to_string_lossy().as_ref())
+                .cloned()
+        });
+        configs.extend(servers(project.as_ref()));
+        configs.extend(servers(Some(&user)));
+    }
+    configs
+}
+
+/// The version a server's launch command pins: `pkg@1.2.3` (npx, bunx),
+/// `pkg==1.2.3` (uvx, pip) or `image:tag` (docker). Remote servers and
+/// unpinned commands have none.
+pub fn pinned_version(server: &serde_json::Value) -> Option<String> {
+    let args: Vec<&str> = server
+        .get("args")
+        .and_then(|a| a.as_array())
+        .map(|a| a.iter().filter_map(|v| v.as_str()).collect())
+        .unwrap_or_default();
+    let is_docker = server
+        .get("command")
+        .and_then(|c| c.as_str())
+        .is_some_and(|c| c.ends_with("docker") || c.ends_with("podman"));
+    let looks_like_version = |v: &str| v.starts_with(|c: char| c.is_ascii_digit());
+    for arg in args.iter().filter(|a| !a.starts_with('-')) {
+        if let Some((_, v)) = arg.split_once("==") {
+            if looks_like_version(v) {
+                return Some(v.to_string());
+            }
+        }
+        // Skip the scope's leading '@' in `@org/pkg@1.2.3`.
+        if let Some((_, v)) = arg.get(1..).and_then(|a| a.rsplit_once('@')) {
+            if looks_like_version(v) {
+                return Some(v.to_string());
+            }
+        }
+        if is_docker {
+            if let Some((image, tag)) = arg.rsplit_once(':') {
+                if !image.is_empty() && !tag.contains('/') && tag != "latest" {
+                    return Some(tag.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_settings_and_pinned_versions() {
+        let mut settings = BTreeMap::new();
+        collect_settings(
+            &json!({"type": "assistant", "version": "2.0.14",
+                "message": {"model": "claude-sonnet-4-5", "temperature": 0.2, "max_tokens": 8192}}),
+            &mut settings,
+        );
+        collect_settings(
+            &json!({"type": "user", "thinkingMetadata": {"level": "high", "disabled": false, "triggers": []}}),
+            &mut settings,
+        );
+        assert_eq!(settings["temperature"], "0.2");
+        assert_eq!(settings["max_tokens"], "8192");
+        assert_eq!(settings["thinking.level"], "high");
+        assert_eq!(settings["thinking.disabled"], "false");
+        assert!(!settings.contains_key("thinking.triggers"));
+
+        let pin = |v: serde_json::Value| pinned_version(&v);
+        assert_eq!(
+            pin(
+                json!({"command": "npx", "args": ["-y", "@modelcontextprotocol/server-github@0.6.2"]})
+            ),
+            Some("0.6.2".to_string())
+        );
+        assert_eq!(
+            pin(json!({"command": "npx", "args": ["-y", "@modelcontextprotocol/server-github"]})),
+            None
+        );
+        assert_eq!(
+            pin(json!({"command": "uvx", "args": ["mcp-server-git==2025.1.14"]})),
+            Some("2025.1.14".to_string())
+        );
+        assert_eq!(
+            pin(
+                json!({"command": "docker", "args": ["run", "-i", "ghcr.io/github/github-mcp-server:v0.9.0"]})
+            ),
+            Some("v0.9.0".to_string())
+        );
+        assert_eq!(
+            pin(json!({"command": "docker", "args": ["run", "localhost:5000/mcp"]})),
+            None
+        );
+        assert_eq!(
+            pin(json!({"type": "http", "url": "https://mcp.example.com/mcp"})),
+            None
+        );
+
+        let env = Environment {
+            agent_version: Some("2.0.14".to_string()),
+            settings,
+            mcp_servers: BTreeMap::from([("github".to_string(), "0.6.2".to_string())]),
+        };
+        let described = env.describe("claude");
+        assert!(described.starts_with("Claude Code 2.0.14; max_tokens=8192; temperature=0.2"));
+        assert!(described.ends_with("; github@0.6.2"));
+    }
+}
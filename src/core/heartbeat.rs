@@ -0,0 +1,240 @@
+/// Hook heartbeat.
+///
+/// Every `checkpoint` invocation stamps `~/.blameprompt/heartbeat.json` with
+/// the agent, event and time, and records the message if it panics. Commands
+/// that run often (`blame`, `show`, `audit`, ...) and `doctor` compare the last
+/// Claude hook event with the Claude sessions on disk: sessions without any
+/// hook events mean capture has silently stopped (hooks removed by a settings
+/// sync, a crashing binary, ...).
+use crate::core::config;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct Heartbeat {
+    #[serde(default)]
+    pub agents: BTreeMap<String, AgentBeat>,
+    /// Last time a frequently-run command printed the stale-hooks warning.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_warned_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AgentBeat {
+    pub last_event_at: DateTime<Utc>,
+    pub last_event: String,
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_panic: Option<PanicRecord>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PanicRecord {
+    pub at: DateTime<Utc>,
+    pub message: String,
+}
+
+/// Why capture looks broken.
+#[derive(Debug, PartialEq)]
+pub struct Staleness {
+    pub last_event_at: Option<DateTime<Utc>>,
+    /// Claude sessions active within the window.
+    pub recent_sessions: usize,
+    pub days: u64,
+}
+
+impl Staleness {
+    pub fn describe(&self) -> String {
+        let seen = match self.last_event_at {
+            Some(t) => format!("last one {}", t.format("%Y-%m-%d %H:%M UTC")),
+            None => "none ever recorded".to_string(),
+        };
+        format!(
+            "No Claude Code hook events in {} day(s) ({}) despite {} Claude session(s) in that time",
+            self.days, seen, self.recent_sessions
+        )
+    }
+}
+
+fn heartbeat_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".blameprompt").join("heartbeat.json"))
+}
+
+pub fn load() -> Heartbeat {
+    heartbeat_path()
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|s| serde_json::from_str(&s).ok())
+        .unwrap_or_default()
+}
+
+/// Written only once BlamePrompt is set up (`~/.blameprompt` exists).
+fn save(hb: &Heartbeat) {
+    let Some(path) = heartbeat_path() else {
+        return;
+    };
+    if !path.parent().is_some_and(|d| d.is_dir()) {
+        return;
+    }
+    let Ok(json) = serde_json::to_string_pretty(hb) else {
+        return;
+    };
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    if std::fs::write(&tmp, json).is_ok() && std::fs::rename(&tmp, &path).is_err() {
+        let _ = std::fs::remove_file(&tmp);
+    }
+}
+
+/// Stamp a hook event for `agent`.
+pub fn record(agent: &str, event: &str) {
+    let mut hb = load();
+    let last_panic = hb.agents.get(agent).and_then(|a| a.last_panic.clone());
+    hb.agents.insert(
+        agent.to_string(),
+        AgentBeat {
+            last_event_at: Utc::now(),
+            last_event: event.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            last_panic,
+        },
+    );
+    save(&hb);
+}
+
+/// Remember a checkpoint panic so `doctor` can show it.
+pub fn record_panic(agent: &str, message: &str) {
+    let mut hb = load();
+    let panic = PanicRecord {
+        at: Utc::now(),
+        message: message.chars().take(500).collect(),
+    };
+    match hb.agents.get_mut(agent) {
+        Some(beat) => beat.last_panic = Some(panic),
+        None => {
+            hb.agents.insert(
+                agent.to_string(),
+                AgentBeat {
+                    last_event_at: panic.at,
+                    last_event: "panic".to_string(),
+                    version: env!("CARGO_PKG_VERSION").to_string(),
+                    last_panic: Some(panic),
+                },
+            );
+        }
+    }
+    save(&hb);
+}
+
+/// Modification times of Claude Code session transcripts
+/// (`~/.claude/projects/*/*.jsonl`).
+fn claude_session_times() -> Vec<DateTime<Utc>> {
+    let Some(projects) = dirs::home_dir().map(|h| h.join(".claude").join("projects")) else {
+        return Vec::new();
+    };
+    let Ok(dirs) = std::fs::read_dir(projects) else {
+        return Vec::new();
+    };
+    dirs.filter_map(Result::ok)
+        .filter_map(|d| std::fs::read_dir(d.path()).ok())
+        .flatten()
+        .filter_map(Result::ok)
+        .filter(|f| f.path().extension().is_some_and(|e| e == "jsonl"))
+        .filter_map(|f| f.metadata().ok()?.modified().ok())
+        .map(DateTime::<Utc>::from)
+        .collect()
+}
+
+/// Stale when Claude sessions were active in the last `days` days but no
+/// Claude hook event arrived in that window.
+pub fn staleness(
+    last_event_at: Option<DateTime<Utc>>,
+    sessions: &[DateTime<Utc>],
+    now: DateTime<Utc>,
+    days: u64,
+) -> Option<Staleness> {
+    let since = now - Duration::days(days as i64);
+    if last_event_at.is_some_and(|t| t >= since) {
+        return None;
+    }
+    // A session only counts if it was written after the last event: the
+    // transcript of the session that produced that event is touched later.
+    let recent_sessions = sessions
+        .iter()
+        .filter(|t| **t >= since && last_event_at.is_none_or(|e| **t > e + Duration::hours(1)))
+        .count();
+    (recent_sessions > 0).then_some(Staleness {
+        last_event_at,
+        recent_sessions,
+        days,
+    })
+}
+
+/// Current state for the Claude hooks, or None when healthy or the check is
+/// disabled (`[capture] heartbeat_warn_days = 0`).
+pub fn check_claude() -> Option<Staleness> {
+    let days = config::load_config().capture.heartbeat_warn_days;
+    if days == 0 {
+        return None;
+    }
+    let last = load().agents.get("claude").map(|a| a.last_event_at);
+    staleness(last, &claude_session_times(), Utc::now(), days)
+}
+
+/// Print the stale-hooks warning at most once a day. Used by frequently-run
+/// commands; skipped in CI, under `--quiet`, and before setup.
+pub fn warn_if_stale() {
+    if std::env::var_os("CI").is_some() || crate::core::term::quiet() {
+        return;
+    }
+    if !heartbeat_path().is_some_and(|p| p.parent().is_some_and(|d| d.is_dir())) {
+        return;
+    }
+    let mut hb = load();
+    if hb
+        .last_warned_at
+        .is_some_and(|t| Utc::now() - t < Duration::hours(24))
+    {
+        return;
+    }
+    let Some(stale) = check_claude() else {
+        return;
+    };
+    eprintln!("[BlamePrompt] Warning: {}.", stale.describe());
+    eprintln!(
+        "[BlamePrompt] Capture may have stopped. Run `blameprompt doctor` to check and re-install the hooks."
+    );
+    hb.last_warned_at = Some(Utc::now());
+    save(&hb);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_staleness() {
+        let now = at("2026-03-10T12:00:00Z");
+        let sessions = vec![at("2026-03-09T10:00:00Z"), at("2026-02-01T10:00:00Z")];
+
+        // Recent hook event: healthy.
+        assert_eq!(
+            staleness(Some(at("2026-03-09T09:00:00Z")), &sessions, now, 3),
+            None
+        );
+        // Old event, recent session: stale.
+        let stale = staleness(Some(at("2026-03-01T09:00:00Z")), &sessions, now, 3).unwrap();
+        assert_eq!(stale.recent_sessions, 1);
+        // Never seen an event at all.
+        assert_eq!(
+            staleness(None, &sessions, now, 3).unwrap().last_event_at,
+            None
+        );
+        // No recent Claude sessions: nothing to compare against.
+        assert_eq!(staleness(None, &[at("2026-02-01T10:00:00Z")], now, 3), None);
+    }
+}
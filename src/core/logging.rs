@@ -0,0 +1,145 @@
+/// Logging via `tracing`.
+///
+/// Two sinks, filtered independently:
+/// - stderr, only with `-v` (info), `-vv` (debug) or `-vvv` (trace);
+/// - `~/.blameprompt/logs/blameprompt.log` at `[logging] level` (or
+///   `BLAMEPROMPT_LOG`), rotated by size when the process starts.
+///
+/// Hooks run without a terminal, so the file is where "why didn't this prompt
+/// get a receipt?" gets answered; `blameprompt logs tail` reads it.
+use crate::core::config::{self, LoggingConfig};
+use std::fs::{self, File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+const LOG_ENV: &str = "BLAMEPROMPT_LOG";
+
+/// `~/.blameprompt/logs/blameprompt.log`
+pub fn log_path() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".blameprompt").join("logs").join("blameprompt.log"))
+}
+
+/// Install the global subscriber. Call once from `main`.
+pub fn init(verbose: u8) {
+    let cfg = config::load_config().logging;
+
+    let stderr = (verbose > 0).then(|| {
+        let level = match verbose {
+            1 => "info",
+            2 => "debug",
+            _ => "trace",
+        };
+        fmt::layer()
+            .with_writer(std::io::stderr)
+            .without_time()
+            .with_target(verbose > 2)
+            .with_filter(filter(level))
+    });
+
+    let file = open_log_file(&cfg).map(|f| {
+        let level = std::env::var(LOG_ENV)
+            .ok()
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| cfg.level.clone());
+        fmt::layer()
+            .with_writer(Mutex::new(f))
+            .with_ansi(false)
+            .with_filter(filter(&level))
+    });
+
+    let _ = tracing_subscriber::registry()
+        .with(stderr)
+        .with(file)
+        .try_init();
+}
+
+fn filter(spec: &str) -> EnvFilter {
+    EnvFilter::try_new(directive(spec)).unwrap_or_else(|_| EnvFilter::new("blameprompt=info"))
+}
+
+/// A bare level applies to blameprompt only (dependencies are noisy at debug);
+/// anything else is taken as a full `EnvFilter` directive.
+fn directive(spec: &str) -> String {
+    let spec = spec.trim();
+    if spec.contains('=') || spec.contains(',') {
+        spec.to_string()
+    } else {
+        format!("blameprompt={}", spec)
+    }
+}
+
+/// Open the log file for appending, rotating first if it is too large.
+/// Returns None when file logging is off or BlamePrompt was never set up
+/// (`~/.blameprompt` missing): logging must not recreate it after uninstall.
+fn open_log_file(cfg: &LoggingConfig) -> Option<File> {
+    if !cfg.file {
+        return None;
+    }
+    let path = log_path()?;
+    let dir = path.parent()?;
+    if !dir.parent()?.is_dir() {
+        return None;
+    }
+    fs::create_dir_all(dir).ok()?;
+    rotate(&path, cfg.max_size_mb * 1024 * 1024, cfg.keep);
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .ok()
+}
+
+/// Shift `log` → `log.1` → ... → `log.<keep>` once `log` reaches `max_bytes`;
+/// the oldest file is dropped.
+fn rotate(path: &Path, max_bytes: u64, keep: usize) {
+    let size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+    if size < max_bytes.max(1) {
+        return;
+    }
+    if keep == 0 {
+        let _ = fs::remove_file(path);
+        return;
+    }
+    let numbered = |n: usize| PathBuf::from(format!("{}.{}", path.display(), n));
+    let _ = fs::remove_file(numbered(keep));
+    for n in (1..keep).rev() {
+        let _ = fs::rename(numbered(n), numbered(n + 1));
+    }
+    let _ = fs::rename(path, numbered(1));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_shifts_and_drops_oldest() {
+        let dir = tempfile::tempdir().unwrap();
+        let log = dir.path().join("blameprompt.log");
+        let read = |name: &str| fs::read_to_string(dir.path().join(name)).ok();
+
+        fs::write(&log, "small").unwrap();
+        rotate(&log, 100, 2);
+        assert_eq!(read("blameprompt.log").as_deref(), Some("small"));
+
+        for round in ["one", "two", "three"] {
+            fs::write(&log, round.repeat(50)).unwrap();
+            rotate(&log, 100, 2);
+        }
+        assert!(read("blameprompt.log").is_none());
+        assert!(read("blameprompt.log.1").unwrap().starts_with("three"));
+        assert!(read("blameprompt.log.2").unwrap().starts_with("two"));
+        assert!(read("blameprompt.log.3").is_none());
+    }
+
+    #[test]
+    fn test_directive_scopes_bare_levels() {
+        assert_eq!(directive(" debug "), "blameprompt=debug");
+        assert_eq!(directive("info,hyper=warn"), "info,hyper=warn");
+        assert_eq!(
+            directive("blameprompt::commands::checkpoint=trace"),
+            "blameprompt::commands::checkpoint=trace"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+/// Receipt note schema versioning.
+///
+/// Every note carries `schema_version`; notes written before the field existed
+/// are version 1. Readers go through [`parse`], which upgrades older payloads
+/// in memory one step at a time, so the rest of the code only ever sees the
+/// current shape. `blameprompt migrate --write` persists the upgrade.
+///
+/// Adding a version: bump `receipt::SCHEMA_VERSION` and append a step to
+/// `MIGRATIONS` that rewrites the previous shape into the new one.
+use crate::core::receipt::{NotePayload, SCHEMA_VERSION};
+use serde_json::Value;
+
+/// Version of notes that predate the `schema_version` field.
+pub const LEGACY_SCHEMA_VERSION: u32 = 1;
+
+/// `(from, step)`: `step` upgrades a `from` payload to `from + 1`.
+type Step = fn(&mut Value);
+const MIGRATIONS: &[(u32, Step)] = &[(1, v1_to_v2)];
+
+/// Schema version declared by a raw note.
+pub fn schema_version_of(note: &Value) -> u32 {
+    note.get("schema_version")
+        .and_then(Value::as_u64)
+        .map(|v| v as u32)
+        .unwrap_or(LEGACY_SCHEMA_VERSION)
+}
+
+/// Upgrade `note` in place to the current schema. Returns the version it
+/// started at; errors (leaving `note` untouched) when it was written by a
+/// newer BlamePrompt.
+pub fn upgrade(note: &mut Value) -> Result<u32, String> {
+    let from = schema_version_of(note);
+    if from > SCHEMA_VERSION {
+        return Err(format!(
+            "schema_version {} is newer than this blameprompt understands ({}); upgrade blameprompt",
+            from, SCHEMA_VERSION
+        ));
+    }
+    let mut version = from;
+    while version < SCHEMA_VERSION {
+        let (_, step) = MIGRATIONS
+            .iter()
+            .find(|(v, _)| *v == version)
+            .ok_or_else(|| format!("no migration from schema_version {}", version))?;
+        step(note);
+        version += 1;
+    }
+    if let Some(obj) = note.as_object_mut() {
+        obj.insert("schema_version".to_string(), Value::from(SCHEMA_VERSION));
+    }
+    Ok(from)
+}
+
+/// Deserialize a raw note, upgrading older schemas. Notes from a newer
+/// BlamePrompt are read best-effort (unknown fields are ignored).
+pub fn from_value(mut note: Value) -> Result<NotePayload, String> {
+    if let Err(e) = upgrade(&mut note) {
+        tracing::warn!("{}", e);
+    }
+    serde_json::from_value(note).map_err(|e| format!("Invalid note: {}", e))
+}
+
+/// [`from_value`] for note text.
+pub fn parse(content: &str) -> Result<NotePayload, String> {
+    let note: Value = serde_json::from_str(content).map_err(|e| format!("Invalid note: {}", e))?;
+    from_value(note)
+}
+
+/// v1 → v2: single-file receipts (`file_path` / `line_range` only) get an
+/// equivalent `files_changed` entry.
+fn v1_to_v2(note: &mut Value) {
+    let Some(receipts) = note.get_mut("receipts").and_then(Value::as_array_mut) else {
+        return;
+    };
+    for r in receipts.iter_mut().filter_map(Value::as_object_mut) {
+        let has_files = r
+            .get("files_changed")
+            .and_then(Value::as_array)
+            .is_some_and(|f| !f.is_empty());
+        let path = r.get("file_path").and_then(Value::as_str).unwrap_or("");
+        if has_files || path.is_empty() {
+            continue;
+        }
+        let range = r
+            .get("line_range")
+            .cloned()
+            .unwrap_or_else(|| serde_json::json!([1, 1]));
+        let change = serde_json::json!({ "path": path, "line_range": range });
+        r.insert("files_changed".to_string(), Value::Array(vec![change]));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn legacy_note() -> Value {
+        serde_json::json!({
+            "blameprompt_version": "0.3.0",
+            "receipts": [{
+                "id": "r1",
+                "provider": "claude",
+                "model": "claude-sonnet-4",
+                "session_id": "s1",
+                "prompt_summary": "fix the parser",
+                "prompt_hash": "sha256:00",
+                "message_count": 2,
+                "cost_usd": 0.01,
+                "timestamp": "2025-06-01T10:00:00Z",
+                "user": "dev <dev@example.com>",
+                "file_path": "src/parser.rs",
+                "line_range": [10, 20]
+            }]
+        })
+    }
+
+    #[test]
+    fn test_upgrade_legacy_note() {
+        let mut note = legacy_note();
+        assert_eq!(schema_version_of(&note), LEGACY_SCHEMA_VERSION);
+        assert_eq!(upgrade(&mut note).unwrap(), LEGACY_SCHEMA_VERSION);
+        assert_eq!(schema_version_of(&note), SCHEMA_VERSION);
+
+        let payload: NotePayload = serde_json::from_value(note.clone()).unwrap();
+        assert_eq!(payload.schema_version, SCHEMA_VERSION);
+        let fc = &payload.receipts[0].files_changed;
+        assert_eq!(fc.len(), 1);
+        assert_eq!(fc[0].path, "src/parser.rs");
+        assert_eq!(fc[0].line_range, (10, 20));
+
+        // Upgrading a current note is a no-op.
+        let before = note.clone();
+        assert_eq!(upgrade(&mut note).unwrap(), SCHEMA_VERSION);
+        assert_eq!(note, before);
+    }
+
+    #[test]
+    fn test_newer_schema_is_reported_but_readable() {
+        let mut note = legacy_note();
+        note["schema_version"] = Value::from(SCHEMA_VERSION + 1);
+        let before = note.clone();
+        assert!(upgrade(&mut note).unwrap_err().contains("newer"));
+        assert_eq!(note, before);
+        assert_eq!(from_value(note).unwrap().receipts.len(), 1);
+    }
+}
@@ -1,12 +1,28 @@
 pub mod api_client;
 pub mod auth;
+pub mod capture_ignore;
 pub mod config;
 pub mod db;
+pub mod environment;
+pub mod heartbeat;
+pub mod logging;
+pub mod migrate;
 pub mod model_classifier;
+pub mod notebook;
+pub mod org_config;
+pub mod pairing;
+pub mod path_map;
 pub mod pricing;
+pub mod privacy;
 pub mod prompt_eval;
 pub mod receipt;
 pub mod redact;
+pub mod scan_exclude;
 pub mod session_stats;
+pub mod smtp;
+pub mod summarize;
+pub mod symbols;
+pub mod term;
+pub mod ticket;
 pub mod transcript;
 pub mod util;
@@ -0,0 +1,418 @@
+/// Jupyter notebook (.ipynb) helpers for cell-level attribution.
+///
+/// Git (and therefore blame and receipt line ranges) sees a notebook as raw JSON.
+/// These helpers map raw JSON line numbers back to the cells they belong to, so
+/// line-level attribution can be rolled up per cell.
+///
+/// The line spans come from a small span-tracking JSON scanner rather than
+/// `serde_json`, which does not expose positions. Cell ids and types are read
+/// with `serde_json` and merged by index.
+use serde_json::Value;
+
+/// A notebook cell and where its `source` sits in the raw file (1-based, inclusive).
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotebookCell {
+    pub index: usize,
+    /// nbformat ≥ 4.5 cell id; absent in older notebooks.
+    pub id: Option<String>,
+    pub cell_type: String,
+    pub source_lines: (u32, u32),
+}
+
+impl NotebookCell {
+    /// Display label: the cell id when present, otherwise `#<index>`.
+    pub fn label(&self) -> String {
+        self.id
+            .clone()
+            .unwrap_or_else(|| format!("#{}", self.index))
+    }
+
+    pub fn contains_line(&self, line: u32) -> bool {
+        line >= self.source_lines.0 && line <= self.source_lines.1
+    }
+}
+
+pub fn is_notebook(path: &str) -> bool {
+    path.ends_with(".ipynb")
+}
+
+struct Scanner<'a> {
+    b: &'a [u8],
+    pos: usize,
+    line: u32,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(text: &'a str) -> Self {
+        Scanner {
+            b: text.as_bytes(),
+            pos: 0,
+            line: 1,
+        }
+    }
+
+    fn ws(&mut self) {
+        while let Some(&c) = self.b.get(self.pos) {
+            if !c.is_ascii_whitespace() {
+                break;
+            }
+            if c == b'\n' {
+                self.line += 1;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.ws();
+        self.b.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, c: u8) -> Option<()> {
+        if self.peek()? == c {
+            self.pos += 1;
+            Some(())
+        } else {
+            None
+        }
+    }
+
+    /// Read a string token. Escapes are skipped, not decoded — keys we care
+    /// about (`cells`, `source`) never contain them.
+    fn string(&mut self) -> Option<String> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        while let Some(&c) = self.b.get(self.pos) {
+            match c {
+                b'\\' => self.pos += 2,
+                b'"' => {
+                    let s = String::from_utf8_lossy(&self.b[start..self.pos]).to_string();
+                    self.pos += 1;
+                    return Some(s);
+                }
+                _ => self.pos += 1,
+            }
+        }
+        None
+    }
+
+    fn skip_value(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'{' => {
+                self.pos += 1;
+                if self.peek()? == b'}' {
+                    self.pos += 1;
+                    return Some(());
+                }
+                loop {
+                    self.string()?;
+                    self.expect(b':')?;
+                    self.skip_value()?;
+                    match self.peek()? {
+                        b',' => self.pos += 1,
+                        b'}' => {
+                            self.pos += 1;
+                            return Some(());
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            b'[' => {
+                self.pos += 1;
+                if self.peek()? == b']' {
+                    self.pos += 1;
+                    return Some(());
+                }
+                loop {
+                    self.skip_value()?;
+                    match self.peek()? {
+                        b',' => self.pos += 1,
+                        b']' => {
+                            self.pos += 1;
+                            return Some(());
+                        }
+                        _ => return None,
+                    }
+                }
+            }
+            b'"' => self.string().map(|_| ()),
+            _ => {
+                while let Some(&c) = self.b.get(self.pos) {
+                    if matches!(c, b',' | b'}' | b']') || c.is_ascii_whitespace() {
+                        break;
+                    }
+                    self.pos += 1;
+                }
+                Some(())
+            }
+        }
+    }
+
+    /// Skip a value and return the lines of its first and last character.
+    fn value_span(&mut self) -> Option<(u32, u32)> {
+        self.ws();
+        let start = self.line;
+        self.skip_value()?;
+        Some((start, self.line))
+    }
+
+    /// Parse one cell object, returning the span of its `source` value.
+    fn cell(&mut self) -> Option<(u32, u32)> {
+        let obj_start = {
+            self.ws();
+            self.line
+        };
+        self.expect(b'{')?;
+        let mut source = None;
+        if self.peek()? == b'}' {
+            self.pos += 1;
+            return Some((obj_start, self.line));
+        }
+        loop {
+            let key = self.string()?;
+            self.expect(b':')?;
+            if key == "source" {
+                source = Some(self.value_span()?);
+            } else {
+                self.skip_value()?;
+            }
+            match self.peek()? {
+                b',' => self.pos += 1,
+                b'}' => {
+                    self.pos += 1;
+                    break;
+                }
+                _ => return None,
+            }
+        }
+        Some(source.unwrap_or((obj_start, self.line)))
+    }
+
+    fn cell_source_spans(&mut self) -> Option<Vec<(u32, u32)>> {
+        self.expect(b'{')?;
+        loop {
+            let key = self.string()?;
+            self.expect(b':')?;
+            if key == "cells" {
+                let mut spans = Vec::new();
+                self.expect(b'[')?;
+                if self.peek()? == b']' {
+                    return Some(spans);
+                }
+                loop {
+                    spans.push(self.cell()?);
+                    match self.peek()? {
+                        b',' => self.pos += 1,
+                        b']' => return Some(spans),
+                        _ => return None,
+                    }
+                }
+            }
+            self.skip_value()?;
+            match self.peek()? {
+                b',' => self.pos += 1,
+                _ => return None,
+            }
+        }
+    }
+}
+
+/// Parse a notebook's cells with the raw-line span of each cell's source.
+/// Returns `None` if the text is not a notebook.
+pub fn parse_cells(raw: &str) -> Option<Vec<NotebookCell>> {
+    let spans = Scanner::new(raw).cell_source_spans()?;
+    let json: Value = serde_json::from_str(raw).ok()?;
+    let cells = json.get("cells")?.as_array()?;
+
+    Some(
+        spans
+            .into_iter()
+            .enumerate()
+            .map(|(index, source_lines)| {
+                let cell = cells.get(index);
+                NotebookCell {
+                    index,
+                    id: cell
+                        .and_then(|c| c.get("id"))
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.to_string()),
+                    cell_type: cell
+                        .and_then(|c| c.get("cell_type"))
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("code")
+                        .to_string(),
+                    source_lines,
+                }
+            })
+            .collect(),
+    )
+}
+
+/// Labels of cells whose source overlaps the raw line range `(start, end)`.
+pub fn cells_in_range(cells: &[NotebookCell], range: (u32, u32)) -> Vec<String> {
+    cells
+        .iter()
+        .filter(|c| c.source_lines.0 <= range.1 && c.source_lines.1 >= range.0)
+        .map(|c| c.label())
+        .collect()
+}
+
+/// Whether `line` may be attributed to a change that touched `labels`.
+/// Changes without cell info (older receipts, non-notebook files) match any line.
+pub fn line_in_cells(cells: &[NotebookCell], labels: &[String], line: u32) -> bool {
+    if labels.is_empty() {
+        return true;
+    }
+    cells
+        .iter()
+        .find(|c| c.contains_line(line))
+        .is_some_and(|c| labels.contains(&c.label()))
+}
+
+/// Line-level attribution rolled up to one notebook cell.
+#[derive(Debug, Clone)]
+pub struct CellAttribution {
+    pub cell: NotebookCell,
+    pub total_lines: u32,
+    pub ai_lines: u32,
+    pub models: Vec<String>,
+}
+
+impl CellAttribution {
+    pub fn ai_pct(&self) -> f64 {
+        if self.total_lines == 0 {
+            0.0
+        } else {
+            (self.ai_lines as f64 / self.total_lines as f64) * 100.0
+        }
+    }
+}
+
+/// Roll up per-line attribution into per-cell totals.
+/// `lines` yields `(raw line number, is_ai, model)`; lines outside any cell's
+/// source (metadata, outputs) are ignored.
+pub fn attribute_cells<'a>(
+    cells: &[NotebookCell],
+    lines: impl IntoIterator<Item = (u32, bool, Option<&'a str>)>,
+) -> Vec<CellAttribution> {
+    let mut out: Vec<CellAttribution> = cells
+        .iter()
+        .map(|c| CellAttribution {
+            cell: c.clone(),
+            total_lines: c.source_lines.1 - c.source_lines.0 + 1,
+            ai_lines: 0,
+            models: Vec::new(),
+        })
+        .collect();
+
+    for (line, is_ai, model) in lines {
+        if !is_ai {
+            continue;
+        }
+        if let Some(ca) = out.iter_mut().find(|ca| ca.cell.contains_line(line)) {
+            ca.ai_lines += 1;
+            if let Some(m) = model.filter(|m| !m.is_empty()) {
+                if !ca.models.iter().any(|x| x == m) {
+                    ca.models.push(m.to_string());
+                }
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOTEBOOK: &str = r##"{
+ "cells": [
+  {
+   "cell_type": "markdown",
+   "id": "intro",
+   "metadata": {},
+   "source": [
+    "# Title\n",
+    "Some text"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "execution_count": 1,
+   "id": "load",
+   "metadata": {"tags": ["a\"b"]},
+   "outputs": [],
+   "source": [
+    "import pandas as pd\n",
+    "df = pd.read_csv(\"x.csv\")"
+   ]
+  },
+  {
+   "cell_type": "code",
+   "metadata": {},
+   "source": "print(1)"
+  }
+ ],
+ "metadata": {},
+ "nbformat": 4,
+ "nbformat_minor": 5
+}"##;
+
+    #[test]
+    fn test_parse_cells_spans() {
+        let cells = parse_cells(NOTEBOOK).unwrap();
+        assert_eq!(cells.len(), 3);
+        assert_eq!(cells[0].id.as_deref(), Some("intro"));
+        assert_eq!(cells[0].cell_type, "markdown");
+        assert_eq!(cells[0].source_lines, (7, 10));
+        assert_eq!(cells[1].id.as_deref(), Some("load"));
+        assert_eq!(cells[1].source_lines, (18, 21));
+        assert_eq!(cells[2].id, None);
+        assert_eq!(cells[2].source_lines, (26, 26));
+    }
+
+    #[test]
+    fn test_cells_in_range_and_labels() {
+        let cells = parse_cells(NOTEBOOK).unwrap();
+        assert_eq!(cells_in_range(&cells, (19, 30)), vec!["load", "#2"]);
+        assert!(cells_in_range(&cells, (11, 17)).is_empty());
+        assert!(cells[1].contains_line(20));
+    }
+
+    #[test]
+    fn test_attribute_cells() {
+        let cells = parse_cells(NOTEBOOK).unwrap();
+        let lines = vec![
+            (19, true, Some("claude-sonnet-4-6")),
+            (20, true, Some("claude-sonnet-4-6")),
+            (21, false, None),
+            (2, true, Some("ignored")),
+        ];
+        let attr = attribute_cells(&cells, lines);
+        assert_eq!(attr.len(), 3);
+        assert_eq!(attr[0].ai_lines, 0);
+        assert_eq!(attr[1].total_lines, 4);
+        assert_eq!(attr[1].ai_lines, 2);
+        assert_eq!(attr[1].ai_pct(), 50.0);
+        assert_eq!(attr[1].models, vec!["claude-sonnet-4-6"]);
+    }
+
+    #[test]
+    fn test_line_in_cells() {
+        let cells = parse_cells(NOTEBOOK).unwrap();
+        assert!(line_in_cells(&cells, &[], 3));
+        let load = vec!["load".to_string()];
+        assert!(line_in_cells(&cells, &load, 19));
+        assert!(!line_in_cells(&cells, &load, 8));
+        assert!(!line_in_cells(&cells, &load, 2));
+    }
+
+    #[test]
+    fn test_not_a_notebook() {
+        assert!(parse_cells("fn main() {}").is_none());
+        assert!(parse_cells(r#"{"metadata": {}}"#).is_none());
+        assert!(is_notebook("analysis.ipynb"));
+        assert!(!is_notebook("main.rs"));
+    }
+}
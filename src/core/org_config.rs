@@ -0,0 +1,326 @@
+/// Organization-wide config distributed from a central file.
+///
+/// `[org] source` in `.blamepromptrc` points at the organization's config: an
+/// HTTPS URL, a git repository and path (`<repo>#<file>`), or a local file.
+/// `blameprompt config sync` (and `init`) fetches it, checks its Ed25519
+/// signature (`<source>.sig`, base64) against `[org] public_key`, and caches
+/// it under `~/.blameprompt/org/`. `load_config` layers the local file over
+/// the cached org file; sections the org lists in its own `[org] enforce`
+/// are taken from the org file whole, keys it leaves out falling back to
+/// their defaults, so local edits cannot weaken them.
+use crate::core::config::{BlamePromptConfig, OrgConfig};
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::process::Command;
+use toml::Table;
+
+/// What `sync` fetched.
+#[derive(Debug)]
+pub struct SyncOutcome {
+    pub source: String,
+    pub signed: bool,
+    pub enforced: Vec<String>,
+    pub cache: PathBuf,
+}
+
+/// Cache file for `source`, keyed by a hash of it so repos pointing at
+/// different org files don't overwrite each other.
+pub fn cache_path(source: &str) -> Option<PathBuf> {
+    let digest = Sha256::digest(source.as_bytes());
+    let key: String = digest
+        .iter()
+        .take(8)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    dirs::home_dir().map(|h| {
+        h.join(".blameprompt")
+            .join("org")
+            .join(format!("{}.toml", key))
+    })
+}
+
+/// The cached org config for `source`, if it was synced.
+pub fn cached(source: &str) -> Option<Table> {
+    let content = std::fs::read_to_string(cache_path(source)?).ok()?;
+    content.parse().ok()
+}
+
+/// Fetch, verify and cache the org config named by `org.source`.
+pub fn sync(org: &OrgConfig) -> Result<SyncOutcome, String> {
+    if org.source.is_empty() {
+        return Err("No org config source: set `[org] source` in .blamepromptrc".to_string());
+    }
+    let (content, signature) = fetch(&org.source)?;
+    let signed = if org.public_key.is_empty() {
+        if !org.allow_unsigned {
+            return Err(
+                "`[org] public_key` is not set: add the org's signing key, or set `allow_unsigned = true`"
+                    .to_string(),
+            );
+        }
+        false
+    } else {
+        let signature = signature.ok_or_else(|| {
+            format!(
+                "{} has no signature ({}.sig not found)",
+                org.source, org.source
+            )
+        })?;
+        verify(&content, &signature, &org.public_key)?;
+        true
+    };
+
+    let text = String::from_utf8(content).map_err(|_| "Org config is not UTF-8".to_string())?;
+    let table: Table = text
+        .parse()
+        .map_err(|e| format!("Org config is not valid TOML: {}", e))?;
+    toml::Value::Table(table.clone())
+        .try_into::<BlamePromptConfig>()
+        .map_err(|e| format!("Org config is not a valid BlamePrompt config: {}", e))?;
+
+    let cache = cache_path(&org.source).ok_or("Cannot locate home directory")?;
+    if let Some(dir) = cache.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    std::fs::write(&cache, &text)
+        .map_err(|e| format!("Cannot write {}: {}", cache.display(), e))?;
+    Ok(SyncOutcome {
+        source: org.source.clone(),
+        signed,
+        enforced: enforced_sections(&table),
+        cache,
+    })
+}
+
+/// Sync when `[org] source` is set, reporting the result; for `init`.
+pub fn sync_if_configured() {
+    let org = crate::core::config::load_config().org;
+    if org.source.is_empty() {
+        return;
+    }
+    match sync(&org) {
+        Ok(outcome) => println!("{}", outcome.describe()),
+        Err(e) => eprintln!(
+            "[BlamePrompt] Warning: org config not synced ({}); run `blameprompt config sync` to retry",
+            e
+        ),
+    }
+}
+
+impl SyncOutcome {
+    pub fn describe(&self) -> String {
+        format!(
+            "[BlamePrompt] Org config synced from {} ({}{})",
+            self.source,
+            if self.signed {
+                "signature verified"
+            } else {
+                "unsigned"
+            },
+            if self.enforced.is_empty() {
+                String::new()
+            } else {
+                format!("; enforced: {}", self.enforced.join(", "))
+            }
+        )
+    }
+}
+
+/// The file's bytes and, when there is one, its base64 signature.
+fn fetch(source: &str) -> Result<(Vec<u8>, Option<String>), String> {
+    if let Some((repo, path)) = source.split_once('#') {
+        return fetch_git(repo, path);
+    }
+    if source.starts_with("http://") {
+        return Err(format!(
+            "Refusing to fetch the org config over plain HTTP: {}; use https://",
+            source
+        ));
+    }
+    if source.starts_with("https://") {
+        let content = http_get(source)?.ok_or_else(|| format!("{} not found", source))?;
+        let signature = http_get(&format!("{}.sig", source))?;
+        return Ok((
+            content,
+            signature.map(|s| String::from_utf8_lossy(&s).into()),
+        ));
+    }
+    let content = std::fs::read(source).map_err(|e| format!("Cannot read {}: {}", source, e))?;
+    let signature = std::fs::read_to_string(format!("{}.sig", source)).ok();
+    Ok((content, signature))
+}
+
+fn http_get(url: &str) -> Result<Option<Vec<u8>>, String> {
+    let response = reqwest::blocking::Client::new()
+        .get(url)
+        .timeout(std::time::Duration::from_secs(30))
+        .send()
+        .map_err(|e| format!("Cannot fetch {}: {}", url, e))?;
+    if response.status() == reqwest::StatusCode::NOT_FOUND {
+        return Ok(None);
+    }
+    if !response.status().is_success() {
+        return Err(format!("Cannot fetch {}: HTTP {}", url, response.status()));
+    }
+    response
+        .bytes()
+        .map(|b| Some(b.to_vec()))
+        .map_err(|e| e.to_string())
+}
+
+/// `path` (and `path.sig`) from the default branch of `repo`, via a shallow clone.
+fn fetch_git(repo: &str, path: &str) -> Result<(Vec<u8>, Option<String>), String> {
+    let dir = std::env::temp_dir().join(format!("blameprompt-org-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let output = Command::new("git")
+        .args(["clone", "--quiet", "--depth", "1", repo])
+        .arg(&dir)
+        .output()
+        .map_err(|e| format!("git clone failed: {}", e))?;
+    let result = if output.status.success() {
+        std::fs::read(dir.join(path))
+            .map(|content| {
+                let sig = std::fs::read_to_string(dir.join(format!("{}.sig", path))).ok();
+                (content, sig)
+            })
+            .map_err(|e| format!("Cannot read {} in {}: {}", path, repo, e))
+    } else {
+        Err(format!(
+            "git clone {} failed: {}",
+            repo,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    };
+    let _ = std::fs::remove_dir_all(&dir);
+    result
+}
+
+/// Check a base64 Ed25519 `signature` of `content` against a base64 public key.
+pub fn verify(content: &[u8], signature: &str, public_key: &str) -> Result<(), String> {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let key = b64
+        .decode(public_key.trim())
+        .map_err(|_| "`[org] public_key` is not valid base64".to_string())?;
+    let signature = b64
+        .decode(signature.trim())
+        .map_err(|_| "Org config signature is not valid base64".to_string())?;
+    ring::signature::UnparsedPublicKey::new(&ring::signature::ED25519, key)
+        .verify(content, &signature)
+        .map_err(|_| "Org config signature does not match `[org] public_key`".to_string())
+}
+
+/// Sections (`policy`, `redaction`, ...) the org file marks as enforced.
+pub fn enforced_sections(org: &Table) -> Vec<String> {
+    org.get("org")
+        .and_then(|o| o.get("enforce"))
+        .and_then(|e| e.as_array())
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `local` layered over `org`, table by table. `org`'s enforced sections
+/// replace the local ones whole: a key the org leaves out gets its default,
+/// never the local value. The `[org]` section always comes from `local`.
+pub fn merge(mut org: Table, local: Table) -> Table {
+    let enforced = enforced_sections(&org);
+    org.remove("org");
+    let mut merged = org.clone();
+    overlay(&mut merged, local);
+    for section in enforced.into_iter().filter(|s| s != "org") {
+        match org.remove(&section) {
+            Some(value) => merged.insert(section, value),
+            None => merged.remove(&section),
+        };
+    }
+    merged
+}
+
+pub fn overlay(base: &mut Table, top: Table) {
+    for (key, value) in top {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Table(b)), toml::Value::Table(t)) => overlay(b, t),
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{Ed25519KeyPair, KeyPair};
+
+    #[test]
+    fn test_org_config_merge_and_signature() {
+        let org: Table = r#"
+[org]
+enforce = ["redaction"]
+
+[redaction]
+mode = "hash"
+disable_patterns = []
+
+[policy]
+min_receipt_coverage = 50.0
+fail_on_secrets = true
+"#
+        .parse()
+        .unwrap();
+        let local: Table = r#"
+[org]
+source = "https://example.com/blameprompt.toml"
+
+[redaction]
+mode = "replace"
+disable_patterns = ["email"]
+
+[policy]
+min_receipt_coverage = 10.0
+"#
+        .parse()
+        .unwrap();
+        let merged: BlamePromptConfig = toml::Value::Table(merge(org, local)).try_into().unwrap();
+        assert_eq!(merged.redaction.mode, "hash");
+        assert!(merged.redaction.disable_patterns.is_empty());
+        assert_eq!(merged.policy.min_receipt_coverage, 10.0);
+        assert!(merged.policy.fail_on_secrets);
+        assert_eq!(merged.org.source, "https://example.com/blameprompt.toml");
+
+        // A key the enforced section omits keeps its default, not the local value.
+        let org: Table =
+            "[org]\nenforce = [\"redaction\", \"privacy\"]\n\n[redaction]\nmode = \"hash\"\n"
+                .parse()
+                .unwrap();
+        let local: Table = "[redaction]\nmode = \"replace\"\ndisable_patterns = [\"email\"]\n\n[privacy]\nprompt_storage = \"full\"\n"
+            .parse()
+            .unwrap();
+        let merged: BlamePromptConfig = toml::Value::Table(merge(org, local)).try_into().unwrap();
+        let defaults = BlamePromptConfig::default();
+        assert_eq!(merged.redaction.mode, "hash");
+        assert!(merged.redaction.disable_patterns.is_empty());
+        assert_eq!(
+            merged.privacy.prompt_storage,
+            defaults.privacy.prompt_storage
+        );
+        assert!(fetch("http://example.com/blameprompt.toml")
+            .unwrap_err()
+            .contains("plain HTTP"));
+
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&rng).unwrap();
+        let pair = Ed25519KeyPair::from_pkcs8(pkcs8.as_ref()).unwrap();
+        let b64 = base64::engine::general_purpose::STANDARD;
+        let content = b"[policy]\nmin_receipt_coverage = 50.0\n";
+        let signature = b64.encode(pair.sign(content).as_ref());
+        let public_key = b64.encode(pair.public_key().as_ref());
+        assert!(verify(content, &signature, &public_key).is_ok());
+        assert!(verify(b"[policy]\n", &signature, &public_key).is_err());
+        assert!(verify(content, "not base64!", &public_key).is_err());
+    }
+}
@@ -0,0 +1,106 @@
+/// Co-authors for pair and mob programming sessions.
+///
+/// A shared agent session runs under one git config, so `user` names only
+/// whoever's machine it is. Everyone else is recorded in `co_authors`: from
+/// `[pairing] co_authors` while the session is captured, and from the
+/// commit's `Co-authored-by` trailers when the receipts are attached. People
+/// are matched by email, so `Jo <JO@x.com>` and `Jo Park <jo@x.com>` are one
+/// person.
+use crate::core::receipt::Receipt;
+use std::process::Command;
+
+/// The part of `Name <email>` that identifies a person: the lowercased email,
+/// or the whole string when there is none.
+pub fn identity_key(identity: &str) -> String {
+    identity
+        .rsplit_once('<')
+        .and_then(|(_, rest)| rest.strip_suffix('>'))
+        .unwrap_or(identity)
+        .trim()
+        .to_lowercase()
+}
+
+/// Add `names` to `receipt.co_authors`, skipping the receipt's own user and
+/// people already listed.
+pub fn add_co_authors(receipt: &mut Receipt, names: &[String]) {
+    for name in names.iter().map(|n| n.trim()).filter(|n| !n.is_empty()) {
+        let key = identity_key(name);
+        let known = identity_key(&receipt.user) == key
+            || receipt.co_authors.iter().any(|c| identity_key(c) == key);
+        if !known {
+            receipt.co_authors.push(name.to_string());
+        }
+    }
+}
+
+/// `author` followed by the receipt's co-authors, one entry per person.
+pub fn participants(author: &str, receipt: &Receipt) -> Vec<String> {
+    let mut people = vec![author.to_string()];
+    for c in &receipt.co_authors {
+        if !people.iter().any(|p| identity_key(p) == identity_key(c)) {
+            people.push(c.clone());
+        }
+    }
+    people
+}
+
+/// `Co-authored-by` values of `rev`'s message.
+pub fn commit_co_authors(rev: &str) -> Vec<String> {
+    Command::new("git")
+        .args([
+            "log",
+            "-1",
+            "--format=%(trailers:key=Co-authored-by,valueonly,unfold)",
+            rev,
+        ])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .map(String::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Record HEAD's `Co-authored-by` trailers on the receipts being attached to
+/// it. Called at attach time.
+pub fn fill_from_head(receipts: &mut [Receipt]) {
+    let trailers = commit_co_authors("HEAD");
+    if trailers.is_empty() {
+        return;
+    }
+    for r in receipts.iter_mut() {
+        add_co_authors(r, &trailers);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_co_authors_dedupe_by_email() {
+        let mut r = Receipt::test("r1").with_user("Dana <dana@example.com>");
+        add_co_authors(
+            &mut r,
+            &[
+                "Jo Park <jo@example.com>".to_string(),
+                "Dana Lee <DANA@example.com>".to_string(),
+                " ".to_string(),
+            ],
+        );
+        add_co_authors(&mut r, &["Jo <JO@example.com>".to_string()]);
+        assert_eq!(r.co_authors, vec!["Jo Park <jo@example.com>"]);
+
+        assert_eq!(
+            participants("Dana L <dana@example.com>", &r),
+            vec!["Dana L <dana@example.com>", "Jo Park <jo@example.com>"]
+        );
+        assert_eq!(identity_key("ci-bot"), "ci-bot");
+    }
+}
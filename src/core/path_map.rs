@@ -0,0 +1,180 @@
+/// Path mapping for agents running in a devcontainer, Codespace or over SSH
+/// (`[remote]` in config).
+///
+/// The hook payload's `cwd`, transcript path and file paths are the agent's
+/// view of the filesystem. When the hook binary runs on the other side of a
+/// mount (host vs container), those paths don't exist where it runs, and the
+/// transcript can't be read. Paths that exist are left alone. Otherwise the
+/// `[[remote.path_map]]` prefixes are tried, longest first. Then, with
+/// `auto`, two guesses: the payload's `cwd` is the hook's own working
+/// directory (agents start hooks in the project), and anything under a
+/// `.claude/` directory lives under this machine's `~/.claude/`.
+use crate::core::config::RemoteConfig;
+use std::path::{Path, PathBuf};
+
+pub struct PathMapper {
+    /// (remote prefix, local prefix), longest remote prefix first.
+    rules: Vec<(String, String)>,
+    home: Option<PathBuf>,
+    auto: bool,
+}
+
+impl PathMapper {
+    /// `payload_cwd` is the `cwd` from the hook payload; with `auto` it maps
+    /// to `local_cwd` when it doesn't exist here.
+    pub fn new(
+        cfg: &RemoteConfig,
+        payload_cwd: Option<&str>,
+        local_cwd: Option<&Path>,
+        home: Option<PathBuf>,
+    ) -> Self {
+        let mut rules: Vec<(String, String)> = cfg
+            .path_map
+            .iter()
+            .map(|m| (trim_slash(&m.remote), trim_slash(&m.local)))
+            .collect();
+        if let (true, Some(remote), Some(local)) = (cfg.auto, payload_cwd, local_cwd) {
+            let remote = trim_slash(remote);
+            let mapped_by_rule = rules.iter().any(|(r, _)| is_under(&remote, r));
+            if !mapped_by_rule && Path::new(&remote).is_absolute() && !Path::new(&remote).exists() {
+                rules.push((remote, trim_slash(&local.to_string_lossy())));
+            }
+        }
+        rules.sort_by_key(|(remote, _)| std::cmp::Reverse(remote.len()));
+        PathMapper {
+            rules,
+            home,
+            auto: cfg.auto,
+        }
+    }
+
+    /// `path` as seen here; unchanged when it exists, is relative, or no rule
+    /// or guess applies.
+    pub fn map(&self, path: &str) -> String {
+        let p = Path::new(path);
+        if !p.is_absolute() || p.exists() {
+            return path.to_string();
+        }
+        for (remote, local) in &self.rules {
+            if is_under(path, remote) {
+                return format!("{}{}", local, &path[remote.len()..]);
+            }
+        }
+        if self.auto {
+            if let (Some(home), Some(idx)) = (&self.home, path.find("/.claude/")) {
+                let candidate = home.join(&path[idx + 1..]);
+                if candidate.exists() {
+                    return candidate.to_string_lossy().to_string();
+                }
+            }
+        }
+        path.to_string()
+    }
+}
+
+fn trim_slash(p: &str) -> String {
+    match p.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    }
+}
+
+/// Whether `path` is `prefix` or inside it.
+fn is_under(path: &str, prefix: &str) -> bool {
+    path == prefix
+        || (path.starts_with(prefix)
+            && (prefix.ends_with('/') || path.as_bytes().get(prefix.len()) == Some(&b'/')))
+}
+
+/// The kind of remote setup this process runs in, for logs and `doctor`.
+pub fn environment() -> Option<&'static str> {
+    if std::env::var_os("CODESPACES").is_some() {
+        Some("codespaces")
+    } else if std::env::var_os("REMOTE_CONTAINERS").is_some()
+        || std::env::var_os("DEVCONTAINER").is_some()
+    {
+        Some("devcontainer")
+    } else if Path::new("/.dockerenv").exists() {
+        Some("docker")
+    } else if Path::new("/run/.containerenv").exists() {
+        Some("podman")
+    } else if std::env::var_os("SSH_CONNECTION").is_some() {
+        Some("ssh")
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::config::PathMapping;
+
+    #[test]
+    fn test_path_mapper() {
+        let dir = std::env::temp_dir().join(format!("bp-path-map-{}", std::process::id()));
+        let project = dir.join("src/app");
+        let home = dir.join("home");
+        std::fs::create_dir_all(&project).unwrap();
+        std::fs::create_dir_all(home.join(".claude/projects/-workspaces-app")).unwrap();
+        std::fs::write(home.join(".claude/projects/-workspaces-app/s.jsonl"), "").unwrap();
+        let project_str = project.to_string_lossy().to_string();
+
+        let mut cfg = RemoteConfig {
+            auto: false,
+            path_map: vec![
+                PathMapping {
+                    remote: "/workspaces/".to_string(),
+                    local: "/srv/all".to_string(),
+                },
+                PathMapping {
+                    remote: "/workspaces/app".to_string(),
+                    local: project_str.clone(),
+                },
+            ],
+        };
+        let mapper = PathMapper::new(
+            &cfg,
+            Some("/workspaces/app"),
+            Some(&dir),
+            Some(home.clone()),
+        );
+        assert_eq!(
+            mapper.map("/workspaces/app/src/main.rs"),
+            format!("{}/src/main.rs", project_str)
+        );
+        assert_eq!(mapper.map("/workspaces/other/a.rs"), "/srv/all/other/a.rs");
+        assert_eq!(
+            mapper.map("/workspaces/application/a.rs"),
+            "/srv/all/application/a.rs"
+        );
+        assert_eq!(mapper.map("src/main.rs"), "src/main.rs");
+        assert_eq!(
+            mapper.map("/home/vscode/.claude/projects/-workspaces-app/s.jsonl"),
+            "/home/vscode/.claude/projects/-workspaces-app/s.jsonl"
+        );
+
+        cfg.path_map.clear();
+        cfg.auto = true;
+        let mapper = PathMapper::new(
+            &cfg,
+            Some("/workspaces/app"),
+            Some(&project),
+            Some(home.clone()),
+        );
+        assert_eq!(mapper.map("/workspaces/app"), project_str);
+        assert_eq!(
+            mapper.map("/workspaces/app/lib.rs"),
+            format!("{}/lib.rs", project_str)
+        );
+        assert_eq!(
+            mapper.map("/home/vscode/.claude/projects/-workspaces-app/s.jsonl"),
+            home.join(".claude/projects/-workspaces-app/s.jsonl")
+                .to_string_lossy()
+        );
+        // Existing paths are never rewritten.
+        assert_eq!(mapper.map(&project_str), project_str);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -17,15 +17,77 @@ pub fn cost_from_usage(
     cache_read_tokens: u64,
     cache_creation_tokens: u64,
 ) -> f64 {
+    cost_breakdown(
+        model,
+        input_tokens,
+        output_tokens,
+        cache_read_tokens,
+        cache_creation_tokens,
+    )
+    .total_usd()
+}
+
+/// Spend split by token type, plus what prompt caching saved.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct CostBreakdown {
+    pub input_tokens: u64,
+    pub cache_creation_tokens: u64,
+    pub cache_read_tokens: u64,
+    pub output_tokens: u64,
+    pub fresh_input_usd: f64,
+    pub cache_write_usd: f64,
+    pub cache_read_usd: f64,
+    pub output_usd: f64,
+    /// What the cache reads would have cost as fresh input, less what they
+    /// and the cache writes' surcharge did cost. Negative when writes were
+    /// never read back enough to pay off.
+    pub cache_savings_usd: f64,
+}
+
+impl CostBreakdown {
+    pub fn total_usd(&self) -> f64 {
+        self.fresh_input_usd + self.cache_write_usd + self.cache_read_usd + self.output_usd
+    }
+
+    pub fn add(&mut self, other: &CostBreakdown) {
+        self.input_tokens += other.input_tokens;
+        self.cache_creation_tokens += other.cache_creation_tokens;
+        self.cache_read_tokens += other.cache_read_tokens;
+        self.output_tokens += other.output_tokens;
+        self.fresh_input_usd += other.fresh_input_usd;
+        self.cache_write_usd += other.cache_write_usd;
+        self.cache_read_usd += other.cache_read_usd;
+        self.output_usd += other.output_usd;
+        self.cache_savings_usd += other.cache_savings_usd;
+    }
+}
+
+/// `cost_from_usage`, itemized.
+pub fn cost_breakdown(
+    model: &str,
+    input_tokens: u64,
+    output_tokens: u64,
+    cache_read_tokens: u64,
+    cache_creation_tokens: u64,
+) -> CostBreakdown {
     let model_lower = model.to_lowercase();
     let (input_rate, output_rate) = get_rates(&model_lower);
     let cache_read_rate = input_rate * 0.1; // 90% discount
     let cache_creation_rate = input_rate * 1.25; // 25% surcharge
+    let usd = |tokens: u64, rate: f64| (tokens as f64 / 1_000_000.0) * rate;
 
-    (input_tokens as f64 / 1_000_000.0) * input_rate
-        + (output_tokens as f64 / 1_000_000.0) * output_rate
-        + (cache_read_tokens as f64 / 1_000_000.0) * cache_read_rate
-        + (cache_creation_tokens as f64 / 1_000_000.0) * cache_creation_rate
+    CostBreakdown {
+        input_tokens,
+        cache_creation_tokens,
+        cache_read_tokens,
+        output_tokens,
+        fresh_input_usd: usd(input_tokens, input_rate),
+        cache_write_usd: usd(cache_creation_tokens, cache_creation_rate),
+        cache_read_usd: usd(cache_read_tokens, cache_read_rate),
+        output_usd: usd(output_tokens, output_rate),
+        cache_savings_usd: usd(cache_read_tokens, input_rate - cache_read_rate)
+            - usd(cache_creation_tokens, cache_creation_rate - input_rate),
+    }
 }
 
 /// Estimate tokens from character count.
@@ -34,10 +96,89 @@ pub fn estimate_tokens_from_chars(char_count: usize) -> u64 {
     (char_count / 4) as u64
 }
 
+/// Approximate token count of `text` without a tokenizer, close to what the
+/// Claude and GPT BPE tokenizers produce for English prose and code: a word
+/// is about one token per 6 letters, numbers one per 3 digits, and every
+/// punctuation mark or non-ASCII character (CJK, emoji) about one token.
+pub fn estimate_tokens(text: &str) -> u64 {
+    let mut tokens = 0u64;
+    let (mut letters, mut digits) = (0u64, 0u64);
+    for c in text.chars() {
+        if c.is_ascii_alphabetic() {
+            tokens += digits.div_ceil(3);
+            digits = 0;
+            letters += 1;
+            continue;
+        }
+        if c.is_ascii_digit() {
+            tokens += letters.div_ceil(6);
+            letters = 0;
+            digits += 1;
+            continue;
+        }
+        tokens += letters.div_ceil(6) + digits.div_ceil(3);
+        letters = 0;
+        digits = 0;
+        if !c.is_whitespace() {
+            tokens += 1;
+        }
+    }
+    tokens + letters.div_ceil(6) + digits.div_ceil(3)
+}
+
+/// Runners for self-hosted models, as they appear in model ids
+/// (`ollama/qwen2.5-coder:7b`, `lmstudio/...`).
+const LOCAL_PREFIXES: &[&str] = &[
+    "ollama/",
+    "ollama:",
+    "ollama_chat/",
+    "lmstudio/",
+    "lm-studio/",
+    "llamacpp/",
+    "llama.cpp/",
+    "llama-cpp/",
+    "local/",
+    "mlx-community/",
+];
+
+/// Open-weight families; with an Ollama size tag (`llama3.1:8b`) they are
+/// running locally rather than through a paid API.
+const OPEN_WEIGHT_FAMILIES: &[&str] = &[
+    "llama",
+    "qwen",
+    "deepseek",
+    "codegemma",
+    "gemma",
+    "mistral",
+    "mixtral",
+    "devstral",
+    "codestral",
+    "phi",
+    "starcoder",
+    "granite",
+    "gpt-oss",
+];
+
+/// Local models (Ollama, llama.cpp, LM Studio, GGUF files) cost nothing per
+/// token; receipts still record their token counts.
+pub fn is_local_model(model: &str) -> bool {
+    let m = model.to_lowercase();
+    if LOCAL_PREFIXES.iter().any(|p| m.starts_with(p)) || m.contains("gguf") {
+        return true;
+    }
+    // `family:tag` is Ollama's naming. Bedrock ids also contain `:` but start
+    // with a vendor (`meta.llama3-8b-instruct-v1:0`), so they don't match.
+    m.contains(':') && OPEN_WEIGHT_FAMILIES.iter().any(|f| m.starts_with(f))
+}
+
 #[allow(clippy::if_same_then_else)]
 fn get_rates(model_lower: &str) -> (f64, f64) {
+    // ── Local / self-hosted ─────────────────────────────────────────────
+    if is_local_model(model_lower) {
+        (0.0, 0.0)
+    }
     // ── Anthropic (Claude) ──────────────────────────────────────────────
-    if model_lower.contains("opus-4-6")
+    else if model_lower.contains("opus-4-6")
         || model_lower.contains("opus-4.6")
         || model_lower.contains("4.6-opus")
     {
@@ -50,21 +191,40 @@ fn get_rates(model_lower: &str) -> (f64, f64) {
     } else if model_lower.contains("opus-4-1")
         || model_lower.contains("opus-4-0")
         || model_lower.contains("opus-4-20")
+        || model_lower.contains("4-opus")
+        || model_lower.contains("4.1-opus")
     {
         (15.00, 75.00)
     } else if model_lower.contains("sonnet-4.6") || model_lower.contains("4.6-sonnet") {
         (3.00, 15.00)
     } else if model_lower.contains("sonnet") {
         (3.00, 15.00)
-    } else if model_lower.contains("haiku-4-5") || model_lower.contains("haiku-4-") {
+    } else if model_lower.contains("haiku-4-5")
+        || model_lower.contains("haiku-4-")
+        || model_lower.contains("4.5-haiku")
+    {
         (1.00, 5.00)
     } else if model_lower.contains("haiku-3-5") || model_lower.contains("3-5-haiku") {
         (0.80, 4.00)
     } else if model_lower.contains("haiku-3") || model_lower.contains("3-haiku") {
         (0.25, 1.25)
     }
+    // ── Cursor ──────────────────────────────────────────────────────────
+    else if model_lower.contains("cursor-small") {
+        (0.0, 0.0) // included with every Cursor plan
+    } else if model_lower.contains("composer") {
+        (1.25, 10.00)
+    } else if model_lower == "auto" || model_lower == "cursor-auto" {
+        (1.25, 6.00)
+    }
     // ── OpenAI / Codex ──────────────────────────────────────────────────
-    else if model_lower.contains("o3-pro") {
+    else if model_lower.contains("gpt-5-nano") || model_lower.contains("gpt-5.1-nano") {
+        (0.05, 0.40)
+    } else if model_lower.contains("gpt-5-mini") || model_lower.contains("gpt-5.1-mini") {
+        (0.25, 2.00)
+    } else if model_lower.contains("gpt-5") {
+        (1.25, 10.00) // gpt-5, gpt-5.1, gpt-5-codex
+    } else if model_lower.contains("o3-pro") {
         (60.00, 240.00)
     } else if model_lower.contains("o3-mini") {
         (1.10, 4.40)
@@ -117,6 +277,18 @@ fn get_rates(model_lower: &str) -> (f64, f64) {
         // Generic Gemini — default to Flash pricing
         (0.15, 0.60)
     }
+    // ── DeepSeek / xAI (hosted APIs, also offered in Cursor) ────────────
+    else if model_lower.contains("deepseek-reasoner") || model_lower.contains("deepseek-r1") {
+        (0.55, 2.19)
+    } else if model_lower.contains("deepseek") {
+        (0.27, 1.10)
+    } else if model_lower.contains("grok-code-fast") {
+        (0.20, 1.50)
+    } else if model_lower.contains("grok-3-mini") {
+        (0.30, 0.50)
+    } else if model_lower.contains("grok") {
+        (3.00, 15.00)
+    }
     // ── Antigravity ─────────────────────────────────────────────────────
     else if model_lower.contains("antigravity-ultra") {
         (10.00, 40.00)
@@ -152,6 +324,28 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_cost_breakdown_and_cache_savings() {
+        let b = cost_breakdown("claude-sonnet-4-5", 1_000_000, 100_000, 2_000_000, 400_000);
+        assert!((b.fresh_input_usd - 3.0).abs() < 1e-9);
+        assert!((b.output_usd - 1.5).abs() < 1e-9);
+        assert!((b.cache_read_usd - 0.6).abs() < 1e-9);
+        assert!((b.cache_write_usd - 1.5).abs() < 1e-9);
+        // 2M reads at $2.70/M off, minus 0.4M writes at $0.75/M extra.
+        assert!((b.cache_savings_usd - (5.4 - 0.3)).abs() < 1e-9);
+        let total = cost_from_usage("claude-sonnet-4-5", 1_000_000, 100_000, 2_000_000, 400_000);
+        assert!((b.total_usd() - total).abs() < 1e-9);
+
+        let mut sum = CostBreakdown::default();
+        sum.add(&b);
+        sum.add(&b);
+        assert_eq!(sum.cache_read_tokens, 4_000_000);
+        assert_eq!(
+            cost_breakdown("ollama/qwen2.5-coder:7b", 10, 10, 10, 10).total_usd(),
+            0.0
+        );
+    }
+
     #[test]
     fn test_opus_4_6_pricing() {
         let cost = estimate_cost("claude-opus-4-6", 1000, 500);
@@ -244,6 +438,58 @@ mod tests {
         assert!((cost - expected).abs() < 0.0001);
     }
 
+    #[test]
+    fn test_gpt5_and_cursor_pricing() {
+        let cost = estimate_cost("gpt-5-codex", 1000, 500);
+        let expected = (1000.0 / 1_000_000.0) * 1.25 + (500.0 / 1_000_000.0) * 10.00;
+        assert!((cost - expected).abs() < 0.0001);
+
+        let mini = estimate_cost("gpt-5-mini", 1000, 500);
+        let expected_mini = (1000.0 / 1_000_000.0) * 0.25 + (500.0 / 1_000_000.0) * 2.00;
+        assert!((mini - expected_mini).abs() < 0.0001);
+
+        // Cursor's own names
+        assert_eq!(estimate_cost("cursor-small", 1000, 500), 0.0);
+        let opus = estimate_cost("claude-4-opus", 1000, 500);
+        let expected_opus = (1000.0 / 1_000_000.0) * 15.0 + (500.0 / 1_000_000.0) * 75.0;
+        assert!((opus - expected_opus).abs() < 0.0001);
+    }
+
+    #[test]
+    fn test_local_models_are_free() {
+        for model in [
+            "ollama/qwen2.5-coder:7b",
+            "qwen2.5-coder:7b",
+            "llama3.1:8b",
+            "deepseek-r1:14b",
+            "gpt-oss:20b",
+            "lmstudio/mistral-7b-instruct",
+            "Meta-Llama-3-8B-Instruct.Q4_K_M.gguf",
+        ] {
+            assert!(is_local_model(model), "{}", model);
+            assert_eq!(cost_from_usage(model, 10_000, 5_000, 1_000, 0), 0.0);
+        }
+        // Hosted APIs keep their price.
+        for model in [
+            "deepseek-chat",
+            "anthropic.claude-3-5-sonnet-20240620-v1:0",
+            "codestral-latest",
+            "gpt-oss-120b",
+        ] {
+            assert!(!is_local_model(model), "{}", model);
+            assert!(estimate_cost(model, 1000, 500) > 0.0);
+        }
+    }
+
+    #[test]
+    fn test_estimate_tokens() {
+        assert_eq!(estimate_tokens(""), 0);
+        assert_eq!(estimate_tokens("hello world"), 2);
+        assert_eq!(estimate_tokens("fn main() {}"), 6);
+        assert_eq!(estimate_tokens("internationalization 2026"), 6);
+        assert_eq!(estimate_tokens("日本語"), 3);
+    }
+
     #[test]
     fn test_estimate_tokens_from_chars() {
         assert_eq!(estimate_tokens_from_chars(400), 100);
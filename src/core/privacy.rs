@@ -0,0 +1,130 @@
+/// Prompt storage policy (`privacy.prompt_storage`).
+///
+/// `full` keeps prompt text unredacted, `redacted` (default) keeps it with
+/// secrets redacted, `hash` replaces every piece of text with its SHA-256, and
+/// `none` drops text entirely. Token counts, costs, file changes and prompt
+/// quality scores are computed before this runs, so analytics that don't read
+/// content keep working in every mode.
+use crate::core::config::PrivacyConfig;
+use crate::core::receipt::Receipt;
+use sha2::{Digest, Sha256};
+
+/// Strip or hash the text a receipt carries according to `cfg.prompt_storage`.
+pub fn apply(receipt: &mut Receipt, cfg: &PrivacyConfig) {
+    let scrub: fn(&str) -> String = match cfg.prompt_storage.as_str() {
+        "hash" => hash_text,
+        "none" => |_| String::new(),
+        _ => return,
+    };
+    let scrub_opt = |s: &Option<String>| s.as_deref().map(scrub).filter(|s| !s.is_empty());
+
+    receipt.prompt_summary = scrub(&receipt.prompt_summary);
+    receipt.intent_summary = scrub_opt(&receipt.intent_summary);
+    receipt.response_summary = scrub_opt(&receipt.response_summary);
+    receipt.plan_summary = None;
+    receipt.conversation = None;
+    for decision in &mut receipt.user_decisions {
+        decision.question = scrub(&decision.question);
+        decision.header = scrub_opt(&decision.header);
+        decision.answer = scrub_opt(&decision.answer);
+        for option in &mut decision.options {
+            option.label = scrub(&option.label);
+        }
+    }
+    for activity in &mut receipt.subagent_activities {
+        activity.description = scrub_opt(&activity.description);
+    }
+}
+
+/// `sha256:<hex>` of `text`; empty text stays empty so upserts still treat
+/// it as "not provided".
+fn hash_text(text: &str) -> String {
+    if text.is_empty() {
+        return String::new();
+    }
+    format!("sha256:{:x}", Sha256::digest(text.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::receipt::ConversationTurn;
+
+    fn receipt() -> Receipt {
+        let mut r = Receipt::test("r1")
+            .with_model("claude-sonnet-4-6")
+            .with_session("s1")
+            .with_prompt("add a parser for the billing export")
+            .with_cost(0.12)
+            .with_timestamp("2026-01-01T00:00:00Z")
+            .with_user("Dev <dev@example.com>")
+            .with_file("src/parser.rs", (1, 40), 0, 0);
+        r.response_summary = Some("Added parser.rs".to_string());
+        r.plan_summary = Some("1. read the format".to_string());
+        r.prompt_hash = "sha256:abc".to_string();
+        r.message_count = 2;
+        r.input_tokens = Some(1000);
+        r.conversation = Some(vec![ConversationTurn {
+            turn: 0,
+            role: "user".to_string(),
+            content: "add a parser".to_string(),
+            tool_name: None,
+            files_touched: None,
+        }]);
+        r.user_decisions = serde_json::from_value(serde_json::json!([{
+            "tool_use_id": "toolu_1",
+            "question": "Which crate?",
+            "options": [{"label": "nom", "selected": true}, {"label": "pest"}],
+            "answer": "nom"
+        }]))
+        .unwrap();
+        r
+    }
+
+    fn cfg(mode: &str) -> PrivacyConfig {
+        PrivacyConfig {
+            prompt_storage: mode.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_hash_mode_keeps_only_hashes() {
+        let mut r = receipt();
+        apply(&mut r, &cfg("hash"));
+        assert_eq!(
+            r.prompt_summary,
+            hash_text("add a parser for the billing export")
+        );
+        assert!(r.response_summary.unwrap().starts_with("sha256:"));
+        assert!(r.plan_summary.is_none());
+        assert!(r.conversation.is_none());
+        let d = &r.user_decisions[0];
+        assert!(d.question.starts_with("sha256:"));
+        assert!(d.options[0].selected);
+        assert_ne!(d.options[0].label, "nom");
+        // Non-content fields survive.
+        assert_eq!(r.input_tokens, Some(1000));
+        assert_eq!(r.files_changed[0].path, "src/parser.rs");
+        assert_eq!(r.prompt_hash, "sha256:abc");
+    }
+
+    #[test]
+    fn test_none_mode_drops_text() {
+        let mut r = receipt();
+        apply(&mut r, &cfg("none"));
+        assert!(r.prompt_summary.is_empty());
+        assert!(r.response_summary.is_none());
+        assert!(r.user_decisions[0].answer.is_none());
+        assert_eq!(r.cost_usd, 0.12);
+    }
+
+    #[test]
+    fn test_text_modes_leave_receipt_alone() {
+        for mode in ["redacted", "full"] {
+            let mut r = receipt();
+            apply(&mut r, &cfg(mode));
+            assert_eq!(r.prompt_summary, "add a parser for the billing export");
+            assert!(r.conversation.is_some());
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use crate::core::environment::Environment;
 use crate::core::prompt_eval::PromptQuality;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -35,6 +36,21 @@ pub struct SubagentActivity {
     /// Tools used by this subagent (e.g., "Glob", "Grep", "Read").
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tools_used: Vec<String>,
+    /// Model the subagent ran on, from its own transcript.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model: Option<String>,
+    /// Tokens the subagent used, summed over its own transcript at SubagentStop.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub input_tokens: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub output_tokens: Option<u64>,
+    /// Cost of the subagent's transcript, cache pricing included. Subagents
+    /// keep their own transcripts, so this is not part of the receipt's `cost_usd`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost_usd: Option<f64>,
+    /// Files the subagent wrote or edited.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files_touched: Vec<String>,
 }
 
 /// A single option presented in an AskUserQuestion prompt.
@@ -82,6 +98,46 @@ pub struct FileChange {
     /// Lines deleted from this file.
     #[serde(default, skip_serializing_if = "is_zero_u32")]
     pub deletions: u32,
+    /// Notebook cells touched by this change (cell id, or `#<index>` for cells
+    /// without one). Only set for `.ipynb` files.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub notebook_cells: Vec<String>,
+    /// Binary asset (image, archive, ...). Line counts are meaningless for these,
+    /// so additions/deletions stay 0 and the byte sizes below are recorded instead.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_binary: bool,
+    /// Size in bytes before the change (0 for new files). Binary files only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_before: Option<u64>,
+    /// Size in bytes after the change (0 for deleted files). Binary files only.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bytes_after: Option<u64>,
+    /// How the file changed, from `git status`. Absent on older receipts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change_type: Option<ChangeType>,
+    /// Previous path when `change_type` is `renamed`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_path: Option<String>,
+    /// Line ranges of the AI-written file (as of `blob_hash`) that were still
+    /// unstaged when the commit was made, e.g. after `git add -p`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unstaged_ranges: Vec<(u32, u32)>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChangeType {
+    Added,
+    Modified,
+    Deleted,
+    Renamed,
+}
+
+impl FileChange {
+    /// Signed byte-size change for binary files; 0 when sizes were not recorded.
+    pub fn byte_delta(&self) -> i64 {
+        self.bytes_after.unwrap_or(0) as i64 - self.bytes_before.unwrap_or(0) as i64
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -91,9 +147,17 @@ pub struct Receipt {
     pub model: String,
     pub session_id: String,
     pub prompt_summary: String,
+    /// One-line intent of the prompt from the configured summarizer
+    /// (`[summarizer]`); `prompt_summary` keeps the truncated raw text.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub intent_summary: Option<String>,
     /// Summary of the AI's response — what it actually did (from Stop hook's last_assistant_message).
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub response_summary: Option<String>,
+    /// What the AI intended: plan-mode output (and optionally extended thinking),
+    /// captured only when `capture.store_plans` is enabled. Size-capped and redacted.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plan_summary: Option<String>,
     pub prompt_hash: String,
     pub message_count: u32,
     pub cost_usd: f64,
@@ -106,6 +170,10 @@ pub struct Receipt {
     pub cache_read_tokens: Option<u64>,
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cache_creation_tokens: Option<u64>,
+    /// Tokens and cost were estimated from the conversation text because the
+    /// provider reported no usage; reports keep these apart from actual spend.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub cost_estimated: bool,
     pub timestamp: DateTime<Utc>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub session_start: Option<DateTime<Utc>>,
@@ -128,7 +196,16 @@ pub struct Receipt {
     /// Lines the AI wrote that were subsequently modified by the human before committing.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub overridden_lines: Option<u32>,
+    /// The acceptance stats come from file states reconstructed after the
+    /// fact (imported sessions), not from blobs captured as the AI wrote.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub acceptance_estimated: bool,
     pub user: String,
+    /// Other people at the keyboard for this prompt (pair or mob programming),
+    /// as `Name <email>`: from `[pairing] co_authors` while capturing and the
+    /// commit's `Co-authored-by` trailers at attach time.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub co_authors: Vec<String>,
     /// Deprecated: use files_changed instead. Kept for backwards compat with old git notes.
     #[serde(default)]
     pub file_path: String,
@@ -153,6 +230,10 @@ pub struct Receipt {
     /// Used to create separate receipts per prompt within the same session.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub prompt_number: Option<u32>,
+    /// Tool call (`tool_use_id`) this receipt covers, when `capture.granularity` is
+    /// `tool_call`. Absent on prompt- and session-level receipts.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_use_id: Option<String>,
     /// Total lines added across all files in this prompt.
     #[serde(default, skip_serializing_if = "is_zero_u32")]
     pub total_additions: u32,
@@ -162,9 +243,13 @@ pub struct Receipt {
     /// Tools used during this prompt session (e.g., "Bash", "Write", "Edit", "Grep").
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub tools_used: Vec<String>,
-    /// MCP servers called during this session (extracted from mcp__<server>__<tool> pattern).
+    /// MCP servers called during this session (extracted from `mcp__<server>__<tool>` pattern).
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub mcp_servers: Vec<String>,
+    /// Agent version, generation settings and MCP server versions, for
+    /// reproducing how the code was written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Environment>,
     /// Sub-agents spawned via the Task tool during this session.
     #[serde(default, skip_serializing_if = "Vec::is_empty")]
     pub agents_spawned: Vec<String>,
@@ -182,6 +267,21 @@ pub struct Receipt {
     /// Prompt quality assessment from the evaluation engine.
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub prompt_quality: Option<PromptQuality>,
+    /// Ticket/issue ID (e.g. `PROJ-123`) from the branch name or prompt, for cost allocation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ticket: Option<String>,
+    /// Best-effort receipt reconstructed from a chat export (e.g. chatgpt.com) rather
+    /// than captured live by a hook. File attribution is inferred from snippet matching.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub manual_import: bool,
+    /// Provisional receipt from `watch-files`: inferred from machine-speed
+    /// edits, with no prompt or conversation behind it.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub heuristic: bool,
+    /// Set when `[compaction]` merged this many consecutive prompts without
+    /// file changes into this receipt at attach time.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub housekeeping_prompts: Option<u32>,
 }
 
 fn default_line_range() -> (u32, u32) {
@@ -213,6 +313,13 @@ impl Receipt {
                 blob_hash: None,
                 additions: 0,
                 deletions: 0,
+                notebook_cells: vec![],
+                is_binary: false,
+                bytes_before: None,
+                bytes_after: None,
+                change_type: None,
+                old_path: None,
+                unstaged_ranges: Vec::new(),
             }]
         } else {
             vec![]
@@ -246,7 +353,11 @@ impl Receipt {
         if self.total_additions > 0 {
             return self.total_additions;
         }
-        self.all_file_changes().iter().map(|fc| fc.additions).sum()
+        self.all_file_changes()
+            .iter()
+            .filter(|fc| !fc.is_binary)
+            .map(|fc| fc.additions)
+            .sum()
     }
 
     #[allow(dead_code)]
@@ -254,7 +365,114 @@ impl Receipt {
         if self.total_deletions > 0 {
             return self.total_deletions;
         }
-        self.all_file_changes().iter().map(|fc| fc.deletions).sum()
+        self.all_file_changes()
+            .iter()
+            .filter(|fc| !fc.is_binary)
+            .map(|fc| fc.deletions)
+            .sum()
+    }
+
+    /// Cost of the subagents spawned by this prompt, on top of `cost_usd`.
+    pub fn subagent_cost_usd(&self) -> f64 {
+        self.subagent_activities
+            .iter()
+            .filter_map(|a| a.cost_usd)
+            .sum()
+    }
+}
+
+/// Test fixtures: `Receipt::test("r1").with_user("dev").with_file("a.rs", (1, 3), 3, 0)`.
+#[cfg(test)]
+impl Receipt {
+    /// A receipt with placeholder values for the required fields (provider
+    /// `claude`, model `sonnet`, session `s`, prompt `p`, one message, no
+    /// cost, user `u`, 2026-03-01T10:00:00Z) and serde's defaults for the
+    /// rest, as a note carrying only those fields would parse.
+    pub fn test(id: &str) -> Receipt {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "provider": "claude",
+            "model": "sonnet",
+            "session_id": "s",
+            "prompt_summary": "p",
+            "prompt_hash": "h",
+            "message_count": 1,
+            "cost_usd": 0.0,
+            "timestamp": "2026-03-01T10:00:00Z",
+            "user": "u",
+        }))
+        .unwrap()
+    }
+
+    pub fn with_provider(mut self, provider: &str) -> Receipt {
+        self.provider = provider.to_string();
+        self
+    }
+
+    pub fn with_model(mut self, model: &str) -> Receipt {
+        self.model = model.to_string();
+        self
+    }
+
+    pub fn with_session(mut self, session_id: &str) -> Receipt {
+        self.session_id = session_id.to_string();
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: &str) -> Receipt {
+        self.prompt_summary = prompt.to_string();
+        self
+    }
+
+    pub fn with_user(mut self, user: &str) -> Receipt {
+        self.user = user.to_string();
+        self
+    }
+
+    pub fn with_cost(mut self, cost_usd: f64) -> Receipt {
+        self.cost_usd = cost_usd;
+        self
+    }
+
+    /// `timestamp` is RFC 3339.
+    pub fn with_timestamp(mut self, timestamp: &str) -> Receipt {
+        self.timestamp = timestamp.parse().unwrap();
+        self
+    }
+
+    pub fn with_file(
+        self,
+        path: &str,
+        line_range: (u32, u32),
+        additions: u32,
+        deletions: u32,
+    ) -> Receipt {
+        self.with_change(FileChange::test(path, line_range, additions, deletions))
+    }
+
+    pub fn with_change(mut self, change: FileChange) -> Receipt {
+        self.files_changed.push(change);
+        self
+    }
+}
+
+#[cfg(test)]
+impl FileChange {
+    pub fn test(path: &str, line_range: (u32, u32), additions: u32, deletions: u32) -> FileChange {
+        FileChange {
+            path: path.to_string(),
+            line_range,
+            blob_hash: None,
+            additions,
+            deletions,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
+        }
     }
 }
 
@@ -277,6 +495,9 @@ pub struct Hunk {
     pub prompt_turn: Option<u32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub model: Option<String>,
+    /// Receipt whose edit wrote (or was edited into) these lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub receipt_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -293,31 +514,153 @@ pub struct CodeOriginStats {
     pub ai_generated_pct: f64,
     pub human_edited_pct: f64,
     pub pure_human_pct: f64,
+    /// Line counts behind the percentages; zero on notes attached before
+    /// they were recorded.
+    #[serde(default)]
+    pub ai_generated_lines: u32,
+    #[serde(default)]
+    pub human_edited_lines: u32,
+    #[serde(default)]
+    pub pure_human_lines: u32,
+    /// The same counts without blank and brace-only lines (see
+    /// `line_origin::is_trivial_line`); absent on older notes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalized: Option<LineCounts>,
+}
+
+/// Added lines by origin.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct LineCounts {
+    pub ai_generated: u32,
+    pub human_edited: u32,
+    pub pure_human: u32,
+}
+
+impl LineCounts {
+    pub fn total(&self) -> u32 {
+        self.ai_generated + self.human_edited + self.pure_human
+    }
+
+    pub fn ai(&self) -> u32 {
+        self.ai_generated + self.human_edited
+    }
+}
+
+impl CodeOriginStats {
+    /// Stats for the given line counts; None when there are no lines.
+    pub fn from_lines(ai_generated: u32, human_edited: u32, pure_human: u32) -> Option<Self> {
+        let total = ai_generated + human_edited + pure_human;
+        let pct = |n: u32| n as f64 / total as f64 * 100.0;
+        (total > 0).then(|| CodeOriginStats {
+            ai_generated_pct: pct(ai_generated),
+            human_edited_pct: pct(human_edited),
+            pure_human_pct: pct(pure_human),
+            ai_generated_lines: ai_generated,
+            human_edited_lines: human_edited,
+            pure_human_lines: pure_human,
+            normalized: None,
+        })
+    }
+
+    /// The raw line counts.
+    pub fn raw(&self) -> LineCounts {
+        LineCounts {
+            ai_generated: self.ai_generated_lines,
+            human_edited: self.human_edited_lines,
+            pure_human: self.pure_human_lines,
+        }
+    }
+
+    pub fn total_lines(&self) -> u32 {
+        self.ai_generated_lines + self.human_edited_lines + self.pure_human_lines
+    }
+
+    /// Lines that came from an AI edit, as written or tweaked by a human.
+    pub fn ai_lines(&self) -> u32 {
+        self.ai_generated_lines + self.human_edited_lines
+    }
+
+    /// Combined stats over several commits; only stats with line counts add up.
+    pub fn sum<'a>(stats: impl IntoIterator<Item = &'a CodeOriginStats>) -> Option<Self> {
+        let (mut ai, mut edited, mut human) = (0, 0, 0);
+        let mut normalized: Option<LineCounts> = None;
+        for s in stats {
+            ai += s.ai_generated_lines;
+            edited += s.human_edited_lines;
+            human += s.pure_human_lines;
+            if let Some(n) = s.normalized {
+                let sum = normalized.get_or_insert_with(LineCounts::default);
+                sum.ai_generated += n.ai_generated;
+                sum.human_edited += n.human_edited;
+                sum.pure_human += n.pure_human;
+            }
+        }
+        Self::from_lines(ai, edited, human).map(|mut s| {
+            s.normalized = normalized;
+            s
+        })
+    }
+}
+
+/// Current note schema; see `core::migrate` for how older notes are upgraded.
+pub const SCHEMA_VERSION: u32 = 2;
+
+fn legacy_schema_version() -> u32 {
+    crate::core::migrate::LEGACY_SCHEMA_VERSION
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct NotePayload {
+    /// Absent in notes written before versioning (version 1).
+    #[serde(default = "legacy_schema_version")]
+    pub schema_version: u32,
     pub blameprompt_version: String,
     pub receipts: Vec<Receipt>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub file_mappings: Option<Vec<FileMapping>>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub code_origin: Option<CodeOriginStats>,
+    /// The pull request this commit landed through, set by `link-pr`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pull_request: Option<PullRequestLink>,
+    /// Receipts attached before their acceptance stats were computed.
+    /// `blameprompt finalize` scores them, classifies the commit's lines and
+    /// clears the list.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub pending_finalize: Vec<String>,
+}
+
+/// A pull request a commit was merged through.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct PullRequestLink {
+    pub number: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    /// The merge or squash commit the PR landed as, when detected from history.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merge_commit: Option<String>,
+    /// The attribution comment `github-comment` posted on the PR, so later
+    /// runs edit it instead of posting another.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub comment_id: Option<u64>,
 }
 
 impl NotePayload {
     pub fn new(receipts: Vec<Receipt>) -> Self {
         NotePayload {
+            schema_version: SCHEMA_VERSION,
             blameprompt_version: env!("CARGO_PKG_VERSION").to_string(),
             receipts,
             file_mappings: None,
             code_origin: None,
+            pull_request: None,
+            pending_finalize: Vec::new(),
         }
     }
 
-    #[allow(dead_code)]
     pub fn with_file_mappings(receipts: Vec<Receipt>, file_mappings: Vec<FileMapping>) -> Self {
         NotePayload {
+            schema_version: SCHEMA_VERSION,
             blameprompt_version: env!("CARGO_PKG_VERSION").to_string(),
             receipts,
             file_mappings: if file_mappings.is_empty() {
@@ -326,8 +669,19 @@ impl NotePayload {
                 Some(file_mappings)
             },
             code_origin: None,
+            pull_request: None,
+            pending_finalize: Vec::new(),
         }
     }
+
+    /// Lines of the commit that came from AI edits, per the line-level
+    /// classification made at attach time. None for notes without it.
+    pub fn mapped_ai_lines(&self) -> Option<u32> {
+        self.code_origin
+            .as_ref()
+            .filter(|o| o.total_lines() > 0)
+            .map(CodeOriginStats::ai_lines)
+    }
 }
 
 #[cfg(test)]
@@ -365,6 +719,13 @@ mod tests {
                     blob_hash: None,
                     additions: 10,
                     deletions: 0,
+                    notebook_cells: vec![],
+                    is_binary: false,
+                    bytes_before: None,
+                    bytes_after: None,
+                    change_type: None,
+                    old_path: None,
+                    unstaged_ranges: Vec::new(),
                 },
                 FileChange {
                     path: "src/lib.rs".to_string(),
@@ -372,6 +733,13 @@ mod tests {
                     blob_hash: None,
                     additions: 16,
                     deletions: 2,
+                    notebook_cells: vec![],
+                    is_binary: false,
+                    bytes_before: None,
+                    bytes_after: None,
+                    change_type: None,
+                    old_path: None,
+                    unstaged_ranges: Vec::new(),
                 },
             ],
             parent_receipt_id: None,
@@ -383,6 +751,7 @@ mod tests {
             total_deletions: 2,
             tools_used: vec!["Write".to_string(), "Bash".to_string()],
             mcp_servers: vec![],
+            environment: None,
             agents_spawned: vec![],
             subagent_activities: vec![],
             concurrent_tool_calls: None,
@@ -393,6 +762,16 @@ mod tests {
             accepted_lines: None,
             overridden_lines: None,
             prompt_quality: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
         };
 
         let json = serde_json::to_string_pretty(&receipt).unwrap();
@@ -439,6 +818,7 @@ mod tests {
             total_deletions: 0,
             tools_used: vec![],
             mcp_servers: vec![],
+            environment: None,
             agents_spawned: vec![],
             subagent_activities: vec![],
             concurrent_tool_calls: None,
@@ -449,6 +829,16 @@ mod tests {
             accepted_lines: None,
             overridden_lines: None,
             prompt_quality: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
         };
 
         let json = serde_json::to_string(&receipt).unwrap();
@@ -480,6 +870,25 @@ mod tests {
         assert!(!json.contains("user_decisions"));
     }
 
+    #[test]
+    fn test_binary_file_change() {
+        let fc: FileChange = serde_json::from_str(
+            r#"{"path":"assets/logo.png","line_range":[0,0],"is_binary":true,"bytes_before":100,"bytes_after":250}"#,
+        )
+        .unwrap();
+        assert!(fc.is_binary);
+        assert_eq!(fc.byte_delta(), 150);
+
+        let text: FileChange =
+            serde_json::from_str(r#"{"path":"src/main.rs","line_range":[1,5],"additions":5}"#)
+                .unwrap();
+        assert!(!text.is_binary);
+        assert_eq!(text.byte_delta(), 0);
+        let json = serde_json::to_string(&text).unwrap();
+        assert!(!json.contains("is_binary"));
+        assert!(!json.contains("bytes_before"));
+    }
+
     #[test]
     fn test_all_file_changes_new_format() {
         let receipt = Receipt {
@@ -511,6 +920,13 @@ mod tests {
                     blob_hash: None,
                     additions: 0,
                     deletions: 0,
+                    notebook_cells: vec![],
+                    is_binary: false,
+                    bytes_before: None,
+                    bytes_after: None,
+                    change_type: None,
+                    old_path: None,
+                    unstaged_ranges: Vec::new(),
                 },
                 FileChange {
                     path: "b.rs".to_string(),
@@ -518,6 +934,13 @@ mod tests {
                     blob_hash: None,
                     additions: 0,
                     deletions: 0,
+                    notebook_cells: vec![],
+                    is_binary: false,
+                    bytes_before: None,
+                    bytes_after: None,
+                    change_type: None,
+                    old_path: None,
+                    unstaged_ranges: Vec::new(),
                 },
             ],
             parent_receipt_id: None,
@@ -529,6 +952,7 @@ mod tests {
             total_deletions: 0,
             tools_used: vec![],
             mcp_servers: vec![],
+            environment: None,
             agents_spawned: vec![],
             subagent_activities: vec![],
             concurrent_tool_calls: None,
@@ -539,6 +963,16 @@ mod tests {
             accepted_lines: None,
             overridden_lines: None,
             prompt_quality: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
         };
         let changes = receipt.all_file_changes();
         assert_eq!(changes.len(), 2);
@@ -579,6 +1013,7 @@ mod tests {
             total_deletions: 0,
             tools_used: vec![],
             mcp_servers: vec![],
+            environment: None,
             agents_spawned: vec![],
             subagent_activities: vec![],
             concurrent_tool_calls: None,
@@ -589,6 +1024,16 @@ mod tests {
             accepted_lines: None,
             overridden_lines: None,
             prompt_quality: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
         };
         let changes = receipt.all_file_changes();
         assert_eq!(changes.len(), 1);
@@ -659,5 +1104,43 @@ mod tests {
         let json = serde_json::to_string_pretty(&payload).unwrap();
         assert!(json.contains("blameprompt_version"));
         assert!(json.contains(env!("CARGO_PKG_VERSION")));
+        assert!(json.contains(&format!("\"schema_version\": {}", SCHEMA_VERSION)));
+    }
+
+    #[test]
+    fn test_code_origin_line_counts() {
+        let a = CodeOriginStats::from_lines(6, 2, 2).unwrap();
+        assert_eq!(a.ai_generated_pct, 60.0);
+        assert_eq!(a.ai_lines(), 8);
+        assert!(CodeOriginStats::from_lines(0, 0, 0).is_none());
+
+        // Notes from before line counts were stored only carry percentages.
+        let legacy: CodeOriginStats = serde_json::from_str(
+            r#"{"ai_generated_pct": 50.0, "human_edited_pct": 0.0, "pure_human_pct": 50.0}"#,
+        )
+        .unwrap();
+        let total =
+            CodeOriginStats::sum([&a, &legacy, &CodeOriginStats::from_lines(0, 0, 10).unwrap()])
+                .unwrap();
+        assert_eq!(total.total_lines(), 20);
+        assert_eq!(total.pure_human_pct, 60.0);
+        assert_eq!(total.normalized, None);
+
+        let mut b = CodeOriginStats::from_lines(4, 0, 6).unwrap();
+        b.normalized = Some(LineCounts {
+            ai_generated: 3,
+            human_edited: 0,
+            pure_human: 5,
+        });
+        let both = CodeOriginStats::sum([&a, &b]).unwrap();
+        assert_eq!(both.raw().ai(), 12);
+        assert_eq!(both.normalized.map(|n| (n.ai(), n.total())), Some((3, 8)));
+
+        let mut payload = NotePayload::new(vec![]);
+        assert_eq!(payload.mapped_ai_lines(), None);
+        payload.code_origin = Some(legacy);
+        assert_eq!(payload.mapped_ai_lines(), None);
+        payload.code_origin = Some(a);
+        assert_eq!(payload.mapped_ai_lines(), Some(8));
     }
 }
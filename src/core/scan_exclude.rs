@@ -0,0 +1,168 @@
+/// Vendored and generated code the scanners skip (`[scan]` in config).
+///
+/// `vuln-scan`, `license-scan` and `prompt-injection` look at every file a
+/// receipt touched. An agent that ran `npm install` or a code generator would
+/// otherwise flood their reports with code nobody reviews by hand. A file is
+/// skipped when its path matches an `exclude` pattern (gitignore syntax) or
+/// one of its first lines carries a generated marker such as `@generated`.
+/// Each scanner can replace or extend the shared lists, and each report lists
+/// what it skipped so reviewers can tell "clean" from "not looked at".
+use crate::core::config::{ScanConfig, ScannerExclusions};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+/// How many leading lines are searched for a generated marker.
+const MARKER_LINES: usize = 5;
+
+pub struct ScanExclusions {
+    root: PathBuf,
+    matcher: Option<Gitignore>,
+    markers: Vec<String>,
+}
+
+impl ScanExclusions {
+    /// The exclusions for one scanner, rooted at the current directory.
+    pub fn load(cfg: &ScanConfig, scanner: &ScannerExclusions) -> Self {
+        let root = std::env::current_dir().unwrap_or_default();
+        Self::new(&root, cfg, scanner)
+    }
+
+    pub fn new(root: &Path, cfg: &ScanConfig, scanner: &ScannerExclusions) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        let shared = scanner.exclude.as_ref().unwrap_or(&cfg.exclude);
+        for pattern in shared.iter().chain(&scanner.extra_exclude) {
+            if let Err(e) = builder.add_line(None, pattern) {
+                tracing::warn!(pattern = %pattern, error = %e, "invalid [scan] exclude pattern");
+            }
+        }
+        ScanExclusions {
+            root: root.to_path_buf(),
+            matcher: builder.build().ok().filter(|m| !m.is_empty()),
+            markers: scanner
+                .generated_markers
+                .clone()
+                .unwrap_or_else(|| cfg.generated_markers.clone()),
+        }
+    }
+
+    /// Why `path` is skipped: the pattern it matched or the marker it
+    /// carries; `None` when it should be scanned.
+    pub fn reason(&self, path: &str) -> Option<String> {
+        if let Some(pattern) = self.matched_pattern(path) {
+            return Some(format!("matches {}", pattern));
+        }
+        let file = std::fs::File::open(self.root.join(path)).ok()?;
+        BufReader::new(file)
+            .lines()
+            .take(MARKER_LINES)
+            .map_while(Result::ok)
+            .find_map(|line| self.markers.iter().find(|m| line.contains(m.as_str())))
+            .map(|marker| format!("generated ({})", marker))
+    }
+
+    fn matched_pattern(&self, path: &str) -> Option<String> {
+        let matcher = self.matcher.as_ref()?;
+        let path = Path::new(path);
+        let rel = if path.is_absolute() {
+            path.strip_prefix(&self.root).ok()?
+        } else {
+            path
+        };
+        match matcher.matched_path_or_any_parents(rel, false) {
+            ignore::Match::Ignore(glob) => Some(glob.original().to_string()),
+            _ => None,
+        }
+    }
+}
+
+/// Files skipped by a scan, with their reasons, for the report.
+#[derive(Debug, Default)]
+pub struct Skipped(Vec<(String, String)>);
+
+impl Skipped {
+    pub fn add(&mut self, path: String, reason: String) {
+        if !self.0.iter().any(|(p, _)| *p == path) {
+            self.0.push((path, reason));
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// A markdown section listing the skipped files; empty when none were.
+    pub fn to_markdown(&self) -> String {
+        if self.0.is_empty() {
+            return String::new();
+        }
+        let mut md = format!(
+            "## Excluded Files\n\n{} file(s) were not scanned (see `[scan]` in `.blamepromptrc`):\n\n",
+            self.0.len()
+        );
+        md.push_str("| File | Reason |\n|------|--------|\n");
+        for (path, reason) in &self.0 {
+            md.push_str(&format!("| `{}` | {} |\n", path, reason));
+        }
+        md.push('\n');
+        md
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan_exclusions() {
+        let dir = std::env::temp_dir().join(format!("bp-scan-exclude-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("src")).unwrap();
+        std::fs::write(
+            dir.join("src/gen.rs"),
+            "// @generated by protoc\nfn a() {}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+
+        let cfg = ScanConfig::default();
+        let shared = ScanExclusions::new(&dir, &cfg, &ScannerExclusions::default());
+        assert_eq!(
+            shared.reason("vendor/lib/a.go").as_deref(),
+            Some("matches vendor/")
+        );
+        assert!(shared.reason("web/node_modules/x/index.js").is_some());
+        assert!(shared
+            .reason(&dir.join("vendor/a.go").to_string_lossy())
+            .is_some());
+        assert_eq!(
+            shared.reason("src/gen.rs").as_deref(),
+            Some("generated (@generated)")
+        );
+        assert_eq!(shared.reason("src/main.rs"), None);
+
+        let scanner = ScannerExclusions {
+            exclude: Some(Vec::new()),
+            extra_exclude: vec!["*.pb.go".to_string()],
+            generated_markers: Some(Vec::new()),
+        };
+        let own = ScanExclusions::new(&dir, &cfg, &scanner);
+        assert_eq!(own.reason("vendor/lib/a.go"), None);
+        assert!(own.reason("api/a.pb.go").is_some());
+        assert_eq!(own.reason("src/gen.rs"), None);
+
+        let mut skipped = Skipped::default();
+        skipped.add("vendor/a.go".to_string(), "matches vendor/".to_string());
+        skipped.add("vendor/a.go".to_string(), "matches vendor/".to_string());
+        assert_eq!(skipped.len(), 1);
+        assert!(skipped
+            .to_markdown()
+            .contains("| `vendor/a.go` | matches vendor/ |"));
+        assert!(Skipped::default().to_markdown().is_empty());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -178,6 +178,7 @@ mod tests {
             total_deletions: 0,
             tools_used: vec![],
             mcp_servers: vec![],
+            environment: None,
             agents_spawned: vec![],
             subagent_activities: vec![],
             concurrent_tool_calls: None,
@@ -188,6 +189,16 @@ mod tests {
             accepted_lines: None,
             overridden_lines: None,
             prompt_quality: None,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: false,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
         }
     }
 
@@ -0,0 +1,192 @@
+/// Minimal SMTP client for sending an HTML email (`digest --send`).
+///
+/// Port 465 speaks TLS from the first byte; any other port must offer
+/// STARTTLS, so credentials and content never cross the network in clear.
+/// Authenticates with AUTH PLAIN when a user is given.
+use base64::Engine;
+use std::io::{Read, Write};
+use std::net::{TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::time::Duration;
+
+const TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct Mail<'a> {
+    pub from: &'a str,
+    pub to: &'a [String],
+    pub subject: &'a str,
+    pub html: &'a str,
+}
+
+pub struct Server<'a> {
+    pub host: &'a str,
+    pub port: u16,
+    pub user: &'a str,
+    pub password: &'a str,
+}
+
+trait Stream: Read + Write {}
+impl<T: Read + Write> Stream for T {}
+
+pub fn send(server: &Server, mail: &Mail) -> Result<(), String> {
+    let addr = (server.host, server.port)
+        .to_socket_addrs()
+        .map_err(|e| format!("Cannot resolve {}: {}", server.host, e))?
+        .next()
+        .ok_or_else(|| format!("Cannot resolve {}", server.host))?;
+    let mut tcp = TcpStream::connect_timeout(&addr, TIMEOUT)
+        .map_err(|e| format!("Cannot connect to {}:{}: {}", server.host, server.port, e))?;
+    tcp.set_read_timeout(Some(TIMEOUT)).ok();
+    tcp.set_write_timeout(Some(TIMEOUT)).ok();
+
+    let mut stream: Box<dyn Stream> = if server.port == 465 {
+        let mut tls = tls_stream(server.host, tcp)?;
+        expect(&mut tls, 220)?;
+        Box::new(tls)
+    } else {
+        expect(&mut tcp, 220)?;
+        let (_, features) = command(&mut tcp, "EHLO blameprompt", 250)?;
+        if !features.to_uppercase().contains("STARTTLS") {
+            return Err(format!(
+                "{}:{} does not offer STARTTLS; use port 465 or a server that supports it",
+                server.host, server.port
+            ));
+        }
+        command(&mut tcp, "STARTTLS", 220)?;
+        Box::new(tls_stream(server.host, tcp)?)
+    };
+
+    command(&mut stream, "EHLO blameprompt", 250)?;
+    if !server.user.is_empty() {
+        let token = base64::engine::general_purpose::STANDARD
+            .encode(format!("\0{}\0{}", server.user, server.password));
+        command(&mut stream, &format!("AUTH PLAIN {}", token), 235)
+            .map_err(|e| format!("SMTP login failed: {}", e))?;
+    }
+    command(&mut stream, &format!("MAIL FROM:<{}>", mail.from), 250)?;
+    for rcpt in mail.to {
+        command(&mut stream, &format!("RCPT TO:<{}>", rcpt), 250)?;
+    }
+    command(&mut stream, "DATA", 354)?;
+    let message = format_message(mail, chrono::Utc::now());
+    command(&mut stream, &format!("{}\r\n.", message), 250)?;
+    let _ = command(&mut stream, "QUIT", 221);
+    Ok(())
+}
+
+fn tls_stream(
+    host: &str,
+    tcp: TcpStream,
+) -> Result<rustls::StreamOwned<rustls::ClientConnection, TcpStream>, String> {
+    let mut roots = rustls::RootCertStore::empty();
+    roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned());
+    let config = rustls::ClientConfig::builder_with_provider(Arc::new(
+        rustls::crypto::ring::default_provider(),
+    ))
+    .with_safe_default_protocol_versions()
+    .map_err(|e| e.to_string())?
+    .with_root_certificates(roots)
+    .with_no_client_auth();
+    let name = rustls::pki_types::ServerName::try_from(host.to_string())
+        .map_err(|_| format!("Invalid SMTP host name: {}", host))?;
+    let conn = rustls::ClientConnection::new(Arc::new(config), name)
+        .map_err(|e| format!("TLS setup failed: {}", e))?;
+    Ok(rustls::StreamOwned::new(conn, tcp))
+}
+
+/// Send `line` and check the reply code; returns the code and reply text.
+fn command(stream: &mut dyn Stream, line: &str, expected: u16) -> Result<(u16, String), String> {
+    stream
+        .write_all(format!("{}\r\n", line).as_bytes())
+        .and_then(|_| stream.flush())
+        .map_err(|e| format!("SMTP write failed: {}", e))?;
+    expect(stream, expected)
+}
+
+fn expect(stream: &mut dyn Stream, expected: u16) -> Result<(u16, String), String> {
+    let (code, text) = read_reply(stream)?;
+    if code != expected {
+        return Err(format!("SMTP server replied {} {}", code, text.trim()));
+    }
+    Ok((code, text))
+}
+
+/// A reply, which may span `250-...` continuation lines.
+fn read_reply(stream: &mut dyn Stream) -> Result<(u16, String), String> {
+    let mut text = String::new();
+    loop {
+        let mut line = Vec::new();
+        let mut byte = [0u8; 1];
+        while !line.ends_with(b"\n") {
+            match stream.read(&mut byte) {
+                Ok(0) => return Err("SMTP connection closed".to_string()),
+                Ok(_) => line.push(byte[0]),
+                Err(e) => return Err(format!("SMTP read failed: {}", e)),
+            }
+        }
+        let line = String::from_utf8_lossy(&line);
+        let code = line
+            .get(..3)
+            .and_then(|c| c.parse::<u16>().ok())
+            .ok_or_else(|| format!("Malformed SMTP reply: {}", line.trim()))?;
+        text.push_str(line.get(4..).unwrap_or_default());
+        if line.as_bytes().get(3) != Some(&b'-') {
+            return Ok((code, text));
+        }
+    }
+}
+
+/// Headers plus the base64 HTML body, CRLF line endings. Base64 keeps lines
+/// short and means no body line can start with a dot.
+pub fn format_message(mail: &Mail, date: chrono::DateTime<chrono::Utc>) -> String {
+    let b64 = base64::engine::general_purpose::STANDARD;
+    let subject = if mail.subject.is_ascii() {
+        mail.subject.to_string()
+    } else {
+        format!("=?UTF-8?B?{}?=", b64.encode(mail.subject))
+    };
+    let body = b64.encode(mail.html);
+    let body_lines: Vec<&str> = body
+        .as_bytes()
+        .chunks(76)
+        .map(|c| std::str::from_utf8(c).unwrap_or_default())
+        .collect();
+    format!(
+        "From: {}\r\nTo: {}\r\nSubject: {}\r\nDate: {}\r\nMIME-Version: 1.0\r\n\
+         Content-Type: text/html; charset=utf-8\r\nContent-Transfer-Encoding: base64\r\n\r\n{}",
+        mail.from,
+        mail.to.join(", "),
+        subject,
+        date.to_rfc2822(),
+        body_lines.join("\r\n")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_format_message() {
+        let to = vec!["a@example.com".to_string(), "b@example.com".to_string()];
+        let html = format!("<p>{}</p>", "x".repeat(100));
+        let mail = Mail {
+            from: "bp@example.com",
+            to: &to,
+            subject: "AI digest — week",
+            html: &html,
+        };
+        let date = chrono::Utc.with_ymd_and_hms(2026, 10, 12, 9, 0, 0).unwrap();
+        let message = format_message(&mail, date);
+        let (headers, body) = message.split_once("\r\n\r\n").unwrap();
+        assert!(headers.contains("To: a@example.com, b@example.com\r\n"));
+        assert!(headers.contains("Subject: =?UTF-8?B?"));
+        assert!(headers.contains("Date: Mon, 12 Oct 2026 09:00:00 +0000"));
+        assert!(body.split("\r\n").all(|l| l.len() <= 76));
+        let decoded = base64::engine::general_purpose::STANDARD
+            .decode(body.replace("\r\n", ""))
+            .unwrap();
+        assert_eq!(decoded, html.as_bytes());
+    }
+}
@@ -0,0 +1,260 @@
+/// One-line intent summaries for prompts (`[summarizer]` in `.blamepromptrc`).
+///
+/// `extractive` picks the first real sentence of the prompt, skipping code
+/// blocks, pasted output and conversational filler ("hey, can you ..."). `ollama`
+/// asks a local model through its `/api/generate` endpoint and falls back to the
+/// extractive summary when the server is down or slow, so capture never waits
+/// longer than `timeout_secs`.
+use crate::core::config::SummarizerConfig;
+use std::time::Duration;
+
+/// Prompt text sent to the model; long pastes add latency, not intent.
+const MAX_MODEL_INPUT_CHARS: usize = 4000;
+
+const INSTRUCTION: &str = "Summarize the intent of the following request to a coding assistant \
+in one short imperative sentence (at most 12 words). Reply with the sentence only.";
+
+/// Leading phrases that carry no intent, matched case-insensitively.
+const FILLERS: &[&str] = &[
+    "hi",
+    "hey",
+    "hello",
+    "ok",
+    "okay",
+    "so",
+    "now",
+    "please",
+    "can you",
+    "could you",
+    "would you",
+    "will you",
+    "i want you to",
+    "i'd like you to",
+    "i need you to",
+    "help me",
+    "let's",
+    "lets",
+];
+
+/// The intent summary for `prompt`, or `None` when summarizing is off or adds
+/// nothing over the prompt itself.
+pub fn intent_summary(prompt: &str, cfg: &SummarizerConfig) -> Option<String> {
+    let summary = match cfg.mode.as_str() {
+        "extractive" => extractive(prompt, cfg.max_chars),
+        "ollama" => match ollama(prompt, cfg) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                tracing::debug!("ollama summarizer unavailable, using extractive: {}", e);
+                extractive(prompt, cfg.max_chars)
+            }
+        },
+        _ => None,
+    }?;
+    (summary != prompt.trim()).then_some(summary)
+}
+
+/// First sentence of prose in `prompt`, without filler, capitalized and
+/// shortened to `max_chars` on a word boundary.
+pub fn extractive(prompt: &str, max_chars: usize) -> Option<String> {
+    let mut prose = String::new();
+    let mut in_fence = false;
+    for line in prompt.lines() {
+        let line = line.trim();
+        if line.starts_with("```") {
+            in_fence = !in_fence;
+            continue;
+        }
+        if in_fence || line.is_empty() || !looks_like_prose(line) {
+            // A paragraph break ends the sentence.
+            if !prose.ends_with('\n') && !prose.is_empty() {
+                prose.push('\n');
+            }
+            continue;
+        }
+        prose.push_str(line);
+        prose.push(' ');
+    }
+
+    let sentence = sentences(&prose)
+        .into_iter()
+        .find(|s| s.split_whitespace().count() >= 2)?;
+    let sentence = strip_fillers(sentence);
+    if sentence.is_empty() {
+        return None;
+    }
+    Some(shorten(&capitalize(sentence), max_chars))
+}
+
+/// Split on line breaks and on sentence punctuation followed by a space, so
+/// file names like `parser.rs` stay whole.
+fn sentences(text: &str) -> Vec<&str> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        let at_break = match c {
+            '\n' => true,
+            '.' | '?' | '!' | ';' => chars.peek().is_none_or(|(_, n)| n.is_whitespace()),
+            _ => false,
+        };
+        if at_break {
+            out.push(text[start..i].trim());
+            start = i + c.len_utf8();
+        }
+    }
+    out.push(text[start..].trim());
+    out
+}
+
+/// Lines of logs, shell sessions, quotes and stack traces are not the ask.
+fn looks_like_prose(line: &str) -> bool {
+    const NOISE: &[&str] = &[
+        "$ ",
+        "> ",
+        "at ",
+        "error[",
+        "Traceback",
+        "File \"",
+        "//",
+        "#!",
+    ];
+    if NOISE.iter().any(|p| line.starts_with(p)) {
+        return false;
+    }
+    let letters = line.chars().filter(|c| c.is_alphabetic()).count();
+    letters * 2 >= line.chars().count()
+}
+
+fn strip_fillers(mut s: &str) -> &str {
+    loop {
+        let trimmed = s.trim_start_matches([',', ' ', ':', '-']);
+        let lower = trimmed.to_lowercase();
+        let filler = FILLERS.iter().find(|f| {
+            lower.starts_with(*f)
+                && trimmed.is_char_boundary(f.len())
+                && lower[f.len()..]
+                    .chars()
+                    .next()
+                    .is_none_or(|c| !c.is_alphanumeric())
+        });
+        match filler {
+            Some(f) => s = &trimmed[f.len()..],
+            None => {
+                return trimmed
+                    .trim_end_matches(" please")
+                    .trim_end_matches(" for me")
+                    .trim();
+            }
+        }
+    }
+}
+
+fn capitalize(s: &str) -> String {
+    let mut chars = s.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+fn shorten(s: &str, max_chars: usize) -> String {
+    if s.chars().count() <= max_chars {
+        return s.to_string();
+    }
+    let cut: String = s.chars().take(max_chars.saturating_sub(1)).collect();
+    let cut = match cut.rfind(' ') {
+        Some(i) if i > max_chars / 2 => &cut[..i],
+        _ => cut.as_str(),
+    };
+    format!("{}…", cut.trim_end_matches([',', ' ']))
+}
+
+fn ollama(prompt: &str, cfg: &SummarizerConfig) -> Result<String, String> {
+    let client = reqwest::blocking::Client::builder()
+        .timeout(Duration::from_secs(cfg.timeout_secs))
+        .connect_timeout(Duration::from_secs(cfg.timeout_secs.min(2)))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let excerpt: String = prompt.chars().take(MAX_MODEL_INPUT_CHARS).collect();
+    let body = serde_json::json!({
+        "model": cfg.model,
+        "prompt": format!("{}\n\n{}", INSTRUCTION, excerpt),
+        "stream": false,
+        "options": { "temperature": 0 },
+    });
+    let res = client
+        .post(format!(
+            "{}/api/generate",
+            cfg.endpoint.trim_end_matches('/')
+        ))
+        .json(&body)
+        .send()
+        .map_err(|e| e.to_string())?;
+    if !res.status().is_success() {
+        return Err(format!("HTTP {}", res.status()));
+    }
+    let value: serde_json::Value = res.json().map_err(|e| e.to_string())?;
+    let text = value["response"].as_str().unwrap_or_default();
+    clean_model_reply(text, cfg.max_chars).ok_or("empty reply".to_string())
+}
+
+/// First line of the model's reply, without quotes or a "Summary:" label.
+fn clean_model_reply(reply: &str, max_chars: usize) -> Option<String> {
+    let line = reply.lines().map(str::trim).find(|l| !l.is_empty())?;
+    let line = line.trim_matches(['"', '\'', '`']);
+    let line = line
+        .strip_prefix("Summary:")
+        .unwrap_or(line)
+        .trim()
+        .trim_matches(['"', '\'', '`'])
+        .trim_end_matches('.');
+    (!line.is_empty()).then(|| shorten(&capitalize(line), max_chars))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extractive_skips_filler_and_code() {
+        let prompt = "hey, can you add retry logic to the HTTP fetcher? It fails on 503s.\n\
+                      ```rust\nfn fetch() {}\n```";
+        assert_eq!(
+            extractive(prompt, 80).as_deref(),
+            Some("Add retry logic to the HTTP fetcher")
+        );
+
+        let pasted = "$ cargo test\nerror[E0308]: mismatched types\n\nPlease fix the type error in parser.rs";
+        assert_eq!(
+            extractive(pasted, 80).as_deref(),
+            Some("Fix the type error in parser.rs")
+        );
+
+        let long = "Refactor the configuration loader so that every section is validated on load";
+        assert_eq!(
+            extractive(long, 40).as_deref(),
+            Some("Refactor the configuration loader so…")
+        );
+        assert_eq!(extractive("```\ncode\n```", 80), None);
+    }
+
+    #[test]
+    fn test_intent_summary_modes() {
+        let mut cfg = SummarizerConfig::default();
+        assert_eq!(
+            intent_summary("please add tests for the parser", &cfg),
+            None
+        );
+        cfg.mode = "extractive".to_string();
+        assert_eq!(
+            intent_summary("please add tests for the parser", &cfg).as_deref(),
+            Some("Add tests for the parser")
+        );
+        // Nothing gained over the prompt itself.
+        assert_eq!(intent_summary("Add tests for the parser", &cfg), None);
+        assert_eq!(
+            clean_model_reply("\n\"Summary: Add retry logic.\"\n", 80).as_deref(),
+            Some("Add retry logic")
+        );
+    }
+}
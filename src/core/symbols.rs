@@ -0,0 +1,341 @@
+/// Symbol-level attribution for `blame --by-symbol`.
+///
+/// In a 5,000-line file, line ranges say little. This module parses the file
+/// with tree-sitter, lists its functions, methods, types and impl blocks with
+/// their line spans, and rolls the per-line attribution up to each symbol, so a
+/// symbol reads as AI-generated, partially AI or human. Supported: Rust,
+/// Python, JavaScript, TypeScript (and TSX) and Go. Methods are named after
+/// their container (`Parser::parse`, `Client.fetch`). Nested functions count
+/// toward the enclosing symbol.
+use std::path::Path;
+use tree_sitter::{Language, Node, Parser};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lang {
+    Rust,
+    Python,
+    JavaScript,
+    TypeScript,
+    Tsx,
+    Go,
+}
+
+impl Lang {
+    fn from_path(path: &str) -> Option<Lang> {
+        let ext = Path::new(path).extension()?.to_str()?.to_lowercase();
+        Some(match ext.as_str() {
+            "rs" => Lang::Rust,
+            "py" | "pyi" => Lang::Python,
+            "js" | "jsx" | "mjs" | "cjs" => Lang::JavaScript,
+            "ts" | "mts" | "cts" => Lang::TypeScript,
+            "tsx" => Lang::Tsx,
+            "go" => Lang::Go,
+            _ => return None,
+        })
+    }
+
+    fn grammar(self) -> Language {
+        match self {
+            Lang::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Lang::Python => tree_sitter_python::LANGUAGE.into(),
+            Lang::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Lang::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Lang::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+            Lang::Go => tree_sitter_go::LANGUAGE.into(),
+        }
+    }
+
+    fn separator(self) -> &'static str {
+        if self == Lang::Rust {
+            "::"
+        } else {
+            "."
+        }
+    }
+}
+
+/// A named definition and the lines it spans (1-based, inclusive).
+#[derive(Debug, Clone, PartialEq)]
+pub struct Symbol {
+    /// "function", "method", "class", "struct", "enum", "trait", "impl",
+    /// "interface", "type", "module" or "macro".
+    pub kind: String,
+    /// Qualified with the container for methods, e.g. `Parser::parse`.
+    pub name: String,
+    pub start_line: u32,
+    pub end_line: u32,
+}
+
+/// Whether symbols can be extracted from `path`.
+pub fn is_supported(path: &str) -> bool {
+    Lang::from_path(path).is_some()
+}
+
+/// The symbols of `source`, in file order. `None` when the language isn't
+/// supported or the parser fails.
+pub fn parse_symbols(path: &str, source: &str) -> Option<Vec<Symbol>> {
+    let lang = Lang::from_path(path)?;
+    let mut parser = Parser::new();
+    parser.set_language(&lang.grammar()).ok()?;
+    let tree = parser.parse(source, None)?;
+    let mut symbols = Vec::new();
+    collect(tree.root_node(), source, lang, None, &mut symbols);
+    Some(symbols)
+}
+
+fn collect(node: Node, src: &str, lang: Lang, parent: Option<&str>, out: &mut Vec<Symbol>) {
+    let mut cursor = node.walk();
+    for child in node.named_children(&mut cursor) {
+        match definition(child, src, lang, parent.is_some()) {
+            Some((kind, name, container)) => {
+                let name = match parent {
+                    Some(p) => format!("{}{}{}", p, lang.separator(), name),
+                    None => name,
+                };
+                out.push(Symbol {
+                    kind: kind.to_string(),
+                    name: name.clone(),
+                    start_line: child.start_position().row as u32 + 1,
+                    end_line: child.end_position().row as u32 + 1,
+                });
+                if container {
+                    collect(child, src, lang, Some(&name), out);
+                }
+            }
+            None => collect(child, src, lang, parent, out),
+        }
+    }
+}
+
+/// `(kind, name, is_container)` when `node` defines a symbol.
+fn definition(
+    node: Node,
+    src: &str,
+    lang: Lang,
+    in_container: bool,
+) -> Option<(&'static str, String, bool)> {
+    let text = |n: Node| n.utf8_text(src.as_bytes()).ok().map(str::to_string);
+    let field = |f: &str| node.child_by_field_name(f).and_then(text);
+    let function = if in_container { "method" } else { "function" };
+    match (lang, node.kind()) {
+        (Lang::Rust, "function_item") => Some((function, field("name")?, false)),
+        (Lang::Rust, "struct_item") => Some(("struct", field("name")?, false)),
+        (Lang::Rust, "enum_item") => Some(("enum", field("name")?, false)),
+        (Lang::Rust, "union_item") => Some(("struct", field("name")?, false)),
+        (Lang::Rust, "trait_item") => Some(("trait", field("name")?, true)),
+        (Lang::Rust, "mod_item") => Some(("module", field("name")?, true)),
+        (Lang::Rust, "macro_definition") => Some(("macro", field("name")?, false)),
+        (Lang::Rust, "impl_item") => {
+            let ty = field("type")?;
+            let name = match field("trait") {
+                Some(t) => format!("<{} as {}>", ty, t),
+                None => ty,
+            };
+            Some(("impl", name, true))
+        }
+        (Lang::Python, "function_definition") => Some((function, field("name")?, false)),
+        (Lang::Python, "class_definition") => Some(("class", field("name")?, true)),
+        (
+            Lang::JavaScript | Lang::TypeScript | Lang::Tsx,
+            "function_declaration" | "generator_function_declaration",
+        ) => Some(("function", field("name")?, false)),
+        (Lang::JavaScript | Lang::TypeScript | Lang::Tsx, "method_definition") => {
+            Some(("method", field("name")?, false))
+        }
+        (
+            Lang::JavaScript | Lang::TypeScript | Lang::Tsx,
+            "class_declaration" | "abstract_class_declaration",
+        ) => Some(("class", field("name")?, true)),
+        (Lang::JavaScript | Lang::TypeScript | Lang::Tsx, "variable_declarator") => {
+            let value = node.child_by_field_name("value")?.kind();
+            matches!(
+                value,
+                "arrow_function" | "function_expression" | "function" | "generator_function"
+            )
+            .then_some(())?;
+            Some(("function", field("name")?, false))
+        }
+        (Lang::TypeScript | Lang::Tsx, "interface_declaration") => {
+            Some(("interface", field("name")?, false))
+        }
+        (Lang::TypeScript | Lang::Tsx, "type_alias_declaration") => {
+            Some(("type", field("name")?, false))
+        }
+        (Lang::TypeScript | Lang::Tsx, "enum_declaration") => Some(("enum", field("name")?, false)),
+        (Lang::Go, "function_declaration") => Some(("function", field("name")?, false)),
+        (Lang::Go, "method_declaration") => {
+            // `func (s *Server) Start()` -> `Server.Start`
+            let receiver = field("receiver").unwrap_or_default();
+            let ty = receiver
+                .trim_matches(|c| c == '(' || c == ')')
+                .split_whitespace()
+                .last()
+                .unwrap_or("")
+                .trim_start_matches('*')
+                .split('[')
+                .next()
+                .unwrap_or("")
+                .to_string();
+            let name = field("name")?;
+            Some((
+                "method",
+                if ty.is_empty() {
+                    name
+                } else {
+                    format!("{}.{}", ty, name)
+                },
+                false,
+            ))
+        }
+        (Lang::Go, "type_spec") => Some(("type", field("name")?, false)),
+        _ => None,
+    }
+}
+
+/// Line-level attribution rolled up to one symbol.
+#[derive(Debug, Clone)]
+pub struct SymbolAttribution {
+    pub symbol: Symbol,
+    pub total_lines: u32,
+    pub ai_lines: u32,
+    /// AI-written lines a human then changed.
+    pub edited_lines: u32,
+    pub models: Vec<String>,
+}
+
+impl SymbolAttribution {
+    /// "ai" when every line is AI-generated, "partial" when some lines are AI
+    /// (or AI then edited), otherwise "human".
+    pub fn status(&self) -> &'static str {
+        if self.ai_lines > 0 && self.ai_lines == self.total_lines {
+            "ai"
+        } else if self.ai_lines + self.edited_lines > 0 {
+            "partial"
+        } else {
+            "human"
+        }
+    }
+
+    pub fn ai_pct(&self) -> f64 {
+        if self.total_lines == 0 {
+            0.0
+        } else {
+            (self.ai_lines as f64 / self.total_lines as f64) * 100.0
+        }
+    }
+}
+
+/// Roll up per-line attribution into per-symbol totals. `lines` yields
+/// `(line number, source, model)` where source is "ai", "edited" or "human".
+/// A line inside a method also counts toward its class or impl.
+pub fn attribute_symbols<'a>(
+    symbols: &[Symbol],
+    lines: impl IntoIterator<Item = (u32, &'a str, Option<&'a str>)>,
+) -> Vec<SymbolAttribution> {
+    let mut out: Vec<SymbolAttribution> = symbols
+        .iter()
+        .map(|s| SymbolAttribution {
+            symbol: s.clone(),
+            total_lines: s.end_line - s.start_line + 1,
+            ai_lines: 0,
+            edited_lines: 0,
+            models: Vec::new(),
+        })
+        .collect();
+    for (line, source, model) in lines {
+        for sa in out
+            .iter_mut()
+            .filter(|sa| (sa.symbol.start_line..=sa.symbol.end_line).contains(&line))
+        {
+            match source {
+                "ai" => {
+                    sa.ai_lines += 1;
+                    if let Some(m) = model.filter(|m| !m.is_empty()) {
+                        if !sa.models.iter().any(|x| x == m) {
+                            sa.models.push(m.to_string());
+                        }
+                    }
+                }
+                "edited" => sa.edited_lines += 1,
+                _ => {}
+            }
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spans(path: &str, src: &str) -> Vec<(String, String, u32, u32)> {
+        parse_symbols(path, src)
+            .unwrap()
+            .into_iter()
+            .map(|s| (s.kind, s.name, s.start_line, s.end_line))
+            .collect()
+    }
+
+    #[test]
+    fn test_symbols_per_language_and_rollup() {
+        let rust = "struct Parser;\n\nimpl Parser {\n    fn parse(&self) {\n        let x = 1;\n    }\n}\n\nfn main() {}\n";
+        let s = |k: &str, n: &str, a, b| (k.to_string(), n.to_string(), a, b);
+        assert_eq!(
+            spans("src/big.rs", rust),
+            vec![
+                s("struct", "Parser", 1, 1),
+                s("impl", "Parser", 3, 7),
+                s("method", "Parser::parse", 4, 6),
+                s("function", "main", 9, 9),
+            ]
+        );
+        assert_eq!(
+            spans(
+                "a.py",
+                "class Client:\n    def fetch(self):\n        pass\n\ndef helper():\n    pass\n"
+            ),
+            vec![
+                s("class", "Client", 1, 3),
+                s("method", "Client.fetch", 2, 3),
+                s("function", "helper", 5, 6),
+            ]
+        );
+        assert_eq!(
+            spans(
+                "a.ts",
+                "export const add = (a: number) => a + 1;\ninterface Opts { x: number }\nclass A {\n  run() {}\n}\n"
+            ),
+            vec![
+                s("function", "add", 1, 1),
+                s("interface", "Opts", 2, 2),
+                s("class", "A", 3, 5),
+                s("method", "A.run", 4, 4),
+            ]
+        );
+        assert_eq!(
+            spans(
+                "main.go",
+                "package main\n\ntype Server struct{}\n\nfunc (s *Server) Start() {\n}\n"
+            ),
+            vec![s("type", "Server", 3, 3), s("method", "Server.Start", 5, 6)]
+        );
+        assert!(parse_symbols("notes.txt", "hello").is_none());
+
+        let symbols = parse_symbols("src/big.rs", rust).unwrap();
+        let sources = [
+            "human", "", "human", "ai", "ai", "ai", "edited", "", "human",
+        ];
+        let rolled = attribute_symbols(
+            &symbols,
+            sources
+                .iter()
+                .enumerate()
+                .map(|(i, s)| ((i + 1) as u32, *s, Some("sonnet"))),
+        );
+        let status: Vec<_> = rolled.iter().map(|r| r.status()).collect();
+        assert_eq!(status, vec!["human", "partial", "ai", "human"]);
+        assert_eq!(rolled[2].models, vec!["sonnet"]);
+        assert_eq!(rolled[1].ai_lines, 3);
+        assert_eq!(rolled[1].edited_lines, 1);
+    }
+}
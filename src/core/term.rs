@@ -0,0 +1,179 @@
+/// Shared terminal output layer.
+///
+/// The global `--quiet`, `--plain` and `--no-emoji` flags, `NO_COLOR`, and
+/// whether stdout is a terminal decide how commands render. `--plain` is meant
+/// for piping: no color, no symbols, borderless tables. `--quiet` drops banners
+/// and hints but keeps results and errors.
+use comfy_table::{presets, Cell, Color, Table};
+use std::io::IsTerminal;
+use std::sync::OnceLock;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Style {
+    pub color: bool,
+    pub emoji: bool,
+    pub quiet: bool,
+    pub plain: bool,
+}
+
+impl Style {
+    fn resolve(quiet: bool, plain: bool, no_emoji: bool, no_color_env: bool, tty: bool) -> Self {
+        Style {
+            color: !plain && !no_color_env && tty,
+            emoji: !plain && !no_emoji,
+            quiet,
+            plain,
+        }
+    }
+}
+
+static STYLE: OnceLock<Style> = OnceLock::new();
+
+/// Record the global flags. Call once from `main` before any output.
+pub fn init(quiet: bool, plain: bool, no_emoji: bool) {
+    let _ = STYLE.set(Style::resolve(
+        quiet,
+        plain,
+        no_emoji,
+        no_color_env(),
+        std::io::stdout().is_terminal(),
+    ));
+}
+
+pub fn style() -> Style {
+    *STYLE.get_or_init(|| {
+        Style::resolve(
+            false,
+            false,
+            false,
+            no_color_env(),
+            std::io::stdout().is_terminal(),
+        )
+    })
+}
+
+/// https://no-color.org: any non-empty value disables color.
+fn no_color_env() -> bool {
+    std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty())
+}
+
+pub fn quiet() -> bool {
+    style().quiet
+}
+
+fn paint(code: &str, text: &str) -> String {
+    if style().color {
+        format!("\x1b[{}m{}\x1b[0m", code, text)
+    } else {
+        text.to_string()
+    }
+}
+
+pub fn bold(text: &str) -> String {
+    paint("1", text)
+}
+
+pub fn dim(text: &str) -> String {
+    paint("2", text)
+}
+
+pub fn cyan(text: &str) -> String {
+    paint("36", text)
+}
+
+pub fn red(text: &str) -> String {
+    paint("31", text)
+}
+
+pub fn green(text: &str) -> String {
+    paint("32", text)
+}
+
+pub fn yellow(text: &str) -> String {
+    paint("33", text)
+}
+
+pub fn blue(text: &str) -> String {
+    paint("34", text)
+}
+
+pub fn success(text: &str) -> String {
+    paint("1;32", text)
+}
+
+/// `emoji` normally, `ascii` under `--no-emoji` / `--plain`.
+pub fn symbol<'a>(emoji: &'a str, ascii: &'a str) -> &'a str {
+    if style().emoji {
+        emoji
+    } else {
+        ascii
+    }
+}
+
+/// Green check mark (or `[ok]`).
+pub fn check() -> String {
+    success(symbol("✓", "[ok]"))
+}
+
+pub fn arrow() -> &'static str {
+    symbol("→", "->")
+}
+
+/// Horizontal separator of `width` characters.
+pub fn rule(width: usize) -> String {
+    dim(&symbol("─", "-").repeat(width))
+}
+
+/// Print a banner or hint line; suppressed by `--quiet`.
+pub fn status(line: &str) {
+    if !quiet() {
+        println!("{}", line);
+    }
+}
+
+/// A table in the current style: borderless in `--plain`, uncolored when
+/// color is off.
+pub fn table() -> Table {
+    let mut table = Table::new();
+    let style = style();
+    if style.plain {
+        table.load_preset(presets::NOTHING);
+    }
+    if !style.color {
+        table.force_no_tty();
+    }
+    table
+}
+
+/// A table cell with a foreground color, left plain when color is off.
+pub fn colored(text: impl ToString, color: Color) -> Cell {
+    let cell = Cell::new(text.to_string());
+    if style().color {
+        cell.fg(color)
+    } else {
+        cell
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_style_resolution() {
+        let s = Style::resolve(false, false, false, false, true);
+        assert!(s.color && s.emoji && !s.quiet && !s.plain);
+
+        // NO_COLOR and non-terminals drop color but keep symbols.
+        assert!(!Style::resolve(false, false, false, true, true).color);
+        let piped = Style::resolve(false, false, false, false, false);
+        assert!(!piped.color && piped.emoji);
+
+        // --plain implies no color and no emoji.
+        let plain = Style::resolve(false, true, false, false, true);
+        assert!(!plain.color && !plain.emoji && plain.plain);
+
+        let no_emoji = Style::resolve(true, false, true, false, true);
+        assert!(no_emoji.color && !no_emoji.emoji && no_emoji.quiet);
+    }
+}
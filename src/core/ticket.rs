@@ -0,0 +1,111 @@
+/// Ticket/issue ID extraction (JIRA `PROJ-123`, Linear `ENG-42`, ...).
+///
+/// Receipts carry the first ticket found so analytics can allocate AI spend
+/// to projects. The branch name wins over the prompt text because it is the
+/// more deliberate signal.
+use crate::core::config::{self, TicketConfig};
+use crate::core::receipt::Receipt;
+use crate::core::util;
+use regex::Regex;
+
+/// Return the first ticket ID in `text` matching the configured pattern.
+pub fn extract_ticket(text: &str, cfg: &TicketConfig) -> Option<String> {
+    let re = Regex::new(&cfg.pattern).ok()?;
+    let found = re.find_iter(text).map(|m| m.as_str()).find(|t| {
+        !cfg.ignore_prefixes.iter().any(|p| {
+            t.strip_prefix(p.as_str())
+                .is_some_and(|rest| rest.starts_with('-'))
+        })
+    });
+    found.map(|t| t.to_string())
+}
+
+/// Branch names are usually lowercase (`feature/proj-123-login`), so they are
+/// uppercased before matching to fit the default JIRA-style pattern.
+pub fn ticket_from_branch(branch: &str, cfg: &TicketConfig) -> Option<String> {
+    extract_ticket(branch, cfg).or_else(|| extract_ticket(&branch.to_uppercase(), cfg))
+}
+
+/// Detect the ticket for a prompt: current branch first, then the prompt text.
+pub fn detect_ticket(prompt: &str, cfg: &TicketConfig) -> Option<String> {
+    if !cfg.enabled {
+        return None;
+    }
+    util::current_branch()
+        .and_then(|b| ticket_from_branch(&b, cfg))
+        .or_else(|| extract_ticket(prompt, cfg))
+}
+
+/// Fill in `ticket` for receipts that were captured without one (imported
+/// sessions, integrations without a prompt hook). Called at attach time.
+pub fn fill_missing(receipts: &mut [Receipt]) {
+    let cfg = config::load_config().tickets;
+    if !cfg.enabled {
+        return;
+    }
+    let branch_ticket = util::current_branch().and_then(|b| ticket_from_branch(&b, &cfg));
+    for r in receipts.iter_mut().filter(|r| r.ticket.is_none()) {
+        r.ticket = branch_ticket
+            .clone()
+            .or_else(|| extract_ticket(&r.prompt_summary, &cfg));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_jira_style() {
+        let cfg = TicketConfig::default();
+        assert_eq!(
+            extract_ticket("Fix login bug from PROJ-123 please", &cfg),
+            Some("PROJ-123".to_string())
+        );
+        assert_eq!(extract_ticket("no ticket here", &cfg), None);
+    }
+
+    #[test]
+    fn test_ignores_common_false_positives() {
+        let cfg = TicketConfig::default();
+        assert_eq!(
+            extract_ticket("decode as UTF-8 and hash with SHA-256", &cfg),
+            None
+        );
+        assert_eq!(
+            extract_ticket("use UTF-8 for ENG-42", &cfg),
+            Some("ENG-42".to_string())
+        );
+    }
+
+    #[test]
+    fn test_branch_is_case_insensitive() {
+        let cfg = TicketConfig::default();
+        assert_eq!(
+            ticket_from_branch("feature/proj-123-login", &cfg),
+            Some("PROJ-123".to_string())
+        );
+        assert_eq!(ticket_from_branch("main", &cfg), None);
+    }
+
+    #[test]
+    fn test_custom_pattern() {
+        let cfg = TicketConfig {
+            pattern: r"#[0-9]+".to_string(),
+            ..TicketConfig::default()
+        };
+        assert_eq!(
+            extract_ticket("closes #481", &cfg),
+            Some("#481".to_string())
+        );
+    }
+
+    #[test]
+    fn test_invalid_pattern_yields_none() {
+        let cfg = TicketConfig {
+            pattern: "([".to_string(),
+            ..TicketConfig::default()
+        };
+        assert_eq!(extract_ticket("PROJ-1", &cfg), None);
+    }
+}
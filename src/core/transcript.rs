@@ -1,5 +1,6 @@
+use crate::core::{environment, pricing};
 use chrono::{DateTime, Utc};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 use std::path::Path;
 
 #[derive(Debug, Clone)]
@@ -21,6 +22,11 @@ pub enum Message {
         name: String,
         input: serde_json::Value,
     },
+    /// Extended-thinking block from an assistant message. Only used for optional
+    /// plan capture; never rendered as a conversation turn.
+    Thinking {
+        text: String,
+    },
 }
 
 #[derive(Debug)]
@@ -50,6 +56,10 @@ pub struct TranscriptParseResult {
     /// Timestamps of each user prompt message (1-indexed: index 0 = prompt 1).
     /// Used for setting accurate per-receipt timestamps instead of Utc::now().
     pub user_prompt_timestamps: Vec<DateTime<Utc>>,
+    /// Agent version stamped on the entries (Claude Code's `version`), last seen wins.
+    pub agent_version: Option<String>,
+    /// Generation settings seen in the transcript; see `environment::collect_settings`.
+    pub settings: BTreeMap<String, String>,
 }
 
 pub fn parse_claude_jsonl(transcript_path: &str) -> Result<TranscriptParseResult, String> {
@@ -76,6 +86,8 @@ pub fn parse_claude_jsonl(transcript_path: &str) -> Result<TranscriptParseResult
     let mut response_times: Vec<f64> = Vec::new();
     let mut last_user_timestamp: Option<DateTime<Utc>> = None;
     let mut user_prompt_timestamps: Vec<DateTime<Utc>> = Vec::new();
+    let mut agent_version: Option<String> = None;
+    let mut settings = BTreeMap::new();
 
     for line in content.lines() {
         let line = line.trim();
@@ -88,6 +100,11 @@ pub fn parse_claude_jsonl(transcript_path: &str) -> Result<TranscriptParseResult
             Err(_) => continue, // Skip malformed lines
         };
 
+        if let Some(v) = entry.get("version").and_then(|v| v.as_str()) {
+            agent_version = Some(v.to_string());
+        }
+        environment::collect_settings(&entry, &mut settings);
+
         // Track timing
         if let Some(ts_str) = entry.get("timestamp").and_then(|v| v.as_str()) {
             if let Ok(ts) = ts_str.parse::<DateTime<Utc>>() {
@@ -274,9 +291,14 @@ pub fn parse_claude_jsonl(transcript_path: &str) -> Result<TranscriptParseResult
                                     }
                                 }
 
-                                // Track modified files — handle both single file_path (Write/Edit)
-                                // and MultiEdit's edits array (edits[].file_path).
-                                if let Some(fp) = input.get("file_path").and_then(|v| v.as_str()) {
+                                // Track modified files — handle single file_path (Write/Edit),
+                                // NotebookEdit's notebook_path and MultiEdit's edits array
+                                // (edits[].file_path).
+                                if let Some(fp) = input
+                                    .get("file_path")
+                                    .or_else(|| input.get("notebook_path"))
+                                    .and_then(|v| v.as_str())
+                                {
                                     if !files_modified.contains(&fp.to_string()) {
                                         files_modified.push(fp.to_string());
                                     }
@@ -301,6 +323,15 @@ pub fn parse_claude_jsonl(transcript_path: &str) -> Result<TranscriptParseResult
                                     .to_string();
                                 messages.push(Message::ToolUse { id, name, input });
                             }
+                            Some("thinking") => {
+                                if let Some(text) = item.get("thinking").and_then(|v| v.as_str()) {
+                                    if !text.trim().is_empty() {
+                                        messages.push(Message::Thinking {
+                                            text: text.to_string(),
+                                        });
+                                    }
+                                }
+                            }
                             _ => {}
                         }
                     }
@@ -340,6 +371,8 @@ pub fn parse_claude_jsonl(transcript_path: &str) -> Result<TranscriptParseResult
         session_duration_secs,
         avg_response_time_secs,
         user_prompt_timestamps,
+        agent_version,
+        settings,
     })
 }
 
@@ -474,6 +507,10 @@ fn tool_summary(name: &str, input: &serde_json::Value) -> String {
             .get("file_path")
             .and_then(|v| v.as_str())
             .map(|s| format!("file: \"{}\"", basename(s))),
+        "NotebookEdit" => input
+            .get("notebook_path")
+            .and_then(|v| v.as_str())
+            .map(|s| format!("file: \"{}\"", basename(s))),
         "MultiEdit" => {
             // MultiEdit has an edits array; show all affected filenames.
             if let Some(edits) = input.get("edits").and_then(|e| e.as_array()) {
@@ -620,6 +657,9 @@ pub fn extract_conversation_turns(
                 });
                 turn_idx += 1;
             }
+            Message::Thinking { .. } => {
+                i += 1;
+            }
         }
     }
 
@@ -759,6 +799,34 @@ pub fn token_usage_for_prompt(transcript: &Transcript, prompt_number: u32) -> Op
     }
 }
 
+/// Token usage summed over every assistant message, for transcripts that
+/// belong to a single task such as a subagent's. Falls back to the text
+/// estimate when no message carries usage; the flag is true in that case.
+pub fn total_token_usage(transcript: &Transcript) -> Option<(TokenUsage, bool)> {
+    let mut total = TokenUsage::default();
+    let mut found = false;
+    for msg in &transcript.messages {
+        if let Message::Assistant { usage: Some(u), .. } = msg {
+            total.input_tokens += u.input_tokens;
+            total.output_tokens += u.output_tokens;
+            total.cache_read_tokens += u.cache_read_tokens;
+            total.cache_creation_tokens += u.cache_creation_tokens;
+            found = true;
+        }
+    }
+    if found {
+        return Some((total, false));
+    }
+    for n in 1..=count_user_prompts(transcript).max(1) {
+        if let Some(u) = estimated_usage_for_prompt(transcript, n) {
+            total.input_tokens += u.input_tokens;
+            total.output_tokens += u.output_tokens;
+            found = true;
+        }
+    }
+    found.then_some((total, true))
+}
+
 pub fn extract_agents_for_prompt(transcript: &Transcript, prompt_number: u32) -> Vec<String> {
     let slice = prompt_message_slice(&transcript.messages, prompt_number);
     if slice.is_empty() {
@@ -779,7 +847,11 @@ pub fn files_for_prompt(transcript: &Transcript, prompt_number: u32) -> Vec<Stri
         if let Message::ToolUse { input, name, .. } = msg {
             match name.as_str() {
                 "Write" | "Edit" | "NotebookEdit" => {
-                    if let Some(fp) = input.get("file_path").and_then(|v| v.as_str()) {
+                    if let Some(fp) = input
+                        .get("file_path")
+                        .or_else(|| input.get("notebook_path"))
+                        .and_then(|v| v.as_str())
+                    {
                         if !files.contains(&fp.to_string()) {
                             files.push(fp.to_string());
                         }
@@ -821,6 +893,8 @@ fn count_concurrent_tools_in(messages: &[Message]) -> u32 {
                 current_streak += 1;
                 max_concurrent = max_concurrent.max(current_streak);
             }
+            // Thinking precedes tool calls in the same API response; it doesn't break a streak.
+            Message::Thinking { .. } => {}
             _ => {
                 current_streak = 0;
             }
@@ -932,6 +1006,16 @@ pub fn extract_user_decisions(transcript: &Transcript) -> Vec<crate::core::recei
 /// Spans from the Nth non-empty user message up to (but not including) the (N+1)th
 /// non-empty user message.  If N is out of range, returns an empty slice.
 fn prompt_message_slice(messages: &[Message], prompt_number: u32) -> &[Message] {
+    prompt_message_range(messages, prompt_number)
+        .map(|r| &messages[r])
+        .unwrap_or(&[])
+}
+
+/// Index range of the Nth prompt (1-indexed) within `messages`.
+fn prompt_message_range(
+    messages: &[Message],
+    prompt_number: u32,
+) -> Option<std::ops::Range<usize>> {
     let mut count = 0u32;
     let mut start: Option<usize> = None;
 
@@ -943,7 +1027,7 @@ fn prompt_message_slice(messages: &[Message], prompt_number: u32) -> &[Message]
                     start = Some(i);
                 } else if count == prompt_number + 1 {
                     if let Some(s) = start {
-                        return &messages[s..i];
+                        return Some(s..i);
                     }
                 }
             }
@@ -951,11 +1035,38 @@ fn prompt_message_slice(messages: &[Message], prompt_number: u32) -> &[Message]
     }
 
     // Prompt N is the last one — return from its start to end of transcript
-    if let Some(s) = start {
-        return &messages[s..];
-    }
+    start.map(|s| s..messages.len())
+}
 
-    &[]
+/// Text-based token estimate for the Nth prompt, for transcripts without
+/// `usage` blocks. Input is the conversation the model had read when it
+/// answered (everything before the prompt plus the prompt's user text);
+/// output is the assistant text, thinking and tool-call arguments in the
+/// prompt. Cache reads can't be told apart, so everything counts as input.
+pub fn estimated_usage_for_prompt(
+    transcript: &Transcript,
+    prompt_number: u32,
+) -> Option<TokenUsage> {
+    let range = prompt_message_range(&transcript.messages, prompt_number)?;
+    let mut usage = TokenUsage::default();
+    for (i, msg) in transcript.messages[..range.end].iter().enumerate() {
+        let in_prompt = i >= range.start;
+        match msg {
+            Message::User { text } => usage.input_tokens += pricing::estimate_tokens(text),
+            Message::Assistant { text, .. } | Message::Thinking { text } if in_prompt => {
+                usage.output_tokens += pricing::estimate_tokens(text)
+            }
+            Message::ToolUse { input, .. } if in_prompt => {
+                usage.output_tokens += pricing::estimate_tokens(&input.to_string())
+            }
+            Message::Assistant { text, .. } => usage.input_tokens += pricing::estimate_tokens(text),
+            Message::ToolUse { input, .. } => {
+                usage.input_tokens += pricing::estimate_tokens(&input.to_string())
+            }
+            Message::Thinking { .. } => {}
+        }
+    }
+    (usage.input_tokens + usage.output_tokens > 0).then_some(usage)
 }
 
 /// Return the timestamp of the Nth user prompt (1-indexed) from the JSONL.
@@ -1010,6 +1121,45 @@ pub fn extract_conversation_for_prompt(
     extract_conversation_turns(&sub, max_turn_length, redact_fn)
 }
 
+/// Plan-mode output for the Nth prompt (1-indexed), optionally followed by the
+/// model's extended thinking. Plans come from `ExitPlanMode` tool calls; when the
+/// plan was revised, the last (approved) version wins. Returns raw, unredacted text.
+pub fn plan_for_prompt(
+    transcript: &Transcript,
+    prompt_number: u32,
+    include_thinking: bool,
+) -> Option<String> {
+    let slice = prompt_message_slice(&transcript.messages, prompt_number);
+
+    let plan = slice.iter().rev().find_map(|msg| match msg {
+        Message::ToolUse { name, input, .. } if name == "ExitPlanMode" => input
+            .get("plan")
+            .and_then(|v| v.as_str())
+            .filter(|p| !p.trim().is_empty())
+            .map(|p| p.trim().to_string()),
+        _ => None,
+    });
+
+    let thinking: Vec<&str> = if include_thinking {
+        slice
+            .iter()
+            .filter_map(|msg| match msg {
+                Message::Thinking { text } => Some(text.trim()),
+                _ => None,
+            })
+            .collect()
+    } else {
+        Vec::new()
+    };
+
+    match (plan, thinking.is_empty()) {
+        (None, true) => None,
+        (Some(p), true) => Some(p),
+        (None, false) => Some(format!("Thinking:\n{}", thinking.join("\n\n"))),
+        (Some(p), false) => Some(format!("{}\n\nThinking:\n{}", p, thinking.join("\n\n"))),
+    }
+}
+
 pub fn full_conversation_text(transcript: &Transcript) -> String {
     let mut text = String::new();
     for msg in &transcript.messages {
@@ -1029,6 +1179,7 @@ pub fn full_conversation_text(transcript: &Transcript) -> String {
                 text.push_str(name);
                 text.push('\n');
             }
+            Message::Thinking { .. } => {}
         }
     }
     text
@@ -1200,6 +1351,143 @@ mod tests {
         assert!(token_usage_for_prompt(&transcript, 3).is_none());
     }
 
+    #[test]
+    fn test_total_token_usage() {
+        let transcript = Transcript {
+            messages: vec![
+                Message::User {
+                    text: "Find the config loader".to_string(),
+                },
+                Message::Assistant {
+                    text: "found".to_string(),
+                    model: None,
+                    usage: Some(TokenUsage {
+                        input_tokens: 300,
+                        output_tokens: 40,
+                        cache_read_tokens: 1000,
+                        cache_creation_tokens: 0,
+                    }),
+                },
+                Message::Assistant {
+                    text: "done".to_string(),
+                    model: None,
+                    usage: Some(TokenUsage {
+                        input_tokens: 100,
+                        output_tokens: 10,
+                        cache_read_tokens: 0,
+                        cache_creation_tokens: 0,
+                    }),
+                },
+            ],
+        };
+        let (usage, estimated) = total_token_usage(&transcript).unwrap();
+        assert_eq!((usage.input_tokens, usage.output_tokens), (400, 50));
+        assert_eq!(usage.cache_read_tokens, 1000);
+        assert!(!estimated);
+
+        let bare = Transcript {
+            messages: vec![
+                Message::User {
+                    text: "Find the config loader".to_string(),
+                },
+                Message::Assistant {
+                    text: "It lives in src/core/config.rs".to_string(),
+                    model: None,
+                    usage: None,
+                },
+            ],
+        };
+        let (usage, estimated) = total_token_usage(&bare).unwrap();
+        assert!(estimated && usage.output_tokens > 0);
+        assert!(total_token_usage(&Transcript { messages: vec![] }).is_none());
+    }
+
+    #[test]
+    fn test_estimated_usage_for_prompt() {
+        let assistant = |text: &str| Message::Assistant {
+            text: text.to_string(),
+            model: None,
+            usage: None,
+        };
+        let transcript = Transcript {
+            messages: vec![
+                Message::User {
+                    text: "first prompt".to_string(),
+                },
+                assistant("response one"),
+                Message::User {
+                    text: "second prompt".to_string(),
+                },
+                Message::ToolUse {
+                    id: "t1".to_string(),
+                    name: "Edit".to_string(),
+                    input: serde_json::json!({"file_path": "a.rs"}),
+                },
+                assistant("done"),
+            ],
+        };
+        assert!(token_usage_for_prompt(&transcript, 2).is_none());
+
+        let u1 = estimated_usage_for_prompt(&transcript, 1).unwrap();
+        assert_eq!((u1.input_tokens, u1.output_tokens), (2, 3));
+        // Prompt 2 re-reads prompt 1's exchange as input.
+        let u2 = estimated_usage_for_prompt(&transcript, 2).unwrap();
+        assert_eq!(u2.input_tokens, 7);
+        assert!(u2.output_tokens > 1);
+        assert!(estimated_usage_for_prompt(&transcript, 3).is_none());
+    }
+
+    #[test]
+    fn test_plan_for_prompt() {
+        let transcript = Transcript {
+            messages: vec![
+                Message::User {
+                    text: "plan the refactor".to_string(),
+                },
+                Message::Thinking {
+                    text: "The parser is the hot spot.".to_string(),
+                },
+                Message::ToolUse {
+                    id: "t1".to_string(),
+                    name: "ExitPlanMode".to_string(),
+                    input: serde_json::json!({"plan": "1. Draft"}),
+                },
+                Message::ToolUse {
+                    id: "t2".to_string(),
+                    name: "ExitPlanMode".to_string(),
+                    input: serde_json::json!({"plan": "1. Split parser\n2. Add tests"}),
+                },
+                Message::User {
+                    text: "go".to_string(),
+                },
+            ],
+        };
+
+        assert_eq!(
+            plan_for_prompt(&transcript, 1, false).as_deref(),
+            Some("1. Split parser\n2. Add tests")
+        );
+        let with_thinking = plan_for_prompt(&transcript, 1, true).unwrap();
+        assert!(with_thinking.starts_with("1. Split parser"));
+        assert!(with_thinking.contains("Thinking:\nThe parser is the hot spot."));
+        assert!(plan_for_prompt(&transcript, 2, true).is_none());
+    }
+
+    #[test]
+    fn test_thinking_blocks_parsed_but_not_turns() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let content = r#"{"type":"user","message":{"content":"Refactor it"},"timestamp":"2026-01-01T00:00:00Z"}
+{"type":"assistant","message":{"content":[{"type":"thinking","thinking":"Consider the module layout"},{"type":"text","text":"I'll restructure the parser module into smaller pieces."}]},"timestamp":"2026-01-01T00:00:01Z"}"#;
+        std::fs::write(tmp.path(), content).unwrap();
+
+        let result = parse_claude_jsonl(tmp.path().to_str().unwrap()).unwrap();
+        assert!(result.transcript.messages.iter().any(
+            |m| matches!(m, Message::Thinking { text } if text == "Consider the module layout")
+        ));
+        let turns = extract_conversation_turns(&result.transcript, 500, &|s| s.to_string());
+        assert!(turns.iter().all(|t| !t.content.contains("module layout")));
+    }
+
     #[test]
     fn test_model_from_jsonl_last_wins() {
         // Transcript where model changes between assistant messages.
@@ -38,6 +38,11 @@ pub fn short_sha(sha: &str) -> String {
     sha.chars().take(8).collect()
 }
 
+/// `git blame` attributes lines that are not committed yet to the all-zero SHA.
+pub fn is_not_committed(sha: &str) -> bool {
+    !sha.is_empty() && sha.chars().all(|c| c == '0')
+}
+
 /// Check whether two file paths refer to the same file.
 /// Handles relative/absolute mismatches by checking if one ends with `/` + the other
 /// (or is an exact match). This avoids false positives like "ob.rs" matching "a/bob.rs".
@@ -74,6 +79,40 @@ pub fn git_user() -> String {
     format!("{} <{}>", name, email)
 }
 
+/// Name of the currently checked-out branch, or `None` on a detached HEAD.
+pub fn current_branch() -> Option<String> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--abbrev-ref", "HEAD"])
+        .output()
+        .ok()?;
+    if output.status.success() {
+        let b = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        if b != "HEAD" {
+            Some(b)
+        } else {
+            None
+        }
+    } else {
+        None
+    }
+}
+
+/// Top-level directory of the innermost git repository containing `path`
+/// (a submodule or nested repo, not its superproject). Works for paths that
+/// no longer exist by starting from the nearest existing ancestor.
+pub fn repo_root_of(path: &std::path::Path) -> Option<String> {
+    let dir = path.ancestors().find(|p| p.is_dir())?;
+    let output = Command::new("git")
+        .args(["-C", &dir.to_string_lossy(), "rev-parse", "--show-toplevel"])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
 /// Parse a single unified-diff hunk header `@@ -old +new_start[,new_count] @@`
 /// and return `(new_start, new_end)`.
 pub fn parse_hunk_range(line: &str) -> (u32, u32) {
@@ -113,6 +152,11 @@ pub fn diff_line_range(diff_output: &str) -> (u32, u32) {
     }
 }
 
+/// Git's binary heuristic: a NUL byte within the first 8000 bytes.
+pub fn is_binary_content(bytes: &[u8]) -> bool {
+    bytes.iter().take(8000).any(|&b| b == 0)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -169,4 +213,11 @@ mod tests {
         assert_eq!(start, 1);
         assert_eq!(end, 15);
     }
+
+    #[test]
+    fn test_is_binary_content() {
+        assert!(!is_binary_content(b"fn main() {}\n"));
+        assert!(!is_binary_content(b""));
+        assert!(is_binary_content(b"\x89PNG\r\n\x1a\n\x00\x00\x00\rIHDR"));
+    }
 }
@@ -0,0 +1,204 @@
+/// Batched object reads over one long-lived `git cat-file --batch` process.
+///
+/// Blame, acceptance stats and line-origin classification read many blobs and
+/// notes; spawning `git cat-file -p` or `git notes show` for each one costs
+/// more than the read itself on large histories. Each thread keeps one
+/// `cat-file --batch` process and sends it object names (`<oid>`,
+/// `HEAD:path`, `refs/notes/...:<path>`) one per line. The process is
+/// restarted when the working directory changes, and when it dies the read is
+/// retried once on a fresh one.
+use std::cell::RefCell;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+/// An object as returned by `cat-file --batch`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Object {
+    pub oid: String,
+    /// "blob", "tree", "commit" or "tag".
+    pub kind: String,
+    pub data: Vec<u8>,
+}
+
+pub struct BlobReader {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    dir: PathBuf,
+}
+
+impl BlobReader {
+    /// Start `git cat-file --batch` in `dir`.
+    pub fn spawn(dir: PathBuf) -> Result<BlobReader, String> {
+        let mut child = Command::new("git")
+            .args(["cat-file", "--batch"])
+            .current_dir(&dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Cannot run git cat-file: {}", e))?;
+        let stdin = child.stdin.take().ok_or("git cat-file has no stdin")?;
+        let stdout = child.stdout.take().ok_or("git cat-file has no stdout")?;
+        Ok(BlobReader {
+            child,
+            stdin,
+            stdout: BufReader::new(stdout),
+            dir,
+        })
+    }
+
+    /// Read the object `spec` names. `Ok(None)` when it doesn't exist; `Err`
+    /// when the process is no longer usable.
+    pub fn read(&mut self, spec: &str) -> Result<Option<Object>, String> {
+        if spec.is_empty() || spec.contains('\n') {
+            return Ok(None);
+        }
+        let io = |e: std::io::Error| format!("git cat-file: {}", e);
+        self.stdin
+            .write_all(format!("{}\n", spec).as_bytes())
+            .map_err(io)?;
+        self.stdin.flush().map_err(io)?;
+        let mut header = String::new();
+        if self.stdout.read_line(&mut header).map_err(io)? == 0 {
+            return Err("git cat-file exited".to_string());
+        }
+        let Some((oid, kind, size)) = parse_header(&header) else {
+            // `<spec> missing` or `<spec> ambiguous`
+            return Ok(None);
+        };
+        let mut data = vec![0; size + 1];
+        self.stdout.read_exact(&mut data).map_err(io)?;
+        data.pop(); // trailing newline
+        Ok(Some(Object { oid, kind, data }))
+    }
+}
+
+impl Drop for BlobReader {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// `<oid> <type> <size>`; `None` for the missing/ambiguous replies.
+fn parse_header(line: &str) -> Option<(String, String, usize)> {
+    let mut parts = line.split_whitespace();
+    let (oid, kind, size) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    Some((oid.to_string(), kind.to_string(), size.parse().ok()?))
+}
+
+thread_local! {
+    static SHARED: RefCell<Option<BlobReader>> = const { RefCell::new(None) };
+}
+
+/// Read `spec` through this thread's shared reader.
+pub fn read(spec: &str) -> Option<Object> {
+    let dir = std::env::current_dir().ok()?;
+    SHARED.with(|shared| {
+        let mut shared = shared.borrow_mut();
+        for _ in 0..2 {
+            if shared.as_ref().is_none_or(|r| r.dir != dir) {
+                *shared = Some(BlobReader::spawn(dir.clone()).ok()?);
+            }
+            match shared.as_mut()?.read(spec) {
+                Ok(object) => return object,
+                Err(e) => {
+                    tracing::debug!(error = e.as_str(), "restarting git cat-file");
+                    *shared = None;
+                }
+            }
+        }
+        None
+    })
+}
+
+/// A blob's content, lossily decoded.
+pub fn read_blob(spec: &str) -> Option<String> {
+    read(spec)
+        .filter(|o| o.kind == "blob")
+        .map(|o| String::from_utf8_lossy(&o.data).into_owned())
+}
+
+/// A blob's lines; empty when it doesn't exist.
+pub fn blob_lines(spec: &str) -> Vec<String> {
+    read_blob(spec)
+        .map(|s| s.lines().map(String::from).collect())
+        .unwrap_or_default()
+}
+
+/// The note `notes_ref` attaches to `commit` (a full SHA), looked up in the
+/// flat and fanned-out layouts git uses as a notes tree grows.
+pub fn read_note(notes_ref: &str, commit: &str) -> Option<String> {
+    let fanouts = [
+        commit.to_string(),
+        format!("{}/{}", commit.get(..2)?, commit.get(2..)?),
+        format!(
+            "{}/{}/{}",
+            commit.get(..2)?,
+            commit.get(2..4)?,
+            commit.get(4..)?
+        ),
+    ];
+    fanouts
+        .iter()
+        .find_map(|path| read_blob(&format!("{}:{}", notes_ref, path)))
+        .map(|s| s.trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_reads_blobs_and_reports_missing() {
+        assert_eq!(
+            parse_header("8c1384d825dbbe41309b7dc18ee7991a9085c46e blob 12\n"),
+            Some((
+                "8c1384d825dbbe41309b7dc18ee7991a9085c46e".to_string(),
+                "blob".to_string(),
+                12
+            ))
+        );
+        assert_eq!(parse_header("HEAD:nope missing\n"), None);
+
+        let dir = tempfile::tempdir().unwrap();
+        let git = |args: &[&str]| {
+            Command::new("git")
+                .args(args)
+                .current_dir(dir.path())
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q"]);
+        std::fs::write(dir.path().join("a.txt"), "one\ntwo\n").unwrap();
+        std::fs::write(dir.path().join("b.txt"), "").unwrap();
+        git(&["add", "."]);
+        git(&[
+            "-c",
+            "user.name=t",
+            "-c",
+            "user.email=t@t",
+            "commit",
+            "-qm",
+            "c",
+        ]);
+
+        let mut reader = BlobReader::spawn(dir.path().to_path_buf()).unwrap();
+        let a = reader.read("HEAD:a.txt").unwrap().unwrap();
+        assert_eq!(
+            (a.kind.as_str(), a.data.as_slice()),
+            ("blob", &b"one\ntwo\n"[..])
+        );
+        assert_eq!(reader.read("HEAD:b.txt").unwrap().unwrap().data, b"");
+        assert_eq!(reader.read("HEAD:missing.txt").unwrap(), None);
+        // The same process keeps serving after a miss.
+        let again = reader.read(&a.oid).unwrap().unwrap();
+        assert_eq!(again.data, a.data);
+        assert_eq!(reader.read("HEAD").unwrap().unwrap().kind, "commit");
+    }
+}
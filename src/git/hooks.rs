@@ -61,26 +61,12 @@ if [ -n "$BLAMEPROMPT" ] && [ -x "$BLAMEPROMPT" ]; then
     if [ ! -d "$BP_DIR" ]; then
         mkdir -p "$BP_DIR"
         echo '{{"receipts":[]}}' > "$BP_DIR/staging.json"
-        # Add .blameprompt/ to .gitignore only if not already covered by any
-        # ignore source: local .gitignore, global excludesFile, or .git/info/exclude.
-        _BP_ALREADY_IGNORED=0
-        if [ -f .gitignore ] && grep -q '\.blameprompt' .gitignore 2>/dev/null; then
-            _BP_ALREADY_IGNORED=1
-        fi
-        if [ "$_BP_ALREADY_IGNORED" = "0" ]; then
-            _BP_GLOBAL_IGNORE=$(git config --global --get core.excludesFile 2>/dev/null)
-            if [ -z "$_BP_GLOBAL_IGNORE" ] && [ -f "$HOME/.gitignore_global" ]; then
-                _BP_GLOBAL_IGNORE="$HOME/.gitignore_global"
-            fi
-            if [ -n "$_BP_GLOBAL_IGNORE" ] && [ -f "$_BP_GLOBAL_IGNORE" ] && grep -q '\.blameprompt' "$_BP_GLOBAL_IGNORE" 2>/dev/null; then
-                _BP_ALREADY_IGNORED=1
-            fi
-        fi
-        if [ "$_BP_ALREADY_IGNORED" = "0" ] && [ -f ".git/info/exclude" ] && grep -q '\.blameprompt' .git/info/exclude 2>/dev/null; then
-            _BP_ALREADY_IGNORED=1
-        fi
-        if [ "$_BP_ALREADY_IGNORED" = "0" ]; then
-            printf '\n# BlamePrompt staging (auto-generated)\n.blameprompt/\n' >> .gitignore
+        # Add the staging files to .gitignore only if git doesn't already
+        # ignore them (local .gitignore, global excludesFile, .git/info/exclude).
+        # The rest of .blameprompt/ (config.toml, ignore, sidecar receipts) is
+        # meant to be committed.
+        if ! git check-ignore -q "$BP_DIR/staging.json" 2>/dev/null; then
+            printf '\n{ignore_block}\n' >> .gitignore
         fi
     fi
     # Fetch remote BlamePrompt notes if they exist
@@ -89,7 +75,8 @@ fi
 # /BlamePrompt
 "#,
         preamble = PATH_PREAMBLE,
-        binary = binary
+        binary = binary,
+        ignore_block = crate::commands::staging::gitignore_block().replace('\n', "\\n"),
     )
 }
 
@@ -104,6 +91,8 @@ if [ -n "$BLAMEPROMPT" ] && [ -x "$BLAMEPROMPT" ]; then
     if [ "$COUNT" != "0" ]; then
         echo "[BlamePrompt] $COUNT staged receipt(s) preserved after merge"
     fi
+    # Link receipts to the PRs whose merge commits just arrived
+    "$BLAMEPROMPT" link-pr >/dev/null 2>&1 || true
 fi
 # /BlamePrompt
 "#,
@@ -210,15 +199,29 @@ fn all_hooks(binary: &str) -> Vec<(&'static str, String)> {
     ]
 }
 
-fn git_hooks_dir() -> Result<std::path::PathBuf, String> {
-    let repo = git2::Repository::discover(".").map_err(|_| {
+fn git_hooks_dir_in(dir: &Path) -> Result<std::path::PathBuf, String> {
+    let repo = git2::Repository::discover(dir).map_err(|_| {
         "Not in a git repository. Run this from inside a git repository.".to_string()
     })?;
     Ok(repo.path().join("hooks"))
 }
 
+fn git_hooks_dir() -> Result<std::path::PathBuf, String> {
+    git_hooks_dir_in(Path::new("."))
+}
+
 pub fn install_hooks() -> Result<(), String> {
-    let hooks_dir = git_hooks_dir()?;
+    install_hooks_in(Path::new("."))?;
+    // Submodules have their own hooks directory (.git/modules/<name>/hooks);
+    // without hooks there, receipts staged for submodule files never attach.
+    for submodule in submodule_paths() {
+        install_hooks_in(&submodule)?;
+    }
+    Ok(())
+}
+
+fn install_hooks_in(dir: &Path) -> Result<(), String> {
+    let hooks_dir = git_hooks_dir_in(dir)?;
     std::fs::create_dir_all(&hooks_dir).map_err(|e| format!("Cannot create hooks dir: {}", e))?;
 
     let binary = resolve_binary_path();
@@ -230,6 +233,23 @@ pub fn install_hooks() -> Result<(), String> {
     Ok(())
 }
 
+/// Working-tree paths of initialized submodules, recursively.
+fn submodule_paths() -> Vec<std::path::PathBuf> {
+    std::process::Command::new("git")
+        .args(["submodule", "foreach", "--quiet", "--recursive", "pwd"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| {
+            String::from_utf8_lossy(&o.stdout)
+                .lines()
+                .filter(|l| !l.is_empty())
+                .map(std::path::PathBuf::from)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
 fn install_hook(hooks_dir: &Path, name: &str, content: &str) -> Result<(), String> {
     let hook_path = hooks_dir.join(name);
 
@@ -268,6 +288,31 @@ fn install_hook(hooks_dir: &Path, name: &str, content: &str) -> Result<(), Strin
     Ok(())
 }
 
+/// Hooks `install_hooks` may have written into a repository.
+const REPO_HOOK_NAMES: [&str; 7] = [
+    "pre-commit",
+    "prepare-commit-msg",
+    "post-commit",
+    "pre-push",
+    "post-checkout",
+    "post-merge",
+    "post-rewrite",
+];
+
+/// Names of the current repository's hooks that carry a BlamePrompt section.
+pub fn installed_hook_names() -> Vec<&'static str> {
+    let Ok(hooks_dir) = git_hooks_dir() else {
+        return Vec::new();
+    };
+    REPO_HOOK_NAMES
+        .into_iter()
+        .filter(|name| {
+            std::fs::read_to_string(hooks_dir.join(name))
+                .is_ok_and(|content| content.contains("BlamePrompt"))
+        })
+        .collect()
+}
+
 pub fn uninstall_hooks() -> Result<(), String> {
     let hooks_dir = match git_hooks_dir() {
         Ok(d) => d,
@@ -277,16 +322,7 @@ pub fn uninstall_hooks() -> Result<(), String> {
         }
     };
 
-    let hook_names = [
-        "pre-commit",
-        "prepare-commit-msg",
-        "post-commit",
-        "pre-push",
-        "post-checkout",
-        "post-merge",
-        "post-rewrite",
-    ];
-    for hook_name in &hook_names {
+    for hook_name in &REPO_HOOK_NAMES {
         let hook_path = hooks_dir.join(hook_name);
         if !hook_path.exists() {
             continue;
@@ -0,0 +1,389 @@
+/// Global `core.hooksPath` multiplexer: commit integration without a git shim.
+///
+/// `install-hooks-path` points the global `core.hooksPath` at
+/// `~/.blameprompt/hooks/`, which holds one dispatcher per client-side hook.
+/// Setting `core.hooksPath` makes git ignore each repository's own
+/// `.git/hooks`, so every dispatcher chains to, in order:
+///
+/// 1. the hook in the previous global `core.hooksPath`, if there was one;
+/// 2. the repository's own hook (`$GIT_COMMON_DIR/hooks/<name>`);
+/// 3. BlamePrompt's section for that hook, unless one of the hooks above
+///    already contains it (repositories set up with `install-hooks`).
+///
+/// A failing chained hook stops the chain with its exit status, so pre-commit
+/// checks still block commits. Hooks that read stdin get it replayed to each
+/// hook in the chain.
+use super::hooks;
+use crate::core::term;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Every hook git runs on the client side.
+const CLIENT_HOOKS: &[&str] = &[
+    "applypatch-msg",
+    "pre-applypatch",
+    "post-applypatch",
+    "pre-commit",
+    "pre-merge-commit",
+    "prepare-commit-msg",
+    "commit-msg",
+    "post-commit",
+    "pre-rebase",
+    "post-checkout",
+    "post-merge",
+    "pre-push",
+    "pre-auto-gc",
+    "post-rewrite",
+    "sendemail-validate",
+    "post-index-change",
+    "reference-transaction",
+    "push-to-checkout",
+];
+
+/// Directory of BlamePrompt's own sections, inside the multiplexer directory.
+const SECTIONS_DIR: &str = "blameprompt";
+
+/// File remembering the global `core.hooksPath` that was set before ours.
+const PREVIOUS_FILE: &str = "previous-hooks-path";
+
+/// Path of the multiplexer directory.
+pub fn mux_dir() -> Option<PathBuf> {
+    dirs::home_dir().map(|h| h.join(".blameprompt").join("hooks"))
+}
+
+/// Whether the global `core.hooksPath` points at the multiplexer.
+pub fn is_installed() -> bool {
+    match (global_hooks_path(), mux_dir()) {
+        (Some(current), Some(dir)) => same_path(&current, &dir),
+        _ => false,
+    }
+}
+
+/// Repository-level `core.hooksPath` (e.g. set by husky), which takes
+/// precedence over the global multiplexer in that repository.
+pub fn local_override() -> Option<String> {
+    git_config(&["--local", "--get", "core.hooksPath"])
+}
+
+/// Write the multiplexer and point the global `core.hooksPath` at it.
+/// Returns the directory and the previous hooks path it chains to.
+pub fn install() -> Result<(PathBuf, Option<String>), String> {
+    let dir = mux_dir().ok_or("Cannot determine home directory")?;
+    let previous = match global_hooks_path() {
+        Some(current) if same_path(&current, &dir) => {
+            // Reinstall: keep chaining to whatever was there before us.
+            std::fs::read_to_string(dir.join(PREVIOUS_FILE))
+                .ok()
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty())
+        }
+        // The dispatcher quotes the path, so the shell won't expand `~` in it.
+        other => other.map(|p| expand_home(&p)),
+    };
+
+    write_dir(&dir, &hooks::resolve_binary_path(), previous.as_deref())?;
+    let previous_file = dir.join(PREVIOUS_FILE);
+    match &previous {
+        Some(p) => std::fs::write(&previous_file, format!("{}\n", p))
+            .map_err(|e| format!("Cannot write {}: {}", previous_file.display(), e))?,
+        None => {
+            let _ = std::fs::remove_file(&previous_file);
+        }
+    }
+
+    let status = Command::new("git")
+        .args([
+            "config",
+            "--global",
+            "core.hooksPath",
+            &dir.to_string_lossy(),
+        ])
+        .status()
+        .map_err(|e| format!("Cannot set git config: {}", e))?;
+    if !status.success() {
+        return Err("Failed to set core.hooksPath".to_string());
+    }
+    Ok((dir, previous))
+}
+
+/// Restore the previous global `core.hooksPath` (or unset it) and remove the
+/// multiplexer directory.
+pub fn uninstall() -> Result<(), String> {
+    let Some(dir) = mux_dir() else {
+        return Ok(());
+    };
+    if is_installed() {
+        let previous = std::fs::read_to_string(dir.join(PREVIOUS_FILE))
+            .ok()
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty());
+        let args: Vec<&str> = match &previous {
+            Some(p) => vec!["config", "--global", "core.hooksPath", p],
+            None => vec!["config", "--global", "--unset", "core.hooksPath"],
+        };
+        Command::new("git")
+            .args(&args)
+            .status()
+            .map_err(|e| format!("Cannot reset core.hooksPath: {}", e))?;
+        println!(
+            "  {} {} global core.hooksPath",
+            term::success("[done]"),
+            if previous.is_some() {
+                "Restored"
+            } else {
+                "Unset"
+            }
+        );
+    }
+    if dir.exists() {
+        std::fs::remove_dir_all(&dir)
+            .map_err(|e| format!("Cannot remove {}: {}", dir.display(), e))?;
+        println!(
+            "  {} Removed hooks multiplexer {}",
+            term::success("[done]"),
+            term::dim("(~/.blameprompt/hooks)")
+        );
+    }
+    Ok(())
+}
+
+/// Write one dispatcher per client hook into `dir`, and BlamePrompt's hook
+/// sections into `dir/blameprompt/`.
+pub fn write_dir(dir: &Path, binary: &str, previous: Option<&str>) -> Result<(), String> {
+    let sections = dir.join(SECTIONS_DIR);
+    std::fs::create_dir_all(&sections)
+        .map_err(|e| format!("Cannot create {}: {}", sections.display(), e))?;
+
+    let dispatcher = dispatcher_content(previous);
+    for name in CLIENT_HOOKS {
+        write_executable(&dir.join(name), &dispatcher)?;
+    }
+    for (name, content) in hooks::all_hook_entries(binary) {
+        write_executable(&sections.join(name), &format!("#!/bin/sh\n\n{}", content))?;
+    }
+    Ok(())
+}
+
+fn dispatcher_content(previous: Option<&str>) -> String {
+    format!(
+        r##"#!/bin/sh
+# BlamePrompt hooks multiplexer (regenerated by: blameprompt install-hooks-path)
+# Chains to the previous global hooks, the repository's own hook, then BlamePrompt.
+_BP_HOOK=$(basename "$0")
+_BP_MUX=$(cd "$(dirname "$0")" && pwd)
+_BP_PREVIOUS="{previous}"
+_BP_COMMON=$(git rev-parse --git-common-dir 2>/dev/null)
+
+# Hooks that read stdin: buffer it so every hook in the chain sees all of it.
+_BP_STDIN=""
+case "$_BP_HOOK" in
+    pre-push|post-rewrite|reference-transaction)
+        _BP_STDIN=$(mktemp "${{TMPDIR:-/tmp}}/blameprompt-hook.XXXXXX") || exit 1
+        trap 'rm -f "$_BP_STDIN"' EXIT
+        cat > "$_BP_STDIN"
+        ;;
+esac
+
+# Set when a chained hook already carries the BlamePrompt section.
+_BP_HAS_SECTION=0
+_bp_run() {{
+    _bp_hook="$1"
+    shift
+    if [ -d "$_bp_hook" ] || [ ! -x "$_bp_hook" ]; then
+        return 0
+    fi
+    if grep -q "# /BlamePrompt" "$_bp_hook" 2>/dev/null; then
+        _BP_HAS_SECTION=1
+    fi
+    if [ -n "$_BP_STDIN" ]; then
+        "$_bp_hook" "$@" < "$_BP_STDIN"
+    else
+        "$_bp_hook" "$@"
+    fi
+}}
+
+if [ -n "$_BP_PREVIOUS" ]; then
+    _bp_run "$_BP_PREVIOUS/$_BP_HOOK" "$@" || exit $?
+fi
+if [ -n "$_BP_COMMON" ]; then
+    _bp_run "$_BP_COMMON/hooks/$_BP_HOOK" "$@" || exit $?
+fi
+if [ "$_BP_HAS_SECTION" = "0" ]; then
+    _bp_run "$_BP_MUX/{sections}/$_BP_HOOK" "$@" || exit $?
+fi
+exit 0
+"##,
+        previous = shell_escape(previous.unwrap_or_default()),
+        sections = SECTIONS_DIR,
+    )
+}
+
+/// Escape `text` for use inside a double-quoted shell string.
+fn shell_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '"' | '$' | '`') {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+fn write_executable(path: &Path, content: &str) -> Result<(), String> {
+    std::fs::write(path, content).map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let _ = std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o755));
+    }
+    Ok(())
+}
+
+fn global_hooks_path() -> Option<String> {
+    git_config(&["--global", "--get", "core.hooksPath"])
+}
+
+fn git_config(args: &[&str]) -> Option<String> {
+    let output = Command::new("git").arg("config").args(args).output().ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|s| !s.is_empty())
+}
+
+/// A configured hooks path with a leading `~` replaced by the home
+/// directory, as git reads it. Relative paths are left as they are: git
+/// resolves them against the repository a hook runs in, and so does the
+/// dispatcher, which git starts from that same directory.
+fn expand_home(configured: &str) -> String {
+    let rest = match configured.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => rest,
+        _ => return configured.to_string(),
+    };
+    match dirs::home_dir() {
+        Some(home) => format!("{}{}", home.to_string_lossy().trim_end_matches('/'), rest),
+        None => configured.to_string(),
+    }
+}
+
+/// Compare a configured hooks path (which may use `~`) with a directory.
+fn same_path(configured: &str, dir: &Path) -> bool {
+    // Collecting components drops trailing slashes and `.` segments.
+    let normalize = |p: &Path| p.components().collect::<PathBuf>();
+    normalize(Path::new(&expand_home(configured))) == normalize(dir)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_in(dir: &Path, args: &[&str]) -> bool {
+        Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "t")
+            .env("GIT_AUTHOR_EMAIL", "t@example.com")
+            .env("GIT_COMMITTER_NAME", "t")
+            .env("GIT_COMMITTER_EMAIL", "t@example.com")
+            .output()
+            .unwrap()
+            .status
+            .success()
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_multiplexer_chains_hooks() {
+        let tmp = tempfile::tempdir().unwrap();
+        let repo = tmp.path().join("repo");
+        let mux = tmp.path().join("mux");
+        let previous = tmp.path().join("previous");
+        let log = tmp.path().join("log");
+        std::fs::create_dir_all(&repo).unwrap();
+        std::fs::create_dir_all(&previous).unwrap();
+        assert!(git_in(&repo, &["init", "-q"]));
+
+        // Stand-in blameprompt binary: records the subcommands hooks run.
+        let binary = tmp.path().join("blameprompt");
+        let script = format!("#!/bin/sh\necho \"bp $1\" >> {}\necho 0\n", log.display());
+        write_executable(&binary, &script).unwrap();
+        let hook = |text: &str, exit: u8| {
+            format!(
+                "#!/bin/sh\necho \"{}\" >> {}\nexit {}\n",
+                text,
+                log.display(),
+                exit
+            )
+        };
+        write_executable(&previous.join("post-commit"), &hook("previous", 0)).unwrap();
+        write_executable(
+            &repo.join(".git/hooks/post-commit"),
+            &hook("repo post-commit", 0),
+        )
+        .unwrap();
+
+        write_dir(
+            &mux,
+            &binary.to_string_lossy(),
+            Some(&previous.to_string_lossy()),
+        )
+        .unwrap();
+        assert!(git_in(
+            &repo,
+            &["config", "core.hooksPath", &mux.to_string_lossy()]
+        ));
+        assert!(git_in(
+            &repo,
+            &["commit", "-q", "--allow-empty", "-m", "one"]
+        ));
+        let ran = std::fs::read_to_string(&log).unwrap();
+        let post: Vec<&str> = ran.lines().filter(|l| *l != "bp staging-count").collect();
+        assert_eq!(post, vec!["previous", "repo post-commit", "bp attach"]);
+
+        // A failing repository pre-commit hook still blocks the commit.
+        write_executable(&repo.join(".git/hooks/pre-commit"), &hook("blocked", 1)).unwrap();
+        assert!(!git_in(
+            &repo,
+            &["commit", "-q", "--allow-empty", "-m", "two"]
+        ));
+    }
+
+    #[test]
+    fn test_dispatcher_skips_duplicate_section() {
+        let content = dispatcher_content(Some("/etc/git-hooks"));
+        assert!(content.contains("_BP_PREVIOUS=\"/etc/git-hooks\""));
+        assert!(content.contains("grep -q \"# /BlamePrompt\""));
+        assert!(content.contains("pre-push|post-rewrite"));
+        assert!(same_path(
+            "/home/u/.blameprompt/hooks/",
+            Path::new("/home/u/.blameprompt/hooks")
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dispatcher_quotes_previous_path() {
+        let home = dirs::home_dir().unwrap();
+        assert_eq!(
+            expand_home("~/hooks"),
+            format!("{}/hooks", home.to_string_lossy().trim_end_matches('/'))
+        );
+        assert_eq!(expand_home("~other/hooks"), "~other/hooks");
+        assert_eq!(expand_home(".githooks"), ".githooks");
+
+        let previous = r#"/tmp/a "b" $HOME `id` \c"#;
+        let content = dispatcher_content(Some(previous));
+        let assignment = content
+            .lines()
+            .find(|l| l.starts_with("_BP_PREVIOUS="))
+            .unwrap();
+        let output = Command::new("sh")
+            .arg("-c")
+            .arg(format!("{}\nprintf %s \"$_BP_PREVIOUS\"", assignment))
+            .output()
+            .unwrap();
+        assert_eq!(String::from_utf8_lossy(&output.stdout), previous);
+    }
+}
@@ -24,7 +24,7 @@ fn is_explicitly_uninstalled() -> bool {
 
 /// Remove the uninstall marker. Called when the user explicitly runs
 /// `blameprompt init` to reinstall, so future auto_setup() calls work again.
-fn remove_uninstall_marker() {
+pub fn remove_uninstall_marker() {
     if let Some(path) = uninstall_marker_path() {
         let _ = std::fs::remove_file(&path);
     }
@@ -36,7 +36,7 @@ pub fn is_globally_configured() -> bool {
 }
 
 /// Write the marker file after successful global setup.
-fn mark_setup_done() {
+pub fn mark_setup_done() {
     if let Some(path) = setup_marker_path() {
         if let Some(parent) = path.parent() {
             let _ = std::fs::create_dir_all(parent);
@@ -259,11 +259,6 @@ pub fn install_git_template() -> Result<(), String> {
     Ok(())
 }
 
-/// Delegates to staging::is_blameprompt_ignored to avoid duplication.
-fn is_blameprompt_ignored(repo_root: &str) -> bool {
-    crate::commands::staging::is_blameprompt_ignored(Path::new(repo_root))
-}
-
 pub fn auto_init_blameprompt(repo_root: &str) -> Result<(), String> {
     let bp_dir = Path::new(repo_root).join(".blameprompt");
 
@@ -278,23 +273,66 @@ pub fn auto_init_blameprompt(repo_root: &str) -> Result<(), String> {
             .map_err(|e| format!("Cannot create staging.json: {}", e))?;
     }
 
-    // Only add to local .gitignore if .blameprompt/ is not already covered by
-    // any gitignore source (local, global, or .git/info/exclude).
-    if !is_blameprompt_ignored(repo_root) {
-        let gitignore = Path::new(repo_root).join(".gitignore");
-        let mut file = std::fs::OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&gitignore)
-            .map_err(|e| format!("Cannot write .gitignore: {}", e))?;
-        use std::io::Write;
-        writeln!(
-            file,
-            "\n# BlamePrompt staging (auto-generated)\n.blameprompt/"
-        )
-        .map_err(|e| format!("Cannot append to .gitignore: {}", e))?;
+    crate::commands::staging::ensure_gitignored(Path::new(repo_root))
+        .map_err(|e| format!("Cannot write .gitignore: {}", e))?;
+
+    Ok(())
+}
+
+/// Non-interactive setup for CI and other ephemeral environments.
+///
+/// Only makes receipts readable: configures the notes fetch refspec, fetches
+/// notes from origin and syncs the SQLite cache. No hooks, no git template, no
+/// wrapper, and no `.gitignore` edits, so the checkout stays clean. Every step
+/// is idempotent, so running it on each job is safe.
+pub fn run_init_ci() -> Result<(), String> {
+    let cwd = std::env::current_dir().map_err(|e| format!("Cannot get cwd: {}", e))?;
+    git2::Repository::discover(&cwd)
+        .map_err(|_| "Not inside a git repository. Run 'git init' first.".to_string())?;
+
+    let has_origin = std::process::Command::new("git")
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .is_ok_and(|o| o.status.success());
+
+    if has_origin {
+        if crate::commands::sync::ensure_notes_fetch_refspec("origin") {
+            println!("[BlamePrompt] Added notes refspec to remote.origin.fetch");
+        }
+        let fetch = std::process::Command::new("git")
+            .args([
+                "fetch",
+                "--quiet",
+                "origin",
+                "+refs/notes/blameprompt:refs/notes/blameprompt",
+            ])
+            .output()
+            .map_err(|e| format!("git fetch failed: {}", e))?;
+        if fetch.status.success() {
+            println!("[BlamePrompt] Fetched notes from origin");
+        } else {
+            let stderr = String::from_utf8_lossy(&fetch.stderr);
+            if stderr.contains("couldn't find remote ref") {
+                println!("[BlamePrompt] No notes on origin yet");
+            } else {
+                return Err(format!("Fetching notes failed: {}", stderr.trim()));
+            }
+        }
+    } else {
+        println!("[BlamePrompt] No 'origin' remote; using local notes only");
     }
 
+    match crate::core::db::sync_from_notes() {
+        Err(e) if crate::core::db::is_locked(&e) => {
+            println!("[BlamePrompt] Cache is locked by another process; skipping cache sync")
+        }
+        result => result?,
+    }
+    crate::core::org_config::sync_if_configured();
+    // The commands the job runs next would otherwise do the first-run global
+    // setup (agent hooks, git template, wrapper) in the runner's home.
+    mark_setup_done();
+    println!("[BlamePrompt] CI setup complete (hooks not installed)");
     Ok(())
 }
 
@@ -357,6 +395,7 @@ pub fn run_init(global: bool) -> Result<(), String> {
         println!("  {c}blameprompt --help{r}             {d}See all commands{r}");
         println!();
     }
+    crate::core::org_config::sync_if_configured();
 
     Ok(())
 }
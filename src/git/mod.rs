@@ -1,4 +1,8 @@
+pub mod blob_reader;
 pub mod hooks;
+pub mod hooks_path;
 pub mod init_hooks;
 pub mod notes;
+pub mod partial_clone;
+pub mod storage;
 pub mod wrap;
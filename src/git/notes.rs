@@ -1,18 +1,41 @@
+use crate::commands::finalize;
 use crate::commands::staging::StagingData;
+use crate::core::migrate;
 use crate::core::receipt::NotePayload;
-use std::process::{Command, Stdio};
+use crate::git::storage;
 
-pub fn attach_receipts_to_head(staging: &StagingData) -> Result<(), String> {
-    if staging.receipts.is_empty() {
-        return Ok(());
-    }
+/// The note HEAD carries once staged receipts are attached.
+pub struct HeadNote {
+    pub head: String,
+    /// The commit `git commit --amend` replaced, whose note moves to HEAD.
+    pub amended: Option<String>,
+    /// Receipts HEAD (or the amended commit) already had.
+    pub existing: usize,
+    pub payload: NotePayload,
+}
+
+/// Build HEAD's note with the staged receipts added, without writing it. The
+/// staged receipts are listed as pending until `finalize` has computed their
+/// acceptance stats and the commit's line origins.
+pub fn head_note(staging: &StagingData) -> Result<HeadNote, String> {
+    let head = rev_parse("HEAD").ok_or("Cannot resolve HEAD")?;
 
-    // Merge with existing notes if present
-    let mut receipts = if let Some(existing) = read_receipts_for_commit("HEAD") {
-        existing.receipts
-    } else {
-        Vec::new()
+    // Merge with existing notes if present. After `git commit --amend` the
+    // receipts still sit on the amended commit; carry them over so the staged
+    // receipts add to them instead of replacing them.
+    let amended = amended_commit();
+    let mut existing = read_receipts_for_commit(&head);
+    if let Some(old) = amended.as_deref().and_then(read_receipts_for_commit) {
+        existing = Some(match existing {
+            Some(current) => merge_payloads(current, old),
+            None => old,
+        });
+    }
+    let mut receipts = match existing {
+        Some(ref e) => e.receipts.clone(),
+        None => Vec::new(),
     };
+    let existing_count = receipts.len();
 
     // Add new receipts, avoiding duplicates by ID
     for r in &staging.receipts {
@@ -20,77 +43,122 @@ pub fn attach_receipts_to_head(staging: &StagingData) -> Result<(), String> {
             receipts.push(r.clone());
         }
     }
+    let mut payload = NotePayload::new(receipts);
+    if let Some(e) = existing {
+        payload.file_mappings = e.file_mappings;
+        payload.code_origin = e.code_origin;
+        payload.pull_request = e.pull_request;
+        payload.pending_finalize = e.pending_finalize;
+    }
+    finalize::mark_pending(&mut payload, &staging.receipts);
+    Ok(HeadNote {
+        head,
+        amended,
+        existing: existing_count,
+        payload,
+    })
+}
 
-    let payload = NotePayload::new(receipts);
-    let json = serde_json::to_string_pretty(&payload)
-        .map_err(|e| format!("Failed to serialize: {}", e))?;
-
-    let mut child = Command::new("git")
-        .args([
-            "notes",
-            "--ref",
-            "refs/notes/blameprompt",
-            "add",
-            "-f",
-            "-F",
-            "-",
-            "HEAD",
-        ])
-        .stdin(Stdio::piped())
-        .stdout(Stdio::null())
-        .stderr(Stdio::piped())
-        .spawn()
-        .map_err(|e| format!("Failed to spawn git notes: {}", e))?;
+/// Attach the staged receipts to HEAD's note and return HEAD's SHA. With
+/// `finalize_now` the note is finalized before it is written.
+pub fn attach_receipts_to_head(
+    staging: &StagingData,
+    finalize_now: bool,
+) -> Result<String, String> {
+    if staging.receipts.is_empty() {
+        return rev_parse("HEAD").ok_or_else(|| "Cannot resolve HEAD".to_string());
+    }
+    let HeadNote {
+        head,
+        amended,
+        mut payload,
+        ..
+    } = head_note(staging)?;
+    if finalize_now {
+        finalize::finalize_payload(&mut payload, &head);
+    }
+    match amended {
+        Some(old) => {
+            let json = serde_json::to_string_pretty(&payload)
+                .map_err(|e| format!("Failed to serialize: {}", e))?;
+            storage::active().move_to(&old, &head, &json)?;
+        }
+        None => write_receipts_for_commit(&head, &payload)?,
+    }
+    Ok(head)
+}
 
-    if let Some(mut stdin) = child.stdin.take() {
-        use std::io::Write;
-        stdin
-            .write_all(json.as_bytes())
-            .map_err(|e| format!("Failed to write to stdin: {}", e))?;
+/// The commit HEAD replaced through `git commit --amend`: the previous HEAD
+/// (from the reflog) when it has the same parents as HEAD and carries
+/// receipts. The post-rewrite hook would remap its note too, but only after
+/// the staged receipts were attached, and it would overwrite them.
+pub fn amended_commit() -> Option<String> {
+    let head = rev_parse("HEAD")?;
+    let previous = rev_parse("HEAD@{1}")?;
+    if previous == head || parents("HEAD")? != parents(&previous)? {
+        return None;
     }
+    storage::active().read(&previous).map(|_| previous)
+}
 
-    let output = child
-        .wait_with_output()
-        .map_err(|e| format!("Failed to wait: {}", e))?;
-    if !output.status.success() {
-        let stderr = String::from_utf8_lossy(&output.stderr);
-        return Err(format!("git notes add failed: {}", stderr.trim()));
+/// Combine two payloads for one commit: `newer`'s receipts first, then the
+/// ones only `older` has; `newer`'s line mappings and links win.
+pub fn merge_payloads(newer: NotePayload, older: NotePayload) -> NotePayload {
+    let mut merged = newer;
+    for r in older.receipts {
+        if !merged.receipts.iter().any(|m| m.id == r.id) {
+            merged.receipts.push(r);
+        }
+    }
+    merged.file_mappings = merged.file_mappings.or(older.file_mappings);
+    merged.code_origin = merged.code_origin.or(older.code_origin);
+    merged.pull_request = merged.pull_request.or(older.pull_request);
+    for id in older.pending_finalize {
+        if !merged.pending_finalize.contains(&id) {
+            merged.pending_finalize.push(id);
+        }
     }
+    merged
+}
 
-    Ok(())
+fn rev_parse(rev: &str) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-parse", "--verify", "--quiet", rev])
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
 }
 
-pub fn read_receipts_for_commit(sha: &str) -> Option<NotePayload> {
-    let output = Command::new("git")
-        .args(["notes", "--ref", "refs/notes/blameprompt", "show", sha])
+fn parents(rev: &str) -> Option<String> {
+    let output = std::process::Command::new("git")
+        .args(["rev-list", "--parents", "-n", "1", rev])
         .output()
         .ok()?;
+    let line = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    output.status.success().then(|| {
+        line.split_once(' ')
+            .map(|(_, p)| p.to_string())
+            .unwrap_or_default()
+    })
+}
 
-    if !output.status.success() {
-        return None;
-    }
+/// Replace the receipts stored for `sha` with `payload`, in the configured
+/// storage backend (git notes by default).
+pub fn write_receipts_for_commit(sha: &str, payload: &NotePayload) -> Result<(), String> {
+    let json =
+        serde_json::to_string_pretty(payload).map_err(|e| format!("Failed to serialize: {}", e))?;
+    storage::active().write(sha, &json)
+}
 
-    let content = String::from_utf8_lossy(&output.stdout);
-    serde_json::from_str(&content).ok()
+/// Receipts stored for `sha`, upgraded to the current schema.
+pub fn read_receipts_for_commit(sha: &str) -> Option<NotePayload> {
+    let content = storage::active().read(sha)?;
+    migrate::parse(&content).ok()
 }
 
-#[allow(dead_code)]
 pub fn list_commits_with_notes() -> Vec<String> {
-    let output = Command::new("git")
-        .args(["notes", "--ref", "refs/notes/blameprompt", "list"])
-        .output();
-
-    match output {
-        Ok(o) if o.status.success() => {
-            let text = String::from_utf8_lossy(&o.stdout);
-            text.lines()
-                .filter_map(|line| {
-                    // Format: <note-object-sha> <commit-sha>
-                    let parts: Vec<&str> = line.split_whitespace().collect();
-                    parts.get(1).map(|s| s.to_string())
-                })
-                .collect()
-        }
-        _ => Vec::new(),
-    }
+    storage::active().list()
 }
@@ -0,0 +1,200 @@
+/// Partial clones and sparse checkouts.
+///
+/// In a partial clone (`git clone --filter=blob:none`) old blobs live on the
+/// promisor remote, and git fetches each one on first use. `git blame` over a
+/// long history then makes one round trip per blob. Before blaming, this
+/// module lists the blobs missing from a file's history without fetching them
+/// (`rev-list --missing=print`) and fetches them all at once, the way git
+/// itself batches promisor fetches. When that fetch fails (offline, or a
+/// remote that refuses object wants), the user is told how many blobs are
+/// still missing and git is kept from fetching them one by one.
+///
+/// Files outside a sparse checkout are tracked but absent from the working
+/// tree; [`read_tracked`] falls back to their content at HEAD.
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::{Mutex, OnceLock};
+
+/// The promisor remote when the repository is a partial clone.
+pub fn promisor_remote() -> Option<String> {
+    static REMOTE: OnceLock<Option<String>> = OnceLock::new();
+    REMOTE
+        .get_or_init(|| {
+            let out = git(&["config", "--get-regexp", r"^remote\..*\.promisor$"])?;
+            out.lines().find_map(|line| {
+                let (key, value) = line.split_once(' ')?;
+                (value.trim() == "true")
+                    .then(|| key.strip_prefix("remote.")?.strip_suffix(".promisor"))
+                    .flatten()
+                    .map(String::from)
+            })
+        })
+        .clone()
+}
+
+/// Blobs reachable from `rev` through `paths` that are not in the local
+/// object store. Never triggers a lazy fetch.
+pub fn missing_blobs(rev: &str, paths: &[String]) -> Vec<String> {
+    if paths.is_empty() {
+        return Vec::new();
+    }
+    let mut args = vec!["rev-list", "--objects", "--missing=print", rev, "--"];
+    args.extend(paths.iter().map(String::as_str));
+    git(&args)
+        .map(|out| parse_missing(&out))
+        .unwrap_or_default()
+}
+
+fn parse_missing(rev_list: &str) -> Vec<String> {
+    rev_list
+        .lines()
+        .filter_map(|l| l.strip_prefix('?'))
+        .map(|oid| oid.trim().to_string())
+        .filter(|oid| !oid.is_empty())
+        .collect()
+}
+
+/// Fetch `objects` from `remote` in a single request.
+pub fn fetch_objects(remote: &str, objects: &[String]) -> Result<(), String> {
+    let mut child = Command::new("git")
+        .args([
+            "-c",
+            "fetch.negotiationAlgorithm=noop",
+            "fetch",
+            remote,
+            "--no-tags",
+            "--no-write-fetch-head",
+            "--recurse-submodules=no",
+            "--filter=blob:none",
+            "--stdin",
+        ])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Cannot run git fetch: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all((objects.join("\n") + "\n").as_bytes());
+    }
+    let out = child
+        .wait_with_output()
+        .map_err(|e| format!("git fetch failed: {}", e))?;
+    if out.status.success() {
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&out.stderr).trim().to_string())
+    }
+}
+
+/// Make the history blobs of `paths` (as of HEAD) local with one batched
+/// fetch. A no-op outside partial clones and for paths already prefetched in
+/// this process. Returns how many blobs are still missing afterwards; a
+/// warning has been printed for them.
+pub fn prefetch_history(paths: &[String]) -> usize {
+    static DONE: Mutex<Option<HashSet<String>>> = Mutex::new(None);
+    let Some(remote) = promisor_remote() else {
+        return 0;
+    };
+    let todo: Vec<String> = {
+        let mut done = DONE.lock().unwrap_or_else(|e| e.into_inner());
+        let done = done.get_or_insert_with(HashSet::new);
+        paths
+            .iter()
+            .filter(|p| done.insert(p.to_string()))
+            .cloned()
+            .collect()
+    };
+    let missing = missing_blobs("HEAD", &todo);
+    if missing.is_empty() {
+        return 0;
+    }
+    tracing::info!(
+        blobs = missing.len(),
+        remote = remote.as_str(),
+        "prefetching blobs for partial clone"
+    );
+    match fetch_objects(&remote, &missing) {
+        Ok(()) => {
+            let still = missing_blobs("HEAD", &todo).len();
+            if still > 0 {
+                warn_unavailable(still, &remote, "");
+            }
+            still
+        }
+        Err(e) => {
+            warn_unavailable(missing.len(), &remote, &e);
+            missing.len()
+        }
+    }
+}
+
+fn warn_unavailable(count: usize, remote: &str, error: &str) {
+    eprintln!(
+        "[BlamePrompt] Warning: {} blob(s) of this history are not available locally and \
+         could not be fetched from '{}' (partial clone){}. Attribution that needs them is \
+         skipped or incomplete.",
+        count,
+        remote,
+        if error.is_empty() {
+            String::new()
+        } else {
+            let line = error
+                .lines()
+                .find_map(|l| l.strip_prefix("fatal: "))
+                .or_else(|| error.lines().next())
+                .unwrap_or(error);
+            format!(": {}", line.trim_end_matches('.'))
+        }
+    );
+}
+
+/// Content of a tracked file: from the working tree, or from HEAD when the
+/// file is outside the sparse checkout. The flag says HEAD was used, so
+/// callers blame `HEAD` rather than the (absent) working copy.
+pub fn read_tracked(file: &str) -> Option<(String, bool)> {
+    if let Ok(content) = std::fs::read_to_string(file) {
+        return Some((content, false));
+    }
+    if Path::new(file).exists() {
+        return None;
+    }
+    let spec = format!("HEAD:./{}", file);
+    git_raw(&["show", &spec]).map(|content| (content, true))
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    git_raw(args).map(|s| s.trim().to_string())
+}
+
+fn git_raw(args: &[&str]) -> Option<String> {
+    Command::new("git")
+        .args(args)
+        .env("GIT_NO_LAZY_FETCH", "1")
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| String::from_utf8_lossy(&o.stdout).to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_missing_objects() {
+        let out = "4b5cc18219f2aa5e160062bd4d0bb3787b72e193\n\
+                   e438d42347ba5417f8eb64b36ea053fa5c40d12e a.txt\n\
+                   ?8c1384d825dbbe41309b7dc18ee7991a9085c46e\n\
+                   ?29ef827e8a45b1039d908884aae4490157bcb2b4\n";
+        assert_eq!(
+            parse_missing(out),
+            vec![
+                "8c1384d825dbbe41309b7dc18ee7991a9085c46e",
+                "29ef827e8a45b1039d908884aae4490157bcb2b4"
+            ]
+        );
+        assert!(parse_missing("").is_empty());
+    }
+}
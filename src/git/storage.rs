@@ -0,0 +1,538 @@
+/// Receipt storage backends.
+///
+/// Every read and write of per-commit receipt JSON goes through [`ReceiptStore`],
+/// selected by `[storage] backend` in `.blamepromptrc`:
+///
+/// - `notes` (default): `refs/notes/blameprompt`
+/// - `branch`: one `<sha>.json` file per commit on an orphan branch
+/// - `sidecar`: one `<sha>.json` file per commit in a tracked directory, staged
+///   so it is committed with the next commit
+use crate::core::config::{self, StorageConfig};
+use crate::git::blob_reader;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+pub const NOTES_REF: &str = "refs/notes/blameprompt";
+
+pub trait ReceiptStore {
+    /// Raw receipt JSON stored for `commit` (any revision git can resolve).
+    fn read(&self, commit: &str) -> Option<String>;
+    /// Replace the receipt JSON stored for `commit`.
+    fn write(&self, commit: &str, json: &str) -> Result<(), String>;
+    /// Store `json` for `new` and drop the receipts of `old`, which was
+    /// rewritten into `new`. Backends that key files by SHA keep the old file;
+    /// nothing reaches it once the commit is gone.
+    fn move_to(&self, _old: &str, new: &str, json: &str) -> Result<(), String> {
+        self.write(new, json)
+    }
+    /// Replace the receipt JSON for `commit` only if the store is still at
+    /// `version` (as returned by [`ReceiptStore::version`]). `Ok(false)` when
+    /// something else wrote meanwhile. Backends without an atomic check just
+    /// write.
+    fn write_if_unchanged(
+        &self,
+        commit: &str,
+        json: &str,
+        _version: Option<&str>,
+    ) -> Result<bool, String> {
+        self.write(commit, json).map(|_| true)
+    }
+    /// Full SHAs of all commits that have receipts.
+    fn list(&self) -> Vec<String>;
+    /// Ref to push/fetch so receipts reach the remote, or None when they
+    /// travel with ordinary commits.
+    fn sync_ref(&self) -> Option<String>;
+    /// Token that changes whenever stored receipts change (for cache invalidation).
+    fn version(&self) -> Option<String>;
+    fn name(&self) -> &'static str;
+}
+
+/// The backend configured for the current repository.
+pub fn active() -> Box<dyn ReceiptStore> {
+    from_config(&config::load_config().storage)
+}
+
+pub fn from_config(cfg: &StorageConfig) -> Box<dyn ReceiptStore> {
+    match cfg.backend.as_str() {
+        "branch" => Box::new(BranchStore {
+            branch: cfg.branch.clone(),
+            cwd: PathBuf::from("."),
+        }),
+        "sidecar" => Box::new(SidecarStore {
+            dir: cfg.sidecar_dir.clone(),
+            cwd: PathBuf::from("."),
+        }),
+        "notes" => Box::new(NotesStore),
+        other => {
+            eprintln!(
+                "[BlamePrompt] Warning: unknown storage backend '{}', using notes",
+                other
+            );
+            Box::new(NotesStore)
+        }
+    }
+}
+
+pub struct NotesStore;
+
+impl ReceiptStore for NotesStore {
+    fn read(&self, commit: &str) -> Option<String> {
+        // Full SHAs are looked up in the notes tree by the shared batch reader;
+        // anything else needs `git notes show` to resolve it.
+        let is_full_sha =
+            matches!(commit.len(), 40 | 64) && commit.bytes().all(|b| b.is_ascii_hexdigit());
+        if is_full_sha {
+            return blob_reader::read_note(NOTES_REF, &commit.to_ascii_lowercase());
+        }
+        git_stdout(
+            Path::new("."),
+            &["notes", "--ref", NOTES_REF, "show", commit],
+        )
+    }
+
+    fn write(&self, commit: &str, json: &str) -> Result<(), String> {
+        git_with_stdin(
+            Path::new("."),
+            &["notes", "--ref", NOTES_REF, "add", "-f", "-F", "-", commit],
+            json,
+        )
+        .map(|_| ())
+        .map_err(|e| format!("git notes add failed: {}", e))
+    }
+
+    /// Both changes land in one update of the notes ref: they are made on a
+    /// scratch ref, which then replaces the notes ref only if nobody else
+    /// changed it meanwhile.
+    fn move_to(&self, old: &str, new: &str, json: &str) -> Result<(), String> {
+        let here = Path::new(".");
+        let scratch = format!("{}-move-{}", NOTES_REF, std::process::id());
+        let base = git_stdout(here, &["rev-parse", "--verify", "--quiet", NOTES_REF]);
+        match &base {
+            Some(b) => git_with_stdin(here, &["update-ref", &scratch, b], "")?,
+            None => git_with_stdin(here, &["update-ref", "-d", &scratch], "")?,
+        };
+        let result = git_with_stdin(
+            here,
+            &["notes", "--ref", &scratch, "add", "-f", "-F", "-", new],
+            json,
+        )
+        .and_then(|_| {
+            git_with_stdin(
+                here,
+                &[
+                    "notes",
+                    "--ref",
+                    &scratch,
+                    "remove",
+                    "--ignore-missing",
+                    old,
+                ],
+                "",
+            )
+        })
+        .and_then(|_| git_with_stdin(here, &["rev-parse", &scratch], ""))
+        .and_then(|tip| {
+            git_with_stdin(
+                here,
+                &["update-ref", NOTES_REF, &tip, base.as_deref().unwrap_or("")],
+                "",
+            )
+        });
+        let _ = git_with_stdin(here, &["update-ref", "-d", &scratch], "");
+        result
+            .map(|_| ())
+            .map_err(|e| format!("Cannot move receipts to {}: {}", new, e))
+    }
+
+    /// The note is added on a scratch ref copied from `version`, which then
+    /// replaces the notes ref only if it is still at `version`.
+    fn write_if_unchanged(
+        &self,
+        commit: &str,
+        json: &str,
+        version: Option<&str>,
+    ) -> Result<bool, String> {
+        let here = Path::new(".");
+        let scratch = format!("{}-write-{}", NOTES_REF, std::process::id());
+        match version {
+            Some(v) => git_with_stdin(here, &["update-ref", &scratch, v], "")?,
+            None => git_with_stdin(here, &["update-ref", "-d", &scratch], "")?,
+        };
+        let result = git_with_stdin(
+            here,
+            &["notes", "--ref", &scratch, "add", "-f", "-F", "-", commit],
+            json,
+        )
+        .and_then(|_| git_with_stdin(here, &["rev-parse", &scratch], ""))
+        .and_then(|tip| {
+            git_with_stdin(
+                here,
+                &["update-ref", NOTES_REF, &tip, version.unwrap_or("")],
+                "",
+            )
+        });
+        let _ = git_with_stdin(here, &["update-ref", "-d", &scratch], "");
+        match result {
+            Ok(_) => Ok(true),
+            Err(_) if self.version().as_deref() != version => Ok(false),
+            Err(e) => Err(format!("git notes add failed: {}", e)),
+        }
+    }
+
+    fn list(&self) -> Vec<String> {
+        // Format: <note-object-sha> <commit-sha>
+        git_stdout(Path::new("."), &["notes", "--ref", NOTES_REF, "list"])
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|line| line.split_whitespace().nth(1).map(String::from))
+            .collect()
+    }
+
+    fn sync_ref(&self) -> Option<String> {
+        Some(NOTES_REF.to_string())
+    }
+
+    fn version(&self) -> Option<String> {
+        git_stdout(
+            Path::new("."),
+            &["rev-parse", "--verify", "--quiet", NOTES_REF],
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        "notes"
+    }
+}
+
+pub struct BranchStore {
+    pub branch: String,
+    /// Directory git runs in (the repository, or anywhere inside it).
+    pub cwd: PathBuf,
+}
+
+impl BranchStore {
+    fn branch_ref(&self) -> String {
+        format!("refs/heads/{}", self.branch)
+    }
+
+    /// Commit `json` as `<sha>.json` on top of `parent`, moving the branch
+    /// only if it still points at `parent`.
+    fn commit_file(&self, commit: &str, json: &str, parent: Option<&str>) -> Result<(), String> {
+        let sha = resolve_commit(&self.cwd, commit)
+            .ok_or_else(|| format!("unknown commit {}", commit))?;
+        let branch_ref = self.branch_ref();
+        let parent = parent.map(String::from);
+
+        let blob = git_with_stdin(&self.cwd, &["hash-object", "-w", "--stdin"], json)?;
+        let file_name = format!("{}.json", sha);
+        let mut entries: Vec<String> = match &parent {
+            Some(p) => git_stdout(&self.cwd, &["ls-tree", p])
+                .unwrap_or_default()
+                .lines()
+                .filter(|l| !l.ends_with(&format!("\t{}", file_name)))
+                .map(String::from)
+                .collect(),
+            None => Vec::new(),
+        };
+        entries.push(format!("100644 blob {}\t{}", blob, file_name));
+        let tree = git_with_stdin(&self.cwd, &["mktree"], &(entries.join("\n") + "\n"))?;
+
+        let message = format!("blameprompt: receipts for {}", &sha[..sha.len().min(8)]);
+        let mut args = vec!["commit-tree", tree.as_str(), "-m", message.as_str()];
+        if let Some(p) = &parent {
+            args.extend(["-p", p.as_str()]);
+        }
+        let new_commit = git_with_stdin(&self.cwd, &args, "")?;
+
+        // An empty old value means the branch must not exist yet.
+        let update = [
+            "update-ref",
+            branch_ref.as_str(),
+            new_commit.as_str(),
+            parent.as_deref().unwrap_or(""),
+        ];
+        git_with_stdin(&self.cwd, &update, "").map(|_| ())
+    }
+}
+
+impl ReceiptStore for BranchStore {
+    fn read(&self, commit: &str) -> Option<String> {
+        let sha = resolve_commit(&self.cwd, commit)?;
+        git_stdout(
+            &self.cwd,
+            &["show", &format!("{}:{}.json", self.branch_ref(), sha)],
+        )
+    }
+
+    fn write(&self, commit: &str, json: &str) -> Result<(), String> {
+        self.commit_file(commit, json, self.version().as_deref())
+    }
+
+    fn write_if_unchanged(
+        &self,
+        commit: &str,
+        json: &str,
+        version: Option<&str>,
+    ) -> Result<bool, String> {
+        match self.commit_file(commit, json, version) {
+            Ok(()) => Ok(true),
+            Err(_) if self.version().as_deref() != version => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+
+    fn list(&self) -> Vec<String> {
+        git_stdout(&self.cwd, &["ls-tree", "--name-only", &self.branch_ref()])
+            .unwrap_or_default()
+            .lines()
+            .filter_map(|name| name.strip_suffix(".json").map(String::from))
+            .collect()
+    }
+
+    fn sync_ref(&self) -> Option<String> {
+        Some(self.branch_ref())
+    }
+
+    fn version(&self) -> Option<String> {
+        git_stdout(
+            &self.cwd,
+            &["rev-parse", "--verify", "--quiet", &self.branch_ref()],
+        )
+    }
+
+    fn name(&self) -> &'static str {
+        "branch"
+    }
+}
+
+pub struct SidecarStore {
+    pub dir: String,
+    /// Directory git runs in (the repository, or anywhere inside it).
+    pub cwd: PathBuf,
+}
+
+impl SidecarStore {
+    fn dir_path(&self) -> Option<PathBuf> {
+        let root = git_stdout(&self.cwd, &["rev-parse", "--show-toplevel"])?;
+        Some(PathBuf::from(root).join(&self.dir))
+    }
+}
+
+impl ReceiptStore for SidecarStore {
+    fn read(&self, commit: &str) -> Option<String> {
+        let sha = resolve_commit(&self.cwd, commit)?;
+        std::fs::read_to_string(self.dir_path()?.join(format!("{}.json", sha))).ok()
+    }
+
+    fn write(&self, commit: &str, json: &str) -> Result<(), String> {
+        let sha = resolve_commit(&self.cwd, commit)
+            .ok_or_else(|| format!("unknown commit {}", commit))?;
+        let dir = self.dir_path().ok_or("not inside a git repository")?;
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Cannot create {}: {}", dir.display(), e))?;
+        let path = dir.join(format!("{}.json", sha));
+        std::fs::write(&path, json)
+            .map_err(|e| format!("Cannot write {}: {}", path.display(), e))?;
+        // `-f`: a configured `sidecar_dir` may be gitignored, and so is
+        // `.blameprompt/` in repos not yet migrated off the old ignore block.
+        let path_str = path.to_string_lossy().to_string();
+        git_with_stdin(&self.cwd, &["add", "-f", "--", &path_str], "").map(|_| ())
+    }
+
+    fn list(&self) -> Vec<String> {
+        let Some(dir) = self.dir_path() else {
+            return Vec::new();
+        };
+        std::fs::read_dir(dir)
+            .map(|entries| {
+                entries
+                    .flatten()
+                    .filter_map(|e| {
+                        e.file_name()
+                            .to_string_lossy()
+                            .strip_suffix(".json")
+                            .map(String::from)
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn sync_ref(&self) -> Option<String> {
+        None
+    }
+
+    fn version(&self) -> Option<String> {
+        // Hash of the file listing plus modification times.
+        use sha2::{Digest, Sha256};
+        let dir = self.dir_path()?;
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .ok()?
+            .flatten()
+            .map(|e| {
+                let mtime = e
+                    .metadata()
+                    .and_then(|m| m.modified())
+                    .ok()
+                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                    .map(|d| d.as_nanos())
+                    .unwrap_or_default();
+                format!("{}:{}", e.file_name().to_string_lossy(), mtime)
+            })
+            .collect();
+        names.sort();
+        Some(format!("{:x}", Sha256::digest(names.join("\n").as_bytes())))
+    }
+
+    fn name(&self) -> &'static str {
+        "sidecar"
+    }
+}
+
+fn resolve_commit(cwd: &Path, rev: &str) -> Option<String> {
+    git_stdout(
+        cwd,
+        &[
+            "rev-parse",
+            "--verify",
+            "--quiet",
+            &format!("{}^{{commit}}", rev),
+        ],
+    )
+    .map(|s| s.trim().to_string())
+}
+
+fn git_stdout(cwd: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .output()
+        .ok()?;
+    output.status.success().then(|| {
+        String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string()
+    })
+}
+
+/// Run git with `input` on stdin; returns trimmed stdout or stderr as the error.
+pub(crate) fn git_with_stdin(cwd: &Path, args: &[&str], input: &str) -> Result<String, String> {
+    let mut child = Command::new("git")
+        .args(args)
+        .current_dir(cwd)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to spawn git: {}", e))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input.as_bytes())
+            .map_err(|e| format!("Failed to write to stdin: {}", e))?;
+    }
+    let output = child
+        .wait_with_output()
+        .map_err(|e| format!("Failed to wait: {}", e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn git_in(dir: &std::path::Path, args: &[&str]) {
+        let ok = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .env("GIT_AUTHOR_NAME", "t")
+            .env("GIT_AUTHOR_EMAIL", "t@example.com")
+            .env("GIT_COMMITTER_NAME", "t")
+            .env("GIT_COMMITTER_EMAIL", "t@example.com")
+            .status()
+            .unwrap()
+            .success();
+        assert!(ok, "git {:?} failed", args);
+    }
+
+    #[test]
+    fn test_from_config_selects_backend() {
+        let mut cfg = StorageConfig::default();
+        assert_eq!(from_config(&cfg).name(), "notes");
+        cfg.backend = "branch".to_string();
+        let store = from_config(&cfg);
+        assert_eq!(store.name(), "branch");
+        assert_eq!(
+            store.sync_ref().as_deref(),
+            Some("refs/heads/blameprompt/receipts")
+        );
+        cfg.backend = "sidecar".to_string();
+        assert!(from_config(&cfg).sync_ref().is_none());
+    }
+
+    fn scratch_repo() -> tempfile::TempDir {
+        let tmp = tempfile::tempdir().unwrap();
+        git_in(tmp.path(), &["init", "-q"]);
+        git_in(tmp.path(), &["config", "user.name", "t"]);
+        git_in(tmp.path(), &["config", "user.email", "t@example.com"]);
+        git_in(tmp.path(), &["commit", "-q", "--allow-empty", "-m", "one"]);
+        git_in(tmp.path(), &["commit", "-q", "--allow-empty", "-m", "two"]);
+        tmp
+    }
+
+    #[test]
+    fn test_branch_store_roundtrip() {
+        let tmp = scratch_repo();
+        let store = BranchStore {
+            branch: "blameprompt/receipts".to_string(),
+            cwd: tmp.path().to_path_buf(),
+        };
+        assert!(store.list().is_empty());
+        store.write("HEAD", "{\"v\":1}").unwrap();
+        store.write("HEAD~1", "{\"v\":2}").unwrap();
+        store.write("HEAD", "{\"v\":3}").unwrap();
+
+        assert_eq!(store.list().len(), 2);
+        assert_eq!(store.read("HEAD").as_deref(), Some("{\"v\":3}"));
+        assert_eq!(store.read("HEAD~1").as_deref(), Some("{\"v\":2}"));
+        // Orphan branch: three receipt commits, no link to the main history.
+        let log = git_stdout(tmp.path(), &["rev-list", "refs/heads/blameprompt/receipts"]).unwrap();
+        assert_eq!(log.lines().count(), 3);
+
+        // A write based on a stale version is refused and changes nothing.
+        let stale = store.version();
+        store.write("HEAD~1", "{\"v\":4}").unwrap();
+        assert_eq!(
+            store.write_if_unchanged("HEAD", "{\"v\":5}", stale.as_deref()),
+            Ok(false)
+        );
+        assert_eq!(store.read("HEAD").as_deref(), Some("{\"v\":3}"));
+        let current = store.version();
+        assert_eq!(
+            store.write_if_unchanged("HEAD", "{\"v\":5}", current.as_deref()),
+            Ok(true)
+        );
+        assert_eq!(store.read("HEAD").as_deref(), Some("{\"v\":5}"));
+    }
+
+    #[test]
+    fn test_sidecar_store_roundtrip() {
+        let tmp = scratch_repo();
+        let store = SidecarStore {
+            dir: ".blameprompt/receipts".to_string(),
+            cwd: tmp.path().to_path_buf(),
+        };
+        store.write("HEAD", "{}").unwrap();
+        let head = resolve_commit(tmp.path(), "HEAD").unwrap();
+        assert_eq!(store.list(), vec![head.clone()]);
+        assert_eq!(store.read(&head).as_deref(), Some("{}"));
+        let staged = git_stdout(tmp.path(), &["diff", "--cached", "--name-only"]).unwrap();
+        assert_eq!(
+            staged.trim(),
+            format!(".blameprompt/receipts/{}.json", head)
+        );
+    }
+}
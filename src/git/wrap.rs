@@ -6,7 +6,6 @@ pub fn shim_path() -> Option<PathBuf> {
 }
 
 /// Check if the git shim is currently installed.
-#[allow(dead_code)]
 pub fn is_installed() -> bool {
     shim_path().is_some_and(|p| p.exists())
 }
@@ -142,31 +141,30 @@ fn inject_path_into_shell_rc() {
 /// Remove the BlamePrompt PATH export block from whichever shell RC file contains it.
 /// This is the inverse of `inject_path_into_shell_rc()`.
 pub fn remove_path_from_shell_rc() {
-    let home = match dirs::home_dir() {
-        Some(h) => h,
-        None => return,
+    let Some((rc_name, content)) = shell_rc_with_path() else {
+        return;
     };
+    let Some(home) = dirs::home_dir() else {
+        return;
+    };
+    // Remove the block (handle both \r\n and \n line endings)
+    let cleaned = remove_blameprompt_path_block(&content);
+    let _ = std::fs::write(home.join(rc_name), &cleaned);
+    println!("  \x1b[1;32m[done]\x1b[0m Removed BlamePrompt PATH from \x1b[2m~/{rc_name}\x1b[0m");
+}
 
-    for rc_name in &[".zshrc", ".bashrc", ".bash_profile", ".profile"] {
-        let rc_path = home.join(rc_name);
-        if !rc_path.exists() {
-            continue;
-        }
-        let content = match std::fs::read_to_string(&rc_path) {
-            Ok(c) => c,
-            Err(_) => continue,
-        };
-        if !content.contains(".blameprompt/bin") {
-            continue;
-        }
-        // Remove the block (handle both \r\n and \n line endings)
-        let cleaned = remove_blameprompt_path_block(&content);
-        let _ = std::fs::write(&rc_path, &cleaned);
-        println!(
-            "  \x1b[1;32m[done]\x1b[0m Removed BlamePrompt PATH from \x1b[2m~/{rc_name}\x1b[0m"
-        );
-        return; // Only one file will have it
-    }
+/// The shell RC file (name and contents) holding the BlamePrompt PATH entry.
+/// Only one file will have it.
+pub fn shell_rc_with_path() -> Option<(&'static str, String)> {
+    let home = dirs::home_dir()?;
+    [".zshrc", ".bashrc", ".bash_profile", ".profile"]
+        .into_iter()
+        .find_map(|rc_name| {
+            let content = std::fs::read_to_string(home.join(rc_name)).ok()?;
+            content
+                .contains(".blameprompt/bin")
+                .then_some((rc_name, content))
+        })
 }
 
 /// Strip the BlamePrompt PATH export lines from a shell RC file contents string.
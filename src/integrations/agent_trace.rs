@@ -4,83 +4,48 @@
 /// Spec: https://github.com/cursor/agent-trace
 ///
 /// Records are stored in `refs/notes/agent-trace` git notes, one per commit.
+/// Export validates the record before writing it; import parses strictly (see
+/// `agent_trace_schema`) and `--validate` only reports spec violations.
 use crate::core::{receipt::Receipt, util};
 use crate::git::notes::read_receipts_for_commit;
-use serde::{Deserialize, Serialize};
+use crate::integrations::agent_trace_schema::{
+    Contributor, Conversation, Range, Tool, TraceRecord, TracedFile, Vcs, VERSION,
+};
+use chrono::SecondsFormat;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::process::{Command, Stdio};
 
-/// Agent Trace v0.1.0 record (one per commit).
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct TraceRecord {
-    pub version: String,
-    pub id: String,
-    pub timestamp: String,
-    pub vcs: VcsInfo,
-    pub tool: ToolInfo,
-    pub files: Vec<TracedFile>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct VcsInfo {
-    #[serde(rename = "type")]
-    pub vcs_type: String,
-    pub revision: String,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct ToolInfo {
-    pub name: String,
-    pub version: String,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct TracedFile {
-    pub path: String,
-    pub conversations: Vec<FileConversation>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct FileConversation {
-    pub contributor: String, // "ai", "human", "mixed"
-    pub model_id: String,    // e.g. "anthropic/claude-sonnet-4-6"
-    #[serde(skip_serializing_if = "Vec::is_empty", default)]
-    pub ranges: Vec<LineRange>,
-}
-
-#[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct LineRange {
-    pub start_line: u32,
-    pub end_line: u32,
-    #[serde(skip_serializing_if = "Option::is_none")]
-    pub content_hash: Option<String>,
-}
-
-/// Convert blameprompt receipts to an Agent Trace record.
+/// Convert blameprompt receipts to an Agent Trace record. The output depends
+/// only on the receipts and the commit, so exporting again writes the same note.
 pub fn to_agent_trace(receipts: &[Receipt], commit_sha: &str) -> TraceRecord {
     let timestamp = receipts
         .first()
-        .map(|r| r.timestamp.to_rfc3339())
-        .unwrap_or_else(|| chrono::Utc::now().to_rfc3339());
-
-    let id = uuid::Uuid::new_v4().to_string();
+        .map(|r| r.timestamp)
+        .unwrap_or_else(chrono::Utc::now)
+        .to_rfc3339_opts(SecondsFormat::Secs, true);
 
     // Group files across all receipts
-    let mut file_map: std::collections::HashMap<String, Vec<FileConversation>> =
-        std::collections::HashMap::new();
+    let mut file_map: BTreeMap<String, Vec<Conversation>> = BTreeMap::new();
 
     for receipt in receipts {
         for fc in receipt.all_file_changes() {
             // Convert provider/model to models.dev format: "provider/model-name"
             let model_id = normalize_model_id(&receipt.provider, &receipt.model);
-            let range = LineRange {
+            let range = Range {
                 start_line: fc.line_range.0.max(1),
-                end_line: fc.line_range.1.max(1),
+                end_line: fc.line_range.1.max(fc.line_range.0).max(1),
                 content_hash: fc.blob_hash.clone(),
+                contributor: None,
             };
-            let conv = FileConversation {
-                contributor: "ai".to_string(),
-                model_id,
+            let conv = Conversation {
+                url: None,
+                contributor: Contributor {
+                    contributor_type: "ai".to_string(),
+                    model_id: Some(model_id),
+                },
                 ranges: vec![range],
+                related: Vec::new(),
             };
             file_map.entry(fc.path.clone()).or_default().push(conv);
         }
@@ -95,21 +60,32 @@ pub fn to_agent_trace(receipts: &[Receipt], commit_sha: &str) -> TraceRecord {
         .collect();
 
     TraceRecord {
-        version: "0.1.0".to_string(),
-        id,
+        version: VERSION.to_string(),
+        id: record_id(commit_sha),
         timestamp,
-        vcs: VcsInfo {
+        vcs: Some(Vcs {
             vcs_type: "git".to_string(),
             revision: commit_sha.to_string(),
-        },
-        tool: ToolInfo {
+        }),
+        tool: Some(Tool {
             name: "blameprompt".to_string(),
-            version: env!("CARGO_PKG_VERSION").to_string(),
-        },
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        }),
         files,
+        metadata: None,
     }
 }
 
+/// A UUID derived from the commit, stable across exports.
+fn record_id(commit_sha: &str) -> String {
+    let digest = Sha256::digest(format!("blameprompt:agent-trace:{}", commit_sha).as_bytes());
+    let mut bytes = [0u8; 16];
+    bytes.copy_from_slice(&digest[..16]);
+    uuid::Builder::from_custom_bytes(bytes)
+        .into_uuid()
+        .to_string()
+}
+
 /// Convert provider + model name to models.dev format "provider/model-name".
 fn normalize_model_id(provider: &str, model: &str) -> String {
     // If model already contains a slash (already namespaced), return as-is
@@ -133,8 +109,11 @@ fn normalize_model_id(provider: &str, model: &str) -> String {
 pub fn write_to_git_notes(sha: &str, record: &TraceRecord) -> Result<(), String> {
     use std::io::Write;
 
-    let json =
-        serde_json::to_string_pretty(record).map_err(|e| format!("Serialize error: {}", e))?;
+    let errors = record.validate();
+    if !errors.is_empty() {
+        return Err(format!("invalid record: {}", errors.join("; ")));
+    }
+    let json = record.to_json();
 
     let mut child = Command::new("git")
         .args([
@@ -166,8 +145,8 @@ pub fn write_to_git_notes(sha: &str, record: &TraceRecord) -> Result<(), String>
     Ok(())
 }
 
-/// Read a TraceRecord from `refs/notes/agent-trace` for a given commit SHA.
-pub fn read_from_git_notes(sha: &str) -> Option<TraceRecord> {
+/// The raw `refs/notes/agent-trace` note for a given commit SHA.
+pub fn read_from_git_notes(sha: &str) -> Option<String> {
     let output = Command::new("git")
         .args(["notes", "--ref", "refs/notes/agent-trace", "show", sha])
         .output()
@@ -177,8 +156,7 @@ pub fn read_from_git_notes(sha: &str) -> Option<TraceRecord> {
         return None;
     }
 
-    let content = String::from_utf8_lossy(&output.stdout);
-    serde_json::from_str(&content).ok()
+    Some(String::from_utf8_lossy(&output.stdout).to_string())
 }
 
 /// Export: convert blameprompt notes for a commit to agent-trace format and write.
@@ -209,40 +187,76 @@ pub fn run_export(commit_ref: Option<&str>) {
     }
 }
 
-/// Import: read agent-trace note for a commit and display it.
-pub fn run_import(commit_ref: Option<&str>) {
-    let sha = resolve_sha(commit_ref);
+/// Import: read the agent-trace note for a commit (or a record from `file`),
+/// then display it or, with `validate`, only check it against the spec.
+pub fn run_import(
+    commit_ref: Option<&str>,
+    file: Option<&str>,
+    validate: bool,
+) -> Result<(), String> {
+    let (source, json) = match file {
+        Some(path) => (
+            path.to_string(),
+            std::fs::read_to_string(path).map_err(|e| format!("Cannot read {}: {}", path, e))?,
+        ),
+        None => {
+            let sha = resolve_sha(commit_ref);
+            let short = util::short_sha(&sha).to_string();
+            let json = read_from_git_notes(&sha)
+                .ok_or_else(|| format!("No agent-trace note found for {}", short))?;
+            (format!("commit {}", short), json)
+        }
+    };
+    let record = TraceRecord::parse(&json).map_err(|e| format!("{}: {}", source, e))?;
+    let errors = record.validate();
 
-    match read_from_git_notes(&sha) {
-        Some(record) => {
-            println!(
-                "Agent Trace v{} — commit {}",
-                record.version,
-                util::short_sha(&sha)
-            );
-            println!("Tool: {}/{}", record.tool.name, record.tool.version);
-            println!("Timestamp: {}", record.timestamp);
-            println!();
-            for f in &record.files {
-                println!("  {}", f.path);
-                for conv in &f.conversations {
-                    println!(
-                        "    contributor={} model={}",
-                        conv.contributor, conv.model_id
-                    );
-                    for r in &conv.ranges {
-                        println!("      lines {}-{}", r.start_line, r.end_line);
-                    }
-                }
+    if validate {
+        if !errors.is_empty() {
+            for e in &errors {
+                eprintln!("  {}", e);
             }
+            return Err(format!(
+                "{}: {} spec violation(s) in Agent Trace record",
+                source,
+                errors.len()
+            ));
         }
-        None => {
-            eprintln!(
-                "[agent-trace] No agent-trace note found for {}",
-                util::short_sha(&sha)
+        println!(
+            "{}: valid Agent Trace v{} record ({} file(s))",
+            source,
+            record.version,
+            record.files.len()
+        );
+        return Ok(());
+    }
+
+    println!("Agent Trace v{} — {}", record.version, source);
+    if let Some(tool) = &record.tool {
+        println!(
+            "Tool: {}/{}",
+            tool.name,
+            tool.version.as_deref().unwrap_or("?")
+        );
+    }
+    println!("Timestamp: {}", record.timestamp);
+    println!();
+    for f in &record.files {
+        println!("  {}", f.path);
+        for conv in &f.conversations {
+            println!(
+                "    contributor={} model={}",
+                conv.contributor.contributor_type,
+                conv.contributor.model_id.as_deref().unwrap_or("-")
             );
+            for r in &conv.ranges {
+                println!("      lines {}-{}", r.start_line, r.end_line);
+            }
         }
     }
+    for e in &errors {
+        eprintln!("[agent-trace] warning: {}", e);
+    }
+    Ok(())
 }
 
 fn resolve_sha(commit_ref: Option<&str>) -> String {
@@ -260,6 +274,7 @@ fn resolve_sha(commit_ref: Option<&str>) -> String {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::core::receipt::FileChange;
 
     #[test]
     fn test_normalize_model_id() {
@@ -293,8 +308,38 @@ mod tests {
     fn test_to_agent_trace_empty() {
         let record = to_agent_trace(&[], "abc123");
         assert_eq!(record.version, "0.1.0");
-        assert_eq!(record.vcs.vcs_type, "git");
-        assert_eq!(record.vcs.revision, "abc123");
+        let vcs = record.vcs.unwrap();
+        assert_eq!(vcs.vcs_type, "git");
+        assert_eq!(vcs.revision, "abc123");
         assert!(record.files.is_empty());
     }
+
+    #[test]
+    fn test_export_import_export_round_trip() {
+        let receipts: Vec<Receipt> = ["r1", "r2"]
+            .iter()
+            .map(|id| {
+                Receipt::test(id)
+                    .with_model("claude-sonnet-4-6")
+                    .with_timestamp("2026-03-01T10:00:00.123Z")
+                    .with_change(FileChange {
+                        blob_hash: Some("abc".to_string()),
+                        ..FileChange::test("src/z.rs", (4, 9), 0, 0)
+                    })
+                    .with_file("src/a.rs", (0, 0), 0, 0)
+            })
+            .collect();
+        let record = to_agent_trace(&receipts, "deadbeef");
+        assert_eq!(record.validate(), Vec::<String>::new());
+        assert_eq!(record.timestamp, "2026-03-01T10:00:00Z");
+        assert_eq!(record.files[0].path, "src/a.rs");
+        assert_eq!(record.files[1].conversations.len(), 2);
+
+        let exported = record.to_json();
+        let imported = TraceRecord::parse(&exported).unwrap();
+        assert_eq!(imported.to_json(), exported);
+        // Exporting the same commit again gives the same record.
+        assert_eq!(to_agent_trace(&receipts, "deadbeef").to_json(), exported);
+        assert_ne!(to_agent_trace(&receipts, "cafe").id, record.id);
+    }
 }
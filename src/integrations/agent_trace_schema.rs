@@ -0,0 +1,274 @@
+/// Typed Agent Trace v0.1.0 records, parsed strictly and validated.
+///
+/// Parsing rejects unknown fields: vendor data belongs in `metadata`, which
+/// is kept as-is. `validate` checks what serde can't: the version, UUID and
+/// RFC 3339 formats, the enumerated `type` values and line ranges.
+/// Serialization is canonical (fixed field order, `metadata` keys sorted), so
+/// a record that has been written once comes back byte for byte from a
+/// parse/serialize round trip.
+use serde::{Deserialize, Serialize};
+
+pub const VERSION: &str = "0.1.0";
+const VCS_TYPES: &[&str] = &["git", "jj", "hg", "svn"];
+const CONTRIBUTOR_TYPES: &[&str] = &["human", "ai", "mixed", "unknown"];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TraceRecord {
+    pub version: String,
+    pub id: String,
+    pub timestamp: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vcs: Option<Vcs>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool: Option<Tool>,
+    pub files: Vec<TracedFile>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Map<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Vcs {
+    #[serde(rename = "type")]
+    pub vcs_type: String,
+    pub revision: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Tool {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TracedFile {
+    /// Relative to the repository root.
+    pub path: String,
+    pub conversations: Vec<Conversation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Conversation {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    pub contributor: Contributor,
+    pub ranges: Vec<Range>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub related: Vec<Related>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Contributor {
+    /// "human", "ai", "mixed" or "unknown".
+    #[serde(rename = "type")]
+    pub contributor_type: String,
+    /// models.dev style `provider/model`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub model_id: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Range {
+    pub start_line: u32,
+    pub end_line: u32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_hash: Option<String>,
+    /// Overrides the conversation's contributor for these lines.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub contributor: Option<Contributor>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Related {
+    #[serde(rename = "type")]
+    pub related_type: String,
+    pub url: String,
+}
+
+impl TraceRecord {
+    /// Parse a record, rejecting malformed JSON, missing fields and unknown
+    /// fields.
+    pub fn parse(json: &str) -> Result<Self, String> {
+        serde_json::from_str(json).map_err(|e| format!("Not an Agent Trace record: {}", e))
+    }
+
+    /// The canonical JSON form.
+    pub fn to_json(&self) -> String {
+        let mut json = serde_json::to_string_pretty(self).unwrap_or_default();
+        json.push('\n');
+        json
+    }
+
+    /// Spec violations, each prefixed with where it is (`files[0].conversations[1]`);
+    /// empty when the record is valid.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+        if self.version != VERSION {
+            errors.push(format!(
+                "version: '{}' is not supported (expected {})",
+                self.version, VERSION
+            ));
+        }
+        if uuid::Uuid::parse_str(&self.id).is_err() {
+            errors.push(format!("id: '{}' is not a UUID", self.id));
+        }
+        if chrono::DateTime::parse_from_rfc3339(&self.timestamp).is_err() {
+            errors.push(format!(
+                "timestamp: '{}' is not an RFC 3339 date-time",
+                self.timestamp
+            ));
+        }
+        if let Some(vcs) = &self.vcs {
+            check_one_of(&mut errors, "vcs.type", &vcs.vcs_type, VCS_TYPES);
+            if vcs.revision.trim().is_empty() {
+                errors.push("vcs.revision: must not be empty".to_string());
+            }
+        }
+        if let Some(tool) = &self.tool {
+            if tool.name.trim().is_empty() {
+                errors.push("tool.name: must not be empty".to_string());
+            }
+        }
+        let mut seen = std::collections::HashSet::new();
+        for (i, file) in self.files.iter().enumerate() {
+            let at = format!("files[{}]", i);
+            if file.path.trim().is_empty() {
+                errors.push(format!("{}.path: must not be empty", at));
+            } else if file.path.starts_with('/') {
+                errors.push(format!(
+                    "{}.path: '{}' must be relative to the repository root",
+                    at, file.path
+                ));
+            } else if !seen.insert(file.path.as_str()) {
+                errors.push(format!("{}.path: '{}' is listed twice", at, file.path));
+            }
+            for (j, conv) in file.conversations.iter().enumerate() {
+                let at = format!("{}.conversations[{}]", at, j);
+                check_contributor(
+                    &mut errors,
+                    &format!("{}.contributor", at),
+                    &conv.contributor,
+                );
+                if conv.ranges.is_empty() {
+                    errors.push(format!("{}.ranges: must list at least one range", at));
+                }
+                for (k, range) in conv.ranges.iter().enumerate() {
+                    let at = format!("{}.ranges[{}]", at, k);
+                    if range.start_line == 0 {
+                        errors.push(format!("{}.start_line: lines are 1-based", at));
+                    }
+                    if range.end_line < range.start_line {
+                        errors.push(format!(
+                            "{}: end_line {} is before start_line {}",
+                            at, range.end_line, range.start_line
+                        ));
+                    }
+                    if let Some(c) = &range.contributor {
+                        check_contributor(&mut errors, &format!("{}.contributor", at), c);
+                    }
+                }
+                for (k, related) in conv.related.iter().enumerate() {
+                    if related.related_type.trim().is_empty() || related.url.trim().is_empty() {
+                        errors.push(format!(
+                            "{}.related[{}]: type and url must not be empty",
+                            at, k
+                        ));
+                    }
+                }
+            }
+        }
+        errors
+    }
+}
+
+fn check_contributor(errors: &mut Vec<String>, at: &str, c: &Contributor) {
+    check_one_of(
+        errors,
+        &format!("{}.type", at),
+        &c.contributor_type,
+        CONTRIBUTOR_TYPES,
+    );
+    if c.model_id.as_deref().is_some_and(|m| m.trim().is_empty()) {
+        errors.push(format!("{}.model_id: must not be empty", at));
+    }
+}
+
+fn check_one_of(errors: &mut Vec<String>, at: &str, value: &str, allowed: &[&str]) {
+    if !allowed.contains(&value) {
+        errors.push(format!(
+            "{}: '{}' is not one of {}",
+            at,
+            value,
+            allowed.join(", ")
+        ));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FULL: &str = include_str!("fixtures/agent_trace/full.json");
+    const MINIMAL: &str = include_str!("fixtures/agent_trace/minimal.json");
+
+    #[test]
+    fn test_spec_fixtures_validate_and_round_trip() {
+        for fixture in [FULL, MINIMAL] {
+            let record = TraceRecord::parse(fixture).unwrap();
+            assert_eq!(record.validate(), Vec::<String>::new());
+            let json = record.to_json();
+            assert_eq!(TraceRecord::parse(&json).unwrap(), record);
+            assert_eq!(TraceRecord::parse(&json).unwrap().to_json(), json);
+        }
+        let full = TraceRecord::parse(FULL).unwrap();
+        let conv = &full.files[0].conversations[0];
+        assert_eq!(conv.contributor.contributor_type, "ai");
+        assert_eq!(conv.related[0].related_type, "session");
+        assert!(full.metadata.unwrap().contains_key("dev.cursor"));
+
+        // Strict parsing: unknown fields and the old flat contributor are rejected.
+        let unknown = FULL.replacen("\"version\"", "\"extra\": 1, \"version\"", 1);
+        assert!(TraceRecord::parse(&unknown).is_err());
+        let flat = r#"{"version": "0.1.0", "id": "550e8400-e29b-41d4-a716-446655440000",
+            "timestamp": "2026-01-23T14:30:00Z", "files": [{"path": "a.rs",
+            "conversations": [{"contributor": "ai", "model_id": "x/y", "ranges": []}]}]}"#;
+        assert!(TraceRecord::parse(flat).is_err());
+    }
+
+    #[test]
+    fn test_validate_reports_violations() {
+        let mut record = TraceRecord::parse(MINIMAL).unwrap();
+        record.version = "0.2.0".to_string();
+        record.id = "not-a-uuid".to_string();
+        record.timestamp = "yesterday".to_string();
+        record.vcs = Some(Vcs {
+            vcs_type: "cvs".to_string(),
+            revision: "abc".to_string(),
+        });
+        let mut dup = record.files[0].clone();
+        dup.conversations[0].ranges[0].start_line = 9;
+        dup.conversations[0].contributor.contributor_type = "robot".to_string();
+        record.files.push(dup);
+        let errors = record.validate();
+        assert_eq!(errors.len(), 7, "{:?}", errors);
+        assert!(errors[0].starts_with("version:"));
+        assert!(errors.contains(&"files[1].path: 'src/lib.rs' is listed twice".to_string()));
+        assert!(errors
+            .iter()
+            .any(|e| e.starts_with("files[1].conversations[0].contributor.type: 'robot'")));
+        assert!(
+            errors
+                .iter()
+                .any(|e| e
+                    == "files[1].conversations[0].ranges[0]: end_line 3 is before start_line 9")
+        );
+    }
+}
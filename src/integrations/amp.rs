@@ -264,6 +264,13 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
             blob_hash: None,
             additions: 0,
             deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
         })
         .collect();
 
@@ -359,6 +366,7 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
         total_deletions: 0,
         tools_used: session.tools_used,
         mcp_servers: vec![],
+        environment: None,
         agents_spawned: vec![],
         subagent_activities: vec![],
         concurrent_tool_calls: None,
@@ -373,10 +381,20 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
         accepted_lines: None,
         overridden_lines: None,
         prompt_quality,
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated: session.input_tokens.is_none() || session.output_tokens.is_none(),
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
     })
 }
 
-pub fn run_record_amp(session_path: Option<&str>) {
+pub fn run_record_amp(session_path: Option<&str>, force: bool) {
     let files = if let Some(path) = session_path {
         let p = PathBuf::from(path);
         if !p.exists() {
@@ -406,14 +424,19 @@ pub fn run_record_amp(session_path: Option<&str>) {
         }
     };
 
+    let mut dedup = staging::ImportDeduper::new(force);
+
     let mut count = 0usize;
     for file in &files {
         if let Some(receipt) = import_session(file) {
-            staging::upsert_receipt(&receipt);
-            count += 1;
+            if dedup.stage(&receipt) {
+                count += 1;
+            }
         }
     }
 
+    dedup.report("[amp]");
+
     if count == 0 {
         eprintln!("[amp] No valid sessions found in the provided file(s).");
     } else {
@@ -73,14 +73,32 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
             blob_hash: None,
             additions: 0,
             deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
         })
         .collect();
 
-    let cost = if let (Some(it), Some(ot)) = (session.input_tokens, session.output_tokens) {
-        crate::core::pricing::cost_from_usage(&session.model, it, ot, 0, 0)
-    } else {
-        0.0
+    // Sessions without usage fields get tokens estimated from the message text.
+    let estimate = |role: &str| -> u64 {
+        session
+            .messages
+            .iter()
+            .filter(|m| m.role == role)
+            .map(|m| crate::core::pricing::estimate_tokens(&m.text))
+            .sum()
     };
+    let cost_estimated = session.input_tokens.is_none() || session.output_tokens.is_none();
+    let input_tokens = session.input_tokens.unwrap_or_else(|| estimate("user"));
+    let output_tokens = session
+        .output_tokens
+        .unwrap_or_else(|| estimate("assistant"));
+    let cost =
+        crate::core::pricing::cost_from_usage(&session.model, input_tokens, output_tokens, 0, 0);
 
     let prompt_quality = Some(crate::core::prompt_eval::evaluate(&prompt_summary));
 
@@ -94,8 +112,8 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
         prompt_hash,
         message_count: session.messages.len() as u32,
         cost_usd: cost,
-        input_tokens: session.input_tokens,
-        output_tokens: session.output_tokens,
+        input_tokens: Some(input_tokens),
+        output_tokens: Some(output_tokens),
         cache_read_tokens: None,
         cache_creation_tokens: None,
         timestamp: session.timestamp,
@@ -119,6 +137,7 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
         total_deletions: 0,
         tools_used: vec![],
         mcp_servers: vec![],
+        environment: None,
         agents_spawned: vec![],
         subagent_activities: vec![],
         concurrent_tool_calls: None,
@@ -129,6 +148,16 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
         accepted_lines: None,
         overridden_lines: None,
         prompt_quality,
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated,
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
     })
 }
 
@@ -283,16 +312,20 @@ fn resolve_session_files(session_path: Option<&str>) -> Vec<PathBuf> {
 }
 
 /// Record Antigravity sessions from the default directory.
-pub fn run_record_antigravity(session_path: Option<&str>) {
+pub fn run_record_antigravity(session_path: Option<&str>, force: bool) {
     let files = resolve_session_files(session_path);
+    let mut dedup = crate::commands::staging::ImportDeduper::new(force);
     let mut count = 0;
     for path in files {
         if let Some(receipt) = import_session(&path) {
-            crate::commands::staging::upsert_receipt(&receipt);
-            count += 1;
+            if dedup.stage(&receipt) {
+                count += 1;
+            }
         }
     }
 
+    dedup.report("[antigravity]");
+
     if count > 0 {
         println!("[antigravity] Recorded {} Antigravity session(s)", count);
         println!("  Receipts staged. They will be attached on next git commit.");
@@ -300,3 +333,28 @@ pub fn run_record_antigravity(session_path: Option<&str>) {
         eprintln!("[antigravity] No valid Antigravity sessions found.");
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_estimates_missing_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("ag-session.json");
+        let doc = serde_json::json!({
+            "model": "gemini-2.5-pro",
+            "contents": [
+                {"role": "user", "parts": [{"text": "Add a retry loop to the uploader"}]},
+                {"role": "model", "parts": [{"text": "Wrapped the upload call in a retry loop."}]}
+            ]
+        });
+        std::fs::write(&path, doc.to_string()).unwrap();
+
+        let r = import_session(&path).unwrap();
+        assert_eq!(r.provider, "antigravity");
+        assert!(r.cost_estimated);
+        assert!(r.input_tokens.unwrap() > 0 && r.output_tokens.unwrap() > 0);
+        assert!(r.cost_usd > 0.0);
+    }
+}
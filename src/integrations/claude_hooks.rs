@@ -58,12 +58,15 @@ pub fn install() -> Result<(), String> {
 
     // All hook events and their matchers for comprehensive auditing
     let hook_configs: Vec<(&str, Option<&str>)> = vec![
-        ("PreToolUse", Some("Write|Edit|MultiEdit|Bash")),
+        ("PreToolUse", Some("Write|Edit|MultiEdit|NotebookEdit|Bash")),
         (
             "PostToolUse",
-            Some("Write|Edit|MultiEdit|Bash|Read|Glob|Grep|WebFetch|WebSearch|Task"),
+            Some("Write|Edit|MultiEdit|NotebookEdit|Bash|Read|Glob|Grep|WebFetch|WebSearch|Task"),
+        ),
+        (
+            "PostToolUseFailure",
+            Some("Write|Edit|MultiEdit|NotebookEdit|Bash"),
         ),
-        ("PostToolUseFailure", Some("Write|Edit|MultiEdit|Bash")),
         ("UserPromptSubmit", None),
         ("SessionStart", None),
         ("SessionEnd", None),
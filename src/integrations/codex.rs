@@ -27,6 +27,8 @@ pub struct CodexSession {
     pub end_timestamp: Option<DateTime<Utc>>,
     pub input_tokens: Option<u64>,
     pub output_tokens: Option<u64>,
+    /// Directory Codex ran in, from the `session_meta` entry.
+    pub cwd: Option<String>,
 }
 
 #[derive(Debug)]
@@ -36,7 +38,7 @@ pub struct CodexMessage {
 }
 
 /// Locate the Codex CLI home directory.
-fn codex_home() -> Option<PathBuf> {
+pub fn codex_home() -> Option<PathBuf> {
     // Respect CODEX_HOME env var (used by Codex CLI)
     if let Ok(home) = std::env::var("CODEX_HOME") {
         let p = PathBuf::from(home);
@@ -153,6 +155,7 @@ pub fn parse_codex_session(path: &Path) -> Option<CodexSession> {
     let mut last_ts: Option<DateTime<Utc>> = None;
     let mut input_tokens: u64 = 0;
     let mut output_tokens: u64 = 0;
+    let mut cwd: Option<String> = None;
 
     // Try JSONL format (one JSON object per line)
     for line in content.lines() {
@@ -182,6 +185,15 @@ pub fn parse_codex_session(path: &Path) -> Option<CodexSession> {
 
         // Handle Codex-specific entry types
         match entry_type {
+            // session_meta opens the file: session id, cwd, CLI version
+            "session_meta" => {
+                cwd = entry
+                    .get("payload")
+                    .unwrap_or(&entry)
+                    .get("cwd")
+                    .and_then(|v| v.as_str())
+                    .map(String::from);
+            }
             // turn_context contains model metadata
             "turn_context" => {
                 if model.is_empty() {
@@ -308,6 +320,7 @@ pub fn parse_codex_session(path: &Path) -> Option<CodexSession> {
         messages,
         files_modified,
         tools_used,
+        cwd,
         timestamp: first_ts.unwrap_or_else(Utc::now),
         end_timestamp: last_ts,
         input_tokens: if input_tokens > 0 {
@@ -460,7 +473,21 @@ args = ["{}", "checkpoint", "codex", "--hook-input", "stdin"]
 
 /// Import a specific Codex CLI transcript file.
 pub fn import_session(path: &Path) -> Option<Receipt> {
-    let session = parse_codex_session(path)?;
+    parse_codex_session(path).map(receipt_from_session)
+}
+
+/// Whether `session` ran inside `root` (a repository's top-level
+/// directory). False when the session doesn't record its directory.
+pub fn session_in(session: &CodexSession, root: &Path) -> bool {
+    let Some(cwd) = session.cwd.as_deref() else {
+        return false;
+    };
+    let canonical = |p: &Path| p.canonicalize().unwrap_or_else(|_| p.to_path_buf());
+    canonical(Path::new(cwd)).starts_with(canonical(root))
+}
+
+/// The receipt for a parsed Codex session.
+pub fn receipt_from_session(session: CodexSession) -> Receipt {
     let cfg = config::load_config();
     let cwd = std::env::current_dir()
         .map(|p| p.to_string_lossy().to_string())
@@ -502,6 +529,13 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
             blob_hash: None,
             additions: 0,
             deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
         })
         .collect();
 
@@ -565,7 +599,7 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
 
     let prompt_quality = Some(crate::core::prompt_eval::evaluate(&prompt_summary));
 
-    Some(Receipt {
+    Receipt {
         id: Receipt::new_id(),
         provider: "codex".to_string(),
         model: session.model,
@@ -600,6 +634,7 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
         total_deletions: 0,
         tools_used: session.tools_used,
         mcp_servers: vec![],
+        environment: None,
         agents_spawned: vec![],
         subagent_activities: vec![],
         concurrent_tool_calls: None,
@@ -614,11 +649,21 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
         accepted_lines: None,
         overridden_lines: None,
         prompt_quality,
-    })
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated: session.input_tokens.is_none() || session.output_tokens.is_none(),
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
+    }
 }
 
 /// Main entry point: scan Codex CLI sessions and create receipts.
-pub fn run_record_codex(session_path: Option<&str>) {
+pub fn run_record_codex(session_path: Option<&str>, force: bool) {
     let files = if let Some(path) = session_path {
         let p = PathBuf::from(path);
         if !p.exists() {
@@ -662,14 +707,19 @@ pub fn run_record_codex(session_path: Option<&str>) {
         all_files.into_iter().take(10).collect()
     };
 
+    let mut dedup = staging::ImportDeduper::new(force);
+
     let mut count = 0usize;
     for file in &files {
         if let Some(receipt) = import_session(file) {
-            staging::upsert_receipt(&receipt);
-            count += 1;
+            if dedup.stage(&receipt) {
+                count += 1;
+            }
         }
     }
 
+    dedup.report("[codex]");
+
     if count == 0 {
         eprintln!("[codex] No valid sessions found in the provided file(s).");
     } else {
@@ -738,4 +788,30 @@ mod tests {
         assert!(s.files_modified.contains(&"src/auth.rs".to_string()));
         assert!(s.tools_used.contains(&"write_file".to_string()));
     }
+
+    #[test]
+    fn test_session_meta_cwd_scopes_session_to_repo() {
+        let repo = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(repo.path().join("sub")).unwrap();
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let content = format!(
+            r#"{{"type":"session_meta","payload":{{"id":"s1","cwd":"{}"}}}}
+{{"type":"response_item","role":"user","content":"Fix the auth module"}}
+"#,
+            repo.path().join("sub").display()
+        );
+        std::fs::write(tmp.path(), content).unwrap();
+
+        let s = parse_codex_session(tmp.path()).unwrap();
+        assert_eq!(
+            s.cwd.as_deref(),
+            Some(repo.path().join("sub").to_string_lossy().as_ref())
+        );
+        assert!(session_in(&s, repo.path()));
+        let other = tempfile::tempdir().unwrap();
+        assert!(!session_in(&s, other.path()));
+
+        let s = CodexSession { cwd: None, ..s };
+        assert!(!session_in(&s, repo.path()));
+    }
 }
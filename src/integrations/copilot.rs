@@ -405,7 +405,7 @@ pub fn find_db_for_current_workspace() -> Option<PathBuf> {
 }
 
 /// Main entry point: scan VS Code workspace for Copilot Chat sessions and create receipts.
-pub fn run_record_copilot(workspace: Option<&str>) {
+pub fn run_record_copilot(workspace: Option<&str>, force: bool) {
     let db_path = if let Some(w) = workspace {
         let p = PathBuf::from(w);
         if p.extension().is_some_and(|e| e == "vscdb") {
@@ -444,6 +444,7 @@ pub fn run_record_copilot(workspace: Option<&str>) {
         .map(|p| p.to_string_lossy().to_string())
         .unwrap_or_default();
     let user = util::git_user();
+    let mut dedup = staging::ImportDeduper::new(force);
     let mut count = 0usize;
 
     let changed_files = get_recent_changed_files();
@@ -476,6 +477,13 @@ pub fn run_record_copilot(workspace: Option<&str>) {
                 blob_hash: None,
                 additions: 0,
                 deletions: 0,
+                notebook_cells: vec![],
+                is_binary: false,
+                bytes_before: None,
+                bytes_after: None,
+                change_type: None,
+                old_path: None,
+                unstaged_ranges: Vec::new(),
             })
             .collect();
 
@@ -580,6 +588,7 @@ pub fn run_record_copilot(workspace: Option<&str>) {
             total_deletions: 0,
             tools_used: vec![],
             mcp_servers: vec![],
+            environment: None,
             agents_spawned: vec![],
             subagent_activities: vec![],
             concurrent_tool_calls: None,
@@ -594,12 +603,25 @@ pub fn run_record_copilot(workspace: Option<&str>) {
             accepted_lines: None,
             overridden_lines: None,
             prompt_quality,
+            ticket: None,
+            manual_import: false,
+            housekeeping_prompts: None,
+            intent_summary: None,
+            heuristic: false,
+            cost_estimated: true,
+            acceptance_estimated: false,
+            co_authors: Vec::new(),
+            plan_summary: None,
+            tool_use_id: None,
         };
 
-        staging::upsert_receipt(&receipt);
-        count += 1;
+        if dedup.stage(&receipt) {
+            count += 1;
+        }
     }
 
+    dedup.report("[copilot]");
+
     println!(
         "[copilot] Recorded {} Copilot Chat session(s) from {}",
         count,
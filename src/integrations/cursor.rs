@@ -6,13 +6,15 @@
 /// Cursor stores chat history in:
 ///   macOS: ~/Library/Application Support/Cursor/User/workspaceStorage/<hash>/state.vscdb
 ///   Linux: ~/.config/Cursor/User/workspaceStorage/<hash>/state.vscdb
-use crate::commands::staging;
+use crate::commands::{acceptance_backfill, staging};
 use crate::core::{config, receipt::Receipt, util};
 use chrono::{DateTime, TimeZone, Utc};
 use rusqlite::Connection;
 use serde::Deserialize;
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::time::{Duration, SystemTime};
 
 /// A parsed Cursor AI chat tab.
 #[derive(Debug)]
@@ -372,42 +374,162 @@ fn parse_cursor_chat_json(json: &str) -> Option<Vec<CursorChatSession>> {
     }
 }
 
-/// Find the workspace storage directory for the current git repo.
-pub fn find_db_for_current_workspace() -> Option<PathBuf> {
-    // Get the workspace root (git root or cwd)
-    let workspace_path = Command::new("git")
+/// A Cursor workspace (one `workspaceStorage/<hash>` directory) whose folders
+/// include the current repo.
+#[derive(Debug)]
+pub struct CursorWorkspace {
+    pub db: PathBuf,
+    pub folders: Vec<PathBuf>,
+    /// The `.code-workspace` file of a multi-root workspace.
+    pub workspace_file: Option<PathBuf>,
+}
+
+/// Every Cursor workspace with a folder equal to, inside, or containing
+/// `repo_root`, most recently used first (parents after the rest).
+///
+/// Folders come from the storage dir's `workspace.json`: `folder` for a plain
+/// folder, `workspace` for a multi-root `.code-workspace` file whose `folders`
+/// are then read. Storage dirs without one fall back to searching the
+/// `workspaceFolders` entry of their database for the repo path.
+pub fn find_workspaces_for_repo(repo_root: &Path) -> Vec<CursorWorkspace> {
+    let root = normalize(repo_root);
+    let mut found = Vec::new();
+    for dir in find_workspace_storage_dirs() {
+        let db = dir.join("state.vscdb");
+        let (folders, workspace_file) = workspace_folders(&dir);
+        let matches = if folders.is_empty() && workspace_file.is_none() {
+            db_mentions_path(&db, &repo_root.to_string_lossy())
+        } else {
+            folders.iter().any(|f| folder_matches(&normalize(f), &root))
+        };
+        if matches {
+            found.push(CursorWorkspace {
+                db,
+                folders,
+                workspace_file,
+            });
+        }
+    }
+    // Workspaces opened on the repo itself before ones opened on a parent.
+    found.sort_by_key(|ws| !ws.folders.iter().any(|f| normalize(f).starts_with(&root)));
+    found
+}
+
+/// Folders of a workspace storage dir, read from its `workspace.json`.
+fn workspace_folders(storage_dir: &Path) -> (Vec<PathBuf>, Option<PathBuf>) {
+    let Some(meta) = std::fs::read_to_string(storage_dir.join("workspace.json"))
+        .ok()
+        .and_then(|s| serde_json::from_str::<serde_json::Value>(&s).ok())
+    else {
+        return (Vec::new(), None);
+    };
+    if let Some(folder) = meta["folder"].as_str().and_then(uri_to_path) {
+        return (vec![folder], None);
+    }
+    let Some(file) = meta["workspace"].as_str().and_then(uri_to_path) else {
+        return (Vec::new(), None);
+    };
+    let folders = std::fs::read_to_string(&file)
+        .map(|content| code_workspace_folders(&content, file.parent().unwrap_or(Path::new("/"))))
+        .unwrap_or_default();
+    (folders, Some(file))
+}
+
+/// `folders` of a `.code-workspace` file. Entries are `{"path": ...}`, relative
+/// to the file, or `{"uri": "file://..."}`. The format allows `//` comments and
+/// trailing commas, which are stripped before parsing.
+fn code_workspace_folders(content: &str, base: &Path) -> Vec<PathBuf> {
+    let cleaned: String = content
+        .lines()
+        .filter(|l| !l.trim_start().starts_with("//"))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let cleaned = regex::Regex::new(r",(\s*[\]}])")
+        .map(|re| re.replace_all(&cleaned, "$1").into_owned())
+        .unwrap_or(cleaned);
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(&cleaned) else {
+        return Vec::new();
+    };
+    value["folders"]
+        .as_array()
+        .map(|folders| {
+            folders
+                .iter()
+                .filter_map(|f| match (f["path"].as_str(), f["uri"].as_str()) {
+                    (Some(path), _) => Some(base.join(path)),
+                    (None, Some(uri)) => uri_to_path(uri),
+                    _ => None,
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Local path of a `file://` URI; remote URIs (SSH, containers) give `None`.
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let rest = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(rest.len());
+    let raw = rest.as_bytes();
+    let mut i = 0;
+    while i < raw.len() {
+        if raw[i] == b'%' && i + 2 < raw.len() {
+            let hex = std::str::from_utf8(&raw[i + 1..i + 3]).unwrap_or("");
+            if let Ok(b) = u8::from_str_radix(hex, 16) {
+                bytes.push(b);
+                i += 3;
+                continue;
+            }
+        }
+        bytes.push(raw[i]);
+        i += 1;
+    }
+    let path = String::from_utf8_lossy(&bytes).to_string();
+    // Windows: file:///c%3A/Users/... decodes to /c:/Users/...
+    let path = match path.as_bytes() {
+        [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => path[1..].to_string(),
+        _ => path,
+    };
+    Some(PathBuf::from(path))
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
+
+/// A folder maps to the repo when it is the repo, a directory inside it, or a
+/// parent it was opened from (a monorepo checkout).
+fn folder_matches(folder: &Path, root: &Path) -> bool {
+    folder.starts_with(root) || (root.starts_with(folder) && folder.parent().is_some())
+}
+
+fn db_mentions_path(db: &Path, path: &str) -> bool {
+    Connection::open_with_flags(db, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)
+        .and_then(|conn| {
+            conn.query_row(
+                "SELECT count(*) FROM ItemTable WHERE key = 'workspaceFolders' AND value LIKE ?1",
+                [format!("%{}%", path)],
+                |r| r.get::<_, i32>(0),
+            )
+        })
+        .unwrap_or(0)
+        > 0
+}
+
+fn repo_root() -> PathBuf {
+    Command::new("git")
         .args(["rev-parse", "--show-toplevel"])
         .output()
         .ok()
         .filter(|o| o.status.success())
         .and_then(|o| String::from_utf8(o.stdout).ok())
-        .map(|s| s.trim().to_string())
-        .unwrap_or_else(|| {
-            std::env::current_dir()
-                .map(|p| p.to_string_lossy().to_string())
-                .unwrap_or_default()
-        });
+        .map(|s| PathBuf::from(s.trim()))
+        .unwrap_or_else(|| std::env::current_dir().unwrap_or_default())
+}
 
-    // Cursor hashes the workspace path to create the storage dir name
-    // We check all dirs and look for one containing a .vscode/state.vscdb
-    // with a reference to our workspace path, or just use the most recently modified
-    let all = find_workspace_storage_dirs();
-    for dir in &all {
-        let db = dir.join("state.vscdb");
-        if let Ok(conn) = Connection::open(&db) {
-            // Check if this workspace matches our path
-            let found: bool = conn
-                .query_row(
-                    "SELECT count(*) FROM ItemTable WHERE key = 'workspaceFolders' AND value LIKE ?1",
-                    [format!("%{}%", workspace_path)],
-                    |r| r.get::<_, i32>(0),
-                )
-                .unwrap_or(0)
-                > 0;
-            if found {
-                return Some(db);
-            }
-        }
+/// Find the workspace storage directory for the current git repo.
+pub fn find_db_for_current_workspace() -> Option<PathBuf> {
+    if let Some(ws) = find_workspaces_for_repo(&repo_root()).into_iter().next() {
+        return Some(ws.db);
     }
 
     // Fallback: try globalStorage (contains all conversations across workspaces)
@@ -416,36 +538,109 @@ pub fn find_db_for_current_workspace() -> Option<PathBuf> {
     }
 
     // Last resort: most recently modified workspace storage
-    all.into_iter().next().map(|d| d.join("state.vscdb"))
+    find_workspace_storage_dirs()
+        .into_iter()
+        .next()
+        .map(|d| d.join("state.vscdb"))
 }
 
-/// Main entry point: scan Cursor workspace and create receipts.
-pub fn run_record_cursor(workspace: Option<&str>) {
-    let db_path = if let Some(w) = workspace {
-        // User specified a workspace storage dir or .vscdb path directly
-        let p = PathBuf::from(w);
-        if p.extension().is_some_and(|e| e == "vscdb") {
-            p
-        } else {
-            p.join("state.vscdb")
-        }
-    } else {
-        match find_db_for_current_workspace() {
-            Some(p) => p,
-            None => {
-                eprintln!("[cursor] Cannot find Cursor workspace storage.");
-                eprintln!("  Pass --workspace <path/to/state.vscdb> to specify the database.");
-                std::process::exit(1);
-            }
-        }
+/// Ask which of several matching workspaces to import. Without a terminal the
+/// most recently used one is taken.
+fn pick_workspaces(workspaces: Vec<CursorWorkspace>) -> Vec<CursorWorkspace> {
+    use std::io::{IsTerminal, Write};
+
+    println!(
+        "[cursor] {} Cursor workspaces include this repo:",
+        workspaces.len()
+    );
+    for (i, ws) in workspaces.iter().enumerate() {
+        let folders: Vec<String> = ws.folders.iter().map(|f| f.display().to_string()).collect();
+        let last_used = std::fs::metadata(&ws.db)
+            .and_then(|m| m.modified())
+            .map(|t| {
+                DateTime::<Utc>::from(t)
+                    .format("%Y-%m-%d %H:%M")
+                    .to_string()
+            })
+            .unwrap_or_else(|_| "unknown".to_string());
+        let kind = match &ws.workspace_file {
+            Some(f) => format!(
+                "multi-root: {}",
+                f.file_name().unwrap_or_default().to_string_lossy()
+            ),
+            None => "folder".to_string(),
+        };
+        println!(
+            "  {}. {}  ({}, last used {})",
+            i + 1,
+            if folders.is_empty() {
+                ws.db.display().to_string()
+            } else {
+                folders.join(", ")
+            },
+            kind,
+            last_used
+        );
+    }
+
+    if !std::io::stdin().is_terminal() {
+        println!("  Importing the most recent one; pass --all to import every workspace.");
+        return workspaces.into_iter().take(1).collect();
+    }
+    print!(
+        "Import which? [1-{}, comma-separated, a = all, default 1] ",
+        workspaces.len()
+    );
+    let _ = std::io::stdout().flush();
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer).unwrap_or(0);
+    let Some(picked) = parse_selection(&answer, workspaces.len()) else {
+        eprintln!("[cursor] Invalid selection: {}", answer.trim());
+        return Vec::new();
     };
+    workspaces
+        .into_iter()
+        .enumerate()
+        .filter(|(i, _)| picked.contains(i))
+        .map(|(_, ws)| ws)
+        .collect()
+}
 
-    if !db_path.exists() {
-        eprintln!("[cursor] Database not found: {}", db_path.display());
-        std::process::exit(1);
+/// Zero-based indices for a picker answer like `2`, `1,3` or `a`; empty means
+/// the first entry.
+fn parse_selection(answer: &str, count: usize) -> Option<Vec<usize>> {
+    let answer = answer.trim().to_lowercase();
+    if answer.is_empty() {
+        return Some(vec![0]);
+    }
+    if answer == "a" || answer == "all" {
+        return Some((0..count).collect());
+    }
+    answer
+        .split([',', ' '])
+        .filter(|s| !s.is_empty())
+        .map(|s| match s.parse::<usize>() {
+            Ok(n) if (1..=count).contains(&n) => Some(n - 1),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Main entry point: scan Cursor workspace and create receipts.
+///
+/// Without `--workspace`, every workspace storage dir that maps to this repo is
+/// found; with several, `all` imports them all, otherwise the user picks.
+pub fn run_record_cursor(workspace: Option<&str>, force: bool, all: bool) {
+    let db_paths = resolve_db_paths(workspace, all);
+    if db_paths.is_empty() {
+        return;
     }
 
-    let mut sessions = read_chat_sessions(&db_path);
+    let mut sessions = Vec::new();
+    for db_path in &db_paths {
+        sessions.extend(read_chat_sessions(db_path));
+    }
+    let source = describe(&db_paths);
 
     // Also try globalStorage for additional sessions not in workspace storage
     if sessions.is_empty() {
@@ -453,183 +648,381 @@ pub fn run_record_cursor(workspace: Option<&str>) {
     }
 
     if sessions.is_empty() {
-        eprintln!(
-            "[cursor] No AI chat sessions found in {}",
-            db_path.display()
-        );
+        eprintln!("[cursor] No AI chat sessions found in {}", source);
         eprintln!("  Make sure you have used Cursor's AI features in this workspace.");
         return;
     }
 
-    let cfg = config::load_config();
-    let cwd = std::env::current_dir()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default();
-
-    let user = util::git_user();
+    let ctx = ImportContext::load();
+    let mut dedup = staging::ImportDeduper::new(force);
     let mut count = 0usize;
 
-    // Find files that have been recently modified in git (possible AI-changed files)
-    let changed_files = get_recent_changed_files();
-
     for session in &sessions {
-        let first_user_msg = session
-            .messages
-            .iter()
-            .find(|m| m.role == "user")
-            .map(|m| {
-                m.text
-                    .chars()
-                    .take(cfg.capture.max_prompt_length)
-                    .collect::<String>()
-            })
-            .unwrap_or_else(|| session.title.clone());
+        let receipt = session_receipt(session, &ctx, (count as u32) + 1);
+        if dedup.stage(&receipt) {
+            count += 1;
+        }
+    }
 
-        let prompt_summary = crate::core::redact::redact_secrets_with_config(&first_user_msg, &cfg);
+    dedup.report("[cursor]");
 
-        use sha2::{Digest, Sha256};
-        let mut hasher = Sha256::new();
-        hasher.update(prompt_summary.as_bytes());
-        let prompt_hash = format!("sha256:{:x}", hasher.finalize());
+    println!(
+        "[cursor] Recorded {} Cursor AI session(s) from {}",
+        count, source
+    );
+    println!("  Receipts staged. They will be attached on next git commit.");
+}
 
-        let files_changed: Vec<crate::core::receipt::FileChange> = changed_files
-            .iter()
-            .map(|f| crate::core::receipt::FileChange {
-                path: util::make_relative(f, &cwd),
-                line_range: (1, 1),
-                blob_hash: None,
-                additions: 0,
-                deletions: 0,
-            })
-            .collect();
+/// `record-cursor --follow`: poll the workspace databases and stage new
+/// sessions, or refresh staged ones that gained messages, as the user chats.
+///
+/// Cursor writes through SQLite's WAL, so both `state.vscdb` and its `-wal`
+/// file are checked for changes; a poll that sees none reads nothing.
+/// Sessions already staged or attached are tracked from the start (unless
+/// `force`), so only activity after that is recorded again.
+pub fn run_follow_cursor(workspace: Option<&str>, force: bool, all: bool, interval_secs: u64) {
+    let db_paths = resolve_db_paths(workspace, all);
+    if db_paths.is_empty() {
+        return;
+    }
+    println!(
+        "[cursor] Following {} for new chat activity every {}s. Ctrl-C to stop.",
+        describe(&db_paths),
+        interval_secs
+    );
 
-        let prompt_quality = Some(crate::core::prompt_eval::evaluate(&prompt_summary));
+    let mut known = if force {
+        HashMap::new()
+    } else {
+        known_sessions()
+    };
+    let mut next_prompt = known.values().map(|(_, n)| *n).max().unwrap_or(0) + 1;
+    let mut stamps: Vec<Option<SystemTime>> = vec![None; db_paths.len()];
+    loop {
+        let current: Vec<Option<SystemTime>> = db_paths.iter().map(|p| db_stamp(p)).collect();
+        if current != stamps {
+            stamps = current;
+            let sessions: Vec<CursorChatSession> = db_paths
+                .iter()
+                .flat_map(|p| read_chat_sessions(p))
+                .collect();
+            let updates = follow_updates(&sessions, &mut known, &mut next_prompt);
+            if !updates.is_empty() {
+                let ctx = ImportContext::load();
+                for (session, prompt_number) in &updates {
+                    staging::upsert_receipt(&session_receipt(session, &ctx, *prompt_number));
+                }
+                println!(
+                    "[cursor] {} Staged {} new or updated session(s)",
+                    Utc::now().format("%H:%M:%S"),
+                    updates.len()
+                );
+            }
+        }
+        std::thread::sleep(Duration::from_secs(interval_secs.max(1)));
+    }
+}
 
-        let response_summary = session
-            .messages
-            .iter()
-            .rev()
-            .find(|m| m.role == "assistant")
-            .map(|m| m.text.chars().take(500).collect());
+/// Sessions that are new or gained messages since they were last seen, with
+/// the prompt number their receipt is staged under. Updates `known`.
+fn follow_updates<'a>(
+    sessions: &'a [CursorChatSession],
+    known: &mut HashMap<String, (usize, u32)>,
+    next_prompt: &mut u32,
+) -> Vec<(&'a CursorChatSession, u32)> {
+    let mut updates = Vec::new();
+    for session in sessions {
+        let messages = session.messages.len();
+        let number = match known.get(&session.session_id) {
+            Some((seen, _)) if *seen >= messages => continue,
+            Some((_, number)) => *number,
+            None => {
+                *next_prompt += 1;
+                *next_prompt - 1
+            }
+        };
+        known.insert(session.session_id.clone(), (messages, number));
+        updates.push((session, number));
+    }
+    updates
+}
 
-        // Build conversation turns for full context tracking
-        let conversation: Vec<crate::core::receipt::ConversationTurn> = session
-            .messages
-            .iter()
-            .enumerate()
-            .map(|(i, m)| crate::core::receipt::ConversationTurn {
-                turn: (i as u32) + 1,
-                role: m.role.clone(),
-                content: crate::core::redact::redact_secrets_with_config(
-                    &m.text
-                        .chars()
-                        .take(cfg.capture.max_prompt_length)
-                        .collect::<String>(),
-                    &cfg,
-                ),
-                tool_name: None,
-                files_touched: None,
-            })
-            .collect();
+/// Cursor sessions already staged or attached to a commit, as session ID to
+/// (message count, prompt number).
+fn known_sessions() -> HashMap<String, (usize, u32)> {
+    let mut known = HashMap::new();
+    let mut note = |r: &Receipt| {
+        if r.provider == "cursor" {
+            known.insert(
+                r.session_id.clone(),
+                (r.message_count as usize, r.prompt_number.unwrap_or(1)),
+            );
+        }
+    };
+    for sha in crate::git::notes::list_commits_with_notes() {
+        if let Some(payload) = crate::git::notes::read_receipts_for_commit(&sha) {
+            payload.receipts.iter().for_each(&mut note);
+        }
+    }
+    // Staged receipts last: they are the ones updates merge into.
+    staging::read_staging().receipts.iter().for_each(&mut note);
+    known
+}
 
-        // Estimate tokens from message content
-        let estimated_input = crate::core::pricing::estimate_tokens_from_chars(
-            session
-                .messages
-                .iter()
-                .filter(|m| m.role == "user")
-                .map(|m| m.text.len())
-                .sum(),
-        );
-        let estimated_output = crate::core::pricing::estimate_tokens_from_chars(
-            session
-                .messages
-                .iter()
-                .filter(|m| m.role == "assistant")
-                .map(|m| m.text.len())
-                .sum(),
-        );
-        let cost =
-            crate::core::pricing::estimate_cost(&session.model, estimated_input, estimated_output);
-
-        // Compute session duration from message timestamps
-        let session_duration_secs = {
-            let first_ts = session.messages.first().and_then(|m| m.timestamp);
-            let last_ts = session.messages.last().and_then(|m| m.timestamp);
-            match (first_ts, last_ts) {
-                (Some(f), Some(l)) => {
-                    let dur = (l - f).num_seconds();
-                    if dur > 0 {
-                        Some(dur as u64)
-                    } else {
-                        None
-                    }
+/// Latest write to a database or its write-ahead log.
+fn db_stamp(db: &Path) -> Option<SystemTime> {
+    let mut wal = db.as_os_str().to_owned();
+    wal.push("-wal");
+    [db.to_path_buf(), PathBuf::from(wal)]
+        .iter()
+        .filter_map(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+        .max()
+}
+
+fn describe(db_paths: &[PathBuf]) -> String {
+    match db_paths {
+        [one] => one.display().to_string(),
+        many => format!("{} workspaces", many.len()),
+    }
+}
+
+/// The databases to import: `--workspace`, else every workspace that maps to
+/// this repo (all of them, or the user's pick), else the global storage.
+/// Exits when none can be found.
+fn resolve_db_paths(workspace: Option<&str>, all: bool) -> Vec<PathBuf> {
+    let db_paths: Vec<PathBuf> = if let Some(w) = workspace {
+        // User specified a workspace storage dir or .vscdb path directly
+        let p = PathBuf::from(w);
+        if p.extension().is_some_and(|e| e == "vscdb") {
+            vec![p]
+        } else {
+            vec![p.join("state.vscdb")]
+        }
+    } else {
+        let matching = find_workspaces_for_repo(&repo_root());
+        if matching.len() > 1 && !all {
+            pick_workspaces(matching)
+                .into_iter()
+                .map(|ws| ws.db)
+                .collect()
+        } else if !matching.is_empty() {
+            matching.into_iter().map(|ws| ws.db).collect()
+        } else {
+            match find_db_for_current_workspace() {
+                Some(p) => vec![p],
+                None => {
+                    eprintln!("[cursor] Cannot find Cursor workspace storage.");
+                    eprintln!("  Pass --workspace <path/to/state.vscdb> to specify the database.");
+                    std::process::exit(1);
                 }
-                _ => None,
             }
-        };
+        }
+    };
+    for db_path in &db_paths {
+        if !db_path.exists() {
+            eprintln!("[cursor] Database not found: {}", db_path.display());
+            std::process::exit(1);
+        }
+    }
+    db_paths
+}
 
-        let receipt = Receipt {
-            id: Receipt::new_id(),
-            provider: "cursor".to_string(),
-            model: session.model.clone(),
-            session_id: session.session_id.clone(),
-            prompt_summary,
-            response_summary,
-            prompt_hash,
-            message_count: session.messages.len() as u32,
-            cost_usd: cost,
-            input_tokens: Some(estimated_input),
-            output_tokens: Some(estimated_output),
-            cache_read_tokens: None,
-            cache_creation_tokens: None,
-            timestamp: session.timestamp,
-            session_start: session.messages.first().and_then(|m| m.timestamp),
-            session_end: session.messages.last().and_then(|m| m.timestamp),
-            session_duration_secs,
-            ai_response_time_secs: None,
-            user: user.clone(),
-            file_path: files_changed
-                .first()
-                .map(|f| f.path.clone())
-                .unwrap_or_default(),
-            line_range: (0, 0),
-            files_changed,
-            parent_receipt_id: None,
-            parent_session_id: None,
-            is_continuation: None,
-            continuation_depth: None,
-            prompt_number: Some((count as u32) + 1),
-            total_additions: 0,
-            total_deletions: 0,
-            tools_used: vec![],
-            mcp_servers: vec![],
-            agents_spawned: vec![],
-            subagent_activities: vec![],
-            concurrent_tool_calls: None,
-            user_decisions: vec![],
-            conversation: if conversation.is_empty() {
-                None
-            } else {
-                Some(conversation)
-            },
-            prompt_submitted_at: Some(session.timestamp),
-            prompt_duration_secs: None,
-            accepted_lines: None,
-            overridden_lines: None,
-            prompt_quality,
-        };
+/// What every receipt built from a Cursor session shares.
+struct ImportContext {
+    cfg: config::BlamePromptConfig,
+    cwd: String,
+    user: String,
+    /// Files modified in the working tree (possible AI-changed files).
+    changed_files: Vec<String>,
+}
 
-        staging::upsert_receipt(&receipt);
-        count += 1;
+impl ImportContext {
+    fn load() -> Self {
+        ImportContext {
+            cfg: config::load_config(),
+            cwd: std::env::current_dir()
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            user: util::git_user(),
+            changed_files: get_recent_changed_files(),
+        }
     }
+}
 
-    println!(
-        "[cursor] Recorded {} Cursor AI session(s) from {}",
-        count,
-        db_path.display()
+/// Build the receipt for one Cursor chat session.
+fn session_receipt(
+    session: &CursorChatSession,
+    ctx: &ImportContext,
+    prompt_number: u32,
+) -> Receipt {
+    let first_user_msg = session
+        .messages
+        .iter()
+        .find(|m| m.role == "user")
+        .map(|m| {
+            m.text
+                .chars()
+                .take(ctx.cfg.capture.max_prompt_length)
+                .collect::<String>()
+        })
+        .unwrap_or_else(|| session.title.clone());
+
+    let prompt_summary = crate::core::redact::redact_secrets_with_config(&first_user_msg, &ctx.cfg);
+
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(prompt_summary.as_bytes());
+    let prompt_hash = format!("sha256:{:x}", hasher.finalize());
+
+    let files_changed: Vec<crate::core::receipt::FileChange> = ctx
+        .changed_files
+        .iter()
+        .map(|f| crate::core::receipt::FileChange {
+            path: util::make_relative(f, &ctx.cwd),
+            line_range: (1, 1),
+            blob_hash: None,
+            additions: 0,
+            deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
+        })
+        .collect();
+
+    let prompt_quality = Some(crate::core::prompt_eval::evaluate(&prompt_summary));
+
+    let response_summary = session
+        .messages
+        .iter()
+        .rev()
+        .find(|m| m.role == "assistant")
+        .map(|m| m.text.chars().take(500).collect());
+
+    // Build conversation turns for full context tracking
+    let conversation: Vec<crate::core::receipt::ConversationTurn> = session
+        .messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| crate::core::receipt::ConversationTurn {
+            turn: (i as u32) + 1,
+            role: m.role.clone(),
+            content: crate::core::redact::redact_secrets_with_config(
+                &m.text
+                    .chars()
+                    .take(ctx.cfg.capture.max_prompt_length)
+                    .collect::<String>(),
+                &ctx.cfg,
+            ),
+            tool_name: None,
+            files_touched: None,
+        })
+        .collect();
+
+    // Estimate tokens from message content
+    let estimated_input = crate::core::pricing::estimate_tokens_from_chars(
+        session
+            .messages
+            .iter()
+            .filter(|m| m.role == "user")
+            .map(|m| m.text.len())
+            .sum(),
     );
-    println!("  Receipts staged. They will be attached on next git commit.");
+    let estimated_output = crate::core::pricing::estimate_tokens_from_chars(
+        session
+            .messages
+            .iter()
+            .filter(|m| m.role == "assistant")
+            .map(|m| m.text.len())
+            .sum(),
+    );
+    let cost =
+        crate::core::pricing::estimate_cost(&session.model, estimated_input, estimated_output);
+
+    // Compute session duration from message timestamps
+    let session_duration_secs = {
+        let first_ts = session.messages.first().and_then(|m| m.timestamp);
+        let last_ts = session.messages.last().and_then(|m| m.timestamp);
+        match (first_ts, last_ts) {
+            (Some(f), Some(l)) => {
+                let dur = (l - f).num_seconds();
+                if dur > 0 {
+                    Some(dur as u64)
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        }
+    };
+
+    let mut receipt = Receipt {
+        id: Receipt::new_id(),
+        provider: "cursor".to_string(),
+        model: session.model.clone(),
+        session_id: session.session_id.clone(),
+        prompt_summary,
+        response_summary,
+        prompt_hash,
+        message_count: session.messages.len() as u32,
+        cost_usd: cost,
+        input_tokens: Some(estimated_input),
+        output_tokens: Some(estimated_output),
+        cache_read_tokens: None,
+        cache_creation_tokens: None,
+        timestamp: session.timestamp,
+        session_start: session.messages.first().and_then(|m| m.timestamp),
+        session_end: session.messages.last().and_then(|m| m.timestamp),
+        session_duration_secs,
+        ai_response_time_secs: None,
+        user: ctx.user.clone(),
+        file_path: files_changed
+            .first()
+            .map(|f| f.path.clone())
+            .unwrap_or_default(),
+        line_range: (0, 0),
+        files_changed,
+        parent_receipt_id: None,
+        parent_session_id: None,
+        is_continuation: None,
+        continuation_depth: None,
+        prompt_number: Some(prompt_number),
+        total_additions: 0,
+        total_deletions: 0,
+        tools_used: vec![],
+        mcp_servers: vec![],
+        environment: None,
+        agents_spawned: vec![],
+        subagent_activities: vec![],
+        concurrent_tool_calls: None,
+        user_decisions: vec![],
+        conversation: if conversation.is_empty() {
+            None
+        } else {
+            Some(conversation)
+        },
+        prompt_submitted_at: Some(session.timestamp),
+        prompt_duration_secs: None,
+        accepted_lines: None,
+        overridden_lines: None,
+        prompt_quality,
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated: true,
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
+    };
+    // Cursor's chat store keeps no edit history; reconstruct from git.
+    acceptance_backfill::backfill(&mut receipt, None);
+    receipt
 }
 
 /// Get files modified in the working tree or staged.
@@ -730,6 +1123,97 @@ pub fn install_hooks() -> Result<(), String> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_workspace_folders_single_and_multi_root() {
+        let dir = tempfile::tempdir().unwrap();
+        let storage = dir.path().join("abc123");
+        std::fs::create_dir_all(&storage).unwrap();
+        std::fs::write(
+            storage.join("workspace.json"),
+            r#"{"folder": "file:///home/dev/my%20repo"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            workspace_folders(&storage),
+            (vec![PathBuf::from("/home/dev/my repo")], None)
+        );
+
+        let ws_file = dir.path().join("team.code-workspace");
+        std::fs::write(
+            &ws_file,
+            "{\n  // frontend and API\n  \"folders\": [\n    {\"path\": \"web\"},\n    {\"uri\": \"file:///srv/api\"},\n  ],\n}",
+        )
+        .unwrap();
+        std::fs::write(
+            storage.join("workspace.json"),
+            format!(r#"{{"workspace": "file://{}"}}"#, ws_file.display()),
+        )
+        .unwrap();
+        let (folders, file) = workspace_folders(&storage);
+        assert_eq!(
+            folders,
+            vec![dir.path().join("web"), PathBuf::from("/srv/api")]
+        );
+        assert_eq!(file, Some(ws_file));
+
+        assert_eq!(uri_to_path("vscode-remote://ssh-remote+box/home/dev"), None);
+        assert_eq!(
+            uri_to_path("file:///c%3A/Users/dev"),
+            Some(PathBuf::from("c:/Users/dev"))
+        );
+        let root = Path::new("/srv/api");
+        assert!(folder_matches(Path::new("/srv/api/src"), root));
+        assert!(folder_matches(Path::new("/srv"), root));
+        assert!(!folder_matches(Path::new("/srv/api-old"), root));
+        assert!(!folder_matches(Path::new("/"), root));
+    }
+
+    #[test]
+    fn test_follow_updates_only_new_or_grown_sessions() {
+        let session = |id: &str, messages: usize| CursorChatSession {
+            session_id: id.to_string(),
+            title: String::new(),
+            model: "auto".to_string(),
+            messages: (0..messages)
+                .map(|i| CursorMessage {
+                    role: if i % 2 == 0 { "user" } else { "assistant" }.to_string(),
+                    text: format!("m{}", i),
+                    timestamp: None,
+                })
+                .collect(),
+            timestamp: Utc::now(),
+        };
+        // s1 was staged earlier as prompt 3 with two messages.
+        let mut known = HashMap::from([("s1".to_string(), (2, 3))]);
+        let mut next = 4;
+
+        let first = vec![session("s1", 2), session("s2", 2)];
+        let updates = follow_updates(&first, &mut known, &mut next);
+        let ids: Vec<(&str, u32)> = updates
+            .iter()
+            .map(|(s, n)| (s.session_id.as_str(), *n))
+            .collect();
+        assert_eq!(ids, vec![("s2", 4)]);
+
+        let second = vec![session("s1", 4), session("s2", 2)];
+        let updates = follow_updates(&second, &mut known, &mut next);
+        let ids: Vec<(&str, u32)> = updates
+            .iter()
+            .map(|(s, n)| (s.session_id.as_str(), *n))
+            .collect();
+        assert_eq!(ids, vec![("s1", 3)]);
+        assert_eq!(next, 5);
+    }
+
+    #[test]
+    fn test_parse_selection() {
+        assert_eq!(parse_selection("\n", 3), Some(vec![0]));
+        assert_eq!(parse_selection("1,3", 3), Some(vec![0, 2]));
+        assert_eq!(parse_selection(" a ", 2), Some(vec![0, 1]));
+        assert_eq!(parse_selection("4", 3), None);
+        assert_eq!(parse_selection("x", 3), None);
+    }
+
     #[test]
     fn test_parse_cursor_chat_json_empty_tabs() {
         let json = r#"{"tabs":[]}"#;
@@ -75,6 +75,8 @@ impl GeminiSession {
             session_duration_secs,
             avg_response_time_secs: None,
             user_prompt_timestamps: vec![],
+            agent_version: None,
+            settings: Default::default(),
         }
     }
 }
@@ -411,6 +413,13 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
             blob_hash: None,
             additions: 0,
             deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
         })
         .collect();
 
@@ -509,6 +518,7 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
         total_deletions: 0,
         tools_used: session.tools_used,
         mcp_servers: vec![],
+        environment: None,
         agents_spawned: vec![],
         subagent_activities: vec![],
         concurrent_tool_calls: None,
@@ -523,6 +533,16 @@ pub fn import_session(path: &Path) -> Option<Receipt> {
         accepted_lines: None,
         overridden_lines: None,
         prompt_quality,
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated: session.input_tokens.is_none() || session.output_tokens.is_none(),
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
     })
 }
 
@@ -559,20 +579,25 @@ pub fn resolve_session_files(session_path: Option<&str>) -> Vec<PathBuf> {
 }
 
 /// Main entry point: scan Gemini CLI sessions and create receipts.
-pub fn run_record_gemini(session_path: Option<&str>) {
+pub fn run_record_gemini(session_path: Option<&str>, force: bool) {
     let files = resolve_session_files(session_path);
     if files.is_empty() {
         return;
     }
 
+    let mut dedup = staging::ImportDeduper::new(force);
+
     let mut count = 0usize;
     for file in &files {
         if let Some(receipt) = import_session(file) {
-            staging::upsert_receipt(&receipt);
-            count += 1;
+            if dedup.stage(&receipt) {
+                count += 1;
+            }
         }
     }
 
+    dedup.report("[gemini]");
+
     if count == 0 {
         eprintln!("[gemini] No valid sessions found in the provided file(s).");
     } else {
@@ -0,0 +1,538 @@
+/// Block's Goose autonomous agent integration for blameprompt.
+///
+/// Imports Goose session logs and converts them to blameprompt receipts.
+///
+/// Goose stores session data in:
+///   ~/.local/share/goose/sessions/ (JSONL files, current releases)
+///   ~/.config/goose/sessions/ (older releases)
+///
+/// The first line of a session file is metadata (`working_dir`, `description`,
+/// `input_tokens`, `output_tokens`). Every following line is a message with
+/// `role`, `created` (epoch seconds) and a `content` array of `text`,
+/// `toolRequest` and `toolResponse` items. Tool names are `<extension>__<tool>`,
+/// where each extension is an MCP server. Subagents are spawned through the
+/// `dynamic_task` / `subagent` extensions and are recorded as subagent_activities.
+use crate::commands::staging;
+use crate::core::{
+    config,
+    receipt::{ConversationTurn, FileChange, Receipt, SubagentActivity},
+    util,
+};
+use chrono::{DateTime, Utc};
+use std::path::{Path, PathBuf};
+
+/// A parsed Goose session.
+#[derive(Debug)]
+pub struct GooseSession {
+    pub session_id: String,
+    pub description: Option<String>,
+    pub model: String,
+    pub messages: Vec<GooseMessage>,
+    pub files_modified: Vec<String>,
+    pub tools_used: Vec<String>,
+    pub extensions: Vec<String>,
+    pub subagents: Vec<SubagentActivity>,
+    pub timestamp: DateTime<Utc>,
+    pub end_timestamp: Option<DateTime<Utc>>,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+}
+
+#[derive(Debug)]
+pub struct GooseMessage {
+    pub role: String,
+    pub text: String,
+}
+
+pub fn find_sessions_dir() -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+
+    let candidates = [
+        home.join(".local")
+            .join("share")
+            .join("goose")
+            .join("sessions"),
+        home.join(".config").join("goose").join("sessions"),
+        home.join("Library")
+            .join("Application Support")
+            .join("Block")
+            .join("goose")
+            .join("sessions"),
+    ];
+    candidates.into_iter().find(|p| p.exists())
+}
+
+pub fn list_session_files(sessions_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<PathBuf> = std::fs::read_dir(sessions_dir)
+        .ok()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "jsonl"))
+        .collect();
+    files.sort_by_key(|f| {
+        std::fs::metadata(f)
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+    });
+    files.reverse();
+    files
+}
+
+fn epoch_to_utc(v: &serde_json::Value) -> Option<DateTime<Utc>> {
+    DateTime::from_timestamp(v.as_i64()?, 0)
+}
+
+/// Goose subagent tools live in the `dynamic_task` and `subagent` extensions.
+fn is_subagent_tool(name: &str) -> bool {
+    let ext = name.split("__").next().unwrap_or("");
+    ext == "dynamic_task" || ext == "subagent" || name.ends_with("__create_task")
+}
+
+pub fn parse_goose_session(path: &Path) -> Option<GooseSession> {
+    let content = std::fs::read_to_string(path).ok()?;
+
+    let mut messages = Vec::new();
+    let mut model = String::new();
+    let mut description = None;
+    let mut files_modified: Vec<String> = Vec::new();
+    let mut tools_used: Vec<String> = Vec::new();
+    let mut extensions: Vec<String> = Vec::new();
+    let mut subagents: Vec<SubagentActivity> = Vec::new();
+    let mut first_ts: Option<DateTime<Utc>> = None;
+    let mut last_ts: Option<DateTime<Utc>> = None;
+    let mut input_tokens: Option<u64> = None;
+    let mut output_tokens: Option<u64> = None;
+
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let entry: serde_json::Value = match serde_json::from_str(line) {
+            Ok(v) => v,
+            Err(_) => continue,
+        };
+
+        // Metadata line: no role, carries the session-level totals.
+        if entry.get("role").is_none() {
+            if let Some(d) = entry.get("description").and_then(|v| v.as_str()) {
+                if !d.is_empty() {
+                    description = Some(d.to_string());
+                }
+            }
+            input_tokens = entry.get("input_tokens").and_then(|v| v.as_u64());
+            output_tokens = entry.get("output_tokens").and_then(|v| v.as_u64());
+            if let Some(m) = entry
+                .get("model")
+                .or_else(|| entry.pointer("/model_config/model_name"))
+                .and_then(|v| v.as_str())
+            {
+                model = m.to_string();
+            }
+            continue;
+        }
+
+        let role = entry.get("role").and_then(|v| v.as_str()).unwrap_or("");
+        let created = entry.get("created").and_then(epoch_to_utc);
+        if created.is_some() {
+            if first_ts.is_none() {
+                first_ts = created;
+            }
+            last_ts = created;
+        }
+
+        let items = entry
+            .get("content")
+            .and_then(|v| v.as_array())
+            .cloned()
+            .unwrap_or_default();
+        let mut text_parts: Vec<String> = Vec::new();
+
+        for item in &items {
+            match item.get("type").and_then(|v| v.as_str()).unwrap_or("") {
+                "text" => {
+                    if let Some(t) = item.get("text").and_then(|v| v.as_str()) {
+                        text_parts.push(t.to_string());
+                    }
+                }
+                "toolRequest" => {
+                    let call = match item.pointer("/toolCall/value") {
+                        Some(c) => c,
+                        None => continue,
+                    };
+                    let name = call.get("name").and_then(|v| v.as_str()).unwrap_or("");
+                    if name.is_empty() {
+                        continue;
+                    }
+                    if !tools_used.iter().any(|t| t == name) {
+                        tools_used.push(name.to_string());
+                    }
+                    if let Some((ext, _)) = name.split_once("__") {
+                        if !extensions.iter().any(|e| e == ext) {
+                            extensions.push(ext.to_string());
+                        }
+                    }
+
+                    let args = call.get("arguments");
+                    if let Some(args) = args {
+                        let command = args.get("command").and_then(|v| v.as_str()).unwrap_or("");
+                        let writes = matches!(
+                            command,
+                            "write" | "str_replace" | "insert" | "undo_edit" | "edit"
+                        );
+                        if writes {
+                            if let Some(p) = args.get("path").and_then(|v| v.as_str()) {
+                                if !files_modified.iter().any(|f| f == p) {
+                                    files_modified.push(p.to_string());
+                                }
+                            }
+                        }
+                    }
+
+                    if is_subagent_tool(name) {
+                        let description = args.and_then(|a| {
+                            a.get("instructions")
+                                .or_else(|| a.get("task"))
+                                .or_else(|| a.get("description"))
+                                .and_then(|v| v.as_str())
+                                .map(|s| s.chars().take(200).collect::<String>())
+                        });
+                        subagents.push(SubagentActivity {
+                            agent_id: item
+                                .get("id")
+                                .and_then(|v| v.as_str())
+                                .map(|s| s.to_string()),
+                            agent_type: Some(name.to_string()),
+                            description,
+                            status: "started".to_string(),
+                            started_at: created,
+                            completed_at: None,
+                            tools_used: vec![],
+                            model: None,
+                            input_tokens: None,
+                            output_tokens: None,
+                            cost_usd: None,
+                            files_touched: vec![],
+                        });
+                    }
+                }
+                "toolResponse" => {
+                    let id = item.get("id").and_then(|v| v.as_str());
+                    if let Some(sa) = subagents
+                        .iter_mut()
+                        .find(|s| s.agent_id.is_some() && s.agent_id.as_deref() == id)
+                    {
+                        sa.status = "completed".to_string();
+                        sa.completed_at = created;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        let text = text_parts.join("\n");
+        if !text.trim().is_empty() && (role == "user" || role == "assistant") {
+            messages.push(GooseMessage {
+                role: role.to_string(),
+                text,
+            });
+        }
+    }
+
+    if messages.is_empty() {
+        return None;
+    }
+
+    if model.is_empty() {
+        model = "goose".to_string();
+    }
+
+    let session_id = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    Some(GooseSession {
+        session_id,
+        description,
+        model,
+        messages,
+        files_modified,
+        tools_used,
+        extensions,
+        subagents,
+        timestamp: first_ts.unwrap_or_else(Utc::now),
+        end_timestamp: last_ts,
+        input_tokens,
+        output_tokens,
+    })
+}
+
+pub fn import_session(path: &Path) -> Option<Receipt> {
+    let session = parse_goose_session(path)?;
+    let cfg = config::load_config();
+    let cwd = std::env::current_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let user = util::git_user();
+
+    let first_user_msg = session
+        .messages
+        .iter()
+        .find(|m| m.role == "user")
+        .map(|m| m.text.clone())
+        .or_else(|| session.description.clone())
+        .unwrap_or_default()
+        .chars()
+        .take(cfg.capture.max_prompt_length)
+        .collect::<String>();
+
+    let prompt_summary = crate::core::redact::redact_secrets_with_config(&first_user_msg, &cfg);
+
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(prompt_summary.as_bytes());
+    let prompt_hash = format!("sha256:{:x}", hasher.finalize());
+
+    let response_summary = session
+        .messages
+        .iter()
+        .rev()
+        .find(|m| m.role == "assistant")
+        .map(|m| m.text.chars().take(500).collect());
+
+    let files_changed: Vec<FileChange> = session
+        .files_modified
+        .iter()
+        .map(|f| FileChange {
+            path: util::make_relative(f, &cwd),
+            line_range: (1, 1),
+            blob_hash: None,
+            additions: 0,
+            deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
+        })
+        .collect();
+
+    let final_input = session.input_tokens.unwrap_or_else(|| {
+        crate::core::pricing::estimate_tokens_from_chars(
+            session
+                .messages
+                .iter()
+                .filter(|m| m.role == "user")
+                .map(|m| m.text.len())
+                .sum(),
+        )
+    });
+    let final_output = session.output_tokens.unwrap_or_else(|| {
+        crate::core::pricing::estimate_tokens_from_chars(
+            session
+                .messages
+                .iter()
+                .filter(|m| m.role == "assistant")
+                .map(|m| m.text.len())
+                .sum(),
+        )
+    });
+    let cost = crate::core::pricing::estimate_cost(&session.model, final_input, final_output);
+
+    let session_duration_secs = session
+        .end_timestamp
+        .map(|end| (end - session.timestamp).num_seconds().max(0) as u64);
+
+    let conversation: Vec<ConversationTurn> = session
+        .messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| ConversationTurn {
+            turn: (i as u32) + 1,
+            role: m.role.clone(),
+            content: crate::core::redact::redact_secrets_with_config(
+                &m.text
+                    .chars()
+                    .take(cfg.capture.max_prompt_length)
+                    .collect::<String>(),
+                &cfg,
+            ),
+            tool_name: None,
+            files_touched: None,
+        })
+        .collect();
+
+    let agents_spawned: Vec<String> = session
+        .subagents
+        .iter()
+        .filter_map(|s| s.agent_type.clone())
+        .fold(Vec::new(), |mut acc, t| {
+            if !acc.contains(&t) {
+                acc.push(t);
+            }
+            acc
+        });
+
+    let prompt_quality = Some(crate::core::prompt_eval::evaluate(&prompt_summary));
+
+    Some(Receipt {
+        id: Receipt::new_id(),
+        provider: "goose".to_string(),
+        model: session.model,
+        session_id: session.session_id,
+        prompt_summary,
+        response_summary,
+        prompt_hash,
+        message_count: session.messages.len() as u32,
+        cost_usd: cost,
+        input_tokens: Some(final_input),
+        output_tokens: Some(final_output),
+        cache_read_tokens: None,
+        cache_creation_tokens: None,
+        timestamp: session.timestamp,
+        session_start: Some(session.timestamp),
+        session_end: session.end_timestamp,
+        session_duration_secs,
+        ai_response_time_secs: None,
+        user,
+        file_path: files_changed
+            .first()
+            .map(|f| f.path.clone())
+            .unwrap_or_default(),
+        line_range: (0, 0),
+        files_changed,
+        parent_receipt_id: None,
+        parent_session_id: None,
+        is_continuation: None,
+        continuation_depth: None,
+        prompt_number: Some(1),
+        total_additions: 0,
+        total_deletions: 0,
+        tools_used: session.tools_used,
+        mcp_servers: session.extensions,
+        environment: None,
+        agents_spawned,
+        subagent_activities: session.subagents,
+        concurrent_tool_calls: None,
+        user_decisions: vec![],
+        conversation: if conversation.is_empty() {
+            None
+        } else {
+            Some(conversation)
+        },
+        prompt_submitted_at: Some(session.timestamp),
+        prompt_duration_secs: None,
+        accepted_lines: None,
+        overridden_lines: None,
+        prompt_quality,
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated: session.input_tokens.is_none() || session.output_tokens.is_none(),
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
+    })
+}
+
+pub fn run_record_goose(session_path: Option<&str>, force: bool) {
+    let files = if let Some(path) = session_path {
+        let p = PathBuf::from(path);
+        if !p.exists() {
+            eprintln!("[goose] File not found: {}", path);
+            std::process::exit(1);
+        }
+        if p.is_dir() {
+            list_session_files(&p)
+        } else {
+            vec![p]
+        }
+    } else {
+        match find_sessions_dir() {
+            Some(dir) => {
+                let files = list_session_files(&dir);
+                if files.is_empty() {
+                    eprintln!("[goose] No session files found in {}", dir.display());
+                    return;
+                }
+                files.into_iter().take(10).collect()
+            }
+            None => {
+                eprintln!("[goose] Cannot find Goose sessions directory.");
+                eprintln!("  Pass --session <path> to specify a session file.");
+                return;
+            }
+        }
+    };
+
+    let mut dedup = staging::ImportDeduper::new(force);
+
+    let mut count = 0usize;
+    for file in &files {
+        if let Some(receipt) = import_session(file) {
+            if dedup.stage(&receipt) {
+                count += 1;
+            }
+        }
+    }
+
+    dedup.report("[goose]");
+
+    if count == 0 {
+        eprintln!("[goose] No valid sessions found in the provided file(s).");
+    } else {
+        println!("[goose] Recorded {} Goose session(s)", count);
+        println!("  Receipts staged. They will be attached on next git commit.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_goose_session_with_subagent() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        let content = r#"{"working_dir":"/repo","description":"Add retry logic","message_count":4,"input_tokens":1200,"output_tokens":300}
+{"role":"user","created":1767225600,"content":[{"type":"text","text":"Add retry logic to the client"}]}
+{"role":"assistant","created":1767225610,"content":[{"type":"text","text":"Delegating research."},{"type":"toolRequest","id":"call_1","toolCall":{"status":"success","value":{"name":"dynamic_task__create_task","arguments":{"instructions":"Find all HTTP call sites"}}}}]}
+{"role":"user","created":1767225640,"content":[{"type":"toolResponse","id":"call_1","toolResult":{"status":"success","value":[]}}]}
+{"role":"assistant","created":1767225650,"content":[{"type":"toolRequest","id":"call_2","toolCall":{"status":"success","value":{"name":"developer__text_editor","arguments":{"command":"write","path":"src/client.rs","file_text":"..."}}}},{"type":"text","text":"Done."}]}
+"#;
+        std::fs::write(tmp.path(), content).unwrap();
+
+        let s = parse_goose_session(tmp.path()).unwrap();
+        assert_eq!(s.messages.len(), 3);
+        assert_eq!(s.input_tokens, Some(1200));
+        assert_eq!(s.output_tokens, Some(300));
+        assert_eq!(s.files_modified, vec!["src/client.rs"]);
+        assert_eq!(s.extensions, vec!["dynamic_task", "developer"]);
+        assert_eq!(s.subagents.len(), 1);
+        let sa = &s.subagents[0];
+        assert_eq!(sa.agent_id.as_deref(), Some("call_1"));
+        assert_eq!(sa.status, "completed");
+        assert_eq!(sa.description.as_deref(), Some("Find all HTTP call sites"));
+        assert!(sa.completed_at > sa.started_at);
+    }
+
+    #[test]
+    fn test_parse_goose_session_empty() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(tmp.path(), r#"{"description":"nothing"}"#).unwrap();
+        assert!(parse_goose_session(tmp.path()).is_none());
+    }
+
+    #[test]
+    fn test_is_subagent_tool() {
+        assert!(is_subagent_tool("dynamic_task__create_task"));
+        assert!(is_subagent_tool("subagent__execute_task"));
+        assert!(!is_subagent_tool("developer__shell"));
+    }
+}
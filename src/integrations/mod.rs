@@ -1,4 +1,5 @@
 pub mod agent_trace;
+pub mod agent_trace_schema;
 pub mod amp;
 pub mod antigravity;
 pub mod claude_hooks;
@@ -8,7 +9,11 @@ pub mod copilot;
 pub mod cursor;
 pub mod droid;
 pub mod gemini;
+pub mod git_ai;
+pub mod goose;
 pub mod junie;
+pub mod openai_export;
 pub mod opencode;
+pub mod openhands;
 pub mod rovo_dev;
 pub mod windsurf;
@@ -0,0 +1,631 @@
+/// OpenAI ChatGPT data-export integration for blameprompt.
+///
+/// Users sometimes paste code from chatgpt.com, which no hook can observe.
+/// This importer reads the official data export (Settings → Data controls →
+/// Export data) and produces best-effort receipts flagged as `manual_import`.
+///
+/// The export archive contains `conversations.json`: an array of conversations,
+/// each with a `mapping` tree of message nodes keyed by id. The active branch is
+/// recovered by walking `parent` links back from `current_node`.
+///
+/// Attribution works by matching code blocks from assistant replies against
+/// lines added in the working tree (`git diff HEAD`). Only conversations updated
+/// within the import window are considered.
+use crate::commands::staging;
+use crate::core::{
+    config,
+    receipt::{ConversationTurn, FileChange, Receipt},
+    util,
+};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+/// Snippet lines shorter than this (after trimming) are too generic to match on
+/// (`}`, `else {`, `return;`).
+const MIN_LINE_LEN: usize = 8;
+/// A file is only attributed when at least this many added lines match a snippet.
+const MIN_MATCHED_LINES: usize = 3;
+
+/// A parsed ChatGPT conversation (active branch only).
+#[derive(Debug)]
+pub struct ChatGptConversation {
+    pub id: String,
+    pub title: String,
+    pub model: String,
+    pub messages: Vec<ChatGptMessage>,
+    pub create_time: DateTime<Utc>,
+    pub update_time: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug)]
+pub struct ChatGptMessage {
+    pub role: String,
+    pub text: String,
+    pub timestamp: Option<DateTime<Utc>>,
+}
+
+/// Lines added to one file in the working tree, with their new line numbers.
+#[derive(Debug)]
+pub struct AddedLines {
+    pub path: String,
+    pub lines: Vec<(u32, String)>,
+}
+
+/// Resolve `conversations.json` from either the file itself or the unpacked export directory.
+pub fn resolve_export_path(path: &Path) -> Option<PathBuf> {
+    if path.is_dir() {
+        let candidate = path.join("conversations.json");
+        candidate.exists().then_some(candidate)
+    } else if path.exists() {
+        Some(path.to_path_buf())
+    } else {
+        None
+    }
+}
+
+fn epoch_to_utc(v: &serde_json::Value) -> Option<DateTime<Utc>> {
+    let secs = v.as_f64()?;
+    DateTime::from_timestamp(secs.trunc() as i64, (secs.fract() * 1e9) as u32)
+}
+
+pub fn parse_export(path: &Path) -> Result<Vec<ChatGptConversation>, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Cannot read {}: {}", path.display(), e))?;
+    let root: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("Invalid export JSON: {}", e))?;
+    let items = root
+        .as_array()
+        .ok_or("conversations.json is not an array of conversations")?;
+    Ok(items.iter().filter_map(parse_conversation).collect())
+}
+
+pub fn parse_conversation(conv: &serde_json::Value) -> Option<ChatGptConversation> {
+    let mapping = conv.get("mapping")?.as_object()?;
+    let id = conv
+        .get("conversation_id")
+        .or_else(|| conv.get("id"))
+        .and_then(|v| v.as_str())?
+        .to_string();
+
+    // Walk from the active leaf back to the root so edited/regenerated
+    // branches that the user abandoned are ignored.
+    let mut chain = Vec::new();
+    let mut node_id = conv
+        .get("current_node")
+        .and_then(|v| v.as_str())
+        .map(|s| s.to_string());
+    let mut visited: HashSet<String> = HashSet::new();
+    while let Some(nid) = node_id {
+        if !visited.insert(nid.clone()) {
+            break;
+        }
+        let node = match mapping.get(&nid) {
+            Some(n) => n,
+            None => break,
+        };
+        chain.push(node);
+        node_id = node
+            .get("parent")
+            .and_then(|v| v.as_str())
+            .map(|s| s.to_string());
+    }
+    chain.reverse();
+
+    let mut model = String::new();
+    let mut messages = Vec::new();
+    for node in chain {
+        let msg = match node.get("message") {
+            Some(m) if !m.is_null() => m,
+            _ => continue,
+        };
+        let role = msg
+            .pointer("/author/role")
+            .and_then(|v| v.as_str())
+            .unwrap_or("");
+        if role != "user" && role != "assistant" {
+            continue;
+        }
+        let text: String = msg
+            .pointer("/content/parts")
+            .and_then(|v| v.as_array())
+            .map(|parts| {
+                parts
+                    .iter()
+                    .filter_map(|p| p.as_str())
+                    .collect::<Vec<_>>()
+                    .join("\n")
+            })
+            .or_else(|| {
+                msg.pointer("/content/text")
+                    .and_then(|v| v.as_str())
+                    .map(|s| s.to_string())
+            })
+            .unwrap_or_default();
+        if text.trim().is_empty() {
+            continue;
+        }
+        if role == "assistant" {
+            if let Some(slug) = msg.pointer("/metadata/model_slug").and_then(|v| v.as_str()) {
+                model = slug.to_string();
+            }
+        }
+        messages.push(ChatGptMessage {
+            role: role.to_string(),
+            text,
+            timestamp: msg.get("create_time").and_then(epoch_to_utc),
+        });
+    }
+
+    if messages.is_empty() {
+        return None;
+    }
+
+    let create_time = conv
+        .get("create_time")
+        .and_then(epoch_to_utc)
+        .or_else(|| messages.iter().find_map(|m| m.timestamp))
+        .unwrap_or_else(Utc::now);
+
+    Some(ChatGptConversation {
+        id,
+        title: conv
+            .get("title")
+            .and_then(|v| v.as_str())
+            .unwrap_or("")
+            .to_string(),
+        model: if model.is_empty() {
+            "chatgpt".to_string()
+        } else {
+            model
+        },
+        messages,
+        create_time,
+        update_time: conv.get("update_time").and_then(epoch_to_utc),
+    })
+}
+
+/// Extract the bodies of fenced (```) code blocks from a markdown message.
+pub fn extract_code_blocks(text: &str) -> Vec<String> {
+    let mut blocks = Vec::new();
+    let mut current: Option<Vec<&str>> = None;
+    for line in text.lines() {
+        if line.trim_start().starts_with("```") {
+            match current.take() {
+                Some(body) => blocks.push(body.join("\n")),
+                None => current = Some(Vec::new()),
+            }
+        } else if let Some(body) = current.as_mut() {
+            body.push(line);
+        }
+    }
+    blocks
+}
+
+fn significant_lines(block: &str) -> HashSet<String> {
+    block
+        .lines()
+        .map(|l| l.trim().to_string())
+        .filter(|l| l.len() >= MIN_LINE_LEN)
+        .collect()
+}
+
+/// Parse `git diff HEAD --unified=0` output into added lines per file.
+pub fn parse_added_lines(diff: &str) -> Vec<AddedLines> {
+    let mut files: Vec<AddedLines> = Vec::new();
+    let mut next_line = 0u32;
+    for line in diff.lines() {
+        if let Some(path) = line.strip_prefix("+++ ") {
+            let path = path.strip_prefix("b/").unwrap_or(path);
+            if path != "/dev/null" {
+                files.push(AddedLines {
+                    path: path.to_string(),
+                    lines: Vec::new(),
+                });
+            }
+        } else if line.starts_with("@@") {
+            next_line = util::parse_hunk_range(line).0;
+        } else if let Some(added) = line.strip_prefix('+') {
+            if let Some(file) = files.last_mut() {
+                file.lines.push((next_line, added.to_string()));
+            }
+            next_line += 1;
+        }
+    }
+    files.retain(|f| !f.lines.is_empty());
+    files
+}
+
+fn collect_added_lines() -> Vec<AddedLines> {
+    std::process::Command::new("git")
+        .args(["diff", "HEAD", "--unified=0", "--no-color"])
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .map(|o| parse_added_lines(&String::from_utf8_lossy(&o.stdout)))
+        .unwrap_or_default()
+}
+
+/// Line numbers in each file whose content appears in one of the snippets.
+fn matched_lines(snippets: &HashSet<String>, added: &[AddedLines]) -> HashMap<String, Vec<u32>> {
+    let mut result: HashMap<String, Vec<u32>> = HashMap::new();
+    if snippets.is_empty() {
+        return result;
+    }
+    for file in added {
+        let hits: Vec<u32> = file
+            .lines
+            .iter()
+            .filter(|(_, content)| snippets.contains(content.trim()))
+            .map(|(n, _)| *n)
+            .collect();
+        if !hits.is_empty() {
+            result.insert(file.path.clone(), hits);
+        }
+    }
+    result
+}
+
+/// Match a conversation's code snippets against working-tree additions.
+/// Returns the file changes plus the index of the first assistant message that matched.
+pub fn match_conversation(
+    conv: &ChatGptConversation,
+    added: &[AddedLines],
+) -> Option<(Vec<FileChange>, usize)> {
+    let mut anchor: Option<usize> = None;
+    let mut all_snippets: HashSet<String> = HashSet::new();
+    for (i, m) in conv.messages.iter().enumerate() {
+        if m.role != "assistant" {
+            continue;
+        }
+        let snippets: HashSet<String> = extract_code_blocks(&m.text)
+            .iter()
+            .flat_map(|b| significant_lines(b))
+            .collect();
+        if anchor.is_none() && !matched_lines(&snippets, added).is_empty() {
+            anchor = Some(i);
+        }
+        all_snippets.extend(snippets);
+    }
+
+    let mut changes: Vec<FileChange> = matched_lines(&all_snippets, added)
+        .into_iter()
+        .filter(|(_, hits)| hits.len() >= MIN_MATCHED_LINES)
+        .map(|(path, hits)| FileChange {
+            path,
+            line_range: (
+                *hits.iter().min().unwrap_or(&1),
+                *hits.iter().max().unwrap_or(&1),
+            ),
+            blob_hash: None,
+            additions: hits.len() as u32,
+            deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
+        })
+        .collect();
+    if changes.is_empty() {
+        return None;
+    }
+    changes.sort_by(|a, b| a.path.cmp(&b.path));
+    Some((changes, anchor.unwrap_or(0)))
+}
+
+pub fn import_conversation(conv: &ChatGptConversation, added: &[AddedLines]) -> Option<Receipt> {
+    let (files_changed, anchor) = match_conversation(conv, added)?;
+    let cfg = config::load_config();
+    let user = util::git_user();
+
+    // The prompt that produced the first matching snippet.
+    let prompt_text = conv.messages[..anchor]
+        .iter()
+        .rev()
+        .find(|m| m.role == "user")
+        .or_else(|| conv.messages.iter().find(|m| m.role == "user"))
+        .map(|m| {
+            m.text
+                .chars()
+                .take(cfg.capture.max_prompt_length)
+                .collect::<String>()
+        })
+        .unwrap_or_else(|| conv.title.clone());
+    let prompt_summary = crate::core::redact::redact_secrets_with_config(&prompt_text, &cfg);
+
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(prompt_summary.as_bytes());
+    let prompt_hash = format!("sha256:{:x}", hasher.finalize());
+
+    let response_summary = conv.messages.get(anchor).map(|m| {
+        crate::core::redact::redact_secrets_with_config(
+            &m.text.chars().take(500).collect::<String>(),
+            &cfg,
+        )
+    });
+
+    let input_tokens = crate::core::pricing::estimate_tokens_from_chars(
+        conv.messages
+            .iter()
+            .filter(|m| m.role == "user")
+            .map(|m| m.text.len())
+            .sum(),
+    );
+    let output_tokens = crate::core::pricing::estimate_tokens_from_chars(
+        conv.messages
+            .iter()
+            .filter(|m| m.role == "assistant")
+            .map(|m| m.text.len())
+            .sum(),
+    );
+
+    let timestamp = conv
+        .messages
+        .get(anchor)
+        .and_then(|m| m.timestamp)
+        .unwrap_or(conv.create_time);
+    let session_end = conv.update_time;
+    let session_duration_secs =
+        session_end.map(|end| (end - conv.create_time).num_seconds().max(0) as u64);
+
+    let conversation: Vec<ConversationTurn> = conv
+        .messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| ConversationTurn {
+            turn: (i as u32) + 1,
+            role: m.role.clone(),
+            content: crate::core::redact::redact_secrets_with_config(
+                &m.text
+                    .chars()
+                    .take(cfg.capture.max_prompt_length)
+                    .collect::<String>(),
+                &cfg,
+            ),
+            tool_name: None,
+            files_touched: None,
+        })
+        .collect();
+
+    let total_additions = files_changed.iter().map(|f| f.additions).sum();
+    let prompt_quality = Some(crate::core::prompt_eval::evaluate(&prompt_summary));
+
+    Some(Receipt {
+        id: Receipt::new_id(),
+        provider: "chatgpt".to_string(),
+        model: conv.model.clone(),
+        session_id: conv.id.clone(),
+        prompt_summary,
+        response_summary,
+        prompt_hash,
+        message_count: conv.messages.len() as u32,
+        // chatgpt.com is subscription-billed; there is no per-token spend to attribute.
+        cost_usd: 0.0,
+        input_tokens: Some(input_tokens),
+        output_tokens: Some(output_tokens),
+        cache_read_tokens: None,
+        cache_creation_tokens: None,
+        timestamp,
+        session_start: Some(conv.create_time),
+        session_end,
+        session_duration_secs,
+        ai_response_time_secs: None,
+        user,
+        file_path: files_changed
+            .first()
+            .map(|f| f.path.clone())
+            .unwrap_or_default(),
+        line_range: files_changed
+            .first()
+            .map(|f| f.line_range)
+            .unwrap_or((0, 0)),
+        files_changed,
+        parent_receipt_id: None,
+        parent_session_id: None,
+        is_continuation: None,
+        continuation_depth: None,
+        prompt_number: Some(1),
+        total_additions,
+        total_deletions: 0,
+        tools_used: vec![],
+        mcp_servers: vec![],
+        environment: None,
+        agents_spawned: vec![],
+        subagent_activities: vec![],
+        concurrent_tool_calls: None,
+        user_decisions: vec![],
+        conversation: if conversation.is_empty() {
+            None
+        } else {
+            Some(conversation)
+        },
+        prompt_submitted_at: Some(timestamp),
+        prompt_duration_secs: None,
+        accepted_lines: None,
+        overridden_lines: None,
+        prompt_quality,
+        ticket: None,
+        manual_import: true,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated: true,
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
+    })
+}
+
+pub fn run_record_chatgpt(export_path: &str, days: u32, force: bool) {
+    let path = match resolve_export_path(Path::new(export_path)) {
+        Some(p) => p,
+        None => {
+            eprintln!("[chatgpt] conversations.json not found at {}", export_path);
+            eprintln!(
+                "  Pass the unzipped ChatGPT data export directory or its conversations.json."
+            );
+            std::process::exit(1);
+        }
+    };
+
+    let conversations = match parse_export(&path) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("[chatgpt] {}", e);
+            std::process::exit(1);
+        }
+    };
+
+    let added = collect_added_lines();
+    if added.is_empty() {
+        eprintln!("[chatgpt] No uncommitted additions in this repository to match against.");
+        return;
+    }
+
+    let cutoff = Utc::now() - Duration::days(days as i64);
+    let mut dedup = staging::ImportDeduper::new(force);
+    let mut count = 0usize;
+    for conv in conversations
+        .iter()
+        .filter(|c| c.update_time.unwrap_or(c.create_time) >= cutoff)
+    {
+        if let Some(receipt) = import_conversation(conv, &added) {
+            println!(
+                "[chatgpt] Matched \"{}\" → {} file(s)",
+                conv.title,
+                receipt.files_changed.len()
+            );
+            if dedup.stage(&receipt) {
+                count += 1;
+            }
+        }
+    }
+
+    dedup.report("[chatgpt]");
+
+    if count == 0 {
+        eprintln!(
+            "[chatgpt] No conversations from the last {} day(s) matched uncommitted changes.",
+            days
+        );
+    } else {
+        println!(
+            "[chatgpt] Recorded {} ChatGPT conversation(s) as manual imports",
+            count
+        );
+        println!("  Receipts staged. They will be attached on next git commit.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_conversation() -> serde_json::Value {
+        serde_json::json!({
+            "title": "Rust retry helper",
+            "create_time": 1767225600.0,
+            "update_time": 1767229200.5,
+            "conversation_id": "conv-1",
+            "current_node": "n3",
+            "mapping": {
+                "root": {"id": "root", "message": null, "parent": null, "children": ["n1"]},
+                "n1": {"id": "n1", "parent": "root", "children": ["n2", "n2b"], "message": {
+                    "author": {"role": "user"},
+                    "create_time": 1767225600.0,
+                    "content": {"content_type": "text", "parts": ["Write a retry helper"]}
+                }},
+                "n2b": {"id": "n2b", "parent": "n1", "children": [], "message": {
+                    "author": {"role": "assistant"},
+                    "content": {"content_type": "text", "parts": ["abandoned branch"]}
+                }},
+                "n2": {"id": "n2", "parent": "n1", "children": ["n3"], "message": {
+                    "author": {"role": "assistant"},
+                    "create_time": 1767225660.0,
+                    "metadata": {"model_slug": "gpt-4o"},
+                    "content": {"content_type": "text", "parts": [
+                        "Here you go:\n```rust\npub fn retry<F: Fn() -> bool>(f: F, attempts: u32) -> bool {\n    for _ in 0..attempts {\n        if f() {\n            return true;\n        }\n    }\n    false\n}\n```"
+                    ]}
+                }},
+                "n3": {"id": "n3", "parent": "n2", "children": [], "message": {
+                    "author": {"role": "user"},
+                    "content": {"content_type": "text", "parts": ["thanks"]}
+                }}
+            }
+        })
+    }
+
+    #[test]
+    fn test_parse_conversation_follows_active_branch() {
+        let conv = parse_conversation(&sample_conversation()).unwrap();
+        assert_eq!(conv.id, "conv-1");
+        assert_eq!(conv.model, "gpt-4o");
+        assert_eq!(conv.messages.len(), 3);
+        assert!(conv.messages.iter().all(|m| m.text != "abandoned branch"));
+        assert!(conv.update_time.unwrap() > conv.create_time);
+    }
+
+    #[test]
+    fn test_extract_code_blocks() {
+        let blocks = extract_code_blocks("text\n```py\nprint(1)\n```\nmore\n```\na\nb\n```");
+        assert_eq!(blocks, vec!["print(1)".to_string(), "a\nb".to_string()]);
+        assert!(extract_code_blocks("no code").is_empty());
+    }
+
+    #[test]
+    fn test_parse_added_lines() {
+        let diff = "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -3,0 +4,2 @@\n+fn a() {}\n+fn b() {}\n--- a/old.rs\n+++ /dev/null\n@@ -1 +0,0 @@\n-gone\n";
+        let added = parse_added_lines(diff);
+        assert_eq!(added.len(), 1);
+        assert_eq!(added[0].path, "src/lib.rs");
+        assert_eq!(
+            added[0].lines,
+            vec![(4, "fn a() {}".to_string()), (5, "fn b() {}".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_match_conversation_against_diff() {
+        let conv = parse_conversation(&sample_conversation()).unwrap();
+        let added = vec![
+            AddedLines {
+                path: "src/retry.rs".to_string(),
+                lines: vec![
+                    (
+                        10,
+                        "pub fn retry<F: Fn() -> bool>(f: F, attempts: u32) -> bool {".into(),
+                    ),
+                    (11, "    for _ in 0..attempts {".into()),
+                    (12, "        if f() {".into()),
+                    (13, "            return true;".into()),
+                    (14, "}".into()),
+                ],
+            },
+            AddedLines {
+                path: "src/unrelated.rs".to_string(),
+                lines: vec![(1, "let x = compute_other_thing();".into())],
+            },
+        ];
+        let (changes, anchor) = match_conversation(&conv, &added).unwrap();
+        assert_eq!(anchor, 1);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(changes[0].path, "src/retry.rs");
+        assert_eq!(changes[0].line_range, (10, 13));
+        assert_eq!(changes[0].additions, 4);
+    }
+
+    #[test]
+    fn test_match_requires_minimum_overlap() {
+        let conv = parse_conversation(&sample_conversation()).unwrap();
+        let added = vec![AddedLines {
+            path: "src/retry.rs".to_string(),
+            lines: vec![(1, "            return true;".into())],
+        }];
+        assert!(match_conversation(&conv, &added).is_none());
+    }
+}
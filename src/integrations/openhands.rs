@@ -0,0 +1,605 @@
+/// OpenHands autonomous agent integration for blameprompt.
+///
+/// Imports OpenHands trajectories and converts them to blameprompt receipts.
+///
+/// OpenHands stores session data in:
+///   ~/.openhands/sessions/<id>/events/<n>.json (one event per file)
+///   ~/.openhands-state/sessions/<id>/events/ (older releases)
+///
+/// Trajectories exported from the UI or `save_trajectory_path` are a single JSON
+/// array of the same events (optionally wrapped as `{"trajectory": [...]}`).
+///
+/// Each event has `id`, `timestamp`, `source` (user/agent/environment) and either
+/// an `action` (message, run, edit, write, delegate, finish, ...) with `args`, or
+/// an `observation` with a `cause` pointing at the action it answers. `delegate`
+/// actions hand work to a sub-agent and are recorded as subagent_activities.
+use crate::commands::staging;
+use crate::core::{
+    config,
+    receipt::{ConversationTurn, FileChange, Receipt, SubagentActivity},
+    util,
+};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::path::{Path, PathBuf};
+
+/// A parsed OpenHands session.
+#[derive(Debug)]
+pub struct OpenHandsSession {
+    pub session_id: String,
+    pub model: String,
+    pub messages: Vec<OpenHandsMessage>,
+    pub files_modified: Vec<String>,
+    pub tools_used: Vec<String>,
+    pub subagents: Vec<SubagentActivity>,
+    pub timestamp: DateTime<Utc>,
+    pub end_timestamp: Option<DateTime<Utc>>,
+    pub input_tokens: Option<u64>,
+    pub output_tokens: Option<u64>,
+    /// Accumulated LLM cost reported by OpenHands itself, when present.
+    pub reported_cost: Option<f64>,
+}
+
+#[derive(Debug)]
+pub struct OpenHandsMessage {
+    pub role: String,
+    pub text: String,
+}
+
+pub fn find_sessions_dir() -> Option<PathBuf> {
+    let home = dirs::home_dir()?;
+
+    let candidates = [
+        home.join(".openhands").join("sessions"),
+        home.join(".openhands-state").join("sessions"),
+    ];
+    candidates.into_iter().find(|p| p.exists())
+}
+
+/// Session directories (each containing `events/`) or trajectory files, newest first.
+pub fn list_sessions(sessions_dir: &Path) -> Vec<PathBuf> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(sessions_dir)
+        .ok()
+        .into_iter()
+        .flatten()
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.join("events").is_dir() || p.extension().is_some_and(|e| e == "json"))
+        .collect();
+    entries.sort_by_key(|f| {
+        std::fs::metadata(f)
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::SystemTime::UNIX_EPOCH)
+    });
+    entries.reverse();
+    entries
+}
+
+fn parse_timestamp(s: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(s)
+        .map(|dt| dt.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            // OpenHands writes naive local-less timestamps like 2026-01-01T10:00:00.123456
+            NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S%.f")
+                .ok()
+                .map(|n| n.and_utc())
+        })
+}
+
+/// Load raw events from a session directory or a trajectory file.
+pub fn load_events(path: &Path) -> Vec<serde_json::Value> {
+    if path.is_dir() {
+        let events_dir = if path.join("events").is_dir() {
+            path.join("events")
+        } else {
+            path.to_path_buf()
+        };
+        let mut files: Vec<(u64, PathBuf)> = std::fs::read_dir(&events_dir)
+            .ok()
+            .into_iter()
+            .flatten()
+            .filter_map(|e| e.ok().map(|e| e.path()))
+            .filter(|p| p.extension().is_some_and(|e| e == "json"))
+            .map(|p| {
+                let n = p
+                    .file_stem()
+                    .and_then(|s| s.to_str())
+                    .and_then(|s| s.parse::<u64>().ok())
+                    .unwrap_or(u64::MAX);
+                (n, p)
+            })
+            .collect();
+        files.sort();
+        return files
+            .iter()
+            .filter_map(|(_, p)| std::fs::read_to_string(p).ok())
+            .filter_map(|c| serde_json::from_str(&c).ok())
+            .collect();
+    }
+
+    let content = match std::fs::read_to_string(path) {
+        Ok(c) => c,
+        Err(_) => return Vec::new(),
+    };
+    match serde_json::from_str::<serde_json::Value>(&content) {
+        Ok(serde_json::Value::Array(events)) => events,
+        Ok(v) => v
+            .get("trajectory")
+            .or_else(|| v.get("events"))
+            .and_then(|t| t.as_array())
+            .cloned()
+            .unwrap_or_default(),
+        // JSONL fallback: one event per line
+        Err(_) => content
+            .lines()
+            .filter_map(|l| serde_json::from_str(l.trim()).ok())
+            .collect(),
+    }
+}
+
+pub fn parse_openhands_session(path: &Path) -> Option<OpenHandsSession> {
+    let events = load_events(path);
+
+    let mut messages = Vec::new();
+    let mut model = String::new();
+    let mut files_modified: Vec<String> = Vec::new();
+    let mut tools_used: Vec<String> = Vec::new();
+    let mut subagents: Vec<SubagentActivity> = Vec::new();
+    let mut first_ts: Option<DateTime<Utc>> = None;
+    let mut last_ts: Option<DateTime<Utc>> = None;
+    let mut input_tokens: Option<u64> = None;
+    let mut output_tokens: Option<u64> = None;
+    let mut reported_cost: Option<f64> = None;
+
+    for event in &events {
+        let ts = event
+            .get("timestamp")
+            .and_then(|v| v.as_str())
+            .and_then(parse_timestamp);
+        if ts.is_some() {
+            if first_ts.is_none() {
+                first_ts = ts;
+            }
+            last_ts = ts;
+        }
+
+        if let Some(m) = event
+            .pointer("/tool_call_metadata/model_response/model")
+            .and_then(|v| v.as_str())
+        {
+            model = m.to_string();
+        }
+
+        // Metrics are cumulative, so the last value seen wins.
+        if let Some(metrics) = event.get("llm_metrics") {
+            if let Some(c) = metrics.get("accumulated_cost").and_then(|v| v.as_f64()) {
+                reported_cost = Some(c);
+            }
+            if let Some(usage) = metrics.get("accumulated_token_usage") {
+                if let Some(p) = usage.get("prompt_tokens").and_then(|v| v.as_u64()) {
+                    input_tokens = Some(p);
+                }
+                if let Some(c) = usage.get("completion_tokens").and_then(|v| v.as_u64()) {
+                    output_tokens = Some(c);
+                }
+            }
+        }
+
+        let source = event.get("source").and_then(|v| v.as_str()).unwrap_or("");
+
+        if let Some(action) = event.get("action").and_then(|v| v.as_str()) {
+            let args = event.get("args");
+            match action {
+                "message" => {
+                    let text = args
+                        .and_then(|a| a.get("content"))
+                        .or_else(|| event.get("message"))
+                        .and_then(|v| v.as_str())
+                        .unwrap_or("");
+                    let role = if source == "user" {
+                        "user"
+                    } else {
+                        "assistant"
+                    };
+                    if !text.trim().is_empty() {
+                        messages.push(OpenHandsMessage {
+                            role: role.to_string(),
+                            text: text.to_string(),
+                        });
+                    }
+                    continue;
+                }
+                "system" | "recall" | "change_agent_state" | "null" => continue,
+                _ => {}
+            }
+
+            if source != "agent" {
+                continue;
+            }
+            if !tools_used.iter().any(|t| t == action) {
+                tools_used.push(action.to_string());
+            }
+            // Attribute the action to every delegate that is still running.
+            for sa in subagents.iter_mut().filter(|s| s.status == "started") {
+                if !sa.tools_used.iter().any(|t| t == action) {
+                    sa.tools_used.push(action.to_string());
+                }
+            }
+
+            match action {
+                "edit" | "write" => {
+                    if let Some(p) = args.and_then(|a| a.get("path")).and_then(|v| v.as_str()) {
+                        if !files_modified.iter().any(|f| f == p) {
+                            files_modified.push(p.to_string());
+                        }
+                    }
+                }
+                "delegate" => {
+                    let description = args
+                        .and_then(|a| a.pointer("/inputs/task"))
+                        .and_then(|v| v.as_str())
+                        .map(|s| s.chars().take(200).collect::<String>());
+                    subagents.push(SubagentActivity {
+                        agent_id: event.get("id").map(|v| v.to_string()),
+                        agent_type: args
+                            .and_then(|a| a.get("agent"))
+                            .and_then(|v| v.as_str())
+                            .map(|s| s.to_string()),
+                        description,
+                        status: "started".to_string(),
+                        started_at: ts,
+                        completed_at: None,
+                        tools_used: vec![],
+                        model: None,
+                        input_tokens: None,
+                        output_tokens: None,
+                        cost_usd: None,
+                        files_touched: vec![],
+                    });
+                }
+                "finish" => {
+                    if let Some(text) = args
+                        .and_then(|a| a.get("final_thought").or_else(|| a.get("outputs")))
+                        .and_then(|v| v.as_str())
+                        .filter(|t| !t.trim().is_empty())
+                    {
+                        messages.push(OpenHandsMessage {
+                            role: "assistant".to_string(),
+                            text: text.to_string(),
+                        });
+                    }
+                }
+                _ => {}
+            }
+        } else if event.get("observation").and_then(|v| v.as_str()) == Some("delegate") {
+            let cause = event.get("cause").map(|v| v.to_string());
+            if let Some(sa) = subagents
+                .iter_mut()
+                .find(|s| s.agent_id.is_some() && s.agent_id == cause)
+            {
+                sa.status = "completed".to_string();
+                sa.completed_at = ts;
+            }
+        }
+    }
+
+    if messages.is_empty() {
+        return None;
+    }
+
+    if model.is_empty() {
+        model = "openhands".to_string();
+    }
+
+    let session_id = path
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    Some(OpenHandsSession {
+        session_id,
+        model,
+        messages,
+        files_modified,
+        tools_used,
+        subagents,
+        timestamp: first_ts.unwrap_or_else(Utc::now),
+        end_timestamp: last_ts,
+        input_tokens,
+        output_tokens,
+        reported_cost,
+    })
+}
+
+pub fn import_session(path: &Path) -> Option<Receipt> {
+    let session = parse_openhands_session(path)?;
+    let cfg = config::load_config();
+    let cwd = std::env::current_dir()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let user = util::git_user();
+
+    let first_user_msg = session
+        .messages
+        .iter()
+        .find(|m| m.role == "user")
+        .map(|m| {
+            m.text
+                .chars()
+                .take(cfg.capture.max_prompt_length)
+                .collect::<String>()
+        })
+        .unwrap_or_default();
+
+    let prompt_summary = crate::core::redact::redact_secrets_with_config(&first_user_msg, &cfg);
+
+    use sha2::{Digest, Sha256};
+    let mut hasher = Sha256::new();
+    hasher.update(prompt_summary.as_bytes());
+    let prompt_hash = format!("sha256:{:x}", hasher.finalize());
+
+    let response_summary = session
+        .messages
+        .iter()
+        .rev()
+        .find(|m| m.role == "assistant")
+        .map(|m| m.text.chars().take(500).collect());
+
+    let files_changed: Vec<FileChange> = session
+        .files_modified
+        .iter()
+        .map(|f| FileChange {
+            // OpenHands runs in a sandbox mounted at /workspace
+            path: util::make_relative(f.strip_prefix("/workspace/").unwrap_or(f), &cwd),
+            line_range: (1, 1),
+            blob_hash: None,
+            additions: 0,
+            deletions: 0,
+            notebook_cells: vec![],
+            is_binary: false,
+            bytes_before: None,
+            bytes_after: None,
+            change_type: None,
+            old_path: None,
+            unstaged_ranges: Vec::new(),
+        })
+        .collect();
+
+    let final_input = session.input_tokens.unwrap_or_else(|| {
+        crate::core::pricing::estimate_tokens_from_chars(
+            session
+                .messages
+                .iter()
+                .filter(|m| m.role == "user")
+                .map(|m| m.text.len())
+                .sum(),
+        )
+    });
+    let final_output = session.output_tokens.unwrap_or_else(|| {
+        crate::core::pricing::estimate_tokens_from_chars(
+            session
+                .messages
+                .iter()
+                .filter(|m| m.role == "assistant")
+                .map(|m| m.text.len())
+                .sum(),
+        )
+    });
+    let cost = session.reported_cost.unwrap_or_else(|| {
+        crate::core::pricing::estimate_cost(&session.model, final_input, final_output)
+    });
+
+    let session_duration_secs = session
+        .end_timestamp
+        .map(|end| (end - session.timestamp).num_seconds().max(0) as u64);
+
+    let conversation: Vec<ConversationTurn> = session
+        .messages
+        .iter()
+        .enumerate()
+        .map(|(i, m)| ConversationTurn {
+            turn: (i as u32) + 1,
+            role: m.role.clone(),
+            content: crate::core::redact::redact_secrets_with_config(
+                &m.text
+                    .chars()
+                    .take(cfg.capture.max_prompt_length)
+                    .collect::<String>(),
+                &cfg,
+            ),
+            tool_name: None,
+            files_touched: None,
+        })
+        .collect();
+
+    let agents_spawned: Vec<String> = session
+        .subagents
+        .iter()
+        .filter_map(|s| s.agent_type.clone())
+        .fold(Vec::new(), |mut acc, t| {
+            if !acc.contains(&t) {
+                acc.push(t);
+            }
+            acc
+        });
+
+    let prompt_quality = Some(crate::core::prompt_eval::evaluate(&prompt_summary));
+
+    Some(Receipt {
+        id: Receipt::new_id(),
+        provider: "openhands".to_string(),
+        model: session.model,
+        session_id: session.session_id,
+        prompt_summary,
+        response_summary,
+        prompt_hash,
+        message_count: session.messages.len() as u32,
+        cost_usd: cost,
+        input_tokens: Some(final_input),
+        output_tokens: Some(final_output),
+        cache_read_tokens: None,
+        cache_creation_tokens: None,
+        timestamp: session.timestamp,
+        session_start: Some(session.timestamp),
+        session_end: session.end_timestamp,
+        session_duration_secs,
+        ai_response_time_secs: None,
+        user,
+        file_path: files_changed
+            .first()
+            .map(|f| f.path.clone())
+            .unwrap_or_default(),
+        line_range: (0, 0),
+        files_changed,
+        parent_receipt_id: None,
+        parent_session_id: None,
+        is_continuation: None,
+        continuation_depth: None,
+        prompt_number: Some(1),
+        total_additions: 0,
+        total_deletions: 0,
+        tools_used: session.tools_used,
+        mcp_servers: vec![],
+        environment: None,
+        agents_spawned,
+        subagent_activities: session.subagents,
+        concurrent_tool_calls: None,
+        user_decisions: vec![],
+        conversation: if conversation.is_empty() {
+            None
+        } else {
+            Some(conversation)
+        },
+        prompt_submitted_at: Some(session.timestamp),
+        prompt_duration_secs: None,
+        accepted_lines: None,
+        overridden_lines: None,
+        prompt_quality,
+        ticket: None,
+        manual_import: false,
+        housekeeping_prompts: None,
+        intent_summary: None,
+        heuristic: false,
+        cost_estimated: session.reported_cost.is_none()
+            && (session.input_tokens.is_none() || session.output_tokens.is_none()),
+        acceptance_estimated: false,
+        co_authors: Vec::new(),
+        plan_summary: None,
+        tool_use_id: None,
+    })
+}
+
+pub fn run_record_openhands(session_path: Option<&str>, force: bool) {
+    let sessions = if let Some(path) = session_path {
+        let p = PathBuf::from(path);
+        if !p.exists() {
+            eprintln!("[openhands] File not found: {}", path);
+            std::process::exit(1);
+        }
+        // A directory is either a single session (has events/) or a sessions root.
+        if p.is_dir() && !p.join("events").is_dir() {
+            list_sessions(&p)
+        } else {
+            vec![p]
+        }
+    } else {
+        match find_sessions_dir() {
+            Some(dir) => {
+                let sessions = list_sessions(&dir);
+                if sessions.is_empty() {
+                    eprintln!("[openhands] No sessions found in {}", dir.display());
+                    return;
+                }
+                sessions.into_iter().take(10).collect()
+            }
+            None => {
+                eprintln!("[openhands] Cannot find OpenHands sessions directory.");
+                eprintln!(
+                    "  Pass --session <path> to specify a session directory or trajectory file."
+                );
+                return;
+            }
+        }
+    };
+
+    let mut dedup = staging::ImportDeduper::new(force);
+
+    let mut count = 0usize;
+    for session in &sessions {
+        if let Some(receipt) = import_session(session) {
+            if dedup.stage(&receipt) {
+                count += 1;
+            }
+        }
+    }
+
+    dedup.report("[openhands]");
+
+    if count == 0 {
+        eprintln!("[openhands] No valid sessions found in the provided path(s).");
+    } else {
+        println!("[openhands] Recorded {} OpenHands session(s)", count);
+        println!("  Receipts staged. They will be attached on next git commit.");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRAJECTORY: &str = r#"[
+{"id":0,"timestamp":"2026-01-01T10:00:00.000000","source":"user","action":"message","args":{"content":"Fix the flaky login test"}},
+{"id":1,"timestamp":"2026-01-01T10:00:05.000000","source":"agent","action":"delegate","args":{"agent":"BrowsingAgent","inputs":{"task":"Read the CI logs"}}},
+{"id":2,"timestamp":"2026-01-01T10:00:20.000000","source":"agent","action":"browse","args":{"url":"https://ci.example.com"}},
+{"id":3,"timestamp":"2026-01-01T10:01:00.000000","source":"agent","observation":"delegate","cause":1,"content":"found timeout"},
+{"id":4,"timestamp":"2026-01-01T10:01:30.000000","source":"agent","action":"edit","args":{"path":"/workspace/tests/login_test.py"},"tool_call_metadata":{"model_response":{"model":"claude-sonnet-4-5"}},"llm_metrics":{"accumulated_cost":0.42,"accumulated_token_usage":{"prompt_tokens":9000,"completion_tokens":800}}},
+{"id":5,"timestamp":"2026-01-01T10:02:00.000000","source":"agent","action":"finish","args":{"final_thought":"Increased the wait timeout."}}
+]"#;
+
+    #[test]
+    fn test_parse_openhands_trajectory() {
+        let tmp = tempfile::NamedTempFile::new().unwrap();
+        std::fs::write(tmp.path(), TRAJECTORY).unwrap();
+
+        let s = parse_openhands_session(tmp.path()).unwrap();
+        assert_eq!(s.model, "claude-sonnet-4-5");
+        assert_eq!(s.messages.len(), 2);
+        assert_eq!(s.files_modified, vec!["/workspace/tests/login_test.py"]);
+        assert_eq!(s.input_tokens, Some(9000));
+        assert_eq!(s.output_tokens, Some(800));
+        assert_eq!(s.reported_cost, Some(0.42));
+        assert_eq!(s.subagents.len(), 1);
+        let sa = &s.subagents[0];
+        assert_eq!(sa.agent_type.as_deref(), Some("BrowsingAgent"));
+        assert_eq!(sa.status, "completed");
+        assert_eq!(sa.tools_used, vec!["browse"]);
+        assert_eq!(sa.description.as_deref(), Some("Read the CI logs"));
+    }
+
+    #[test]
+    fn test_load_events_from_session_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        let events = dir.path().join("events");
+        std::fs::create_dir(&events).unwrap();
+        std::fs::write(
+            events.join("10.json"),
+            r#"{"id":10,"source":"agent","action":"message","args":{"content":"second"}}"#,
+        )
+        .unwrap();
+        std::fs::write(
+            events.join("2.json"),
+            r#"{"id":2,"source":"user","action":"message","args":{"content":"first"}}"#,
+        )
+        .unwrap();
+
+        let s = parse_openhands_session(dir.path()).unwrap();
+        assert_eq!(s.messages[0].text, "first");
+        assert_eq!(s.messages[1].role, "assistant");
+    }
+
+    #[test]
+    fn test_parse_timestamp_naive_and_rfc3339() {
+        assert!(parse_timestamp("2026-01-01T10:00:00.123456").is_some());
+        assert!(parse_timestamp("2026-01-01T10:00:00Z").is_some());
+        assert!(parse_timestamp("yesterday").is_none());
+    }
+}
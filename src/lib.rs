@@ -0,0 +1,49 @@
+//! `blameprompt_core`: read and write BlamePrompt receipts from Rust.
+//!
+//! The `blameprompt` CLI is built on this library. Other tools (CI bots,
+//! dashboards) can use it to consume receipts directly instead of running the
+//! CLI and parsing its tables. Everything operates on the git repository in
+//! the current working directory, like the CLI does.
+//!
+//! The stable surface is re-exported at the crate root:
+//!
+//! - receipts: [`Receipt`], [`FileChange`], [`NotePayload`], [`SCHEMA_VERSION`]
+//! - notes I/O: [`read_receipts_for_commit`], [`write_receipts_for_commit`],
+//!   [`list_commits_with_notes`], and [`ReceiptStore`] / [`active_store`] for the
+//!   configured storage backend
+//! - schema: [`parse_note`] upgrades any stored note to the current schema
+//! - transcripts: [`parse_claude_jsonl`] and its [`Transcript`] / [`Message`] types
+//! - provenance: [`compute_provenance`] (per-line `git blame` × receipts) and
+//!   [`collapse_to_ranges`]
+//! - config: [`load_config`] / [`BlamePromptConfig`] (`.blamepromptrc`)
+//!
+//! ```no_run
+//! use blameprompt_core::{list_commits_with_notes, read_receipts_for_commit};
+//!
+//! for sha in list_commits_with_notes() {
+//!     if let Some(note) = read_receipts_for_commit(&sha) {
+//!         let cost: f64 = note.receipts.iter().map(|r| r.cost_usd).sum();
+//!         println!("{} {} receipt(s) ${:.2}", &sha[..8], note.receipts.len(), cost);
+//!     }
+//! }
+//! ```
+//!
+//! The modules below are public so the CLI can use them, but anything not
+//! re-exported here may change between releases.
+
+pub mod commands;
+pub mod core;
+pub mod git;
+pub mod integrations;
+
+pub use crate::commands::check_provenance::{
+    collapse_to_ranges, compute_provenance, LineProvenance, ProvenanceRange,
+};
+pub use crate::core::config::{load_config, BlamePromptConfig};
+pub use crate::core::migrate::parse as parse_note;
+pub use crate::core::receipt::{FileChange, NotePayload, Receipt, SCHEMA_VERSION};
+pub use crate::core::transcript::{parse_claude_jsonl, Message, Transcript, TranscriptParseResult};
+pub use crate::git::notes::{
+    list_commits_with_notes, read_receipts_for_commit, write_receipts_for_commit,
+};
+pub use crate::git::storage::{active as active_store, ReceiptStore};
@@ -1,18 +1,27 @@
-mod commands;
-mod core;
-mod git;
-mod integrations;
-
-use clap::{Parser, Subcommand};
+use blameprompt_core::{commands, core, git, integrations};
+use clap::{Args, CommandFactory, Parser, Subcommand};
+use core::term;
 
 /// BlamePrompt: Your AI skills deserve a portfolio.
 /// Track your AI coding activity across 15 agents. Build your developer score.
 #[derive(Parser)]
 #[command(name = "blameprompt", version = env!("CARGO_PKG_VERSION"), about = "Your AI skills deserve a portfolio")]
 struct Cli {
-    /// Enable verbose debug output
+    /// Log to stderr: -v info, -vv debug, -vvv trace
+    #[arg(long, short, global = true, action = clap::ArgAction::Count)]
+    verbose: u8,
+
+    /// Suppress banners and hints (results and errors are still printed)
+    #[arg(long, short, global = true)]
+    quiet: bool,
+
+    /// Plain output for piping: no color, no symbols, borderless tables
+    #[arg(long, global = true)]
+    plain: bool,
+
+    /// Use ASCII instead of emoji and symbols
     #[arg(long, global = true)]
-    verbose: bool,
+    no_emoji: bool,
 
     #[command(subcommand)]
     command: Commands,
@@ -34,6 +43,16 @@ enum Commands {
         /// Configure git template for all future repos
         #[arg(long)]
         global: bool,
+        /// Non-interactive setup for CI: fetch notes and sync the cache, no hooks
+        #[arg(long, conflicts_with = "global")]
+        ci: bool,
+    },
+
+    /// Guided setup: pick integrations, privacy level and notes sharing, then write .blamepromptrc
+    Setup {
+        /// Accept every proposed setting without asking
+        #[arg(long)]
+        yes: bool,
     },
 
     /// Install Claude Code + git hooks (legacy, same as 'init')
@@ -47,6 +66,12 @@ enum Commands {
         /// Remove everything including Git Notes and binary info
         #[arg(long)]
         purge: bool,
+        /// List what would be removed without changing anything
+        #[arg(long)]
+        dry_run: bool,
+        /// Remove only these parts (repeat or comma-separate); default: everything but notes
+        #[arg(long, value_delimiter = ',', value_parser = ["hooks", "wrapper", "cache", "notes"], conflicts_with = "purge")]
+        component: Vec<String>,
     },
 
     /// Update BlamePrompt to the latest (or a specific) version
@@ -64,11 +89,20 @@ enum Commands {
 
     /// Show line-by-line AI/human attribution for a file
     Blame {
-        /// File to analyze
-        file: String,
-        /// Output format: table, json
+        /// File to analyze (optional with --format sidecar: all files with receipts)
+        file: Option<String>,
+        /// Output format: table, json, sidecar (per-file JSON for editor plugins)
         #[arg(long, default_value = "table")]
         format: String,
+        /// Directory for --format sidecar output
+        #[arg(long, default_value = ".blameprompt/attribution")]
+        out_dir: String,
+        /// Attribute uncommitted lines from staged receipts (marked provisional)
+        #[arg(long)]
+        include_uncommitted: bool,
+        /// Roll attribution up to functions, classes and other symbols (Rust, Python, JS/TS, Go)
+        #[arg(long)]
+        by_symbol: bool,
     },
 
     /// Display all AI receipts attached to a specific commit
@@ -78,20 +112,59 @@ enum Commands {
         /// Output format: table, json
         #[arg(long, default_value = "table")]
         format: String,
+        /// List each subagent with its tokens, cost and files instead of the receipts
+        #[arg(long)]
+        subagents: bool,
     },
 
     /// Search across stored prompts
     Search {
-        /// Search query
-        query: String,
+        /// Search query (optional when a filter is given)
+        query: Option<String>,
         /// Maximum number of results (default: 50)
         #[arg(long, default_value = "50")]
         limit: usize,
         /// Output format: table, json
         #[arg(long, default_value = "table")]
         format: String,
+        /// Only receipts whose model contains this
+        #[arg(long)]
+        model: Option<String>,
+        /// Only receipts from this provider (e.g. claude, cursor)
+        #[arg(long)]
+        provider: Option<String>,
+        /// Only receipts whose author (name or email) contains this
+        #[arg(long)]
+        author: Option<String>,
+        /// Only receipts that changed a file matching this glob (e.g. 'src/**/*.rs')
+        #[arg(long)]
+        file: Option<String>,
+        /// Only receipts from this date on (YYYY-MM-DD)
+        #[arg(long)]
+        from: Option<String>,
+        /// Only receipts up to and including this date (YYYY-MM-DD)
+        #[arg(long)]
+        to: Option<String>,
+        /// Only receipts costing at least this many USD
+        #[arg(long)]
+        min_cost: Option<f64>,
+        /// Result order
+        #[arg(long, default_value = "relevance", value_parser = ["relevance", "date", "cost"])]
+        sort: String,
+        /// Treat the query as a regular expression
+        #[arg(long)]
+        regex: bool,
+        /// Match the query's case exactly
+        #[arg(long)]
+        case_sensitive: bool,
+        /// Also search staged receipts not committed yet (marked provisional)
+        #[arg(long)]
+        include_uncommitted: bool,
     },
 
+    /// Serve provenance to agents over the Model Context Protocol (stdio)
+    McpServe,
+
     /// Show complete AI audit trail with filters
     Audit {
         /// Start date filter (e.g., 2026-01-01)
@@ -109,6 +182,15 @@ enum Commands {
         /// Include uncommitted/staged receipts
         #[arg(long)]
         include_uncommitted: bool,
+        /// Aggregate receipts instead of listing commits: session, author, day, model, file
+        #[arg(long)]
+        group_by: Option<String>,
+        /// Keep receipts whose files were all deleted by later commits
+        #[arg(long)]
+        include_deleted: bool,
+        /// Replace file paths with stable pseudonyms (extensions kept) for sharing outside the team
+        #[arg(long)]
+        anonymize_paths: bool,
     },
 
     /// Show aggregated AI usage statistics
@@ -116,6 +198,27 @@ enum Commands {
         /// Export format: json, csv
         #[arg(long)]
         export: Option<String>,
+        /// Per-developer breakdown (requires [analytics] per_author = true)
+        #[arg(long)]
+        by_author: bool,
+        /// Cost rollup per ticket/issue ID (from branch names and prompts)
+        #[arg(long, conflicts_with = "by_author")]
+        by_ticket: bool,
+        /// Add a daily or weekly trend of acceptance rate, AI line share and cost
+        #[arg(long, value_parser = ["daily", "weekly"], conflicts_with_all = ["by_author", "by_ticket"])]
+        trend: Option<String>,
+        /// Tokens, cost and files of subagents, per agent type
+        #[arg(long, conflicts_with_all = ["by_author", "by_ticket", "trend"])]
+        subagents: bool,
+        /// Keep receipts whose files were all deleted by later commits
+        #[arg(long)]
+        include_deleted: bool,
+        /// Also count staged receipts not committed yet (provisional)
+        #[arg(long, conflicts_with_all = ["by_author", "by_ticket", "subagents"])]
+        include_uncommitted: bool,
+        /// Replace file paths with stable pseudonyms (extensions kept) for sharing outside the team
+        #[arg(long, conflicts_with_all = ["by_author", "by_ticket", "subagents"])]
+        anonymize_paths: bool,
     },
 
     /// Alias for analytics
@@ -127,6 +230,27 @@ enum Commands {
         /// Export format: json, csv
         #[arg(long)]
         export: Option<String>,
+        /// Per-developer breakdown (requires [analytics] per_author = true)
+        #[arg(long)]
+        by_author: bool,
+        /// Cost rollup per ticket/issue ID (from branch names and prompts)
+        #[arg(long, conflicts_with = "by_author")]
+        by_ticket: bool,
+        /// Add a daily or weekly trend of acceptance rate, AI line share and cost
+        #[arg(long, value_parser = ["daily", "weekly"], conflicts_with_all = ["by_author", "by_ticket"])]
+        trend: Option<String>,
+        /// Tokens, cost and files of subagents, per agent type
+        #[arg(long, conflicts_with_all = ["by_author", "by_ticket", "trend"])]
+        subagents: bool,
+        /// Keep receipts whose files were all deleted by later commits
+        #[arg(long)]
+        include_deleted: bool,
+        /// Also count staged receipts not committed yet (provisional)
+        #[arg(long, conflicts_with_all = ["by_author", "by_ticket", "subagents"])]
+        include_uncommitted: bool,
+        /// Replace file paths with stable pseudonyms (extensions kept) for sharing outside the team
+        #[arg(long, conflicts_with_all = ["by_author", "by_ticket", "subagents"])]
+        anonymize_paths: bool,
     },
 
     /// Generate comprehensive markdown report
@@ -146,6 +270,31 @@ enum Commands {
         /// Include uncommitted/staged receipts
         #[arg(long)]
         include_uncommitted: bool,
+        /// Replace file paths with stable pseudonyms (extensions kept) for sharing outside the team
+        #[arg(long)]
+        anonymize_paths: bool,
+    },
+
+    /// Export AI-line density per directory/file as JSON or a treemap SVG
+    Heatmap {
+        /// Output file; a .svg extension renders a treemap, anything else writes JSON
+        #[arg(long, default_value = "blameprompt-heatmap.svg")]
+        output: String,
+    },
+
+    /// Write a README badge with the repo's AI share and receipt coverage
+    Badge {
+        /// Output file; a .json extension writes a shields.io endpoint document, anything else an SVG
+        #[arg(long, default_value = "blameprompt-badge.svg")]
+        output: String,
+    },
+
+    /// Show the receipts and AI/human line origins of a bisect culprit
+    BisectReport {
+        /// First bad commit (e.g. refs/bisect/bad)
+        bad: String,
+        /// Last known good commit
+        good: String,
     },
 
     /// Show annotated diff with AI/human attribution
@@ -157,20 +306,54 @@ enum Commands {
     /// Install transparent git wrapper (auto-attaches receipts on every commit)
     InstallGitWrap,
 
+    /// Auto-attach receipts through a global core.hooksPath that chains to existing hooks (no git wrapper)
+    InstallHooksPath,
+
     /// Remap BlamePrompt notes after rebase/amend (called by post-rewrite hook, internal)
     RebaseNotes,
 
     /// Push BlamePrompt notes to origin
-    Push,
+    Push {
+        /// Notes commits per push; an interrupted push resumes after the last batch that landed
+        #[arg(long, default_value_t = commands::sync::DEFAULT_BATCH_SIZE)]
+        batch_size: usize,
+        /// Retries of a failed transfer, with exponential backoff
+        #[arg(long, default_value_t = commands::sync::DEFAULT_RETRIES)]
+        retries: u32,
+        /// Drop notes of commits that no longer exist before pushing, so origin stops serving them
+        #[arg(long)]
+        prune_remote: bool,
+    },
+
+    /// Step through AI commits nobody has reviewed yet and mark them reviewed or flagged
+    Review {
+        /// Print the queue and exit
+        #[arg(long)]
+        list: bool,
+        /// Also revisit flagged commits
+        #[arg(long)]
+        flagged: bool,
+    },
 
     /// Fetch BlamePrompt notes from origin
-    Pull,
+    Pull {
+        /// Retries of a failed fetch, with exponential backoff
+        #[arg(long, default_value_t = commands::sync::DEFAULT_RETRIES)]
+        retries: u32,
+    },
 
-    /// Dry-run the redaction engine on a file
+    /// Dry-run the redaction engine on a file or on stored receipts
+    #[command(group(clap::ArgGroup::new("source").required(true).args(["test", "receipt", "staged"])))]
     Redact {
         /// File to test redaction on
         #[arg(long)]
-        test: String,
+        test: Option<String>,
+        /// Preview the current rules on a stored receipt (id or prefix), before/after
+        #[arg(long)]
+        receipt: Option<String>,
+        /// Preview the current rules on every staged receipt, before/after
+        #[arg(long)]
+        staged: bool,
     },
 
     /// Import a Claude Code JSONL transcript
@@ -181,6 +364,42 @@ enum Commands {
         /// AI provider name (claude, cursor, copilot, openai …)
         #[arg(long)]
         provider: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+
+    /// Watch the working tree and stage provisional receipts for machine-speed edits
+    /// (last resort for AI tools without hooks or transcripts)
+    WatchFiles {
+        /// Tool the edits are attributed to (e.g. aider, zed, jetbrains-ai)
+        #[arg(long)]
+        provider: String,
+        /// Model name to record, if known
+        #[arg(long)]
+        model: Option<String>,
+        /// Smallest insertion (in lines) that can count as AI-written
+        #[arg(long, default_value_t = 5)]
+        min_lines: u32,
+        /// Typing speed (characters/second) above which an insertion counts as machine-written
+        #[arg(long, default_value_t = 30.0)]
+        max_human_cps: f64,
+        /// Seconds without machine-speed edits that end a burst (one receipt per burst)
+        #[arg(long, default_value_t = 3)]
+        burst_secs: u64,
+    },
+
+    /// Print a bash/zsh snippet that captures files changed by terminal AI tools (gh copilot, sgpt, aichat, llm)
+    ShellIntegration {
+        /// Target shell
+        #[arg(default_value = "bash", value_parser = ["bash", "zsh"])]
+        shell: String,
+    },
+
+    /// Snapshot / record file changes around a terminal AI command (called by the shell integration, internal)
+    #[command(hide = true)]
+    ShellCapture {
+        #[command(subcommand)]
+        action: ShellCaptureAction,
     },
 
     /// Import recent AI chat sessions from Cursor IDE
@@ -188,6 +407,17 @@ enum Commands {
         /// Path to a specific Cursor workspace storage directory or state.vscdb
         #[arg(long)]
         workspace: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
+        /// Import every Cursor workspace that includes this repo without asking
+        #[arg(long, conflicts_with = "workspace")]
+        all: bool,
+        /// Keep running and stage new or updated chat sessions as they appear
+        #[arg(long)]
+        follow: bool,
+        /// Seconds between database checks with --follow
+        #[arg(long, default_value_t = 5, requires = "follow")]
+        interval: u64,
     },
 
     /// Import recent AI chat sessions from GitHub Copilot (VS Code)
@@ -195,6 +425,8 @@ enum Commands {
         /// Path to a specific VS Code workspace storage directory or state.vscdb
         #[arg(long)]
         workspace: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import OpenAI Codex CLI session transcripts
@@ -202,6 +434,8 @@ enum Commands {
         /// Path to a specific session file or directory
         #[arg(long)]
         session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import Google Gemini CLI session transcripts
@@ -209,6 +443,8 @@ enum Commands {
         /// Path to a specific session file or directory
         #[arg(long)]
         session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import recent AI chat sessions from Windsurf (Codeium)
@@ -216,6 +452,8 @@ enum Commands {
         /// Path to a specific Windsurf workspace storage directory or state.vscdb
         #[arg(long)]
         workspace: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import Antigravity session transcripts
@@ -223,6 +461,8 @@ enum Commands {
         /// Path to a specific session file or directory
         #[arg(long)]
         session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import Continue AI coding assistant session transcripts
@@ -230,6 +470,8 @@ enum Commands {
         /// Path to a specific session file or directory
         #[arg(long)]
         session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import Droid CLI AI coding agent session transcripts
@@ -237,6 +479,8 @@ enum Commands {
         /// Path to a specific session file or directory
         #[arg(long)]
         session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import JetBrains Junie AI coding assistant session transcripts
@@ -244,6 +488,8 @@ enum Commands {
         /// Path to a specific session file or directory
         #[arg(long)]
         session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import Atlassian Rovo Dev AI coding agent session transcripts
@@ -251,6 +497,8 @@ enum Commands {
         /// Path to a specific session file or directory
         #[arg(long)]
         session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import Sourcegraph Amp AI coding agent session transcripts
@@ -258,6 +506,8 @@ enum Commands {
         /// Path to a specific session file or directory
         #[arg(long)]
         session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
     },
 
     /// Import OpenCode terminal AI coding tool session transcripts
@@ -265,6 +515,44 @@ enum Commands {
         /// Path to a specific session file or directory
         #[arg(long)]
         session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+
+    /// Import Block Goose autonomous agent session logs
+    RecordGoose {
+        /// Path to a specific session file or directory
+        #[arg(long)]
+        session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+
+    /// Import OpenHands agent trajectories
+    RecordOpenhands {
+        /// Path to a session directory, sessions root, or trajectory JSON file
+        #[arg(long)]
+        session: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+
+    /// Import ChatGPT (chatgpt.com) conversations from an official data export
+    RecordChatgpt {
+        /// Path to the unzipped export directory or its conversations.json
+        #[arg(long)]
+        export: String,
+        /// Only consider conversations updated within this many days
+        #[arg(long, default_value = "14")]
+        days: u32,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+
+    /// Mine prompt history for reusable patterns
+    Prompts {
+        #[command(subcommand)]
+        action: PromptsAction,
     },
 
     /// Manage the local SQLite cache
@@ -273,6 +561,55 @@ enum Commands {
         action: CacheAction,
     },
 
+    /// Upgrade stored notes to the current receipt schema (dry run unless --write)
+    Migrate {
+        /// Rewrite upgradable notes in place
+        #[arg(long)]
+        write: bool,
+    },
+
+    /// Erase one person's or session's receipts from notes, staging and the cache (dry run unless --write)
+    Forget {
+        /// Email address of the person whose receipts to erase
+        #[arg(long, conflicts_with = "session", required_unless_present = "session")]
+        author: Option<String>,
+        /// Session ID whose receipts to erase
+        #[arg(long)]
+        session: Option<String>,
+        /// Keep the receipts for totals but strip the author and all prompt text
+        #[arg(long)]
+        anonymize: bool,
+        /// Apply the changes (otherwise only report what would be erased)
+        #[arg(long)]
+        write: bool,
+    },
+
+    /// Report how much space receipts take (notes, conversations, cache) and how fast it grows
+    StorageStats {
+        /// Number of largest payloads to list
+        #[arg(long, default_value = "10")]
+        top: usize,
+    },
+
+    /// Check every note for corruption (bad JSON, missing commits/blobs, odd timestamps)
+    LintNotes {
+        /// Rewrite notes whose problems can be repaired
+        #[arg(long)]
+        fix: bool,
+    },
+
+    /// Export or import all receipts as a single portable archive
+    Bundle {
+        #[command(subcommand)]
+        action: BundleAction,
+    },
+
+    /// Background sync: push/pull notes, refresh the cache, import Cursor/Codex sessions
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonAction,
+    },
+
     /// Scan AI model licenses for compliance issues
     LicenseScan {
         /// Output file path
@@ -311,16 +648,89 @@ enum Commands {
     /// Print count of staged receipts (used by git hooks, internal)
     StagingCount,
 
+    /// Suggest a commit message body summarizing the staged AI contribution
+    CommitSummary {
+        /// Prefix each line with "# " so git drops it unless kept
+        #[arg(long)]
+        comment: bool,
+    },
+
     /// Attach staged receipts to HEAD as git notes and clear staging (used by git hooks, internal)
     Attach {
         /// Amend the commit message to append [AI: XX%] annotation
         #[arg(long)]
         annotate: bool,
+        /// Print the receipts, files and acceptance stats that would be attached; write nothing
+        #[arg(long)]
+        dry_run: bool,
+        /// Output format with --dry-run: text, json
+        #[arg(long, default_value = "text")]
+        format: String,
+    },
+
+    /// Compute the acceptance stats and line origins of an attached note (run by attach, internal)
+    Finalize {
+        /// Commit whose note to finalize
+        #[arg(default_value = "HEAD")]
+        commit: String,
+    },
+
+    /// Attach staged receipts left behind by commits the post-commit hook missed (e.g. --no-verify)
+    Recover {
+        /// Attach without asking
+        #[arg(long)]
+        yes: bool,
+    },
+
+    /// Manage the organization-wide config
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
     },
 
     /// Run diagnostic checks on your BlamePrompt installation
     Doctor,
 
+    /// Read the BlamePrompt log file (~/.blameprompt/logs/blameprompt.log)
+    Logs {
+        #[command(subcommand)]
+        action: LogsAction,
+    },
+
+    /// Turn off prompt capture for this repository (or everywhere with --global)
+    Disable {
+        /// Disable for all repositories
+        #[arg(long)]
+        global: bool,
+    },
+
+    /// Turn prompt capture back on
+    Enable {
+        /// Enable for all repositories
+        #[arg(long)]
+        global: bool,
+    },
+
+    /// Export every commit's receipts as another provenance tool's notes (git-ai: refs/notes/ai)
+    Export {
+        /// Target format
+        #[arg(long, value_parser = ["git-ai"])]
+        format: String,
+        /// Overwrite notes the other tool already has
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Import another provenance tool's notes as receipts (git-ai: refs/notes/ai)
+    Import {
+        /// Source format
+        #[arg(long, value_parser = ["git-ai"])]
+        format: String,
+        /// Also import into commits that already have BlamePrompt receipts, merging into their note
+        #[arg(long)]
+        force: bool,
+    },
+
     /// Export blameprompt notes for a commit to Agent Trace v0.1.0 format
     ExportAgentTrace {
         /// Commit reference (default: HEAD)
@@ -331,6 +741,42 @@ enum Commands {
     ImportAgentTrace {
         /// Commit reference (default: HEAD)
         commit: Option<String>,
+        /// Read the record from a JSON file instead of the commit's note
+        #[arg(long, conflicts_with = "commit")]
+        file: Option<String>,
+        /// Only check the record against the spec; exits non-zero on violations
+        #[arg(long)]
+        validate: bool,
+    },
+
+    /// Record the pull request commits landed through (detects merged PRs when no PR is given)
+    LinkPr {
+        /// PR number or URL (omit to detect merge/squash commits in recent history)
+        pr: Option<String>,
+        /// Commits to link (default: commits on this branch not on the base branch)
+        #[arg(long = "commit")]
+        commits: Vec<String>,
+        /// How many first-parent commits to scan when detecting
+        #[arg(long, default_value = "200")]
+        limit: usize,
+        /// Replace links to a different PR
+        #[arg(long)]
+        force: bool,
+    },
+
+    /// Summarize the receipts between the previous tag and <TAG> and attach it to the tag
+    ReleaseSummary {
+        /// Release tag to summarize
+        tag: String,
+        /// Start of the range (default: the previous tag)
+        #[arg(long)]
+        from: Option<String>,
+        /// Output format: md, json
+        #[arg(long, default_value = "md")]
+        format: String,
+        /// Also upload the summary as an asset of the GitHub Release (needs GITHUB_TOKEN)
+        #[arg(long)]
+        upload: bool,
     },
 
     /// Post AI attribution summary as a GitHub PR comment
@@ -341,6 +787,47 @@ enum Commands {
         /// Repository slug (owner/repo, auto-detected from remote if omitted)
         #[arg(long)]
         repo: Option<String>,
+        /// Also post a review comment on each AI-generated hunk in the PR diff
+        #[arg(long)]
+        per_file: bool,
+        /// Append a markdown summary to this file (defaults to $GITHUB_STEP_SUMMARY)
+        #[arg(long, num_args = 0..=1, default_missing_value = "")]
+        summary_file: Option<String>,
+    },
+
+    /// Report policy results (coverage, secrets, injection) as a GitHub Check Run with inline annotations
+    GithubCheck {
+        /// Repository slug (owner/repo, auto-detected from remote if omitted)
+        #[arg(long)]
+        repo: Option<String>,
+        /// Commit to attach the check to (defaults to the PR head)
+        #[arg(long)]
+        sha: Option<String>,
+    },
+
+    /// Manage policy exemptions honored by github-check and gerrit-comment
+    Policy {
+        #[command(subcommand)]
+        action: PolicyAction,
+    },
+
+    /// Post AI attribution as a Gerrit review, optionally voting on a label
+    GerritComment {
+        /// Gerrit base URL (defaults to $GERRIT_URL)
+        #[arg(long)]
+        url: Option<String>,
+        /// Change number or ID (defaults to $GERRIT_CHANGE_NUMBER)
+        #[arg(long)]
+        change: Option<String>,
+        /// Patchset revision (defaults to $GERRIT_PATCHSET_REVISION, then "current")
+        #[arg(long)]
+        revision: Option<String>,
+        /// Label to vote on when receipt coverage meets the policy (e.g. AI-Provenance)
+        #[arg(long)]
+        label: Option<String>,
+        /// Vote value for --label
+        #[arg(long, default_value_t = 1, allow_hyphen_values = true)]
+        label_value: i32,
     },
 
     /// Generate a hackathon fairness verification report
@@ -355,11 +842,84 @@ enum Commands {
         #[arg(long, default_value = "./hackathon-report.md")]
         output: String,
         /// Filter to a specific participant (git user name or email)
-        #[arg(long)]
+        #[arg(long, conflicts_with = "team")]
         author: Option<String>,
+        /// Combined report for several participants (comma-separated names or emails)
+        #[arg(long, value_delimiter = ',')]
+        team: Vec<String>,
+        /// Also write a CSV leaderboard ranked by integrity score (requires --team)
+        #[arg(long, requires = "team")]
+        leaderboard: Option<String>,
         /// Include uncommitted/staged receipts
         #[arg(long)]
         include_uncommitted: bool,
+        /// Cross-check commit dates against push times on this GitHub repo ("owner/repo"; needs GITHUB_TOKEN)
+        #[arg(long)]
+        github_repo: Option<String>,
+    },
+
+    /// DORA-style metrics for AI-assisted changes (lead time, change failure, rework)
+    Metrics {
+        /// Start date filter (e.g., 2026-01-01)
+        #[arg(long)]
+        from: Option<String>,
+        /// End date filter (e.g., 2026-02-09)
+        #[arg(long)]
+        to: Option<String>,
+        /// Branch AI commits are merged into (default: origin/HEAD, main or master)
+        #[arg(long)]
+        base: Option<String>,
+        /// Output format: table, json
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Per-student AI usage report for a classroom or take-home assignment
+    AssignmentReport {
+        /// Rubric file (TOML: title, assignment text, students, thresholds)
+        #[arg(long)]
+        rubric: String,
+        /// Students to include (comma-separated names or emails); defaults to the rubric's list
+        #[arg(long, value_delimiter = ',')]
+        students: Vec<String>,
+        /// Output file path
+        #[arg(long, default_value = "./assignment-report.md")]
+        output: String,
+        /// Output format: markdown, json
+        #[arg(long, default_value = "markdown")]
+        format: String,
+    },
+
+    /// Flag integrity anomalies (pre-written code, batch commits, duplicate prompts, ...)
+    Anomalies {
+        /// Start date filter (e.g., 2026-01-01)
+        #[arg(long)]
+        from: Option<String>,
+        /// End date filter (e.g., 2026-02-09)
+        #[arg(long)]
+        to: Option<String>,
+        /// Filter by author name
+        #[arg(long)]
+        author: Option<String>,
+        /// Output format: table, json
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Summarize the team's AI activity, spend, anomalies and budget for a week or month
+    Digest {
+        /// Period covered, ending today: week or month
+        #[arg(long, default_value = "week")]
+        period: String,
+        /// Output format: text, email (HTML email body), json
+        #[arg(long, default_value = "text")]
+        format: String,
+        /// Write to this file instead of stdout
+        #[arg(long)]
+        output: Option<String>,
+        /// Email the HTML digest to `[digest] to` over SMTP
+        #[arg(long)]
+        send: bool,
     },
 
     /// Show line-by-line AI provenance for a file
@@ -369,6 +929,37 @@ enum Commands {
         /// Show provenance for a specific line number
         #[arg(long)]
         line: Option<u32>,
+        /// Recompute from git blame instead of using the provenance cache
+        #[arg(long)]
+        no_cache: bool,
+        /// Attribute uncommitted lines from staged receipts (marked provisional)
+        #[arg(long)]
+        include_uncommitted: bool,
+    },
+
+    /// Before deleting or rewriting code: the prompts behind it, their review status and sibling files
+    Impact {
+        /// File, optionally with lines (src/auth.rs:10-40)
+        target: String,
+        /// Output format: table or json
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
+
+    /// Graph of sessions, prompts, files and commits for Graphviz or Mermaid
+    Graph {
+        /// Output format: dot or mermaid
+        #[arg(long, default_value = "dot")]
+        format: String,
+        /// Start date filter (e.g., 2026-01-01)
+        #[arg(long)]
+        from: Option<String>,
+        /// End date filter (e.g., 2026-02-09)
+        #[arg(long)]
+        to: Option<String>,
+        /// Write to this file instead of stdout
+        #[arg(long, short)]
+        output: Option<String>,
     },
 
     /// Log in to BlamePrompt Cloud via GitHub
@@ -394,6 +985,23 @@ enum Commands {
     /// Open your BlamePrompt dashboard in the browser
     Dash,
 
+    /// pre-receive helper: validate pushed notes (size, schema version, signatures)
+    ServerHook,
+
+    /// Print a shell completion script (bash, zsh, fish, powershell, elvish)
+    Completions {
+        /// Target shell
+        shell: clap_complete::Shell,
+    },
+
+    /// Write man pages for blameprompt and every subcommand
+    #[command(hide = true)]
+    Mangen {
+        /// Output directory
+        #[arg(long, default_value = "./man")]
+        out_dir: String,
+    },
+
     /// Show or edit your BlamePrompt profile
     Profile {
         /// Open profile settings in browser
@@ -402,87 +1010,162 @@ enum Commands {
     },
 }
 
+/// Flags shared by the session import commands (`record`, `record-*`).
+#[derive(Args)]
+struct ImportArgs {
+    /// Re-import sessions even if they were already staged or attached
+    #[arg(long)]
+    force: bool,
+}
+
 #[derive(Subcommand)]
 enum CacheAction {
     /// Sync Git Notes into the local SQLite cache for fast queries
     Sync,
 }
 
-/// Get the blob SHA stored in HEAD for a given file path.
-fn get_head_blob(file_path: &str) -> Option<String> {
-    let spec = format!("HEAD:{}", file_path);
-    std::process::Command::new("git")
-        .args(["rev-parse", &spec])
-        .output()
-        .ok()
-        .filter(|o| o.status.success())
-        .and_then(|o| String::from_utf8(o.stdout).ok())
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
+#[derive(Subcommand)]
+enum ShellCaptureAction {
+    /// Save the changed tracked files and print the snapshot path
+    Snapshot,
+    /// Stage a heuristic receipt for files changed since the snapshot
+    Record {
+        /// Terminal provider tag (e.g. gh-copilot)
+        #[arg(long)]
+        provider: String,
+        /// Path printed by `shell-capture snapshot`
+        #[arg(long)]
+        snapshot: String,
+        /// The command that ran
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
 }
 
-/// Retrieve lines from a git blob by SHA.
-fn get_blob_lines(blob_sha: &str) -> Vec<String> {
-    std::process::Command::new("git")
-        .args(["cat-file", "-p", blob_sha])
-        .output()
-        .ok()
-        .filter(|o| o.status.success())
-        .and_then(|o| String::from_utf8(o.stdout).ok())
-        .map(|s| s.lines().map(String::from).collect())
-        .unwrap_or_default()
+#[derive(Subcommand)]
+enum PromptsAction {
+    /// Cluster similar prompts and rank the most reused patterns
+    Top {
+        /// Number of patterns to show
+        #[arg(long, default_value_t = 20)]
+        limit: usize,
+        /// Only show patterns used at least this many times
+        #[arg(long, default_value_t = 2)]
+        min_uses: usize,
+        /// Estimated similarity (0.0-1.0) for two prompts to share a pattern
+        #[arg(long, default_value_t = 0.6)]
+        similarity: f64,
+        /// Output format: table, json
+        #[arg(long, default_value = "table")]
+        format: String,
+    },
 }
 
-/// Count how many lines from `staging_blob` are present in `head_blob` (accepted)
-/// vs absent (overridden by human edits).
-fn count_accepted_overridden(staging_blob: &str, head_blob: &str) -> (u32, u32) {
-    let staging_lines = get_blob_lines(staging_blob);
-    let head_lines = get_blob_lines(head_blob);
+#[derive(Subcommand)]
+enum ConfigAction {
+    /// Fetch the org config named by `[org] source`, verify its signature and cache it
+    Sync,
+    /// Print the effective config after layering system, user, repo, env and org settings
+    Show {
+        /// List every setting with the file or variable it came from
+        #[arg(long)]
+        origin: bool,
+    },
+}
 
-    let head_set: std::collections::HashSet<&str> = head_lines.iter().map(|s| s.as_str()).collect();
+#[derive(Subcommand)]
+enum PolicyAction {
+    /// Record an exemption in .blameprompt-exemptions.toml (commit it with the change)
+    Exempt {
+        /// Rule to exempt: coverage, secret or injection
+        #[arg(long)]
+        rule: String,
+        /// Glob of repository paths the exemption covers
+        #[arg(long)]
+        path: String,
+        /// Why the finding is acceptable
+        #[arg(long)]
+        reason: String,
+        /// Last day the exemption applies (YYYY-MM-DD)
+        #[arg(long)]
+        expires: String,
+        /// Ed25519 private key (PEM) to sign the exemption with
+        #[arg(long)]
+        key: Option<String>,
+    },
+    /// List exemptions and whether each one applies
+    Exemptions,
+}
 
-    let mut accepted = 0u32;
-    let mut overridden = 0u32;
-    for line in &staging_lines {
-        if head_set.contains(line.as_str()) {
-            accepted += 1;
-        } else {
-            overridden += 1;
-        }
-    }
-    (accepted, overridden)
+#[derive(Subcommand)]
+enum LogsAction {
+    /// Print the most recent log lines
+    Tail {
+        /// Number of lines to show
+        #[arg(long, short = 'n', default_value_t = 50)]
+        lines: usize,
+        /// Keep printing new lines as they are written
+        #[arg(long, short)]
+        follow: bool,
+        /// Only show lines at or above this level: trace, debug, info, warn, error
+        #[arg(long)]
+        level: Option<String>,
+    },
+    /// Print the log file path
+    Path,
 }
 
-/// Enrich receipts with `accepted_lines` / `overridden_lines` by comparing the
-/// blob hashes captured at PostToolUse time against the blobs actually committed to HEAD.
-fn compute_acceptance_stats(receipts: &mut [core::receipt::Receipt]) {
-    for receipt in receipts.iter_mut() {
-        let mut total_accepted = 0u32;
-        let mut total_overridden = 0u32;
-        let mut has_data = false;
-
-        for fc in &receipt.files_changed {
-            if let Some(ref staging_blob) = fc.blob_hash {
-                if let Some(head_blob) = get_head_blob(&fc.path) {
-                    has_data = true;
-                    if head_blob == *staging_blob {
-                        // File unchanged between AI write and commit — all additions accepted
-                        total_accepted += fc.additions;
-                    } else {
-                        let (accepted, overridden) =
-                            count_accepted_overridden(staging_blob, &head_blob);
-                        total_accepted += accepted;
-                        total_overridden += overridden;
-                    }
-                }
-            }
-        }
+#[derive(Subcommand)]
+enum BundleAction {
+    /// Write all notes to a gzip-compressed bundle file
+    Export {
+        /// Output file (e.g. receipts.bpbundle)
+        file: String,
+        /// Also include the matching SQLite cache rows
+        #[arg(long)]
+        include_cache: bool,
+    },
+    /// Restore notes from a bundle, merging with existing notes
+    Import {
+        /// Bundle file written by `bundle export`
+        file: String,
+        /// Report what would be imported without writing notes
+        #[arg(long)]
+        dry_run: bool,
+    },
+}
 
-        if has_data {
-            receipt.accepted_lines = Some(total_accepted);
-            receipt.overridden_lines = Some(total_overridden);
-        }
-    }
+#[derive(Subcommand)]
+enum DaemonAction {
+    /// Run sync cycles in the foreground for the current repo
+    Run {
+        /// Seconds between cycles (minimum 30)
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+        /// Run a single cycle and exit
+        #[arg(long)]
+        once: bool,
+        /// Fetch and import only; never push notes
+        #[arg(long)]
+        no_push: bool,
+    },
+    /// Print a launchd plist or systemd user unit for the current repo
+    Unit {
+        /// Seconds between cycles
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+        /// Unit format: launchd or systemd (defaults to the current platform)
+        #[arg(long)]
+        format: Option<String>,
+    },
+    /// Install the launchd agent / systemd user unit for the current repo
+    Install {
+        /// Seconds between cycles
+        #[arg(long, default_value_t = 300)]
+        interval: u64,
+    },
+    /// Remove the installed unit for the current repo
+    Uninstall,
 }
 
 /// Calculate the percentage of AI-generated lines from the attached receipts.
@@ -572,25 +1255,61 @@ fn amend_commit_with_annotation(ai_pct: f64) {
 
 fn main() {
     let cli = Cli::parse();
+    core::term::init(cli.quiet, cli.plain, cli.no_emoji);
+    core::logging::init(cli.verbose);
 
     // Auto-setup global hooks on first run after install
     // Skip auto-setup for uninstall (would re-create what we're removing)
-    if !matches!(cli.command, Commands::Uninstall { .. }) {
+    // Skip it for `init --ci` too (CI must not get global hooks installed)
+    if !matches!(
+        cli.command,
+        Commands::Uninstall { .. }
+            | Commands::Init { ci: true, .. }
+            | Commands::Setup { .. }
+            | Commands::ServerHook
+    ) {
         git::init_hooks::auto_setup();
     }
 
+    // Commands people run every day double as a canary for silently broken hooks.
+    if matches!(
+        cli.command,
+        Commands::Blame { .. }
+            | Commands::Show { .. }
+            | Commands::Search { .. }
+            | Commands::Audit { .. }
+            | Commands::Analytics { .. }
+            | Commands::Stats { .. }
+            | Commands::Diff { .. }
+    ) {
+        core::heartbeat::warn_if_stale();
+        commands::recover::warn_if_stranded();
+    }
+
     match cli.command {
         Commands::Checkpoint { agent, hook_input } => {
             commands::checkpoint::run(&agent, &hook_input);
         }
 
-        Commands::Init { global } => {
-            if let Err(e) = git::init_hooks::run_init(global) {
+        Commands::Init { global, ci } => {
+            let result = if ci {
+                git::init_hooks::run_init_ci()
+            } else {
+                git::init_hooks::run_init(global)
+            };
+            if let Err(e) = result {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         }
 
+        Commands::Setup { yes } => {
+            if let Err(e) = commands::setup::run(yes) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
         Commands::InstallHooks => {
             if let Err(e) = integrations::claude_hooks::install() {
                 eprintln!("Error installing Claude Code hooks: {}", e);
@@ -601,19 +1320,35 @@ fn main() {
                 std::process::exit(1);
             }
             println!();
-            println!("  \x1b[1;32m✓\x1b[0m Claude Code hooks installed");
-            println!("  \x1b[1;32m✓\x1b[0m Git hooks installed");
-            println!();
-            println!("  \x1b[2m───────────────────────────────────────────────\x1b[0m");
-            println!();
-            println!("  \x1b[1mShare receipts with your team:\x1b[0m");
-            println!("    \x1b[36mblameprompt push\x1b[0m     Push receipts to remote");
-            println!("    \x1b[36mblameprompt pull\x1b[0m     Fetch receipts from remote");
+            println!("  {} Claude Code hooks installed", term::check());
+            println!("  {} Git hooks installed", term::check());
             println!();
+            term::status(&format!("  {}", term::rule(47)));
+            term::status("");
+            term::status(&format!(
+                "  {}",
+                term::bold("Share receipts with your team:")
+            ));
+            term::status(&format!(
+                "    {}     Push receipts to remote",
+                term::cyan("blameprompt push")
+            ));
+            term::status(&format!(
+                "    {}     Fetch receipts from remote",
+                term::cyan("blameprompt pull")
+            ));
+            term::status("");
         }
 
-        Commands::Uninstall { keep_notes, purge } => {
-            if let Err(e) = commands::uninstall::run(keep_notes, purge) {
+        Commands::Uninstall {
+            keep_notes,
+            purge,
+            dry_run,
+            component,
+        } => {
+            let components: Vec<commands::uninstall::Component> =
+                component.iter().filter_map(|c| c.parse().ok()).collect();
+            if let Err(e) = commands::uninstall::run(keep_notes, purge, &components, dry_run) {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
@@ -630,20 +1365,101 @@ fn main() {
             }
         }
 
-        Commands::Blame { file, format } => {
-            commands::blame::run(&file, &format);
+        Commands::Blame {
+            file,
+            format,
+            out_dir,
+            include_uncommitted,
+            by_symbol,
+        } => {
+            if format == "sidecar" {
+                commands::blame::export_sidecars(file.as_deref(), &out_dir);
+            } else if let Some(file) = file {
+                commands::blame::run(&file, &format, include_uncommitted, by_symbol);
+            } else {
+                eprintln!("Error: blame needs a FILE (or --format sidecar for all files)");
+                std::process::exit(1);
+            }
         }
 
-        Commands::Show { commit, format } => {
-            commands::show::run(&commit, &format);
+        Commands::Show {
+            commit,
+            format,
+            subagents,
+        } => {
+            if subagents {
+                commands::show::run_subagents(&commit, &format);
+            } else {
+                commands::show::run(&commit, &format);
+            }
         }
 
         Commands::Search {
             query,
             limit,
             format,
+            model,
+            provider,
+            author,
+            file,
+            from,
+            to,
+            min_cost,
+            sort,
+            regex,
+            case_sensitive,
+            include_uncommitted,
         } => {
-            commands::search::run(&query, limit, &format);
+            let has_filter = model.is_some()
+                || provider.is_some()
+                || author.is_some()
+                || file.is_some()
+                || from.is_some()
+                || to.is_some()
+                || min_cost.is_some();
+            if query.is_none() && !has_filter {
+                eprintln!("[BlamePrompt] Give a search query, a filter (--model, --provider, --author, --file, --from, --to, --min-cost), or both.");
+                std::process::exit(1);
+            }
+            let filter = match commands::search::SearchFilter::parse(
+                model,
+                provider,
+                author,
+                file.as_deref(),
+                from.as_deref(),
+                to.as_deref(),
+                min_cost,
+            ) {
+                Ok(f) => f,
+                Err(e) => {
+                    eprintln!("[BlamePrompt] {}", e);
+                    std::process::exit(1);
+                }
+            };
+            let query = query.unwrap_or_default();
+            let pattern = match commands::search::QueryPattern::new(&query, regex, case_sensitive) {
+                Ok(p) => p,
+                Err(e) => {
+                    eprintln!("[BlamePrompt] {}", e);
+                    std::process::exit(1);
+                }
+            };
+            commands::search::run(
+                &query,
+                &pattern,
+                &filter,
+                commands::search::SortOrder::parse(&sort),
+                limit,
+                &format,
+                include_uncommitted,
+            );
+        }
+
+        Commands::McpServe => {
+            if let Err(e) = commands::mcp::run() {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
         }
 
         Commands::Audit {
@@ -652,6 +1468,9 @@ fn main() {
             author,
             format,
             include_uncommitted,
+            group_by,
+            include_deleted,
+            anonymize_paths,
         } => {
             commands::audit::run(
                 from.as_deref(),
@@ -659,11 +1478,45 @@ fn main() {
                 author.as_deref(),
                 &format,
                 include_uncommitted,
+                group_by.as_deref(),
+                include_deleted,
+                anonymize_paths,
             );
         }
 
-        Commands::Analytics { export } | Commands::Stats { export } => {
-            commands::analytics::run(export.as_deref());
+        Commands::Analytics {
+            export,
+            by_author,
+            by_ticket,
+            trend,
+            subagents,
+            include_deleted,
+            include_uncommitted,
+            anonymize_paths,
+        }
+        | Commands::Stats {
+            export,
+            by_author,
+            by_ticket,
+            trend,
+            subagents,
+            include_deleted,
+            include_uncommitted,
+            anonymize_paths,
+        } => {
+            if subagents {
+                commands::analytics::run_subagents(export.as_deref());
+            } else {
+                commands::analytics::run(
+                    export.as_deref(),
+                    by_author,
+                    by_ticket,
+                    trend.as_deref(),
+                    include_deleted,
+                    include_uncommitted,
+                    anonymize_paths,
+                );
+            }
         }
 
         Commands::Report {
@@ -672,6 +1525,7 @@ fn main() {
             to,
             author,
             include_uncommitted,
+            anonymize_paths,
         } => {
             if let Err(e) = commands::report::generate_report(
                 &output,
@@ -679,32 +1533,115 @@ fn main() {
                 to.as_deref(),
                 author.as_deref(),
                 include_uncommitted,
+                anonymize_paths,
             ) {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         }
 
+        Commands::Heatmap { output } => {
+            if let Err(e) = commands::heatmap::run(&output) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::BisectReport { bad, good } => {
+            if let Err(e) = commands::bisect_report::run(&bad, &good) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Badge { output } => {
+            if let Err(e) = commands::badge::run(&output) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
         Commands::Diff { commit } => {
             commands::diff::run(commit.as_deref());
         }
 
+        Commands::InstallHooksPath => match git::hooks_path::install() {
+            Ok((dir, previous)) => {
+                println!();
+                println!("  {} Hooks multiplexer installed", term::success("[done]"));
+                println!(
+                    "         {}",
+                    term::dim(&format!(
+                        "{} git config --global core.hooksPath {}",
+                        term::arrow(),
+                        dir.display()
+                    ))
+                );
+                if let Some(p) = previous {
+                    println!(
+                        "         {}",
+                        term::dim(&format!(
+                            "{} chains to previous hooks in {}",
+                            term::arrow(),
+                            p
+                        ))
+                    );
+                }
+                term::status("");
+                term::status(&format!(
+                    "Every {} now auto-attaches AI receipts; each repository's own hooks keep running.",
+                    term::cyan("git commit")
+                ));
+                if let Some(local) = git::hooks_path::local_override() {
+                    term::status(&format!(
+                        "[warn] This repository sets core.hooksPath = {} locally, which takes precedence. \
+                         Run {} here instead.",
+                        local,
+                        term::cyan("blameprompt install-hooks")
+                    ));
+                }
+                term::status("");
+            }
+            Err(e) => {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        },
+
         Commands::InstallGitWrap => match git::wrap::install() {
             Ok(path) => {
                 let home = dirs::home_dir()
                     .map(|h| h.display().to_string())
                     .unwrap_or_else(|| "~".to_string());
                 println!();
-                println!("  \x1b[1;32m[done]\x1b[0m Git wrapper installed");
-                println!("         \x1b[2m→ {}\x1b[0m", path.display());
-                println!("  \x1b[1;32m[done]\x1b[0m PATH export added to shell RC");
-                println!("         \x1b[2m→ {}/.blameprompt/bin:$PATH\x1b[0m", home);
-                println!();
+                println!("  {} Git wrapper installed", term::success("[done]"));
                 println!(
-                    "\x1b[1mReload your shell to activate:\x1b[0m  \x1b[36msource ~/.zshrc\x1b[0m"
+                    "         {}",
+                    term::dim(&format!("{} {}", term::arrow(), path.display()))
                 );
-                println!("Every \x1b[36mgit commit\x1b[0m will now auto-attach AI receipts.");
-                println!();
+                println!(
+                    "  {} PATH export added to shell RC",
+                    term::success("[done]")
+                );
+                println!(
+                    "         {}",
+                    term::dim(&format!(
+                        "{} {}/.blameprompt/bin:$PATH",
+                        term::arrow(),
+                        home
+                    ))
+                );
+                term::status("");
+                term::status(&format!(
+                    "{}  {}",
+                    term::bold("Reload your shell to activate:"),
+                    term::cyan("source ~/.zshrc")
+                ));
+                term::status(&format!(
+                    "Every {} will now auto-attach AI receipts.",
+                    term::cyan("git commit")
+                ));
+                term::status("");
             }
             Err(e) => {
                 eprintln!("Error installing git wrapper: {}", e);
@@ -716,70 +1653,259 @@ fn main() {
             commands::rebase_notes::run_from_stdin();
         }
 
-        Commands::Push => {
-            commands::sync::push();
+        Commands::Push {
+            batch_size,
+            retries,
+            prune_remote,
+        } => {
+            commands::sync::push(&commands::sync::SyncOptions {
+                batch_size,
+                retries,
+                prune_remote,
+            });
+        }
+
+        Commands::Review { list, flagged } => {
+            if let Err(e) = commands::review::run(list, flagged) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Pull { retries } => {
+            commands::sync::pull(&commands::sync::SyncOptions {
+                retries,
+                ..Default::default()
+            });
+        }
+
+        Commands::Redact {
+            test,
+            receipt,
+            staged,
+        } => {
+            let result = match (test, receipt) {
+                (Some(file), _) => {
+                    commands::redact_test::run(&file);
+                    Ok(())
+                }
+                (None, Some(id)) => commands::redact_test::run_receipt(&id),
+                (None, None) if staged => commands::redact_test::run_staged(),
+                (None, None) => Ok(()),
+            };
+            if let Err(e) = result {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Record {
+            session,
+            provider,
+            import,
+        } => {
+            commands::record::run(&session, provider.as_deref(), import.force);
+        }
+
+        Commands::WatchFiles {
+            provider,
+            model,
+            min_lines,
+            max_human_cps,
+            burst_secs,
+        } => {
+            let opts = commands::watch_files::WatchOptions {
+                provider,
+                model,
+                min_lines,
+                max_human_cps,
+                burst_window: std::time::Duration::from_secs(burst_secs),
+            };
+            if let Err(e) = commands::watch_files::run(opts) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::ShellIntegration { shell } => {
+            print!("{}", commands::shell_capture::integration_script(&shell));
+        }
+
+        Commands::ShellCapture { action } => {
+            let result = match action {
+                ShellCaptureAction::Snapshot => commands::shell_capture::snapshot(),
+                ShellCaptureAction::Record {
+                    provider,
+                    snapshot,
+                    command,
+                } => commands::shell_capture::record(&provider, &snapshot, &command),
+            };
+            if let Err(e) = result {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::RecordCursor {
+            workspace,
+            import,
+            all,
+            follow,
+            interval,
+        } => {
+            if follow {
+                integrations::cursor::run_follow_cursor(
+                    workspace.as_deref(),
+                    import.force,
+                    all,
+                    interval,
+                );
+            } else {
+                integrations::cursor::run_record_cursor(workspace.as_deref(), import.force, all);
+            }
+        }
+
+        Commands::RecordCopilot { workspace, import } => {
+            integrations::copilot::run_record_copilot(workspace.as_deref(), import.force);
         }
 
-        Commands::Pull => {
-            commands::sync::pull();
+        Commands::RecordCodex { session, import } => {
+            integrations::codex::run_record_codex(session.as_deref(), import.force);
         }
 
-        Commands::Redact { test } => {
-            commands::redact_test::run(&test);
+        Commands::RecordGemini { session, import } => {
+            integrations::gemini::run_record_gemini(session.as_deref(), import.force);
         }
 
-        Commands::Record { session, provider } => {
-            commands::record::run(&session, provider.as_deref());
+        Commands::RecordWindsurf { workspace, import } => {
+            integrations::windsurf::run_record_windsurf(workspace.as_deref(), import.force);
         }
 
-        Commands::RecordCursor { workspace } => {
-            integrations::cursor::run_record_cursor(workspace.as_deref());
+        Commands::RecordAntigravity { session, import } => {
+            integrations::antigravity::run_record_antigravity(session.as_deref(), import.force);
         }
 
-        Commands::RecordCopilot { workspace } => {
-            integrations::copilot::run_record_copilot(workspace.as_deref());
+        Commands::RecordContinue { session, import } => {
+            integrations::continue_ai::run_record_continue(session.as_deref(), import.force);
         }
 
-        Commands::RecordCodex { session } => {
-            integrations::codex::run_record_codex(session.as_deref());
+        Commands::RecordDroid { session, import } => {
+            integrations::droid::run_record_droid(session.as_deref(), import.force);
         }
 
-        Commands::RecordGemini { session } => {
-            integrations::gemini::run_record_gemini(session.as_deref());
+        Commands::RecordJunie { session, import } => {
+            integrations::junie::run_record_junie(session.as_deref(), import.force);
         }
 
-        Commands::RecordWindsurf { workspace } => {
-            integrations::windsurf::run_record_windsurf(workspace.as_deref());
+        Commands::RecordRovoDev { session, import } => {
+            integrations::rovo_dev::run_record_rovo_dev(session.as_deref(), import.force);
         }
 
-        Commands::RecordAntigravity { session } => {
-            integrations::antigravity::run_record_antigravity(session.as_deref());
+        Commands::RecordAmp { session, import } => {
+            integrations::amp::run_record_amp(session.as_deref(), import.force);
         }
 
-        Commands::RecordContinue { session } => {
-            integrations::continue_ai::run_record_continue(session.as_deref());
+        Commands::RecordOpenCode { session, import } => {
+            integrations::opencode::run_record_opencode(session.as_deref(), import.force);
         }
 
-        Commands::RecordDroid { session } => {
-            integrations::droid::run_record_droid(session.as_deref());
+        Commands::RecordGoose { session, import } => {
+            integrations::goose::run_record_goose(session.as_deref(), import.force);
         }
 
-        Commands::RecordJunie { session } => {
-            integrations::junie::run_record_junie(session.as_deref());
+        Commands::RecordOpenhands { session, import } => {
+            integrations::openhands::run_record_openhands(session.as_deref(), import.force);
         }
 
-        Commands::RecordRovoDev { session } => {
-            integrations::rovo_dev::run_record_rovo_dev(session.as_deref());
+        Commands::RecordChatgpt {
+            export,
+            days,
+            import,
+        } => {
+            integrations::openai_export::run_record_chatgpt(&export, days, import.force);
+        }
+
+        Commands::Migrate { write } => {
+            if let Err(e) = commands::migrate::run(write) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
         }
 
-        Commands::RecordAmp { session } => {
-            integrations::amp::run_record_amp(session.as_deref());
+        Commands::Forget {
+            author,
+            session,
+            anonymize,
+            write,
+        } => {
+            let subject = match (author, session) {
+                (Some(a), _) => commands::forget::Subject::Author(a),
+                (None, Some(s)) => commands::forget::Subject::Session(s),
+                (None, None) => unreachable!("clap requires --author or --session"),
+            };
+            if let Err(e) = commands::forget::run(subject, anonymize, write) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
         }
 
-        Commands::RecordOpenCode { session } => {
-            integrations::opencode::run_record_opencode(session.as_deref());
+        Commands::StorageStats { top } => {
+            if let Err(e) = commands::storage_stats::run(top) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
         }
 
+        Commands::LintNotes { fix } => {
+            if let Err(e) = commands::lint_notes::run(fix) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Bundle { action } => match action {
+            BundleAction::Export {
+                file,
+                include_cache,
+            } => commands::bundle::export(&file, include_cache),
+            BundleAction::Import { file, dry_run } => commands::bundle::import(&file, dry_run),
+        },
+
+        Commands::Daemon { action } => match action {
+            DaemonAction::Run {
+                interval,
+                once,
+                no_push,
+            } => commands::daemon::run(interval, once, !no_push),
+            DaemonAction::Unit { interval, format } => {
+                let format = match format.as_deref() {
+                    None => None,
+                    Some(f) => match commands::daemon::UnitFormat::parse(f) {
+                        Some(f) => Some(f),
+                        None => {
+                            eprintln!(
+                                "Error: unknown unit format '{}' (use launchd or systemd)",
+                                f
+                            );
+                            std::process::exit(1);
+                        }
+                    },
+                };
+                commands::daemon::print_unit(interval, format);
+            }
+            DaemonAction::Install { interval } => commands::daemon::install(interval),
+            DaemonAction::Uninstall => commands::daemon::uninstall(),
+        },
+
+        Commands::Prompts { action } => match action {
+            PromptsAction::Top {
+                limit,
+                min_uses,
+                similarity,
+                format,
+            } => commands::prompts::run_top(limit, min_uses, similarity, &format),
+        },
+
         Commands::Cache { action } => match action {
             CacheAction::Sync => {
                 if let Err(e) = core::db::sync_from_notes() {
@@ -814,16 +1940,131 @@ fn main() {
             println!("{}", data.receipts.len());
         }
 
+        Commands::CommitSummary { comment } => {
+            commands::commit_summary::run(comment);
+        }
+
+        Commands::Export { format: _, force } => {
+            if let Err(e) = integrations::git_ai::run_export(force) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Import { format: _, force } => {
+            if let Err(e) = integrations::git_ai::run_import(force) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
         Commands::ExportAgentTrace { commit } => {
             integrations::agent_trace::run_export(commit.as_deref());
         }
 
-        Commands::ImportAgentTrace { commit } => {
-            integrations::agent_trace::run_import(commit.as_deref());
+        Commands::ImportAgentTrace {
+            commit,
+            file,
+            validate,
+        } => {
+            if let Err(e) =
+                integrations::agent_trace::run_import(commit.as_deref(), file.as_deref(), validate)
+            {
+                eprintln!("[agent-trace] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::GithubComment {
+            pr,
+            repo,
+            per_file,
+            summary_file,
+        } => {
+            commands::github::run(pr, repo.as_deref(), per_file, summary_file.as_deref());
+        }
+
+        Commands::LinkPr {
+            pr,
+            commits,
+            limit,
+            force,
+        } => {
+            if let Err(e) = commands::link_pr::run(pr.as_deref(), &commits, limit, force) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::ReleaseSummary {
+            tag,
+            from,
+            format,
+            upload,
+        } => {
+            if let Err(e) = commands::release_summary::run(&tag, from.as_deref(), &format, upload) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::GithubCheck { repo, sha } => {
+            commands::github::run_check(repo.as_deref(), sha.as_deref());
         }
 
-        Commands::GithubComment { pr, repo } => {
-            commands::github::run(pr, repo.as_deref());
+        Commands::Policy { action } => match action {
+            PolicyAction::Exempt {
+                rule,
+                path,
+                reason,
+                expires,
+                key,
+            } => {
+                let new = commands::policy_exemptions::NewExemption {
+                    rule: &rule,
+                    path: &path,
+                    reason: &reason,
+                    expires: &expires,
+                    key: key.as_deref(),
+                };
+                match commands::policy_exemptions::add(&new) {
+                    Ok(e) => println!(
+                        "[BlamePrompt] Added exemption {} ({} in {} until {}{}). Commit {} to apply it.",
+                        e.id,
+                        e.rule,
+                        e.path,
+                        e.expires,
+                        if e.signature.is_some() { ", signed" } else { ", unsigned" },
+                        commands::policy_exemptions::EXEMPTIONS_FILE
+                    ),
+                    Err(e) => {
+                        eprintln!("[BlamePrompt] {}", e);
+                        std::process::exit(1);
+                    }
+                }
+            }
+            PolicyAction::Exemptions => {
+                if let Err(e) = commands::policy_exemptions::run_list() {
+                    eprintln!("[BlamePrompt] {}", e);
+                    std::process::exit(1);
+                }
+            }
+        },
+
+        Commands::GerritComment {
+            url,
+            change,
+            revision,
+            label,
+            label_value,
+        } => {
+            commands::gerrit::run(&commands::gerrit::GerritOptions {
+                url: url.as_deref(),
+                change: change.as_deref(),
+                revision: revision.as_deref(),
+                label: label.as_deref(),
+                label_value,
+            });
         }
 
         Commands::HackathonReport {
@@ -831,7 +2072,10 @@ fn main() {
             end,
             output,
             author,
+            team,
+            leaderboard,
             include_uncommitted,
+            github_repo,
         } => {
             // Default: 24h window ending now
             let default_end = chrono::Utc::now().to_rfc3339();
@@ -839,20 +2083,112 @@ fn main() {
             let start_str = start.as_deref().unwrap_or(&default_start);
             let end_str = end.as_deref().unwrap_or(&default_end);
 
-            if let Err(e) = commands::hackathon::generate_hackathon_report(
-                start_str,
-                end_str,
-                &output,
-                author.as_deref(),
-                include_uncommitted,
-            ) {
+            let result = if team.is_empty() {
+                commands::hackathon::generate_hackathon_report(
+                    start_str,
+                    end_str,
+                    &output,
+                    author.as_deref(),
+                    include_uncommitted,
+                    github_repo.as_deref(),
+                )
+            } else {
+                commands::hackathon::generate_team_report(
+                    start_str,
+                    end_str,
+                    &output,
+                    &team,
+                    leaderboard.as_deref(),
+                    include_uncommitted,
+                    github_repo.as_deref(),
+                )
+            };
+            if let Err(e) = result {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Metrics {
+            from,
+            to,
+            base,
+            format,
+        } => {
+            if let Err(e) =
+                commands::metrics::run(from.as_deref(), to.as_deref(), base.as_deref(), &format)
+            {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::AssignmentReport {
+            rubric,
+            students,
+            output,
+            format,
+        } => {
+            if let Err(e) = commands::assignment::run(&rubric, &students, &output, &format) {
                 eprintln!("Error: {}", e);
                 std::process::exit(1);
             }
         }
 
-        Commands::CheckProvenance { file, line } => {
-            commands::check_provenance::run(&file, line);
+        Commands::Anomalies {
+            from,
+            to,
+            author,
+            format,
+        } => {
+            if let Err(e) =
+                commands::anomalies::run(from.as_deref(), to.as_deref(), author.as_deref(), &format)
+            {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Digest {
+            period,
+            format,
+            output,
+            send,
+        } => {
+            if let Err(e) = commands::digest::run(&period, &format, output.as_deref(), send) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::CheckProvenance {
+            file,
+            line,
+            no_cache,
+            include_uncommitted,
+        } => {
+            commands::check_provenance::run(&file, line, !no_cache, include_uncommitted);
+        }
+
+        Commands::Impact { target, format } => {
+            if let Err(e) = commands::impact::run(&target, &format) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Graph {
+            format,
+            from,
+            to,
+            output,
+        } => {
+            if let Err(e) =
+                commands::graph::run(&format, from.as_deref(), to.as_deref(), output.as_deref())
+            {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
         }
 
         Commands::Login { token, api_url } => {
@@ -875,26 +2211,76 @@ fn main() {
             commands::profile::run(edit);
         }
 
-        Commands::Attach { annotate } => {
+        Commands::Attach {
+            annotate,
+            dry_run,
+            format,
+        } => {
+            let started = std::time::Instant::now();
             let mut data = commands::staging::read_all_staging();
+            if data.receipts.is_empty() && dry_run {
+                println!("[BlamePrompt] Nothing staged; attach would leave HEAD's note unchanged.");
+                return;
+            }
             if data.receipts.is_empty() {
+                commands::auto_label::apply_untracked(&core::config::load_config().policy.labels);
+                return;
+            }
+            // Receipts an earlier commit already holds (its post-commit hook
+            // never ran) stay staged for `blameprompt recover`.
+            let stranded = commands::recover::stranded_before_head(&data.receipts);
+            let (held, receipts): (Vec<_>, Vec<_>) = std::mem::take(&mut data.receipts)
+                .into_iter()
+                .partition(|r| stranded.contains(&r.id));
+            data.receipts = receipts;
+            if !held.is_empty() {
+                eprintln!(
+                    "[BlamePrompt] {} staged receipt(s) belong to an earlier commit; left staged. Attach them with: blameprompt recover",
+                    held.len()
+                );
+            }
+            let restage = |held: Vec<core::receipt::Receipt>| {
+                if !held.is_empty() {
+                    commands::staging::write_staging_data_in(
+                        &commands::staging::StagingData { receipts: held },
+                        ".",
+                    );
+                }
+            };
+            if data.receipts.is_empty() && !dry_run {
+                return;
+            }
+            core::ticket::fill_missing(&mut data.receipts);
+            core::pairing::fill_from_head(&mut data.receipts);
+            let cfg = core::config::load_config();
+            if cfg.capture.granularity == "session" {
+                data.receipts =
+                    commands::staging::collapse_sessions(std::mem::take(&mut data.receipts));
+            }
+            if cfg.compaction.enabled {
+                data.receipts = commands::staging::compact_housekeeping(
+                    std::mem::take(&mut data.receipts),
+                    &cfg.compaction,
+                );
+            }
+            if dry_run {
+                if let Err(e) = commands::attach_preview::run(&data, &held, &format) {
+                    eprintln!("[BlamePrompt] {}", e);
+                    std::process::exit(1);
+                }
                 return;
             }
-            // Compute accepted/overridden lines by comparing AI-written blobs against HEAD
-            compute_acceptance_stats(&mut data.receipts);
-            match git::notes::attach_receipts_to_head(&data) {
-                Ok(()) => {
+            // Acceptance stats and line origins are computed by `finalize`,
+            // detached from the hook unless configured to run inline.
+            let finalize_now = cfg.attach.finalize == "sync";
+            match git::notes::attach_receipts_to_head(&data, finalize_now) {
+                Ok(mut head) => {
                     // Record which prompts are being committed so the backfill loop
                     // in handle_stop() won't recreate them after staging is cleared.
                     commands::staging::record_committed_prompts(&data.receipts);
                     commands::staging::clear_staging();
-                    let head_short = std::process::Command::new("git")
-                        .args(["rev-parse", "--short", "HEAD"])
-                        .output()
-                        .ok()
-                        .and_then(|o| String::from_utf8(o.stdout).ok())
-                        .map(|s| s.trim().to_string())
-                        .unwrap_or_else(|| "HEAD".to_string());
+                    restage(held);
+                    let head_short = &head[..head.len().min(7)];
                     println!(
                         "[BlamePrompt] {} receipt(s) attached to {}",
                         data.receipts.len(),
@@ -905,6 +2291,34 @@ fn main() {
                     if annotate {
                         let ai_pct = compute_ai_percentage(&data.receipts);
                         amend_commit_with_annotation(ai_pct);
+                        // The amend rewrote HEAD and post-rewrite moved the note.
+                        if let Some(amended) = std::process::Command::new("git")
+                            .args(["rev-parse", "HEAD"])
+                            .output()
+                            .ok()
+                            .filter(|o| o.status.success())
+                            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+                        {
+                            head = amended;
+                        }
+                    }
+                    commands::auto_label::apply(
+                        &cfg.policy.labels,
+                        compute_ai_percentage(&data.receipts),
+                    );
+                    if !finalize_now && !commands::finalize::spawn_detached(&head) {
+                        if let Err(e) = commands::finalize::run(Some(&head)) {
+                            eprintln!("[BlamePrompt] {}", e);
+                        }
+                    }
+                    let elapsed = started.elapsed().as_millis() as u64;
+                    if elapsed > cfg.attach.budget_ms {
+                        tracing::warn!(
+                            elapsed_ms = elapsed,
+                            budget_ms = cfg.attach.budget_ms,
+                            receipts = data.receipts.len(),
+                            "attach exceeded its time budget"
+                        );
                     }
                 }
                 Err(e) => {
@@ -913,8 +2327,78 @@ fn main() {
             }
         }
 
+        Commands::Finalize { commit } => {
+            if let Err(e) = commands::finalize::run(Some(&commit)) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Recover { yes } => {
+            if let Err(e) = commands::recover::run(yes) {
+                eprintln!("[BlamePrompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Config { action } => match action {
+            ConfigAction::Sync => match core::org_config::sync(&core::config::load_config().org) {
+                Ok(outcome) => println!("{}", outcome.describe()),
+                Err(e) => {
+                    eprintln!("[BlamePrompt] {}", e);
+                    std::process::exit(1);
+                }
+            },
+            ConfigAction::Show { origin } => {
+                if let Err(e) = commands::config_show::run(origin) {
+                    eprintln!("[BlamePrompt] {}", e);
+                    std::process::exit(1);
+                }
+            }
+        },
+
         Commands::Doctor => {
             commands::doctor::run();
         }
+
+        Commands::Logs { action } => match action {
+            LogsAction::Tail {
+                lines,
+                follow,
+                level,
+            } => {
+                if let Err(e) = commands::logs::tail(lines, follow, level.as_deref()) {
+                    eprintln!("Error: {}", e);
+                    std::process::exit(1);
+                }
+            }
+            LogsAction::Path => commands::logs::path(),
+        },
+
+        Commands::ServerHook => {
+            if let Err(e) = commands::server_hook::run() {
+                eprintln!("[blameprompt] {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Completions { shell } => {
+            commands::completions::completions(Cli::command(), shell);
+        }
+
+        Commands::Mangen { out_dir } => {
+            if let Err(e) = commands::completions::mangen(Cli::command(), &out_dir) {
+                eprintln!("Error: {}", e);
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Disable { global } => {
+            commands::capture::disable(global);
+        }
+
+        Commands::Enable { global } => {
+            commands::capture::enable(global);
+        }
     }
 }